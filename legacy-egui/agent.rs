@@ -37,6 +37,13 @@ pub struct ToolCall {
     pub raw_text: String, // Testo originale per debug
 }
 
+impl ToolCall {
+    /// Stesso tool con gli stessi parametri (il testo originale può differire)
+    pub fn is_same_call(&self, other: &ToolCall) -> bool {
+        self.tool_name == other.tool_name && self.parameters == other.parameters
+    }
+}
+
 /// Risultato dell'esecuzione di un tool
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
@@ -44,9 +51,23 @@ pub struct ToolResult {
     pub output: String,
     pub error: Option<String>,
     pub tool_name: String,
+    /// Dati strutturati di `sql_query`, per mostrarli come tabella nella UI
+    #[serde(skip)]
+    pub query_result: Option<mcp_sql::QueryResult>,
 }
 
 impl ToolResult {
+    /// Riassunto breve dell'output, es. "✅ 15 righe"
+    pub fn summary(&self) -> String {
+        if !self.success {
+            return "❌ errore".to_string();
+        }
+        match self.output.lines().count() {
+            1 => "✅ 1 riga".to_string(),
+            n => format!("✅ {} righe", n),
+        }
+    }
+
     pub fn to_markdown(&self) -> String {
         if self.success {
             format!(
@@ -65,6 +86,43 @@ impl ToolResult {
     }
 }
 
+/// Un passo del ciclo agentico: la chiamata e, quando disponibile, il suo risultato
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentTraceStep {
+    pub iteration: usize,
+    pub call: ToolCall,
+    pub result: Option<ToolResult>,
+}
+
+/// Traccia dei tool eseguiti dall'agente nella sessione corrente
+#[derive(Debug, Clone, Default)]
+pub struct AgentTrace {
+    pub steps: Vec<AgentTraceStep>,
+}
+
+impl AgentTrace {
+    /// Registra le chiamate in partenza, ancora senza risultato
+    pub fn record_calls(&mut self, iteration: usize, calls: &[ToolCall]) {
+        self.steps.extend(calls.iter().map(|call| AgentTraceStep {
+            iteration,
+            call: call.clone(),
+            result: None,
+        }));
+    }
+
+    /// Associa i risultati ai passi in attesa, nello stesso ordine di esecuzione
+    pub fn record_results(&mut self, results: &[ToolResult]) {
+        let pending = self.steps.iter_mut().filter(|step| step.result.is_none());
+        for (step, result) in pending.zip(results) {
+            step.result = Some(result.clone());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
+
 /// Sistema agentico che gestisce i tool
 #[derive(Clone)]
 pub struct AgentSystem {
@@ -514,10 +572,12 @@ impl AgentSystem {
                 output: String::new(),
                 error: Some("Tool pericoloso: conferma richiesta".to_string()),
                 tool_name: call.tool_name.clone(),
+                query_result: None,
             });
         }
 
         // Esegui il tool specifico
+        let mut query_result = None;
         let result = match call.tool_name.as_str() {
             "shell_execute" => self.execute_shell(&call.parameters).await,
             "file_read" => self.execute_file_read(&call.parameters).await,
@@ -532,7 +592,13 @@ impl AgentSystem {
             "document_view" => self.execute_document_view(&call.parameters).await,
             // MCP SQL Server tools
             "sql_connect" => self.execute_sql_connect(&call.parameters).await,
-            "sql_query" => self.execute_sql_query(&call.parameters).await,
+            "sql_query" => self
+                .execute_sql_query(&call.parameters)
+                .await
+                .map(|(output, data)| {
+                    query_result = Some(data);
+                    output
+                }),
             "sql_list_tables" => self.execute_sql_list_tables(&call.parameters).await,
             "sql_describe_table" => self.execute_sql_describe_table(&call.parameters).await,
             "sql_disconnect" => self.execute_sql_disconnect(&call.parameters).await,
@@ -545,12 +611,14 @@ impl AgentSystem {
                 output,
                 error: None,
                 tool_name: call.tool_name.clone(),
+                query_result,
             },
             Err(e) => ToolResult {
                 success: false,
                 output: String::new(),
                 error: Some(e.to_string()),
                 tool_name: call.tool_name.clone(),
+                query_result: None,
             },
         };
 
@@ -987,10 +1055,11 @@ impl AgentSystem {
     }
 
     /// Esegue query SQL SELECT
+    /// Restituisce il testo per l'LLM e il risultato strutturato per la UI
     async fn execute_sql_query(
         &self,
         params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
+    ) -> Result<(String, mcp_sql::QueryResult)> {
         let connection_id = match params.get("connection_id").and_then(|v| v.as_str()) {
             Some(id) => id.to_string(),
             None => {
@@ -1055,7 +1124,7 @@ impl AgentSystem {
         response.push_str(&json_pretty);
         response.push_str("\n```\n");
 
-        Ok(response)
+        Ok((response, result))
     }
 
     /// Lista tutte le tabelle del database
@@ -1586,7 +1655,7 @@ fn value_to_f64(value: &serde_json::Value) -> Option<f64> {
     }
 }
 
-fn value_to_display(value: &serde_json::Value) -> String {
+pub fn value_to_display(value: &serde_json::Value) -> String {
     match value {
         serde_json::Value::Null => "null".to_string(),
         serde_json::Value::Bool(flag) => flag.to_string(),
@@ -0,0 +1,205 @@
+// Evidenziazione dei blocchi di codice nelle risposte. egui_commonmark colora il
+// codice solo con la feature `better_syntax_highlighting`: i blocchi delimitati da
+// ``` vengono estratti dal markdown e divisi in commenti, stringhe, numeri e parole
+// chiave, che la chat disegna con i colori del tema.
+
+use regex::{Regex, RegexBuilder};
+
+/// Parole chiave comuni ai linguaggi più usati
+const KEYWORDS: &str =
+    "as async await break case catch class const continue def default do elif else \
+     enum except export extends false finally fn for from func function if impl \
+     import in interface lambda let match mod mut new None null pass pub raise \
+     return self static struct switch this throw trait true try type use var void \
+     while with yield False True";
+
+/// Parole chiave SQL, riconosciute senza distinzione tra maiuscole e minuscole
+const SQL_KEYWORDS: &str =
+    "and as asc by case create delete desc distinct drop else end from group having \
+     in inner insert into is join left like limit not null on or order outer right \
+     select set table then top union update values when where with";
+
+/// Parte di un testo markdown: testo normale o blocco di codice
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'a> {
+    Markdown(&'a str),
+    Code { language: &'a str, code: &'a str },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Plain,
+    Comment,
+    String,
+    Number,
+    Keyword,
+}
+
+/// Separa i blocchi di codice che iniziano a inizio riga. Quelli rientrati
+/// appartengono a un elenco e restano nel markdown; un blocco non ancora chiuso
+/// (risposta in arrivo) prosegue fino alla fine
+pub fn split_code_blocks(markdown: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut offset = 0;
+    let mut open: Option<(&str, &str, usize)> = None; // (marcatore, linguaggio, inizio codice)
+
+    for line in markdown.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        match open {
+            Some((marker, language, code_start)) => {
+                if line.trim() == marker {
+                    segments.push(Segment::Code {
+                        language,
+                        code: markdown[code_start..line_start].trim_end_matches('\n'),
+                    });
+                    open = None;
+                    text_start = offset;
+                }
+            }
+            None => {
+                let Some(marker) = ["```", "~~~"].into_iter().find(|m| line.starts_with(m)) else {
+                    continue;
+                };
+                let text = &markdown[text_start..line_start];
+                if !text.trim().is_empty() {
+                    segments.push(Segment::Markdown(text));
+                }
+                let language = line[marker.len()..].split_whitespace().next().unwrap_or("");
+                open = Some((marker, language, offset));
+            }
+        }
+    }
+
+    match open {
+        Some((_, language, code_start)) => segments.push(Segment::Code {
+            language,
+            code: markdown[code_start..].trim_end_matches('\n'),
+        }),
+        None => {
+            let text = &markdown[text_start..];
+            if !text.trim().is_empty() || segments.is_empty() {
+                segments.push(Segment::Markdown(text));
+            }
+        }
+    }
+    segments
+}
+
+/// Il codice diviso in parti da colorare. Senza linguaggio (o per il testo
+/// semplice) resta un'unica parte normale
+pub fn highlight<'a>(language: &str, code: &'a str) -> Vec<(TokenKind, &'a str)> {
+    let Some(regex) = highlight_regex(language) else {
+        return vec![(TokenKind::Plain, code)];
+    };
+
+    let mut tokens = Vec::new();
+    let mut last = 0;
+    for captures in regex.captures_iter(code) {
+        let Some((kind, token)) = [
+            ("comment", TokenKind::Comment),
+            ("string", TokenKind::String),
+            ("number", TokenKind::Number),
+            ("keyword", TokenKind::Keyword),
+        ]
+        .iter()
+        .find_map(|(name, kind)| captures.name(name).map(|token| (*kind, token))) else {
+            continue;
+        };
+        if token.start() > last {
+            tokens.push((TokenKind::Plain, &code[last..token.start()]));
+        }
+        tokens.push((kind, token.as_str()));
+        last = token.end();
+    }
+    if last < code.len() {
+        tokens.push((TokenKind::Plain, &code[last..]));
+    }
+    tokens
+}
+
+fn highlight_regex(language: &str) -> Option<Regex> {
+    let language = language.to_lowercase();
+    let comment = match language.as_str() {
+        "" | "text" | "txt" | "plain" | "plaintext" | "markdown" | "md" => return None,
+        "python" | "py" | "bash" | "sh" | "shell" | "zsh" | "yaml" | "yml" | "toml" | "ruby"
+        | "rb" | "r" | "powershell" | "ps1" | "dockerfile" => r"#[^\n]*",
+        "sql" | "tsql" | "mysql" | "postgresql" | "sqlite" | "lua" => r"--[^\n]*|/\*[\s\S]*?\*/",
+        "html" | "xml" | "svg" => r"<!--[\s\S]*?-->",
+        _ => r"//[^\n]*|/\*[\s\S]*?\*/",
+    };
+    let is_sql = comment.starts_with("--") && language != "lua";
+    let keywords = if is_sql { SQL_KEYWORDS } else { KEYWORDS };
+    // In Rust l'apice singolo apre anche i lifetime: solo le virgolette fanno stringhe
+    let single_quoted = if language == "rust" || language == "rs" {
+        ""
+    } else {
+        r"|'(?:[^'\\\n]|\\.)*'"
+    };
+
+    let pattern = format!(
+        r#"(?P<comment>{})|(?P<string>"(?:[^"\\\n]|\\.)*"{})|(?P<number>\b\d+(?:\.\d+)?\b)|(?P<keyword>\b(?:{})\b)"#,
+        comment,
+        single_quoted,
+        keywords.split_whitespace().collect::<Vec<_>>().join("|")
+    );
+    RegexBuilder::new(&pattern)
+        .case_insensitive(is_sql)
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_code_blocks() {
+        let markdown = "Esempio:\n\n```python\nprint(1)\n```\n\n- voce\n  ```\n  rientrato\n  ```\nFine\n```sql\nSELECT 1";
+        assert_eq!(
+            split_code_blocks(markdown),
+            vec![
+                Segment::Markdown("Esempio:\n\n"),
+                Segment::Code {
+                    language: "python",
+                    code: "print(1)",
+                },
+                Segment::Markdown("\n- voce\n  ```\n  rientrato\n  ```\nFine\n"),
+                // Blocco non ancora chiuso mentre la risposta arriva
+                Segment::Code {
+                    language: "sql",
+                    code: "SELECT 1",
+                },
+            ]
+        );
+        assert_eq!(split_code_blocks("testo"), vec![Segment::Markdown("testo")]);
+        assert_eq!(split_code_blocks(""), vec![Segment::Markdown("")]);
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight("python", "x = \"a\"  # nota"),
+            vec![
+                (TokenKind::Plain, "x = "),
+                (TokenKind::String, "\"a\""),
+                (TokenKind::Plain, "  "),
+                (TokenKind::Comment, "# nota"),
+            ]
+        );
+        assert_eq!(
+            highlight("sql", "select 42 -- totale"),
+            vec![
+                (TokenKind::Keyword, "select"),
+                (TokenKind::Plain, " "),
+                (TokenKind::Number, "42"),
+                (TokenKind::Plain, " "),
+                (TokenKind::Comment, "-- totale"),
+            ]
+        );
+        // Nessun colore per il testo semplice
+        assert_eq!(highlight("", "if x"), vec![(TokenKind::Plain, "if x")]);
+    }
+}
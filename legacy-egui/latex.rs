@@ -0,0 +1,598 @@
+// Conversione delle formule LaTeX in testo Unicode per il rendering markdown.
+// egui non sa disegnare LaTeX: le formule tra $...$, $$...$$, \(...\) e \[...\]
+// vengono riscritte con simboli Unicode (√, ², ∑, α...), il resto resta invariato.
+
+use std::borrow::Cow;
+
+/// Sostituisce le formule LaTeX nel markdown, lasciando intatti i blocchi di codice
+pub fn render_math(markdown: &str) -> Cow<'_, str> {
+    if !markdown.contains('$') && !markdown.contains("\\(") && !markdown.contains("\\[") {
+        return Cow::Borrowed(markdown);
+    }
+
+    let mut output = String::with_capacity(markdown.len());
+    let mut prose = String::new();
+    let mut fence: Option<String> = None;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        match &fence {
+            Some(marker) => {
+                output.push_str(line);
+                if trimmed.starts_with(marker.as_str()) {
+                    fence = None;
+                }
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                output.push_str(&convert_prose(&prose));
+                prose.clear();
+                output.push_str(line);
+                fence = Some(trimmed[..3].to_string());
+            }
+            None => prose.push_str(line),
+        }
+    }
+    output.push_str(&convert_prose(&prose));
+
+    Cow::Owned(output)
+}
+
+/// Testo fuori dai blocchi di codice: converte le formule e salta il codice inline
+fn convert_prose(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let marker = &rest[..ticks];
+            match rest[ticks..].find(marker) {
+                Some(end) => {
+                    let span = ticks + end + ticks;
+                    output.push_str(&rest[..span]);
+                    rest = &rest[span..];
+                }
+                None => {
+                    output.push_str(marker);
+                    rest = &rest[ticks..];
+                }
+            }
+            continue;
+        }
+
+        if let Some((formula, consumed)) = find_formula(rest) {
+            output.push_str(&escape_markdown(&latex_to_unicode(formula)));
+            rest = &rest[consumed..];
+            continue;
+        }
+
+        if rest.starts_with("\\$") {
+            output.push_str("\\$");
+            rest = &rest[2..];
+            continue;
+        }
+
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    output
+}
+
+/// Formula che inizia all'inizio di `text`: contenuto e byte consumati
+fn find_formula(text: &str) -> Option<(&str, usize)> {
+    for (open, close) in [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)")] {
+        if let Some(body) = text.strip_prefix(open) {
+            let end = body.find(close)?;
+            return Some((&body[..end], open.len() + end + close.len()));
+        }
+    }
+
+    // $...$ sulla stessa riga, con le regole di pandoc per non confondere i prezzi
+    // ("costa $5 o $10"): niente spazio dopo l'apertura né prima della chiusura,
+    // e la chiusura non è seguita da una cifra
+    let body = text.strip_prefix('$')?;
+    if body.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let line = &body[..body.find('\n').unwrap_or(body.len())];
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find('$') {
+        let end = search_from + offset;
+        let escaped = line[..end].ends_with('\\');
+        let after = line[end + 1..].chars().next();
+        if end > 0
+            && !escaped
+            && !line[..end].ends_with(char::is_whitespace)
+            && !after.is_some_and(|c| c.is_ascii_digit())
+        {
+            return Some((&body[..end], end + 2));
+        }
+        search_from = end + 1;
+    }
+    None
+}
+
+/// Evita che `*`, `_` e simili nel risultato vengano interpretati come markdown
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '~' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Converte il contenuto di una formula LaTeX in testo Unicode
+pub fn latex_to_unicode(latex: &str) -> String {
+    let chars: Vec<char> = latex.chars().collect();
+    let mut pos = 0;
+    let converted = parse_sequence(&chars, &mut pos, false);
+    collapse_spaces(&converted)
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Legge fino alla fine o alla `}` che chiude il gruppo corrente
+fn parse_sequence(chars: &[char], pos: &mut usize, in_group: bool) -> String {
+    let mut output = String::new();
+
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        match c {
+            '}' if in_group => {
+                *pos += 1;
+                return output;
+            }
+            '{' => {
+                *pos += 1;
+                output.push_str(&parse_sequence(chars, pos, true));
+            }
+            '^' | '_' => {
+                *pos += 1;
+                let argument = parse_argument(chars, pos);
+                output.push_str(&script(&argument, c == '^'));
+            }
+            '\\' => {
+                *pos += 1;
+                output.push_str(&parse_command(chars, pos));
+            }
+            '&' => *pos += 1,
+            '~' => {
+                *pos += 1;
+                output.push(' ');
+            }
+            _ => {
+                *pos += 1;
+                output.push(c);
+            }
+        }
+    }
+
+    output
+}
+
+/// Argomento di un comando o di `^`/`_`: un gruppo, un comando o un singolo carattere
+fn parse_argument(chars: &[char], pos: &mut usize) -> String {
+    while *pos < chars.len() && chars[*pos].is_whitespace() {
+        *pos += 1;
+    }
+
+    match chars.get(*pos) {
+        Some('{') => {
+            *pos += 1;
+            parse_sequence(chars, pos, true)
+        }
+        Some('\\') => {
+            *pos += 1;
+            parse_command(chars, pos)
+        }
+        Some(&c) => {
+            *pos += 1;
+            c.to_string()
+        }
+        None => String::new(),
+    }
+}
+
+fn parse_command(chars: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < chars.len() && chars[*pos].is_ascii_alphabetic() {
+        *pos += 1;
+    }
+
+    // Comandi di un solo simbolo: \, \; \{ \% \\ ...
+    if *pos == start {
+        let Some(&c) = chars.get(*pos) else {
+            return String::new();
+        };
+        *pos += 1;
+        return match c {
+            ',' | ':' | ';' | ' ' => " ".to_string(),
+            '!' => String::new(),
+            '\\' => "; ".to_string(),
+            other => other.to_string(),
+        };
+    }
+
+    let name: String = chars[start..*pos].iter().collect();
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = parse_argument(chars, pos);
+            let denominator = parse_argument(chars, pos);
+            format!("{}/{}", wrap(&numerator), wrap(&denominator))
+        }
+        "sqrt" => {
+            let index = optional_argument(chars, pos);
+            let radicand = parse_argument(chars, pos);
+            let root = match index.as_deref() {
+                Some("3") => "∛".to_string(),
+                Some("4") => "∜".to_string(),
+                Some(index) => format!("{}√", script(index, true)),
+                None => "√".to_string(),
+            };
+            format!("{}{}", root, wrap(&radicand))
+        }
+        "text" | "textrm" | "textit" | "textbf" | "mathrm" | "mathit" | "mathbf" | "mathsf"
+        | "mathcal" | "boldsymbol" | "operatorname" => parse_argument(chars, pos),
+        "mathbb" => parse_argument(chars, pos)
+            .chars()
+            .map(|c| match c {
+                'N' => 'ℕ',
+                'Z' => 'ℤ',
+                'Q' => 'ℚ',
+                'R' => 'ℝ',
+                'C' => 'ℂ',
+                other => other,
+            })
+            .collect(),
+        "hat" | "widehat" => accent(&parse_argument(chars, pos), '\u{0302}'),
+        "bar" | "overline" => accent(&parse_argument(chars, pos), '\u{0304}'),
+        "vec" => accent(&parse_argument(chars, pos), '\u{20D7}'),
+        "dot" => accent(&parse_argument(chars, pos), '\u{0307}'),
+        "tilde" => accent(&parse_argument(chars, pos), '\u{0303}'),
+        "begin" | "end" => {
+            parse_argument(chars, pos);
+            String::new()
+        }
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" => {
+            // Il delimitatore che segue resta, "\left." non ne ha
+            if chars.get(*pos) == Some(&'.') {
+                *pos += 1;
+            }
+            String::new()
+        }
+        "quad" | "qquad" => " ".to_string(),
+        "displaystyle" | "limits" | "nolimits" => String::new(),
+        _ => symbol(&name).map(str::to_string).unwrap_or(name),
+    }
+}
+
+/// `[n]` opzionale, come l'indice di `\sqrt[n]{x}`
+fn optional_argument(chars: &[char], pos: &mut usize) -> Option<String> {
+    if chars.get(*pos) != Some(&'[') {
+        return None;
+    }
+    let end = chars[*pos..].iter().position(|&c| c == ']')? + *pos;
+    let inner: String = chars[*pos + 1..end].iter().collect();
+    *pos = end + 1;
+    Some(latex_to_unicode(&inner))
+}
+
+/// Parentesi attorno agli argomenti composti, per non cambiare il senso di a+b/c
+fn wrap(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= 1 || text.chars().all(|c| c.is_alphanumeric() || c == '.') {
+        text.to_string()
+    } else {
+        format!("({})", text)
+    }
+}
+
+fn accent(text: &str, mark: char) -> String {
+    let mut output = text.to_string();
+    output.push(mark);
+    output
+}
+
+/// Apice o pedice in caratteri Unicode quando esistono per tutti i caratteri,
+/// altrimenti ^(...) / _(...)
+fn script(text: &str, superscript: bool) -> String {
+    let text = text.trim();
+    let mapped: Option<String> = text
+        .chars()
+        .map(|c| {
+            if superscript {
+                superscript_char(c)
+            } else {
+                subscript_char(c)
+            }
+        })
+        .collect();
+
+    match mapped {
+        Some(mapped) if !mapped.is_empty() => mapped,
+        _ => {
+            let marker = if superscript { '^' } else { '_' };
+            // `x^` a fine formula: resta il simbolo, senza parentesi vuote
+            if text.chars().count() <= 1 {
+                format!("{}{}", marker, text)
+            } else {
+                format!("{}({})", marker, text)
+            }
+        }
+    }
+}
+
+fn superscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        '′' => '′',
+        '∗' | '*' => '*',
+        _ => return None,
+    })
+}
+
+fn subscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" | "cdotp" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "leftrightarrow" => "↔",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "cdots" | "ldots" | "dots" => "…",
+        "vdots" => "⋮",
+        "angle" => "∠",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "circ" => "∘",
+        "degree" => "°",
+        "prime" => "′",
+        "ell" => "ℓ",
+        "hbar" => "ℏ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" | "mid" => "|",
+        "Vert" => "‖",
+        "star" => "⋆",
+        "bullet" => "•",
+        "therefore" => "∴",
+        "because" => "∵",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fractions() {
+        assert_eq!(latex_to_unicode(r"\frac{a}{b}"), "a/b");
+        assert_eq!(latex_to_unicode(r"\frac{a+b}{c}"), "(a+b)/c");
+        assert_eq!(latex_to_unicode(r"\frac12"), "1/2");
+        assert_eq!(latex_to_unicode(r"\dfrac{1}{x^2}"), "1/x²");
+    }
+
+    #[test]
+    fn test_scripts() {
+        assert_eq!(latex_to_unicode("x^2 + y_1"), "x² + y₁");
+        assert_eq!(latex_to_unicode("x^{n+1}"), "xⁿ⁺¹");
+        assert_eq!(latex_to_unicode("a_{ij}"), "aᵢⱼ");
+        assert_eq!(latex_to_unicode(r"\sum_{i=1}^{n} i"), "∑ᵢ₌₁ⁿ i");
+        // Senza un carattere Unicode per ogni simbolo si usa la forma testuale
+        assert_eq!(latex_to_unicode(r"e^{i\pi}"), "e^(iπ)");
+        assert_eq!(latex_to_unicode("x_q"), "x_q");
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(latex_to_unicode(r"\sqrt{x}"), "√x");
+        assert_eq!(latex_to_unicode(r"\sqrt{a+b}"), "√(a+b)");
+        assert_eq!(latex_to_unicode(r"\sqrt[3]{x+1}"), "∛(x+1)");
+        assert_eq!(latex_to_unicode(r"\sqrt[n]{x}"), "ⁿ√x");
+    }
+
+    #[test]
+    fn test_symbols_and_unknown_commands() {
+        assert_eq!(latex_to_unicode(r"\alpha + \beta \leq \infty"), "α + β ≤ ∞");
+        assert_eq!(latex_to_unicode(r"\text{area} = \pi r^2"), "area = π r²");
+        assert_eq!(latex_to_unicode(r"\left( x \right)"), "( x )");
+        // Un comando sconosciuto mantiene il nome, senza la barra
+        assert_eq!(latex_to_unicode(r"\unknown"), "unknown");
+        assert_eq!(latex_to_unicode(r"\foo{x}"), "foox");
+    }
+
+    #[test]
+    fn test_unbalanced_braces() {
+        assert_eq!(latex_to_unicode("{a"), "a");
+        assert_eq!(latex_to_unicode("a}"), "a}");
+        assert_eq!(latex_to_unicode(r"\frac{a"), "a/");
+        assert_eq!(latex_to_unicode("x^"), "x^");
+        assert_eq!(latex_to_unicode(r"\sqrt[3"), "√[3");
+    }
+
+    #[test]
+    fn test_render_math() {
+        assert_eq!(
+            render_math(r"La formula $x^2$ e $$\frac{1}{2}$$ fine"),
+            "La formula x² e 1/2 fine"
+        );
+        assert_eq!(render_math(r"\(a*b\) e \[x_i\]"), r"a\*b e xᵢ");
+        // Codice inline e blocchi di codice restano invariati
+        assert_eq!(
+            render_math("`$x^2$` e\n```\n$a_1$\n```\ndopo $a_1$"),
+            "`$x^2$` e\n```\n$a_1$\n```\ndopo a₁"
+        );
+        assert_eq!(render_math("prezzo 5$ e 10$"), "prezzo 5$ e 10$");
+        assert!(matches!(render_math("nessuna formula"), Cow::Borrowed(_)));
+    }
+}
@@ -5,27 +5,31 @@ use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use lopdf::Document;
 use poll_promise::Promise;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs;
 use std::net::IpAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 
 mod agent;
+mod code_highlight;
+mod latex;
+mod markdown_table;
 mod mcp_sql;
-use agent::{AgentSystem, ToolCall, ToolResult};
+mod settings;
+mod theme;
+use agent::{AgentSystem, AgentTrace, ToolCall, ToolResult};
+use settings::AppSettings;
+use theme::{Palette, ThemeMode};
 
 // Helper per ottenere timestamp formattato
 fn get_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    // Calcola ore e minuti dal timestamp Unix (considera fuso orario UTC+1 per Italia)
-    let total_seconds = now + 3600; // +1 ora per CET/CEST
-    let hours = (total_seconds / 3600) % 24;
-    let minutes = (total_seconds % 3600) / 60;
-    format!("{:02}:{:02}", hours, minutes)
+    // Ora locale del sistema, con gestione automatica dell'ora legale
+    chrono::Local::now().format("%H:%M").to_string()
 }
 
 #[derive(Debug, Serialize)]
@@ -43,6 +47,8 @@ struct Message {
     hidden: bool, // Se true, non mostrare nella chat UI
     #[serde(skip)]
     timestamp: Option<String>, // Orario del messaggio
+    #[serde(skip)]
+    tool_result: Option<ToolResult>, // Se presente, il messaggio mostra l'output di un tool
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,11 +70,11 @@ impl ModelInfo {
     fn weight_category(&self) -> (&str, egui::Color32) {
         let gb = self.size_gb();
         if gb < 4.0 {
-            ("🟢", egui::Color32::from_rgb(52, 199, 89)) // Verde - leggero
+            ("🟢", theme::SUCCESS) // Verde - leggero
         } else if gb < 8.0 {
-            ("🟡", egui::Color32::from_rgb(255, 204, 0)) // Giallo - medio
+            ("🟡", theme::WARNING) // Giallo - medio
         } else {
-            ("🔴", egui::Color32::from_rgb(255, 59, 48)) // Rosso - pesante
+            ("🔴", theme::DANGER) // Rosso - pesante
         }
     }
 }
@@ -152,7 +158,12 @@ impl OllamaClient {
     }
 }
 
-async fn scan_local_network() -> Vec<String> {
+/// Host della sottorete controllati contemporaneamente
+const SCAN_MAX_CONCURRENCY: usize = 32;
+
+/// Scansiona localhost e la sottorete; con `cancel` i controlli in corso vengono
+/// interrotti e si restituiscono i server trovati fino a quel momento
+async fn scan_local_network(cancel: CancellationToken) -> Vec<String> {
     let mut servers = Vec::new();
 
     // Controlla localhost
@@ -167,6 +178,10 @@ async fn scan_local_network() -> Vec<String> {
         servers.push("http://127.0.0.1:11434".to_string());
     }
 
+    if cancel.is_cancelled() {
+        return servers;
+    }
+
     // Ottieni l'IP locale
     if let Ok(local_ip) = local_ip_address::local_ip() {
         match local_ip {
@@ -174,29 +189,44 @@ async fn scan_local_network() -> Vec<String> {
                 let octets = ip.octets();
                 let base = format!("{}.{}.{}", octets[0], octets[1], octets[2]);
 
-                // Scansiona gli IP comuni nella rete locale (range ristretto per velocità)
-                let mut handles = vec![];
+                // Scansiona gli IP comuni nella rete locale, pochi alla volta per non
+                // esaurire i file descriptor
+                let permits = Arc::new(Semaphore::new(SCAN_MAX_CONCURRENCY));
+                let mut checks = JoinSet::new();
 
-                for i in 1..255 {
+                for i in 1..255u8 {
                     let url = format!("http://{}.{}:11434", base, i);
-                    let handle = tokio::spawn(async move {
+                    let permits = permits.clone();
+                    checks.spawn(async move {
+                        let _permit = permits.acquire_owned().await.ok()?;
                         if OllamaClient::check_server(&url).await {
-                            Some(url)
+                            Some((i, url))
                         } else {
                             None
                         }
                     });
-                    handles.push(handle);
                 }
 
-                // Raccogli i risultati
-                for handle in handles {
-                    if let Ok(Some(url)) = handle.await {
-                        if !servers.contains(&url) {
-                            servers.push(url);
-                        }
+                // Raccogli i risultati finché la scansione non viene annullata
+                let mut found: Vec<(u8, String)> = Vec::new();
+                loop {
+                    tokio::select! {
+                        result = checks.join_next() => match result {
+                            Some(Ok(Some((i, url)))) => {
+                                if !servers.contains(&url) {
+                                    found.push((i, url));
+                                }
+                            }
+                            Some(_) => {}
+                            None => break,
+                        },
+                        _ = cancel.cancelled() => break,
                     }
                 }
+                checks.abort_all();
+
+                found.sort_by_key(|(i, _)| *i);
+                servers.extend(found.into_iter().map(|(_, url)| url));
             }
             _ => {}
         }
@@ -285,6 +315,16 @@ fn extract_text_from_excel(path: &PathBuf) -> Result<String> {
     Ok(text)
 }
 
+/// Estensioni dei file che si possono allegare (dialog e drag-and-drop)
+const ATTACHMENT_EXTENSIONS: &[&str] = &["pdf", "xlsx", "xls", "ods", "txt", "md", "csv"];
+
+fn is_supported_attachment(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|ext| ATTACHMENT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
 fn extract_text_from_file(path: &PathBuf) -> Result<String> {
     let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
@@ -299,6 +339,530 @@ fn extract_text_from_file(path: &PathBuf) -> Result<String> {
     }
 }
 
+/// Righe massime mostrate nella tabella di un risultato SQL
+const MAX_TABLE_ROWS: usize = 500;
+
+/// Tabella di un `QueryResult` con colonne ordinabili (clic sull'intestazione)
+fn show_query_result_table(
+    ui: &mut egui::Ui,
+    id: usize,
+    result: &mcp_sql::QueryResult,
+    sort: &mut Option<(usize, bool)>,
+    font_scale: f32,
+) {
+    if result.columns.is_empty() {
+        ui.label("Nessuna colonna restituita");
+        return;
+    }
+
+    let mut order: Vec<usize> = (0..result.rows.len()).collect();
+    if let Some((column_index, ascending)) = *sort {
+        if let Some(column) = result.columns.get(column_index) {
+            order.sort_by(|&a, &b| {
+                let null = serde_json::Value::Null;
+                let left = result.rows[a].get(&column.name).unwrap_or(&null);
+                let right = result.rows[b].get(&column.name).unwrap_or(&null);
+                let ordering = compare_sql_values(left, right);
+                if ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+    }
+
+    egui::ScrollArea::both()
+        .id_source(("query_table", id))
+        .max_height(320.0)
+        .show(ui, |ui| {
+            egui::Grid::new(("query_grid", id))
+                .striped(true)
+                .spacing(egui::vec2(12.0, 4.0))
+                .show(ui, |ui| {
+                    for (column_index, column) in result.columns.iter().enumerate() {
+                        let arrow = match *sort {
+                            Some((index, true)) if index == column_index => " ▲",
+                            Some((index, false)) if index == column_index => " ▼",
+                            _ => "",
+                        };
+                        if ui
+                            .button(egui::RichText::new(format!("{}{}", column.name, arrow)).strong())
+                            .on_hover_text(&column.data_type)
+                            .clicked()
+                        {
+                            *sort = match *sort {
+                                Some((index, ascending)) if index == column_index => {
+                                    Some((index, !ascending))
+                                }
+                                _ => Some((column_index, true)),
+                            };
+                        }
+                    }
+                    ui.end_row();
+
+                    for &row_index in order.iter().take(MAX_TABLE_ROWS) {
+                        let row = &result.rows[row_index];
+                        for column in &result.columns {
+                            let value = row
+                                .get(&column.name)
+                                .map(agent::value_to_display)
+                                .unwrap_or_default();
+                            ui.label(value);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    let total = result.rows.len();
+    let summary = if total > MAX_TABLE_ROWS {
+        format!("{} righe, mostrate le prime {}", total, MAX_TABLE_ROWS)
+    } else {
+        format!("{} righe", total)
+    };
+    ui.label(
+        egui::RichText::new(summary)
+            .size(11.0 * font_scale)
+            .color(theme::SECONDARY_TEXT),
+    );
+}
+
+/// Ordinamento per le colonne SQL: numeri per valore, il resto come testo, NULL in fondo
+fn compare_sql_values(left: &serde_json::Value, right: &serde_json::Value) -> Ordering {
+    use serde_json::Value;
+
+    match (left, right) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        _ => agent::value_to_display(left).cmp(&agent::value_to_display(right)),
+    }
+}
+
+/// Converte il risultato di una query in CSV (RFC 4180), con intestazione dei nomi colonna
+fn query_result_to_csv(result: &mcp_sql::QueryResult) -> String {
+    fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    let mut csv = result
+        .columns
+        .iter()
+        .map(|column| csv_field(&column.name))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push_str("\r\n");
+
+    for row in &result.rows {
+        let line = result
+            .columns
+            .iter()
+            .map(|column| match row.get(&column.name) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(text)) => csv_field(text),
+                Some(value) => csv_field(&value.to_string()),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&line);
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// Restituisce la chiamata ripetuta se due chiamate consecutive (inclusa l'ultima
+/// eseguita nell'iterazione precedente) usano lo stesso tool con gli stessi parametri
+fn find_repeated_tool_call<'a>(
+    previous: Option<&'a ToolCall>,
+    calls: &'a [ToolCall],
+) -> Option<&'a ToolCall> {
+    let mut last = previous;
+    for call in calls {
+        if last.map(|last| last.is_same_call(call)).unwrap_or(false) {
+            return Some(call);
+        }
+        last = Some(call);
+    }
+    None
+}
+
+/// Separa i blocchi `<think>...</think>` dei modelli reasoning (deepseek-r1, qwq)
+/// dalla risposta finale. Gestisce anche il tag di apertura mancante o quello di
+/// chiusura assente (risposta troncata).
+fn split_thinking(content: &str) -> (Option<String>, String) {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+
+    if !content.contains(OPEN) && !content.contains(CLOSE) {
+        return (None, content.to_string());
+    }
+
+    let mut thinking = Vec::new();
+    let mut answer = String::new();
+    let mut rest = content;
+
+    // Alcuni modelli omettono il tag di apertura e iniziano direttamente a ragionare
+    if let Some(close) = rest.find(CLOSE) {
+        if !rest[..close].contains(OPEN) {
+            thinking.push(rest[..close].trim().to_string());
+            rest = &rest[close + CLOSE.len()..];
+        }
+    }
+
+    while let Some(open) = rest.find(OPEN) {
+        answer.push_str(&rest[..open]);
+        let after_open = &rest[open + OPEN.len()..];
+        match after_open.find(CLOSE) {
+            Some(close) => {
+                thinking.push(after_open[..close].trim().to_string());
+                rest = &after_open[close + CLOSE.len()..];
+            }
+            None => {
+                thinking.push(after_open.trim().to_string());
+                rest = "";
+            }
+        }
+    }
+    answer.push_str(rest);
+
+    thinking.retain(|block| !block.is_empty());
+    let thinking = (!thinking.is_empty()).then(|| thinking.join("\n\n"));
+
+    (thinking, answer.trim().to_string())
+}
+
+/// Markdown con le tabelle disegnate a parte da `show_markdown_table` e i blocchi
+/// di codice da `show_code_block`: CommonMarkViewer mostrerebbe le prime senza
+/// bordi né allineamento e il codice senza colori
+fn show_markdown(
+    ui: &mut egui::Ui,
+    cache: &mut CommonMarkCache,
+    id: impl std::hash::Hash + Copy,
+    markdown: &str,
+) {
+    let blocks = markdown_table::split_tables(markdown);
+    for (block_index, block) in blocks.iter().enumerate() {
+        match block {
+            markdown_table::Block::Markdown(text) => {
+                let segments = code_highlight::split_code_blocks(text);
+                for (segment_index, segment) in segments.into_iter().enumerate() {
+                    match segment {
+                        code_highlight::Segment::Markdown(text) => {
+                            CommonMarkViewer::new().show(ui, cache, text);
+                        }
+                        code_highlight::Segment::Code { language, code } => {
+                            show_code_block(ui, (id, block_index, segment_index), language, code);
+                        }
+                    }
+                }
+            }
+            markdown_table::Block::Table(table) => {
+                show_markdown_table(ui, (id, block_index), table);
+            }
+        }
+    }
+}
+
+/// Codice in monospace su sfondo proprio, colorato secondo il linguaggio con gli
+/// stessi colori dell'esportazione HTML. Le righe lunghe scorrono in orizzontale
+fn show_code_block(ui: &mut egui::Ui, id: impl std::hash::Hash + Copy, language: &str, code: &str) {
+    let dark = ui.visuals().dark_mode;
+    let text_color = ui.visuals().text_color();
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+
+    let mut job = egui::text::LayoutJob::default();
+    job.wrap.max_width = f32::INFINITY;
+    for (kind, token) in code_highlight::highlight(language, code) {
+        let color = match (kind, dark) {
+            (code_highlight::TokenKind::Plain, _) => text_color,
+            (code_highlight::TokenKind::Keyword, false) => egui::Color32::from_rgb(173, 61, 164),
+            (code_highlight::TokenKind::Keyword, true) => egui::Color32::from_rgb(255, 122, 178),
+            (code_highlight::TokenKind::String, false) => egui::Color32::from_rgb(209, 47, 27),
+            (code_highlight::TokenKind::String, true) => egui::Color32::from_rgb(255, 129, 112),
+            (code_highlight::TokenKind::Number, false) => egui::Color32::from_rgb(39, 42, 216),
+            (code_highlight::TokenKind::Number, true) => egui::Color32::from_rgb(217, 201, 124),
+            (code_highlight::TokenKind::Comment, false) => egui::Color32::from_rgb(112, 127, 140),
+            (code_highlight::TokenKind::Comment, true) => egui::Color32::from_rgb(127, 140, 152),
+        };
+        let mut format = egui::TextFormat::simple(font.clone(), color);
+        format.italics = kind == code_highlight::TokenKind::Comment;
+        job.append(token, 0.0, format);
+    }
+
+    // Impaginato qui senza larghezza massima, così la Label non va a capo
+    let galley = ui.fonts(|fonts| fonts.layout_job(job));
+
+    egui::Frame::none()
+        .fill(ui.visuals().code_bg_color)
+        .rounding(egui::Rounding::same(6.0))
+        .inner_margin(egui::Margin::symmetric(10.0, 8.0))
+        .show(ui, |ui| {
+            egui::ScrollArea::horizontal()
+                .id_source(("code_block", id))
+                .show(ui, |ui| {
+                    ui.label(galley);
+                });
+        });
+}
+
+/// Tabella con bordo, intestazione in evidenza e colonne allineate; le tabelle
+/// più larghe della bolla scorrono in orizzontale
+fn show_markdown_table(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash + Copy,
+    table: &markdown_table::Table,
+) {
+    let alignments: Vec<_> = (0..table.headers.len())
+        .map(|column| table.alignment(column))
+        .collect();
+    let header_color = ui.visuals().strong_text_color();
+    let text_color = ui.visuals().text_color();
+
+    egui::Frame::none()
+        .stroke(ui.visuals().widgets.noninteractive.bg_stroke)
+        .rounding(egui::Rounding::same(6.0))
+        .inner_margin(egui::Margin::symmetric(8.0, 6.0))
+        .show(ui, |ui| {
+            egui::ScrollArea::horizontal()
+                .id_source(("markdown_table", id))
+                .show(ui, |ui| {
+                    egui::Grid::new(("markdown_grid", id))
+                        .striped(true)
+                        .spacing(egui::vec2(16.0, 6.0))
+                        .show(ui, |ui| {
+                            for (header, &alignment) in table.headers.iter().zip(&alignments) {
+                                show_table_cell(ui, alignment, header, header_color);
+                            }
+                            ui.end_row();
+
+                            for row in &table.rows {
+                                for (cell, &alignment) in row.iter().zip(&alignments) {
+                                    show_table_cell(ui, alignment, cell, text_color);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+}
+
+/// Cella con grassetto, `codice` e link resi come nel resto della risposta;
+/// i link si aprono con un clic
+fn show_table_cell(
+    ui: &mut egui::Ui,
+    alignment: markdown_table::Alignment,
+    cell: &str,
+    color: egui::Color32,
+) {
+    let layout = match alignment {
+        markdown_table::Alignment::Right => egui::Layout::right_to_left(egui::Align::Center),
+        markdown_table::Alignment::Center => egui::Layout::top_down(egui::Align::Center),
+        _ => egui::Layout::left_to_right(egui::Align::Center),
+    };
+    ui.with_layout(layout, |ui| {
+        let visuals = ui.visuals().clone();
+        let body = egui::TextStyle::Body.resolve(ui.style());
+        let monospace = egui::TextStyle::Monospace.resolve(ui.style());
+
+        let mut job = egui::text::LayoutJob::default();
+        // Intervalli in caratteri del testo dei link, per riconoscerli sotto il puntatore
+        let mut links = Vec::new();
+        for span in markdown_table::inline_spans(cell) {
+            let (text, format) = match span {
+                markdown_table::Span::Text(text) => {
+                    (text, egui::TextFormat::simple(body.clone(), color))
+                }
+                markdown_table::Span::Strong(text) => (
+                    text,
+                    egui::TextFormat::simple(body.clone(), visuals.strong_text_color()),
+                ),
+                markdown_table::Span::Code(text) => (
+                    text,
+                    egui::TextFormat {
+                        font_id: monospace.clone(),
+                        color,
+                        background: visuals.code_bg_color,
+                        ..Default::default()
+                    },
+                ),
+                markdown_table::Span::Link { text, url } => {
+                    let start = job.text.chars().count();
+                    links.push((start..start + text.chars().count(), url));
+                    (
+                        text,
+                        egui::TextFormat {
+                            font_id: body.clone(),
+                            color: visuals.hyperlink_color,
+                            underline: egui::Stroke::new(1.0, visuals.hyperlink_color),
+                            ..Default::default()
+                        },
+                    )
+                }
+            };
+            job.append(&text, 0.0, format);
+        }
+
+        let (pos, galley, response) = egui::Label::new(job)
+            .sense(egui::Sense::click())
+            .layout_in_ui(ui);
+        if ui.is_rect_visible(response.rect) {
+            ui.painter().galley(pos, galley.clone(), color);
+        }
+
+        let hovered_link = response.hover_pos().and_then(|pointer| {
+            let index = galley.cursor_from_pos(pointer - pos).ccursor.index;
+            links
+                .iter()
+                .find(|(range, _)| range.contains(&index))
+                .map(|(_, url)| url.clone())
+        });
+        if let Some(url) = hovered_link {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+            if response.clicked() {
+                ui.ctx().open_url(egui::OpenUrl::new_tab(&url));
+            }
+            response.on_hover_text(url);
+        }
+    });
+}
+
+// Estrae i blocchi di codice delimitati da ``` (linguaggio, contenuto) per i pulsanti "copia"
+fn extract_code_blocks(content: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("```") {
+            match current.take() {
+                Some((language, lines)) => blocks.push((language, lines.join("\n"))),
+                None => current = Some((rest.trim().to_string(), Vec::new())),
+            }
+            continue;
+        }
+
+        if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+
+    // Blocco non chiuso (es. risposta troncata): lo rendiamo comunque copiabile
+    if let Some((language, lines)) = current {
+        blocks.push((language, lines.join("\n")));
+    }
+
+    blocks
+}
+
+/// Azioni richiamabili da tastiera nella chat
+#[derive(Clone, Copy)]
+enum ShortcutAction {
+    NewChat,
+    SearchConversation,
+    FocusInput,
+    Cancel,
+    ShowHelp,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+/// Scorciatoie della chat: basta cambiare questa tabella, il pannello di aiuto
+/// la mostra così com'è. `COMMAND` è Ctrl su Windows/Linux e Cmd su macOS
+const SHORTCUTS: &[(egui::KeyboardShortcut, ShortcutAction, &str)] = &[
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::N),
+        ShortcutAction::NewChat,
+        "Nuova chat",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::K),
+        ShortcutAction::SearchConversation,
+        "Cerca nella conversazione",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::L),
+        ShortcutAction::FocusInput,
+        "Vai al campo di testo",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::Escape),
+        ShortcutAction::Cancel,
+        "Annulla la generazione o chiudi la ricerca",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F1),
+        ShortcutAction::ShowHelp,
+        "Mostra le scorciatoie da tastiera",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Plus),
+        ShortcutAction::ZoomIn,
+        "Ingrandisci il testo",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Minus),
+        ShortcutAction::ZoomOut,
+        "Riduci il testo",
+    ),
+    (
+        egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Num0),
+        ShortcutAction::ZoomReset,
+        "Testo a dimensione normale",
+    ),
+];
+
+/// Dimensioni del testo a scala 100%, quelle non indicate restano ai default di egui
+const BASE_TEXT_SIZES: &[(egui::TextStyle, f32)] = &[
+    (egui::TextStyle::Body, 15.0),
+    (egui::TextStyle::Button, 14.0),
+    (egui::TextStyle::Heading, 22.0),
+];
+
+/// Quanto le impostazioni devono restare ferme prima di essere scritte su disco
+const SETTINGS_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(800);
+
+/// Sezioni del pannello "Impostazioni". Manca "Calendario": questa versione non
+/// ha il calendario (c'è solo nell'app Tauri), quindi non ci sono scelte da salvare
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsSection {
+    General,
+    Backend,
+    Agent,
+    Sql,
+    Privacy,
+}
+
+impl SettingsSection {
+    const ALL: [SettingsSection; 5] = [
+        SettingsSection::General,
+        SettingsSection::Backend,
+        SettingsSection::Agent,
+        SettingsSection::Sql,
+        SettingsSection::Privacy,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SettingsSection::General => "Generale",
+            SettingsSection::Backend => "Backend",
+            SettingsSection::Agent => "Agente",
+            SettingsSection::Sql => "SQL",
+            SettingsSection::Privacy => "Privacy",
+        }
+    }
+}
+
 #[derive(PartialEq)]
 enum AppState {
     Setup,
@@ -318,13 +882,17 @@ struct OllamaChatApp {
     error_message: Option<String>,
     client: Option<OllamaClient>,
     scanning_promise: Option<Promise<Vec<String>>>,
+    scan_cancel: Option<CancellationToken>,
     loading_models_promise: Option<Promise<Result<Vec<ModelInfo>>>>,
     chat_promise: Option<Promise<Result<String>>>,
     scroll_to_bottom: bool,
     markdown_cache: CommonMarkCache,
+    query_table_sort: HashMap<usize, (usize, bool)>, // indice messaggio -> (colonna, crescente)
     system_prompt_added: bool,
     attached_files: Vec<(String, String)>, // (nome_file, contenuto)
-    file_loading_promise: Option<Promise<Result<(String, String)>>>,
+    file_picker_promise: Option<Promise<Vec<PathBuf>>>,
+    file_loading_promises: Vec<(String, Promise<Result<String>>)>, // (nome_file, estrazione in corso)
+    tool_save_promise: Option<Promise<Result<Option<PathBuf>>>>,   // None se annullato
     // Nuovi campi per funzionalità agentiche
     agent_system: AgentSystem,
     agent_mode_enabled: bool,
@@ -333,6 +901,14 @@ struct OllamaChatApp {
     awaiting_confirmation: Option<ToolCall>,
     max_agent_iterations: usize,
     current_agent_iteration: usize,
+    last_tool_call: Option<ToolCall>, // Ultima chiamata eseguita, per rilevare i loop
+    agent_trace: AgentTrace,
+    show_agent_trace: bool,
+    // Ricerca nella conversazione (Ctrl+K): None quando la barra è chiusa
+    conversation_search: Option<String>,
+    focus_search: bool,
+    focus_input: bool,
+    show_shortcuts: bool,
     // Campi per configurazione SQL Server
     show_sql_config: bool,
     sql_server: String,
@@ -343,6 +919,21 @@ struct OllamaChatApp {
     sql_connection_status: Option<String>, // None, Some("connecting"), Some("connected: ..."), Some("error: ...")
     sql_test_promise: Option<Promise<Result<String>>>,
     sql_trust_server_certificate: bool,
+    // Impostazioni persistenti: ultime salvate su disco, modifiche in attesa
+    // di essere salvate (con l'istante dell'ultima) e pannello
+    saved_settings: AppSettings,
+    unsaved_settings: Option<(AppSettings, std::time::Instant)>,
+    scan_network_on_startup: bool,
+    remember_sql_username: bool,
+    theme_mode: ThemeMode,
+    accent_color: [u8; 3],
+    font_scale: f32,
+    // Tema del sistema visto l'ultima volta con "Sistema", e se ora è forzato
+    system_dark: bool,
+    theme_forced: bool,
+    show_settings: bool,
+    settings_were_open: bool,
+    settings_section: SettingsSection,
 }
 
 impl Default for OllamaChatApp {
@@ -358,13 +949,17 @@ impl Default for OllamaChatApp {
             error_message: None,
             client: None,
             scanning_promise: None,
+            scan_cancel: None,
             loading_models_promise: None,
             chat_promise: None,
             scroll_to_bottom: false,
             markdown_cache: CommonMarkCache::default(),
+            query_table_sort: HashMap::new(),
             system_prompt_added: false,
             attached_files: Vec::new(),
-            file_loading_promise: None,
+            file_picker_promise: None,
+            file_loading_promises: Vec::new(),
+            tool_save_promise: None,
             agent_system: AgentSystem::new(),
             agent_mode_enabled: false,
             tool_execution_promise: None,
@@ -372,6 +967,13 @@ impl Default for OllamaChatApp {
             awaiting_confirmation: None,
             max_agent_iterations: 5,
             current_agent_iteration: 0,
+            last_tool_call: None,
+            agent_trace: AgentTrace::default(),
+            show_agent_trace: false,
+            conversation_search: None,
+            focus_search: false,
+            focus_input: false,
+            show_shortcuts: false,
             show_sql_config: false,
             sql_server: "localhost".to_string(),
             sql_database: String::new(),
@@ -381,26 +983,173 @@ impl Default for OllamaChatApp {
             sql_connection_status: None,
             sql_test_promise: None,
             sql_trust_server_certificate: false,
+            saved_settings: AppSettings::default(),
+            unsaved_settings: None,
+            scan_network_on_startup: true,
+            remember_sql_username: false,
+            theme_mode: ThemeMode::System,
+            accent_color: theme::DEFAULT_ACCENT,
+            font_scale: 1.0,
+            system_dark: false,
+            theme_forced: false,
+            show_settings: false,
+            settings_were_open: false,
+            settings_section: SettingsSection::General,
         }
     }
 }
 
 impl OllamaChatApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut app = Self::default();
-        app.start_network_scan();
+        app.system_dark = cc.egui_ctx.style().visuals.dark_mode;
+        // Ctrl+/Ctrl- cambiano la scala del testo, non lo zoom di tutta l'interfaccia
+        cc.egui_ctx
+            .options_mut(|options| options.zoom_with_keyboard = false);
+        let settings = settings::load_settings().unwrap_or_else(|e| {
+            app.error_message = Some(format!("Impostazioni non caricate: {}", e));
+            AppSettings::default()
+        });
+        app.apply_settings(&settings);
+        app.saved_settings = settings;
+
+        if app.scan_network_on_startup {
+            app.start_network_scan();
+        }
         app
     }
 
+    fn apply_settings(&mut self, settings: &AppSettings) {
+        self.scan_network_on_startup = settings.scan_network_on_startup;
+        self.remember_sql_username = settings.remember_sql_username;
+        self.theme_mode = settings.theme;
+        self.accent_color = settings.accent_color;
+        self.font_scale = settings
+            .font_scale
+            .clamp(settings::MIN_FONT_SCALE, settings::MAX_FONT_SCALE);
+        self.ollama_url = settings.ollama_url.clone();
+        self.agent_mode_enabled = settings.agent_mode_enabled;
+        self.max_agent_iterations = settings.max_agent_iterations.clamp(1, 20);
+        self.show_agent_trace = settings.show_agent_trace;
+        self.sql_server = settings.sql_server.clone();
+        self.sql_database = settings.sql_database.clone();
+        self.sql_auth_method = settings.sql_auth_method.clone();
+        self.sql_trust_server_certificate = settings.sql_trust_server_certificate;
+        self.sql_username = settings.sql_username.clone();
+    }
+
+    /// Impostazioni correnti, lette dallo stato dell'app
+    fn current_settings(&self) -> AppSettings {
+        AppSettings {
+            scan_network_on_startup: self.scan_network_on_startup,
+            theme: self.theme_mode,
+            accent_color: self.accent_color,
+            font_scale: self.font_scale,
+            ollama_url: self.ollama_url.clone(),
+            last_model: self
+                .selected_model
+                .clone()
+                .or_else(|| self.saved_settings.last_model.clone()),
+            agent_mode_enabled: self.agent_mode_enabled,
+            max_agent_iterations: self.max_agent_iterations,
+            show_agent_trace: self.show_agent_trace,
+            sql_server: self.sql_server.clone(),
+            sql_database: self.sql_database.clone(),
+            sql_auth_method: self.sql_auth_method.clone(),
+            sql_trust_server_certificate: self.sql_trust_server_certificate,
+            remember_sql_username: self.remember_sql_username,
+            sql_username: if self.remember_sql_username {
+                self.sql_username.clone()
+            } else {
+                String::new()
+            },
+        }
+    }
+
+    /// Salva le impostazioni cambiate da qualunque punto dell'interfaccia quando
+    /// restano ferme per `SETTINGS_SAVE_DELAY`, così scrivendo l'URL del server
+    /// il file non viene riscritto a ogni tasto. Con `flush` salva subito
+    fn persist_settings(&mut self, ctx: &egui::Context, flush: bool) {
+        let current = self.current_settings();
+        if current == self.saved_settings {
+            self.unsaved_settings = None;
+            return;
+        }
+
+        let changed_at = match &self.unsaved_settings {
+            Some((pending, changed_at)) if *pending == current => *changed_at,
+            _ => std::time::Instant::now(),
+        };
+        if !flush && changed_at.elapsed() < SETTINGS_SAVE_DELAY {
+            self.unsaved_settings = Some((current, changed_at));
+            // Ridisegna allo scadere anche se nel frattempo non succede nulla
+            ctx.request_repaint_after(SETTINGS_SAVE_DELAY);
+            return;
+        }
+
+        if let Err(e) = settings::save_settings(&current) {
+            self.error_message = Some(format!("Impostazioni non salvate: {}", e));
+        }
+        self.saved_settings = current;
+        self.unsaved_settings = None;
+    }
+
+    /// Tema scuro o chiaro secondo l'impostazione. Con "Sistema" vale quello scelto
+    /// da eframe in base al sistema operativo, ricordato per quando si torna
+    /// da un tema forzato
+    fn theme_is_dark(&mut self, ctx: &egui::Context) -> bool {
+        match self.theme_mode {
+            ThemeMode::System if self.theme_forced => {
+                self.theme_forced = false;
+                self.system_dark
+            }
+            ThemeMode::System => {
+                self.system_dark = ctx.style().visuals.dark_mode;
+                self.system_dark
+            }
+            ThemeMode::Light | ThemeMode::Dark => {
+                self.theme_forced = true;
+                self.theme_mode == ThemeMode::Dark
+            }
+        }
+    }
+
+    /// Ingrandisce o riduce il testo di `steps` passi, entro i limiti consentiti
+    fn change_font_scale(&mut self, steps: f32) {
+        let scale = self.font_scale + steps * settings::FONT_SCALE_STEP;
+        // Arrotonda al passo per non accumulare errori di virgola mobile
+        let scale = (scale / settings::FONT_SCALE_STEP).round() * settings::FONT_SCALE_STEP;
+        self.font_scale = scale.clamp(settings::MIN_FONT_SCALE, settings::MAX_FONT_SCALE);
+    }
+
+    /// Torna alla schermata iniziale mantenendo le impostazioni
+    fn disconnect(&mut self) {
+        let settings = self.current_settings();
+        *self = Self::default();
+        self.apply_settings(&settings);
+        self.saved_settings = settings;
+    }
+
     fn start_network_scan(&mut self) {
+        let cancel = CancellationToken::new();
+        self.scan_cancel = Some(cancel.clone());
         self.state = AppState::ScanningNetwork;
         self.scanning_promise = Some(Promise::spawn_thread("scan_network", move || {
             tokio::runtime::Runtime::new()
                 .unwrap()
-                .block_on(scan_local_network())
+                .block_on(scan_local_network(cancel))
         }));
     }
 
+    /// Interrompe la scansione e torna al Setup per l'inserimento manuale dell'URL
+    fn cancel_network_scan(&mut self) {
+        if let Some(cancel) = self.scan_cancel.take() {
+            cancel.cancel();
+        }
+        self.scanning_promise = None;
+        self.state = AppState::Setup;
+    }
+
     fn load_models(&mut self) {
         let client = OllamaClient::new(self.ollama_url.clone());
         let client_clone = client.clone();
@@ -417,31 +1166,182 @@ impl OllamaChatApp {
     }
 
     fn open_file_dialog(&mut self) {
-        self.file_loading_promise = Some(Promise::spawn_thread("file_picker", move || {
+        self.file_picker_promise = Some(Promise::spawn_thread("file_picker", move || {
             // Usa il dialog sincrono invece di async
-            if let Some(path) = rfd::FileDialog::new()
-                .add_filter(
-                    "Documenti",
-                    &["pdf", "xlsx", "xls", "ods", "txt", "md", "csv"],
-                )
-                .pick_file()
-            {
-                let filename = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("file")
-                    .to_string();
-
-                match extract_text_from_file(&path) {
-                    Ok(content) => Ok((filename, content)),
-                    Err(e) => Err(e),
-                }
+            rfd::FileDialog::new()
+                .add_filter("Documenti", ATTACHMENT_EXTENSIONS)
+                .pick_files()
+                .unwrap_or_default()
+        }));
+    }
+
+    /// Estrae il testo del file in un thread dedicato, così più file si caricano in parallelo
+    fn start_file_loading(&mut self, path: PathBuf) {
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        let promise = Promise::spawn_thread("file_loading", move || extract_text_from_file(&path));
+        self.file_loading_promises.push((filename, promise));
+    }
+
+    /// Overlay durante il trascinamento di file sulla finestra e allegato al rilascio
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_overlay"),
+            ));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(170));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                "📎 Rilascia per allegare",
+                egui::FontId::proportional(26.0 * self.font_scale),
+                egui::Color32::WHITE,
+            );
+        }
+
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        let mut unsupported = Vec::new();
+        for path in dropped {
+            if is_supported_attachment(&path) {
+                self.start_file_loading(path);
             } else {
-                Err(anyhow::anyhow!("Nessun file selezionato"))
+                unsupported.push(
+                    path.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| path.display().to_string()),
+                );
             }
+        }
+        if !unsupported.is_empty() {
+            self.error_message = Some(format!(
+                "Formato non supportato, file non allegati: {} (usa {})",
+                unsupported.join(", "),
+                ATTACHMENT_EXTENSIONS.join(", ")
+            ));
+        }
+    }
+
+    fn save_tool_result(&mut self, result: ToolResult) {
+        self.tool_save_promise = Some(Promise::spawn_thread("save_tool_result", move || {
+            // I risultati SQL si salvano in CSV, il resto come testo/markdown
+            let dialog = if result.query_result.is_some() {
+                rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name(format!("{}.csv", result.tool_name))
+            } else {
+                rfd::FileDialog::new()
+                    .add_filter("Markdown", &["md"])
+                    .add_filter("Testo", &["txt"])
+                    .set_file_name(format!("{}.md", result.tool_name))
+            };
+
+            // Dialogo annullato: nessun errore da mostrare
+            let Some(path) = dialog.save_file() else {
+                return Ok(None);
+            };
+
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+
+            let content = match (&result.query_result, extension.as_str()) {
+                (Some(query_result), "csv") => query_result_to_csv(query_result),
+                (_, "md") => result.to_markdown(),
+                _ if result.success => result.output.clone(),
+                _ => result.error.clone().unwrap_or_default(),
+            };
+
+            fs::write(&path, content)
+                .with_context(|| format!("Impossibile scrivere {}", path.display()))?;
+            Ok(Some(path))
         }));
     }
 
+    fn show_agent_trace_panel(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(8.0);
+        ui.heading("🧭 Traccia agente");
+
+        let status = if self.tool_execution_promise.is_some() {
+            "⏳ esecuzione tool in corso"
+        } else if self.awaiting_confirmation.is_some() {
+            "⚠️ in attesa di conferma"
+        } else if self.chat_promise.is_some() {
+            "💭 in attesa del modello"
+        } else {
+            "inattivo"
+        };
+        ui.label(
+            egui::RichText::new(format!(
+                "Iterazione {}/{} · {}",
+                self.current_agent_iteration, self.max_agent_iterations, status
+            ))
+            .size(12.0 * self.font_scale)
+            .color(theme::SECONDARY_TEXT),
+        );
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!self.agent_trace.steps.is_empty(), egui::Button::new("🗑 Svuota"))
+                .clicked()
+            {
+                self.agent_trace.clear();
+            }
+        });
+        ui.separator();
+
+        if self.agent_trace.steps.is_empty() {
+            ui.label("Nessun tool eseguito in questa sessione");
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for (index, step) in self.agent_trace.steps.iter().enumerate() {
+                    let outcome = step
+                        .result
+                        .as_ref()
+                        .map(|result| result.summary())
+                        .unwrap_or_else(|| "⏳ in corso".to_string());
+
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{}. {} (iterazione {})",
+                            index + 1,
+                            step.call.tool_name,
+                            step.iteration
+                        ))
+                        .strong(),
+                    );
+                    ui.label(egui::RichText::new(outcome).size(12.0 * self.font_scale));
+
+                    egui::CollapsingHeader::new("Dettagli JSON")
+                        .id_source(("agent_trace_step", index))
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            let json = serde_json::to_string_pretty(step)
+                                .unwrap_or_else(|e| format!("Errore serializzazione: {}", e));
+                            ui.monospace(json);
+                        });
+                    ui.add_space(6.0);
+                }
+            });
+    }
+
     fn process_next_tool_call(&mut self) {
         if let Some(tool_call) = self.pending_tool_calls.first() {
             // Controlla se il tool richiede conferma
@@ -459,6 +1359,8 @@ impl OllamaChatApp {
 
     fn execute_pending_tools(&mut self) {
         let tools_to_execute = std::mem::take(&mut self.pending_tool_calls);
+        self.agent_trace
+            .record_calls(self.current_agent_iteration + 1, &tools_to_execute);
         let mut agent_system = self.agent_system.clone();
 
         self.tool_execution_promise = Some(Promise::spawn_thread("execute_tools", move || {
@@ -473,6 +1375,7 @@ impl OllamaChatApp {
                             output: String::new(),
                             error: Some(e.to_string()),
                             tool_name: tool_call.tool_name.clone(),
+                            query_result: None,
                         });
                     }
                 }
@@ -506,6 +1409,82 @@ impl OllamaChatApp {
         }
     }
 
+    fn new_chat(&mut self) {
+        self.conversation.clear();
+        self.error_message = None;
+        self.system_prompt_added = false;
+        self.current_agent_iteration = 0;
+        self.last_tool_call = None;
+        self.agent_trace.clear();
+        self.agent_system = AgentSystem::new();
+        // Risposte e tool ancora in corso appartengono alla chat precedente:
+        // senza la promise il loro risultato viene ignorato
+        self.chat_promise = None;
+        self.tool_execution_promise = None;
+        self.pending_tool_calls.clear();
+        self.awaiting_confirmation = None;
+    }
+
+    /// Smette di attendere la risposta in corso. La richiesta a Ollama non si può
+    /// interrompere: quando arriva viene semplicemente ignorata
+    fn cancel_generation(&mut self) {
+        if self.chat_promise.take().is_some() {
+            self.conversation.push(Message {
+                role: "system".to_string(),
+                content: "⏹️ Generazione annullata".to_string(),
+                hidden: false,
+                timestamp: Some(get_timestamp()),
+                tool_result: None,
+            });
+        }
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.state != AppState::Chat {
+            return;
+        }
+
+        // Su molte tastiere il + si ottiene con Shift: vale anche Ctrl+=
+        let zoom_in_equals =
+            egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Equals);
+        if ctx.input_mut(|i| i.consume_shortcut(&zoom_in_equals)) {
+            self.change_font_scale(1.0);
+        }
+
+        for (shortcut, action, _) in SHORTCUTS {
+            if !ctx.input_mut(|i| i.consume_shortcut(shortcut)) {
+                continue;
+            }
+            match action {
+                ShortcutAction::NewChat => self.new_chat(),
+                ShortcutAction::SearchConversation => {
+                    self.conversation_search.get_or_insert_with(String::new);
+                    self.focus_search = true;
+                }
+                ShortcutAction::FocusInput => self.focus_input = true,
+                // Esc chiude prima ciò che è aperto, poi annulla la generazione
+                ShortcutAction::Cancel => {
+                    if self.show_shortcuts {
+                        self.show_shortcuts = false;
+                    } else if self.show_settings {
+                        self.show_settings = false;
+                    } else if self.awaiting_confirmation.is_some() {
+                        self.cancel_dangerous_tool();
+                    } else if self.conversation_search.is_some() {
+                        self.conversation_search = None;
+                        self.focus_input = true;
+                    } else {
+                        self.cancel_generation();
+                    }
+                }
+                ShortcutAction::ShowHelp => self.show_shortcuts = !self.show_shortcuts,
+                ShortcutAction::ZoomIn => self.change_font_scale(1.0),
+                ShortcutAction::ZoomOut => self.change_font_scale(-1.0),
+                ShortcutAction::ZoomReset => self.font_scale = 1.0,
+            }
+        }
+    }
+
     fn cancel_dangerous_tool(&mut self) {
         self.awaiting_confirmation = None;
         self.conversation.push(Message {
@@ -513,6 +1492,7 @@ impl OllamaChatApp {
             content: "❌ Operazione annullata dall'utente".to_string(),
             hidden: false,
             timestamp: Some(get_timestamp()),
+            tool_result: None,
         });
     }
 
@@ -589,23 +1569,30 @@ impl OllamaChatApp {
         if self.input_text.trim().is_empty() && self.attached_files.is_empty() {
             return;
         }
+        // Aspetta che tutti i file selezionati siano allegati
+        if !self.file_loading_promises.is_empty() {
+            return;
+        }
 
         // Resetta il contatore di iterazioni per nuova richiesta utente
         self.current_agent_iteration = 0;
+        self.last_tool_call = None;
 
         // Aggiungi istruzioni di formattazione solo alla prima interazione
         if !self.system_prompt_added && self.conversation.is_empty() {
             // Usa un approccio user/assistant per garantire che il modello capisca
-            let mut instruction_content = "IMPORTANTE: Per questa conversazione, quando devi mostrare formule matematiche NON usare LaTeX (no \\frac, \\sqrt, \\( \\), ecc). Usa SOLO:
+            let mut instruction_content = "IMPORTANTE: Per questa conversazione, quando devi mostrare formule matematiche puoi usare LaTeX, l'app lo converte in simboli Unicode:
 
-• Caratteri Unicode: √ ² ³ ∫ ∑ π ∞ ≤ ≥ ≠ ± × ÷
-• Notazione testuale: sqrt(), ^2, ^3, /
+• Formule nel testo tra $ ... $ oppure \\( ... \\)
+• Formule su riga separata tra $$ ... $$ oppure \\[ ... \\]
+• Comandi supportati: \\frac, \\sqrt, apici e pedici (^, _), lettere greche, \\sum, \\int, \\leq, \\geq, \\neq, \\pm, \\times, \\to, \\infty, \\text
+• Evita ambienti complessi (matrici, \\begin{align}): scrivili come più formule separate
 • Esempi:
-  - x = (-b ± √(b² - 4ac)) / (2a)
-  - a² + b² = c²
-  - lim(x→∞) f(x)
+  - $x = \\frac{-b \\pm \\sqrt{b^2 - 4ac}}{2a}$
+  - $a^2 + b^2 = c^2$
+  - $\\lim_{x \\to \\infty} f(x)$
 
-Conferma che userai solo Unicode e notazione testuale, MAI LaTeX.".to_string();
+Conferma che userai questi delimitatori per le formule.".to_string();
 
             // Se la modalità agente è abilitata, aggiungi descrizione tools e linee guida
             if self.agent_mode_enabled {
@@ -638,21 +1625,14 @@ Conferma che userai solo Unicode e notazione testuale, MAI LaTeX.".to_string();
             }
 
             let instruction = Message {
-                role: "user".to_string(),
+                role: "system".to_string(),
                 content: instruction_content,
                 hidden: true,    // Non mostrare nella UI
                 timestamp: None, // Messaggi di sistema senza timestamp
-            };
-
-            let confirmation = Message {
-                role: "assistant".to_string(),
-                content: "Perfetto! Userò solo caratteri Unicode (√, ², ³, π, ±, ecc.) e notazione testuale chiara (sqrt, ^2, /) per le formule matematiche. Non userò LaTeX. Sono pronto ad aiutarti!".to_string(),
-                hidden: true,  // Non mostrare nella UI
-                timestamp: None,  // Messaggi di sistema senza timestamp
+                tool_result: None,
             };
 
             self.conversation.push(instruction);
-            self.conversation.push(confirmation);
             self.system_prompt_added = true;
         }
 
@@ -687,6 +1667,7 @@ Conferma che userai solo Unicode e notazione testuale, MAI LaTeX.".to_string();
             content: display_content,
             hidden: false,
             timestamp: Some(get_timestamp()),
+            tool_result: None,
         };
         self.conversation.push(user_message_display);
 
@@ -715,24 +1696,33 @@ Conferma che userai solo Unicode e notazione testuale, MAI LaTeX.".to_string();
 
 impl eframe::App for OllamaChatApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Usa il tema di sistema (chiaro/scuro)
-        let is_dark = ctx.style().visuals.dark_mode;
+        // Tema scelto nelle impostazioni (sistema, chiaro o scuro) e colore d'accento
+        let is_dark = self.theme_is_dark(ctx);
+        let palette = Palette::new(is_dark, self.accent_color);
 
         let mut style = (*ctx.style()).clone();
+        if style.visuals.dark_mode != is_dark {
+            style.visuals = if is_dark {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            };
+        }
 
-        // Font più grandi e leggibili
-        style.text_styles.insert(
-            egui::TextStyle::Body,
-            egui::FontId::new(15.0, egui::FontFamily::Proportional),
-        );
-        style.text_styles.insert(
-            egui::TextStyle::Button,
-            egui::FontId::new(14.0, egui::FontFamily::Proportional),
-        );
-        style.text_styles.insert(
-            egui::TextStyle::Heading,
-            egui::FontId::new(22.0, egui::FontFamily::Proportional),
-        );
+        // Font più grandi e leggibili, moltiplicati per la scala del testo. Si parte
+        // sempre dai default: lo stile del frame precedente è già scalato
+        let font_scale = self.font_scale;
+        let mut text_styles = egui::Style::default().text_styles;
+        for (text_style, size) in BASE_TEXT_SIZES {
+            text_styles.insert(
+                text_style.clone(),
+                egui::FontId::new(*size, egui::FontFamily::Proportional),
+            );
+        }
+        for font_id in text_styles.values_mut() {
+            font_id.size *= font_scale;
+        }
+        style.text_styles = text_styles;
 
         // Spaziatura generosa
         style.spacing.item_spacing = egui::vec2(10.0, 10.0);
@@ -747,21 +1737,7 @@ impl eframe::App for OllamaChatApp {
         style.visuals.widgets.active.rounding = egui::Rounding::same(8.0);
 
         // Colori adattivi al tema
-        if is_dark {
-            // Tema scuro
-            style.visuals.widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(44, 44, 46);
-            style.visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(44, 44, 46);
-            style.visuals.widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(58, 58, 60);
-            style.visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(58, 58, 60);
-        } else {
-            // Tema chiaro
-            style.visuals.widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(242, 242, 247);
-            style.visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(242, 242, 247);
-            style.visuals.widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(229, 229, 234);
-            style.visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(229, 229, 234);
-        }
-
-        style.visuals.widgets.active.bg_fill = egui::Color32::from_rgb(0, 122, 255);
+        palette.apply(&mut style.visuals);
 
         // Ombre sottili
         style.visuals.window_shadow = egui::Shadow {
@@ -779,6 +1755,9 @@ impl eframe::App for OllamaChatApp {
 
         ctx.set_style(style);
 
+        // Prima dei widget, così il campo di testo non riceve i tasti delle scorciatoie
+        self.handle_shortcuts(ctx);
+
         // Controlla promise per il caricamento dei modelli
         if let Some(promise) = &self.loading_models_promise {
             if let Some(result) = promise.ready() {
@@ -791,7 +1770,14 @@ impl eframe::App for OllamaChatApp {
                             self.state = AppState::Setup;
                         } else {
                             self.available_models = models.clone();
-                            self.selected_model = Some(models[0].name.clone());
+                            // Il modello dell'ultima sessione, se il server lo ha ancora
+                            let last_model = self
+                                .saved_settings
+                                .last_model
+                                .clone()
+                                .filter(|name| models.iter().any(|m| &m.name == name));
+                            self.selected_model =
+                                Some(last_model.unwrap_or_else(|| models[0].name.clone()));
                             self.state = AppState::Chat;
                         }
                     }
@@ -807,21 +1793,46 @@ impl eframe::App for OllamaChatApp {
             }
         }
 
-        // Controlla promise per il caricamento file
-        if let Some(promise) = &self.file_loading_promise {
+        // Controlla promise per la selezione file: avvia l'estrazione di ciascuno
+        if let Some(promise) = &self.file_picker_promise {
+            if let Some(paths) = promise.ready() {
+                let paths = paths.clone();
+                self.file_picker_promise = None;
+                for path in paths {
+                    self.start_file_loading(path);
+                }
+            }
+        }
+
+        // Controlla promise per il caricamento file, allegando ciascuno appena pronto
+        let mut load_errors = Vec::new();
+        let attached_files = &mut self.attached_files;
+        self.file_loading_promises
+            .retain(|(filename, promise)| match promise.ready() {
+                Some(Ok(content)) => {
+                    attached_files.push((filename.clone(), content.clone()));
+                    false
+                }
+                Some(Err(e)) => {
+                    load_errors.push(format!("{}: {}", filename, e));
+                    false
+                }
+                None => true,
+            });
+        if !load_errors.is_empty() {
+            self.error_message = Some(format!(
+                "Errore caricamento file:\n{}",
+                load_errors.join("\n")
+            ));
+        }
+
+        // Controlla promise per il salvataggio dei risultati dei tool
+        if let Some(promise) = &self.tool_save_promise {
             if let Some(result) = promise.ready() {
-                match result {
-                    Ok((filename, content)) => {
-                        self.attached_files
-                            .push((filename.clone(), content.clone()));
-                    }
-                    Err(e) => {
-                        if e.to_string() != "Nessun file selezionato" {
-                            self.error_message = Some(format!("Errore caricamento file: {}", e));
-                        }
-                    }
+                if let Err(e) = result {
+                    self.error_message = Some(format!("Errore salvataggio risultato: {}", e));
                 }
-                self.file_loading_promise = None;
+                self.tool_save_promise = None;
             }
         }
 
@@ -835,6 +1846,7 @@ impl eframe::App for OllamaChatApp {
                             content: response.clone(),
                             hidden: false,
                             timestamp: Some(get_timestamp()),
+                            tool_result: None,
                         });
                         self.scroll_to_bottom = true;
                         self.attached_files.clear(); // Pulisci file dopo invio
@@ -842,7 +1854,23 @@ impl eframe::App for OllamaChatApp {
                         // Se modalità agente abilitata, cerca tool calls nella risposta
                         if self.agent_mode_enabled {
                             let tool_calls = self.agent_system.parse_tool_calls(response);
-                            if !tool_calls.is_empty() {
+                            if let Some(repeated) =
+                                find_repeated_tool_call(self.last_tool_call.as_ref(), &tool_calls)
+                            {
+                                // Stessa chiamata due volte di fila: l'agente è in loop
+                                self.conversation.push(Message {
+                                    role: "system".to_string(),
+                                    content: format!(
+                                        "⏹️ Agente fermato: ha richiesto di nuovo `{}` con gli stessi parametri. \
+                                         Il risultato sarebbe identico al precedente, prova a riformulare la richiesta.",
+                                        repeated.tool_name
+                                    ),
+                                    hidden: false,
+                                    timestamp: Some(get_timestamp()),
+                                    tool_result: None,
+                                });
+                            } else if !tool_calls.is_empty() {
+                                self.last_tool_call = tool_calls.last().cloned();
                                 self.pending_tool_calls = tool_calls;
                                 self.process_next_tool_call();
                             }
@@ -862,6 +1890,8 @@ impl eframe::App for OllamaChatApp {
             if let Some(result) = promise.ready() {
                 match result {
                     Ok(results) => {
+                        self.agent_trace.record_results(results);
+
                         // Aggiungi i risultati alla conversazione come messaggio nascosto per il context
                         let mut tool_results_text = String::from("**Risultati Tool:**\n\n");
                         for result in results {
@@ -874,6 +1904,7 @@ impl eframe::App for OllamaChatApp {
                                 content: format!("🔧 {}", result.to_markdown()),
                                 hidden: false,
                                 timestamp: Some(get_timestamp()),
+                                tool_result: Some(result.clone()),
                             });
                         }
 
@@ -883,6 +1914,7 @@ impl eframe::App for OllamaChatApp {
                             content: tool_results_text,
                             hidden: true,
                             timestamp: None,
+                            tool_result: None,
                         });
 
                         self.scroll_to_bottom = true;
@@ -892,9 +1924,17 @@ impl eframe::App for OllamaChatApp {
                         if self.current_agent_iteration < self.max_agent_iterations {
                             self.continue_agent_loop();
                         } else {
-                            self.error_message = Some(
-                                "Raggiunto limite massimo di iterazioni agentiche".to_string(),
-                            );
+                            self.conversation.push(Message {
+                                role: "system".to_string(),
+                                content: format!(
+                                    "⏹️ Raggiunto il limite di {} iterazioni agentiche. \
+                                     Puoi aumentarlo dalla barra in alto o inviare un nuovo messaggio per continuare.",
+                                    self.max_agent_iterations
+                                ),
+                                hidden: false,
+                                timestamp: Some(get_timestamp()),
+                                tool_result: None,
+                            });
                         }
                     }
                     Err(e) => {
@@ -912,15 +1952,32 @@ impl eframe::App for OllamaChatApp {
                 self.discovered_servers = servers.clone();
                 self.state = AppState::Setup;
 
-                // Se c'è almeno un server, usa il primo come default
-                if !servers.is_empty() {
+                // Se c'è almeno un server, usa il primo come default (tranne quando
+                // quello salvato nelle impostazioni è stato ritrovato)
+                if !servers.is_empty() && !servers.contains(&self.ollama_url) {
                     self.ollama_url = servers[0].clone();
                 }
 
                 self.scanning_promise = None;
+                self.scan_cancel = None;
             }
         }
 
+        // File trascinati nella finestra della chat
+        if self.state == AppState::Chat {
+            self.handle_dropped_files(ctx);
+        }
+
+        // Pannello laterale con la traccia dei passi dell'agente
+        if self.state == AppState::Chat && self.agent_mode_enabled && self.show_agent_trace {
+            egui::SidePanel::right("agent_trace_panel")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    self.show_agent_trace_panel(ui);
+                });
+        }
+
         egui::CentralPanel::default()
             .frame(egui::Frame::none().inner_margin(egui::Margin::symmetric(16.0, 8.0)))
             .show(ctx, |ui| {
@@ -930,13 +1987,17 @@ impl eframe::App for OllamaChatApp {
                         ui.add_space(150.0);
                         ui.spinner();
                         ui.add_space(16.0);
-                        ui.label(egui::RichText::new("🔍 Ricerca server Ollama in corso...").size(18.0));
+                        ui.label(egui::RichText::new("🔍 Ricerca server Ollama in corso...").size(18.0 * font_scale));
                         ui.add_space(8.0);
                         ui.label(
                             egui::RichText::new("Scansione della rete locale")
-                                .size(14.0)
-                                .color(egui::Color32::from_rgb(142, 142, 147))
+                                .size(14.0 * font_scale)
+                                .color(palette.secondary_text)
                         );
+                        ui.add_space(24.0);
+                        if ui.button("✕ Annulla scansione").clicked() {
+                            self.cancel_network_scan();
+                        }
                     });
                 }
                 AppState::Setup => {
@@ -945,8 +2006,8 @@ impl eframe::App for OllamaChatApp {
                         ui.heading("🤖 MatePro");
                         ui.add_space(10.0);
                         ui.label(egui::RichText::new("Connettiti a un'istanza Ollama per iniziare")
-                            .size(14.0)
-                            .color(egui::Color32::from_rgb(142, 142, 147)));
+                            .size(14.0 * font_scale)
+                            .color(palette.secondary_text));
                         ui.add_space(40.0);
 
                         ui.horizontal(|ui| {
@@ -968,8 +2029,8 @@ impl eframe::App for OllamaChatApp {
                                         };
 
                                         let button = if is_selected {
-                                            egui::Button::new(egui::RichText::new(&button_text).color(egui::Color32::WHITE))
-                                                .fill(egui::Color32::from_rgb(0, 122, 255))
+                                            egui::Button::new(egui::RichText::new(&button_text).color(palette.on_accent))
+                                                .fill(palette.accent)
                                                 .min_size(egui::vec2(400.0, 36.0))
                                         } else {
                                             egui::Button::new(&button_text)
@@ -1000,9 +2061,9 @@ impl eframe::App for OllamaChatApp {
 
                                 ui.horizontal(|ui| {
                                     let connect_button = egui::Button::new(
-                                        egui::RichText::new("Connetti").size(16.0).color(egui::Color32::WHITE)
+                                        egui::RichText::new("Connetti").size(16.0 * font_scale).color(palette.on_accent)
                                     )
-                                    .fill(egui::Color32::from_rgb(0, 122, 255))
+                                    .fill(palette.accent)
                                     .min_size(egui::vec2(280.0, 44.0));
 
                                     if ui.add(connect_button).clicked() {
@@ -1012,7 +2073,7 @@ impl eframe::App for OllamaChatApp {
                                     ui.add_space(8.0);
 
                                     let rescan_button = egui::Button::new(
-                                        egui::RichText::new("🔄 Ricarica").size(16.0)
+                                        egui::RichText::new("🔄 Ricarica").size(16.0 * font_scale)
                                     )
                                     .min_size(egui::vec2(110.0, 44.0));
 
@@ -1023,7 +2084,7 @@ impl eframe::App for OllamaChatApp {
 
                                 if let Some(error) = &self.error_message {
                                     ui.add_space(16.0);
-                                    ui.colored_label(egui::Color32::from_rgb(255, 59, 48), error);
+                                    ui.colored_label(palette.danger, error);
                                 }
                             });
                         });
@@ -1034,17 +2095,12 @@ impl eframe::App for OllamaChatApp {
                         ui.add_space(150.0);
                         ui.spinner();
                         ui.add_space(16.0);
-                        ui.label(egui::RichText::new("Caricamento modelli...").size(18.0));
+                        ui.label(egui::RichText::new("Caricamento modelli...").size(18.0 * font_scale));
                     });
                 }
                 AppState::Chat => {
                     // Header elegante con selezione modello
-                    let is_dark = ui.style().visuals.dark_mode;
-                    let header_bg = if is_dark {
-                        egui::Color32::from_rgb(28, 28, 30)
-                    } else {
-                        egui::Color32::from_rgb(248, 248, 248)
-                    };
+                    let header_bg = palette.panel_bg;
 
                     egui::Frame::none()
                         .fill(header_bg)
@@ -1055,7 +2111,7 @@ impl eframe::App for OllamaChatApp {
                                 ui.add_space(8.0);
 
                                 egui::ComboBox::new("model_selector", "")
-                                    .selected_text(egui::RichText::new(self.selected_model.as_ref().unwrap()).size(16.0))
+                                    .selected_text(egui::RichText::new(self.selected_model.as_ref().unwrap()).size(16.0 * font_scale))
                                     .width(280.0)
                                     .show_ui(ui, |ui| {
                                         for model in &self.available_models {
@@ -1070,7 +2126,7 @@ impl eframe::App for OllamaChatApp {
                                                 );
 
                                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                                    ui.label(egui::RichText::new(size_text).size(11.0).color(egui::Color32::GRAY));
+                                                    ui.label(egui::RichText::new(size_text).size(11.0 * font_scale).color(egui::Color32::GRAY));
                                                     ui.label(egui::RichText::new(indicator).color(color));
                                                 });
 
@@ -1083,22 +2139,29 @@ impl eframe::App for OllamaChatApp {
 
                                 // Toggle per modalità agente
                                 let agent_color = if self.agent_mode_enabled {
-                                    egui::Color32::from_rgb(52, 199, 89)
+                                    palette.success
                                 } else {
-                                    egui::Color32::from_rgb(142, 142, 147)
+                                    palette.secondary_text
                                 };
 
                                 ui.toggle_value(&mut self.agent_mode_enabled,
                                     egui::RichText::new("🤖 Modalità Agente")
                                         .color(agent_color)
-                                        .size(14.0));
+                                        .size(14.0 * font_scale));
 
                                 if self.agent_mode_enabled {
                                     ui.label(
                                         egui::RichText::new(format!("({}/{})", self.current_agent_iteration, self.max_agent_iterations))
-                                            .size(11.0)
+                                            .size(11.0 * font_scale)
                                             .color(egui::Color32::GRAY)
                                     );
+                                    ui.add(
+                                        egui::Slider::new(&mut self.max_agent_iterations, 1..=20)
+                                            .text("iterazioni max"),
+                                    )
+                                    .on_hover_text("Numero massimo di cicli tool → risposta per ogni messaggio");
+                                    ui.toggle_value(&mut self.show_agent_trace, "🧭 Traccia")
+                                        .on_hover_text("Mostra i passi eseguiti dall'agente");
                                 }
 
                                 ui.add_space(12.0);
@@ -1111,12 +2174,12 @@ impl eframe::App for OllamaChatApp {
                                     .unwrap_or(false);
                                 let sql_btn_text = if sql_connected {
                                     egui::RichText::new("🗄️ SQL (✓)")
-                                        .color(egui::Color32::from_rgb(52, 199, 89))
-                                        .size(14.0)
+                                        .color(palette.success)
+                                        .size(14.0 * font_scale)
                                 } else {
                                     egui::RichText::new("🗄️ SQL")
-                                        .color(egui::Color32::from_rgb(142, 142, 147))
-                                        .size(14.0)
+                                        .color(palette.secondary_text)
+                                        .size(14.0 * font_scale)
                                 };
 
                                 if ui.button(sql_btn_text).on_hover_text("Configura database SQL Server").clicked() {
@@ -1125,27 +2188,45 @@ impl eframe::App for OllamaChatApp {
 
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                     let disconnect_btn = egui::Button::new(
-                                        egui::RichText::new("✕").size(20.0).strong()
+                                        egui::RichText::new("✕").size(20.0 * font_scale).strong()
                                     )
                                     .frame(false);
 
                                     if ui.add(disconnect_btn).on_hover_text("Disconnetti").clicked() {
-                                        *self = Self::default();
+                                        self.disconnect();
                                     }
 
                                     ui.add_space(8.0);
 
                                     let new_chat_btn = egui::Button::new(
-                                        egui::RichText::new("⟲").size(20.0).strong()
+                                        egui::RichText::new("⟲").size(20.0 * font_scale).strong()
                                     )
                                     .frame(false);
 
-                                    if ui.add(new_chat_btn).on_hover_text("Nuova chat").clicked() {
-                                        self.conversation.clear();
-                                        self.error_message = None;
-                                        self.system_prompt_added = false;
-                                        self.current_agent_iteration = 0;
-                                        self.agent_system = AgentSystem::new();
+                                    if ui.add(new_chat_btn).on_hover_text("Nuova chat (Ctrl+N)").clicked() {
+                                        self.new_chat();
+                                    }
+
+                                    ui.add_space(8.0);
+
+                                    let shortcuts_btn = egui::Button::new(
+                                        egui::RichText::new("⌨").size(18.0 * font_scale)
+                                    )
+                                    .frame(false);
+
+                                    if ui.add(shortcuts_btn).on_hover_text("Scorciatoie da tastiera (F1)").clicked() {
+                                        self.show_shortcuts = !self.show_shortcuts;
+                                    }
+
+                                    ui.add_space(8.0);
+
+                                    let settings_btn = egui::Button::new(
+                                        egui::RichText::new("⚙").size(18.0 * font_scale)
+                                    )
+                                    .frame(false);
+
+                                    if ui.add(settings_btn).on_hover_text("Impostazioni").clicked() {
+                                        self.show_settings = !self.show_settings;
                                     }
                                 });
                             });
@@ -1153,6 +2234,49 @@ impl eframe::App for OllamaChatApp {
 
                     ui.add_space(4.0);
 
+                    // Barra di ricerca nella conversazione (Ctrl+K)
+                    let mut close_search = false;
+                    if let Some(query) = self.conversation_search.as_mut() {
+                        ui.horizontal(|ui| {
+                            ui.label("🔍");
+                            let response = ui.add(
+                                egui::TextEdit::singleline(query)
+                                    .hint_text("Cerca nella conversazione...")
+                                    .desired_width(280.0),
+                            );
+                            if std::mem::take(&mut self.focus_search) {
+                                response.request_focus();
+                            }
+
+                            let needle = query.trim().to_lowercase();
+                            if !needle.is_empty() {
+                                let found = self
+                                    .conversation
+                                    .iter()
+                                    .filter(|m| !m.hidden && m.content.to_lowercase().contains(&needle))
+                                    .count();
+                                ui.label(
+                                    egui::RichText::new(format!("{} messaggi trovati", found))
+                                        .size(12.0 * font_scale)
+                                        .color(palette.secondary_text),
+                                );
+                            }
+
+                            if ui.small_button("✕").on_hover_text("Chiudi la ricerca (Esc)").clicked() {
+                                close_search = true;
+                            }
+                        });
+                        ui.add_space(4.0);
+                    }
+                    if close_search {
+                        self.conversation_search = None;
+                    }
+                    let search = self
+                        .conversation_search
+                        .as_deref()
+                        .map(|query| query.trim().to_lowercase())
+                        .filter(|query| !query.is_empty());
+
                     // Area messaggi con più spazio
                     let available_height = ui.available_height() - 150.0;
 
@@ -1168,26 +2292,97 @@ impl eframe::App for OllamaChatApp {
                                     ui.add_space(60.0);
                                     ui.label(
                                         egui::RichText::new("Inizia una conversazione")
-                                            .size(20.0)
-                                            .color(egui::Color32::from_rgb(142, 142, 147))
+                                            .size(20.0 * font_scale)
+                                            .color(palette.secondary_text)
                                     );
                                     ui.add_space(8.0);
                                     ui.label(
                                         egui::RichText::new("Scrivi un messaggio per iniziare")
-                                            .size(14.0)
-                                            .color(egui::Color32::from_rgb(174, 174, 178))
+                                            .size(14.0 * font_scale)
+                                            .color(palette.tertiary_text)
                                     );
                                 });
                             }
 
-                            for message in &self.conversation {
+                            let mut save_request: Option<ToolResult> = None;
+
+                            for (index, message) in self.conversation.iter().enumerate() {
                                 // Salta i messaggi nascosti (istruzioni di sistema)
                                 if message.hidden {
                                     continue;
                                 }
+                                // Durante la ricerca restano solo i messaggi che contengono il testo
+                                if let Some(query) = &search {
+                                    if !message.content.to_lowercase().contains(query.as_str()) {
+                                        continue;
+                                    }
+                                }
+
+                                // Output dei tool in un riquadro collassabile, chiuso di default
+                                if let Some(tool_result) = &message.tool_result {
+                                    egui::CollapsingHeader::new(format!(
+                                        "🔧 {} · {}",
+                                        tool_result.tool_name,
+                                        tool_result.summary()
+                                    ))
+                                    .id_source(("tool_result", index))
+                                    .default_open(false)
+                                    .show(ui, |ui| {
+                                        if let Some(query_result) = &tool_result.query_result {
+                                            let mut sort = self.query_table_sort.get(&index).copied();
+                                            show_query_result_table(ui, index, query_result, &mut sort, font_scale);
+                                            if let Some(sort) = sort {
+                                                self.query_table_sort.insert(index, sort);
+                                            }
+                                            egui::CollapsingHeader::new("Dettagli testuali")
+                                                .id_source(("tool_result_text", index))
+                                                .show(ui, |ui| {
+                                                    show_markdown(
+                                                        ui,
+                                                        &mut self.markdown_cache,
+                                                        ("tool_result_text", index),
+                                                        &message.content,
+                                                    );
+                                                });
+                                        } else {
+                                            show_markdown(
+                                                ui,
+                                                &mut self.markdown_cache,
+                                                ("tool_result", index),
+                                                &message.content,
+                                            );
+                                        }
+                                        ui.horizontal(|ui| {
+                                            if let Some(timestamp) = &message.timestamp {
+                                                ui.label(
+                                                    egui::RichText::new(timestamp)
+                                                        .color(palette.secondary_text)
+                                                        .size(10.0 * font_scale)
+                                                );
+                                            }
+
+                                            let hint = if tool_result.query_result.is_some() {
+                                                "Salva il risultato in CSV"
+                                            } else {
+                                                "Salva il risultato in un file di testo"
+                                            };
+                                            if ui
+                                                .add_enabled(
+                                                    self.tool_save_promise.is_none(),
+                                                    egui::Button::new("💾 Salva").small(),
+                                                )
+                                                .on_hover_text(hint)
+                                                .clicked()
+                                            {
+                                                save_request = Some(tool_result.clone());
+                                            }
+                                        });
+                                    });
+                                    ui.add_space(10.0);
+                                    continue;
+                                }
 
                                 let is_user = message.role == "user";
-                                let is_dark = ui.style().visuals.dark_mode;
 
                                 ui.horizontal_top(|ui| {
                                     let max_bubble_width = ui.available_width() * 0.7;
@@ -1198,19 +2393,15 @@ impl eframe::App for OllamaChatApp {
                                     }
 
                                     let frame_color = if is_user {
-                                        egui::Color32::from_rgb(0, 122, 255)
-                                    } else if is_dark {
-                                        egui::Color32::from_rgb(58, 58, 60)
+                                        palette.accent
                                     } else {
-                                        egui::Color32::from_rgb(229, 229, 234)
+                                        palette.assistant_bubble
                                     };
 
                                     let text_color = if is_user {
-                                        egui::Color32::WHITE
-                                    } else if is_dark {
-                                        egui::Color32::WHITE
+                                        palette.on_accent
                                     } else {
-                                        egui::Color32::BLACK
+                                        palette.text
                                     };
 
                                     egui::Frame::none()
@@ -1226,7 +2417,7 @@ impl eframe::App for OllamaChatApp {
                                                     ui.label(
                                                         egui::RichText::new(&message.content)
                                                             .color(text_color)
-                                                            .size(14.5)
+                                                            .size(14.5 * font_scale)
                                                     );
 
                                                     // Timestamp in basso a destra
@@ -1234,8 +2425,8 @@ impl eframe::App for OllamaChatApp {
                                                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
                                                             ui.label(
                                                                 egui::RichText::new(timestamp)
-                                                                    .color(egui::Color32::from_rgba_premultiplied(255, 255, 255, 180))
-                                                                    .size(10.0)
+                                                                    .color(text_color.gamma_multiply(0.7))
+                                                                    .size(10.0 * font_scale)
                                                             );
                                                         });
                                                     }
@@ -1244,20 +2435,20 @@ impl eframe::App for OllamaChatApp {
                                                 // Messaggi assistente con rendering Markdown migliorato
                                                 {
                                                     let style = ui.style_mut();
-                                                    style.visuals.hyperlink_color = egui::Color32::from_rgb(0, 122, 255);
+                                                    style.visuals.hyperlink_color = palette.accent;
 
                                                     // Aumenta la dimensione del font per migliore leggibilità
                                                     style.text_styles.insert(
                                                         egui::TextStyle::Body,
-                                                        egui::FontId::new(15.0, egui::FontFamily::Proportional),
+                                                        egui::FontId::new(15.0 * font_scale, egui::FontFamily::Proportional),
                                                     );
                                                     style.text_styles.insert(
                                                         egui::TextStyle::Monospace,
-                                                        egui::FontId::new(14.0, egui::FontFamily::Monospace),
+                                                        egui::FontId::new(14.0 * font_scale, egui::FontFamily::Monospace),
                                                     );
                                                     style.text_styles.insert(
                                                         egui::TextStyle::Heading,
-                                                        egui::FontId::new(18.0, egui::FontFamily::Proportional),
+                                                        egui::FontId::new(18.0 * font_scale, egui::FontFamily::Proportional),
                                                     );
 
                                                     // Aumenta la spaziatura tra elementi
@@ -1266,20 +2457,75 @@ impl eframe::App for OllamaChatApp {
 
                                                 // Rendering markdown con sintassi codice e formule (Unicode)
                                                 ui.vertical(|ui| {
-                                                    CommonMarkViewer::new().show(
+                                                    // Il ragionamento dei modelli "thinking" va in una sezione separata
+                                                    let (thinking, answer) = split_thinking(&message.content);
+                                                    if let Some(thinking) = &thinking {
+                                                        egui::CollapsingHeader::new(
+                                                            egui::RichText::new("💭 Ragionamento")
+                                                                .color(palette.secondary_text)
+                                                                .size(12.0 * font_scale),
+                                                        )
+                                                        .id_source(("thinking", index))
+                                                        .default_open(false)
+                                                        .show(ui, |ui| {
+                                                            show_markdown(
+                                                                ui,
+                                                                &mut self.markdown_cache,
+                                                                ("thinking", index),
+                                                                &latex::render_math(thinking),
+                                                            );
+                                                        });
+                                                    }
+
+                                                    // Le formule LaTeX diventano testo Unicode, egui non sa disegnarle
+                                                    show_markdown(
                                                         ui,
                                                         &mut self.markdown_cache,
-                                                        &message.content,
+                                                        ("answer", index),
+                                                        &latex::render_math(&answer),
                                                     );
 
-                                                    // Timestamp in basso a sinistra per l'assistente
-                                                    if let Some(timestamp) = &message.timestamp {
-                                                        ui.label(
-                                                            egui::RichText::new(timestamp)
-                                                                .color(egui::Color32::from_rgb(142, 142, 147))
-                                                                .size(10.0)
-                                                        );
-                                                    }
+                                                    // Pulsanti "copia" visibili solo al passaggio del mouse sulla bolla
+                                                    let bubble_hovered = ui.rect_contains_pointer(ui.min_rect());
+
+                                                    ui.horizontal_wrapped(|ui| {
+                                                        // Timestamp in basso a sinistra per l'assistente
+                                                        if let Some(timestamp) = &message.timestamp {
+                                                            ui.label(
+                                                                egui::RichText::new(timestamp)
+                                                                    .color(palette.secondary_text)
+                                                                    .size(10.0 * font_scale)
+                                                            );
+                                                        }
+
+                                                        if bubble_hovered {
+                                                            if ui
+                                                                .small_button("📋 Copia")
+                                                                .on_hover_text("Copia l'intera risposta")
+                                                                .clicked()
+                                                            {
+                                                                ui.ctx().copy_text(answer.clone());
+                                                            }
+
+                                                            for (index, (language, code)) in
+                                                                extract_code_blocks(&answer).into_iter().enumerate()
+                                                            {
+                                                                let label = if language.is_empty() {
+                                                                    format!("📋 Codice {}", index + 1)
+                                                                } else {
+                                                                    format!("📋 {} #{}", language, index + 1)
+                                                                };
+
+                                                                if ui
+                                                                    .small_button(label)
+                                                                    .on_hover_text("Copia il blocco di codice")
+                                                                    .clicked()
+                                                                {
+                                                                    ui.ctx().copy_text(code);
+                                                                }
+                                                            }
+                                                        }
+                                                    });
                                                 });
                                             }
                                         });
@@ -1288,14 +2534,13 @@ impl eframe::App for OllamaChatApp {
                                 ui.add_space(10.0);
                             }
 
+                            if let Some(result) = save_request {
+                                self.save_tool_result(result);
+                            }
+
                             // Indicatore di caricamento elegante
                             if self.chat_promise.is_some() {
-                                let is_dark = ui.style().visuals.dark_mode;
-                                let loading_bg = if is_dark {
-                                    egui::Color32::from_rgb(58, 58, 60)
-                                } else {
-                                    egui::Color32::from_rgb(229, 229, 234)
-                                };
+                                let loading_bg = palette.assistant_bubble;
 
                                 ui.horizontal(|ui| {
                                     ui.add_space(0.0);
@@ -1306,7 +2551,7 @@ impl eframe::App for OllamaChatApp {
                                         .show(ui, |ui| {
                                             ui.horizontal(|ui| {
                                                 ui.spinner();
-                                                ui.label(egui::RichText::new("Sto pensando...").size(14.5));
+                                                ui.label(egui::RichText::new("Sto pensando...").size(14.5 * font_scale));
                                             });
                                         });
                                 });
@@ -1323,23 +2568,18 @@ impl eframe::App for OllamaChatApp {
                     if let Some(error) = &self.error_message {
                         ui.add_space(8.0);
                         egui::Frame::none()
-                            .fill(egui::Color32::from_rgb(255, 239, 239))
+                            .fill(palette.error_bg)
                             .rounding(egui::Rounding::same(8.0))
                             .inner_margin(egui::Margin::symmetric(12.0, 8.0))
                             .show(ui, |ui| {
-                                ui.colored_label(egui::Color32::from_rgb(255, 59, 48), format!("⚠️ {}", error));
+                                ui.colored_label(palette.danger, format!("⚠️ {}", error));
                             });
                     }
 
                     ui.add_space(12.0);
 
                     // Input area spaziosa e moderna - stile Apple
-                    let is_dark = ui.style().visuals.dark_mode;
-                    let input_bg = if is_dark {
-                        egui::Color32::from_rgb(28, 28, 30)
-                    } else {
-                        egui::Color32::from_rgb(248, 248, 248)
-                    };
+                    let input_bg = palette.panel_bg;
 
                     egui::Frame::none()
                         .fill(input_bg)
@@ -1347,17 +2587,15 @@ impl eframe::App for OllamaChatApp {
                         .show(ui, |ui| {
                             ui.set_max_width(ui.available_width() - 8.0); // Margine interno extra
                             ui.vertical(|ui| {
-                                // Mostra file allegati
-                                if !self.attached_files.is_empty() {
+                                // Mostra file allegati e quelli ancora in caricamento
+                                if !self.attached_files.is_empty()
+                                    || !self.file_loading_promises.is_empty()
+                                {
                                     let mut to_remove = None;
                                     ui.horizontal_wrapped(|ui| {
                                         ui.spacing_mut().item_spacing.x = 6.0; // Spaziatura tra chip
                                         for (i, (filename, _)) in self.attached_files.iter().enumerate() {
-                                            let chip_color = if is_dark {
-                                                egui::Color32::from_rgb(48, 48, 50)
-                                            } else {
-                                                egui::Color32::from_rgb(229, 229, 234)
-                                            };
+                                            let chip_color = palette.chip_bg;
 
                                             egui::Frame::none()
                                                 .fill(chip_color)
@@ -1365,11 +2603,11 @@ impl eframe::App for OllamaChatApp {
                                                 .inner_margin(egui::Margin::symmetric(10.0, 6.0))
                                                 .show(ui, |ui| {
                                                     ui.horizontal(|ui| {
-                                                        ui.label(egui::RichText::new("📎").size(12.0));
-                                                        ui.label(egui::RichText::new(filename).size(12.0));
+                                                        ui.label(egui::RichText::new("📎").size(12.0 * font_scale));
+                                                        ui.label(egui::RichText::new(filename).size(12.0 * font_scale));
 
                                                         let remove_btn = egui::Button::new(
-                                                            egui::RichText::new("✕").size(10.0)
+                                                            egui::RichText::new("✕").size(10.0 * font_scale)
                                                         )
                                                         .frame(false)
                                                         .small();
@@ -1380,6 +2618,23 @@ impl eframe::App for OllamaChatApp {
                                                     });
                                                 });
                                         }
+
+                                        for (filename, _) in &self.file_loading_promises {
+                                            egui::Frame::none()
+                                                .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
+                                                .rounding(egui::Rounding::same(12.0))
+                                                .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+                                                .show(ui, |ui| {
+                                                    ui.horizontal(|ui| {
+                                                        ui.spinner();
+                                                        ui.label(
+                                                            egui::RichText::new(filename)
+                                                                .size(12.0 * font_scale)
+                                                                .weak(),
+                                                        );
+                                                    });
+                                                });
+                                        }
                                     });
 
                                     if let Some(index) = to_remove {
@@ -1403,6 +2658,10 @@ impl eframe::App for OllamaChatApp {
                                         text_edit
                                     );
 
+                                    if std::mem::take(&mut self.focus_input) {
+                                        response.request_focus();
+                                    }
+
                                     // Invia con Cmd+Enter o Ctrl+Enter
                                     let modifiers = ui.input(|i| i.modifiers);
                                     if response.has_focus()
@@ -1417,21 +2676,17 @@ impl eframe::App for OllamaChatApp {
                                     // Pulsanti orizzontali
                                     ui.horizontal(|ui| {
                                         // Pulsante allegato file
-                                        let attach_color = if is_dark {
-                                            egui::Color32::from_rgb(99, 99, 102)
-                                        } else {
-                                            egui::Color32::from_rgb(174, 174, 178)
-                                        };
+                                        let attach_color = palette.attach_bg;
 
                                         let attach_button = egui::Button::new(
-                                            egui::RichText::new("📎").size(16.0)
+                                            egui::RichText::new("📎").size(16.0 * font_scale)
                                         )
                                         .fill(attach_color)
                                         .rounding(egui::Rounding::same(22.0))
                                         .min_size(egui::vec2(44.0, 44.0));
 
                                         if ui.add(attach_button)
-                                            .on_hover_text("Allega file (PDF, Excel, TXT)")
+                                            .on_hover_text("Allega uno o più file (PDF, Excel, TXT)")
                                             .clicked() {
                                             self.open_file_dialog();
                                         }
@@ -1440,15 +2695,16 @@ impl eframe::App for OllamaChatApp {
 
                                         // Pulsante di invio grande e tondeggiante
                                         let button_enabled = self.chat_promise.is_none()
+                                            && self.file_loading_promises.is_empty()
                                             && (!self.input_text.trim().is_empty() || !self.attached_files.is_empty());
                                         let button_color = if button_enabled {
-                                            egui::Color32::from_rgb(0, 122, 255)
+                                            palette.accent
                                         } else {
-                                            egui::Color32::from_rgb(142, 142, 147)
+                                            palette.secondary_text
                                         };
 
                                         let send_button = egui::Button::new(
-                                            egui::RichText::new("▶").size(18.0).color(egui::Color32::WHITE).strong()
+                                            egui::RichText::new("▶").size(18.0 * font_scale).color(palette.on_accent).strong()
                                         )
                                         .fill(button_color)
                                         .rounding(egui::Rounding::same(22.0))
@@ -1464,14 +2720,10 @@ impl eframe::App for OllamaChatApp {
 
                                 // Suggerimento tasti rapidi
                                 ui.add_space(4.0);
-                                let hint_color = if is_dark {
-                                    egui::Color32::from_rgb(142, 142, 147)
-                                } else {
-                                    egui::Color32::from_rgb(142, 142, 147)
-                                };
+                                let hint_color = palette.secondary_text;
                                 ui.label(
-                                    egui::RichText::new("Premi Ctrl+Enter per inviare")
-                                        .size(11.0)
+                                    egui::RichText::new("Premi Ctrl+Enter per inviare · F1 per le scorciatoie")
+                                        .size(11.0 * font_scale)
                                         .color(hint_color)
                                 );
                             });
@@ -1498,13 +2750,13 @@ impl eframe::App for OllamaChatApp {
                             egui::RichText::new(
                                 "L'agente vuole eseguire un'operazione potenzialmente pericolosa:",
                             )
-                            .size(15.0),
+                            .size(15.0 * font_scale),
                         );
                         ui.add_space(12.0);
 
                         // Mostra dettagli del tool
                         egui::Frame::none()
-                            .fill(egui::Color32::from_rgb(248, 248, 248))
+                            .fill(palette.detail_bg)
                             .rounding(egui::Rounding::same(8.0))
                             .inner_margin(egui::Margin::same(12.0))
                             .show(ui, |ui| {
@@ -1524,10 +2776,10 @@ impl eframe::App for OllamaChatApp {
                         ui.horizontal(|ui| {
                             let allow_btn = egui::Button::new(
                                 egui::RichText::new("✓ Consenti")
-                                    .size(14.0)
+                                    .size(14.0 * font_scale)
                                     .color(egui::Color32::WHITE),
                             )
-                            .fill(egui::Color32::from_rgb(52, 199, 89))
+                            .fill(palette.success)
                             .min_size(egui::vec2(150.0, 36.0));
 
                             if ui
@@ -1540,10 +2792,11 @@ impl eframe::App for OllamaChatApp {
 
                             ui.add_space(8.0);
 
-                            let cancel_btn =
-                                egui::Button::new(egui::RichText::new("✕ Annulla").size(14.0))
-                                    .fill(egui::Color32::from_rgb(255, 59, 48))
-                                    .min_size(egui::vec2(150.0, 36.0));
+                            let cancel_btn = egui::Button::new(
+                                egui::RichText::new("✕ Annulla").size(14.0 * font_scale),
+                            )
+                            .fill(palette.danger)
+                            .min_size(egui::vec2(150.0, 36.0));
 
                             if ui.add(cancel_btn).on_hover_text("Non eseguire").clicked() {
                                 should_cancel = true;
@@ -1561,6 +2814,193 @@ impl eframe::App for OllamaChatApp {
             }
         }
 
+        // Pannello delle scorciatoie da tastiera (F1)
+        if self.show_shortcuts {
+            let mut open = true;
+            egui::Window::new("⌨ Scorciatoie da tastiera")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    egui::Grid::new("shortcuts_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .spacing(egui::vec2(24.0, 8.0))
+                        .show(ui, |ui| {
+                            // Gestita dal campo di testo, non dalla tabella
+                            ui.label(egui::RichText::new("Ctrl+Enter").monospace().strong());
+                            ui.label("Invia il messaggio");
+                            ui.end_row();
+
+                            for (shortcut, _, description) in SHORTCUTS {
+                                ui.label(
+                                    egui::RichText::new(ctx.format_shortcut(shortcut))
+                                        .monospace()
+                                        .strong(),
+                                );
+                                ui.label(*description);
+                                ui.end_row();
+                            }
+                        });
+                });
+            if !open {
+                self.show_shortcuts = false;
+            }
+        }
+
+        // Pannello impostazioni: le modifiche si applicano subito e vengono salvate
+        if self.show_settings {
+            let mut open = true;
+            let mut reconnect = false;
+            let mut reset = false;
+
+            egui::Window::new("⚙ Impostazioni")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.set_min_width(460.0);
+
+                    ui.horizontal(|ui| {
+                        for section in SettingsSection::ALL {
+                            ui.selectable_value(&mut self.settings_section, section, section.label());
+                        }
+                    });
+                    ui.separator();
+                    ui.add_space(6.0);
+
+                    let hint_color = palette.secondary_text;
+                    match self.settings_section {
+                        SettingsSection::General => {
+                            ui.checkbox(
+                                &mut self.scan_network_on_startup,
+                                "Cerca i server Ollama nella rete all'avvio",
+                            );
+                            ui.label(
+                                egui::RichText::new("Se disattivato, all'avvio viene proposto l'ultimo server usato.")
+                                    .size(11.0 * font_scale)
+                                    .color(hint_color),
+                            );
+
+                            ui.add_space(12.0);
+                            ui.horizontal(|ui| {
+                                ui.label("Tema:");
+                                for mode in ThemeMode::ALL {
+                                    ui.selectable_value(&mut self.theme_mode, mode, mode.label());
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Colore d'accento:");
+                                ui.color_edit_button_srgb(&mut self.accent_color);
+                                if ui
+                                    .add_enabled(self.accent_color != theme::DEFAULT_ACCENT, egui::Button::new("Ripristina"))
+                                    .on_hover_text("Torna al blu predefinito")
+                                    .clicked()
+                                {
+                                    self.accent_color = theme::DEFAULT_ACCENT;
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Dimensione testo:");
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut self.font_scale,
+                                        settings::MIN_FONT_SCALE..=settings::MAX_FONT_SCALE,
+                                    )
+                                    .step_by(settings::FONT_SCALE_STEP as f64)
+                                    .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                                );
+                                if ui
+                                    .add_enabled(self.font_scale != 1.0, egui::Button::new("Ripristina"))
+                                    .on_hover_text("Torna alla dimensione normale")
+                                    .clicked()
+                                {
+                                    self.font_scale = 1.0;
+                                }
+                            });
+                            ui.label(
+                                egui::RichText::new("Anche con Ctrl++ e Ctrl+- (Ctrl+0 per ripristinare).")
+                                    .size(11.0 * font_scale)
+                                    .color(hint_color),
+                            );
+                        }
+                        SettingsSection::Backend => {
+                            ui.horizontal(|ui| {
+                                ui.label("Server Ollama:");
+                                ui.text_edit_singleline(&mut self.ollama_url);
+                            });
+                            if let Some(model) = &self.selected_model {
+                                ui.label(format!("Modello in uso: {}", model));
+                            }
+                            if ui.button("🔄 Riconnetti").on_hover_text("Carica i modelli dal server indicato").clicked() {
+                                reconnect = true;
+                            }
+                        }
+                        SettingsSection::Agent => {
+                            ui.checkbox(&mut self.agent_mode_enabled, "Modalità agente");
+                            ui.add(
+                                egui::Slider::new(&mut self.max_agent_iterations, 1..=20)
+                                    .text("iterazioni max"),
+                            );
+                            ui.checkbox(&mut self.show_agent_trace, "Mostra la traccia dell'agente");
+                        }
+                        SettingsSection::Sql => {
+                            let auth = if self.sql_auth_method == "sql" {
+                                "SQL Authentication"
+                            } else {
+                                "Windows (Integrated)"
+                            };
+                            ui.label(format!("Server: {}", self.sql_server));
+                            ui.label(format!(
+                                "Database: {}",
+                                if self.sql_database.is_empty() { "-" } else { &self.sql_database }
+                            ));
+                            ui.label(format!("Autenticazione: {}", auth));
+                            ui.checkbox(
+                                &mut self.sql_trust_server_certificate,
+                                "Considera attendibile il certificato del server",
+                            );
+                            if ui.button("🗄️ Configura connessione...").clicked() {
+                                self.show_sql_config = true;
+                            }
+                        }
+                        SettingsSection::Privacy => {
+                            ui.checkbox(
+                                &mut self.remember_sql_username,
+                                "Ricorda il nome utente SQL",
+                            );
+                            ui.label(
+                                egui::RichText::new("La password SQL non viene mai salvata su disco.")
+                                    .size(11.0 * font_scale)
+                                    .color(hint_color),
+                            );
+                            if let Ok(path) = settings::settings_path() {
+                                ui.label(
+                                    egui::RichText::new(format!("File: {}", path.display()))
+                                        .size(11.0 * font_scale)
+                                        .color(hint_color),
+                                );
+                            }
+                            if ui.button("Ripristina le impostazioni predefinite").clicked() {
+                                reset = true;
+                            }
+                        }
+                    }
+                });
+
+            if reconnect {
+                self.load_models();
+            }
+            if reset {
+                self.apply_settings(&AppSettings::default());
+            }
+            if !open {
+                self.show_settings = false;
+            }
+        }
+
         // Finestra configurazione SQL Server
         if self.show_sql_config {
             let mut should_close = false;
@@ -1578,17 +3018,17 @@ impl eframe::App for OllamaChatApp {
 
                         // Server
                         ui.horizontal(|ui| {
-                            ui.label(egui::RichText::new("Server:").size(14.0).strong());
+                            ui.label(egui::RichText::new("Server:").size(14.0 * font_scale).strong());
                             ui.add_space(8.0);
                             ui.text_edit_singleline(&mut self.sql_server);
                         });
-                        ui.label(egui::RichText::new("  (es: localhost, 192.168.1.10, server.domain.com)").size(11.0).color(egui::Color32::GRAY));
+                        ui.label(egui::RichText::new("  (es: localhost, 192.168.1.10, server.domain.com)").size(11.0 * font_scale).color(egui::Color32::GRAY));
 
                         ui.add_space(8.0);
 
                         // Database
                         ui.horizontal(|ui| {
-                            ui.label(egui::RichText::new("Database:").size(14.0).strong());
+                            ui.label(egui::RichText::new("Database:").size(14.0 * font_scale).strong());
                             ui.add_space(8.0);
                             ui.text_edit_singleline(&mut self.sql_database);
                         });
@@ -1598,22 +3038,22 @@ impl eframe::App for OllamaChatApp {
                         ui.add_space(12.0);
 
                         // Metodo autenticazione
-                        ui.label(egui::RichText::new("Autenticazione:").size(14.0).strong());
+                        ui.label(egui::RichText::new("Autenticazione:").size(14.0 * font_scale).strong());
                         ui.add_space(8.0);
 
                         ui.horizontal(|ui| {
                             ui.radio_value(&mut self.sql_auth_method, "windows".to_string(),
-                                egui::RichText::new("🪟 Windows (Integrated)").size(13.0));
+                                egui::RichText::new("🪟 Windows (Integrated)").size(13.0 * font_scale));
                             ui.add_space(16.0);
                             ui.radio_value(&mut self.sql_auth_method, "sql".to_string(),
-                                egui::RichText::new("🔑 SQL Authentication").size(13.0));
+                                egui::RichText::new("🔑 SQL Authentication").size(13.0 * font_scale));
                         });
 
                         if self.sql_auth_method == "windows" {
                             ui.add_space(8.0);
                             ui.label(egui::RichText::new("  ℹ️ Su Windows con dominio, verranno usate le credenziali dell'utente corrente.")
-                                .size(11.0)
-                                .color(egui::Color32::from_rgb(0, 122, 255)));
+                                .size(11.0 * font_scale)
+                                .color(palette.accent));
                         }
 
                         // Username e Password (solo per SQL Auth)
@@ -1621,7 +3061,7 @@ impl eframe::App for OllamaChatApp {
                             ui.add_space(12.0);
 
                             ui.horizontal(|ui| {
-                                ui.label(egui::RichText::new("Username:").size(14.0));
+                                ui.label(egui::RichText::new("Username:").size(14.0 * font_scale));
                                 ui.add_space(8.0);
                                 ui.text_edit_singleline(&mut self.sql_username);
                             });
@@ -1629,7 +3069,7 @@ impl eframe::App for OllamaChatApp {
                             ui.add_space(8.0);
 
                             ui.horizontal(|ui| {
-                                ui.label(egui::RichText::new("Password:").size(14.0));
+                                ui.label(egui::RichText::new("Password:").size(14.0 * font_scale));
                                 ui.add_space(8.0);
                                 let password_edit = egui::TextEdit::singleline(&mut self.sql_password)
                                     .password(true);
@@ -1644,16 +3084,16 @@ impl eframe::App for OllamaChatApp {
                         ui.checkbox(
                             &mut self.sql_trust_server_certificate,
                             egui::RichText::new(
-                                "Accetta certificato server non attendibile (disabilita verifica TLS)",
+                                "Fidati del certificato del server (disabilita verifica TLS)",
                             )
-                            .size(12.0),
+                            .size(12.0 * font_scale),
                         );
                         ui.label(
                             egui::RichText::new(
-                                "  Disattiva solo se usi certificati self-signed in ambienti di test.",
+                                "  Attiva solo con certificati self-signed in ambienti controllati.",
                             )
-                            .size(11.0)
-                            .color(egui::Color32::from_rgb(142, 142, 147)),
+                            .size(11.0 * font_scale)
+                            .color(palette.secondary_text),
                         );
 
                         ui.add_space(12.0);
@@ -1663,18 +3103,18 @@ impl eframe::App for OllamaChatApp {
                         // Status connessione
                         if let Some(status) = &self.sql_connection_status {
                             let (icon, color) = if status.starts_with("connected") {
-                                ("✓", egui::Color32::from_rgb(52, 199, 89))
+                                ("✓", palette.success)
                             } else if status == "connecting" {
-                                ("⟳", egui::Color32::from_rgb(0, 122, 255))
+                                ("⟳", palette.accent)
                             } else if status.starts_with("error:") {
-                                ("✕", egui::Color32::from_rgb(255, 59, 48))
+                                ("✕", palette.danger)
                             } else {
                                 ("", egui::Color32::GRAY)
                             };
 
                             ui.horizontal(|ui| {
-                                ui.label(egui::RichText::new(icon).size(16.0).color(color));
-                                ui.label(egui::RichText::new(status).size(13.0).color(color));
+                                ui.label(egui::RichText::new(icon).size(16.0 * font_scale).color(color));
+                                ui.label(egui::RichText::new(status).size(13.0 * font_scale).color(color));
                             });
 
                             ui.add_space(12.0);
@@ -1682,13 +3122,13 @@ impl eframe::App for OllamaChatApp {
 
                         // Nota read-only
                         egui::Frame::none()
-                            .fill(egui::Color32::from_rgb(255, 249, 196))
+                            .fill(palette.notice_bg)
                             .rounding(egui::Rounding::same(6.0))
                             .inner_margin(egui::Margin::same(10.0))
                             .show(ui, |ui| {
                                 ui.label(egui::RichText::new("🔒 SOLO LETTURA: Le query sono limitate a SELECT. UPDATE, INSERT, DELETE non sono permesse.")
-                                    .size(11.0)
-                                    .color(egui::Color32::from_rgb(138, 109, 0)));
+                                    .size(11.0 * font_scale)
+                                    .color(palette.notice_text));
                             });
 
                         ui.add_space(16.0);
@@ -1696,9 +3136,9 @@ impl eframe::App for OllamaChatApp {
                         // Pulsanti
                         ui.horizontal(|ui| {
                             let test_btn = egui::Button::new(
-                                egui::RichText::new("🔌 Test Connessione").size(14.0).color(egui::Color32::WHITE)
+                                egui::RichText::new("🔌 Test Connessione").size(14.0 * font_scale).color(palette.on_accent)
                             )
-                            .fill(egui::Color32::from_rgb(0, 122, 255))
+                            .fill(palette.accent)
                             .min_size(egui::vec2(160.0, 36.0));
 
                             if ui.add(test_btn).clicked() && self.sql_test_promise.is_none() {
@@ -1708,7 +3148,7 @@ impl eframe::App for OllamaChatApp {
                             ui.add_space(8.0);
 
                             let close_btn = egui::Button::new(
-                                egui::RichText::new("Chiudi").size(14.0)
+                                egui::RichText::new("Chiudi").size(14.0 * font_scale)
                             )
                             .min_size(egui::vec2(100.0, 36.0));
 
@@ -1759,12 +3199,19 @@ impl eframe::App for OllamaChatApp {
         if self.scanning_promise.is_some()
             || self.loading_models_promise.is_some()
             || self.chat_promise.is_some()
-            || self.file_loading_promise.is_some()
+            || self.file_picker_promise.is_some()
+            || !self.file_loading_promises.is_empty()
             || self.tool_execution_promise.is_some()
             || self.sql_test_promise.is_some()
         {
             ctx.request_repaint();
         }
+
+        // Chiudendo il pannello o la finestra le modifiche si salvano subito
+        let flush = (self.settings_were_open && !self.show_settings)
+            || ctx.input(|i| i.viewport().close_requested());
+        self.settings_were_open = self.show_settings;
+        self.persist_settings(ctx, flush);
     }
 }
 
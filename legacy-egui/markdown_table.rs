@@ -0,0 +1,334 @@
+// Tabelle markdown estratte dalle risposte per disegnarle con una griglia egui.
+// CommonMarkViewer le mostra senza bordi né allineamento: il markdown viene diviso
+// in parti di testo e tabelle (GFM), lasciando intatti i blocchi di codice.
+
+/// Allineamento di una colonna, dalla riga di separazione (`:--`, `:-:`, `--:`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    /// Nessun `:`: a destra se la colonna contiene solo numeri, altrimenti a sinistra
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub alignments: Vec<Alignment>,
+    /// Righe con lo stesso numero di celle dell'intestazione
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Allineamento effettivo di una colonna, risolvendo `Auto`
+    pub fn alignment(&self, column: usize) -> Alignment {
+        match self
+            .alignments
+            .get(column)
+            .copied()
+            .unwrap_or(Alignment::Auto)
+        {
+            Alignment::Auto => {
+                let mut values = self
+                    .rows
+                    .iter()
+                    .map(|row| row[column].as_str())
+                    .filter(|value| !value.is_empty())
+                    .peekable();
+                if values.peek().is_some() && values.all(is_numeric) {
+                    Alignment::Right
+                } else {
+                    Alignment::Left
+                }
+            }
+            alignment => alignment,
+        }
+    }
+}
+
+/// Parte di una cella con la sua formattazione inline
+#[derive(Debug, Clone, PartialEq)]
+pub enum Span {
+    Text(String),
+    /// `**grassetto**`
+    Strong(String),
+    /// `` `codice` ``
+    Code(String),
+    /// `[testo](url)`
+    Link {
+        text: String,
+        url: String,
+    },
+}
+
+/// Formattazione inline di una cella: grassetto, codice e link. Il resto del
+/// markdown resta testo, così come i marcatori senza chiusura
+pub fn inline_spans(cell: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut rest = cell;
+
+    while let Some(c) = rest.chars().next() {
+        let parsed = if let Some(after) = rest.strip_prefix("**") {
+            after
+                .split_once("**")
+                .filter(|(inner, _)| !inner.is_empty())
+                .map(|(inner, tail)| (Span::Strong(inner.to_string()), tail))
+        } else if let Some(after) = rest.strip_prefix('`') {
+            after
+                .split_once('`')
+                .filter(|(inner, _)| !inner.is_empty())
+                .map(|(inner, tail)| (Span::Code(inner.to_string()), tail))
+        } else if let Some(after) = rest.strip_prefix('[') {
+            after.split_once("](").and_then(|(label, tail)| {
+                let (url, tail) = tail.split_once(')')?;
+                (!label.is_empty() && !url.is_empty()).then(|| {
+                    let link = Span::Link {
+                        text: label.to_string(),
+                        url: url.trim().to_string(),
+                    };
+                    (link, tail)
+                })
+            })
+        } else {
+            None
+        };
+
+        match parsed {
+            Some((span, tail)) => {
+                if !text.is_empty() {
+                    spans.push(Span::Text(std::mem::take(&mut text)));
+                }
+                spans.push(span);
+                rest = tail;
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        spans.push(Span::Text(text));
+    }
+    spans
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Markdown(String),
+    Table(Table),
+}
+
+/// Divide il markdown in testo e tabelle, nell'ordine in cui compaiono
+pub fn split_tables(markdown: &str) -> Vec<Block> {
+    if !markdown.contains('|') {
+        return vec![Block::Markdown(markdown.to_string())];
+    }
+
+    let lines: Vec<&str> = markdown.split_inclusive('\n').collect();
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut fence: Option<&str> = None;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+        } else if let Some(table) = parse_table(&lines[index..]) {
+            if !text.trim().is_empty() {
+                blocks.push(Block::Markdown(text.clone()));
+            }
+            text.clear();
+            // Intestazione, separatore e righe
+            index += 2 + table.rows.len();
+            blocks.push(Block::Table(table));
+            continue;
+        }
+
+        text.push_str(line);
+        index += 1;
+    }
+
+    if !text.trim().is_empty() || blocks.is_empty() {
+        blocks.push(Block::Markdown(text));
+    }
+    blocks
+}
+
+/// Tabella che inizia alla prima riga: intestazione seguita dal separatore
+fn parse_table(lines: &[&str]) -> Option<Table> {
+    let (header, separator) = (lines.first()?, lines.get(1)?);
+    if !header.contains('|') {
+        return None;
+    }
+
+    let headers = split_row(header);
+    let alignments = split_row(separator)
+        .iter()
+        .map(|cell| parse_alignment(cell))
+        .collect::<Option<Vec<_>>>()?;
+    if alignments.len() != headers.len() {
+        return None;
+    }
+
+    let rows = lines[2..]
+        .iter()
+        .take_while(|line| line.contains('|') && !line.trim().is_empty())
+        .map(|line| {
+            // Le celle mancanti restano vuote, quelle in più vengono scartate
+            let mut cells = split_row(line);
+            cells.resize(headers.len(), String::new());
+            cells
+        })
+        .collect();
+
+    Some(Table {
+        headers,
+        alignments,
+        rows,
+    })
+}
+
+/// Celle di una riga, senza le barre ai bordi; `\|` è una barra nel testo
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn parse_alignment(cell: &str) -> Option<Alignment> {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+        return None;
+    }
+
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Alignment::Center,
+        (true, false) => Alignment::Left,
+        (false, true) => Alignment::Right,
+        (false, false) => Alignment::Auto,
+    })
+}
+
+/// Numeri anche con separatori, segno, valuta o percentuale (1.234,5 · -3 · 12% · €10)
+fn is_numeric(value: &str) -> bool {
+    let digits = value
+        .trim()
+        .trim_start_matches(['€', '$', '+', '-'])
+        .trim_end_matches(['%', '€'])
+        .trim();
+    digits.chars().any(|c| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '\'' | ' '))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(markdown: &str) -> Table {
+        match split_tables(markdown).as_slice() {
+            [Block::Table(table)] => table.clone(),
+            blocks => panic!("attesa una sola tabella: {:?}", blocks),
+        }
+    }
+
+    #[test]
+    fn test_escaped_pipe() {
+        let table = table("| Comando | Uso |\n|---|---|\n| `a \\| b` | pipe \\| filtro |\n");
+        assert_eq!(table.rows, vec![vec!["`a | b`", "pipe | filtro"]]);
+    }
+
+    #[test]
+    fn test_fenced_code_with_pipes() {
+        let markdown = "Prima\n```\n| a | b |\n|---|---|\n```\n| x | y |\n|---|---|\n| 1 | 2 |\n";
+        let blocks = split_tables(markdown);
+        assert_eq!(blocks.len(), 2);
+        match &blocks[0] {
+            Block::Markdown(text) => assert!(text.contains("| a | b |")),
+            block => panic!("atteso il testo con il codice: {:?}", block),
+        }
+        match &blocks[1] {
+            Block::Table(table) => assert_eq!(table.rows, vec![vec!["1", "2"]]),
+            block => panic!("attesa la tabella: {:?}", block),
+        }
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let table = table("| a | b | c |\n|---|---|---|\n| 1 |\n| 1 | 2 | 3 | 4 |\n");
+        assert_eq!(table.rows, vec![vec!["1", "", ""], vec!["1", "2", "3"]]);
+        // Un separatore con un numero diverso di colonne non è una tabella
+        assert_eq!(
+            split_tables("| a | b |\n|---|\n"),
+            vec![Block::Markdown("| a | b |\n|---|\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_alignment() {
+        let table = table(
+            "| Nome | Importo | Quota | Nota | Centro |\n|---|---|---|--:|:-:|\n\
+             | Mario | 1.234,50 | 12% | x | y |\n| Anna | -3 | | z | w |\n",
+        );
+        assert_eq!(table.alignment(0), Alignment::Left);
+        // Numeri con separatori, segno e percentuale; le celle vuote non contano
+        assert_eq!(table.alignment(1), Alignment::Right);
+        assert_eq!(table.alignment(2), Alignment::Right);
+        assert_eq!(table.alignment(3), Alignment::Right);
+        assert_eq!(table.alignment(4), Alignment::Center);
+    }
+
+    #[test]
+    fn test_inline_spans() {
+        assert_eq!(
+            inline_spans("**Totale**: `42` su [sito](https://example.org)"),
+            vec![
+                Span::Strong("Totale".to_string()),
+                Span::Text(": ".to_string()),
+                Span::Code("42".to_string()),
+                Span::Text(" su ".to_string()),
+                Span::Link {
+                    text: "sito".to_string(),
+                    url: "https://example.org".to_string(),
+                },
+            ]
+        );
+        // Marcatori senza chiusura restano testo
+        assert_eq!(
+            inline_spans("2 ** 3 e `a [b]"),
+            vec![Span::Text("2 ** 3 e `a [b]".to_string())]
+        );
+        assert!(inline_spans("").is_empty());
+    }
+}
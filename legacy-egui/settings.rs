@@ -0,0 +1,109 @@
+// Impostazioni persistenti dell'app egui.
+// Le scelte fatte nel pannello "Impostazioni" e nei toggle della chat vengono salvate
+// in settings.json e ricaricate all'avvio. La password SQL non viene mai scritta su disco.
+
+use crate::theme::{self, ThemeMode};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Nome del file delle impostazioni
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Limiti e passo della scala del testo (1.0 = dimensioni originali)
+pub const MIN_FONT_SCALE: f32 = 0.8;
+pub const MAX_FONT_SCALE: f32 = 1.5;
+pub const FONT_SCALE_STEP: f32 = 0.1;
+
+/// Ogni campo ha un default: un file scritto da una versione precedente resta valido
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    // Generale
+    /// Cerca i server Ollama nella rete locale all'avvio
+    pub scan_network_on_startup: bool,
+    pub theme: ThemeMode,
+    /// Colore d'accento in sRGB
+    pub accent_color: [u8; 3],
+    /// Fattore applicato a tutte le dimensioni del testo
+    pub font_scale: f32,
+
+    // Backend
+    pub ollama_url: String,
+    /// Modello usato l'ultima volta, preselezionato se il server lo ha ancora
+    pub last_model: Option<String>,
+
+    // Agente
+    pub agent_mode_enabled: bool,
+    pub max_agent_iterations: usize,
+    pub show_agent_trace: bool,
+
+    // SQL
+    pub sql_server: String,
+    pub sql_database: String,
+    /// "windows" o "sql"
+    pub sql_auth_method: String,
+    pub sql_trust_server_certificate: bool,
+
+    // Privacy
+    /// Salva anche il nome utente SQL, vuoto altrimenti
+    pub remember_sql_username: bool,
+    pub sql_username: String,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            scan_network_on_startup: true,
+            theme: ThemeMode::System,
+            accent_color: theme::DEFAULT_ACCENT,
+            font_scale: 1.0,
+            ollama_url: "http://localhost:11434".to_string(),
+            last_model: None,
+            agent_mode_enabled: false,
+            max_agent_iterations: 5,
+            show_agent_trace: false,
+            sql_server: "localhost".to_string(),
+            sql_database: String::new(),
+            sql_auth_method: "windows".to_string(),
+            sql_trust_server_certificate: false,
+            remember_sql_username: false,
+            sql_username: String::new(),
+        }
+    }
+}
+
+/// Percorso di settings.json, in una sottocartella separata da quella dell'app Tauri
+pub fn settings_path() -> Result<PathBuf> {
+    let base_dir = dirs::data_local_dir()
+        .or_else(dirs::data_dir)
+        .context("Cartella dei dati utente non trovata")?;
+    Ok(base_dir
+        .join("MatePro")
+        .join("egui")
+        .join(SETTINGS_FILE_NAME))
+}
+
+/// Impostazioni salvate, o quelle predefinite al primo avvio
+pub fn load_settings() -> Result<AppSettings> {
+    let path = settings_path()?;
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Impossibile leggere {}", path.display()))?;
+    serde_json::from_str(&content).context("File delle impostazioni non valido")
+}
+
+pub fn save_settings(settings: &AppSettings) -> Result<()> {
+    let path = settings_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Impossibile creare la cartella {}", dir.display()))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings)?;
+    fs::write(&path, content).with_context(|| format!("Impossibile scrivere {}", path.display()))
+}
@@ -0,0 +1,124 @@
+// Tema dell'app egui: quello di sistema, chiaro o scuro, con il colore d'accento
+// scelto dall'utente. I colori dell'interfaccia vengono tutti da `Palette`,
+// così la scelta del tema si applica ovunque allo stesso modo.
+
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+
+/// Accento predefinito, il blu di sistema di macOS/iOS
+pub const DEFAULT_ACCENT: [u8; 3] = [0, 122, 255];
+/// Colori uguali nei due temi
+pub const SECONDARY_TEXT: Color32 = Color32::from_rgb(142, 142, 147);
+pub const SUCCESS: Color32 = Color32::from_rgb(52, 199, 89);
+pub const WARNING: Color32 = Color32::from_rgb(255, 204, 0);
+pub const DANGER: Color32 = Color32::from_rgb(255, 59, 48);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    /// Segue il tema chiaro/scuro del sistema operativo
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::System, ThemeMode::Light, ThemeMode::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeMode::System => "Sistema",
+            ThemeMode::Light => "Chiaro",
+            ThemeMode::Dark => "Scuro",
+        }
+    }
+}
+
+/// Colori dell'interfaccia per il tema in uso
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub dark: bool,
+    pub accent: Color32,
+    /// Testo sopra l'accento (bolle utente, pulsanti pieni): nero se l'accento è chiaro
+    pub on_accent: Color32,
+    pub text: Color32,
+    pub secondary_text: Color32,
+    pub tertiary_text: Color32,
+    pub success: Color32,
+    pub danger: Color32,
+    /// Sfondo di intestazione e area di input
+    pub panel_bg: Color32,
+    pub control_bg: Color32,
+    pub control_hover: Color32,
+    pub assistant_bubble: Color32,
+    pub chip_bg: Color32,
+    pub attach_bg: Color32,
+    /// Riquadri con i dettagli di un tool
+    pub detail_bg: Color32,
+    pub error_bg: Color32,
+    pub notice_bg: Color32,
+    pub notice_text: Color32,
+}
+
+impl Palette {
+    pub fn new(dark: bool, accent: [u8; 3]) -> Self {
+        let [r, g, b] = accent;
+        // Luminanza relativa approssimata (Rec. 709)
+        let luminance = 0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b);
+        let on_accent = if luminance > 160.0 {
+            Color32::BLACK
+        } else {
+            Color32::WHITE
+        };
+
+        let base = Self {
+            dark,
+            accent: Color32::from_rgb(r, g, b),
+            on_accent,
+            text: Color32::BLACK,
+            secondary_text: SECONDARY_TEXT,
+            tertiary_text: Color32::from_rgb(174, 174, 178),
+            success: SUCCESS,
+            danger: DANGER,
+            panel_bg: Color32::from_rgb(248, 248, 248),
+            control_bg: Color32::from_rgb(242, 242, 247),
+            control_hover: Color32::from_rgb(229, 229, 234),
+            assistant_bubble: Color32::from_rgb(229, 229, 234),
+            chip_bg: Color32::from_rgb(229, 229, 234),
+            attach_bg: Color32::from_rgb(174, 174, 178),
+            detail_bg: Color32::from_rgb(248, 248, 248),
+            error_bg: Color32::from_rgb(255, 239, 239),
+            notice_bg: Color32::from_rgb(255, 249, 196),
+            notice_text: Color32::from_rgb(138, 109, 0),
+        };
+
+        if !dark {
+            return base;
+        }
+        Self {
+            text: Color32::WHITE,
+            panel_bg: Color32::from_rgb(28, 28, 30),
+            control_bg: Color32::from_rgb(44, 44, 46),
+            control_hover: Color32::from_rgb(58, 58, 60),
+            assistant_bubble: Color32::from_rgb(58, 58, 60),
+            chip_bg: Color32::from_rgb(48, 48, 50),
+            attach_bg: Color32::from_rgb(99, 99, 102),
+            detail_bg: Color32::from_rgb(44, 44, 46),
+            error_bg: Color32::from_rgb(72, 36, 36),
+            notice_bg: Color32::from_rgb(66, 58, 20),
+            notice_text: Color32::from_rgb(255, 214, 10),
+            ..base
+        }
+    }
+
+    /// Applica la palette agli stili dei widget
+    pub fn apply(&self, visuals: &mut egui::Visuals) {
+        visuals.widgets.inactive.weak_bg_fill = self.control_bg;
+        visuals.widgets.inactive.bg_fill = self.control_bg;
+        visuals.widgets.hovered.weak_bg_fill = self.control_hover;
+        visuals.widgets.hovered.bg_fill = self.control_hover;
+        visuals.widgets.active.bg_fill = self.accent;
+        visuals.hyperlink_color = self.accent;
+    }
+}
@@ -14,6 +14,8 @@ const state = {
     messageHistoryIndex: -1,
     attachedFiles: [],
     agentMode: true,
+    // Automatic web search context: null follows agent mode until the user picks a value
+    webContext: null,
     currentIteration: 0,
     maxIterations: 5,
     systemPromptAdded: false,
@@ -23,6 +25,11 @@ const state = {
     greetingShown: false,
     // AIConnect state
     backendKind: 'ollama_local',
+    // Model selected in the previous session, preferred when loading the model list
+    lastSelectedModel: null,
+    // AIConnect node forced by the user, null lets the orchestrator balance
+    selectedNode: null,
+    aiconnectNodes: [],
     aiconnectFound: false,
     aiconnectServices: [],
     // Local storage state
@@ -32,19 +39,28 @@ const state = {
     },
     currentConversationId: null,
     memoryConversations: [],
+    pendingDraft: null,
+    draftSignature: null,
+    historySearchResults: null,
+    archivedConversations: null,
     memoryContext: '',
     memoryContextInjected: false,
     calendarEvents: [],
+    // Upcoming occurrences of the recurring events, by event id
+    calendarOccurrences: {},
     calendarStatusTimeout: null,
     integrations: {
         outlook: {
             configured: false,
             connected: false,
             pending: false,
+            reconnectRequired: false,
             tenant: null,
             clientId: null,
+            calendarId: null,
             message: null,
             expiresAt: null,
+            pendingExpiresAt: null,
             userCode: null,
             verificationUri: null,
             interval: 5,
@@ -53,10 +69,12 @@ const state = {
             configured: false,
             connected: false,
             pending: false,
+            reconnectRequired: false,
             clientId: null,
             calendarId: 'primary',
             message: null,
             expiresAt: null,
+            pendingExpiresAt: null,
             userCode: null,
             verificationUri: null,
             interval: 5,
@@ -71,6 +89,54 @@ const state = {
         outlookCredentials: false,
     },
     pendingIntegrationStep: null,
+    appSettings: {
+        version: 1,
+        auto_update_check: true,
+        last_update_check: null,
+        time_format: '24h',
+        proxy: {
+            enabled: false,
+            url: '',
+            username: null,
+            password: null,
+            no_proxy: 'localhost,127.0.0.1,::1,10.0.0.0/8,172.16.0.0/12,192.168.0.0/16',
+        },
+        translation: {
+            deepl_api_key: null,
+            libretranslate_url: null,
+            libretranslate_api_key: null,
+        },
+        embedding_model: 'nomic-embed-text',
+        memory_max_conversations: 500,
+        memory_max_size_mb: 50,
+        offline_mode: false,
+        save_attachment_content: true,
+        suggest_calendar_events: true,
+        event_notifications: {
+            enabled: true,
+            default_reminder_minutes: 15,
+        },
+        log_level: 'info',
+        personalize_with_profile: false,
+        voice: {
+            language: 'it',
+            whisper_model_path: null,
+            read_aloud_language: 'auto',
+        },
+        sql_query_timeout_secs: 30,
+        chat_timeout_secs: 300,
+        sql_masking: {
+            enabled: false,
+            patterns: [],
+        },
+    },
+    availableUpdate: null,
+    // Dictation: 'idle', 'recording' or 'transcribing'
+    voiceInput: 'idle',
+    // "Ascolta" button of the reply being read aloud
+    readAloudButton: null,
+    // Refresh timer of the AIConnect nodes panel while it is open
+    nodesRefreshTimer: null,
 };
 
 // ============ DOM ELEMENTS ============
@@ -83,6 +149,8 @@ const elements = {
     
     // Setup
     scanningIndicator: document.getElementById('scanning-indicator'),
+    scanProgress: document.getElementById('scan-progress'),
+    scanProgressText: document.getElementById('scan-progress-text'),
     serverList: document.getElementById('server-list'),
     servers: document.getElementById('servers'),
     serverUrl: document.getElementById('server-url'),
@@ -95,8 +163,11 @@ const elements = {
     
     // Chat
     modelSelector: document.getElementById('model-selector'),
+    nodeSelector: document.getElementById('node-selector'),
     agentModeToggle: document.getElementById('agent-mode-toggle'),
+    webContextToggle: document.getElementById('web-context-toggle'),
     iterationCounter: document.getElementById('iteration-counter'),
+    undoFileBtn: document.getElementById('undo-file-btn'),
     sqlConfigBtn: document.getElementById('sql-config-btn'),
     newChatBtn: document.getElementById('new-chat-btn'),
     disconnectBtn: document.getElementById('disconnect-btn'),
@@ -107,19 +178,29 @@ const elements = {
     attachedFilesContainer: document.getElementById('attached-files'),
     messageInput: document.getElementById('message-input'),
     attachBtn: document.getElementById('attach-btn'),
+    micBtn: document.getElementById('mic-btn'),
     sendBtn: document.getElementById('send-btn'),
     fileInput: document.getElementById('file-input'),
     backendIndicator: document.getElementById('backend-indicator'),
     calendarList: document.getElementById('calendar-list'),
     calendarStatus: document.getElementById('calendar-status'),
+    calendarAuthStatus: document.getElementById('calendar-auth-status'),
     exportCalendarBtn: document.getElementById('export-calendar-btn'),
+    addCalendarEventBtn: document.getElementById('add-calendar-event-btn'),
+    calendarNotificationsBtn: document.getElementById('calendar-notifications-btn'),
+    calendarQuickAdd: document.getElementById('calendar-quick-add'),
+    calendarQuickText: document.getElementById('calendar-quick-text'),
+    calendarQuickPreview: document.getElementById('calendar-quick-preview'),
     clearCalendarBtn: document.getElementById('clear-calendar-btn'),
     
     // SQL Modal
     sqlModal: document.getElementById('sql-modal'),
     closeSqlModal: document.getElementById('close-sql-modal'),
     closeSqlBtn: document.getElementById('close-sql-btn'),
+    sqlDbType: document.getElementById('sql-db-type'),
     sqlServer: document.getElementById('sql-server'),
+    sqlServerHint: document.getElementById('sql-server-hint'),
+    sqlAuthWindows: document.getElementById('sql-auth-windows'),
     sqlDatabase: document.getElementById('sql-database'),
     sqlCredentials: document.getElementById('sql-credentials'),
     sqlUsername: document.getElementById('sql-username'),
@@ -134,6 +215,28 @@ const elements = {
     confirmAllow: document.getElementById('confirm-allow'),
     confirmCancel: document.getElementById('confirm-cancel'),
     versionIndicator: document.getElementById('version-indicator'),
+
+    // Model Details Modal
+    modelInfoBtn: document.getElementById('model-info-btn'),
+    modelInfoModal: document.getElementById('model-info-modal'),
+    modelInfoTitle: document.getElementById('model-info-title'),
+    modelInfoBody: document.getElementById('model-info-body'),
+    closeModelInfoModal: document.getElementById('close-model-info-modal'),
+
+    // AIConnect Nodes Modal
+    nodesBtn: document.getElementById('aiconnect-nodes-btn'),
+    nodesModal: document.getElementById('nodes-modal'),
+    nodesBody: document.getElementById('nodes-body'),
+    closeNodesModal: document.getElementById('close-nodes-modal'),
+
+    // Sheet Selection Modal
+    sheetModal: document.getElementById('sheet-modal'),
+    sheetModalFile: document.getElementById('sheet-modal-file'),
+    sheetList: document.getElementById('sheet-list'),
+    sheetRange: document.getElementById('sheet-range'),
+    sheetConfirm: document.getElementById('sheet-confirm'),
+    sheetCancel: document.getElementById('sheet-cancel'),
+    closeSheetModal: document.getElementById('close-sheet-modal'),
     
     // Settings Modal
     settingsBtn: document.getElementById('settings-btn'),
@@ -146,10 +249,58 @@ const elements = {
     saveSettingsBtn: document.getElementById('save-settings-btn'),
     dataDirInfo: document.getElementById('data-dir-info'),
     dataDirPath: document.getElementById('data-dir-path'),
+    logDirPath: document.getElementById('log-dir-path'),
+    storageInfo: document.getElementById('storage-info'),
+    storageStats: document.getElementById('storage-stats'),
+    cleanupStorageBtn: document.getElementById('cleanup-storage-btn'),
+    autoUpdateCheck: document.getElementById('auto-update-check'),
+    timeFormatSelector: document.getElementById('time-format'),
+    proxyEnabled: document.getElementById('proxy-enabled'),
+    proxyFields: document.getElementById('proxy-fields'),
+    proxyUrl: document.getElementById('proxy-url'),
+    proxyUsername: document.getElementById('proxy-username'),
+    proxyPassword: document.getElementById('proxy-password'),
+    proxyNoProxy: document.getElementById('proxy-no-proxy'),
+    deeplApiKey: document.getElementById('deepl-api-key'),
+    libretranslateUrl: document.getElementById('libretranslate-url'),
+    libretranslateApiKey: document.getElementById('libretranslate-api-key'),
+    embeddingModel: document.getElementById('embedding-model'),
+    memoryMaxConversations: document.getElementById('memory-max-conversations'),
+    sqlQueryTimeout: document.getElementById('sql-query-timeout'),
+    chatTimeout: document.getElementById('chat-timeout'),
+    sqlMaskingEnabled: document.getElementById('sql-masking-enabled'),
+    sqlMaskingPatterns: document.getElementById('sql-masking-patterns'),
+    memoryMaxSizeMb: document.getElementById('memory-max-size-mb'),
+    offlineMode: document.getElementById('offline-mode'),
+    saveAttachmentContent: document.getElementById('save-attachment-content'),
+    suggestCalendarEvents: document.getElementById('suggest-calendar-events'),
+    eventNotificationsEnabled: document.getElementById('event-notifications-enabled'),
+    eventReminderMinutes: document.getElementById('event-reminder-minutes'),
+    logLevel: document.getElementById('log-level'),
+    personalizeWithProfile: document.getElementById('personalize-with-profile'),
+    voiceLanguage: document.getElementById('voice-language'),
+    whisperModelPath: document.getElementById('whisper-model-path'),
+    readAloudLanguage: document.getElementById('read-aloud-language'),
+    checkUpdatesBtn: document.getElementById('check-updates-btn'),
+    diagnosticReportBtn: document.getElementById('diagnostic-report-btn'),
+    changeDataDirBtn: document.getElementById('change-data-dir-btn'),
+    resetDataDirBtn: document.getElementById('reset-data-dir-btn'),
+
+    // Update Banner
+    updateBanner: document.getElementById('update-banner'),
+    updateBannerTitle: document.getElementById('update-banner-title'),
+    updateNotes: document.getElementById('update-notes'),
+    updateNotesContent: document.getElementById('update-notes-content'),
+    updateNowBtn: document.getElementById('update-now-btn'),
+    updateLaterBtn: document.getElementById('update-later-btn'),
     
     // History Sidebar
     historyList: document.getElementById('history-list'),
     clearHistoryBtn: document.getElementById('clear-history-btn'),
+    exportHistoryBtn: document.getElementById('export-history-btn'),
+    importHistoryBtn: document.getElementById('import-history-btn'),
+    archiveHistoryBtn: document.getElementById('archive-history-btn'),
+    historySearch: document.getElementById('history-search'),
 };
 
 const greetingTemplates = {
@@ -199,6 +350,18 @@ const MEMORY_CONTEXT_CONFIG = {
     maxHighlights: 8,
 };
 
+// Attachments longer than minChars are indexed and only the most relevant chunks are sent
+const RAG_CONFIG = {
+    minChars: 12000,
+    topK: 6,
+};
+
+// The active chat is autosaved as a draft so it survives crashes
+const DRAFT_AUTOSAVE_INTERVAL_MS = 30000;
+
+// Formats accepted by the file dialog and by drag-and-drop
+const ATTACHMENT_EXTENSIONS = ['pdf', 'xlsx', 'xls', 'ods', 'txt', 'md', 'csv', 'json', 'xml'];
+
 const MEMORY_HIGHLIGHT_PATTERNS = [
     /\bmi piace\b/i,
     /\bmi ador[oa]\b/i,
@@ -221,30 +384,15 @@ const MEMORY_HIGHLIGHT_PATTERNS = [
     /\bho\s+una\s+(gara|maratona|mezza maratona|visita|riunione)\b/i,
 ];
 
-const MONTHS_IT = {
-    gennaio: 0,
-    febbraio: 1,
-    marzo: 2,
-    aprile: 3,
-    maggio: 4,
-    giugno: 5,
-    luglio: 6,
-    agosto: 7,
-    settembre: 8,
-    ottobre: 9,
-    novembre: 10,
-    dicembre: 11,
-};
-
-const WEEK_DAYS_IT = ['domenica', 'lunedì', 'martedì', 'mercoledì', 'giovedì', 'venerdì', 'sabato'];
+// Reminder attached to the events added from the chat, exported as VALARM
+const DEFAULT_EVENT_REMINDER_MINUTES = 15;
+// Days ahead in which the occurrences of recurring events are listed
+const CALENDAR_OCCURRENCES_DAYS = 60;
 
-const RELATIVE_KEYWORDS = {
-    oggi: 0,
-    stasera: 0,
-    stanotte: 0,
-    stamattina: 0,
-    domani: 1,
-    dopodomani: 2,
+const RECURRENCE_LABELS = {
+    daily: ['ogni giorno', 'giorni'],
+    weekly: ['ogni settimana', 'settimane'],
+    monthly: ['ogni mese', 'mesi'],
 };
 
 // ============ UTILITIES ============
@@ -393,10 +541,13 @@ function applyOutlookStatus(rawStatus = {}) {
         configured: Boolean(rawStatus.configured),
         connected: Boolean(rawStatus.connected),
         pending: Boolean(rawStatus.pending),
+        reconnectRequired: Boolean(rawStatus.reconnect_required),
         tenant: rawStatus.tenant || null,
         clientId: rawStatus.client_id || null,
+        calendarId: rawStatus.calendar_id || null,
         message: rawStatus.message || null,
         expiresAt: rawStatus.expires_at || null,
+        pendingExpiresAt: rawStatus.pending_expires_at || null,
         userCode: rawStatus.user_code || null,
         verificationUri: rawStatus.verification_uri || null,
         interval: rawStatus.interval || 5,
@@ -407,6 +558,7 @@ function applyOutlookStatus(rawStatus = {}) {
     } else {
         clearOutlookPollTimer();
     }
+    renderCalendarAuthStatus();
 }
 
 function applyGoogleStatus(rawStatus = {}) {
@@ -414,10 +566,12 @@ function applyGoogleStatus(rawStatus = {}) {
         configured: Boolean(rawStatus.configured),
         connected: Boolean(rawStatus.connected),
         pending: Boolean(rawStatus.pending),
+        reconnectRequired: Boolean(rawStatus.reconnect_required),
         clientId: rawStatus.client_id || null,
         calendarId: rawStatus.calendar_id || 'primary',
         message: rawStatus.message || null,
         expiresAt: rawStatus.expires_at || null,
+        pendingExpiresAt: rawStatus.pending_expires_at || null,
         userCode: rawStatus.user_code || null,
         verificationUri: rawStatus.verification_uri || null,
         interval: rawStatus.interval || 5,
@@ -428,6 +582,59 @@ function applyGoogleStatus(rawStatus = {}) {
     } else {
         clearGooglePollTimer();
     }
+    renderCalendarAuthStatus();
+}
+
+const CALENDAR_AUTH_LABELS = {
+    outlook: 'Outlook',
+    google: 'Google Calendar',
+};
+
+// Live state of the authorizations waiting in the browser, refreshed at
+// every poll until they complete or expire
+function renderCalendarAuthStatus() {
+    const container = elements.calendarAuthStatus;
+    if (!container) return;
+
+    container.innerHTML = '';
+    Object.entries(CALENDAR_AUTH_LABELS).forEach(([provider, label]) => {
+        const integration = state.integrations[provider];
+        if (!integration.pending) return;
+
+        const remainingMs = integration.pendingExpiresAt
+            ? new Date(integration.pendingExpiresAt).getTime() - Date.now()
+            : null;
+        const expiry = remainingMs === null
+            ? ''
+            : remainingMs > 0
+                ? ` · scade tra ${Math.max(1, Math.ceil(remainingMs / 60000))} min`
+                : ' · scaduta';
+
+        const row = document.createElement('div');
+        row.className = 'calendar-auth-row';
+        const text = document.createElement('span');
+        text.textContent = `⏳ ${label}: ${integration.message || 'in attesa di autorizzazione'}${expiry}`;
+        row.appendChild(text);
+
+        if (integration.verificationUri && (remainingMs === null || remainingMs > 0)) {
+            const openBtn = document.createElement('button');
+            openBtn.className = 'calendar-btn';
+            openBtn.textContent = 'Apri browser';
+            openBtn.addEventListener('click', () => openCalendarAuthorization(integration.verificationUri));
+            row.appendChild(openBtn);
+        }
+        container.appendChild(row);
+    });
+    container.classList.toggle('hidden', container.childElementCount === 0);
+}
+
+async function openCalendarAuthorization(url) {
+    try {
+        await openExternal(url);
+    } catch (error) {
+        // The link in the chat still works
+        console.warn('Impossibile aprire il browser per l\'autorizzazione:', error);
+    }
 }
 
 async function refreshCalendarIntegrationsStatus(options = {}) {
@@ -449,6 +656,17 @@ async function refreshCalendarIntegrationsStatus(options = {}) {
     }
 }
 
+// Tells the user which calendars lost the authorization on the provider side
+function reportCalendarsToReconnect() {
+    const labels = [];
+    if (state.integrations.outlook.reconnectRequired) labels.push('Outlook');
+    if (state.integrations.google.reconnectRequired) labels.push('Google Calendar');
+    if (labels.length === 0) return false;
+
+    showCalendarStatus(`Accesso revocato: ricollega ${labels.join(' e ')}`, true);
+    return true;
+}
+
 function scheduleOutlookPoll(intervalSeconds = 5) {
     clearOutlookPollTimer();
     const delay = Math.max(2000, (intervalSeconds || 5) * 1000);
@@ -466,8 +684,9 @@ async function pollOutlookDeviceFlowOnce() {
             case 'pending':
                 scheduleOutlookPoll(result.retry_in || state.integrations.outlook.interval || 5);
                 if (result.message) {
-                    showCalendarStatus(result.message, false);
+                    state.integrations.outlook.message = result.message;
                 }
+                renderCalendarAuthStatus();
                 break;
             case 'completed':
                 await refreshCalendarIntegrationsStatus({ silent: true });
@@ -478,6 +697,9 @@ async function pollOutlookDeviceFlowOnce() {
             case 'declined':
                 await refreshCalendarIntegrationsStatus({ silent: true });
                 showCalendarStatus(result.message || 'Connessione Outlook non completata', true);
+                if (result.status === 'expired') {
+                    addAssistantResponse('Il link di autorizzazione di Outlook è scaduto. Scrivi "Collega Outlook" per generarne uno nuovo.');
+                }
                 break;
             case 'error':
                 showCalendarStatus(result.message || 'Errore durante il collegamento a Outlook', true);
@@ -511,8 +733,9 @@ async function pollGoogleDeviceFlowOnce() {
             case 'pending':
                 scheduleGooglePoll(result.retry_in || state.integrations.google.interval || 5);
                 if (result.message) {
-                    showCalendarStatus(result.message, false);
+                    state.integrations.google.message = result.message;
                 }
+                renderCalendarAuthStatus();
                 break;
             case 'completed':
                 await refreshCalendarIntegrationsStatus({ silent: true });
@@ -523,6 +746,9 @@ async function pollGoogleDeviceFlowOnce() {
             case 'declined':
                 await refreshCalendarIntegrationsStatus({ silent: true });
                 showCalendarStatus(result.message || 'Connessione Google Calendar non completata', true);
+                if (result.status === 'expired') {
+                    addAssistantResponse('Il link di autorizzazione di Google Calendar è scaduto. Scrivi "Collega Google Calendar" per generarne uno nuovo.');
+                }
                 break;
             case 'error':
                 showCalendarStatus(result.message || 'Errore durante il collegamento a Google Calendar', true);
@@ -634,6 +860,9 @@ async function startOutlookDeviceFlowWithPrompt() {
         ].filter(Boolean).join('\n'));
         showCalendarStatus('In attesa di autorizzazione Outlook');
         scheduleOutlookPoll(flow.interval || 5);
+        if (flow.verification_uri) {
+            await openCalendarAuthorization(flow.verification_uri);
+        }
         return true;
     } catch (error) {
         console.error('Impossibile avviare il device flow Outlook:', error);
@@ -666,6 +895,9 @@ async function startGoogleDeviceFlowWithPrompt() {
         ].filter(Boolean).join('\n'));
         showCalendarStatus('In attesa di autorizzazione Google Calendar');
         scheduleGooglePoll(flow.interval || 5);
+        if (flow.verification_uri) {
+            await openCalendarAuthorization(flow.verification_uri);
+        }
         return true;
     } catch (error) {
         console.error('Impossibile avviare il device flow Google Calendar:', error);
@@ -675,6 +907,93 @@ async function startGoogleDeviceFlowWithPrompt() {
     }
 }
 
+// Checks the saved credentials before opening the browser; false when they
+// are wrong, leaving the conversation waiting for new ones
+async function checkCalendarCredentials(provider, label) {
+    try {
+        const check = await invoke('validate_calendar_credentials', { provider });
+        if (check.valid) {
+            return true;
+        }
+        showCalendarStatus(`Credenziali ${label} non valide`, true);
+        addAssistantResponse(`La configurazione ${label} non sembra valida: ${check.message}.\nCorreggila e inviami di nuovo le credenziali.`);
+        return false;
+    } catch (error) {
+        // Without network the check is skipped: the authorization reports the errors
+        console.warn(`Impossibile verificare le credenziali ${label}:`, error);
+        return true;
+    }
+}
+
+function buildCalendarsSummary(calendars, label) {
+    if (!Array.isArray(calendars) || calendars.length === 0) {
+        return `Non ho trovato calendari su ${label}.`;
+    }
+
+    const lines = [`Calendari disponibili su ${label}:`];
+    calendars.forEach(calendar => {
+        const notes = [
+            calendar.primary ? 'predefinito' : null,
+            calendar.can_edit ? null : 'sola lettura',
+        ].filter(Boolean);
+        const suffix = notes.length > 0 ? ` (${notes.join(', ')})` : '';
+        lines.push(`${calendar.selected ? '✅' : '•'} ${calendar.name}${suffix}`);
+    });
+    lines.push('', `Per cambiarlo scrivi ad esempio "Usa il calendario ${calendars[0].name} su ${label}".`);
+    return lines.join('\n');
+}
+
+// "Calendari Outlook" lists the calendars of the account, "Usa il calendario
+// Lavoro su Outlook" picks the one used to read and create events
+async function handleCalendarSelectionCommand(provider, label, normalized) {
+    const wantsList = /\bcalendari\b/.test(normalized);
+    const wantsSelection = /\busa\b/.test(normalized) && /\bcalendario\b/.test(normalized);
+    if (!wantsList && !wantsSelection) {
+        return false;
+    }
+
+    let calendars;
+    try {
+        calendars = await invoke('list_available_calendars', { provider });
+    } catch (error) {
+        console.error(`Errore durante il recupero dei calendari ${label}:`, error);
+        await refreshCalendarIntegrationsStatus({ silent: true });
+        reportCalendarsToReconnect();
+        addAssistantResponse(`Non riesco a leggere i calendari di ${label}: ${error}`);
+        return true;
+    }
+
+    if (!wantsSelection) {
+        addAssistantResponse(buildCalendarsSummary(calendars, label));
+        return true;
+    }
+
+    // The longest name wins, so "Lavoro condiviso" is preferred to "Lavoro"
+    const wantsDefault = /predefinito|principale/.test(normalized);
+    const chosen = wantsDefault ? null : calendars
+        .filter(calendar => normalized.includes(normalizeTextForMatch(calendar.name)))
+        .sort((a, b) => b.name.length - a.name.length)[0];
+    if (!wantsDefault && !chosen) {
+        addAssistantResponse(`Non ho trovato il calendario indicato.\n${buildCalendarsSummary(calendars, label)}`);
+        return true;
+    }
+
+    try {
+        await invoke('select_calendar', { provider, calendarId: chosen ? chosen.id : null });
+        await refreshCalendarIntegrationsStatus({ silent: true });
+        const name = chosen ? chosen.name : 'predefinito';
+        showCalendarStatus(`Calendario ${label}: ${name}`);
+        addAssistantResponse(`Da ora uso il calendario ${name} di ${label} per leggere e creare gli eventi.`);
+        if (chosen && !chosen.can_edit) {
+            addAssistantResponse('Attenzione: questo calendario è in sola lettura, quindi non potrò aggiungervi eventi.');
+        }
+    } catch (error) {
+        console.error(`Errore durante la scelta del calendario ${label}:`, error);
+        showCalendarStatus('Impossibile salvare il calendario scelto', true);
+    }
+    return true;
+}
+
 async function handleCalendarIntegrationCommand(text) {
     if (!text || typeof text !== 'string') {
         return false;
@@ -694,6 +1013,9 @@ async function handleCalendarIntegrationCommand(text) {
                 clientId: parsed.clientId,
                 tenant: parsed.tenant,
             });
+            if (!await checkCalendarCredentials('outlook', 'Outlook')) {
+                return true;
+            }
             addAssistantResponse('Credenziali Outlook salvate. Avvio la procedura di collegamento...');
             return await startOutlookDeviceFlowWithPrompt();
         } catch (error) {
@@ -717,6 +1039,9 @@ async function handleCalendarIntegrationCommand(text) {
                 clientSecret: parsed.clientSecret || null,
                 calendarId: parsed.calendarId,
             });
+            if (!await checkCalendarCredentials('google', 'Google')) {
+                return true;
+            }
             addAssistantResponse('Credenziali Google salvate. Avvio la procedura di collegamento...');
             return await startGoogleDeviceFlowWithPrompt();
         } catch (error) {
@@ -735,6 +1060,10 @@ async function handleCalendarIntegrationCommand(text) {
     }
 
     if (mentionsGoogle) {
+        if (await handleCalendarSelectionCommand('google', 'Google Calendar', normalized)) {
+            return true;
+        }
+
         if (/disconnetti|scollega|rimuovi|dimentica/.test(normalized)) {
             try {
                 clearGooglePollTimer();
@@ -751,6 +1080,10 @@ async function handleCalendarIntegrationCommand(text) {
 
         if (/mostra|lista|elenca|dammi|visualizza/.test(normalized) && /event/i.test(normalized)) {
             await refreshCalendarIntegrationsStatus({ silent: true });
+            if (state.integrations.google.reconnectRequired) {
+                addAssistantResponse('L\'accesso a Google Calendar è stato revocato o è scaduto. Scrivi "Collega Google Calendar" per autorizzarlo di nuovo.');
+                return true;
+            }
             if (!state.integrations.google.connected) {
                 addAssistantResponse('Google Calendar non risulta collegato. Puoi chiedermi "Collega Google Calendar" per avviare la procedura.');
                 return true;
@@ -760,8 +1093,14 @@ async function handleCalendarIntegrationCommand(text) {
                 addAssistantResponse(buildGoogleEventsSummary(events));
             } catch (error) {
                 console.error('Errore durante il recupero degli eventi Google Calendar:', error);
-                showCalendarStatus('Impossibile ottenere gli eventi Google Calendar', true);
-                addAssistantResponse('Non riesco a leggere gli eventi da Google Calendar in questo momento.');
+                await refreshCalendarIntegrationsStatus({ silent: true });
+                if (state.integrations.google.reconnectRequired) {
+                    reportCalendarsToReconnect();
+                    addAssistantResponse('L\'accesso a Google Calendar è stato revocato o è scaduto. Scrivi "Collega Google Calendar" per autorizzarlo di nuovo.');
+                } else {
+                    showCalendarStatus('Impossibile ottenere gli eventi Google Calendar', true);
+                    addAssistantResponse('Non riesco a leggere gli eventi da Google Calendar in questo momento.');
+                }
             }
             return true;
         }
@@ -821,6 +1160,8 @@ async function handleCalendarIntegrationCommand(text) {
                     ? `Se Google ti chiede un codice, usa: \`${state.integrations.google.userCode}\`.`
                     : '';
                 addAssistantResponse([link, codeFallback].filter(Boolean).join('\n'));
+            } else if (state.integrations.google.reconnectRequired) {
+                addAssistantResponse('L\'accesso a Google Calendar è stato revocato o è scaduto. Scrivi "Collega Google Calendar" per autorizzarlo di nuovo.');
             } else if (state.integrations.google.configured) {
                 addAssistantResponse('Ho le credenziali ma Google Calendar non è ancora autorizzato. Scrivi "Collega Google Calendar" per avviare il codice di autorizzazione.');
             } else {
@@ -842,6 +1183,9 @@ async function handleCalendarIntegrationCommand(text) {
                     clientSecret: parsed.clientSecret || null,
                     calendarId: parsed.calendarId,
                 });
+                if (!await checkCalendarCredentials('google', 'Google')) {
+                    return true;
+                }
                 addAssistantResponse('Ho aggiornato le credenziali Google. Avvio ora il collegamento.');
                 return await startGoogleDeviceFlowWithPrompt();
             } catch (error) {
@@ -856,6 +1200,10 @@ async function handleCalendarIntegrationCommand(text) {
     }
 
     // Outlook flow (invariato)
+    if (await handleCalendarSelectionCommand('outlook', 'Outlook', normalized)) {
+        return true;
+    }
+
     if (/disconnetti|scollega|rimuovi|dimentica/.test(normalized)) {
         try {
             clearOutlookPollTimer();
@@ -872,6 +1220,10 @@ async function handleCalendarIntegrationCommand(text) {
 
     if (/mostra|lista|elenca|dammi|visualizza/.test(normalized) && /event/i.test(normalized)) {
         await refreshCalendarIntegrationsStatus({ silent: true });
+        if (state.integrations.outlook.reconnectRequired) {
+            addAssistantResponse('L\'accesso a Outlook è stato revocato o è scaduto. Scrivi "Collega Outlook" per autorizzarlo di nuovo.');
+            return true;
+        }
         if (!state.integrations.outlook.connected) {
             addAssistantResponse('Outlook non risulta collegato. Puoi chiedermi "Collega Outlook" per avviare la procedura.');
             return true;
@@ -881,8 +1233,14 @@ async function handleCalendarIntegrationCommand(text) {
             addAssistantResponse(buildOutlookEventsSummary(events));
         } catch (error) {
             console.error('Errore durante il recupero degli eventi Outlook:', error);
-            showCalendarStatus('Impossibile ottenere gli eventi Outlook', true);
-            addAssistantResponse('Non riesco a leggere gli eventi da Outlook in questo momento.');
+            await refreshCalendarIntegrationsStatus({ silent: true });
+            if (state.integrations.outlook.reconnectRequired) {
+                reportCalendarsToReconnect();
+                addAssistantResponse('L\'accesso a Outlook è stato revocato o è scaduto. Scrivi "Collega Outlook" per autorizzarlo di nuovo.');
+            } else {
+                showCalendarStatus('Impossibile ottenere gli eventi Outlook', true);
+                addAssistantResponse('Non riesco a leggere gli eventi da Outlook in questo momento.');
+            }
         }
         return true;
     }
@@ -937,6 +1295,8 @@ async function handleCalendarIntegrationCommand(text) {
                 ? `Se Microsoft ti chiede un codice, usa: \`${state.integrations.outlook.userCode}\`.`
                 : '';
             addAssistantResponse([link, codeFallback].filter(Boolean).join('\n'));
+        } else if (state.integrations.outlook.reconnectRequired) {
+            addAssistantResponse('L\'accesso a Outlook è stato revocato o è scaduto. Scrivi "Collega Outlook" per autorizzarlo di nuovo.');
         } else if (state.integrations.outlook.configured) {
             addAssistantResponse('Ho le credenziali ma Outlook non è ancora autorizzato. Scrivi "Collega Outlook" per avviare il codice di autorizzazione.');
         } else {
@@ -957,6 +1317,9 @@ async function handleCalendarIntegrationCommand(text) {
                 clientId: parsed.clientId,
                 tenant: parsed.tenant,
             });
+            if (!await checkCalendarCredentials('outlook', 'Outlook')) {
+                return true;
+            }
             addAssistantResponse('Ho aggiornato le credenziali Outlook. Avvio ora il collegamento.');
             return await startOutlookDeviceFlowWithPrompt();
         } catch (error) {
@@ -1000,8 +1363,14 @@ function formatEventDateRange(startIso, endIso) {
 function renderCalendarList() {
     if (!elements.calendarList) return;
 
+    // Recurring events are shown at their next occurrence
     const events = Array.isArray(state.calendarEvents)
-        ? [...state.calendarEvents].sort((a, b) => new Date(a.start) - new Date(b.start))
+        ? state.calendarEvents
+            .map(event => {
+                const next = state.calendarOccurrences[event.id]?.[0];
+                return next ? { ...event, start: next.start, end: next.end } : event;
+            })
+            .sort((a, b) => new Date(a.start) - new Date(b.start))
         : [];
 
     if (elements.clearCalendarBtn) {
@@ -1014,8 +1383,8 @@ function renderCalendarList() {
     if (events.length === 0) {
         elements.calendarList.innerHTML = `
             <div class="empty-calendar">
-                <p>Nessun evento registrato</p>
-                <small>Quando segnali un impegno, verrà aggiunto automaticamente qui</small>
+                <p>${t('calendar.empty')}</p>
+                <small>${t('calendar.emptyHint')}</small>
             </div>
         `;
         return;
@@ -1024,6 +1393,11 @@ function renderCalendarList() {
     elements.calendarList.innerHTML = events
         .map(event => {
             const description = (event.description || '').trim();
+            const meta = [
+                formatRecurrence(event),
+                event.location ? `📍 ${event.location}` : '',
+                event.reminder_minutes != null ? `🔔 ${event.reminder_minutes} min prima` : '',
+            ].filter(Boolean).join(' · ');
             return `
                 <div class="calendar-event" data-id="${event.id}">
                     <div class="calendar-event-header">
@@ -1031,6 +1405,7 @@ function renderCalendarList() {
                         <div class="calendar-event-date">${escapeHtml(formatEventDateRange(event.start, event.end))}</div>
                     </div>
                     ${description ? `<div class="calendar-event-details">${escapeHtml(description)}</div>` : ''}
+                    ${meta ? `<div class="calendar-event-details">${escapeHtml(meta)}</div>` : ''}
                     <div class="calendar-event-actions">
                         <button class="calendar-event-delete" data-id="${event.id}" title="Elimina evento">Elimina</button>
                     </div>
@@ -1050,10 +1425,32 @@ function renderCalendarList() {
     });
 }
 
+function formatRecurrence(event) {
+    const rule = event.recurrence;
+    if (!rule || !RECURRENCE_LABELS[rule.frequency]) return '';
+
+    const [single, plural] = RECURRENCE_LABELS[rule.frequency];
+    const every = rule.interval > 1 ? `ogni ${rule.interval} ${plural}` : single;
+    const upcoming = (state.calendarOccurrences[event.id] || [])
+        .slice(1, 4)
+        .map(occurrence => new Date(occurrence.start).toLocaleDateString(undefined, { day: '2-digit', month: 'short' }));
+    return `🔁 ${every}${upcoming.length ? ` · poi ${upcoming.join(', ')}` : ''}`;
+}
+
 async function loadCalendarEventsFromStore() {
     try {
         const events = await invoke('load_calendar_events');
         state.calendarEvents = Array.isArray(events) ? events : [];
+
+        const from = new Date();
+        const to = new Date(from.getTime() + CALENDAR_OCCURRENCES_DAYS * 24 * 60 * 60 * 1000);
+        const occurrences = await invoke('get_calendar_occurrences', { from: from.toISOString(), to: to.toISOString() });
+        state.calendarOccurrences = {};
+        for (const occurrence of occurrences) {
+            const event = state.calendarEvents.find(e => e.id === occurrence.event_id);
+            if (!event?.recurrence) continue;
+            (state.calendarOccurrences[occurrence.event_id] ||= []).push(occurrence);
+        }
     } catch (error) {
         console.warn('Impossibile caricare il calendario:', error);
         state.calendarEvents = [];
@@ -1090,6 +1487,30 @@ async function clearAllCalendarEvents() {
     }
 }
 
+function updateCalendarNotificationsButton() {
+    if (!elements.calendarNotificationsBtn) return;
+    const enabled = state.appSettings.event_notifications.enabled !== false;
+    elements.calendarNotificationsBtn.textContent = enabled ? '🔔' : '🔕';
+    elements.calendarNotificationsBtn.title = enabled
+        ? 'Notifiche degli eventi attive: clicca per disattivarle'
+        : 'Notifiche degli eventi disattivate: clicca per attivarle';
+}
+
+async function toggleEventNotifications() {
+    const enabled = state.appSettings.event_notifications.enabled === false;
+    try {
+        state.appSettings.event_notifications = await invoke('set_event_notifications', { enabled });
+        if (elements.eventNotificationsEnabled) {
+            elements.eventNotificationsEnabled.checked = enabled;
+        }
+        updateCalendarNotificationsButton();
+        showCalendarStatus(enabled ? 'Notifiche degli eventi attivate' : 'Notifiche degli eventi disattivate');
+    } catch (error) {
+        console.warn('Impossibile aggiornare le notifiche degli eventi:', error);
+        showCalendarStatus('Errore durante l\'aggiornamento delle notifiche', true);
+    }
+}
+
 async function exportCalendarAsIcs() {
     try {
         const path = await invoke('export_calendar_to_ics');
@@ -1100,172 +1521,123 @@ async function exportCalendarAsIcs() {
     }
 }
 
-function getNextWeekday(baseDate, targetWeekdayIndex) {
-    const date = new Date(baseDate.getTime());
-    const currentIndex = date.getDay();
-    let diff = targetWeekdayIndex - currentIndex;
-    if (diff <= 0) {
-        diff += 7;
-    }
-    date.setDate(date.getDate() + diff);
-    return date;
-}
-
-function parseFullDate(day, monthIndex, year, reference) {
-    const date = new Date(reference.getTime());
-    date.setHours(9, 0, 0, 0);
-    date.setMonth(monthIndex);
-    date.setDate(day);
-    if (typeof year === 'number') {
-        const normalizedYear = year < 100 ? 2000 + year : year;
-        date.setFullYear(normalizedYear);
-    }
-
-    if (typeof year !== 'number' && date < reference) {
-        date.setFullYear(date.getFullYear() + 1);
-    }
-
-    if (date < new Date(reference.getTime() - 24 * 60 * 60 * 1000)) {
-        return null;
-    }
-
-    return date;
+// Value of a datetime-local input, in local time
+function toDateTimeLocalValue(iso) {
+    const date = new Date(iso);
+    const pad = value => String(value).padStart(2, '0');
+    return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}T${pad(date.getHours())}:${pad(date.getMinutes())}`;
 }
 
-function applyTimeHints(date, sentence) {
-    if (!date) return null;
-
-    const timeMatch = sentence.match(/(?:alle|ore|h)\s*(\d{1,2})(?:[:.](\d{2}))?/i);
-    if (timeMatch) {
-        const hours = Math.min(23, parseInt(timeMatch[1], 10));
-        const minutes = timeMatch[2] ? Math.min(59, parseInt(timeMatch[2], 10)) : 0;
-        date.setHours(hours, minutes, 0, 0);
-        return date;
-    }
-
-    if (/mattin/i.test(sentence)) {
-        date.setHours(9, 0, 0, 0);
-    } else if (/pomeriggio/i.test(sentence)) {
-        date.setHours(15, 0, 0, 0);
-    } else if (/sera|stasera/i.test(sentence)) {
-        date.setHours(19, 0, 0, 0);
-    } else if (/notte|stanotte/i.test(sentence)) {
-        date.setHours(22, 0, 0, 0);
-    } else {
-        date.setHours(9, 0, 0, 0);
-    }
-
-    return date;
-}
+// Editable card for an event recognized in a text, added to the calendar only when confirmed
+function createEventSuggestion(event, onClose = () => {}) {
+    const card = document.createElement('div');
+    card.className = 'event-suggestion';
+    card.innerHTML = `
+        <input type="text" class="event-suggestion-title" aria-label="Titolo">
+        <div class="event-suggestion-times">
+            <input type="datetime-local" class="event-suggestion-start" value="${toDateTimeLocalValue(event.start)}" aria-label="Inizio">
+            <input type="datetime-local" class="event-suggestion-end" value="${event.end ? toDateTimeLocalValue(event.end) : ''}" aria-label="Fine">
+        </div>
+        <select class="event-suggestion-recurrence" aria-label="Ripetizione">
+            <option value="">Non si ripete</option>
+            <option value="daily">Ogni giorno</option>
+            <option value="weekly">Ogni settimana</option>
+            <option value="monthly">Ogni mese</option>
+        </select>
+        <div class="event-suggestion-actions">
+            <button type="button" class="calendar-btn event-suggestion-add">Aggiungi al calendario</button>
+            <button type="button" class="calendar-btn event-suggestion-dismiss">Ignora</button>
+        </div>
+    `;
+    card.querySelector('.event-suggestion-title').value = event.title;
+    card.querySelector('.event-suggestion-recurrence').value = event.recurrence?.frequency || '';
 
-function extractDateFromSentence(sentence, reference) {
-    const normalized = sentence.toLowerCase();
-    const accentless = normalized.normalize('NFD').replace(/[\u0300-\u036f]/g, '');
-    let date = null;
+    const close = added => {
+        card.remove();
+        onClose(added);
+    };
 
-    for (const [keyword, offset] of Object.entries(RELATIVE_KEYWORDS)) {
-        if (accentless.includes(keyword)) {
-            const candidate = new Date(reference.getTime());
-            candidate.setDate(candidate.getDate() + offset);
-            date = candidate;
-            break;
+    card.querySelector('.event-suggestion-add').addEventListener('click', async () => {
+        const title = card.querySelector('.event-suggestion-title').value.trim();
+        const start = card.querySelector('.event-suggestion-start').value;
+        const end = card.querySelector('.event-suggestion-end').value;
+        const frequency = card.querySelector('.event-suggestion-recurrence').value;
+        if (!title || !start) {
+            showCalendarStatus('Indica almeno titolo e inizio dell\'evento', true);
+            return;
         }
-    }
-
-    if (!date) {
-        WEEK_DAYS_IT.forEach((weekday, index) => {
-            if (date) return;
-            const accented = weekday;
-            const plain = weekday.normalize('NFD').replace(/[\u0300-\u036f]/g, '');
-            if (normalized.includes(accented) || accentless.includes(plain)) {
-                date = getNextWeekday(reference, index);
-            }
+        const added = await confirmCalendarEvent({
+            ...event,
+            title,
+            start: new Date(start).toISOString(),
+            end: end ? new Date(end).toISOString() : null,
+            recurrence: frequency ? { frequency, interval: 1 } : null,
         });
-    }
-
-    if (!date) {
-        const monthRegex = /(?:il\s+)?(\d{1,2})\s+(gennaio|febbraio|marzo|aprile|maggio|giugno|luglio|agosto|settembre|ottobre|novembre|dicembre)(?:\s+(\d{2,4}))?/i;
-        const match = sentence.match(monthRegex);
-        if (match) {
-            const day = parseInt(match[1], 10);
-            const monthName = match[2].toLowerCase();
-            const year = match[3] ? parseInt(match[3], 10) : undefined;
-            const monthIndex = MONTHS_IT[monthName];
-            if (Number.isInteger(monthIndex)) {
-                date = parseFullDate(day, monthIndex, year, reference);
-            }
-        }
-    }
+        if (added) close(true);
+    });
+    card.querySelector('.event-suggestion-dismiss').addEventListener('click', () => close(false));
 
-    if (!date) {
-        const numericRegex = /(?:il\s+)?(\d{1,2})[\/.\-](\d{1,2})(?:[\/.\-](\d{2,4}))?/;
-        const match = sentence.match(numericRegex);
-        if (match) {
-            const day = parseInt(match[1], 10);
-            const month = parseInt(match[2], 10) - 1;
-            const year = match[3] ? parseInt(match[3], 10) : undefined;
-            date = parseFullDate(day, month, year, reference);
-        }
-    }
+    return card;
+}
 
-    if (date) {
-        return applyTimeHints(date, sentence);
+async function confirmCalendarEvent(event) {
+    try {
+        const eventId = await invoke('add_calendar_event', {
+            event: {
+                title: event.title,
+                description: event.description || null,
+                start: event.start,
+                end: event.end,
+                source_text: event.source_text || null,
+                location: event.location || null,
+                reminder_minutes: event.reminder_minutes ?? DEFAULT_EVENT_REMINDER_MINUTES,
+                recurrence: event.recurrence || null,
+            },
+        });
+        await syncEventToIntegrations(eventId);
+        await loadCalendarEventsFromStore();
+        showCalendarStatus('Evento aggiunto al calendario');
+        return true;
+    } catch (error) {
+        console.warn('Impossibile aggiungere evento al calendario:', error);
+        showCalendarStatus('Errore durante il salvataggio di un evento', true);
+        return false;
     }
-
-    return null;
 }
 
-function deriveEventTitle(sentence) {
-    const cleaned = sentence
-        .replace(/(?:io|noi)\s+/, '')
-        .replace(/(?:devo|dovr[oò]|avr[oò]|ho|ci sarà|c'?è)\s+/i, '')
-        .trim();
-    const noTrailing = cleaned.replace(/[.?!]+$/, '').trim();
-    if (noTrailing.length <= 0) {
-        return 'Impegno personale';
+function toggleCalendarQuickAdd() {
+    if (!elements.calendarQuickAdd) return;
+    const hidden = elements.calendarQuickAdd.classList.toggle('hidden');
+    if (!hidden) {
+        elements.calendarQuickText?.focus();
     }
-    if (noTrailing.length <= 60) {
-        return noTrailing.charAt(0).toUpperCase() + noTrailing.slice(1);
-    }
-    return `${noTrailing.slice(0, 57).trimEnd()}…`;
 }
 
-function detectCalendarEntries(text) {
-    if (!text || typeof text !== 'string') return [];
-
-    const reference = new Date();
-    const sentences = text
-        .split(/[.!?\n]+/)
-        .map(sentence => sentence.trim())
-        .filter(Boolean);
-
-    const triggers = /(ho|avr[oò]|devo|parteciper[oò]|partecipo|vado|ci sara|ci sarà|organizzo|ricordami|programma|prenotato|inizia|comincia|allenamento|gara|riunione)/i;
-
-    const candidates = [];
-
-    sentences.forEach(sentence => {
-        if (!triggers.test(sentence)) return;
-        const date = extractDateFromSentence(sentence, reference);
-        if (!date) return;
-
-        const start = new Date(date.getTime());
-        const end = new Date(date.getTime() + 60 * 60 * 1000);
-
-        candidates.push({
-            title: deriveEventTitle(sentence),
-            description: sentence,
-            startIso: start.toISOString(),
-            endIso: end.toISOString(),
-            source: sentence,
-        });
-    });
+async function parseCalendarQuickText() {
+    const text = elements.calendarQuickText?.value.trim();
+    if (!text) return;
 
-    return candidates;
+    try {
+        const event = await invoke('parse_event_from_text', { text });
+        if (!event) {
+            showCalendarStatus('Nessuna data o ora riconosciuta nel testo', true);
+            return;
+        }
+        elements.calendarQuickPreview.replaceChildren(createEventSuggestion(event, added => {
+            if (added) {
+                elements.calendarQuickText.value = '';
+                elements.calendarQuickAdd.classList.add('hidden');
+            }
+        }));
+    } catch (error) {
+        console.warn('Impossibile interpretare il testo:', error);
+        showCalendarStatus('Impossibile interpretare il testo', true);
+    }
 }
 
 function isDuplicateCalendarEvent(candidate) {
     const candidateTitle = normalizeTitleForComparison(candidate.title);
-    const candidateTime = new Date(candidate.startIso).getTime();
+    const candidateTime = new Date(candidate.start).getTime();
 
     return state.calendarEvents.some(event => {
         const storedTitle = normalizeTitleForComparison(event.title);
@@ -1288,44 +1660,35 @@ async function syncEventToIntegrations(eventId) {
         await invoke('sync_calendar_event_to_integrations', { id: eventId });
     } catch (error) {
         console.warn('Impossibile sincronizzare l\'evento con le integrazioni calendario:', error);
-        showCalendarStatus('Evento salvato localmente ma non sincronizzato con il calendario remoto', true);
+        await refreshCalendarIntegrationsStatus({ silent: true });
+        if (!reportCalendarsToReconnect()) {
+            showCalendarStatus('Evento salvato localmente ma non sincronizzato con il calendario remoto', true);
+        }
     }
 }
 
-async function autoCaptureCalendarEvents(text) {
-    if (!text || text.length < 6) return;
-
-    const candidates = detectCalendarEntries(text);
-    if (candidates.length === 0) return;
-
-    let added = 0;
-
-    for (const candidate of candidates) {
-        if (isDuplicateCalendarEvent(candidate)) {
-            continue;
-        }
-
-        try {
-            const eventId = await invoke('add_calendar_event', {
-                event: {
-                    title: candidate.title,
-                    description: candidate.description,
-                    start: candidate.startIso,
-                    end: candidate.endIso,
-                    source_text: candidate.source,
-                },
-            });
-            added += 1;
-            await syncEventToIntegrations(eventId);
-        } catch (error) {
-            console.warn('Impossibile aggiungere evento al calendario:', error);
-            showCalendarStatus('Errore durante il salvataggio di un evento', true);
-        }
-    }
+// Offers to add an appointment mentioned in a user message, below the message itself
+async function suggestCalendarEvent(text, messageDiv) {
+    if (!text || state.appSettings.suggest_calendar_events === false) return;
 
-    if (added > 0) {
-        await loadCalendarEventsFromStore();
-        showCalendarStatus(added === 1 ? 'Aggiunto 1 evento al calendario' : `Aggiunti ${added} eventi al calendario`);
+    try {
+        const event = await invoke('detect_calendar_event', { text });
+        if (!event || isDuplicateCalendarEvent(event)) return;
+
+        const suggestion = document.createElement('div');
+        suggestion.className = 'calendar-suggestion';
+        suggestion.innerHTML = `
+            <span>📅 Aggiungere al calendario?</span>
+            <button type="button" class="calendar-btn calendar-suggestion-open">${escapeHtml(event.title)} · ${escapeHtml(formatEventDateRange(event.start, event.end))}</button>
+            <button type="button" class="calendar-suggestion-dismiss" title="Ignora">✕</button>
+        `;
+        suggestion.querySelector('.calendar-suggestion-open').addEventListener('click', () => {
+            suggestion.replaceWith(createEventSuggestion(event));
+        });
+        suggestion.querySelector('.calendar-suggestion-dismiss').addEventListener('click', () => suggestion.remove());
+        messageDiv?.querySelector('.message-bubble')?.appendChild(suggestion);
+    } catch (error) {
+        console.warn('Impossibile analizzare il messaggio per il calendario:', error);
     }
 }
 
@@ -1392,9 +1755,72 @@ function hideError() {
 }
 
 function getTimestamp() {
-    const now = new Date();
-    const locale = (typeof navigator !== 'undefined' && navigator.language) ? navigator.language : 'it-IT';
-    return now.toLocaleTimeString(locale, { hour: '2-digit', minute: '2-digit' });
+    return new Date().toISOString();
+}
+
+function getDisplayLocale() {
+    return (typeof navigator !== 'undefined' && navigator.language) ? navigator.language : 'it-IT';
+}
+
+// Timestamps are stored as ISO 8601; older conversations only have "HH:MM"
+function parseTimestamp(value) {
+    if (!value || typeof value !== 'string' || /^\d{1,2}:\d{2}$/.test(value.trim())) {
+        return null;
+    }
+    const date = new Date(value);
+    return Number.isNaN(date.getTime()) ? null : date;
+}
+
+function formatTimestamp(value) {
+    const date = parseTimestamp(value);
+    if (!date) {
+        return value || '';
+    }
+    return date.toLocaleTimeString(getDisplayLocale(), {
+        hour: '2-digit',
+        minute: '2-digit',
+        hour12: state.appSettings.time_format === '12h',
+    });
+}
+
+function getDayKey(date) {
+    return `${date.getFullYear()}-${date.getMonth()}-${date.getDate()}`;
+}
+
+function formatDaySeparator(date) {
+    const today = new Date();
+    today.setHours(0, 0, 0, 0);
+    const day = new Date(date.getTime());
+    day.setHours(0, 0, 0, 0);
+
+    const diffDays = Math.round((today - day) / (24 * 60 * 60 * 1000));
+    if (diffDays === 0) return 'Oggi';
+    if (diffDays === 1) return 'Ieri';
+
+    const options = { day: 'numeric', month: 'short' };
+    if (day.getFullYear() !== today.getFullYear()) {
+        options.year = 'numeric';
+    }
+    return day.toLocaleDateString(getDisplayLocale(), options);
+}
+
+// Insert a "— Oggi —" style separator when the day changes between messages
+function addDaySeparatorIfNeeded(timestamp) {
+    const date = parseTimestamp(timestamp);
+    if (!date) return null;
+
+    const dayKey = getDayKey(date);
+    const previous = elements.messages.querySelectorAll('[data-day]');
+    const lastDay = previous.length > 0 ? previous[previous.length - 1].dataset.day : null;
+
+    if (lastDay !== dayKey) {
+        const separator = document.createElement('div');
+        separator.className = 'date-separator';
+        separator.textContent = `— ${formatDaySeparator(date)} —`;
+        elements.messages.appendChild(separator);
+    }
+
+    return dayKey;
 }
 
 function escapeHtml(text) {
@@ -1628,6 +2054,26 @@ async function loadVersionIndicator() {
     }
 }
 
+// UI language from the system locale, then the webview languages
+async function loadUiLanguage() {
+    const candidates = [];
+    try {
+        const profile = await invoke('get_user_profile');
+        if (profile?.primary_language) {
+            candidates.push(profile.primary_language);
+        }
+    } catch (error) {
+        console.warn('Impossibile leggere la lingua di sistema:', error);
+    }
+
+    if (typeof navigator !== 'undefined') {
+        candidates.push(...(navigator.languages || [navigator.language]));
+    }
+
+    setLanguage(candidates);
+    applyTranslations();
+}
+
 async function loadGreeting() {
     if (!elements.greetingBanner) {
         return;
@@ -1688,65 +2134,139 @@ async function loadGreeting() {
     }
 }
 
-// Check GitHub releases for Windows updates and prompt the user when available.
-async function checkForUpdates() {
-    try {
-        const result = await invoke('check_for_updates');
-        if (!result || !result.status) return;
+// Check GitHub releases for updates and show a non-blocking banner when available.
+// At startup the backend honours the user setting and checks at most once a day.
+async function checkForUpdates(options = {}) {
+    const { manual = false } = options;
 
-        if (result.status === 'unsupported' || result.status === 'up_to_date') {
-            return;
-        }
+    try {
+        const result = await invoke(manual ? 'check_for_updates' : 'check_for_updates_on_startup');
+        if (!result || !result.status) return result;
 
         if (result.status === 'error') {
             console.warn('Update check error:', result.message);
-            return;
+            return result;
         }
 
         if (result.status === 'update_available') {
-            const latestVersion = result.latest_version;
-            const currentVersion = result.current_version;
-            const downloadUrl = result.download_url;
-
-            if (!downloadUrl) {
+            if (!result.download_url) {
                 console.warn('Nessun URL di download disponibile per l\'aggiornamento.');
-                return;
+                return result;
             }
+            showUpdateBanner(result);
+        }
 
-            const confirmUpdate = window.confirm(
-                `È disponibile una nuova versione (${latestVersion}).\nVersione corrente: ${currentVersion}.\nVuoi installare l'aggiornamento ora?`
-            );
+        return result;
+    } catch (error) {
+        console.warn('Controllo aggiornamenti non riuscito:', error);
+        return null;
+    }
+}
 
-            if (!confirmUpdate) {
-                return;
-            }
+function showUpdateBanner(update) {
+    if (!elements.updateBanner) return;
 
-            try {
-                await invoke('download_and_install_update', { url: downloadUrl, version: latestVersion });
-                window.alert('Installazione avviata. L\'applicazione verrà chiusa per completare l\'aggiornamento.');
+    state.availableUpdate = update;
+    elements.updateBannerTitle.textContent =
+        `È disponibile MatePro ${update.latest_version} (versione corrente: ${update.current_version})`;
 
-                if (appWindow && typeof appWindow.close === 'function') {
-                    await appWindow.close();
-                } else {
-                    window.close();
-                }
-            } catch (error) {
-                showError(`Errore durante l'installazione dell'aggiornamento: ${error}`);
-            }
-        }
-    } catch (error) {
-        console.warn('Controllo aggiornamenti non riuscito:', error);
+    if (update.release_notes && elements.updateNotes) {
+        elements.updateNotesContent.innerHTML = formatMessage(update.release_notes);
+        elements.updateNotes.classList.remove('hidden');
+    } else if (elements.updateNotes) {
+        elements.updateNotes.classList.add('hidden');
     }
+
+    elements.updateNowBtn.disabled = false;
+    elements.updateBanner.classList.remove('hidden');
 }
 
-// ============ NETWORK SCAN ============
+function hideUpdateBanner() {
+    elements.updateBanner?.classList.add('hidden');
+}
 
-function updateAiConnectStatus(found, services) {
-    state.aiconnectFound = found;
-    state.aiconnectServices = services;
-    
-    if (elements.aiconnectStatus) {
-        if (found) {
+async function installAvailableUpdate() {
+    const update = state.availableUpdate;
+    if (!update) return;
+
+    let allowUnverified = false;
+    if (!update.checksum_url) {
+        allowUnverified = window.confirm(
+            'Questa release non include un checksum SHA256 e l\'integrità del file non può essere verificata.\nVuoi installarla comunque?'
+        );
+        if (!allowUnverified) {
+            return;
+        }
+    }
+
+    elements.updateNowBtn.disabled = true;
+    elements.updateBannerTitle.textContent = `Download di MatePro ${update.latest_version} in corso...`;
+
+    try {
+        const outcome = await invoke('download_and_install_update', {
+            url: update.download_url,
+            version: update.latest_version,
+            checksumUrl: update.checksum_url || null,
+            allowUnverified,
+        });
+        hideUpdateBanner();
+        window.alert(outcome?.message || 'Aggiornamento scaricato.');
+
+        if (!outcome?.close_app) {
+            return;
+        }
+
+        if (appWindow && typeof appWindow.close === 'function') {
+            await appWindow.close();
+        } else {
+            window.close();
+        }
+    } catch (error) {
+        showUpdateBanner(update);
+        showError(`Errore durante l'installazione dell'aggiornamento: ${error}`);
+    }
+}
+
+async function checkForUpdatesManually() {
+    if (elements.checkUpdatesBtn) {
+        elements.checkUpdatesBtn.disabled = true;
+    }
+
+    const result = await checkForUpdates({ manual: true });
+    let message = '✕ Impossibile controllare gli aggiornamenti';
+    let className = 'sql-status error';
+
+    if (result?.status === 'up_to_date') {
+        message = `✓ MatePro è aggiornato (v${result.current_version})`;
+        className = 'sql-status success';
+    } else if (result?.status === 'update_available') {
+        message = `✓ Nuova versione disponibile: ${result.latest_version}`;
+        className = 'sql-status success';
+    } else if (result?.status === 'unsupported') {
+        message = 'Aggiornamenti automatici non supportati su questa piattaforma';
+    } else if (result?.status === 'error' && result.message) {
+        message = `✕ ${result.message}`;
+    }
+
+    if (elements.settingsStatus) {
+        elements.settingsStatus.className = className;
+        elements.settingsStatus.textContent = message;
+        elements.settingsStatus.classList.remove('hidden');
+    }
+
+    if (elements.checkUpdatesBtn) {
+        elements.checkUpdatesBtn.disabled = false;
+    }
+}
+
+// ============ NETWORK SCAN ============
+
+function updateAiConnectStatus(found, services) {
+    state.aiconnectFound = found;
+    state.aiconnectServices = services;
+    
+    if (elements.aiconnectStatus) {
+        if (found) {
             elements.aiconnectStatus.textContent = '🤖 AIConnect trovato';
             elements.aiconnectStatus.className = 'aiconnect-status found';
             elements.aiconnectStatus.classList.remove('hidden');
@@ -1768,11 +2288,65 @@ function updateBackendIndicator() {
         }
         elements.backendIndicator.classList.remove('hidden');
     }
+
+    const isAiConnect = state.backendKind === 'ai_connect';
+    if (elements.nodesBtn) {
+        elements.nodesBtn.classList.toggle('hidden', !isAiConnect);
+    }
+    if (elements.nodeSelector) {
+        elements.nodeSelector.classList.toggle('hidden', !isAiConnect);
+    }
+    if (!isAiConnect) {
+        state.selectedNode = null;
+        state.aiconnectNodes = [];
+    }
+}
+
+// Shows the subnet scan progress and the servers found so far; the final list
+// is rebuilt by scanNetwork when the scan completes
+function renderScanProgress(progress) {
+    if (elements.scanningIndicator.classList.contains('hidden')) {
+        return;
+    }
+
+    elements.scanProgress.max = progress.total;
+    elements.scanProgress.value = progress.checked;
+    elements.scanProgress.classList.remove('hidden');
+    elements.scanProgressText.textContent = t('setup.scanProgress', {
+        checked: progress.checked,
+        total: progress.total,
+        found: progress.found.length,
+    });
+
+    if (progress.found.length === 0) {
+        return;
+    }
+
+    elements.servers.innerHTML = '';
+    progress.found.forEach(server => {
+        const isLocal = server.includes('localhost') || server.includes('127.0.0.1');
+        const option = document.createElement('div');
+        option.className = 'server-option';
+        option.textContent = `${isLocal ? '🏠' : '🌐'} ${server}`;
+        option.dataset.url = server;
+        if (server === elements.serverUrl.value) {
+            option.classList.add('selected');
+        }
+        option.addEventListener('click', () => {
+            document.querySelectorAll('.server-option').forEach(el => el.classList.remove('selected'));
+            option.classList.add('selected');
+            elements.serverUrl.value = server;
+        });
+        elements.servers.appendChild(option);
+    });
+    elements.serverList.classList.remove('hidden');
 }
 
 async function scanNetwork() {
     elements.scanningIndicator.classList.remove('hidden');
     elements.serverList.classList.add('hidden');
+    elements.scanProgress.classList.add('hidden');
+    elements.scanProgressText.textContent = '';
     
     try {
         // Try the new scan_services command first (AIConnect + Ollama)
@@ -1795,6 +2369,7 @@ async function scanNetwork() {
             
             // Build server list
             const servers = [];
+            const serverSources = {};
             
             // Add AIConnect services first if found
             if (discoveryResult.aiconnect_found && discoveryResult.aiconnect_services.length > 0) {
@@ -1806,10 +2381,11 @@ async function scanNetwork() {
                 });
             }
             
-            // Add Ollama servers
+            // Add Ollama servers (already deduplicated by address in the backend)
             discoveryResult.ollama_servers.forEach(server => {
-                if (!servers.includes(server)) {
-                    servers.push(server);
+                if (!servers.includes(server.url)) {
+                    servers.push(server.url);
+                    serverSources[server.url] = server.sources;
                 }
             });
             
@@ -1831,6 +2407,16 @@ async function scanNetwork() {
                     option.textContent = `${icon} ${server}`;
                     option.dataset.url = server;
                     option.dataset.isAiconnect = isAiConnect ? 'true' : 'false';
+
+                    const sources = serverSources[server];
+                    if (sources && sources.length > 0) {
+                        const sourceLabel = document.createElement('span');
+                        sourceLabel.className = 'server-source';
+                        sourceLabel.textContent = sources
+                            .map(source => (source === 'mdns' ? 'mDNS' : 'scan'))
+                            .join(' + ');
+                        option.appendChild(sourceLabel);
+                    }
                     
                     if (server === elements.serverUrl.value || index === 0) {
                         option.classList.add('selected');
@@ -1892,11 +2478,21 @@ async function scanNetwork() {
 // ============ CONNECTION ============
 
 async function connect() {
-    const url = elements.serverUrl.value.trim();
-    if (!url) {
-        showError('Inserisci un URL valido');
+    const input = elements.serverUrl.value.trim();
+    if (!input) {
+        showError(t('connect.invalidUrl'));
         return;
     }
+
+    // "localhost:11434" or a trailing slash are fixed up, nonsense is refused
+    let url;
+    try {
+        url = await invoke('normalize_ollama_url', { url: input });
+    } catch (error) {
+        showError(error);
+        return;
+    }
+    elements.serverUrl.value = url;
     
     hideError();
     elements.connectBtn.disabled = true;
@@ -1906,9 +2502,9 @@ async function connect() {
     const isAiConnect = state.backendKind === 'ai_connect';
     
     if (isAiConnect) {
-        elements.loadingText.textContent = 'Connessione ad AIConnect...';
+        elements.loadingText.textContent = t('connect.aiconnect');
     } else {
-        elements.loadingText.textContent = 'Connessione al server...';
+        elements.loadingText.textContent = t('connect.server');
     }
     
     try {
@@ -1926,7 +2522,12 @@ async function connect() {
             console.log('set_backend_config not available, using legacy connect');
         }
         
-        await invoke('connect_to_server', { url });
+        try {
+            await invoke('connect_to_server', { url });
+        } catch (error) {
+            console.warn('Connessione a Ollama non riuscita:', error);
+            throw await describeConnectionFailure(url);
+        }
         await loadModels();
         
         // Update backend indicator after successful connection
@@ -1938,15 +2539,28 @@ async function connect() {
     }
 }
 
-async function loadModels() {
-    elements.loadingText.textContent = 'Caricamento modelli...';
+// Error message for a failed connection, with the reason found by the backend test
+async function describeConnectionFailure(url) {
+    try {
+        const diagnostic = await invoke('diagnose_backend_connection');
+        if (!diagnostic.ok) {
+            return t('connect.failedReason', { url, reason: diagnostic.message });
+        }
+    } catch (error) {
+        console.warn('Diagnostica connessione non disponibile:', error);
+    }
+    return t('connect.failed', { url });
+}
+
+async function loadModels(forceRefresh = false) {
+    elements.loadingText.textContent = t('models.loading');
     
     try {
-        const models = await invoke('list_models');
+        const models = await invoke('list_models', { forceRefresh });
         
         if (models.length === 0) {
             showScreen('setup-screen');
-            showError('Nessun modello disponibile. Scarica un modello con "ollama pull <model>"');
+            showError(t('models.none'));
             elements.connectBtn.disabled = false;
             return;
         }
@@ -1958,16 +2572,31 @@ async function loadModels() {
             const option = document.createElement('option');
             option.value = model.name;
             const indicator = model.category === 'light' ? '🟢' : model.category === 'medium' ? '🟡' : '🔴';
-            option.textContent = `${indicator} ${model.name} (${model.size_gb.toFixed(1)} GB)`;
+            const fit = model.gpu_fit ? ` · ${t(`models.fit.${model.gpu_fit}`)}` : '';
+            option.textContent = `${indicator} ${model.name} (${model.size_gb.toFixed(1)} GB)${fit}`;
+            if (model.gpu_fit) {
+                option.title = t(`models.fitHint.${model.gpu_fit}`);
+            }
             elements.modelSelector.appendChild(option);
         });
         
-        state.selectedModel = models[0].name;
+        // Keep the model used last time when the server still has it
+        const preferred = models.find(model => model.name === state.lastSelectedModel);
+        state.selectedModel = preferred ? preferred.name : models[0].name;
+        elements.modelSelector.value = state.selectedModel;
+        saveLastBackend();
+        if (state.backendKind === 'ai_connect') {
+            loadNodeSelector();
+        }
         showScreen('chat-screen');
         await loadMemory();
         renderHistoryList();
         elements.connectBtn.disabled = false;
 
+        if (await offerDraftRestore()) {
+            state.greetingShown = true;
+        }
+
         if (state.greetingMessage && !state.greetingShown) {
             addMessage('system', state.greetingMessage, getTimestamp());
             state.greetingShown = true;
@@ -1980,6 +2609,67 @@ async function loadModels() {
     }
 }
 
+// Reconnects at startup to the backend used last time. Returns false when there is
+// none or it is no longer reachable, so the caller falls back to the network scan.
+async function reconnectLastBackend() {
+    let last;
+    try {
+        last = await invoke('load_last_backend');
+    } catch (error) {
+        console.warn('Impossibile leggere l\'ultimo backend utilizzato:', error);
+        return false;
+    }
+    if (!last) return false;
+
+    // The token or password is not saved on disk: ask for it again
+    const auth = last.backend.auth;
+    if (auth?.bearer) {
+        const token = prompt(t('connect.askToken', { url: last.backend.endpoint }));
+        if (!token) return false;
+        auth.bearer.token = token;
+    } else if (auth?.basic) {
+        const password = prompt(t('connect.askPassword', {
+            url: last.backend.endpoint,
+            username: auth.basic.username,
+        }));
+        if (!password) return false;
+        auth.basic.password = password;
+    }
+
+    state.lastSelectedModel = last.selected_model;
+    elements.serverUrl.value = last.backend.endpoint;
+    showScreen('loading-screen');
+    elements.loadingText.textContent = t('connect.lastBackend', { url: last.backend.endpoint });
+
+    try {
+        await invoke('set_backend_config', { config: last.backend });
+        if (!(await invoke('check_backend_health'))) {
+            showScreen('setup-screen');
+            return false;
+        }
+        state.backendKind = last.backend.kind;
+        await invoke('connect_to_server', { url: last.backend.endpoint });
+    } catch (error) {
+        console.warn('Ultimo backend non disponibile:', error);
+        showScreen('setup-screen');
+        return false;
+    }
+
+    await loadModels();
+    updateBackendIndicator();
+    // loadModels goes back to the setup screen when the server has no models
+    return !elements.chatScreen.classList.contains('hidden');
+}
+
+async function saveLastBackend() {
+    state.lastSelectedModel = state.selectedModel;
+    try {
+        await invoke('save_last_backend', { selectedModel: state.selectedModel });
+    } catch (error) {
+        console.warn('Impossibile salvare l\'ultimo backend utilizzato:', error);
+    }
+}
+
 // ============ CHAT ============
 
 const THINK_TAG_REGEX = /<think>([\s\S]*?)<\/think>/gi;
@@ -2009,15 +2699,41 @@ function splitVisibleContentAndReasoning(content) {
     };
 }
 
-function addMessage(role, content, timestamp = null) {
+// Format Ollama generation metrics as "42 tok/s · 1.3s"
+function formatGenerationMetrics(metrics) {
+    if (!metrics || !metrics.eval_count || !metrics.eval_duration) {
+        return '';
+    }
+
+    const tokensPerSecond = metrics.eval_count / (metrics.eval_duration / 1e9);
+    const seconds = (metrics.total_duration || metrics.eval_duration) / 1e9;
+    return `${tokensPerSecond.toFixed(0)} tok/s · ${seconds.toFixed(1)}s`;
+}
+
+function addMessage(role, content, timestamp = null, options = {}) {
     const emptyState = elements.messages.querySelector('.empty-state');
     if (emptyState) {
         emptyState.remove();
     }
-    
+
+    const dayKey = addDaySeparatorIfNeeded(timestamp);
+
     const messageDiv = document.createElement('div');
     messageDiv.className = `message ${role}`;
-    
+    if (dayKey) {
+        messageDiv.dataset.day = dayKey;
+    }
+    if (options.alternative) {
+        messageDiv.classList.add('alternative');
+    }
+
+    if (options.label) {
+        const label = document.createElement('span');
+        label.className = 'message-label';
+        label.textContent = options.label;
+        messageDiv.appendChild(label);
+    }
+
     const bubble = document.createElement('div');
     bubble.className = 'message-bubble';
     let displayContent = content;
@@ -2058,26 +2774,263 @@ function addMessage(role, content, timestamp = null) {
     if (timestamp) {
         const timeSpan = document.createElement('span');
         timeSpan.className = 'message-timestamp';
-        timeSpan.textContent = timestamp;
+        timeSpan.textContent = formatTimestamp(timestamp);
+        const fullDate = parseTimestamp(timestamp);
+        if (fullDate) {
+            timeSpan.title = fullDate.toLocaleString(getDisplayLocale());
+        }
         messageDiv.appendChild(timeSpan);
     }
-    
+
+    const metricsLabel = formatGenerationMetrics(options.metrics);
+    if (metricsLabel) {
+        const metricsSpan = document.createElement('span');
+        metricsSpan.className = 'message-metrics';
+        metricsSpan.textContent = metricsLabel;
+        messageDiv.appendChild(metricsSpan);
+    }
+
+    if (role === 'assistant' && displayContent.trim()) {
+        messageDiv.appendChild(createReadAloudAction(displayContent));
+    }
+
+    if (role === 'assistant' && !options.alternative) {
+        // Only the latest assistant reply can be regenerated
+        elements.messages.querySelectorAll('.regenerate-actions').forEach(el => el.remove());
+        messageDiv.appendChild(createRegenerateActions());
+    }
+
+    if (options.actions) {
+        messageDiv.appendChild(options.actions);
+    }
+
+    if (options.message && (role === 'user' || role === 'assistant')) {
+        messageDiv.appendChild(createDeleteMessageAction(options.message));
+    }
+
     elements.messages.appendChild(messageDiv);
     scrollToBottom();
+    return messageDiv;
+}
+
+function createDeleteMessageAction(message) {
+    const container = document.createElement('div');
+    container.className = 'message-actions delete-message-actions';
+
+    const button = document.createElement('button');
+    button.className = 'message-action-btn message-delete-btn';
+    button.textContent = '🗑️';
+    button.title = message.role === 'user'
+        ? 'Elimina il messaggio e le risposte collegate'
+        : 'Elimina la risposta';
+    button.addEventListener('click', () => deleteMessage(message));
+
+    container.appendChild(button);
+    return container;
+}
+
+// Deletes a message from the saved conversation; the backend also removes the hidden
+// context tied to it and, for user messages, the replies that would be left orphaned.
+async function deleteMessage(message) {
+    if (state.isProcessing) return;
+
+    const question = message.role === 'user'
+        ? 'Eliminare questo messaggio e le risposte collegate?'
+        : 'Eliminare questa risposta?';
+    if (!confirm(question)) return;
+
+    try {
+        // Make sure the stored messages match the ones on screen before using the index
+        await saveCurrentConversation({ force: true });
+        const messageIndex = state.conversation.indexOf(message);
+        if (messageIndex === -1 || !state.currentConversationId) return;
+
+        const messages = await invoke('delete_message_from_conversation', {
+            convId: state.currentConversationId,
+            messageIndex,
+        });
+
+        state.conversation = messages.map(fromStoredMessage);
+        state.pendingToolCalls = [];
+        renderConversation();
+        restoreAttachmentIndex();
+        await clearDraft();
+        await loadMemory();
+        renderHistoryList();
+    } catch (error) {
+        showError(`Impossibile eliminare il messaggio: ${error}`);
+    }
+}
+
+function createReadAloudAction(text) {
+    const container = document.createElement('div');
+    container.className = 'message-actions read-aloud-actions';
+
+    const button = document.createElement('button');
+    button.className = 'message-action-btn read-aloud-btn';
+    setReadAloudButton(button, false);
+    button.addEventListener('click', () => toggleReadAloud(button, text));
+
+    container.appendChild(button);
+    return container;
+}
+
+function setReadAloudButton(button, playing) {
+    button.classList.toggle('playing', playing);
+    button.textContent = playing ? '⏹ Stop' : '🔊 Ascolta';
+    button.title = playing ? 'Interrompi la lettura' : 'Leggi la risposta ad alta voce';
+}
+
+// Only one reply is read at a time: starting another one stops the current reading
+async function toggleReadAloud(button, text) {
+    const current = state.readAloudButton;
+    if (current) {
+        state.readAloudButton = null;
+        setReadAloudButton(current, false);
+        if (current === button) {
+            await invoke('stop_speaking').catch(error => console.warn('Impossibile interrompere la lettura:', error));
+            return;
+        }
+    }
+
+    state.readAloudButton = button;
+    setReadAloudButton(button, true);
+    try {
+        await invoke('speak_text', { text });
+    } catch (error) {
+        if (state.readAloudButton === button) {
+            showError(`Lettura ad alta voce non disponibile: ${error}`);
+        }
+    } finally {
+        if (state.readAloudButton === button) {
+            state.readAloudButton = null;
+            setReadAloudButton(button, false);
+        }
+    }
+}
+
+function createRegenerateActions() {
+    const container = document.createElement('div');
+    container.className = 'message-actions regenerate-actions';
+
+    const button = document.createElement('button');
+    button.className = 'message-action-btn';
+    button.textContent = '🔁 Rigenera con…';
+    button.title = 'Rigenera l\'ultima risposta con un altro modello';
+
+    const select = document.createElement('select');
+    select.className = 'regenerate-select hidden';
+
+    button.addEventListener('click', () => {
+        select.innerHTML = '<option value="">Scegli modello...</option>';
+        state.models.forEach(model => {
+            const option = document.createElement('option');
+            option.value = model.name;
+            option.textContent = model.name === state.selectedModel
+                ? `${model.name} (corrente)`
+                : model.name;
+            select.appendChild(option);
+        });
+        select.classList.toggle('hidden');
+    });
+
+    select.addEventListener('change', async () => {
+        const model = select.value;
+        select.classList.add('hidden');
+        if (model) {
+            await regenerateLastResponse(model);
+        }
+    });
+
+    container.appendChild(button);
+    container.appendChild(select);
+    return container;
+}
+
+function findLastAssistantIndex() {
+    for (let i = state.conversation.length - 1; i >= 0; i--) {
+        const message = state.conversation[i];
+        if (message.role === 'assistant' && !message.hidden) {
+            return i;
+        }
+    }
+    return -1;
+}
+
+// Re-run the last exchange on another model and show the answer next to the original one.
+async function regenerateLastResponse(model) {
+    if (state.isProcessing) return;
+
+    const assistantIndex = findLastAssistantIndex();
+    if (assistantIndex === -1) return;
+
+    state.isProcessing = true;
+    updateSendButton();
+    hideError();
+    addLoadingIndicator();
+
+    try {
+        const response = await invoke('chat', {
+            model,
+            messages: state.conversation.slice(0, assistantIndex),
+            nodeId: state.selectedNode,
+            enableWebContext: isWebContextEnabled(),
+        });
+
+        removeLoadingIndicator();
+
+        const actions = document.createElement('div');
+        actions.className = 'message-actions';
+        const adoptBtn = document.createElement('button');
+        adoptBtn.className = 'message-action-btn';
+        adoptBtn.textContent = '✓ Usa questa risposta';
+        adoptBtn.addEventListener('click', async () => {
+            // The list may have changed in the meantime: only replace if this is still the last reply
+            if (findLastAssistantIndex() !== assistantIndex) {
+                showError('La conversazione è cambiata, impossibile sostituire la risposta');
+                return;
+            }
+            state.conversation[assistantIndex] = {
+                ...state.conversation[assistantIndex],
+                content: response.content,
+                timestamp: response.timestamp,
+                metrics: response.metrics || null,
+            };
+            renderConversation();
+            await saveCurrentConversation();
+        });
+        actions.appendChild(adoptBtn);
+
+        addMessage('assistant', response.content, response.timestamp, {
+            alternative: true,
+            label: `Risposta alternativa · ${model}`,
+            metrics: response.metrics,
+            actions,
+        });
+    } catch (error) {
+        removeLoadingIndicator();
+        showError(`Errore rigenerazione: ${error}`);
+    }
+
+    state.isProcessing = false;
+    updateSendButton();
 }
 
 function addAssistantMessage(content) {
-    if (!content) return;
-    addMessage('assistant', content, getTimestamp());
+    if (!content) return null;
+    const timestamp = getTimestamp();
+    addMessage('assistant', content, timestamp);
+    return timestamp;
 }
 
 function addAssistantResponse(content) {
     if (!content) return;
-    addAssistantMessage(content);
+    const timestamp = addAssistantMessage(content);
     state.conversation.push({
         role: 'assistant',
         content,
         hidden: false,
+        timestamp,
     });
 }
 
@@ -2100,6 +3053,15 @@ function removeLoadingIndicator() {
     }
 }
 
+async function getUserProfilePrompt() {
+    try {
+        return await invoke('get_user_profile_prompt');
+    } catch (error) {
+        console.warn('Impossibile leggere il profilo utente:', error);
+        return null;
+    }
+}
+
 async function getToolsDescription() {
     if (state.agentMode) {
         return await invoke('get_tools_description');
@@ -2122,9 +3084,9 @@ async function sendMessage() {
     
     if (state.attachedFiles.length > 0) {
         fullContent += 'File allegati:\n\n';
-        state.attachedFiles.forEach(file => {
-            fullContent += `=== ${file.name} ===\n${file.content}\n\n`;
-        });
+        for (const file of state.attachedFiles) {
+            fullContent += `=== ${file.name} ===\n${await getAttachmentContext(file, text)}\n\n`;
+        }
         fullContent += '---\n\n';
     }
     
@@ -2135,7 +3097,16 @@ async function sendMessage() {
         ? state.attachedFiles.map(f => `📎 ${f.name}`).join('\n') + '\n\n' + text
         : text;
     
-    addMessage('user', displayContent, getTimestamp());
+    const userTimestamp = getTimestamp();
+    const userMessage = {
+        role: 'user',
+        content: fullContent,
+        hidden: false,
+        timestamp: userTimestamp,
+        attachments: [...state.attachedFiles],
+    };
+    const userMessageDiv = addMessage('user', displayContent, userTimestamp, { message: userMessage });
+    const earlierDocuments = await getEarlierDocumentsContext(text);
     
     // Add to conversation
     if (!state.systemPromptAdded && state.conversation.length === 0) {
@@ -2147,6 +3118,11 @@ async function sendMessage() {
         if (state.customSystemPrompt.enabled && state.customSystemPrompt.content.trim()) {
             systemContent += '\n\n**ISTRUZIONI PERSONALIZZATE DELL\'UTENTE:**\n' + state.customSystemPrompt.content.trim();
         }
+
+        const profilePrompt = await getUserProfilePrompt();
+        if (profilePrompt) {
+            systemContent += '\n\n**PROFILO UTENTE:**\n' + profilePrompt;
+        }
         
         if (state.agentMode) {
             const toolsDesc = await getToolsDescription();
@@ -2163,18 +3139,17 @@ async function sendMessage() {
             state.memoryContextInjected = true;
         }
         
-        state.conversation.push({ role: 'user', content: systemContent, hidden: true });
-        state.conversation.push({ 
-            role: 'assistant', 
-            content: 'Perfetto! Sono pronto ad aiutarti.',
-            hidden: true 
-        });
+        state.conversation.push({ role: 'system', content: systemContent, hidden: true });
         state.systemPromptAdded = true;
     }
     
-    state.conversation.push({ role: 'user', content: fullContent, hidden: false });
+    state.conversation.push(userMessage);
+    if (earlierDocuments) {
+        state.conversation.push({ role: 'user', content: earlierDocuments, hidden: true });
+    }
 
-    if (state.agentMode) {
+    // Offline the backend refuses web_search: no point asking the model to call it
+    if (state.agentMode && !state.appSettings.offline_mode) {
         const newsQuery = detectNewsQuery(text);
         if (newsQuery) {
             const safeQuery = newsQuery.replace(/"/g, "'");
@@ -2187,7 +3162,6 @@ async function sendMessage() {
     }
 
     await saveCurrentConversation({ force: true });
-    await autoCaptureCalendarEvents(text);
 
     const handledIntegration = await handleCalendarIntegrationCommand(text);
     if (handledIntegration) {
@@ -2204,6 +3178,8 @@ async function sendMessage() {
         return;
     }
 
+    suggestCalendarEvent(text, userMessageDiv);
+
     if (text) {
         state.messageHistory.push(text);
         state.messageHistoryIndex = -1;
@@ -2225,18 +3201,26 @@ async function processChat() {
     try {
         const response = await invoke('chat', {
             model: state.selectedModel,
-            messages: state.conversation
+            messages: state.conversation,
+            nodeId: state.selectedNode,
+            enableWebContext: isWebContextEnabled(),
         });
         
         removeLoadingIndicator();
         
-        state.conversation.push({
+        const assistantMessage = {
             role: 'assistant',
             content: response.content,
-            hidden: false
-        });
+            hidden: false,
+            timestamp: response.timestamp,
+            metrics: response.metrics || null,
+        };
+        state.conversation.push(assistantMessage);
         
-        addMessage('assistant', response.content, response.timestamp);
+        addMessage('assistant', response.content, response.timestamp, {
+            metrics: response.metrics,
+            message: assistantMessage,
+        });
         
         // Check for tool calls if agent mode is enabled
         if (state.agentMode) {
@@ -2269,10 +3253,10 @@ async function processNextToolCall() {
     const toolCall = state.pendingToolCalls[0];
     
     // Check if tool is dangerous
-    const isDangerous = await invoke('check_tool_dangerous', { toolName: toolCall.tool_name });
+    const isDangerous = await invoke('check_tool_dangerous', { toolCall });
     
     if (isDangerous) {
-        showConfirmModal(toolCall);
+        await showConfirmModal(toolCall);
         return;
     }
     
@@ -2286,7 +3270,17 @@ async function executeToolCall(toolCall) {
         const result = await invoke('execute_tool', { toolCall });
         
         // Show result to user
-        addMessage('system', `🔧 ${result.tool_name}: ${result.success ? '✅' : '❌'}\n${result.output || result.error || ''}`, getTimestamp());
+        const resultMessage = addMessage('system', `🔧 ${result.tool_name}: ${result.success ? '✅' : '❌'}\n${result.output || result.error || ''}`, getTimestamp());
+        if (result.image) {
+            const image = document.createElement('img');
+            image.className = 'tool-result-image';
+            image.src = result.image;
+            image.alt = result.tool_name;
+            resultMessage.querySelector('.message-bubble')?.appendChild(image);
+        }
+        if (result.calendar_event) {
+            resultMessage.querySelector('.message-bubble')?.appendChild(createEventSuggestion(result.calendar_event));
+        }
         
         // Add to conversation for context
         state.conversation.push({
@@ -2294,6 +3288,8 @@ async function executeToolCall(toolCall) {
             content: `**Risultato Tool:** ${result.tool_name}\n${result.output || result.error}`,
             hidden: true
         });
+        await saveDraft();
+        await refreshFileUndoButton();
         
         // Handle URL results (open in browser)
         if (result.success && result.output.startsWith('URL: ')) {
@@ -2323,24 +3319,59 @@ async function executeToolCall(toolCall) {
     }
 }
 
+async function refreshFileUndoButton() {
+    try {
+        const depth = await invoke('get_file_undo_depth');
+        elements.undoFileBtn.textContent = t('header.undoFile', { count: depth });
+        elements.undoFileBtn.classList.toggle('hidden', depth === 0);
+    } catch (error) {
+        console.error('Failed to read file undo depth:', error);
+    }
+}
+
+async function undoLastFileOperation() {
+    try {
+        const message = await invoke('undo_last_file_operation');
+        addMessage('system', `↩️ ${message}`, getTimestamp());
+        // Let the model know the file is back to its previous state
+        state.conversation.push({
+            role: 'user',
+            content: `**Operazione annullata dall'utente:** ${message}`,
+            hidden: true
+        });
+        await saveDraft();
+    } catch (error) {
+        showError(t('header.undoFileFailed', { error }));
+    }
+    await refreshFileUndoButton();
+}
+
 async function continueAgentLoop() {
     addLoadingIndicator();
     
     try {
         const response = await invoke('chat', {
             model: state.selectedModel,
-            messages: state.conversation
+            messages: state.conversation,
+            nodeId: state.selectedNode,
+            enableWebContext: isWebContextEnabled(),
         });
         
         removeLoadingIndicator();
         
-        state.conversation.push({
+        const assistantMessage = {
             role: 'assistant',
             content: response.content,
-            hidden: false
-        });
+            hidden: false,
+            timestamp: response.timestamp,
+            metrics: response.metrics || null,
+        };
+        state.conversation.push(assistantMessage);
         
-        addMessage('assistant', response.content, response.timestamp);
+        addMessage('assistant', response.content, response.timestamp, {
+            metrics: response.metrics,
+            message: assistantMessage,
+        });
         
         // Check for more tool calls
         const toolCalls = await invoke('parse_tool_calls', { response: response.content });
@@ -2359,11 +3390,20 @@ async function continueAgentLoop() {
     }
 }
 
-function showConfirmModal(toolCall) {
+async function showConfirmModal(toolCall) {
+    // Simulated outcome, for the tools that support it
+    let preview = null;
+    try {
+        preview = await invoke('dry_run_tool', { toolCall });
+    } catch (error) {
+        preview = t('confirm.previewFailed', { error });
+    }
+
     elements.confirmDetails.innerHTML = `
         <strong>Tool:</strong> ${toolCall.tool_name}<br>
         <strong>Parametri:</strong><br>
         ${Object.entries(toolCall.parameters).map(([k, v]) => `  ${k}: ${JSON.stringify(v)}`).join('<br>')}
+        ${preview ? `<div class="confirm-preview-title">${t('confirm.preview')}</div><pre class="confirm-preview">${escapeHtml(preview)}</pre>` : ''}
     `;
     elements.confirmModal.classList.remove('hidden');
     
@@ -2394,8 +3434,35 @@ async function addAttachmentFromPath(path) {
     }
 
     try {
-        const [filename, content] = await invoke('read_file', { path });
-        state.attachedFiles.push({ name: filename, content, path });
+        let selection = null;
+        if (/\.(xlsx|xls|ods)$/i.test(path)) {
+            const sheets = await invoke('list_spreadsheet_sheets', { path });
+            if (sheets.length > 1) {
+                selection = await showSheetSelectionModal(path, sheets);
+                if (!selection) {
+                    return false;
+                }
+            }
+        }
+
+        const [filename, content] = await invoke('read_file', {
+            path,
+            sheets: selection?.sheets ?? null,
+            cellRange: selection?.range ?? null,
+        });
+        const attachment = { name: filename, content, path, pages: null, selection };
+        if (filename.toLowerCase().endsWith('.pdf')) {
+            try {
+                const metadata = await invoke('get_pdf_metadata', { path });
+                attachment.pages = metadata.pages;
+            } catch (error) {
+                console.warn('Metadati PDF non disponibili:', error);
+            }
+        }
+        if (content.length > RAG_CONFIG.minChars) {
+            await indexAttachment(attachment);
+        }
+        state.attachedFiles.push(attachment);
         return true;
     } catch (error) {
         showError(`Errore lettura file: ${error}`);
@@ -2403,6 +3470,313 @@ async function addAttachmentFromPath(path) {
     }
 }
 
+async function indexAttachment(attachment) {
+    try {
+        const indexed = await invoke('index_document', {
+            name: attachment.name,
+            content: attachment.content,
+        });
+        attachment.ragId = indexed.id;
+        attachment.chunks = indexed.chunks;
+    } catch (error) {
+        // Without an embedding model the whole document is sent as before
+        console.warn('Indicizzazione documento non riuscita:', error);
+    }
+}
+
+async function getAttachmentContext(file, query) {
+    if (!file.ragId || !query) {
+        return file.content;
+    }
+
+    try {
+        const chunks = await invoke('retrieve_chunks', {
+            query,
+            documentIds: [file.ragId],
+            topK: RAG_CONFIG.topK,
+        });
+        if (chunks.length === 0) {
+            return file.content;
+        }
+        const excerpts = chunks.map(chunk => chunk.text).join('\n\n[...]\n\n');
+        return `(Documento lungo: ${chunks.length} estratti più pertinenti su ${file.chunks})\n\n${excerpts}`;
+    } catch (error) {
+        console.warn('Recupero estratti non riuscito:', error);
+        return file.content;
+    }
+}
+
+// Long documents attached earlier, also before the conversation was reopened: the
+// excerpts relevant to the new question are given to the model again
+async function getEarlierDocumentsContext(query) {
+    const currentIds = new Set(state.attachedFiles.map(f => f.ragId).filter(Boolean));
+    const documentIds = [...new Set(state.conversation
+        .flatMap(m => m.attachments || [])
+        .map(a => a.ragId)
+        .filter(id => id && !currentIds.has(id)))];
+    if (!query || documentIds.length === 0) return null;
+
+    try {
+        const chunks = await invoke('retrieve_chunks', {
+            query,
+            documentIds,
+            topK: RAG_CONFIG.topK,
+        });
+        if (chunks.length === 0) return null;
+        const excerpts = chunks
+            .map(chunk => `=== ${chunk.document_name} ===\n${chunk.text}`)
+            .join('\n\n[...]\n\n');
+        return `CONTESTO DOCUMENTI: estratti dei file allegati in precedenza pertinenti alla domanda dell'utente.\n\n${excerpts}`;
+    } catch (error) {
+        console.warn('Recupero estratti non riuscito:', error);
+        return null;
+    }
+}
+
+// Saved attachments lose their retrieval id: long ones are indexed again,
+// which is immediate when the document is still in the index
+async function restoreAttachmentIndex() {
+    const attachments = state.conversation
+        .flatMap(m => m.attachments || [])
+        .filter(a => !a.ragId && a.content && a.content.length > RAG_CONFIG.minChars);
+    for (const attachment of attachments) {
+        await indexAttachment(attachment);
+    }
+}
+
+// ============ MODEL DETAILS ============
+
+async function showModelDetails() {
+    const name = state.selectedModel;
+    if (!name) {
+        showError('Seleziona prima un modello');
+        return;
+    }
+
+    elements.modelInfoTitle.textContent = `ℹ️ ${name}`;
+    elements.modelInfoBody.innerHTML = '<p class="model-info-loading">Caricamento dettagli...</p>';
+    elements.modelInfoModal.classList.remove('hidden');
+
+    try {
+        const details = await invoke('show_model', { name });
+        const rows = [
+            ['Famiglia', details.family],
+            ['Parametri', details.parameter_size],
+            ['Quantizzazione', details.quantization_level],
+            ['Formato', details.format],
+            ['Context length', details.context_length ? `${details.context_length.toLocaleString('it-IT')} token` : null],
+        ].filter(([, value]) => value);
+
+        const sections = [
+            ['System prompt del modello', details.system],
+            ['Parametri di default', details.parameters],
+            ['Template', details.template],
+            ['Licenza', details.license],
+            ['Modelfile', details.modelfile],
+        ].filter(([, value]) => value);
+
+        elements.modelInfoBody.innerHTML = `
+            <dl class="model-info-grid">
+                ${rows.map(([label, value]) => `<dt>${label}</dt><dd>${escapeHtml(String(value))}</dd>`).join('')}
+            </dl>
+            ${sections.map(([label, value]) => `
+                <details class="model-info-section">
+                    <summary>${label}</summary>
+                    <pre>${escapeHtml(value)}</pre>
+                </details>
+            `).join('')}
+        `;
+    } catch (error) {
+        elements.modelInfoBody.innerHTML = `<p class="sql-status error">Errore caricamento dettagli: ${escapeHtml(String(error))}</p>`;
+    }
+}
+
+function hideModelDetails() {
+    elements.modelInfoModal.classList.add('hidden');
+}
+
+// ============ AICONNECT NODES ============
+
+const NODES_REFRESH_INTERVAL_MS = 10000;
+
+async function showAiConnectNodes() {
+    elements.nodesBody.innerHTML = `<p class="model-info-loading">${t('nodes.loading')}</p>`;
+    elements.nodesModal.classList.remove('hidden');
+
+    await refreshAiConnectNodes();
+    clearInterval(state.nodesRefreshTimer);
+    state.nodesRefreshTimer = setInterval(refreshAiConnectNodes, NODES_REFRESH_INTERVAL_MS);
+}
+
+function hideAiConnectNodes() {
+    clearInterval(state.nodesRefreshTimer);
+    state.nodesRefreshTimer = null;
+    elements.nodesModal.classList.add('hidden');
+}
+
+async function refreshAiConnectNodes() {
+    try {
+        const nodes = await invoke('get_aiconnect_nodes');
+        renderAiConnectNodes(nodes);
+    } catch (error) {
+        elements.nodesBody.innerHTML = `<p class="sql-status error">${escapeHtml(t('nodes.error', { error: String(error) }))}</p>`;
+    }
+}
+
+function isNodeOnline(node) {
+    return ['online', 'ready', 'active', 'healthy', 'idle', 'busy'].includes((node.status || '').toLowerCase());
+}
+
+// Load bar for a 0-100 utilization value reported by the node
+function nodeUsageBar(label, value) {
+    if (typeof value !== 'number') {
+        return '';
+    }
+
+    const percent = Math.max(0, Math.min(100, value));
+    const level = percent >= 85 ? 'high' : percent >= 60 ? 'medium' : 'low';
+    return `
+        <div class="node-usage">
+            <span>${label}</span>
+            <div class="node-usage-bar"><div class="${level}" style="width: ${percent}%"></div></div>
+            <span>${Math.round(percent)}%</span>
+        </div>
+    `;
+}
+
+async function loadNodeSelector() {
+    try {
+        state.aiconnectNodes = await invoke('get_aiconnect_nodes');
+    } catch (error) {
+        console.warn('Impossibile caricare i nodi AIConnect:', error);
+        state.aiconnectNodes = [];
+    }
+    renderNodeSelector();
+}
+
+// Offline nodes and nodes without the selected model cannot be chosen
+function renderNodeSelector() {
+    const selector = elements.nodeSelector;
+    if (!selector) return;
+
+    selector.innerHTML = '';
+    const auto = document.createElement('option');
+    auto.value = '';
+    auto.textContent = t('header.autoNode');
+    selector.appendChild(auto);
+
+    state.aiconnectNodes.forEach(node => {
+        const option = document.createElement('option');
+        option.value = node.id;
+        option.textContent = `${isNodeOnline(node) ? '🟢' : '🔴'} ${node.name || node.id}`;
+        const hasModel = !node.models.length || node.models.includes(state.selectedModel);
+        option.disabled = !isNodeOnline(node) || !hasModel;
+        selector.appendChild(option);
+    });
+
+    const current = state.aiconnectNodes.find(node => node.id === state.selectedNode);
+    if (!current || Array.from(selector.options).find(opt => opt.value === current.id)?.disabled) {
+        state.selectedNode = null;
+    }
+    selector.value = state.selectedNode || '';
+}
+
+function renderAiConnectNodes(nodes) {
+    state.aiconnectNodes = nodes;
+    renderNodeSelector();
+
+    if (!nodes.length) {
+        elements.nodesBody.innerHTML = `<p class="model-info-loading">${t('nodes.empty')}</p>`;
+        return;
+    }
+
+    const online = nodes.filter(isNodeOnline).length;
+    const cards = nodes.map(node => {
+        const counters = [
+            [t('nodes.active'), node.active_requests],
+            [t('nodes.queued'), node.queued_requests],
+        ].filter(([, value]) => typeof value === 'number');
+        const extra = Object.entries(node.metadata || {});
+        const models = node.models.length
+            ? node.models.map(model => `<span class="node-model">${escapeHtml(model)}</span>`).join('')
+            : `<span class="model-info-loading">${t('nodes.noModels')}</span>`;
+
+        return `
+            <div class="node-card ${isNodeOnline(node) ? 'online' : 'offline'}">
+                <div class="node-card-header">
+                    <span class="node-status-dot"></span>
+                    <strong>${escapeHtml(node.name || node.id)}</strong>
+                    <span class="node-status">${escapeHtml(node.status || '?')}</span>
+                </div>
+                ${node.address ? `<div class="node-address">${escapeHtml(node.address)}</div>` : ''}
+                ${counters.length ? `
+                    <dl class="model-info-grid">
+                        ${counters.map(([label, value]) => `<dt>${label}</dt><dd>${value}</dd>`).join('')}
+                    </dl>
+                ` : ''}
+                ${nodeUsageBar(t('nodes.cpu'), node.cpu_usage)}
+                ${nodeUsageBar(t('nodes.gpu'), node.gpu_usage)}
+                ${nodeUsageBar(t('nodes.memory'), node.memory_usage)}
+                <div class="node-models">
+                    <span>${t('nodes.models')}:</span>
+                    ${models}
+                </div>
+                ${extra.length ? `
+                    <details class="model-info-section">
+                        <summary>${t('nodes.details')}</summary>
+                        <pre>${escapeHtml(JSON.stringify(Object.fromEntries(extra), null, 2))}</pre>
+                    </details>
+                ` : ''}
+            </div>
+        `;
+    }).join('');
+
+    elements.nodesBody.innerHTML = `
+        <p class="nodes-summary">
+            ${t('nodes.summary', { online, total: nodes.length })}
+            · ${t('nodes.updated', { time: new Date().toLocaleTimeString() })}
+        </p>
+        <div class="node-list">${cards}</div>
+    `;
+}
+
+// Resolves with { sheets, range } or null when the user cancels
+function showSheetSelectionModal(path, sheets) {
+    const fileName = path.split(/[\\/]/).pop();
+    elements.sheetModalFile.textContent = `Quali fogli di "${fileName}" vuoi allegare?`;
+    elements.sheetList.innerHTML = sheets.map((sheet, index) => `
+        <label class="checkbox-label">
+            <input type="checkbox" value="${escapeHtml(sheet)}" ${index === 0 ? 'checked' : ''}>
+            ${escapeHtml(sheet)}
+        </label>
+    `).join('');
+    elements.sheetRange.value = '';
+    elements.sheetModal.classList.remove('hidden');
+
+    return new Promise(resolve => {
+        const close = (result) => {
+            elements.sheetModal.classList.add('hidden');
+            elements.sheetConfirm.onclick = null;
+            elements.sheetCancel.onclick = null;
+            elements.closeSheetModal.onclick = null;
+            resolve(result);
+        };
+
+        elements.sheetConfirm.onclick = () => {
+            const selected = Array.from(elements.sheetList.querySelectorAll('input:checked'))
+                .map(input => input.value);
+            const range = elements.sheetRange.value.trim();
+            if (selected.length === 0 && !range.includes('!')) {
+                showError('Seleziona almeno un foglio');
+                return;
+            }
+            close({ sheets: selected, range: range || null });
+        };
+        elements.sheetCancel.onclick = () => close(null);
+        elements.closeSheetModal.onclick = () => close(null);
+    });
+}
+
 async function attachFile() {
     const dialogOpen = window.__TAURI__?.dialog?.open;
 
@@ -2413,7 +3787,7 @@ async function attachFile() {
                 filters: [
                     {
                         name: 'Documenti supportati',
-                        extensions: ['pdf', 'xlsx', 'xls', 'ods', 'txt', 'md', 'csv'],
+                        extensions: ATTACHMENT_EXTENSIONS,
                     },
                 ],
             });
@@ -2454,6 +3828,55 @@ async function attachFile() {
     elements.fileInput.click();
 }
 
+function isSupportedAttachment(path) {
+    const extension = path.split('.').pop().toLowerCase();
+    return path.includes('.') && ATTACHMENT_EXTENSIONS.includes(extension);
+}
+
+async function attachDroppedFiles(paths) {
+    const supported = paths.filter(isSupportedAttachment);
+    const unsupported = paths.filter(path => !isSupportedAttachment(path));
+
+    let added = false;
+    for (const path of supported) {
+        const result = await addAttachmentFromPath(path);
+        added = added || result;
+    }
+    if (added) {
+        updateAttachedFiles();
+        updateSendButton();
+    }
+
+    if (unsupported.length > 0) {
+        const files = unsupported.map(path => path.split(/[\\/]/).pop()).join(', ');
+        showError(t('input.dropUnsupported', { files, formats: ATTACHMENT_EXTENSIONS.join(', ') }));
+    }
+}
+
+// Tauri delivers the paths of the files dropped on the window, not their content
+async function initFileDrop() {
+    const webview = window.__TAURI__.webview?.getCurrentWebview?.();
+    if (!webview) {
+        return;
+    }
+
+    const overlay = document.getElementById('drop-overlay');
+    await webview.onDragDropEvent(async (event) => {
+        const chatVisible = !elements.chatScreen.classList.contains('hidden');
+        const { type, paths } = event.payload;
+        if (type === 'enter' || type === 'over') {
+            overlay.classList.toggle('hidden', !chatVisible);
+        } else if (type === 'leave') {
+            overlay.classList.add('hidden');
+        } else if (type === 'drop') {
+            overlay.classList.add('hidden');
+            if (chatVisible && paths?.length) {
+                await attachDroppedFiles(paths);
+            }
+        }
+    });
+}
+
 async function handleFileSelect(event) {
     const file = event.target.files[0];
     event.target.value = '';
@@ -2466,6 +3889,13 @@ async function handleFileSelect(event) {
     }
 }
 
+function describeSheetSelection(selection) {
+    if (selection.range) {
+        return selection.range;
+    }
+    return selection.sheets.length === 1 ? selection.sheets[0] : `${selection.sheets.length} fogli`;
+}
+
 function updateAttachedFiles() {
     if (state.attachedFiles.length === 0) {
         elements.attachedFilesContainer.classList.add('hidden');
@@ -2475,7 +3905,7 @@ function updateAttachedFiles() {
     elements.attachedFilesContainer.classList.remove('hidden');
     elements.attachedFilesContainer.innerHTML = state.attachedFiles.map((file, index) => `
         <div class="file-chip">
-            <span>📎 ${escapeHtml(file.name)}</span>
+            <span>📎 ${escapeHtml(file.name)}${file.pages ? ` · ${file.pages} pag.` : ''}${file.selection ? ` · ${escapeHtml(describeSheetSelection(file.selection))}` : ''}${file.ragId ? ' · indicizzato' : ''}</span>
             <button class="remove-btn" data-index="${index}">✕</button>
         </div>
     `).join('');
@@ -2489,6 +3919,82 @@ function updateAttachedFiles() {
     });
 }
 
+// ============ VOICE INPUT ============
+
+async function toggleVoiceInput() {
+    if (state.voiceInput === 'transcribing') {
+        return;
+    }
+
+    if (state.voiceInput === 'idle') {
+        try {
+            await invoke('start_voice_recording');
+            state.voiceInput = 'recording';
+        } catch (error) {
+            showError(t('input.dictationFailed', { error }));
+        }
+        updateMicButton();
+        return;
+    }
+
+    state.voiceInput = 'transcribing';
+    updateMicButton();
+    try {
+        const text = await invoke('stop_voice_recording');
+        insertDictatedText(text);
+    } catch (error) {
+        showError(t('input.dictationFailed', { error }));
+    } finally {
+        state.voiceInput = 'idle';
+        updateMicButton();
+    }
+}
+
+async function cancelVoiceInput() {
+    if (state.voiceInput !== 'recording') {
+        return;
+    }
+    state.voiceInput = 'idle';
+    updateMicButton();
+    try {
+        await invoke('cancel_voice_recording');
+    } catch (error) {
+        console.warn('Impossibile annullare la registrazione:', error);
+    }
+}
+
+function updateMicButton() {
+    if (!elements.micBtn) return;
+
+    const recording = state.voiceInput === 'recording';
+    const transcribing = state.voiceInput === 'transcribing';
+    elements.micBtn.classList.toggle('recording', recording);
+    elements.micBtn.classList.toggle('transcribing', transcribing);
+    elements.micBtn.disabled = transcribing;
+    elements.micBtn.textContent = recording ? '⏹' : transcribing ? '⏳' : '🎤';
+    elements.micBtn.title = t(recording ? 'input.dictateStop' : transcribing ? 'input.transcribing' : 'input.dictate');
+}
+
+// Inserts the transcription at the cursor, separated by spaces from the surrounding text
+function insertDictatedText(text) {
+    const dictated = (text || '').trim();
+    if (!dictated) return;
+
+    const input = elements.messageInput;
+    const start = input.selectionStart ?? input.value.length;
+    const end = input.selectionEnd ?? input.value.length;
+    const before = input.value.slice(0, start);
+    const after = input.value.slice(end);
+    const prefix = before && !/\s$/.test(before) ? ' ' : '';
+    const suffix = after && !/^\s/.test(after) ? ' ' : '';
+
+    input.value = `${before}${prefix}${dictated}${suffix}${after}`;
+    const caret = before.length + prefix.length + dictated.length;
+    input.setSelectionRange(caret, caret);
+    input.focus();
+    updateSendButton();
+}
+
 function updateSendButton() {
     const hasContent = elements.messageInput.value.trim() || state.attachedFiles.length > 0;
     elements.sendBtn.disabled = !hasContent || state.isProcessing;
@@ -2541,6 +4047,14 @@ function navigateMessageHistory(direction) {
     return true;
 }
 
+function isWebContextEnabled() {
+    return state.webContext ?? state.agentMode;
+}
+
+function updateWebContextToggle() {
+    elements.webContextToggle.checked = isWebContextEnabled();
+}
+
 function updateIterationCounter() {
     if (state.agentMode) {
         elements.iterationCounter.textContent = `(${state.currentIteration}/${state.maxIterations})`;
@@ -2569,7 +4083,25 @@ function updateSqlAuth() {
     }
 }
 
+// Windows authentication exists only for SQL Server; the others take "host:port"
+function updateSqlDbType() {
+    const dbType = elements.sqlDbType.value;
+    const isSqlServer = dbType === 'sqlserver';
+
+    elements.sqlAuthWindows.classList.toggle('hidden', !isSqlServer);
+    if (!isSqlServer) {
+        document.querySelector('input[name="sql-auth"][value="sql"]').checked = true;
+        updateSqlAuth();
+    }
+
+    const defaultPorts = { postgres: 5432, mysql: 3306 };
+    elements.sqlServerHint.textContent = isSqlServer
+        ? 'Nome o IP del server SQL'
+        : `Nome o IP del server, porta opzionale (default ${defaultPorts[dbType]}, es. db.local:${defaultPorts[dbType]})`;
+}
+
 async function testSqlConnection() {
+    const dbType = elements.sqlDbType.value;
     const server = elements.sqlServer.value.trim();
     const database = elements.sqlDatabase.value.trim();
     const authMethod = document.querySelector('input[name="sql-auth"]:checked').value;
@@ -2588,6 +4120,7 @@ async function testSqlConnection() {
     
     try {
         const connectionId = await invoke('sql_connect', {
+            dbType,
             server,
             database,
             authMethod,
@@ -2612,6 +4145,77 @@ async function testSqlConnection() {
 // ============ SETTINGS & CUSTOM SYSTEM PROMPT ============
 
 async function loadSettings() {
+    try {
+        const settings = await invoke('load_app_settings');
+        state.appSettings = { ...state.appSettings, ...settings };
+        if (elements.autoUpdateCheck) {
+            elements.autoUpdateCheck.checked = Boolean(state.appSettings.auto_update_check);
+        }
+        if (elements.timeFormatSelector) {
+            elements.timeFormatSelector.value = state.appSettings.time_format || '24h';
+        }
+        if (elements.proxyEnabled) {
+            const proxy = state.appSettings.proxy;
+            elements.proxyEnabled.checked = Boolean(proxy.enabled);
+            elements.proxyUrl.value = proxy.url || '';
+            elements.proxyUsername.value = proxy.username || '';
+            elements.proxyPassword.value = proxy.password || '';
+            elements.proxyNoProxy.value = proxy.no_proxy || '';
+            updateProxyFieldsVisibility();
+        }
+        if (elements.deeplApiKey) {
+            const translation = state.appSettings.translation;
+            elements.deeplApiKey.value = translation.deepl_api_key || '';
+            elements.libretranslateUrl.value = translation.libretranslate_url || '';
+            elements.libretranslateApiKey.value = translation.libretranslate_api_key || '';
+        }
+        if (elements.embeddingModel) {
+            elements.embeddingModel.value = state.appSettings.embedding_model || '';
+        }
+        if (elements.memoryMaxConversations) {
+            elements.memoryMaxConversations.value = state.appSettings.memory_max_conversations;
+            elements.memoryMaxSizeMb.value = state.appSettings.memory_max_size_mb;
+        }
+        if (elements.sqlQueryTimeout) {
+            elements.sqlQueryTimeout.value = state.appSettings.sql_query_timeout_secs;
+        }
+        if (elements.chatTimeout) {
+            elements.chatTimeout.value = state.appSettings.chat_timeout_secs;
+        }
+        if (elements.sqlMaskingEnabled) {
+            elements.sqlMaskingEnabled.checked = Boolean(state.appSettings.sql_masking.enabled);
+            elements.sqlMaskingPatterns.value = formatMaskingPatterns(state.appSettings.sql_masking.patterns);
+        }
+        if (elements.offlineMode) {
+            elements.offlineMode.checked = Boolean(state.appSettings.offline_mode);
+        }
+        if (elements.saveAttachmentContent) {
+            elements.saveAttachmentContent.checked = state.appSettings.save_attachment_content !== false;
+        }
+        if (elements.suggestCalendarEvents) {
+            elements.suggestCalendarEvents.checked = state.appSettings.suggest_calendar_events !== false;
+        }
+        if (elements.eventNotificationsEnabled) {
+            const notifications = state.appSettings.event_notifications;
+            elements.eventNotificationsEnabled.checked = notifications.enabled !== false;
+            elements.eventReminderMinutes.value = notifications.default_reminder_minutes;
+        }
+        updateCalendarNotificationsButton();
+        if (elements.logLevel) {
+            elements.logLevel.value = state.appSettings.log_level || 'info';
+        }
+        if (elements.personalizeWithProfile) {
+            elements.personalizeWithProfile.checked = Boolean(state.appSettings.personalize_with_profile);
+        }
+        if (elements.voiceLanguage) {
+            elements.voiceLanguage.value = state.appSettings.voice.language || 'it';
+            elements.whisperModelPath.value = state.appSettings.voice.whisper_model_path || '';
+            elements.readAloudLanguage.value = state.appSettings.voice.read_aloud_language || 'auto';
+        }
+    } catch (error) {
+        console.warn('Impossibile caricare le impostazioni dell\'applicazione:', error);
+    }
+
     try {
         const prompt = await invoke('load_custom_system_prompt');
         state.customSystemPrompt = {
@@ -2631,6 +4235,37 @@ async function loadSettings() {
     }
 }
 
+// Masking patterns are edited as "name | regex | replacement" lines; the regex
+// may itself contain "|", so name and replacement are taken from the ends
+function formatMaskingPatterns(patterns) {
+    return (patterns || [])
+        .map(pattern => `${pattern.name} | ${pattern.pattern} | ${pattern.replacement}`)
+        .join('\n');
+}
+
+function parseMaskingPatterns(text) {
+    return text
+        .split('\n')
+        .map(line => line.trim())
+        .filter(Boolean)
+        .map(line => {
+            const first = line.indexOf(' | ');
+            const last = line.lastIndexOf(' | ');
+            if (first === -1) {
+                return { name: 'personalizzato', pattern: line, replacement: '****' };
+            }
+            if (first === last) {
+                return { name: line.slice(0, first).trim(), pattern: line.slice(first + 3).trim(), replacement: '****' };
+            }
+            return {
+                name: line.slice(0, first).trim(),
+                pattern: line.slice(first + 3, last).trim(),
+                replacement: line.slice(last + 3).trim(),
+            };
+        })
+        .filter(pattern => pattern.pattern);
+}
+
 async function saveSettings() {
     const enabled = elements.customPromptEnabled?.checked || false;
     const content = elements.customPromptContent?.value || '';
@@ -2645,6 +4280,60 @@ async function saveSettings() {
         });
         
         state.customSystemPrompt = { enabled, content };
+
+        const appSettings = {
+            ...state.appSettings,
+            auto_update_check: elements.autoUpdateCheck?.checked ?? state.appSettings.auto_update_check,
+            time_format: elements.timeFormatSelector?.value || state.appSettings.time_format,
+            proxy: readProxySettingsFromForm(),
+            translation: elements.deeplApiKey ? {
+                deepl_api_key: elements.deeplApiKey.value.trim() || null,
+                libretranslate_url: elements.libretranslateUrl.value.trim() || null,
+                libretranslate_api_key: elements.libretranslateApiKey.value.trim() || null,
+            } : state.appSettings.translation,
+            embedding_model: elements.embeddingModel?.value.trim() || state.appSettings.embedding_model,
+            memory_max_conversations: parseInt(elements.memoryMaxConversations?.value, 10) > 0
+                ? parseInt(elements.memoryMaxConversations.value, 10)
+                : state.appSettings.memory_max_conversations,
+            memory_max_size_mb: parseInt(elements.memoryMaxSizeMb?.value, 10) > 0
+                ? parseInt(elements.memoryMaxSizeMb.value, 10)
+                : state.appSettings.memory_max_size_mb,
+            sql_query_timeout_secs: parseInt(elements.sqlQueryTimeout?.value, 10) > 0
+                ? Math.min(parseInt(elements.sqlQueryTimeout.value, 10), 600)
+                : state.appSettings.sql_query_timeout_secs,
+            chat_timeout_secs: parseInt(elements.chatTimeout?.value, 10) > 0
+                ? Math.min(Math.max(parseInt(elements.chatTimeout.value, 10), 10), 3600)
+                : state.appSettings.chat_timeout_secs,
+            sql_masking: elements.sqlMaskingEnabled ? {
+                enabled: elements.sqlMaskingEnabled.checked,
+                patterns: parseMaskingPatterns(elements.sqlMaskingPatterns.value),
+            } : state.appSettings.sql_masking,
+            offline_mode: elements.offlineMode?.checked ?? state.appSettings.offline_mode,
+            save_attachment_content: elements.saveAttachmentContent?.checked
+                ?? state.appSettings.save_attachment_content,
+            suggest_calendar_events: elements.suggestCalendarEvents?.checked
+                ?? state.appSettings.suggest_calendar_events,
+            event_notifications: elements.eventNotificationsEnabled ? {
+                enabled: elements.eventNotificationsEnabled.checked,
+                default_reminder_minutes: parseInt(elements.eventReminderMinutes.value, 10) >= 0
+                    ? Math.min(parseInt(elements.eventReminderMinutes.value, 10), 10080)
+                    : state.appSettings.event_notifications.default_reminder_minutes,
+            } : state.appSettings.event_notifications,
+            log_level: elements.logLevel?.value || state.appSettings.log_level,
+            personalize_with_profile: elements.personalizeWithProfile?.checked
+                ?? state.appSettings.personalize_with_profile,
+            voice: elements.voiceLanguage ? {
+                language: elements.voiceLanguage.value || 'it',
+                whisper_model_path: elements.whisperModelPath.value.trim() || null,
+                read_aloud_language: elements.readAloudLanguage.value || 'auto',
+            } : state.appSettings.voice,
+        };
+        await invoke('save_app_settings', { settings: appSettings });
+        const timeFormatChanged = appSettings.time_format !== state.appSettings.time_format;
+        state.appSettings = appSettings;
+        if (timeFormatChanged && state.conversation.length > 0) {
+            renderConversation();
+        }
         
         if (elements.settingsStatus) {
             elements.settingsStatus.className = 'sql-status success';
@@ -2664,6 +4353,25 @@ async function saveSettings() {
     }
 }
 
+function updateProxyFieldsVisibility() {
+    if (elements.proxyFields) {
+        elements.proxyFields.classList.toggle('hidden', !elements.proxyEnabled.checked);
+    }
+}
+
+function readProxySettingsFromForm() {
+    if (!elements.proxyEnabled) {
+        return state.appSettings.proxy;
+    }
+    return {
+        enabled: elements.proxyEnabled.checked,
+        url: elements.proxyUrl.value.trim(),
+        username: elements.proxyUsername.value.trim() || null,
+        password: elements.proxyPassword.value || null,
+        no_proxy: elements.proxyNoProxy.value.trim(),
+    };
+}
+
 async function showSettingsModal() {
     await loadSettings();
     
@@ -2673,12 +4381,16 @@ async function showSettingsModal() {
         if (elements.dataDirPath) {
             elements.dataDirPath.textContent = dataDir;
         }
+        if (elements.logDirPath) {
+            elements.logDirPath.textContent = await invoke('get_log_path');
+        }
         if (elements.dataDirInfo) {
             elements.dataDirInfo.classList.remove('hidden');
         }
     } catch (error) {
         console.warn('Impossibile ottenere la directory dati:', error);
     }
+    await loadStorageStats();
     
     if (elements.settingsModal) {
         elements.settingsModal.classList.remove('hidden');
@@ -2708,6 +4420,45 @@ async function loadMemory() {
     }
 }
 
+// Attachments keep their name, path and text (the backend may drop or cut the text);
+// retrieval ids are rebuilt when the conversation is reopened
+function toStoredMessage(m) {
+    return {
+        role: m.role,
+        content: m.content,
+        hidden: m.hidden || false,
+        timestamp: m.timestamp || null,
+        metrics: m.metrics || null,
+        attachments: (m.attachments || []).map(a => ({
+            name: a.name,
+            path: a.path || null,
+            content: a.content ?? null,
+            truncated: a.truncated || false,
+        })),
+    };
+}
+
+// Older conversations sent the instructions as a hidden user message followed by a
+// hidden acknowledgement: they become a single system message, as new ones use
+function migrateHiddenInstructions(messages) {
+    const [first, second] = messages;
+    if (first?.hidden && first.role === 'user' && second?.hidden && second.role === 'assistant') {
+        return [{ ...first, role: 'system' }, ...messages.slice(2)];
+    }
+    return messages;
+}
+
+function fromStoredMessage(m) {
+    return {
+        role: m.role,
+        content: m.content,
+        hidden: m.hidden || false,
+        timestamp: m.timestamp || null,
+        metrics: m.metrics || null,
+        attachments: m.attachments || [],
+    };
+}
+
 async function saveCurrentConversation(options = {}) {
     const { force = false } = options;
 
@@ -2716,19 +4467,8 @@ async function saveCurrentConversation(options = {}) {
     if (!force && visibleMessages.length < 2) return;
     if (force && visibleMessages.length === 0) return;
     
-    // Generate a title from the first user message
-    const firstUserMessage = visibleMessages.find(m => m.role === 'user');
-    const title = firstUserMessage 
-        ? firstUserMessage.content.substring(0, 50) + (firstUserMessage.content.length > 50 ? '...' : '')
-        : 'Conversazione senza titolo';
-    
     // Convert conversation to memory format
-    const messages = state.conversation.map(m => ({
-        role: m.role,
-        content: m.content,
-        hidden: m.hidden || false,
-        timestamp: m.timestamp || null,
-    }));
+    const messages = state.conversation.map(toStoredMessage);
     
     try {
         if (state.currentConversationId) {
@@ -2736,17 +4476,18 @@ async function saveCurrentConversation(options = {}) {
             await invoke('update_conversation_in_memory', {
                 id: state.currentConversationId,
                 messages,
+                model: state.selectedModel,
             });
         } else {
-            // Add new conversation
+            // Add new conversation: the backend generates the title once the model has replied
             const id = await invoke('add_conversation_to_memory', {
-                title,
                 messages,
                 model: state.selectedModel,
             });
             state.currentConversationId = id;
         }
 
+        await clearDraft();
         await loadMemory();
         renderHistoryList();
     } catch (error) {
@@ -2754,16 +4495,119 @@ async function saveCurrentConversation(options = {}) {
     }
 }
 
-async function loadConversationFromMemory(conversationId) {
+// ============ DRAFT AUTOSAVE ============
+
+function conversationSignature() {
+    return `${state.currentConversationId || ''}:${state.conversation.length}:${state.currentIteration}`;
+}
+
+async function saveDraft() {
+    if (!state.conversation.some(m => !m.hidden)) return;
+
+    const signature = conversationSignature();
+    if (signature === state.draftSignature) return;
+
+    try {
+        await invoke('save_conversation_draft', {
+            draft: {
+                messages: state.conversation.map(toStoredMessage),
+                model: state.selectedModel,
+                conversation_id: state.currentConversationId,
+                agent_mode: state.agentMode,
+                current_iteration: state.currentIteration,
+                system_prompt_added: state.systemPromptAdded,
+                saved_at: new Date().toISOString(),
+            },
+        });
+        state.draftSignature = signature;
+    } catch (error) {
+        console.warn('Impossibile salvare la bozza della conversazione:', error);
+    }
+}
+
+async function clearDraft() {
+    try {
+        await invoke('clear_conversation_draft');
+        state.draftSignature = conversationSignature();
+    } catch (error) {
+        console.warn('Impossibile eliminare la bozza della conversazione:', error);
+    }
+}
+
+async function loadDraft() {
+    try {
+        state.pendingDraft = await invoke('load_conversation_draft');
+    } catch (error) {
+        console.warn('Impossibile leggere la bozza della conversazione:', error);
+        state.pendingDraft = null;
+    }
+}
+
+// Offers to resume the draft left by a previous session, once the models are loaded
+async function offerDraftRestore() {
+    const draft = state.pendingDraft;
+    state.pendingDraft = null;
+    if (!draft || !draft.messages || !draft.messages.some(m => !m.hidden)) return false;
+
+    const savedAt = new Date(draft.saved_at).toLocaleString('it-IT');
+    if (!confirm(`Riprendi conversazione precedente?\n\nÈ stata trovata una conversazione non salvata (${savedAt}).`)) {
+        await clearDraft();
+        return false;
+    }
+
+    state.conversation = migrateHiddenInstructions(draft.messages).map(fromStoredMessage);
+    state.currentConversationId = draft.conversation_id || null;
+    state.systemPromptAdded = draft.system_prompt_added;
+    state.currentIteration = draft.current_iteration;
+    state.agentMode = draft.agent_mode;
+    state.pendingToolCalls = [];
+    state.memoryContextInjected = true;
+    elements.agentModeToggle.checked = state.agentMode;
+    updateWebContextToggle();
+
+    if (draft.model && state.models.some(m => m.name === draft.model)) {
+        state.selectedModel = draft.model;
+        elements.modelSelector.value = draft.model;
+    }
+
+    state.draftSignature = conversationSignature();
+    renderConversation();
+    restoreAttachmentIndex();
+    updateIterationCounter();
+    renderHistoryList();
+    return true;
+}
+
+async function renameConversation(conversationId) {
     const conversation = state.memoryConversations.find(c => c.id === conversationId);
     if (!conversation) return;
+
+    const newTitle = prompt('Nuovo titolo della conversazione:', conversation.title);
+    if (newTitle === null || !newTitle.trim() || newTitle.trim() === conversation.title) return;
+
+    try {
+        await invoke('rename_conversation', { id: conversationId, newTitle: newTitle.trim() });
+        await loadMemory();
+        renderHistoryList();
+    } catch (error) {
+        showError(`Impossibile rinominare la conversazione: ${error}`);
+    }
+}
+
+async function loadConversationFromMemory(conversationId) {
+    // Archived conversations are reopened as new ones: continuing them saves a copy in the history
+    const archived = !state.memoryConversations.some(c => c.id === conversationId);
+    const conversation = archived
+        ? state.archivedConversations?.find(c => c.id === conversationId)
+        : state.memoryConversations.find(c => c.id === conversationId);
+    if (!conversation) return;
     
     // Clear current chat
     state.conversation = [];
     state.systemPromptAdded = false;
     state.currentIteration = 0;
     state.pendingToolCalls = [];
-    state.currentConversationId = conversationId;
+    state.currentConversationId = archived ? null : conversationId;
     state.memoryContextInjected = true;
     state.memoryContext = buildMemoryContext();
     
@@ -2776,22 +4620,18 @@ async function loadConversationFromMemory(conversationId) {
     }
     
     // Load messages
-    conversation.messages.forEach(m => {
-        state.conversation.push({
-            role: m.role,
-            content: m.content,
-            hidden: m.hidden || false,
-            timestamp: m.timestamp || null,
-        });
+    migrateHiddenInstructions(conversation.messages).forEach(m => {
+        state.conversation.push(fromStoredMessage(m));
         
         // Mark system prompt as added if it was in the saved conversation
-        if (m.hidden && m.role === 'user') {
+        if (m.hidden && (m.role === 'system' || m.role === 'user')) {
             state.systemPromptAdded = true;
         }
     });
     
     // Render messages
     renderConversation();
+    restoreAttachmentIndex();
 
     // Update sidebar highlight
     renderHistoryList();
@@ -2805,18 +4645,29 @@ function renderConversation() {
     if (visibleMessages.length === 0) {
         elements.messages.innerHTML = `
             <div class="empty-state">
-                <p class="empty-title">Inizia una conversazione</p>
-                <p class="empty-subtitle">Scrivi un messaggio per iniziare</p>
+                <p class="empty-title">${t('chat.emptyTitle')}</p>
+                <p class="empty-subtitle">${t('chat.emptySubtitle')}</p>
             </div>
         `;
         return;
     }
     
     visibleMessages.forEach(m => {
-        addMessage(m.role, m.content, m.timestamp);
+        addMessage(m.role, messageDisplayContent(m), m.timestamp, { metrics: m.metrics, message: m });
     });
 }
 
+// User messages with attachments show the file names instead of their text, as when sent.
+// The text follows the last "---" separator, attached files may contain their own.
+function messageDisplayContent(m) {
+    if (m.role !== 'user' || !m.attachments?.length) return m.content;
+
+    const separator = '\n\n---\n\n';
+    const index = m.content.lastIndexOf(separator);
+    const text = index === -1 ? m.content : m.content.slice(index + separator.length);
+    return m.attachments.map(a => `📎 ${a.name}`).join('\n') + '\n\n' + text;
+}
+
 async function deleteConversationFromMemory(conversationId) {
     try {
         await invoke('delete_conversation_from_memory', { id: conversationId });
@@ -2824,6 +4675,7 @@ async function deleteConversationFromMemory(conversationId) {
         if (state.currentConversationId === conversationId) {
             state.currentConversationId = null;
             state.conversation = [];
+            await clearDraft();
             state.pendingToolCalls = [];
             state.currentIteration = 0;
             state.attachedFiles = [];
@@ -2848,6 +4700,7 @@ async function clearAllConversations() {
     
     try {
         await invoke('clear_all_conversations');
+        await clearDraft();
         state.memoryConversations = [];
         state.currentConversationId = null;
         state.conversation = [];
@@ -2869,20 +4722,33 @@ async function clearAllConversations() {
 function renderHistoryList() {
     if (!elements.historyList) return;
 
+    const isEmpty = state.memoryConversations.length === 0;
     if (elements.clearHistoryBtn) {
-        const isEmpty = state.memoryConversations.length === 0;
         elements.clearHistoryBtn.disabled = isEmpty;
     }
+    if (elements.exportHistoryBtn) {
+        elements.exportHistoryBtn.disabled = isEmpty;
+    }
+
+    if (state.archivedConversations) {
+        renderArchivedConversations();
+        return;
+    }
     
     if (state.memoryConversations.length === 0) {
         elements.historyList.innerHTML = `
             <div class="empty-history">
-                <p>Nessuna conversazione salvata</p>
-                <small>Le conversazioni verranno salvate automaticamente quando le termini</small>
+                <p>${t('history.empty')}</p>
+                <small>${t('history.emptyHint')}</small>
             </div>
         `;
         return;
     }
+
+    if (state.historySearchResults) {
+        renderSemanticSearchResults();
+        return;
+    }
     
     // Sort by updated_at descending (most recent first)
     const sorted = [...state.memoryConversations].sort((a, b) => {
@@ -2908,6 +4774,8 @@ function renderHistoryList() {
                     ${isActive ? '<span class="history-item-status">Conversazione attiva</span>' : ''}
                 </div>
                 <div class="history-item-actions">
+                    <button class="rename-conv-btn" data-id="${escapeHtml(conv.id)}" title="Rinomina conversazione">✏️</button>
+                    <button class="share-conv-btn" data-id="${escapeHtml(conv.id)}" title="Esporta come pagina HTML da condividere">📤</button>
                     <button class="delete-conv-btn" data-id="${escapeHtml(conv.id)}" title="Elimina conversazione">🗑️</button>
                 </div>
             </div>
@@ -2915,6 +4783,20 @@ function renderHistoryList() {
     }).join('');
     
     // Add event listeners
+    elements.historyList.querySelectorAll('.rename-conv-btn').forEach(btn => {
+        btn.addEventListener('click', (e) => {
+            e.stopPropagation();
+            renameConversation(btn.dataset.id);
+        });
+    });
+
+    elements.historyList.querySelectorAll('.share-conv-btn').forEach(btn => {
+        btn.addEventListener('click', (e) => {
+            e.stopPropagation();
+            exportConversationHtml(btn.dataset.id);
+        });
+    });
+
     elements.historyList.querySelectorAll('.delete-conv-btn').forEach(btn => {
         btn.addEventListener('click', (e) => {
             e.stopPropagation();
@@ -2932,11 +4814,344 @@ function renderHistoryList() {
     });
 }
 
+async function importConversations() {
+    const dialogOpen = window.__TAURI__?.dialog?.open;
+    if (typeof dialogOpen !== 'function') {
+        showError('Finestra di selezione file non disponibile');
+        return;
+    }
+
+    try {
+        const path = await dialogOpen({
+            multiple: false,
+            filters: [{ name: 'Export ChatGPT o JSON/JSONL', extensions: ['json', 'jsonl'] }],
+        });
+        if (!path) return;
+
+        const count = await invoke('import_conversations', { path, format: 'auto' });
+        await loadMemory();
+        renderHistoryList();
+        window.alert(`Importate ${count} conversazioni`);
+    } catch (error) {
+        showError(`Importazione non riuscita: ${error}`);
+    }
+}
+
+async function exportConversationsJsonl() {
+    const dialogSave = window.__TAURI__?.dialog?.save;
+    if (typeof dialogSave !== 'function') {
+        showError('Finestra di salvataggio non disponibile');
+        return;
+    }
+
+    const model = state.selectedModel
+        && confirm(`Esportare solo le conversazioni fatte con ${state.selectedModel}?\nAnnulla per esportarle tutte.`)
+        ? state.selectedModel
+        : null;
+    const includeAttachments = confirm('Includere il contenuto dei file allegati?\nAnnulla per mantenere solo i nomi dei file.');
+
+    try {
+        const path = await dialogSave({
+            defaultPath: 'matepro-conversazioni.jsonl',
+            filters: [{ name: 'JSONL', extensions: ['jsonl'] }],
+        });
+        if (!path) return;
+
+        const count = await invoke('export_conversations_jsonl', { path, model, includeAttachments });
+        window.alert(`Esportate ${count} conversazioni in ${path}`);
+    } catch (error) {
+        showError(`Esportazione non riuscita: ${error}`);
+    }
+}
+
+// Read-only copy of a conversation, viewable in any browser without MatePro
+async function exportConversationHtml(conversationId) {
+    const conversation = state.memoryConversations.find(c => c.id === conversationId);
+    const dialogSave = window.__TAURI__?.dialog?.save;
+    if (!conversation) return;
+    if (typeof dialogSave !== 'function') {
+        showError('Finestra di salvataggio non disponibile');
+        return;
+    }
+
+    const fileName = conversation.title
+        .replace(/[\\/:*?"<>|]+/g, '')
+        .replace(/\s+/g, ' ')
+        .trim()
+        .slice(0, 80) || 'conversazione';
+
+    try {
+        const path = await dialogSave({
+            defaultPath: `${fileName}.html`,
+            filters: [{ name: 'HTML', extensions: ['html'] }],
+        });
+        if (!path) return;
+
+        await invoke('export_conversation_html', { id: conversationId, path });
+        window.alert(`Conversazione esportata in ${path}`);
+    } catch (error) {
+        showError(`Esportazione non riuscita: ${error}`);
+    }
+}
+
+async function chooseDataDirectory() {
+    const dialogOpen = window.__TAURI__?.dialog?.open;
+    if (typeof dialogOpen !== 'function') {
+        showError('Finestra di selezione cartella non disponibile');
+        return;
+    }
+
+    const path = await dialogOpen({ directory: true, multiple: false });
+    if (path) {
+        await changeDataDirectory(path);
+    }
+}
+
+// An empty path goes back to the default folder
+async function changeDataDirectory(path) {
+    const target = path || 'la cartella predefinita';
+    if (!window.confirm(`Spostare i dati di MatePro in ${target}?`)) {
+        return;
+    }
+
+    elements.changeDataDirBtn.disabled = true;
+    elements.resetDataDirBtn.disabled = true;
+    try {
+        const change = await invoke('set_data_directory', { path });
+        elements.dataDirPath.textContent = change.path;
+
+        let message = `✓ Cartella dati: ${change.path} (${change.migrated} elementi spostati)`;
+        if (change.kept_existing.length) {
+            message += `. Già presenti e mantenuti: ${change.kept_existing.join(', ')}`;
+        }
+        elements.settingsStatus.className = 'sql-status success';
+        elements.settingsStatus.textContent = message;
+        elements.settingsStatus.classList.remove('hidden');
+
+        // The new folder may already hold data from another device
+        await loadMemory();
+        renderHistoryList();
+        await loadStorageStats();
+    } catch (error) {
+        showError(`Impossibile cambiare la cartella dati: ${error}`);
+    } finally {
+        elements.changeDataDirBtn.disabled = false;
+        elements.resetDataDirBtn.disabled = false;
+    }
+}
+
+function formatBytes(bytes) {
+    const units = ['B', 'KB', 'MB', 'GB'];
+    let value = bytes;
+    let unit = 0;
+    while (value >= 1024 && unit < units.length - 1) {
+        value /= 1024;
+        unit++;
+    }
+    return unit === 0 ? `${value} B` : `${value.toFixed(1)} ${units[unit]}`;
+}
+
+async function loadStorageStats() {
+    if (!elements.storageInfo) return;
+
+    try {
+        const stats = await invoke('get_storage_stats');
+        const rows = [
+            ['Totale', formatBytes(stats.total_bytes)],
+            ['Conversazioni', `${stats.conversations} (${stats.archived_conversations} in archivio)`],
+            ['Cronologia e archivi', formatBytes(stats.memory_bytes)],
+            ['Calendario', formatBytes(stats.calendar_bytes)],
+            ['Cache (indici documenti e ricerca)', formatBytes(stats.cache_bytes)],
+            ['Log', formatBytes(stats.logs_bytes)],
+            ['Altro (impostazioni, bozze, modelli vocali)', formatBytes(stats.other_bytes)],
+        ];
+        elements.storageStats.innerHTML = rows
+            .map(([label, value]) => `<li><span>${label}</span><span>${escapeHtml(value)}</span></li>`)
+            .join('');
+        elements.storageInfo.classList.remove('hidden');
+    } catch (error) {
+        console.warn('Impossibile calcolare lo spazio occupato:', error);
+    }
+}
+
+async function cleanupStorage() {
+    elements.cleanupStorageBtn.disabled = true;
+    try {
+        const cleanup = await invoke('cleanup_storage');
+        let message = `✓ Liberati ${formatBytes(cleanup.freed_bytes)}: ${cleanup.removed_documents} documenti e ${cleanup.removed_conversation_embeddings} conversazioni rimossi dalla cache`;
+        if (cleanup.removed_draft) {
+            message += ', bozza orfana eliminata';
+        }
+        elements.settingsStatus.className = 'sql-status success';
+        elements.settingsStatus.textContent = message;
+        elements.settingsStatus.classList.remove('hidden');
+        await loadStorageStats();
+    } catch (error) {
+        showError(`Impossibile pulire la cartella dati: ${error}`);
+    } finally {
+        elements.cleanupStorageBtn.disabled = false;
+    }
+}
+
+async function generateDiagnosticReport() {
+    const dialogSave = window.__TAURI__?.dialog?.save;
+    if (typeof dialogSave !== 'function') {
+        showError('Finestra di salvataggio non disponibile');
+        return;
+    }
+
+    try {
+        const date = new Date().toISOString().slice(0, 10);
+        const path = await dialogSave({
+            defaultPath: `matepro-diagnostica-${date}.json`,
+            filters: [{ name: 'JSON', extensions: ['json'] }],
+        });
+        if (!path) return;
+
+        elements.diagnosticReportBtn.disabled = true;
+        const savedPath = await invoke('generate_diagnostic_report', { path });
+        window.alert(`Report diagnostico salvato in ${savedPath}\nControllalo prima di allegarlo a una segnalazione.`);
+    } catch (error) {
+        showError(`Report diagnostico non riuscito: ${error}`);
+    } finally {
+        elements.diagnosticReportBtn.disabled = false;
+    }
+}
+
+async function runSemanticSearch() {
+    const query = elements.historySearch?.value.trim();
+    if (!query) {
+        clearSemanticSearch();
+        return;
+    }
+
+    elements.historyList.innerHTML = `
+        <div class="empty-history">
+            <p>Ricerca in corso...</p>
+            <small>Le conversazioni nuove o modificate vengono indicizzate alla prima ricerca</small>
+        </div>
+    `;
+
+    try {
+        state.historySearchResults = await invoke('semantic_search', { query, limit: 10 });
+    } catch (error) {
+        state.historySearchResults = null;
+        showError(`Ricerca semantica non riuscita: ${error}`);
+    }
+    renderHistoryList();
+}
+
+function clearSemanticSearch() {
+    if (state.historySearchResults === null) return;
+    state.historySearchResults = null;
+    renderHistoryList();
+}
+
+function renderSemanticSearchResults() {
+    // Conversations deleted after the search are dropped from the results
+    const results = state.historySearchResults
+        .filter(match => state.memoryConversations.some(conv => conv.id === match.conversation_id));
+    if (results.length === 0) {
+        elements.historyList.innerHTML = `
+            <div class="empty-history">
+                <p>Nessuna conversazione simile</p>
+                <small>Prova a descrivere l'argomento con altre parole</small>
+            </div>
+        `;
+        return;
+    }
+
+    elements.historyList.innerHTML = results.map(match => {
+        const date = new Date(match.updated_at);
+        const isActive = state.currentConversationId === match.conversation_id;
+
+        return `
+            <div class="history-item${isActive ? ' active' : ''}" data-id="${escapeHtml(match.conversation_id)}">
+                <div class="history-item-content">
+                    <div class="history-item-title">${escapeHtml(match.title)}</div>
+                    ${match.snippet ? `<div class="history-item-snippet">${escapeHtml(match.snippet)}</div>` : ''}
+                    <div class="history-item-meta">
+                        <span>🎯 ${Math.round(match.score * 100)}%</span>
+                        <span>📅 ${date.toLocaleDateString()}</span>
+                    </div>
+                </div>
+            </div>
+        `;
+    }).join('');
+
+    elements.historyList.querySelectorAll('.history-item').forEach(item => {
+        item.addEventListener('click', () => {
+            loadConversationFromMemory(item.dataset.id);
+        });
+    });
+}
+
+async function toggleMemoryArchive() {
+    if (state.archivedConversations) {
+        state.archivedConversations = null;
+        elements.archiveHistoryBtn.textContent = 'Archivio';
+        renderHistoryList();
+        return;
+    }
+
+    try {
+        const archives = await invoke('list_memory_archives');
+        const conversations = [];
+        for (const archive of archives) {
+            const memory = await invoke('load_memory_archive', { fileName: archive.file_name });
+            conversations.push(...memory.conversations);
+        }
+        conversations.sort((a, b) => new Date(b.updated_at) - new Date(a.updated_at));
+
+        state.archivedConversations = conversations;
+        elements.archiveHistoryBtn.textContent = 'Cronologia';
+        renderHistoryList();
+    } catch (error) {
+        showError(`Impossibile leggere l'archivio: ${error}`);
+    }
+}
+
+function renderArchivedConversations() {
+    if (state.archivedConversations.length === 0) {
+        elements.historyList.innerHTML = `
+            <div class="empty-history">
+                <p>Archivio vuoto</p>
+                <small>Le conversazioni più vecchie vengono archiviate quando la cronologia supera i limiti impostati</small>
+            </div>
+        `;
+        return;
+    }
+
+    elements.historyList.innerHTML = state.archivedConversations.map(conv => {
+        const date = new Date(conv.updated_at);
+        const msgCount = conv.messages.filter(m => !m.hidden).length;
+
+        return `
+            <div class="history-item" data-id="${escapeHtml(conv.id)}" title="Apri la conversazione archiviata: continuandola ne verrà salvata una copia nella cronologia">
+                <div class="history-item-content">
+                    <div class="history-item-title">📦 ${escapeHtml(conv.title)}</div>
+                    <div class="history-item-meta">
+                        <span>📅 ${date.toLocaleDateString()}</span>
+                        <span>💬 ${msgCount} messaggi</span>
+                    </div>
+                </div>
+            </div>
+        `;
+    }).join('');
+
+    elements.historyList.querySelectorAll('.history-item').forEach(item => {
+        item.addEventListener('click', () => {
+            loadConversationFromMemory(item.dataset.id);
+        });
+    });
+}
+
 // ============ NEW CHAT / DISCONNECT ============
 
 async function newChat() {
     // Save current conversation before starting new one
     await saveCurrentConversation();
+    await clearDraft();
     
     state.conversation = [];
     state.attachedFiles = [];
@@ -2950,8 +5165,8 @@ async function newChat() {
     
     elements.messages.innerHTML = `
         <div class="empty-state">
-            <p class="empty-title">Inizia una conversazione</p>
-            <p class="empty-subtitle">Scrivi un messaggio per iniziare</p>
+            <p class="empty-title">${t('chat.emptyTitle')}</p>
+            <p class="empty-subtitle">${t('chat.emptySubtitle')}</p>
         </div>
     `;
     
@@ -2964,10 +5179,12 @@ async function newChat() {
 async function disconnect() {
     // Save current conversation before disconnecting
     await saveCurrentConversation();
+    await clearDraft();
     
     state.conversation = [];
     state.models = [];
     state.selectedModel = null;
+    state.selectedNode = null;
     state.attachedFiles = [];
     state.systemPromptAdded = false;
     state.currentIteration = 0;
@@ -2976,6 +5193,7 @@ async function disconnect() {
     state.currentConversationId = null;
     state.memoryContextInjected = false;
     state.memoryContext = buildMemoryContext();
+    hideAiConnectNodes();
     
     showScreen('setup-screen');
     elements.setupError.classList.add('hidden');
@@ -2983,6 +5201,11 @@ async function disconnect() {
     updateAttachedFiles();
     updateIterationCounter();
     hideError();
+
+    // After an automatic reconnection the server list was never filled
+    if (elements.servers.children.length === 0) {
+        scanNetwork();
+    }
 }
 
 // ============ EVENT LISTENERS ============
@@ -2996,21 +5219,41 @@ function initEventListeners() {
     });
     
     // Chat
+    elements.modelInfoBtn.addEventListener('click', showModelDetails);
+    elements.closeModelInfoModal.addEventListener('click', hideModelDetails);
+    elements.nodesBtn.addEventListener('click', showAiConnectNodes);
+    elements.closeNodesModal.addEventListener('click', hideAiConnectNodes);
     elements.modelSelector.addEventListener('change', (e) => {
         state.selectedModel = e.target.value;
+        saveLastBackend();
+        renderNodeSelector();
+    });
+    elements.nodeSelector.addEventListener('change', (e) => {
+        state.selectedNode = e.target.value || null;
     });
     
     elements.agentModeToggle.addEventListener('change', (e) => {
         state.agentMode = e.target.checked;
         updateIterationCounter();
+        updateWebContextToggle();
+    });
+    elements.webContextToggle.addEventListener('change', (e) => {
+        state.webContext = e.target.checked;
     });
     
     elements.sendBtn.addEventListener('click', sendMessage);
     elements.attachBtn.addEventListener('click', attachFile);
+    elements.micBtn?.addEventListener('click', toggleVoiceInput);
     elements.fileInput.addEventListener('change', handleFileSelect);
     
     elements.messageInput.addEventListener('input', updateSendButton);
     elements.messageInput.addEventListener('keydown', (e) => {
+        if (e.key === 'Escape' && state.voiceInput === 'recording') {
+            e.preventDefault();
+            cancelVoiceInput();
+            return;
+        }
+
         if (e.key === 'Enter' && (e.ctrlKey || e.metaKey)) {
             e.preventDefault();
             sendMessage();
@@ -3053,9 +5296,11 @@ function initEventListeners() {
     
     // SQL Modal
     elements.sqlConfigBtn.addEventListener('click', showSqlModal);
+    elements.undoFileBtn.addEventListener('click', undoLastFileOperation);
     elements.closeSqlModal.addEventListener('click', hideSqlModal);
     elements.closeSqlBtn.addEventListener('click', hideSqlModal);
     elements.testSqlBtn.addEventListener('click', testSqlConnection);
+    elements.sqlDbType.addEventListener('change', updateSqlDbType);
     
     document.querySelectorAll('input[name="sql-auth"]').forEach(radio => {
         radio.addEventListener('change', updateSqlAuth);
@@ -3074,14 +5319,76 @@ function initEventListeners() {
     if (elements.saveSettingsBtn) {
         elements.saveSettingsBtn.addEventListener('click', saveSettings);
     }
+    if (elements.checkUpdatesBtn) {
+        elements.checkUpdatesBtn.addEventListener('click', checkForUpdatesManually);
+    }
+    if (elements.diagnosticReportBtn) {
+        elements.diagnosticReportBtn.addEventListener('click', generateDiagnosticReport);
+    }
+    if (elements.changeDataDirBtn) {
+        elements.changeDataDirBtn.addEventListener('click', chooseDataDirectory);
+        elements.resetDataDirBtn.addEventListener('click', () => changeDataDirectory(''));
+    }
+    if (elements.cleanupStorageBtn) {
+        elements.cleanupStorageBtn.addEventListener('click', cleanupStorage);
+    }
+    if (elements.proxyEnabled) {
+        elements.proxyEnabled.addEventListener('change', updateProxyFieldsVisibility);
+    }
+
+    // Update Banner
+    if (elements.updateNowBtn) {
+        elements.updateNowBtn.addEventListener('click', installAvailableUpdate);
+    }
+    if (elements.updateLaterBtn) {
+        elements.updateLaterBtn.addEventListener('click', hideUpdateBanner);
+    }
     
     // History Sidebar
     if (elements.clearHistoryBtn) {
         elements.clearHistoryBtn.addEventListener('click', clearAllConversations);
     }
+    if (elements.exportHistoryBtn) {
+        elements.exportHistoryBtn.addEventListener('click', exportConversationsJsonl);
+    }
+    if (elements.archiveHistoryBtn) {
+        elements.archiveHistoryBtn.addEventListener('click', toggleMemoryArchive);
+    }
+    if (elements.importHistoryBtn) {
+        elements.importHistoryBtn.addEventListener('click', importConversations);
+    }
+    if (elements.historySearch) {
+        elements.historySearch.addEventListener('keydown', (e) => {
+            if (e.key === 'Enter') {
+                e.preventDefault();
+                runSemanticSearch();
+            }
+        });
+        elements.historySearch.addEventListener('input', () => {
+            if (!elements.historySearch.value.trim()) {
+                clearSemanticSearch();
+            }
+        });
+    }
     if (elements.clearCalendarBtn) {
         elements.clearCalendarBtn.addEventListener('click', clearAllCalendarEvents);
     }
+    if (elements.addCalendarEventBtn) {
+        elements.addCalendarEventBtn.addEventListener('click', toggleCalendarQuickAdd);
+    }
+    if (elements.calendarNotificationsBtn) {
+        elements.calendarNotificationsBtn.addEventListener('click', toggleEventNotifications);
+    }
+    if (elements.calendarQuickText) {
+        elements.calendarQuickText.addEventListener('keydown', (e) => {
+            if (e.key === 'Enter') {
+                e.preventDefault();
+                parseCalendarQuickText();
+            } else if (e.key === 'Escape') {
+                elements.calendarQuickAdd.classList.add('hidden');
+            }
+        });
+    }
     if (elements.exportCalendarBtn) {
         elements.exportCalendarBtn.addEventListener('click', exportCalendarAsIcs);
     }
@@ -3108,19 +5415,47 @@ function initEventListeners() {
 
 // ============ INITIALIZATION ============
 
+// Data changed by a sync client or another MatePro instance
+async function reloadChangedStorage(kind) {
+    if (kind === 'memory') {
+        await loadMemory();
+        renderHistoryList();
+    } else if (kind === 'calendar') {
+        await loadCalendarEventsFromStore();
+    } else if (kind === 'settings' && elements.settingsModal?.classList.contains('hidden')) {
+        // Do not overwrite the form while the user is editing it
+        await loadSettings();
+    }
+}
+
 async function init() {
     initEventListeners();
     elements.agentModeToggle.checked = state.agentMode;
     updateIterationCounter();
+    updateWebContextToggle();
     await loadVersionIndicator();
+    await loadUiLanguage();
     await loadGreeting();
     await loadSettings();
     await loadMemory();
+    await loadDraft();
+    setInterval(saveDraft, DRAFT_AUTOSAVE_INTERVAL_MS);
+    await window.__TAURI__.event?.listen('conversation-title-updated', async () => {
+        await loadMemory();
+        renderHistoryList();
+    });
+    await window.__TAURI__.event?.listen('local-storage-changed', (event) => reloadChangedStorage(event.payload));
+    await window.__TAURI__.event?.listen('calendar-reminder', (event) => showCalendarStatus(`🔔 ${event.payload}`));
+    await window.__TAURI__.event?.listen('scan_progress', (event) => renderScanProgress(event.payload));
+    await initFileDrop();
     await loadCalendarEventsFromStore();
     await refreshCalendarIntegrationsStatus({ silent: true });
+    reportCalendarsToReconnect();
     renderHistoryList();
     checkForUpdates();
-    await scanNetwork();
+    if (!(await reconnectLastBackend())) {
+        await scanNetwork();
+    }
 }
 
 // Start the app
@@ -0,0 +1,227 @@
+// ============ LOCALIZATION ============
+// Minimal UI translations. Italian is the source language: any key missing in
+// the active language falls back to it.
+
+const I18N_FALLBACK_LANGUAGE = 'it';
+
+const I18N_STRINGS = {
+    it: {
+        'update.notes': 'Novità della versione',
+        'update.now': 'Aggiorna',
+        'update.later': 'Più tardi',
+        'setup.subtitle': 'Connettiti a un\'istanza Ollama per iniziare',
+        'setup.scanning': 'Ricerca server in corso...',
+        'setup.scanProgress': '{checked}/{total} host · {found} server',
+        'setup.availableServers': 'Server disponibili:',
+        'setup.serverUrl': 'URL del server:',
+        'setup.connect': 'Connetti',
+        'setup.rescan': '🔄 Ricarica',
+        'connect.invalidUrl': 'Inserisci un URL valido',
+        'connect.aiconnect': 'Connessione ad AIConnect...',
+        'connect.server': 'Connessione al server...',
+        'connect.lastBackend': 'Riconnessione a {url}...',
+        'connect.askToken': 'Token di accesso per {url}:',
+        'connect.askPassword': 'Password di {username} per {url}:',
+        'connect.failed': 'Impossibile connettersi a Ollama su {url}',
+        'connect.failedReason': 'Impossibile connettersi a {url}. {reason}',
+        'models.loading': 'Caricamento modelli...',
+        'models.none': 'Nessun modello disponibile. Scarica un modello con "ollama pull <model>"',
+        'models.fit.gpu': 'GPU ✓',
+        'models.fit.partial': 'GPU/CPU ⚠️',
+        'models.fit.cpu': 'CPU ⚠️ lento',
+        'models.fitHint.gpu': 'Il modello entra nella memoria della GPU',
+        'models.fitHint.partial': 'Il modello supera la memoria della GPU: una parte girerà su CPU/RAM',
+        'models.fitHint.cpu': 'Nessuna GPU adatta: il modello girerà su CPU/RAM e sarà lento',
+        'nodes.title': '🖧 Nodi AIConnect',
+        'nodes.loading': 'Caricamento nodi...',
+        'nodes.empty': 'Nessun nodo registrato',
+        'nodes.error': 'Errore caricamento nodi: {error}',
+        'nodes.summary': '{online} di {total} nodi online',
+        'nodes.models': 'Modelli',
+        'nodes.noModels': 'Nessun modello',
+        'nodes.active': 'Richieste attive',
+        'nodes.queued': 'In coda',
+        'nodes.cpu': 'CPU',
+        'nodes.gpu': 'GPU',
+        'nodes.memory': 'Memoria',
+        'nodes.details': 'Altri dettagli',
+        'nodes.updated': 'Aggiornato alle {time}',
+        'header.model': 'Modello',
+        'header.selectModel': 'Seleziona modello...',
+        'header.modelInfo': 'Dettagli modello',
+        'header.agentMode': 'Modalità agente',
+        'header.webContext': 'Ricerca web',
+        'header.webContextTitle': 'Cerca sul web e aggiunge i risultati al contesto di ogni messaggio',
+        'header.sql': 'Configura SQL Server',
+        'header.undoFile': '↩️ Annulla file ({count})',
+        'header.undoFileTitle': 'Annulla l\'ultima modifica ai file fatta dall\'agente',
+        'header.undoFileFailed': 'Impossibile annullare l\'operazione: {error}',
+        'header.settings': 'Impostazioni',
+        'header.node': 'Nodo su cui eseguire la richiesta',
+        'header.autoNode': '⚖️ Nodo automatico',
+        'header.nodes': 'Nodi',
+        'header.nodesTitle': 'Nodi AIConnect',
+        'header.newChat': 'Nuova chat',
+        'header.disconnect': 'Disconnetti',
+        'history.title': '📜 Cronologia',
+        'history.archive': 'Archivio',
+        'history.import': 'Importa',
+        'history.export': 'Esporta',
+        'history.clear': 'Svuota',
+        'history.search': '🔎 Cerca per significato...',
+        'history.empty': 'Nessuna conversazione salvata',
+        'history.emptyHint': 'Le conversazioni verranno salvate automaticamente quando le termini',
+        'calendar.title': '🗓️ Calendario',
+        'calendar.add': 'Nuovo',
+        'calendar.quickPlaceholder': 'es. pranzo con Marco venerdì alle 13',
+        'calendar.export': 'Esporta',
+        'calendar.clear': 'Svuota',
+        'calendar.empty': 'Nessun evento registrato',
+        'calendar.emptyHint': 'Quando citi un impegno in chat, MatePro ti proporrà di aggiungerlo qui',
+        'chat.emptyTitle': 'Inizia una conversazione',
+        'chat.emptySubtitle': 'Scrivi un messaggio per iniziare',
+        'input.placeholder': 'Scrivi un messaggio...',
+        'input.attach': 'Allega file',
+        'input.send': 'Invia (Ctrl+Enter)',
+        'input.hint': 'Premi Ctrl+Enter per inviare',
+        'input.dictate': 'Detta un messaggio',
+        'input.dictateStop': 'Termina la dettatura',
+        'input.transcribing': 'Trascrizione in corso...',
+        'input.dictationFailed': 'Dettatura non riuscita: {error}',
+        'input.dropHint': '📎 Rilascia per allegare',
+        'input.dropUnsupported': 'Formato non supportato, file non allegati: {files} (usa {formats})',
+        'confirm.title': '⚠️ Conferma Operazione',
+        'confirm.intro': 'L\'agente vuole eseguire un\'operazione potenzialmente pericolosa:',
+        'confirm.allow': '✓ Consenti',
+        'confirm.cancel': '✕ Annulla',
+        'confirm.preview': 'Cosa succederebbe:',
+        'confirm.previewFailed': 'Anteprima non disponibile: {error}',
+    },
+    en: {
+        'update.notes': 'What\'s new',
+        'update.now': 'Update',
+        'update.later': 'Later',
+        'setup.subtitle': 'Connect to an Ollama instance to get started',
+        'setup.scanning': 'Looking for servers...',
+        'setup.scanProgress': '{checked}/{total} hosts · {found} servers',
+        'setup.availableServers': 'Available servers:',
+        'setup.serverUrl': 'Server URL:',
+        'setup.connect': 'Connect',
+        'setup.rescan': '🔄 Rescan',
+        'connect.invalidUrl': 'Enter a valid URL',
+        'connect.aiconnect': 'Connecting to AIConnect...',
+        'connect.server': 'Connecting to the server...',
+        'connect.lastBackend': 'Reconnecting to {url}...',
+        'connect.askToken': 'Access token for {url}:',
+        'connect.askPassword': 'Password of {username} for {url}:',
+        'connect.failed': 'Unable to connect to Ollama at {url}',
+        'connect.failedReason': 'Unable to connect to {url}. {reason}',
+        'models.loading': 'Loading models...',
+        'models.none': 'No models available. Download one with "ollama pull <model>"',
+        'models.fit.gpu': 'GPU ✓',
+        'models.fit.partial': 'GPU/CPU ⚠️',
+        'models.fit.cpu': 'CPU ⚠️ slow',
+        'models.fitHint.gpu': 'The model fits in the GPU memory',
+        'models.fitHint.partial': 'The model exceeds the GPU memory: part of it will run on CPU/RAM',
+        'models.fitHint.cpu': 'No suitable GPU: the model will run on CPU/RAM and be slow',
+        'nodes.title': '🖧 AIConnect nodes',
+        'nodes.loading': 'Loading nodes...',
+        'nodes.empty': 'No registered nodes',
+        'nodes.error': 'Failed to load nodes: {error}',
+        'nodes.summary': '{online} of {total} nodes online',
+        'nodes.models': 'Models',
+        'nodes.noModels': 'No models',
+        'nodes.active': 'Active requests',
+        'nodes.queued': 'Queued',
+        'nodes.cpu': 'CPU',
+        'nodes.gpu': 'GPU',
+        'nodes.memory': 'Memory',
+        'nodes.details': 'More details',
+        'nodes.updated': 'Updated at {time}',
+        'header.model': 'Model',
+        'header.selectModel': 'Select model...',
+        'header.modelInfo': 'Model details',
+        'header.agentMode': 'Agent mode',
+        'header.webContext': 'Web search',
+        'header.webContextTitle': 'Search the web and add the results to the context of every message',
+        'header.sql': 'Configure SQL Server',
+        'header.undoFile': '↩️ Undo file ({count})',
+        'header.undoFileTitle': 'Undo the last file change made by the agent',
+        'header.undoFileFailed': 'Could not undo the operation: {error}',
+        'header.settings': 'Settings',
+        'header.node': 'Node that runs the request',
+        'header.autoNode': '⚖️ Automatic node',
+        'header.nodes': 'Nodes',
+        'header.nodesTitle': 'AIConnect nodes',
+        'header.newChat': 'New chat',
+        'header.disconnect': 'Disconnect',
+        'history.title': '📜 History',
+        'history.archive': 'Archive',
+        'history.import': 'Import',
+        'history.export': 'Export',
+        'history.clear': 'Clear',
+        'history.search': '🔎 Search by meaning...',
+        'history.empty': 'No saved conversations',
+        'history.emptyHint': 'Conversations are saved automatically when you finish them',
+        'calendar.title': '🗓️ Calendar',
+        'calendar.add': 'New',
+        'calendar.quickPlaceholder': 'e.g. pranzo con Marco venerdì alle 13',
+        'calendar.export': 'Export',
+        'calendar.clear': 'Clear',
+        'calendar.empty': 'No events yet',
+        'calendar.emptyHint': 'When you mention an appointment in the chat, MatePro offers to add it here',
+        'chat.emptyTitle': 'Start a conversation',
+        'chat.emptySubtitle': 'Type a message to begin',
+        'input.placeholder': 'Type a message...',
+        'input.attach': 'Attach file',
+        'input.send': 'Send (Ctrl+Enter)',
+        'input.hint': 'Press Ctrl+Enter to send',
+        'input.dictate': 'Dictate a message',
+        'input.dictateStop': 'Stop dictation',
+        'input.transcribing': 'Transcribing...',
+        'input.dictationFailed': 'Dictation failed: {error}',
+        'input.dropHint': '📎 Drop to attach',
+        'input.dropUnsupported': 'Unsupported format, files not attached: {files} (use {formats})',
+        'confirm.title': '⚠️ Confirm action',
+        'confirm.intro': 'The agent wants to run a potentially dangerous operation:',
+        'confirm.allow': '✓ Allow',
+        'confirm.cancel': '✕ Cancel',
+        'confirm.preview': 'What would happen:',
+        'confirm.previewFailed': 'Preview not available: {error}',
+    },
+};
+
+let currentLanguage = I18N_FALLBACK_LANGUAGE;
+
+// Uses the first candidate (e.g. "en_US", "it-IT") with a translation
+function setLanguage(candidates) {
+    const supported = candidates
+        .map(tag => (typeof tag === 'string' ? tag.trim().toLowerCase().split(/[-_]/)[0] : ''))
+        .find(code => I18N_STRINGS[code]);
+
+    currentLanguage = supported || I18N_FALLBACK_LANGUAGE;
+    document.documentElement.lang = currentLanguage;
+}
+
+// Translated string for `key`; `{name}` placeholders are replaced from `vars`
+function t(key, vars = {}) {
+    const text = I18N_STRINGS[currentLanguage]?.[key]
+        ?? I18N_STRINGS[I18N_FALLBACK_LANGUAGE][key]
+        ?? key;
+
+    return text.replace(/\{(\w+)\}/g, (match, name) => (name in vars ? String(vars[name]) : match));
+}
+
+// Applies translations to elements marked with data-i18n, data-i18n-placeholder
+// and data-i18n-title
+function applyTranslations(root = document) {
+    root.querySelectorAll('[data-i18n]').forEach(el => {
+        el.textContent = t(el.dataset.i18n);
+    });
+    root.querySelectorAll('[data-i18n-placeholder]').forEach(el => {
+        el.placeholder = t(el.dataset.i18nPlaceholder);
+    });
+    root.querySelectorAll('[data-i18n-title]').forEach(el => {
+        el.title = t(el.dataset.i18nTitle);
+    });
+}
@@ -1,31 +1,54 @@
 // Agent module - Tool system for agentic features
 // Migrated from egui app to Tauri backend
 
-use crate::mcp_sql;
+use crate::calendar_integration::{self, RemoteCalendarEvent};
+use crate::event_parser::{self, CalendarEventInput};
+use crate::http_client;
+use crate::local_storage::{self, CalendarEvent, TranslationSettings};
+use crate::mcp_sql::{self, SqlBackend};
 use anyhow::{anyhow, Context, Result};
+use base64::Engine;
 use calamine::{open_workbook, Data, Ods, Range, Reader, Xls, Xlsx};
-use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc, Weekday,
+};
 use html_escape::decode_html_entities;
+use image::{ImageFormat, Luma};
 use lazy_static::lazy_static;
 use lopdf::Document;
+use pulldown_cmark::{
+    Event as MarkdownEvent, Options as MarkdownOptions, Parser as MarkdownParser,
+    Tag as MarkdownTag, TagEnd,
+};
+use qrcode::types::QrError;
+use qrcode::QrCode;
 use regex::Regex;
 use reqwest::Client;
+use rust_xlsxwriter::{Format, Workbook};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs;
 use std::future::Future;
-use std::io::{BufReader, ErrorKind, Read};
-use std::path::Path;
+use std::io::{BufReader, Cursor, ErrorKind, Read, Seek};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 use sysinfo::System;
+use tempfile::TempDir;
 use tokio::sync::Mutex;
 use url::Url;
 use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
+use zip::result::ZipError;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
 
 /// Tool definition with name, description and parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +82,13 @@ pub struct ToolResult {
     pub output: String,
     pub error: Option<String>,
     pub tool_name: String,
+    /// Image shown in the chat as a data URL, never sent to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Event proposed by `parse_calendar_event`, added to the calendar only
+    /// once the user confirms it in the chat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_event: Option<CalendarEventInput>,
 }
 
 impl ToolResult {
@@ -117,6 +147,71 @@ lazy_static! {
     static ref QUERY_DOMANI_RE: Regex = Regex::new(r"(?i)\bdomani\b").unwrap();
 }
 
+/// Timeout for web search, news and translation requests
+const WEB_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Limits for `web_fetch`: download size, redirects and returned text
+const WEB_FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+const WEB_FETCH_MAX_BYTES: usize = 2 * 1024 * 1024;
+const WEB_FETCH_MAX_REDIRECTS: usize = 5;
+const WEB_FETCH_DEFAULT_CHARS: usize = 8000;
+const WEB_FETCH_MAX_CHARS: usize = 20000;
+/// Below this much text the page is most likely rendered by JavaScript
+const WEB_FETCH_MIN_TEXT: usize = 200;
+
+/// Alternative titles suggested by `wikipedia_lookup` for missing or ambiguous pages
+const WIKIPEDIA_SUGGESTIONS: usize = 5;
+
+/// Limits for `zip_list` / `zip_extract_text`: listed entries, uncompressed size read
+/// into memory and returned text
+const ZIP_LIST_MAX_ENTRIES: usize = 500;
+const ZIP_EXTRACT_MAX_BYTES: u64 = 20 * 1024 * 1024;
+const ZIP_EXTRACT_MAX_CHARS: usize = 20000;
+
+/// Limits for `file_search`: folder depth, results and size of the files searched by content
+const FILE_SEARCH_MAX_DEPTH: usize = 8;
+const FILE_SEARCH_DEFAULT_RESULTS: usize = 50;
+const FILE_SEARCH_MAX_RESULTS: usize = 200;
+const FILE_SEARCH_MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const FILE_SEARCH_LINE_CHARS: usize = 160;
+
+/// Entries listed by `disk_usage`
+const DISK_USAGE_DEFAULT_TOP: usize = 10;
+const DISK_USAGE_MAX_TOP: usize = 50;
+
+/// Upper bound for the `timeout_secs` of `sql_query`
+const SQL_QUERY_MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Tools that contact external hosts, disabled in offline mode
+const NETWORK_TOOLS: &[&str] = &[
+    "web_search",
+    "web_fetch",
+    "text_translate",
+    "browser_open",
+    "map_open",
+    "youtube_search",
+    "wikipedia_lookup",
+    "network_info",
+    "calendar_list_events",
+    "calendar_sync",
+];
+
+/// Tools that write a file, with the parameter holding its path: the previous content is
+/// backed up so `undo_last_file_operation` can restore it
+const FILE_WRITING_TOOLS: &[(&str, &str)] = &[
+    ("file_write", "path"),
+    ("zip_create", "dest_path"),
+    ("excel_write", "path"),
+    ("convert_document", "dest_path"),
+    ("generate_qr", "dest_path"),
+];
+
+/// Minimum side in pixels of the images made by `generate_qr`
+const QR_MIN_SIZE: u32 = 256;
+
+/// File operations that can be undone; older backups are deleted
+const FILE_UNDO_DEPTH: usize = 20;
+
 const TRUSTED_DOMAINS: &[&str] = &[
     "ansa.it",
     "repubblica.it",
@@ -158,13 +253,152 @@ enum QueryIntent {
     General,
 }
 
+/// A file written by a tool, with the copy needed to restore it
+#[derive(Debug)]
+struct FileOperation {
+    tool_name: String,
+    path: PathBuf,
+    /// Previous content; `None` when the tool created the file
+    backup: Option<PathBuf>,
+}
+
+/// Undo history of the files written by the agent's tools
+#[derive(Debug)]
+pub struct FileOperationHistory {
+    /// Where the private backup folder is created
+    backup_root: PathBuf,
+    /// Created on the first backup with a random name, readable only by the
+    /// user, and deleted with the history
+    backup_dir: Option<TempDir>,
+    operations: Vec<FileOperation>,
+    max_depth: usize,
+}
+
+impl FileOperationHistory {
+    pub fn new(backup_root: PathBuf, max_depth: usize) -> Self {
+        Self {
+            backup_root,
+            backup_dir: None,
+            operations: Vec::new(),
+            max_depth,
+        }
+    }
+
+    fn backup_dir(&mut self) -> Result<&Path> {
+        let dir = match self.backup_dir.take() {
+            Some(dir) => dir,
+            None => {
+                let mut builder = tempfile::Builder::new();
+                builder.prefix("matepro_file_backups_");
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    builder.permissions(fs::Permissions::from_mode(0o700));
+                }
+                builder
+                    .tempdir_in(&self.backup_root)
+                    .context("Impossibile creare la cartella dei backup")?
+            }
+        };
+        Ok(self.backup_dir.insert(dir).path())
+    }
+
+    /// Copies the current content of `path` before a tool overwrites it
+    fn prepare(&mut self, tool_name: &str, path: &Path) -> Result<FileOperation> {
+        let backup = if path.is_file() {
+            let backup = self.backup_dir()?.join(Uuid::new_v4().to_string());
+            fs::copy(path, &backup)
+                .with_context(|| format!("Impossibile salvare un backup di {}", path.display()))?;
+            Some(backup)
+        } else {
+            None
+        };
+
+        Ok(FileOperation {
+            tool_name: tool_name.to_string(),
+            path: path.to_path_buf(),
+            backup,
+        })
+    }
+
+    fn record(&mut self, operation: FileOperation) {
+        self.operations.push(operation);
+        if self.operations.len() > self.max_depth {
+            let oldest = self.operations.remove(0);
+            Self::discard(oldest);
+        }
+    }
+
+    /// Drops the backup of an operation that will never be undone
+    fn discard(operation: FileOperation) {
+        if let Some(backup) = operation.backup {
+            let _ = fs::remove_file(backup);
+        }
+    }
+
+    /// Number of operations that can still be undone
+    pub fn depth(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Restores the file written by the most recent operation, or deletes it if the
+    /// tool created it
+    pub fn undo_last(&mut self) -> Result<String> {
+        let operation = self
+            .operations
+            .pop()
+            .context("Nessuna operazione sui file da annullare")?;
+
+        let restored = match &operation.backup {
+            Some(backup) => fs::copy(backup, &operation.path).map(|_| ()),
+            None if operation.path.exists() => fs::remove_file(&operation.path),
+            None => Ok(()),
+        };
+        if let Err(e) = restored {
+            let message = format!(
+                "Impossibile annullare l'operazione su {}: {}",
+                operation.path.display(),
+                e
+            );
+            self.operations.push(operation);
+            anyhow::bail!(message);
+        }
+
+        let message = match operation.backup {
+            Some(_) => format!(
+                "Ripristinato {} com'era prima di {}",
+                operation.path.display(),
+                operation.tool_name
+            ),
+            None => format!(
+                "Eliminato {}, creato da {}",
+                operation.path.display(),
+                operation.tool_name
+            ),
+        };
+        Self::discard(operation);
+        Ok(message)
+    }
+}
+
+impl Drop for FileOperationHistory {
+    fn drop(&mut self) {
+        for operation in self.operations.drain(..) {
+            Self::discard(operation);
+        }
+    }
+}
+
 /// Agent system that manages tools
 #[derive(Clone)]
 pub struct AgentSystem {
     pub tools: HashMap<String, ToolDefinition>,
     pub allow_dangerous: bool,
+    /// When set, tools that reach the network are refused and no web context is added
+    pub offline_mode: bool,
     sql_manager: mcp_sql::SqlConnectionManager,
     last_sql_connection_id: Arc<Mutex<Option<String>>>,
+    file_history: Arc<Mutex<FileOperationHistory>>,
 }
 
 impl AgentSystem {
@@ -261,61 +495,38 @@ impl AgentSystem {
             },
         );
 
-        // Tool: ProcessList
-        tools.insert(
-            "process_list".to_string(),
-            ToolDefinition {
-                name: "process_list".to_string(),
-                description: "Lista i processi attivi nel sistema.".to_string(),
-                parameters: vec![],
-                dangerous: false,
-            },
-        );
-
-        // Tool: SystemInfo
-        tools.insert(
-            "system_info".to_string(),
-            ToolDefinition {
-                name: "system_info".to_string(),
-                description: "Ottiene informazioni sul sistema (CPU, RAM, disco).".to_string(),
-                parameters: vec![],
-                dangerous: false,
-            },
-        );
-
-        // Tool: BrowserOpen
-        tools.insert(
-            "browser_open".to_string(),
-            ToolDefinition {
-                name: "browser_open".to_string(),
-                description: "Apre un URL nel browser predefinito.".to_string(),
-                parameters: vec![ToolParameter {
-                    name: "url".to_string(),
-                    param_type: "string".to_string(),
-                    description: "URL completo da aprire".to_string(),
-                    required: true,
-                }],
-                dangerous: false,
-            },
-        );
-
-        // Tool: WebSearch
+        // Tool: FileSearch
         tools.insert(
-            "web_search".to_string(),
+            "file_search".to_string(),
             ToolDefinition {
-                name: "web_search".to_string(),
-                description: "Esegue una ricerca web e restituisce risultati e snippet aggiornati.".to_string(),
+                name: "file_search".to_string(),
+                description: "Cerca file per nome (pattern glob come *.pdf o report_202?.xlsx) ed eventualmente per contenuto a partire da una directory. Le cartelle nascoste vengono saltate.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "query".to_string(),
+                        name: "root".to_string(),
                         param_type: "string".to_string(),
-                        description: "La query di ricerca".to_string(),
+                        description: "Directory da cui iniziare la ricerca".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "name_pattern".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Pattern glob sul nome del file, senza distinzione tra maiuscole e minuscole (* per tutti)".to_string(),
                         required: true,
                     },
+                    ToolParameter {
+                        name: "content_pattern".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Testo o espressione regolare da cercare nei file di testo (opzionale)".to_string(),
+                        required: false,
+                    },
                     ToolParameter {
                         name: "max_results".to_string(),
-                        param_type: "integer".to_string(),
-                        description: "Numero massimo di risultati (default 5)".to_string(),
+                        param_type: "number".to_string(),
+                        description: format!(
+                            "Numero massimo di risultati (default {}, max {})",
+                            FILE_SEARCH_DEFAULT_RESULTS, FILE_SEARCH_MAX_RESULTS
+                        ),
                         required: false,
                     },
                 ],
@@ -323,24 +534,23 @@ impl AgentSystem {
             },
         );
 
-        // Tool: MapOpen
+        // Tool: GenerateQr
         tools.insert(
-            "map_open".to_string(),
+            "generate_qr".to_string(),
             ToolDefinition {
-                name: "map_open".to_string(),
-                description: "Apre Google Maps con una località o percorso.".to_string(),
+                name: "generate_qr".to_string(),
+                description: "Genera un QR code in formato PNG da un testo o URL (es. link, credenziali Wi-Fi nel formato WIFI:T:WPA;S:rete;P:password;;) e lo mostra in chat.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "location".to_string(),
+                        name: "content".to_string(),
                         param_type: "string".to_string(),
-                        description: "Nome della località, indirizzo o coordinate".to_string(),
+                        description: "Testo o URL da codificare".to_string(),
                         required: true,
                     },
                     ToolParameter {
-                        name: "mode".to_string(),
+                        name: "dest_path".to_string(),
                         param_type: "string".to_string(),
-                        description: "Modalità: 'search' (default), 'directions' per percorsi"
-                            .to_string(),
+                        description: "Percorso del file PNG (opzionale, di default nella cartella temporanea; se indicato richiede conferma utente)".to_string(),
                         required: false,
                     },
                 ],
@@ -348,1929 +558,5692 @@ impl AgentSystem {
             },
         );
 
-        // Tool: YouTubeSearch
+        // Tool: ZipList
         tools.insert(
-            "youtube_search".to_string(),
+            "zip_list".to_string(),
             ToolDefinition {
-                name: "youtube_search".to_string(),
-                description: "Cerca video su YouTube.".to_string(),
+                name: "zip_list".to_string(),
+                description: "Elenca i file contenuti in un archivio ZIP senza estrarlo."
+                    .to_string(),
                 parameters: vec![ToolParameter {
-                    name: "query".to_string(),
+                    name: "path".to_string(),
                     param_type: "string".to_string(),
-                    description: "La query di ricerca su YouTube".to_string(),
+                    description: "Percorso dell'archivio ZIP".to_string(),
                     required: true,
                 }],
                 dangerous: false,
             },
         );
 
+        // Tool: ZipExtractText
         tools.insert(
-            "text_translate".to_string(),
+            "zip_extract_text".to_string(),
             ToolDefinition {
-                name: "text_translate".to_string(),
-                description:
-                    "Traduce un testo in un'altra lingua utilizzando servizi di traduzione online"
-                        .to_string(),
+                name: "zip_extract_text".to_string(),
+                description: "Estrae il testo di un file dentro un archivio ZIP (testo, PDF, DOCX) senza scriverlo su disco.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "text".to_string(),
+                        name: "path".to_string(),
                         param_type: "string".to_string(),
-                        description: "Testo da tradurre (max 1500 caratteri)".to_string(),
+                        description: "Percorso dell'archivio ZIP".to_string(),
                         required: true,
                     },
                     ToolParameter {
-                        name: "target_language".to_string(),
+                        name: "inner_path".to_string(),
                         param_type: "string".to_string(),
-                        description: "Lingua di destinazione (ISO code es: it, en, es)".to_string(),
+                        description: "Percorso del file dentro l'archivio, come restituito da zip_list".to_string(),
                         required: true,
                     },
-                    ToolParameter {
-                        name: "source_language".to_string(),
-                        param_type: "string".to_string(),
-                        description: "Lingua sorgente (ISO code). Default rilevamento automatico"
-                            .to_string(),
-                        required: false,
-                    },
                 ],
                 dangerous: false,
             },
         );
 
+        // Tool: ZipCreate
         tools.insert(
-            "document_summarize".to_string(),
+            "zip_create".to_string(),
             ToolDefinition {
-                name: "document_summarize".to_string(),
-                description:
-                    "Crea un riassunto compatto di un documento di testo, PDF, Excel o Word."
-                        .to_string(),
+                name: "zip_create".to_string(),
+                description: "Comprime uno o più file o directory in un archivio ZIP, mantenendo la struttura delle cartelle.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "path".to_string(),
+                        name: "sources".to_string(),
+                        param_type: "array".to_string(),
+                        description: "Lista dei percorsi di file o directory da comprimere".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "dest_path".to_string(),
                         param_type: "string".to_string(),
-                        description: "Percorso del file da riassumere".to_string(),
+                        description: "Percorso dell'archivio .zip da creare (sovrascritto se esiste)".to_string(),
                         required: true,
                     },
                     ToolParameter {
-                        name: "max_sentences".to_string(),
+                        name: "compression_level".to_string(),
                         param_type: "integer".to_string(),
-                        description: "Numero massimo di frasi nel riassunto (default 5)"
-                            .to_string(),
+                        description: "Livello di compressione da 0 (nessuna) a 9 (massima), default 6".to_string(),
                         required: false,
                     },
                 ],
-                dangerous: false,
+                dangerous: true,
             },
         );
 
+        // Tool: ProcessList
         tools.insert(
-            "excel_improve".to_string(),
+            "process_list".to_string(),
             ToolDefinition {
-                name: "excel_improve".to_string(),
-                description:
-                    "Analizza un file Excel e suggerisce miglioramenti (metriche, grafici, pulizia dati)."
-                        .to_string(),
-                parameters: vec![ToolParameter {
-                    name: "path".to_string(),
-                    param_type: "string".to_string(),
-                    description: "Percorso del file Excel (.xlsx o .xls)".to_string(),
-                    required: true,
-                }],
+                name: "process_list".to_string(),
+                description: "Lista i processi attivi nel sistema.".to_string(),
+                parameters: vec![],
                 dangerous: false,
             },
         );
 
+        // Tool: SystemInfo
         tools.insert(
-            "word_improve".to_string(),
+            "system_info".to_string(),
             ToolDefinition {
-                name: "word_improve".to_string(),
-                description:
-                    "Analizza un documento Word (.docx) e propone miglioramenti di stile e leggibilità."
-                        .to_string(),
-                parameters: vec![ToolParameter {
-                    name: "path".to_string(),
-                    param_type: "string".to_string(),
-                    description: "Percorso del file Word".to_string(),
-                    required: true,
-                }],
+                name: "system_info".to_string(),
+                description: "Ottiene informazioni sul sistema (CPU, RAM, disco).".to_string(),
+                parameters: vec![],
                 dangerous: false,
             },
         );
 
-        // MCP SQL Server tools
+        // Tool: DiskUsage
         tools.insert(
-            "sql_connect".to_string(),
+            "disk_usage".to_string(),
             ToolDefinition {
-                name: "sql_connect".to_string(),
-                description: "Connette a un database SQL Server.".to_string(),
+                name: "disk_usage".to_string(),
+                description: "Calcola lo spazio occupato da una directory e ne elenca le sottodirectory e i file più grandi.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "server".to_string(),
+                        name: "path".to_string(),
                         param_type: "string".to_string(),
-                        description: "Nome o IP del server SQL".to_string(),
+                        description: "Directory da analizzare".to_string(),
                         required: true,
                     },
                     ToolParameter {
-                        name: "database".to_string(),
-                        param_type: "string".to_string(),
-                        description: "Nome del database".to_string(),
-                        required: true,
+                        name: "top".to_string(),
+                        param_type: "number".to_string(),
+                        description: format!(
+                            "Numero di elementi da elencare (default {}, max {})",
+                            DISK_USAGE_DEFAULT_TOP, DISK_USAGE_MAX_TOP
+                        ),
+                        required: false,
                     },
+                ],
+                dangerous: false,
+            },
+        );
+
+        // Tool: DatetimeNow
+        tools.insert(
+            "datetime_now".to_string(),
+            ToolDefinition {
+                name: "datetime_now".to_string(),
+                description: "Restituisce data, ora, giorno della settimana e fuso orario attuali dell'utente. Usalo prima di rispondere a domande su date relative (oggi, domani, fra una settimana).".to_string(),
+                parameters: vec![],
+                dangerous: false,
+            },
+        );
+
+        // Tool: DatetimeCalculate
+        tools.insert(
+            "datetime_calculate".to_string(),
+            ToolDefinition {
+                name: "datetime_calculate".to_string(),
+                description: "Calcoli su date: aggiunge o sottrae un intervallo (anche in giorni lavorativi, lunedì-venerdì) oppure calcola la differenza tra due date.".to_string(),
+                parameters: vec![
                     ToolParameter {
-                        name: "auth_method".to_string(),
+                        name: "operation".to_string(),
                         param_type: "string".to_string(),
-                        description: "'windows' o 'sql'".to_string(),
+                        description: "add (aggiungi), subtract (sottrai) o diff (differenza tra base e target)".to_string(),
                         required: true,
                     },
                     ToolParameter {
-                        name: "username".to_string(),
-                        param_type: "string".to_string(),
-                        description: "Username SQL".to_string(),
-                        required: false,
-                    },
-                    ToolParameter {
-                        name: "password".to_string(),
+                        name: "base".to_string(),
                         param_type: "string".to_string(),
-                        description: "Password SQL".to_string(),
+                        description: "Data di partenza: AAAA-MM-GG, AAAA-MM-GG HH:MM, GG/MM/AAAA o 'now' (default: adesso)".to_string(),
                         required: false,
                     },
                     ToolParameter {
-                        name: "trust_server_certificate".to_string(),
-                        param_type: "boolean".to_string(),
-                        description: "Imposta true per accettare certificati TLS non attendibili"
-                            .to_string(),
+                        name: "amount".to_string(),
+                        param_type: "integer".to_string(),
+                        description: "Quantità da aggiungere o sottrarre (per add/subtract)".to_string(),
                         required: false,
                     },
-                ],
-                dangerous: false,
-            },
-        );
-
-        tools.insert(
-            "sql_query".to_string(),
-            ToolDefinition {
-                name: "sql_query".to_string(),
-                description: "Esegue query SELECT su database SQL Server (SOLO LETTURA)."
-                    .to_string(),
-                parameters: vec![
                     ToolParameter {
-                        name: "connection_id".to_string(),
+                        name: "unit".to_string(),
                         param_type: "string".to_string(),
-                        description: "ID della connessione SQL".to_string(),
+                        description: "minutes, hours, days, business_days, weeks, months o years (default: days)".to_string(),
                         required: false,
                     },
                     ToolParameter {
-                        name: "query".to_string(),
+                        name: "target".to_string(),
                         param_type: "string".to_string(),
-                        description: "Query SQL SELECT da eseguire".to_string(),
-                        required: true,
+                        description: "Seconda data, per diff".to_string(),
+                        required: false,
                     },
                 ],
                 dangerous: false,
             },
         );
 
+        // Tool: ParseCalendarEvent
         tools.insert(
-            "sql_list_tables".to_string(),
+            "parse_calendar_event".to_string(),
             ToolDefinition {
-                name: "sql_list_tables".to_string(),
-                description: "Lista tutte le tabelle del database SQL Server.".to_string(),
+                name: "parse_calendar_event".to_string(),
+                description: "Riconosce un appuntamento descritto in italiano (es. 'pranzo con Marco venerdì alle 13') e propone all'utente l'evento da aggiungere al calendario. Capisce date relative (oggi, domani, venerdì, tra 3 giorni), date come '12 marzo' o 12/03, orari e durate.".to_string(),
                 parameters: vec![ToolParameter {
-                    name: "connection_id".to_string(),
+                    name: "text".to_string(),
                     param_type: "string".to_string(),
-                    description: "ID della connessione SQL".to_string(),
-                    required: false,
+                    description: "Frase che descrive l'appuntamento".to_string(),
+                    required: true,
                 }],
                 dangerous: false,
             },
         );
 
+        // Tool: CalendarListEvents
         tools.insert(
-            "sql_describe_table".to_string(),
+            "calendar_list_events".to_string(),
             ToolDefinition {
-                name: "sql_describe_table".to_string(),
-                description: "Mostra struttura di una tabella SQL.".to_string(),
+                name: "calendar_list_events".to_string(),
+                description: "Elenca gli eventi dei calendari Outlook e Google collegati in un intervallo di date (es. 'eventi del mese prossimo'). Usa datetime_now o datetime_calculate per ricavare le date.".to_string(),
                 parameters: vec![
                     ToolParameter {
-                        name: "connection_id".to_string(),
+                        name: "start_date".to_string(),
                         param_type: "string".to_string(),
-                        description: "ID della connessione SQL".to_string(),
+                        description: "Inizio dell'intervallo (AAAA-MM-GG o AAAA-MM-GG HH:MM). Default: adesso".to_string(),
                         required: false,
                     },
                     ToolParameter {
-                        name: "schema".to_string(),
+                        name: "end_date".to_string(),
                         param_type: "string".to_string(),
-                        description: "Schema della tabella (es: dbo)".to_string(),
-                        required: true,
+                        description: "Fine dell'intervallo, giorno incluso se indicato senza ora".to_string(),
+                        required: false,
                     },
                     ToolParameter {
-                        name: "table".to_string(),
+                        name: "provider".to_string(),
                         param_type: "string".to_string(),
-                        description: "Nome della tabella".to_string(),
+                        description: "outlook o google. Default: tutti i calendari collegati".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "limit".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Numero massimo di eventi per calendario (default 20, max 50)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        // Tool: CalendarSync
+        tools.insert(
+            "calendar_sync".to_string(),
+            ToolDefinition {
+                name: "calendar_sync".to_string(),
+                description: "Aggiunge un evento del calendario di MatePro a Outlook e/o Google Calendar (es. 'aggiungi questo al mio Google Calendar'). Indica event_id di un evento esistente oppure title e start per crearlo al volo. Restituisce i link agli eventi creati.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "event_id".to_string(),
+                        param_type: "string".to_string(),
+                        description: "ID di un evento già nel calendario di MatePro".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "title".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Titolo del nuovo evento, se non usi event_id".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "start".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Inizio del nuovo evento (AAAA-MM-GG HH:MM)".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "end".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Fine del nuovo evento (default: un'ora dopo l'inizio)".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "description".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Descrizione del nuovo evento".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "location".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Luogo del nuovo evento".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "targets".to_string(),
+                        param_type: "array".to_string(),
+                        description: "Calendari di destinazione: outlook, google. Default: tutti quelli collegati".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: true,
+            },
+        );
+
+        // Tool: ReadLogs
+        tools.insert(
+            "read_logs".to_string(),
+            ToolDefinition {
+                name: "read_logs".to_string(),
+                description: "Legge le ultime righe dei log di sistema (journalctl su Linux, Event Log su Windows). Può contenere dati sensibili: usalo solo per diagnostica.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "source".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Unità systemd su Linux (es: sshd) o nome del log su Windows (System, Application). Default: log di sistema".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "lines".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Numero di righe da leggere (default 50, max 500)".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "filter".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Testo da cercare nelle righe (case-insensitive)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        // Tool: NetworkInfo
+        tools.insert(
+            "network_info".to_string(),
+            ToolDefinition {
+                name: "network_info".to_string(),
+                description: "Diagnostica di rete: ping di un host, risoluzione DNS o verifica di una porta TCP.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "operation".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Operazione: ping, dns_lookup, check_port".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "target".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Hostname o indirizzo IP".to_string(),
                         required: true,
                     },
+                    ToolParameter {
+                        name: "port".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Porta TCP da verificare (solo check_port)".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "count".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Numero di ping (default 4, max 10)".to_string(),
+                        required: false,
+                    },
                 ],
                 dangerous: false,
             },
         );
 
+        // Tool: BrowserOpen
         tools.insert(
-            "sql_disconnect".to_string(),
+            "browser_open".to_string(),
             ToolDefinition {
-                name: "sql_disconnect".to_string(),
-                description: "Chiude connessione SQL Server.".to_string(),
+                name: "browser_open".to_string(),
+                description: "Apre un URL nel browser predefinito.".to_string(),
                 parameters: vec![ToolParameter {
-                    name: "connection_id".to_string(),
+                    name: "url".to_string(),
                     param_type: "string".to_string(),
-                    description: "ID della connessione SQL da chiudere".to_string(),
-                    required: false,
+                    description: "URL completo da aprire".to_string(),
+                    required: true,
                 }],
                 dangerous: false,
             },
         );
 
-        Self {
-            tools,
-            allow_dangerous: false,
-            sql_manager,
-            last_sql_connection_id,
-        }
-    }
+        // Tool: WebSearch
+        tools.insert(
+            "web_search".to_string(),
+            ToolDefinition {
+                name: "web_search".to_string(),
+                description: "Esegue una ricerca web e restituisce risultati e snippet aggiornati.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "La query di ricerca".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "max_results".to_string(),
+                        param_type: "integer".to_string(),
+                        description: "Numero massimo di risultati (default 5)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
 
-    pub fn get_tools_description(&self) -> String {
-        let mut desc = String::from(
-            "**TOOLS DISPONIBILI** - Puoi usare questi tool per interagire con il sistema.\n\n",
+        // Tool: WebFetch
+        tools.insert(
+            "web_fetch".to_string(),
+            ToolDefinition {
+                name: "web_fetch".to_string(),
+                description: "Scarica una pagina web e restituisce il testo principale, senza menu, footer e script. Utile per riassumere articoli da un URL.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "url".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Indirizzo http/https della pagina".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "max_chars".to_string(),
+                        param_type: "integer".to_string(),
+                        description: "Numero massimo di caratteri restituiti (default 8000, max 20000)"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
         );
-        desc.push_str("Per usare un tool, rispondi con il seguente formato JSON:\n");
-        desc.push_str("```json\n{\n  \"tool\": \"nome_tool\",\n  \"parameters\": {\n    \"param1\": \"valore1\"\n  }\n}\n```\n\n");
-        desc.push_str("**Lista Tool:**\n\n");
 
-        for tool in self.tools.values() {
-            desc.push_str(&format!("### {}\n", tool.name));
-            desc.push_str(&format!("{}\n", tool.description));
+        // Tool: MapOpen
+        tools.insert(
+            "map_open".to_string(),
+            ToolDefinition {
+                name: "map_open".to_string(),
+                description: "Apre Google Maps con una località o percorso.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "location".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Nome della località, indirizzo o coordinate".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "mode".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Modalità: 'search' (default), 'directions' per percorsi"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
 
-            if !tool.parameters.is_empty() {
-                desc.push_str("**Parametri:**\n");
-                for param in &tool.parameters {
-                    let required = if param.required {
-                        "obbligatorio"
-                    } else {
-                        "opzionale"
-                    };
-                    desc.push_str(&format!(
-                        "- `{}` ({}): {} - {}\n",
-                        param.name, param.param_type, required, param.description
-                    ));
-                }
-            }
+        // Tool: YouTubeSearch
+        tools.insert(
+            "youtube_search".to_string(),
+            ToolDefinition {
+                name: "youtube_search".to_string(),
+                description: "Cerca video su YouTube.".to_string(),
+                parameters: vec![ToolParameter {
+                    name: "query".to_string(),
+                    param_type: "string".to_string(),
+                    description: "La query di ricerca su YouTube".to_string(),
+                    required: true,
+                }],
+                dangerous: false,
+            },
+        );
 
-            if tool.dangerous {
-                desc.push_str("⚠️ *Tool pericoloso: richiede conferma utente*\n");
-            }
-            desc.push('\n');
-        }
+        // Tool: WikipediaLookup
+        tools.insert(
+            "wikipedia_lookup".to_string(),
+            ToolDefinition {
+                name: "wikipedia_lookup".to_string(),
+                description: "Cerca una voce su Wikipedia e restituisce l'estratto del sommario con il link alla pagina, da citare nella risposta.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Titolo della voce o argomento da cercare".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "lang".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Lingua di Wikipedia (codice ISO es: it, en, de), default la lingua del sistema".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
 
-        desc
-    }
+        tools.insert(
+            "text_translate".to_string(),
+            ToolDefinition {
+                name: "text_translate".to_string(),
+                description:
+                    "Traduce un testo in un'altra lingua utilizzando servizi di traduzione online"
+                        .to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "text".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Testo da tradurre (max 1500 caratteri)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "target_language".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Lingua di destinazione (ISO code es: it, en, es)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "source_language".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Lingua sorgente (ISO code). Default rilevamento automatico"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
 
-    pub fn parse_tool_calls(&self, response: &str) -> Vec<ToolCall> {
+        tools.insert(
+            "detect_language".to_string(),
+            ToolDefinition {
+                name: "detect_language".to_string(),
+                description:
+                    "Rileva la lingua di un testo (offline) e restituisce codice ISO e confidenza."
+                        .to_string(),
+                parameters: vec![ToolParameter {
+                    name: "text".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Testo da analizzare".to_string(),
+                    required: true,
+                }],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "document_summarize".to_string(),
+            ToolDefinition {
+                name: "document_summarize".to_string(),
+                description:
+                    "Crea un riassunto compatto di un documento di testo, PDF, Excel o Word."
+                        .to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Percorso del file da riassumere".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "max_sentences".to_string(),
+                        param_type: "integer".to_string(),
+                        description: "Numero massimo di frasi nel riassunto (default 5)"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "excel_read".to_string(),
+            ToolDefinition {
+                name: "excel_read".to_string(),
+                description:
+                    "Legge solo i fogli o l'intervallo di celle richiesti da un file Excel/ODS."
+                        .to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Percorso del file (.xlsx, .xls o .ods)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "sheet".to_string(),
+                        param_type: "string".to_string(),
+                        description:
+                            "Nome del foglio (più fogli separati da virgola). Default: tutti"
+                                .to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "range".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Intervallo di celle, es: A1:D100 oppure Vendite!A1:D100"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "excel_write".to_string(),
+            ToolDefinition {
+                name: "excel_write".to_string(),
+                description: "Crea un file Excel (.xlsx) a partire da dati tabellari.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Percorso del file .xlsx da creare".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "rows".to_string(),
+                        param_type: "array".to_string(),
+                        description: "Righe di dati come array di array, es: [[\"Roma\", 12], [\"Milano\", 8]]"
+                            .to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "headers".to_string(),
+                        param_type: "array".to_string(),
+                        description: "Intestazioni di colonna, scritte in grassetto nella prima riga"
+                            .to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "sheet_name".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Nome del foglio (default: Foglio1)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: true,
+            },
+        );
+
+        tools.insert(
+            "excel_improve".to_string(),
+            ToolDefinition {
+                name: "excel_improve".to_string(),
+                description:
+                    "Analizza un file Excel e suggerisce miglioramenti (metriche, grafici, pulizia dati)."
+                        .to_string(),
+                parameters: vec![ToolParameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Percorso del file Excel (.xlsx o .xls)".to_string(),
+                    required: true,
+                }],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "word_improve".to_string(),
+            ToolDefinition {
+                name: "word_improve".to_string(),
+                description:
+                    "Analizza un documento Word (.docx) e propone miglioramenti di stile e leggibilità."
+                        .to_string(),
+                parameters: vec![ToolParameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Percorso del file Word".to_string(),
+                    required: true,
+                }],
+                dangerous: false,
+            },
+        );
+
+        // Tool: ConvertDocument
+        tools.insert(
+            "convert_document".to_string(),
+            ToolDefinition {
+                name: "convert_document".to_string(),
+                description: "Converte un documento in un altro formato (md, html, txt, docx, pdf). Usa pandoc se installato, altrimenti esegue internamente le conversioni di base verso md, html e txt.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "source_path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Percorso del documento da convertire (md, html, txt, docx, pdf, xlsx...)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "dest_path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Percorso del file da generare (sovrascritto se esiste)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "target_format".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Formato di destinazione: md, html, txt, docx o pdf (default: estensione di dest_path)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: true,
+            },
+        );
+
+        // MCP SQL tools (SQL Server, PostgreSQL, MySQL)
+        tools.insert(
+            "sql_connect".to_string(),
+            ToolDefinition {
+                name: "sql_connect".to_string(),
+                description: "Connette a un database SQL Server, PostgreSQL o MySQL.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "db_type".to_string(),
+                        param_type: "string".to_string(),
+                        description: "'sqlserver' (default), 'postgres' o 'mysql'".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "server".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Nome o IP del server, porta opzionale (es: db:5432)"
+                            .to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "database".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Nome del database".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "auth_method".to_string(),
+                        param_type: "string".to_string(),
+                        description: "'windows' (solo SQL Server) o 'sql'".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "username".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Username SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "password".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Password SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "trust_server_certificate".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Imposta true per accettare certificati TLS non attendibili"
+                            .to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_query".to_string(),
+            ToolDefinition {
+                name: "sql_query".to_string(),
+                description: "Esegue query SELECT sul database connesso (SOLO LETTURA).".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "connection_id".to_string(),
+                        param_type: "string".to_string(),
+                        description: "ID della connessione SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Query SQL SELECT da eseguire".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "timeout_secs".to_string(),
+                        param_type: "integer".to_string(),
+                        description: "Secondi dopo i quali la query viene interrotta, default dalle impostazioni (30)".to_string(),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_list_tables".to_string(),
+            ToolDefinition {
+                name: "sql_list_tables".to_string(),
+                description: "Lista tutte le tabelle del database connesso.".to_string(),
+                parameters: vec![ToolParameter {
+                    name: "connection_id".to_string(),
+                    param_type: "string".to_string(),
+                    description: "ID della connessione SQL".to_string(),
+                    required: false,
+                }],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_describe_table".to_string(),
+            ToolDefinition {
+                name: "sql_describe_table".to_string(),
+                description: "Mostra struttura di una tabella SQL.".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "connection_id".to_string(),
+                        param_type: "string".to_string(),
+                        description: "ID della connessione SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "schema".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Schema della tabella (es: dbo)".to_string(),
+                        required: true,
+                    },
+                    ToolParameter {
+                        name: "table".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Nome della tabella".to_string(),
+                        required: true,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_schema".to_string(),
+            ToolDefinition {
+                name: "sql_schema".to_string(),
+                description: "Mostra in un colpo solo tabelle, colonne, tipi, chiavi primarie e foreign key del database SQL (solo SQL Server).".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "connection_id".to_string(),
+                        param_type: "string".to_string(),
+                        description: "ID della connessione SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "schema".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Limita il risultato a uno schema (es: dbo)".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "max_tables".to_string(),
+                        param_type: "integer".to_string(),
+                        description: format!(
+                            "Numero massimo di tabelle restituite, default {}",
+                            mcp_sql::SCHEMA_DEFAULT_TABLES
+                        ),
+                        required: false,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_explain".to_string(),
+            ToolDefinition {
+                name: "sql_explain".to_string(),
+                description: "Mostra il piano di esecuzione stimato di una query SELECT senza eseguirla, per suggerire indici o riscritture (solo SQL Server).".to_string(),
+                parameters: vec![
+                    ToolParameter {
+                        name: "connection_id".to_string(),
+                        param_type: "string".to_string(),
+                        description: "ID della connessione SQL".to_string(),
+                        required: false,
+                    },
+                    ToolParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Query SQL SELECT da analizzare".to_string(),
+                        required: true,
+                    },
+                ],
+                dangerous: false,
+            },
+        );
+
+        tools.insert(
+            "sql_disconnect".to_string(),
+            ToolDefinition {
+                name: "sql_disconnect".to_string(),
+                description: "Chiude connessione SQL Server.".to_string(),
+                parameters: vec![ToolParameter {
+                    name: "connection_id".to_string(),
+                    param_type: "string".to_string(),
+                    description: "ID della connessione SQL da chiudere".to_string(),
+                    required: false,
+                }],
+                dangerous: false,
+            },
+        );
+
+        Self {
+            tools,
+            allow_dangerous: false,
+            offline_mode: false,
+            sql_manager,
+            last_sql_connection_id,
+            file_history: Arc::new(Mutex::new(FileOperationHistory::new(
+                std::env::temp_dir(),
+                FILE_UNDO_DEPTH,
+            ))),
+        }
+    }
+
+    pub fn get_tools_description(&self) -> String {
+        let mut desc = String::from(
+            "**TOOLS DISPONIBILI** - Puoi usare questi tool per interagire con il sistema.\n\n",
+        );
+        desc.push_str("Per usare un tool, rispondi con il seguente formato JSON:\n");
+        desc.push_str("```json\n{\n  \"tool\": \"nome_tool\",\n  \"parameters\": {\n    \"param1\": \"valore1\"\n  }\n}\n```\n\n");
+        desc.push_str("**Lista Tool:**\n\n");
+
+        for tool in self.tools.values() {
+            if self.offline_mode && is_network_tool(&tool.name) {
+                continue;
+            }
+
+            desc.push_str(&format!("### {}\n", tool.name));
+            desc.push_str(&format!("{}\n", tool.description));
+
+            if !tool.parameters.is_empty() {
+                desc.push_str("**Parametri:**\n");
+                for param in &tool.parameters {
+                    let required = if param.required {
+                        "obbligatorio"
+                    } else {
+                        "opzionale"
+                    };
+                    desc.push_str(&format!(
+                        "- `{}` ({}): {} - {}\n",
+                        param.name, param.param_type, required, param.description
+                    ));
+                }
+            }
+
+            if tool.dangerous {
+                desc.push_str("⚠️ *Tool pericoloso: richiede conferma utente*\n");
+            }
+            desc.push('\n');
+        }
+
+        desc
+    }
+
+    pub fn parse_tool_calls(&self, response: &str) -> Vec<ToolCall> {
         let mut calls = Vec::new();
         let json_regex = regex::Regex::new(r"```json\s*(\{[^`]*\})\s*```").unwrap();
 
-        for cap in json_regex.captures_iter(response) {
-            if let Some(json_str) = cap.get(1) {
-                let json_text = json_str.as_str();
-                if let Ok(value) = serde_json::from_str::<serde_json::Value>(json_text) {
-                    if let Some(tool_name) = value.get("tool").and_then(|v| v.as_str()) {
-                        let parameters = value
-                            .get("parameters")
-                            .and_then(|v| v.as_object())
-                            .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
-                            .unwrap_or_default();
+        for cap in json_regex.captures_iter(response) {
+            if let Some(json_str) = cap.get(1) {
+                let json_text = json_str.as_str();
+                if let Ok(value) = serde_json::from_str::<serde_json::Value>(json_text) {
+                    if let Some(tool_name) = value.get("tool").and_then(|v| v.as_str()) {
+                        let parameters = value
+                            .get("parameters")
+                            .and_then(|v| v.as_object())
+                            .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+                            .unwrap_or_default();
+
+                        calls.push(ToolCall {
+                            tool_name: tool_name.to_string(),
+                            parameters,
+                            raw_text: json_text.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        calls
+    }
+
+    pub async fn execute_tool(&mut self, call: &ToolCall) -> Result<ToolResult> {
+        if !self.tools.contains_key(&call.tool_name) {
+            return Err(anyhow!("Tool non trovato"));
+        }
+
+        if self.offline_mode && is_network_tool(&call.tool_name) {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!(
+                    "Modalità offline attiva: il tool '{}' richiede una connessione di rete ed è disabilitato",
+                    call.tool_name
+                )),
+                tool_name: call.tool_name.clone(),
+                image: None,
+                calendar_event: None,
+            });
+        }
+
+        let dangerous = self.requires_confirmation(call);
+        if dangerous && !self.allow_dangerous {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Tool pericoloso: conferma richiesta".to_string()),
+                tool_name: call.tool_name.clone(),
+                image: None,
+                calendar_event: None,
+            });
+        }
+        // The confirmation covers this call only: consume it before any exit path
+        if dangerous {
+            self.allow_dangerous = false;
+        }
+
+        let file_operation = match written_file(call) {
+            Some(path) => {
+                let mut history = self.file_history.lock().await;
+                match history.prepare(&call.tool_name, &path) {
+                    Ok(operation) => Some(operation),
+                    Err(e) => {
+                        return Ok(ToolResult {
+                            success: false,
+                            output: String::new(),
+                            error: Some(e.to_string()),
+                            tool_name: call.tool_name.clone(),
+                            image: None,
+                            calendar_event: None,
+                        })
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let mut image = None;
+        let mut calendar_event = None;
+        let result = match call.tool_name.as_str() {
+            "shell_execute" => self.execute_shell(&call.parameters).await,
+            "file_read" => self.execute_file_read(&call.parameters).await,
+            "file_write" => self.execute_file_write(&call.parameters).await,
+            "file_list" => self.execute_file_list(&call.parameters).await,
+            "file_search" => self.execute_file_search(&call.parameters).await,
+            "generate_qr" => {
+                self.execute_generate_qr(&call.parameters)
+                    .await
+                    .map(|(output, data_url)| {
+                        image = Some(data_url);
+                        output
+                    })
+            }
+            "zip_list" => self.execute_zip_list(&call.parameters).await,
+            "zip_extract_text" => self.execute_zip_extract_text(&call.parameters).await,
+            "zip_create" => self.execute_zip_create(&call.parameters).await,
+            "process_list" => self.execute_process_list().await,
+            "system_info" => self.execute_system_info().await,
+            "disk_usage" => self.execute_disk_usage(&call.parameters).await,
+            "datetime_now" => self.execute_datetime_now().await,
+            "datetime_calculate" => self.execute_datetime_calculate(&call.parameters).await,
+            "parse_calendar_event" => {
+                execute_parse_calendar_event(&call.parameters).map(|(output, event)| {
+                    calendar_event = Some(event);
+                    output
+                })
+            }
+            "calendar_list_events" => execute_calendar_list_events(&call.parameters).await,
+            "calendar_sync" => execute_calendar_sync(&call.parameters).await,
+            "read_logs" => self.execute_read_logs(&call.parameters).await,
+            "network_info" => self.execute_network_info(&call.parameters).await,
+            "browser_open" => self.execute_browser_open(&call.parameters).await,
+            "web_search" => self.execute_web_search(&call.parameters).await,
+            "web_fetch" => self.execute_web_fetch(&call.parameters).await,
+            "map_open" => self.execute_map_open(&call.parameters).await,
+            "youtube_search" => self.execute_youtube_search(&call.parameters).await,
+            "wikipedia_lookup" => self.execute_wikipedia_lookup(&call.parameters).await,
+            "text_translate" => self.execute_text_translate(&call.parameters).await,
+            "detect_language" => self.execute_detect_language(&call.parameters).await,
+            "document_summarize" => self.execute_document_summarize(&call.parameters).await,
+            "excel_read" => self.execute_excel_read(&call.parameters).await,
+            "excel_write" => self.execute_excel_write(&call.parameters).await,
+            "excel_improve" => self.execute_excel_improve(&call.parameters).await,
+            "word_improve" => self.execute_word_improve(&call.parameters).await,
+            "convert_document" => self.execute_convert_document(&call.parameters).await,
+            "sql_connect" => self.execute_sql_connect(&call.parameters).await,
+            "sql_query" => self.execute_sql_query(&call.parameters).await,
+            "sql_list_tables" => self.execute_sql_list_tables(&call.parameters).await,
+            "sql_describe_table" => self.execute_sql_describe_table(&call.parameters).await,
+            "sql_schema" => self.execute_sql_schema(&call.parameters).await,
+            "sql_explain" => self.execute_sql_explain(&call.parameters).await,
+            "sql_disconnect" => self.execute_sql_disconnect(&call.parameters).await,
+            _ => Err(anyhow::anyhow!("Tool non implementato: {}", call.tool_name)),
+        };
+
+        if let Some(operation) = file_operation {
+            if result.is_ok() {
+                self.file_history.lock().await.record(operation);
+            } else {
+                FileOperationHistory::discard(operation);
+            }
+        }
+
+        let tool_result = match result {
+            Ok(output) => ToolResult {
+                success: true,
+                output,
+                error: None,
+                tool_name: call.tool_name.clone(),
+                image,
+                calendar_event,
+            },
+            Err(e) => ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(e.to_string()),
+                tool_name: call.tool_name.clone(),
+                image: None,
+                calendar_event: None,
+            },
+        };
+
+        Ok(tool_result)
+    }
+
+    /// Whether the call needs the user's confirmation. `generate_qr` is harmless
+    /// in the temp folder but overwrites any file when given a destination
+    pub fn requires_confirmation(&self, call: &ToolCall) -> bool {
+        let dangerous = self
+            .tools
+            .get(&call.tool_name)
+            .is_some_and(|tool| tool.dangerous);
+        dangerous || (call.tool_name == "generate_qr" && qr_dest_path(&call.parameters).is_some())
+    }
+
+    /// Describes what a dangerous tool would do without running it, for the confirmation
+    /// dialog. `None` for tools that cannot be simulated.
+    pub fn dry_run(&self, call: &ToolCall) -> Result<Option<String>> {
+        let params = &call.parameters;
+        let preview = match call.tool_name.as_str() {
+            "shell_execute" => dry_run_shell(params)?,
+            "file_write" => dry_run_file_write(params)?,
+            "zip_create" => dry_run_zip_create(params)?,
+            "excel_write" => dry_run_excel_write(params)?,
+            "convert_document" => dry_run_convert_document(params)?,
+            "generate_qr" => match qr_dest_path(params) {
+                Some(dest) => format!(
+                    "Salverebbe il QR code in {}\n{}",
+                    dest.display(),
+                    destination_note(&dest)
+                ),
+                None => return Ok(None),
+            },
+            "calendar_sync" => dry_run_calendar_sync(params)?,
+            _ => return Ok(None),
+        };
+        Ok(Some(preview))
+    }
+
+    pub async fn build_web_search_context(&self, user_message: &str) -> Option<String> {
+        if self.offline_mode {
+            return None;
+        }
+
+        let trimmed = user_message.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        let intent = Self::classify_query_intent(trimmed);
+        if matches!(intent, QueryIntent::General) {
+            return None;
+        }
+
+        let (refined_query, preprocess_note) = Self::refine_web_query(trimmed);
+        let search_query = if refined_query.trim().is_empty() {
+            trimmed.to_string()
+        } else {
+            refined_query
+        };
+
+        let mut params = HashMap::new();
+        params.insert(
+            "query".to_string(),
+            serde_json::Value::String(search_query.clone()),
+        );
+
+        if matches!(intent, QueryIntent::DeepAnalysis) {
+            params.insert("max_results".to_string(), json!(6));
+        }
+
+        match self.execute_web_search(&params).await {
+            Ok(raw_output) => {
+                let sanitized = raw_output
+                    .lines()
+                    .filter(|line| {
+                        let trimmed = line.trim_start();
+                        !trimmed.starts_with("🔗 Altri risultati:")
+                            && !trimmed.starts_with("🔗 Prova a consultare:")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                if sanitized.trim().is_empty()
+                    || sanitized.contains("⚠️ Non ho trovato risultati strutturati")
+                {
+                    return Some(format!(
+                        "NESSUNA FONTE AFFIDABILE TROVATA per la ricerca \"{}\".\nDevi rispondere seguendo queste istruzioni, senza eccezioni:\n1. Comunica chiaramente che non sono disponibili aggiornamenti verificati per l'argomento richiesto.\n2. Non inventare eventi, non riassumere conoscenze pregresse e non citare fonti inesistenti.\n3. Suggerisci eventualmente di riprovare più tardi o di fornire maggiori dettagli, ma evita qualsiasi speculazione.",
+                        search_query
+                    ));
+                }
+
+                let mut context = String::from(
+                    "ISTRUZIONI FONTI:\n- Usa esclusivamente i riferimenti elencati di seguito.\n- Cita ogni fonte nel formato [Titolo](URL) indicando il dominio.\n- Non integrare conoscenze non supportate dalle fonti elencate.\n- Se qualcosa è incerto o contraddittorio, evidenzialo invece di colmare i vuoti.\n\n",
+                );
+                context.push_str(&sanitized);
+
+                if let Some(note) = preprocess_note {
+                    context.push_str(&format!("\n\nNota preprocessing: {}", note));
+                }
+
+                Some(context)
+            }
+            Err(err) => Some(format!(
+                "Ricerca web fallita per \"{}\": {}. Comunica all'utente che non è stato possibile ottenere fonti aggiornate.",
+                search_query, err
+            )),
+        }
+    }
+
+    pub fn set_allow_dangerous(&mut self, allow: bool) {
+        self.allow_dangerous = allow;
+    }
+
+    pub fn set_offline_mode(&mut self, offline: bool) {
+        self.offline_mode = offline;
+    }
+
+    pub async fn undo_last_file_operation(&self) -> Result<String> {
+        self.file_history.lock().await.undo_last()
+    }
+
+    pub async fn file_undo_depth(&self) -> usize {
+        self.file_history.lock().await.depth()
+    }
+
+    async fn execute_shell(&self, params: &HashMap<String, serde_json::Value>) -> Result<String> {
+        let command = params
+            .get("command")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'command' mancante")?;
+
+        let output = if cfg!(target_os = "windows") {
+            match Command::new("pwsh")
+                .arg("-NoLogo")
+                .arg("-NoProfile")
+                .arg("-Command")
+                .arg(command)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+            {
+                Ok(output) => output,
+                Err(err) if err.kind() == ErrorKind::NotFound => Command::new("powershell")
+                    .arg("-NoLogo")
+                    .arg("-NoProfile")
+                    .arg("-Command")
+                    .arg(command)
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .output()
+                    .with_context(|| "Errore esecuzione comando con PowerShell")?,
+                Err(err) => {
+                    return Err(anyhow!("Errore esecuzione comando pwsh: {}", err));
+                }
+            }
+        } else {
+            Command::new("bash")
+                .arg("-lc")
+                .arg(command)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .context("Errore esecuzione comando")?
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if output.status.success() {
+            Ok(format!("{}{}", stdout, stderr))
+        } else {
+            Err(anyhow::anyhow!(
+                "Comando fallito (exit {}): {}{}",
+                output.status.code().unwrap_or(-1),
+                stdout,
+                stderr
+            ))
+        }
+    }
+
+    async fn execute_file_read(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+
+        let content =
+            fs::read_to_string(path).context(format!("Impossibile leggere file: {}", path))?;
+        Ok(content)
+    }
+
+    async fn execute_file_write(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+
+        let content = params
+            .get("content")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'content' mancante")?;
+
+        fs::write(path, content).context(format!("Impossibile scrivere file: {}", path))?;
+        Ok(format!("File scritto: {} ({} bytes)", path, content.len()))
+    }
+
+    async fn execute_file_list(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+
+        let recursive = params
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let mut entries = Vec::new();
+
+        if recursive {
+            for entry in WalkDir::new(path).max_depth(5) {
+                if let Ok(e) = entry {
+                    entries.push(e.path().display().to_string());
+                }
+            }
+        } else {
+            for entry in fs::read_dir(path)? {
+                if let Ok(e) = entry {
+                    entries.push(e.path().display().to_string());
+                }
+            }
+        }
+
+        Ok(entries.join("\n"))
+    }
+
+    async fn execute_file_search(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let root = params
+            .get("root")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'root' mancante")?;
+        let name_pattern = params
+            .get("name_pattern")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or("*");
+        let max_results = params
+            .get("max_results")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, FILE_SEARCH_MAX_RESULTS))
+            .unwrap_or(FILE_SEARCH_DEFAULT_RESULTS);
+
+        let root = PathBuf::from(root);
+        if !root.is_dir() {
+            return Err(anyhow!("Directory non trovata: {}", root.display()));
+        }
+        let name_regex = glob_to_regex(name_pattern.trim())?;
+        let content_regex = match params.get("content_pattern").and_then(|v| v.as_str()) {
+            Some(pattern) if !pattern.trim().is_empty() => Some(content_search_regex(pattern)?),
+            _ => None,
+        };
+
+        let dir = root.clone();
+        let (matches, truncated) = tokio::task::spawn_blocking(move || {
+            search_files(&dir, &name_regex, content_regex.as_ref(), max_results)
+        })
+        .await?;
+
+        if matches.is_empty() {
+            return Ok(format!(
+                "Nessun file corrispondente a '{}' trovato in {}",
+                name_pattern,
+                root.display()
+            ));
+        }
+
+        let mut output = format!("Trovati {} file in {}:\n", matches.len(), root.display());
+        for found in &matches {
+            output.push_str(&format!(
+                "- {} ({})",
+                found.path.display(),
+                format_file_size(found.size)
+            ));
+            if let Some((line_number, line)) = &found.line {
+                output.push_str(&format!(" — riga {}: {}", line_number, line));
+            }
+            output.push('\n');
+        }
+        if truncated {
+            output.push_str(&format!(
+                "\nRisultati limitati a {}: restringi il pattern o la directory per vederne altri",
+                max_results
+            ));
+        }
+        Ok(output.trim_end().to_string())
+    }
+
+    /// Returns the tool output and the PNG as a data URL for the chat
+    async fn execute_generate_qr(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<(String, String)> {
+        let content = params
+            .get("content")
+            .and_then(|v| v.as_str())
+            .filter(|c| !c.is_empty())
+            .context("Parametro 'content' mancante")?;
+        let dest = qr_dest_path(params).unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("matepro_qr_{}.png", Uuid::new_v4().simple()))
+        });
+
+        let png = qr_code_png(content)?;
+        fs::write(&dest, &png)
+            .with_context(|| format!("Impossibile salvare il QR code in {}", dest.display()))?;
+
+        let data_url = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&png)
+        );
+        let output = format!(
+            "QR code salvato in {}\nContenuto: {}",
+            dest.display(),
+            content
+        );
+        Ok((output, data_url))
+    }
+
+    async fn execute_zip_list(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+
+        let mut archive = open_zip_archive(path)?;
+        let mut lines = Vec::new();
+        let mut total_size = 0u64;
+        let mut file_count = 0usize;
+
+        for index in 0..archive.len() {
+            // Raw access reads the headers without decrypting protected entries
+            let (name, size) = {
+                let entry = archive.by_index_raw(index).map_err(zip_error)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                (entry.name().to_string(), entry.size())
+            };
+
+            file_count += 1;
+            total_size += size;
+            if lines.len() < ZIP_LIST_MAX_ENTRIES {
+                let encrypted = matches!(
+                    archive.by_index(index),
+                    Err(ZipError::UnsupportedArchive(message)) if message == ZipError::PASSWORD_REQUIRED
+                );
+                let marker = if encrypted { " 🔒 cifrato" } else { "" };
+                lines.push(format!("- {} ({}){}", name, format_file_size(size), marker));
+            }
+        }
+
+        if file_count == 0 {
+            return Ok(format!("L'archivio {} non contiene file", path));
+        }
+
+        let mut output = format!(
+            "📦 {}: {} file, {} non compressi\n\n{}",
+            path,
+            file_count,
+            format_file_size(total_size),
+            lines.join("\n")
+        );
+        if file_count > lines.len() {
+            output.push_str(&format!("\n... e altri {} file", file_count - lines.len()));
+        }
+
+        Ok(output)
+    }
+
+    async fn execute_zip_extract_text(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+
+        let inner_path = params
+            .get("inner_path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'inner_path' mancante")?;
+
+        let mut archive = open_zip_archive(path)?;
+        let mut entry = archive.by_name(inner_path).map_err(|e| match e {
+            ZipError::FileNotFound => anyhow::anyhow!(
+                "File '{}' non trovato nell'archivio: usa zip_list per vedere i percorsi disponibili",
+                inner_path
+            ),
+            other => zip_error(other),
+        })?;
+
+        if entry.is_dir() {
+            anyhow::bail!("'{}' è una cartella, indica un file", inner_path);
+        }
+        if entry.size() > ZIP_EXTRACT_MAX_BYTES {
+            anyhow::bail!(
+                "File troppo grande da estrarre in memoria ({}, massimo {})",
+                format_file_size(entry.size()),
+                format_file_size(ZIP_EXTRACT_MAX_BYTES)
+            );
+        }
+
+        let mut bytes = Vec::new();
+        entry
+            .by_ref()
+            .take(ZIP_EXTRACT_MAX_BYTES)
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("Archivio corrotto, impossibile leggere '{}'", inner_path))?;
+
+        let extension = Path::new(inner_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let text = match extension.as_str() {
+            "pdf" => pdf_document_text(&Document::load_mem(&bytes)?),
+            "docx" => extract_docx_text(Cursor::new(bytes))?,
+            _ if bytes.contains(&0) => anyhow::bail!(
+                "'{}' è un file binario: sono supportati testo, PDF e DOCX",
+                inner_path
+            ),
+            _ => String::from_utf8_lossy(&bytes).into_owned(),
+        };
+
+        let total_chars = text.chars().count();
+        if total_chars == 0 {
+            return Ok(format!("Il file '{}' non contiene testo", inner_path));
+        }
+        if total_chars <= ZIP_EXTRACT_MAX_CHARS {
+            return Ok(text);
+        }
+
+        let truncated: String = text.chars().take(ZIP_EXTRACT_MAX_CHARS).collect();
+        Ok(format!(
+            "{}\n\n[Testo troncato: mostrati {} caratteri su {}]",
+            truncated, ZIP_EXTRACT_MAX_CHARS, total_chars
+        ))
+    }
+
+    async fn execute_zip_create(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let sources: Vec<PathBuf> = match params.get("sources") {
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(PathBuf::from)
+                .collect(),
+            Some(serde_json::Value::String(single)) => vec![PathBuf::from(single)],
+            _ => anyhow::bail!("Parametro 'sources' mancante o non è una lista di percorsi"),
+        };
+        if sources.is_empty() {
+            anyhow::bail!("Indica almeno un file o una directory da comprimere");
+        }
+
+        let dest_path = params
+            .get("dest_path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'dest_path' mancante")?;
+
+        let level = params
+            .get("compression_level")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(6)
+            .clamp(0, 9) as i32;
+        let options = if level == 0 {
+            FileOptions::default().compression_method(CompressionMethod::Stored)
+        } else {
+            FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(Some(level))
+        };
+
+        for source in &sources {
+            if !source.exists() {
+                anyhow::bail!("Percorso non trovato: {}", source.display());
+            }
+        }
+
+        // The archive is written to a temp file next to the destination and renamed
+        // over it at the end: an error half way leaves an existing archive untouched
+        // and the temp file is removed when dropped
+        let dest = Path::new(dest_path);
+        let dest_dir = match dest.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".matepro_zip_").suffix(".tmp");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(fs::Permissions::from_mode(0o644));
+        }
+        let temp = builder
+            .tempfile_in(dest_dir)
+            .with_context(|| format!("Impossibile creare l'archivio: {}", dest_path))?;
+        let temp_path = temp.path().canonicalize()?;
+        let dest = dest.canonicalize().unwrap_or_else(|_| dest.to_path_buf());
+        let mut writer = ZipWriter::new(temp);
+        let mut names = HashSet::new();
+        let mut file_count = 0usize;
+        let mut original_size = 0u64;
+
+        for source in &sources {
+            // Entries are named relative to the parent, so a directory keeps its own name as root
+            let base = source
+                .canonicalize()?
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+
+            for entry in WalkDir::new(source).sort_by_file_name() {
+                let entry = entry?;
+                let path = entry.path().canonicalize()?;
+                if path == dest || path == temp_path {
+                    continue;
+                }
+
+                let name = zip_entry_name(path.strip_prefix(&base).unwrap_or(&path));
+                if name.is_empty() {
+                    continue;
+                }
+
+                if entry.file_type().is_dir() {
+                    if names.insert(format!("{}/", name)) {
+                        writer.add_directory(name, options)?;
+                    }
+                    continue;
+                }
+
+                if !names.insert(name.clone()) {
+                    anyhow::bail!(
+                        "Più sorgenti producono lo stesso file nell'archivio: {}",
+                        name
+                    );
+                }
+
+                writer.start_file(name, options)?;
+                let mut input = fs::File::open(&path)
+                    .with_context(|| format!("Impossibile leggere: {}", path.display()))?;
+                original_size += std::io::copy(&mut input, &mut writer)?;
+                file_count += 1;
+            }
+        }
+
+        writer
+            .finish()?
+            .persist(dest_path)
+            .with_context(|| format!("Impossibile salvare l'archivio: {}", dest_path))?;
+        let archive_size = fs::metadata(dest_path)?.len();
+
+        Ok(format!(
+            "✅ Archivio creato: {}\n📄 File: {}\n📦 Dimensione: {} (originali {})",
+            dest_path,
+            file_count,
+            format_file_size(archive_size),
+            format_file_size(original_size)
+        ))
+    }
+
+    async fn execute_process_list(&self) -> Result<String> {
+        let mut sys = System::new_all();
+        sys.refresh_all();
+
+        let mut processes: Vec<String> = sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| {
+                format!(
+                    "PID: {} | Name: {} | CPU: {:.1}% | Memory: {} MB",
+                    pid,
+                    process.name(),
+                    process.cpu_usage(),
+                    process.memory() / 1024 / 1024
+                )
+            })
+            .collect();
+
+        processes.sort();
+        processes.truncate(50);
+        Ok(processes.join("\n"))
+    }
+
+    async fn execute_disk_usage(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'path' mancante")?;
+        let top = params
+            .get("top")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, DISK_USAGE_MAX_TOP))
+            .unwrap_or(DISK_USAGE_DEFAULT_TOP);
+
+        let root = PathBuf::from(path);
+        if !root.is_dir() {
+            return Err(anyhow!("Directory non trovata: {}", root.display()));
+        }
+
+        let dir = root.clone();
+        let usage = tokio::task::spawn_blocking(move || measure_disk_usage(&dir, top)).await?;
+        Ok(format_disk_usage(&root, &usage, top))
+    }
+
+    async fn execute_system_info(&self) -> Result<String> {
+        let mut sys = System::new_all();
+        sys.refresh_all();
+
+        let total_memory = sys.total_memory() / 1024 / 1024;
+        let used_memory = sys.used_memory() / 1024 / 1024;
+        let total_swap = sys.total_swap() / 1024 / 1024;
+        let used_swap = sys.used_swap() / 1024 / 1024;
+
+        let mut info = format!(
+            "Sistema: {}\nKernel: {}\nCPU: {} cores\nRAM: {} MB / {} MB ({:.1}%)\nSwap: {} MB / {} MB\nProcessi attivi: {}",
+            System::name().unwrap_or_else(|| "Unknown".to_string()),
+            System::kernel_version().unwrap_or_else(|| "Unknown".to_string()),
+            sys.cpus().len(),
+            used_memory,
+            total_memory,
+            (used_memory as f64 / total_memory as f64) * 100.0,
+            used_swap,
+            total_swap,
+            sys.processes().len()
+        );
+
+        let gpus = tokio::task::spawn_blocking(crate::gpu::detect_gpus)
+            .await
+            .unwrap_or_default();
+        if gpus.is_empty() {
+            info.push_str("\nGPU: nessuna GPU rilevata (i modelli girano su CPU/RAM)");
+        }
+        for gpu in gpus {
+            info.push_str(&format!(
+                "\nGPU: {} - VRAM libera {} MB / {} MB",
+                gpu.name,
+                gpu.free_memory / 1024 / 1024,
+                gpu.total_memory / 1024 / 1024
+            ));
+        }
+
+        Ok(info)
+    }
+
+    async fn execute_datetime_now(&self) -> Result<String> {
+        let now = Local::now();
+        Ok(format!(
+            "📅 Oggi è {}\n🕒 Ora: {}\n🌍 Fuso orario: UTC{}\nISO 8601: {}\nSettimana ISO: {}",
+            format_italian_datetime(now.naive_local(), false),
+            now.format("%H:%M:%S"),
+            now.format("%:z"),
+            now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            now.iso_week().week()
+        ))
+    }
+
+    async fn execute_datetime_calculate(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let operation = params
+            .get("operation")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'operation' mancante")?
+            .trim()
+            .to_lowercase();
+        let (base, base_has_time) =
+            parse_local_datetime(params.get("base").and_then(|v| v.as_str()).unwrap_or("now"))?;
+
+        match operation.as_str() {
+            "add" | "aggiungi" | "subtract" | "sottrai" => {
+                let amount = params
+                    .get("amount")
+                    .and_then(|v| {
+                        v.as_i64()
+                            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+                    })
+                    .context("Parametro 'amount' mancante o non è un numero intero")?;
+                let unit = params.get("unit").and_then(|v| v.as_str());
+                let unit = TimeUnit::parse(unit.unwrap_or("days"))?;
+                let signed = if matches!(operation.as_str(), "subtract" | "sottrai") {
+                    -amount
+                } else {
+                    amount
+                };
+
+                let result = shift_datetime(base, signed, unit)?;
+                let has_time = base_has_time || unit.has_time();
+                Ok(format!(
+                    "📅 {} {} {} {} = {}\nISO 8601: {}",
+                    format_italian_datetime(base, has_time),
+                    if signed < 0 { "-" } else { "+" },
+                    amount.abs(),
+                    unit.label(),
+                    format_italian_datetime(result, has_time),
+                    if has_time {
+                        result.format("%Y-%m-%dT%H:%M").to_string()
+                    } else {
+                        result.format("%Y-%m-%d").to_string()
+                    }
+                ))
+            }
+            "diff" | "difference" | "differenza" => {
+                let (target, target_has_time) = parse_local_datetime(
+                    params
+                        .get("target")
+                        .and_then(|v| v.as_str())
+                        .context("Parametro 'target' mancante per diff")?,
+                )?;
+                let has_time = base_has_time || target_has_time;
+                // A target before the base is reported as "... fa" instead of
+                // with negative counts
+                let elapsed = target - base;
+                let past = elapsed < chrono::Duration::zero();
+                let span = |text: String| {
+                    if past {
+                        format!("{} fa", text)
+                    } else {
+                        format!("tra {}", text)
+                    }
+                };
+                let days = elapsed.abs().num_days();
+
+                let mut output = format!(
+                    "📅 Da {} a {}:\n- {} ({} settimane e {} giorni)\n- {} (lunedì-venerdì)",
+                    format_italian_datetime(base, has_time),
+                    format_italian_datetime(target, has_time),
+                    span(format!("{} giorni", days)),
+                    days / 7,
+                    days % 7,
+                    span(format!(
+                        "{} giorni lavorativi",
+                        business_days_between(base.date(), target.date()).abs()
+                    ))
+                );
+                if has_time {
+                    output.push_str(&format!(
+                        "\n- {}",
+                        span(format!(
+                            "{} ore e {} minuti",
+                            elapsed.abs().num_hours(),
+                            elapsed.abs().num_minutes() % 60
+                        ))
+                    ));
+                }
+                Ok(output)
+            }
+            other => anyhow::bail!(
+                "Operazione non supportata: {} (usa add, subtract o diff)",
+                other
+            ),
+        }
+    }
+
+    async fn execute_read_logs(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let source = params
+            .get("source")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let lines = params
+            .get("lines")
+            .and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            })
+            .unwrap_or(50)
+            .clamp(1, 500) as usize;
+        let filter = params
+            .get("filter")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty());
+
+        // With a filter, read a wider window so the matches can still fill `lines`
+        let fetch_lines = if filter.is_some() { lines * 10 } else { lines };
+
+        let (label, output) = if cfg!(target_os = "windows") {
+            let log_name = source.unwrap_or("System");
+            // One line per event, oldest first like journalctl
+            let script = format!(
+                "Get-WinEvent -LogName '{}' -MaxEvents {} -ErrorAction Stop | Sort-Object TimeCreated | ForEach-Object {{ '{{0:s}} [{{1}}] {{2}}: {{3}}' -f $_.TimeCreated, $_.LevelDisplayName, $_.ProviderName, ($_.Message -replace '\\s+', ' ') }}",
+                log_name.replace('\'', "''"),
+                fetch_lines
+            );
+            let output = Command::new("powershell")
+                .arg("-NoLogo")
+                .arg("-NoProfile")
+                .arg("-Command")
+                .arg(script)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .context("Errore esecuzione Get-WinEvent")?;
+            (format!("Event Log {}", log_name), output)
+        } else if cfg!(target_os = "linux") {
+            let mut command = Command::new("journalctl");
+            command
+                .arg("--no-pager")
+                .arg("-o")
+                .arg("short-iso")
+                .arg("-n")
+                .arg(fetch_lines.to_string());
+            if let Some(unit) = source {
+                command.arg("-u").arg(unit);
+            }
+            let output = match command
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+            {
+                Ok(output) => output,
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    return Err(anyhow!(
+                        "journalctl non disponibile: il sistema non usa systemd-journald"
+                    ));
+                }
+                Err(err) => return Err(anyhow!("Errore esecuzione journalctl: {}", err)),
+            };
+            let label = source
+                .map(|unit| format!("journalctl -u {}", unit))
+                .unwrap_or_else(|| "journalctl".to_string());
+            (label, output)
+        } else {
+            return Err(anyhow!(
+                "Lettura log non supportata su questo sistema operativo"
+            ));
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if !output.status.success() {
+            let lower = stderr.to_lowercase();
+            if lower.contains("permission")
+                || lower.contains("unauthorized")
+                || lower.contains("access is denied")
+                || lower.contains("accesso negato")
+            {
+                return Err(anyhow!(
+                    "Permessi insufficienti per leggere {}. Su Linux aggiungi l'utente al gruppo 'systemd-journal' o 'adm'; su Windows alcuni log richiedono privilegi di amministratore.",
+                    label
+                ));
+            }
+            return Err(anyhow!(
+                "Lettura log fallita (exit {}): {}",
+                output.status.code().unwrap_or(-1),
+                stderr.trim()
+            ));
+        }
+
+        let mut entries: Vec<&str> = stdout
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| {
+                filter
+                    .as_ref()
+                    .map(|needle| line.to_lowercase().contains(needle))
+                    .unwrap_or(true)
+            })
+            .collect();
+        if entries.len() > lines {
+            entries.drain(..entries.len() - lines);
+        }
+
+        let mut result = format!("**Log: {}** ", label);
+        match &filter {
+            Some(needle) => result.push_str(&format!(
+                "({} righe contenenti \"{}\")\n\n",
+                entries.len(),
+                needle
+            )),
+            None => result.push_str(&format!("(ultime {} righe)\n\n", entries.len())),
+        }
+
+        // journalctl only shows the user's own entries without the right group membership
+        if stderr.to_lowercase().contains("not seeing messages") {
+            result.push_str("⚠️ Accesso parziale: alcuni log di sistema non sono visibili con i permessi attuali.\n\n");
+        }
+
+        if entries.is_empty() {
+            result.push_str("Nessuna riga trovata.");
+        } else {
+            result.push_str("```\n");
+            result.push_str(&entries.join("\n"));
+            result.push_str("\n```");
+        }
+
+        Ok(result)
+    }
+
+    async fn execute_network_info(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let operation = params
+            .get("operation")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'operation' mancante")?;
+        let target = params
+            .get("target")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'target' mancante")?;
+        let target = validate_network_target(target)?;
+
+        match operation.trim().to_lowercase().as_str() {
+            "ping" => {
+                let count = params
+                    .get("count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(4)
+                    .clamp(1, 10);
+                Self::network_ping(target, count).await
+            }
+            "dns_lookup" | "dns" => Self::network_dns_lookup(target).await,
+            "check_port" | "port" => {
+                let port = params
+                    .get("port")
+                    .and_then(|v| {
+                        v.as_u64()
+                            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+                    })
+                    .context("Parametro 'port' mancante")?;
+                let port = u16::try_from(port)
+                    .ok()
+                    .filter(|p| *p > 0)
+                    .context("Porta non valida (1-65535)")?;
+                Self::network_check_port(target, port).await
+            }
+            other => Err(anyhow!(
+                "Operazione '{}' non supportata. Usa ping, dns_lookup o check_port",
+                other
+            )),
+        }
+    }
+
+    async fn network_ping(target: &str, count: u64) -> Result<String> {
+        // The system ping avoids needing raw socket privileges
+        let mut command = tokio::process::Command::new("ping");
+        if cfg!(target_os = "windows") {
+            command
+                .arg("-n")
+                .arg(count.to_string())
+                .arg("-w")
+                .arg("2000");
+        } else {
+            command.arg("-c").arg(count.to_string()).arg("-W").arg("2");
+        }
+        command
+            .arg(target)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let timeout = std::time::Duration::from_secs(3 * count + 5);
+        let output = tokio::time::timeout(timeout, command.output())
+            .await
+            .map_err(|_| anyhow!("Ping verso {} scaduto dopo {}s", target, timeout.as_secs()))?
+            .context("Errore esecuzione ping")?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let status = if output.status.success() {
+            "✅ Host raggiungibile"
+        } else {
+            "❌ Host non raggiungibile"
+        };
+
+        Ok(format!(
+            "**Ping {}**: {}\n```\n{}{}\n```",
+            target,
+            status,
+            stdout.trim_end(),
+            stderr.trim_end()
+        ))
+    }
+
+    async fn network_dns_lookup(target: &str) -> Result<String> {
+        let lookup = tokio::net::lookup_host((target, 0));
+        let addresses = tokio::time::timeout(std::time::Duration::from_secs(5), lookup)
+            .await
+            .map_err(|_| anyhow!("Risoluzione DNS di {} scaduta", target))?
+            .with_context(|| format!("Impossibile risolvere {}", target))?;
+
+        let mut ips: Vec<std::net::IpAddr> = Vec::new();
+        for address in addresses {
+            if !ips.contains(&address.ip()) {
+                ips.push(address.ip());
+            }
+        }
+
+        if ips.is_empty() {
+            return Ok(format!("Nessun indirizzo trovato per {}", target));
+        }
+
+        let mut result = format!("**DNS {}**: {} indirizzi\n", target, ips.len());
+        for ip in ips {
+            let kind = if ip.is_ipv4() { "IPv4" } else { "IPv6" };
+            result.push_str(&format!("- {} ({})\n", ip, kind));
+        }
+        Ok(result)
+    }
+
+    async fn network_check_port(target: &str, port: u16) -> Result<String> {
+        let started = std::time::Instant::now();
+        let connect = tokio::net::TcpStream::connect((target, port));
+
+        let outcome = match tokio::time::timeout(std::time::Duration::from_secs(3), connect).await {
+            Ok(Ok(_)) => format!(
+                "✅ Porta {} aperta su {} (connessione in {} ms)",
+                port,
+                target,
+                started.elapsed().as_millis()
+            ),
+            Ok(Err(err)) if err.kind() == ErrorKind::ConnectionRefused => {
+                format!(
+                    "❌ Porta {} chiusa su {} (connessione rifiutata)",
+                    port, target
+                )
+            }
+            Ok(Err(err)) => format!("❌ Porta {} non raggiungibile su {}: {}", port, target, err),
+            Err(_) => format!(
+                "⚠️ Porta {} su {}: nessuna risposta entro 3s (filtrata o host irraggiungibile)",
+                port, target
+            ),
+        };
+
+        Ok(outcome)
+    }
+
+    async fn execute_browser_open(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let url_str = params
+            .get("url")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'url' mancante")?;
+
+        // URL will be opened by the frontend via tauri-plugin-opener
+        Ok(format!("URL: {}", url_str))
+    }
+
+    async fn execute_web_fetch(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let raw_url = params
+            .get("url")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .context("Parametro 'url' mancante")?;
+        let max_chars = params
+            .get("max_chars")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(500, WEB_FETCH_MAX_CHARS))
+            .unwrap_or(WEB_FETCH_DEFAULT_CHARS);
+
+        let mut url =
+            Url::parse(raw_url).with_context(|| format!("URL non valido: {}", raw_url))?;
+
+        // Redirects are followed by hand so every hop goes through the address check
+        let mut redirects = 0;
+        let mut response = loop {
+            let client = web_fetch_client(&url).await?;
+            let response = client
+                .get(url.clone())
+                .timeout(WEB_FETCH_TIMEOUT)
+                .send()
+                .await
+                .with_context(|| format!("Impossibile scaricare {}", url))?;
+
+            if !response.status().is_redirection() {
+                break response
+                    .error_for_status()
+                    .context("La pagina ha restituito un errore")?;
+            }
+
+            redirects += 1;
+            if redirects > WEB_FETCH_MAX_REDIRECTS {
+                return Err(anyhow!("Troppi redirect per {}", raw_url));
+            }
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .context("Redirect senza destinazione")?;
+            url = url
+                .join(location)
+                .with_context(|| format!("Redirect non valido: {}", location))?;
+        };
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase();
+        let is_html = content_type.is_empty() || content_type.contains("html");
+        if !is_html && !content_type.starts_with("text/") {
+            return Err(anyhow!(
+                "Tipo di contenuto non supportato: {} (solo pagine HTML o testo)",
+                content_type
+            ));
+        }
+
+        let mut body: Vec<u8> = Vec::new();
+        let mut body_truncated = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("Errore durante il download della pagina")?
+        {
+            let remaining = WEB_FETCH_MAX_BYTES - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                body_truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let raw = String::from_utf8_lossy(&body);
+
+        let page = if is_html {
+            extract_readable_text(&raw)
+        } else {
+            ReadablePage {
+                title: None,
+                text: raw.trim().to_string(),
+            }
+        };
+
+        let mut output = format!(
+            "🌐 {}\nURL: {}\n\n",
+            page.title.as_deref().unwrap_or("Pagina web"),
+            url
+        );
+
+        let text_chars = page.text.chars().count();
+        if is_html && text_chars < WEB_FETCH_MIN_TEXT {
+            output.push_str(
+                "⚠️ La pagina contiene poco testo statico: probabilmente richiede JavaScript, che non viene eseguito. Di seguito solo il contenuto dell'HTML statico.\n\n",
+            );
+        }
+        if page.text.is_empty() {
+            output.push_str("(nessun testo estratto)");
+        } else {
+            output.extend(page.text.chars().take(max_chars));
+        }
+
+        if text_chars > max_chars {
+            output.push_str(&format!(
+                "\n\n[... testo troncato a {} caratteri su {}]",
+                max_chars, text_chars
+            ));
+        } else if body_truncated {
+            output.push_str(&format!(
+                "\n\n[... pagina troncata: superato il limite di {} MB]",
+                WEB_FETCH_MAX_BYTES / (1024 * 1024)
+            ));
+        }
+
+        Ok(output)
+    }
+
+    async fn execute_web_search(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'query' mancante")?;
+
+        let mut max_results = params
+            .get("max_results")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.clamp(1, 8) as usize)
+            .unwrap_or(5);
+
+        let (refined_query, preprocess_note) = Self::refine_web_query(query);
+        let search_query = if refined_query.trim().is_empty() {
+            query.to_string()
+        } else {
+            refined_query
+        };
+        let intent = Self::classify_query_intent(&search_query);
+        if matches!(intent, QueryIntent::DeepAnalysis) && max_results < 4 {
+            max_results = 4;
+        }
+
+        let encoded_query = urlencoding::encode(&search_query);
+        let request_url = format!(
+            "https://api.duckduckgo.com/?q={}&format=json&no_html=1&skip_disambig=1&no_redirect=1&t=matepro-agent",
+            encoded_query
+        );
+
+        let client = http_client::shared_client();
+
+        let response = client
+            .get(&request_url)
+            .timeout(WEB_REQUEST_TIMEOUT)
+            .send()
+            .await
+            .context("Errore durante la richiesta di ricerca web")?
+            .error_for_status()
+            .context("Risposta non valida dal motore di ricerca")?;
+
+        let payload: serde_json::Value = response
+            .json()
+            .await
+            .context("Impossibile analizzare la risposta del motore di ricerca")?;
+
+        let mut quick_answers: Vec<String> = Vec::new();
+        let mut filtered_out = 0usize;
+        let mut link_rewrites: Vec<(String, String)> = Vec::new();
+
+        if payload
+            .get("Answer")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .is_some()
+        {
+            // Risposte senza fonte esplicita vengono ignorate per mantenere solo contenuti verificabili
+            filtered_out += 1;
+        }
+
+        if let Some(abstract_text) = payload
+            .get("AbstractText")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+        {
+            if let Some(abstract_url) = payload
+                .get("AbstractURL")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.trim().is_empty())
+            {
+                if AgentSystem::is_trusted_url(abstract_url) {
+                    quick_answers.push(format!(
+                        "Sintesi principale: {} (fonte: {})",
+                        abstract_text.trim(),
+                        abstract_url
+                    ));
+                } else {
+                    filtered_out += 1;
+                }
+            }
+        }
+
+        let mut results: Vec<(String, Option<String>, Option<String>)> = Vec::new();
+        let mut news_items: Vec<(String, Option<String>, String)> = Vec::new();
+        if matches!(intent, QueryIntent::News) {
+            if let Ok((items, rewrites)) = Self::fetch_google_news(&client, &search_query, max_results).await {
+                news_items = items;
+                link_rewrites.extend(rewrites);
+            }
+        }
+
+        if !news_items.is_empty() {
+            news_items = AgentSystem::filter_trusted_news(news_items, &mut filtered_out);
+        }
+
+        fn push_entry(
+            results: &mut Vec<(String, Option<String>, Option<String>)>,
+            max_results: usize,
+            text: &str,
+            url: Option<&str>,
+            filtered_out: &mut usize,
+        ) {
+            if results.len() >= max_results {
+                return;
+            }
+
+            let trimmed = text.trim();
+            if trimmed.is_empty() {
+                return;
+            }
+
+            let (title_raw, snippet_raw) = if let Some((t, s)) = trimmed.split_once(" - ") {
+                (t.trim(), Some(s.trim()))
+            } else {
+                (trimmed, None)
+            };
+
+            let snippet = snippet_raw.and_then(|text| {
+                let cleaned = text.trim();
+                if cleaned.is_empty() {
+                    None
+                } else {
+                    Some(cleaned.chars().take(220).collect::<String>())
+                }
+            });
+
+            let title = title_raw.trim();
+            let url = url
+                .map(|u| u.trim())
+                .filter(|u| !u.is_empty())
+                .map(|u| u.to_string());
+
+            if let Some(ref url_value) = url {
+                if !AgentSystem::is_trusted_url(url_value) {
+                    *filtered_out += 1;
+                    return;
+                }
+            }
+
+            if !title.is_empty() {
+                results.push((title.to_string(), snippet, url));
+            }
+        }
+
+        if !matches!(intent, QueryIntent::News) {
+            if let Some(items) = payload.get("Results").and_then(|v| v.as_array()) {
+                for item in items {
+                    if results.len() >= max_results {
+                        break;
+                    }
+
+                    if let Some(text) = item.get("Text").and_then(|v| v.as_str()) {
+                        let url = item.get("FirstURL").and_then(|v| v.as_str());
+                        push_entry(&mut results, max_results, text, url, &mut filtered_out);
+                    }
+                }
+            }
+
+            if results.len() < max_results {
+                if let Some(related) = payload.get("RelatedTopics").and_then(|v| v.as_array()) {
+                    for entry in related {
+                        if results.len() >= max_results {
+                            break;
+                        }
+
+                        if let Some(topics) = entry.get("Topics").and_then(|v| v.as_array()) {
+                            for topic in topics {
+                                if results.len() >= max_results {
+                                    break;
+                                }
+
+                                if let Some(text) = topic.get("Text").and_then(|v| v.as_str()) {
+                                    let url = topic.get("FirstURL").and_then(|v| v.as_str());
+                                    push_entry(&mut results, max_results, text, url, &mut filtered_out);
+                                }
+                            }
+                        } else if let Some(text) = entry.get("Text").and_then(|v| v.as_str()) {
+                            let url = entry.get("FirstURL").and_then(|v| v.as_str());
+                            push_entry(&mut results, max_results, text, url, &mut filtered_out);
+                        }
+                    }
+                }
+            }
+        }
+
+        let fallback_url = format!("https://www.google.com/search?q={}", encoded_query);
+        let mut output = String::new();
+        output.push_str(&format!("🌐 Ricerca web per \"{}\"\n", query));
+        if search_query != query {
+            output.push_str(&format!("➡️ Query ottimizzata: \"{}\"\n", search_query));
+        }
+        if let Some(note) = &preprocess_note {
+            output.push_str(&format!("ℹ️ {}\n", note));
+        }
+        output.push('\n');
+
+        let mut had_structured = false;
+
+        if !quick_answers.is_empty() {
+            for answer in &quick_answers {
+                output.push_str(answer);
+                output.push('\n');
+            }
+            output.push('\n');
+            had_structured = true;
+        }
+
+        if !news_items.is_empty() {
+            output.push_str("📰 Notizie recenti\n");
+            for (idx, (title, snippet, url)) in news_items.iter().enumerate() {
+                let display_domain = Url::parse(url)
+                    .ok()
+                    .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
+                    .unwrap_or_else(|| url.clone());
+
+                output.push_str(&format!(
+                    "{}. [{}]({}) — {}\n",
+                    idx + 1,
+                    title,
+                    url,
+                    display_domain
+                ));
+                if let Some(snippet) = snippet {
+                    output.push_str(&format!("   {}\n", snippet));
+                }
+                output.push('\n');
+            }
+            had_structured = true;
+        }
+
+        if !results.is_empty() {
+            for (idx, (title, snippet, url)) in results.iter().enumerate() {
+                let (title_line, domain_label) = if let Some(url) = url {
+                    let display_domain = Url::parse(url)
+                        .ok()
+                        .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
+                        .unwrap_or_else(|| url.clone());
+                    (format!("[{}]({})", title, url), format!(" — {}", display_domain))
+                } else {
+                    (title.clone(), String::new())
+                };
+
+                output.push_str(&format!("{}. {}{}\n", idx + 1, title_line, domain_label));
+                if let Some(snippet) = snippet {
+                    output.push_str(&format!("   {}\n", snippet));
+                }
+                output.push('\n');
+            }
+            had_structured = true;
+            if matches!(intent, QueryIntent::DeepAnalysis) && results.len() >= 2 {
+                output.push_str("ℹ️ Ho incluso più fonti per supportare l'analisi richiesta.\n\n");
+            }
+        }
+
+        if !had_structured {
+            if let Ok((items, rewrites)) = Self::fetch_google_news(&client, &search_query, max_results).await {
+                let news_items = AgentSystem::filter_trusted_news(items, &mut filtered_out);
+                link_rewrites.extend(rewrites);
+                if !news_items.is_empty() {
+                    output.push_str("📰 Notizie recenti\n");
+                    for (idx, (title, snippet, url)) in news_items.iter().enumerate() {
+                        let display_domain = Url::parse(url)
+                            .ok()
+                            .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
+                            .unwrap_or_else(|| url.clone());
+
+                        output.push_str(&format!(
+                            "{}. [{}]({}) — {}\n",
+                            idx + 1,
+                            title,
+                            url,
+                            display_domain
+                        ));
+                        if let Some(snippet) = snippet {
+                            output.push_str(&format!("   {}\n", snippet));
+                        }
+                        output.push('\n');
+                    }
+                    had_structured = true;
+                }
+            }
+        }
+
+        if filtered_out > 0 {
+            output.push_str(&format!(
+                "ℹ️ {} risultati sono stati scartati perché la fonte non rientra nell'elenco di siti affidabili.\n\n",
+                filtered_out
+            ));
+        }
+
+        if !link_rewrites.is_empty() {
+            output.push_str("🔧 Ho normalizzato i seguenti link provenienti da Google News per puntare alla fonte originale:\n");
+            for (original, normalized) in &link_rewrites {
+                output.push_str(&format!("- {} → {}\n", original, normalized));
+            }
+            output.push('\n');
+        }
+
+        if had_structured {
+            output.push_str(&format!("🔗 Altri risultati: {}", fallback_url));
+        } else {
+            output.push_str("⚠️ Non ho trovato risultati strutturati dal motore di ricerca.\n");
+            output.push_str(&format!("🔗 Prova a consultare: {}", fallback_url));
+        }
+
+        Ok(output)
+    }
+
+    fn refine_web_query(query: &str) -> (String, Option<String>) {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            return (String::new(), None);
+        }
+
+        let mut working = trimmed.to_string();
+        let mut notes: Vec<String> = Vec::new();
+
+        if let Some(prefix_match) = COMMAND_PREFIX_RE.find(trimmed) {
+            let remainder = trimmed[prefix_match.end()..].trim();
+            if !remainder.is_empty() {
+                working = remainder.to_string();
+                notes.push(
+                    "Ho ignorato l'istruzione iniziale per concentrarmi sui termini di ricerca principali.".to_string(),
+                );
+            }
+        }
+
+        if REDUNDANT_ODIERNA_RE.is_match(&working) {
+            let cleaned = REDUNDANT_ODIERNA_RE
+                .replace_all(&working, "")
+                .to_string();
+            if !cleaned.trim().is_empty() {
+                working = cleaned;
+            }
+        }
+
+        let today = Local::now().date_naive();
+
+        Self::apply_relative_keyword(
+            &mut working,
+            &QUERY_GIORNATA_ODIERNA_RE,
+            today,
+            "giornata odierna",
+            &mut notes,
+        );
+        Self::apply_relative_keyword(
+            &mut working,
+            &QUERY_ODIERNA_WORD_RE,
+            today,
+            "odierna",
+            &mut notes,
+        );
+        Self::apply_relative_keyword(
+            &mut working,
+            &QUERY_OGGI_RE,
+            today,
+            "oggi",
+            &mut notes,
+        );
+        Self::apply_relative_keyword(
+            &mut working,
+            &QUERY_IERI_RE,
+            today - Duration::days(1),
+            "ieri",
+            &mut notes,
+        );
+        Self::apply_relative_keyword(
+            &mut working,
+            &QUERY_DOMANI_RE,
+            today + Duration::days(1),
+            "domani",
+            &mut notes,
+        );
+
+        let normalized = normalize_whitespace(&working);
+        let cleaned = normalized.trim();
+
+        let final_query = if cleaned.is_empty() {
+            trimmed.to_string()
+        } else {
+            cleaned.to_string()
+        };
+
+        let note = if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join(" "))
+        };
+
+        (final_query, note)
+    }
+
+    fn classify_query_intent(query: &str) -> QueryIntent {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            return QueryIntent::General;
+        }
+
+        if NEWS_QUERY_RE.is_match(trimmed) {
+            return QueryIntent::News;
+        }
+
+        if ANALYSIS_QUERY_RE.is_match(trimmed) {
+            return QueryIntent::DeepAnalysis;
+        }
+
+        QueryIntent::General
+    }
+
+    fn apply_relative_keyword(
+        working: &mut String,
+        regex: &Regex,
+        date: NaiveDate,
+        label: &str,
+        notes: &mut Vec<String>,
+    ) {
+        if regex.is_match(working) {
+            let formatted = Self::format_italian_date(date);
+            let updated = regex
+                .replace_all(working, formatted.as_str())
+                .to_string();
+            if updated != *working {
+                *working = updated;
+                notes.push(format!("Ho interpretato '{}' come {}.", label, formatted));
+            }
+        }
+    }
+
+    fn format_italian_date(date: NaiveDate) -> String {
+        let month = match date.month() {
+            1 => "gennaio",
+            2 => "febbraio",
+            3 => "marzo",
+            4 => "aprile",
+            5 => "maggio",
+            6 => "giugno",
+            7 => "luglio",
+            8 => "agosto",
+            9 => "settembre",
+            10 => "ottobre",
+            11 => "novembre",
+            12 => "dicembre",
+            _ => "",
+        };
+
+        format!("{} {} {}", date.day(), month, date.year())
+    }
+
+    fn is_trusted_url(url: &str) -> bool {
+        if let Ok(parsed) = Url::parse(url) {
+            if let Some(domain) = parsed.domain() {
+                return AgentSystem::is_trusted_domain(domain);
+            }
+        }
+
+        false
+    }
+
+    fn is_trusted_domain(domain: &str) -> bool {
+        let domain_lc = domain.to_ascii_lowercase();
+
+        for allowed in TRUSTED_DOMAINS {
+            if domain_lc == *allowed {
+                return true;
+            }
+
+            if let Some(prefix) = domain_lc.strip_suffix(allowed) {
+                if prefix.ends_with('.') {
+                    return true;
+                }
+            }
+        }
+
+        if domain_lc.ends_with(".gov")
+            || domain_lc.ends_with(".gov.it")
+            || domain_lc.ends_with(".gouv.fr")
+            || domain_lc.ends_with(".parliament.uk")
+            || domain_lc.ends_with(".int")
+            || domain_lc.ends_with(".eu")
+            || domain_lc.ends_with(".europa.eu")
+            || domain_lc.ends_with(".edu")
+        {
+            return true;
+        }
+
+        false
+    }
+
+    fn filter_trusted_news(
+        items: Vec<(String, Option<String>, String)>,
+        filtered_out: &mut usize,
+    ) -> Vec<(String, Option<String>, String)> {
+        items
+            .into_iter()
+            .filter(|(_, _, url)| {
+                if AgentSystem::is_trusted_url(url) {
+                    true
+                } else {
+                    *filtered_out += 1;
+                    false
+                }
+            })
+            .collect()
+    }
+
+    fn normalize_google_news_link(url: &str) -> (String, Option<(String, String)>) {
+        if let Ok(parsed) = Url::parse(url) {
+            if parsed
+                .domain()
+                .map(|domain| domain == "news.google.com")
+                .unwrap_or(false)
+            {
+                let mut candidate = None;
+                for (key, value) in parsed.query_pairs() {
+                    if key == "url" || key == "u" {
+                        let decoded = value.into_owned();
+                        if !decoded.trim().is_empty() {
+                            candidate = Some(decoded);
+                            break;
+                        }
+                    }
+                }
+
+                if let Some(target) = candidate {
+                    if target != url {
+                        if let Ok(parsed_target) = Url::parse(&target) {
+                            let normalized_string: String = parsed_target.into();
+                            let rewrite_pair = (url.to_string(), normalized_string.clone());
+                            return (normalized_string, Some(rewrite_pair));
+                        } else {
+                            return (target.clone(), Some((url.to_string(), target)));
+                        }
+                    }
+                }
+            }
+        }
+
+        (url.to_string(), None)
+    }
+
+    async fn fetch_google_news(
+        client: &Client,
+        query: &str,
+        max_results: usize,
+    ) -> Result<(
+        Vec<(String, Option<String>, String)>,
+        Vec<(String, String)>,
+    )> {
+        let encoded_query = urlencoding::encode(query);
+        let rss_url = format!(
+            "https://news.google.com/rss/search?q={}&hl=it&gl=IT&ceid=IT:it",
+            encoded_query
+        );
+
+        let response = client
+            .get(&rss_url)
+            .timeout(WEB_REQUEST_TIMEOUT)
+            .send()
+            .await
+            .context("Errore durante il recupero delle notizie")?
+            .error_for_status()
+            .context("Risposta non valida da Google News")?;
+
+        let body = response
+            .text()
+            .await
+            .context("Impossibile leggere il feed di notizie")?;
+
+        let mut items = Vec::new();
+        let mut rewrites = Vec::new();
+        let recency_cutoff = Utc::now() - Duration::hours(48); // enforce recent news only
+
+        for capture in NEWS_ITEM_RE.captures_iter(&body) {
+            let block = capture.get(1).map(|m| m.as_str()).unwrap_or("");
+
+            let published = capture_rss_field(block, &NEWS_PUBDATE_RE)
+                .and_then(|raw| DateTime::parse_from_rfc2822(&raw).ok())
+                .map(|dt: DateTime<FixedOffset>| dt.with_timezone(&Utc));
+
+            if let Some(published) = published {
+                if published < recency_cutoff {
+                    continue;
+                }
+            } else {
+                continue;
+            }
+
+            let title = capture_rss_field(block, &NEWS_TITLE_RE).unwrap_or_default();
+            let raw_link = capture_rss_field(block, &NEWS_LINK_RE).unwrap_or_default();
+            let (link, rewrite) = AgentSystem::normalize_google_news_link(&raw_link);
+            if let Some(pair) = rewrite {
+                rewrites.push(pair);
+            }
+
+            if title.is_empty() || link.is_empty() {
+                continue;
+            }
+
+            let description = capture_rss_field(block, &NEWS_DESC_RE);
+            let snippet = description.and_then(|raw| {
+                let stripped = HTML_TAG_RE.replace_all(&raw, " ");
+                let normalized = normalize_whitespace(stripped.as_ref());
+                if normalized.is_empty() {
+                    None
+                } else {
+                    Some(normalized.chars().take(220).collect::<String>())
+                }
+            });
+
+            items.push((title, snippet, link));
+
+            if items.len() >= max_results {
+                break;
+            }
+        }
+
+        Ok((items, rewrites))
+    }
+
+    async fn execute_map_open(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let location = params
+            .get("location")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'location' mancante")?;
+
+        let mode = params
+            .get("mode")
+            .and_then(|v| v.as_str())
+            .unwrap_or("search");
+
+        let encoded_location = urlencoding::encode(location);
+
+        let map_url = match mode {
+            "directions" => format!(
+                "https://www.google.com/maps/dir/?api=1&destination={}",
+                encoded_location
+            ),
+            _ => format!(
+                "https://www.google.com/maps/search/?api=1&query={}",
+                encoded_location
+            ),
+        };
+
+        Ok(format!("URL: {}", map_url))
+    }
+
+    async fn execute_youtube_search(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'query' mancante")?;
+
+        let encoded_query = urlencoding::encode(query);
+        let youtube_url = format!(
+            "https://www.youtube.com/results?search_query={}",
+            encoded_query
+        );
+        Ok(format!("URL: {}", youtube_url))
+    }
+
+    async fn execute_wikipedia_lookup(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Parametro 'query' mancante o vuoto"))?;
+
+        let lang = match params.get("lang").and_then(|v| v.as_str()) {
+            Some(lang) if !lang.trim().is_empty() => lang.trim().to_lowercase(),
+            _ => default_wikipedia_language(),
+        };
+        if lang.len() > 12 || !lang.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+            anyhow::bail!("Codice lingua non valido: {}", lang);
+        }
+
+        let mut note = None;
+        let summary = match fetch_wikipedia_summary(&lang, query).await? {
+            Some(summary) => summary,
+            None => {
+                // No page with this exact title: use the most relevant search result
+                let titles = search_wikipedia_titles(&lang, query).await?;
+                let Some(best) = titles.first() else {
+                    anyhow::bail!(
+                        "Nessuna voce trovata su Wikipedia ({}) per '{}'",
+                        lang,
+                        query
+                    );
+                };
+                let summary = fetch_wikipedia_summary(&lang, best)
+                    .await?
+                    .with_context(|| format!("Voce '{}' non disponibile", best))?;
+                note = Some(format!(
+                    "Nessuna voce con titolo esatto '{}': mostrato il risultato più pertinente.",
+                    query
+                ));
+                if titles.len() > 1 {
+                    note = note.map(|n| format!("{}\nAltre voci: {}", n, titles[1..].join(", ")));
+                }
+                summary
+            }
+        };
+
+        if summary.kind == "disambiguation" {
+            let alternatives: Vec<String> = search_wikipedia_titles(&lang, query)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|title| *title != summary.title)
+                .collect();
+            let mut output = format!(
+                "🔀 '{}' è una pagina di disambiguazione su Wikipedia ({}).\n",
+                summary.title, lang
+            );
+            if alternatives.is_empty() {
+                output.push_str("Riprova con un titolo più specifico.");
+            } else {
+                output.push_str("Riprova con uno di questi titoli:\n");
+                for title in alternatives {
+                    output.push_str(&format!("- {}\n", title));
+                }
+            }
+            return Ok(output);
+        }
+
+        let mut output = format!("📚 **{}** — Wikipedia ({})\n", summary.title, lang);
+        if let Some(description) = summary.description.filter(|d| !d.is_empty()) {
+            output.push_str(&format!("_{}_\n", description));
+        }
+        if let Some(note) = note {
+            output.push_str(&format!("ℹ️ {}\n", note));
+        }
+        output.push('\n');
+        output.push_str(summary.extract.trim());
+        if let Some(url) = summary.page_url() {
+            output.push_str(&format!("\n\nFonte: [{}]({})", summary.title, url));
+        }
+        Ok(output)
+    }
+
+    async fn execute_text_translate(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let text = params
+            .get("text")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Parametro 'text' mancante o vuoto"))?;
+
+        if text.chars().count() > 1_500 {
+            anyhow::bail!("Testo troppo lungo: massimo 1500 caratteri");
+        }
+
+        let target_language = params
+            .get("target_language")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Parametro 'target_language' mancante"))?
+            .to_lowercase();
+
+        let requested_source = params
+            .get("source_language")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .unwrap_or("auto");
 
-                        calls.push(ToolCall {
-                            tool_name: tool_name.to_string(),
-                            parameters,
-                            raw_text: json_text.to_string(),
-                        });
+        // Detect locally so every provider gets an explicit source language
+        let detected = if requested_source == "auto" {
+            detect_language(text).filter(|d| d.reliable)
+        } else {
+            None
+        };
+        let source_language = detected
+            .as_ref()
+            .map(|d| d.code.as_str())
+            .unwrap_or(requested_source);
+
+        let settings = local_storage::load_app_settings()
+            .map(|s| s.translation)
+            .unwrap_or_default();
+
+        let mut failures = Vec::new();
+        for provider in TranslationProvider::chain(&settings) {
+            match provider
+                .translate(text, source_language, &target_language)
+                .await
+            {
+                Ok(translated) => {
+                    let mut output = String::new();
+                    output.push_str("🌐 Traduzione completata\n");
+                    match &detected {
+                        Some(d) => output.push_str(&format!(
+                            "- Sorgente: {} (rilevata, confidenza {:.0}%)\n",
+                            d.code,
+                            d.confidence * 100.0
+                        )),
+                        None => output.push_str(&format!("- Sorgente: {}\n", source_language)),
                     }
+                    output.push_str(&format!("- Destinazione: {}\n", target_language));
+                    output.push_str(&format!("- Servizio: {}\n", provider.name()));
+                    if !failures.is_empty() {
+                        output.push_str(&format!("- Fallback dopo: {}\n", failures.join("; ")));
+                    }
+                    output.push_str("\n**Risultato**\n");
+                    output.push_str(&translated);
+                    return Ok(output);
                 }
+                Err(err) => failures.push(format!("{} ({})", provider.name(), err)),
             }
         }
 
-        calls
+        Err(anyhow!(
+            "Traduzione non disponibile: {}",
+            failures.join("; ")
+        ))
     }
 
-    pub async fn execute_tool(&mut self, call: &ToolCall) -> Result<ToolResult> {
-        let tool_def = self
-            .tools
-            .get(&call.tool_name)
-            .context("Tool non trovato")?;
-
-        if tool_def.dangerous && !self.allow_dangerous {
-            return Ok(ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some("Tool pericoloso: conferma richiesta".to_string()),
-                tool_name: call.tool_name.clone(),
-            });
-        }
-
-        let result = match call.tool_name.as_str() {
-            "shell_execute" => self.execute_shell(&call.parameters).await,
-            "file_read" => self.execute_file_read(&call.parameters).await,
-            "file_write" => self.execute_file_write(&call.parameters).await,
-            "file_list" => self.execute_file_list(&call.parameters).await,
-            "process_list" => self.execute_process_list().await,
-            "system_info" => self.execute_system_info().await,
-            "browser_open" => self.execute_browser_open(&call.parameters).await,
-            "web_search" => self.execute_web_search(&call.parameters).await,
-            "map_open" => self.execute_map_open(&call.parameters).await,
-            "youtube_search" => self.execute_youtube_search(&call.parameters).await,
-            "text_translate" => self.execute_text_translate(&call.parameters).await,
-            "document_summarize" => self.execute_document_summarize(&call.parameters).await,
-            "excel_improve" => self.execute_excel_improve(&call.parameters).await,
-            "word_improve" => self.execute_word_improve(&call.parameters).await,
-            "sql_connect" => self.execute_sql_connect(&call.parameters).await,
-            "sql_query" => self.execute_sql_query(&call.parameters).await,
-            "sql_list_tables" => self.execute_sql_list_tables(&call.parameters).await,
-            "sql_describe_table" => self.execute_sql_describe_table(&call.parameters).await,
-            "sql_disconnect" => self.execute_sql_disconnect(&call.parameters).await,
-            _ => Err(anyhow::anyhow!("Tool non implementato: {}", call.tool_name)),
-        };
+    async fn execute_detect_language(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let text = params
+            .get("text")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Parametro 'text' mancante o vuoto"))?;
 
-        let tool_result = match result {
-            Ok(output) => ToolResult {
-                success: true,
-                output,
-                error: None,
-                tool_name: call.tool_name.clone(),
-            },
-            Err(e) => ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some(e.to_string()),
-                tool_name: call.tool_name.clone(),
-            },
-        };
+        let detected = detect_language(text)
+            .ok_or_else(|| anyhow!("Impossibile rilevare la lingua del testo"))?;
 
-        if tool_def.dangerous {
-            self.allow_dangerous = false;
+        let mut output = format!(
+            "🗣️ Lingua rilevata: {} ({})\n- Confidenza: {:.0}%",
+            detected.name,
+            detected.code,
+            detected.confidence * 100.0
+        );
+        if !detected.reliable {
+            output.push_str("\n⚠️ Rilevamento poco affidabile: il testo è breve o ambiguo");
         }
-
-        Ok(tool_result)
+        Ok(output)
     }
 
-    pub async fn build_web_search_context(&self, user_message: &str) -> Option<String> {
-        let trimmed = user_message.trim();
-        if trimmed.is_empty() {
-            return None;
-        }
-
-        let intent = Self::classify_query_intent(trimmed);
-        if matches!(intent, QueryIntent::General) {
-            return None;
-        }
+    async fn execute_document_summarize(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
 
-        let (refined_query, preprocess_note) = Self::refine_web_query(trimmed);
-        let search_query = if refined_query.trim().is_empty() {
-            trimmed.to_string()
-        } else {
-            refined_query
-        };
+        let max_sentences = params
+            .get("max_sentences")
+            .and_then(|v| v.as_i64())
+            .map(|n| n.max(1).min(10) as usize)
+            .unwrap_or(5);
 
-        let mut params = HashMap::new();
-        params.insert(
-            "query".to_string(),
-            serde_json::Value::String(search_query.clone()),
-        );
+        let text = extract_text_from_path(Path::new(path))
+            .with_context(|| format!("Impossibile leggere il documento: {}", path))?;
 
-        if matches!(intent, QueryIntent::DeepAnalysis) {
-            params.insert("max_results".to_string(), json!(6));
+        if text.trim().is_empty() {
+            anyhow::bail!("Il documento non contiene testo analizzabile");
         }
 
-        match self.execute_web_search(&params).await {
-            Ok(raw_output) => {
-                let sanitized = raw_output
-                    .lines()
-                    .filter(|line| {
-                        let trimmed = line.trim_start();
-                        !trimmed.starts_with("🔗 Altri risultati:")
-                            && !trimmed.starts_with("🔗 Prova a consultare:")
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n");
+        let summary = summarize_text(&text, max_sentences);
+        let stats = compute_text_statistics(&text);
 
-                if sanitized.trim().is_empty()
-                    || sanitized.contains("⚠️ Non ho trovato risultati strutturati")
-                {
-                    return Some(format!(
-                        "NESSUNA FONTE AFFIDABILE TROVATA per la ricerca \"{}\".\nDevi rispondere seguendo queste istruzioni, senza eccezioni:\n1. Comunica chiaramente che non sono disponibili aggiornamenti verificati per l'argomento richiesto.\n2. Non inventare eventi, non riassumere conoscenze pregresse e non citare fonti inesistenti.\n3. Suggerisci eventualmente di riprovare più tardi o di fornire maggiori dettagli, ma evita qualsiasi speculazione.",
-                        search_query
-                    ));
-                }
+        let mut output = String::new();
+        output.push_str("📝 Riassunto documento\n");
+        output.push_str(&format!(
+            "- parole totali: {}\n- frasi stimate: {}\n- lunghezza media frase: {:.1} parole\n\n",
+            stats.word_count, stats.sentence_count, stats.avg_sentence_len
+        ));
+        output.push_str("**Riassunto**\n");
+        output.push_str(&summary);
 
-                let mut context = String::from(
-                    "ISTRUZIONI FONTI:\n- Usa esclusivamente i riferimenti elencati di seguito.\n- Cita ogni fonte nel formato [Titolo](URL) indicando il dominio.\n- Non integrare conoscenze non supportate dalle fonti elencate.\n- Se qualcosa è incerto o contraddittorio, evidenzialo invece di colmare i vuoti.\n\n",
-                );
-                context.push_str(&sanitized);
+        Ok(output)
+    }
 
-                if let Some(note) = preprocess_note {
-                    context.push_str(&format!("\n\nNota preprocessing: {}", note));
-                }
+    async fn execute_excel_read(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
 
-                Some(context)
-            }
-            Err(err) => Some(format!(
-                "Ricerca web fallita per \"{}\": {}. Comunica all'utente che non è stato possibile ottenere fonti aggiornate.",
-                search_query, err
-            )),
-        }
-    }
+        let sheets: Vec<String> = params
+            .get("sheet")
+            .and_then(|v| v.as_str())
+            .map(|s| {
+                s.split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let range = params
+            .get("range")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|r| !r.is_empty());
 
-    pub fn set_allow_dangerous(&mut self, allow: bool) {
-        self.allow_dangerous = allow;
+        extract_spreadsheet_selection(Path::new(path), &sheets, range)
+            .with_context(|| format!("Impossibile leggere il file Excel: {}", path))
     }
 
-    async fn execute_shell(&self, params: &HashMap<String, serde_json::Value>) -> Result<String> {
-        let command = params
-            .get("command")
+    async fn execute_excel_write(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let path = params
+            .get("path")
             .and_then(|v| v.as_str())
-            .context("Parametro 'command' mancante")?;
+            .context("Parametro 'path' mancante")?;
+        if !path.to_lowercase().ends_with(".xlsx") {
+            anyhow::bail!("Il percorso deve avere estensione .xlsx: {}", path);
+        }
 
-        let output = if cfg!(target_os = "windows") {
-            match Command::new("pwsh")
-                .arg("-NoLogo")
-                .arg("-NoProfile")
-                .arg("-Command")
-                .arg(command)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()
-            {
-                Ok(output) => output,
-                Err(err) if err.kind() == ErrorKind::NotFound => Command::new("powershell")
-                    .arg("-NoLogo")
-                    .arg("-NoProfile")
-                    .arg("-Command")
-                    .arg(command)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .output()
-                    .with_context(|| "Errore esecuzione comando con PowerShell")?,
-                Err(err) => {
-                    return Err(anyhow!("Errore esecuzione comando pwsh: {}", err));
+        let rows = params
+            .get("rows")
+            .and_then(|v| v.as_array())
+            .context("Parametro 'rows' mancante o non è un array")?;
+        let headers: Vec<String> = params
+            .get("headers")
+            .and_then(|v| v.as_array())
+            .map(|values| values.iter().map(json_cell_to_string).collect())
+            .unwrap_or_default();
+        let sheet_name = params
+            .get("sheet_name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or("Foglio1");
+
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet
+            .set_name(sheet_name)
+            .with_context(|| format!("Nome foglio non valido: {}", sheet_name))?;
+
+        let mut row_index: u32 = 0;
+        if !headers.is_empty() {
+            let bold = Format::new().set_bold();
+            for (col, header) in headers.iter().enumerate() {
+                worksheet.write_string_with_format(row_index, col as u16, header, &bold)?;
+            }
+            row_index += 1;
+        }
+
+        for row in rows {
+            let cells = row
+                .as_array()
+                .context("Ogni elemento di 'rows' deve essere un array di celle")?;
+            for (col, cell) in cells.iter().enumerate() {
+                let col = col as u16;
+                match cell {
+                    serde_json::Value::Null => {}
+                    serde_json::Value::Bool(b) => {
+                        worksheet.write_boolean(row_index, col, *b)?;
+                    }
+                    serde_json::Value::Number(n) => match n.as_f64() {
+                        Some(number) => {
+                            worksheet.write_number(row_index, col, number)?;
+                        }
+                        None => {
+                            worksheet.write_string(row_index, col, n.to_string())?;
+                        }
+                    },
+                    other => {
+                        worksheet.write_string(row_index, col, json_cell_to_string(other))?;
+                    }
                 }
             }
-        } else {
-            Command::new("bash")
-                .arg("-lc")
-                .arg(command)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()
-                .context("Errore esecuzione comando")?
-        };
+            row_index += 1;
+        }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        worksheet.autofit();
+        workbook
+            .save(path)
+            .with_context(|| format!("Impossibile salvare il file Excel: {}", path))?;
 
-        if output.status.success() {
-            Ok(format!("{}{}", stdout, stderr))
-        } else {
-            Err(anyhow::anyhow!(
-                "Comando fallito (exit {}): {}{}",
-                output.status.code().unwrap_or(-1),
-                stdout,
-                stderr
-            ))
-        }
+        Ok(format!(
+            "File Excel creato: {} (foglio \"{}\", {} righe di dati{})",
+            path,
+            sheet_name,
+            rows.len(),
+            if headers.is_empty() {
+                ""
+            } else {
+                " + intestazioni"
+            }
+        ))
     }
 
-    async fn execute_file_read(
+    async fn execute_excel_improve(
         &self,
         params: &HashMap<String, serde_json::Value>,
     ) -> Result<String> {
         let path = params
             .get("path")
             .and_then(|v| v.as_str())
-            .context("Parametro 'path' mancante")?;
+            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
 
-        let content =
-            fs::read_to_string(path).context(format!("Impossibile leggere file: {}", path))?;
-        Ok(content)
+        let improvement = analyze_excel(Path::new(path))
+            .with_context(|| format!("Impossibile analizzare il file Excel: {}", path))?;
+
+        Ok(improvement)
     }
 
-    async fn execute_file_write(
+    async fn execute_word_improve(
         &self,
         params: &HashMap<String, serde_json::Value>,
     ) -> Result<String> {
         let path = params
             .get("path")
             .and_then(|v| v.as_str())
-            .context("Parametro 'path' mancante")?;
+            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
 
-        let content = params
-            .get("content")
-            .and_then(|v| v.as_str())
-            .context("Parametro 'content' mancante")?;
+        let improvement = analyze_word_document(Path::new(path))
+            .with_context(|| format!("Impossibile analizzare il file Word: {}", path))?;
 
-        fs::write(path, content).context(format!("Impossibile scrivere file: {}", path))?;
-        Ok(format!("File scritto: {} ({} bytes)", path, content.len()))
+        Ok(improvement)
     }
 
-    async fn execute_file_list(
+    async fn execute_convert_document(
         &self,
         params: &HashMap<String, serde_json::Value>,
     ) -> Result<String> {
-        let path = params
-            .get("path")
+        let source_path = params
+            .get("source_path")
             .and_then(|v| v.as_str())
-            .context("Parametro 'path' mancante")?;
-
-        let recursive = params
-            .get("recursive")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+            .context("Parametro 'source_path' mancante")?;
+        let dest_path = params
+            .get("dest_path")
+            .and_then(|v| v.as_str())
+            .context("Parametro 'dest_path' mancante")?;
 
-        let mut entries = Vec::new();
+        let source = Path::new(source_path);
+        if !source.exists() {
+            anyhow::bail!("File non trovato: {}", source_path);
+        }
+        let dest = Path::new(dest_path);
 
-        if recursive {
-            for entry in WalkDir::new(path).max_depth(5) {
-                if let Ok(e) = entry {
-                    entries.push(e.path().display().to_string());
-                }
-            }
-        } else {
-            for entry in fs::read_dir(path)? {
-                if let Ok(e) = entry {
-                    entries.push(e.path().display().to_string());
-                }
+        let target = params
+            .get("target_format")
+            .and_then(|v| v.as_str())
+            .or_else(|| dest.extension().and_then(|e| e.to_str()))
+            .context("Indica 'target_format' oppure un'estensione in 'dest_path'")?;
+        let target = document_format(target)?;
+
+        // pandoc cannot read PDFs and spreadsheets: those always use the internal extractors
+        let extension = file_extension(source);
+        let pandoc_readable = !matches!(extension.as_str(), "pdf" | "xlsx" | "xls" | "ods");
+        if pandoc_readable {
+            if let Some(result) = convert_with_pandoc(source, dest, target) {
+                result?;
+                return Ok(format!(
+                    "Documento convertito in {} con pandoc: {}",
+                    target,
+                    dest.display()
+                ));
             }
         }
 
-        Ok(entries.join("\n"))
-    }
-
-    async fn execute_process_list(&self) -> Result<String> {
-        let mut sys = System::new_all();
-        sys.refresh_all();
-
-        let mut processes: Vec<String> = sys
-            .processes()
-            .iter()
-            .map(|(pid, process)| {
-                format!(
-                    "PID: {} | Name: {} | CPU: {:.1}% | Memory: {} MB",
-                    pid,
-                    process.name(),
-                    process.cpu_usage(),
-                    process.memory() / 1024 / 1024
-                )
-            })
-            .collect();
-
-        processes.sort();
-        processes.truncate(50);
-        Ok(processes.join("\n"))
-    }
-
-    async fn execute_system_info(&self) -> Result<String> {
-        let mut sys = System::new_all();
-        sys.refresh_all();
-
-        let total_memory = sys.total_memory() / 1024 / 1024;
-        let used_memory = sys.used_memory() / 1024 / 1024;
-        let total_swap = sys.total_swap() / 1024 / 1024;
-        let used_swap = sys.used_swap() / 1024 / 1024;
-
-        let info = format!(
-            "Sistema: {}\nKernel: {}\nCPU: {} cores\nRAM: {} MB / {} MB ({:.1}%)\nSwap: {} MB / {} MB\nProcessi attivi: {}",
-            System::name().unwrap_or_else(|| "Unknown".to_string()),
-            System::kernel_version().unwrap_or_else(|| "Unknown".to_string()),
-            sys.cpus().len(),
-            used_memory,
-            total_memory,
-            (used_memory as f64 / total_memory as f64) * 100.0,
-            used_swap,
-            total_swap,
-            sys.processes().len()
-        );
-
-        Ok(info)
+        convert_document_internally(source, dest, target)?;
+        Ok(format!(
+            "Documento convertito in {}: {}",
+            target,
+            dest.display()
+        ))
     }
 
-    async fn execute_browser_open(
+    async fn execute_sql_connect(
         &self,
         params: &HashMap<String, serde_json::Value>,
     ) -> Result<String> {
-        let url_str = params
-            .get("url")
+        let server = params
+            .get("server")
             .and_then(|v| v.as_str())
-            .context("Parametro 'url' mancante")?;
+            .ok_or_else(|| anyhow!("Parametro 'server' mancante"))?;
 
-        // URL will be opened by the frontend via tauri-plugin-opener
-        Ok(format!("URL: {}", url_str))
-    }
+        let database = params
+            .get("database")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'database' mancante"))?;
 
-    async fn execute_web_search(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let query = params
-            .get("query")
+        let auth_method = params
+            .get("auth_method")
             .and_then(|v| v.as_str())
-            .context("Parametro 'query' mancante")?;
+            .ok_or_else(|| anyhow!("Parametro 'auth_method' mancante (usa 'windows' o 'sql')"))?;
 
-        let mut max_results = params
-            .get("max_results")
-            .and_then(|v| v.as_i64())
-            .map(|n| n.clamp(1, 8) as usize)
-            .unwrap_or(5);
+        let requested_trust = params
+            .get("trust_server_certificate")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-        let (refined_query, preprocess_note) = Self::refine_web_query(query);
-        let search_query = if refined_query.trim().is_empty() {
-            query.to_string()
+        let db_type = mcp_sql::DbType::parse(
+            params
+                .get("db_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+        )?;
+
+        let connection_id = format!("sql_{}", Uuid::new_v4());
+
+        let (username, password) = if auth_method.eq_ignore_ascii_case("windows") {
+            (None, None)
+        } else if auth_method.eq_ignore_ascii_case("sql") {
+            let username = params
+                .get("username")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Parametro 'username' richiesto per SQL auth"))?;
+
+            let password = params
+                .get("password")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Parametro 'password' richiesto per SQL auth"))?;
+
+            (Some(username.to_string()), Some(password.to_string()))
         } else {
-            refined_query
+            return Err(anyhow!("auth_method non valido: usa 'windows' o 'sql'"));
         };
-        let intent = Self::classify_query_intent(&search_query);
-        if matches!(intent, QueryIntent::DeepAnalysis) && max_results < 4 {
-            max_results = 4;
-        }
 
-        let encoded_query = urlencoding::encode(&search_query);
-        let request_url = format!(
-            "https://api.duckduckgo.com/?q={}&format=json&no_html=1&skip_disambig=1&no_redirect=1&t=matepro-agent",
-            encoded_query
-        );
-
-        let client = Client::builder()
-            .user_agent("MatePro-Agent/1.0 (+https://github.com/FrancescoZanti/MatePro)")
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .context("Impossibile creare client HTTP per la ricerca web")?;
+        let mut conn_info = mcp_sql::SqlConnection {
+            connection_id: connection_id.clone(),
+            db_type,
+            server: server.to_string(),
+            database: database.to_string(),
+            auth_type: auth_method.to_lowercase(),
+            username,
+            password,
+            trust_server_certificate: requested_trust,
+        };
 
-        let response = client
-            .get(&request_url)
-            .send()
-            .await
-            .context("Errore durante la richiesta di ricerca web")?
-            .error_for_status()
-            .context("Risposta non valida dal motore di ricerca")?;
+        let (client, effective_trust, auto_trust_applied) = connect_with_optional_trust(
+            |trust| {
+                let attempt = mcp_sql::SqlConnection {
+                    trust_server_certificate: trust,
+                    ..conn_info.clone()
+                };
+                async move { mcp_sql::connect_with_info(&attempt).await }
+            },
+            requested_trust,
+        )
+        .await?;
 
-        let payload: serde_json::Value = response
-            .json()
-            .await
-            .context("Impossibile analizzare la risposta del motore di ricerca")?;
+        drop(client);
+        conn_info.trust_server_certificate = effective_trust;
 
-        let mut quick_answers: Vec<String> = Vec::new();
-        let mut filtered_out = 0usize;
-        let mut link_rewrites: Vec<(String, String)> = Vec::new();
+        self.sql_manager.add_connection(conn_info);
 
-        if payload
-            .get("Answer")
-            .and_then(|v| v.as_str())
-            .filter(|s| !s.trim().is_empty())
-            .is_some()
         {
-            // Risposte senza fonte esplicita vengono ignorate per mantenere solo contenuti verificabili
-            filtered_out += 1;
+            let mut last_conn = self.last_sql_connection_id.lock().await;
+            *last_conn = Some(connection_id.clone());
         }
 
-        if let Some(abstract_text) = payload
-            .get("AbstractText")
-            .and_then(|v| v.as_str())
-            .filter(|s| !s.trim().is_empty())
-        {
-            if let Some(abstract_url) = payload
-                .get("AbstractURL")
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.trim().is_empty())
-            {
-                if AgentSystem::is_trusted_url(abstract_url) {
-                    quick_answers.push(format!(
-                        "Sintesi principale: {} (fonte: {})",
-                        abstract_text.trim(),
-                        abstract_url
-                    ));
-                } else {
-                    filtered_out += 1;
-                }
-            }
-        }
+        let mut response = format!(
+            "✅ Connessione riuscita\nConnection ID: {}\nTipo: {}\nServer: {}\nDatabase: {}\nAutenticazione: {}\nTrust certificato TLS: {}",
+            connection_id,
+            db_type.label(),
+            server,
+            database,
+            auth_method,
+            if effective_trust { "disabilitata" } else { "attiva" }
+        );
 
-        let mut results: Vec<(String, Option<String>, Option<String>)> = Vec::new();
-        let mut news_items: Vec<(String, Option<String>, String)> = Vec::new();
-        if matches!(intent, QueryIntent::News) {
-            if let Ok((items, rewrites)) = Self::fetch_google_news(&client, &search_query, max_results).await {
-                news_items = items;
-                link_rewrites.extend(rewrites);
-            }
+        if auto_trust_applied && !requested_trust {
+            response.push_str(
+                "\n⚠️ Il certificato TLS presentato dal server non è attendibile. La connessione è stata completata accettando il certificato per proseguire.",
+            );
         }
 
-        if !news_items.is_empty() {
-            news_items = AgentSystem::filter_trusted_news(news_items, &mut filtered_out);
-        }
+        Ok(response)
+    }
 
-        fn push_entry(
-            results: &mut Vec<(String, Option<String>, Option<String>)>,
-            max_results: usize,
-            text: &str,
-            url: Option<&str>,
-            filtered_out: &mut usize,
-        ) {
-            if results.len() >= max_results {
-                return;
-            }
+    async fn execute_sql_query(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = self.resolve_connection_id(params).await?;
 
-            let trimmed = text.trim();
-            if trimmed.is_empty() {
-                return;
-            }
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'query' mancante"))?;
 
-            let (title_raw, snippet_raw) = if let Some((t, s)) = trimmed.split_once(" - ") {
-                (t.trim(), Some(s.trim()))
-            } else {
-                (trimmed, None)
-            };
+        let conn_info = self
+            .sql_manager
+            .get_connection(&connection_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
+                    connection_id
+                )
+            })?;
+
+        let settings = local_storage::load_app_settings().unwrap_or_default();
+        let timeout_secs = params
+            .get("timeout_secs")
+            .and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            })
+            .unwrap_or(settings.sql_query_timeout_secs)
+            .clamp(1, SQL_QUERY_MAX_TIMEOUT_SECS);
 
-            let snippet = snippet_raw.and_then(|text| {
-                let cleaned = text.trim();
-                if cleaned.is_empty() {
-                    None
-                } else {
-                    Some(cleaned.chars().take(220).collect::<String>())
-                }
-            });
+        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
 
-            let title = title_raw.trim();
-            let url = url
-                .map(|u| u.trim())
-                .filter(|u| !u.is_empty())
-                .map(|u| u.to_string());
+        let mut result = client
+            .query(query, std::time::Duration::from_secs(timeout_secs))
+            .await?;
+        let masked = if settings.sql_masking.enabled {
+            mcp_sql::mask_query_result(&mut result, &settings.sql_masking.patterns)
+        } else {
+            0
+        };
+        let mut summary = summarize_query_result(&result);
+        if masked > 0 {
+            summary.push_str(&format!("- valori oscurati per privacy: {}\n", masked));
+        }
+        let table_preview = render_result_table(&result, 20);
+        let payload = json!({
+            "columns": result.columns,
+            "rows": result.rows,
+        });
+        let json_pretty = serde_json::to_string_pretty(&payload)?;
 
-            if let Some(ref url_value) = url {
-                if !AgentSystem::is_trusted_url(url_value) {
-                    *filtered_out += 1;
-                    return;
-                }
-            }
+        let mut response = String::new();
+        response.push_str("📊 Risultato query\n");
+        response.push_str(&summary);
 
-            if !title.is_empty() {
-                results.push((title.to_string(), snippet, url));
-            }
+        if let Some(table) = table_preview {
+            response.push_str("\n\n**Anteprima dati**\n");
+            response.push_str(&table);
         }
 
-        if !matches!(intent, QueryIntent::News) {
-            if let Some(items) = payload.get("Results").and_then(|v| v.as_array()) {
-                for item in items {
-                    if results.len() >= max_results {
-                        break;
-                    }
+        response.push_str("\n\n**JSON completo**\n```json\n");
+        response.push_str(&json_pretty);
+        response.push_str("\n```\n");
 
-                    if let Some(text) = item.get("Text").and_then(|v| v.as_str()) {
-                        let url = item.get("FirstURL").and_then(|v| v.as_str());
-                        push_entry(&mut results, max_results, text, url, &mut filtered_out);
-                    }
-                }
-            }
+        Ok(response)
+    }
 
-            if results.len() < max_results {
-                if let Some(related) = payload.get("RelatedTopics").and_then(|v| v.as_array()) {
-                    for entry in related {
-                        if results.len() >= max_results {
-                            break;
-                        }
+    async fn execute_sql_list_tables(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = self.resolve_connection_id(params).await?;
 
-                        if let Some(topics) = entry.get("Topics").and_then(|v| v.as_array()) {
-                            for topic in topics {
-                                if results.len() >= max_results {
-                                    break;
-                                }
+        let conn_info = self
+            .sql_manager
+            .get_connection(&connection_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
+                    connection_id
+                )
+            })?;
 
-                                if let Some(text) = topic.get("Text").and_then(|v| v.as_str()) {
-                                    let url = topic.get("FirstURL").and_then(|v| v.as_str());
-                                    push_entry(&mut results, max_results, text, url, &mut filtered_out);
-                                }
-                            }
-                        } else if let Some(text) = entry.get("Text").and_then(|v| v.as_str()) {
-                            let url = entry.get("FirstURL").and_then(|v| v.as_str());
-                            push_entry(&mut results, max_results, text, url, &mut filtered_out);
-                        }
-                    }
-                }
-            }
-        }
+        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
+        let result = client.list_tables().await?;
 
-        let fallback_url = format!("https://www.google.com/search?q={}", encoded_query);
-        let mut output = String::new();
-        output.push_str(&format!("🌐 Ricerca web per \"{}\"\n", query));
-        if search_query != query {
-            output.push_str(&format!("➡️ Query ottimizzata: \"{}\"\n", search_query));
-        }
-        if let Some(note) = &preprocess_note {
-            output.push_str(&format!("ℹ️ {}\n", note));
-        }
-        output.push('\n');
+        let total_items = result.rows.len();
+        let base_tables = result
+            .rows
+            .iter()
+            .filter(|row| matches_ignore_case(row.get("Type"), "BASE TABLE"))
+            .count();
+        let views = result
+            .rows
+            .iter()
+            .filter(|row| matches_ignore_case(row.get("Type"), "VIEW"))
+            .count();
+        let table_preview = render_result_table(&result, 40);
+        let payload = json!({
+            "columns": result.columns,
+            "rows": result.rows,
+        });
+        let json_pretty = serde_json::to_string_pretty(&payload)?;
 
-        let mut had_structured = false;
+        let mut response = String::new();
+        response.push_str("📋 Tabelle disponibili\n");
+        response.push_str(&format!(
+            "- elementi totali: {}\n- tabelle: {}\n- viste: {}\n",
+            total_items, base_tables, views
+        ));
 
-        if !quick_answers.is_empty() {
-            for answer in &quick_answers {
-                output.push_str(answer);
-                output.push('\n');
-            }
-            output.push('\n');
-            had_structured = true;
+        if let Some(table) = table_preview {
+            response.push_str("\n**Dettaglio**\n");
+            response.push_str(&table);
         }
 
-        if !news_items.is_empty() {
-            output.push_str("📰 Notizie recenti\n");
-            for (idx, (title, snippet, url)) in news_items.iter().enumerate() {
-                let display_domain = Url::parse(url)
-                    .ok()
-                    .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
-                    .unwrap_or_else(|| url.clone());
+        response.push_str("\n\n**JSON completo**\n```json\n");
+        response.push_str(&json_pretty);
+        response.push_str("\n```\n");
 
-                output.push_str(&format!(
-                    "{}. [{}]({}) — {}\n",
-                    idx + 1,
-                    title,
-                    url,
-                    display_domain
-                ));
-                if let Some(snippet) = snippet {
-                    output.push_str(&format!("   {}\n", snippet));
-                }
-                output.push('\n');
-            }
-            had_structured = true;
-        }
+        Ok(response)
+    }
 
-        if !results.is_empty() {
-            for (idx, (title, snippet, url)) in results.iter().enumerate() {
-                let (title_line, domain_label) = if let Some(url) = url {
-                    let display_domain = Url::parse(url)
-                        .ok()
-                        .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
-                        .unwrap_or_else(|| url.clone());
-                    (format!("[{}]({})", title, url), format!(" — {}", display_domain))
-                } else {
-                    (title.clone(), String::new())
-                };
+    async fn execute_sql_describe_table(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = self.resolve_connection_id(params).await?;
 
-                output.push_str(&format!("{}. {}{}\n", idx + 1, title_line, domain_label));
-                if let Some(snippet) = snippet {
-                    output.push_str(&format!("   {}\n", snippet));
-                }
-                output.push('\n');
-            }
-            had_structured = true;
-            if matches!(intent, QueryIntent::DeepAnalysis) && results.len() >= 2 {
-                output.push_str("ℹ️ Ho incluso più fonti per supportare l'analisi richiesta.\n\n");
-            }
-        }
+        let schema = params
+            .get("schema")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'schema' mancante"))?;
 
-        if !had_structured {
-            if let Ok((items, rewrites)) = Self::fetch_google_news(&client, &search_query, max_results).await {
-                let news_items = AgentSystem::filter_trusted_news(items, &mut filtered_out);
-                link_rewrites.extend(rewrites);
-                if !news_items.is_empty() {
-                    output.push_str("📰 Notizie recenti\n");
-                    for (idx, (title, snippet, url)) in news_items.iter().enumerate() {
-                        let display_domain = Url::parse(url)
-                            .ok()
-                            .and_then(|parsed| parsed.domain().map(|d| d.to_string()))
-                            .unwrap_or_else(|| url.clone());
+        let table = params
+            .get("table")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'table' mancante"))?;
 
-                        output.push_str(&format!(
-                            "{}. [{}]({}) — {}\n",
-                            idx + 1,
-                            title,
-                            url,
-                            display_domain
-                        ));
-                        if let Some(snippet) = snippet {
-                            output.push_str(&format!("   {}\n", snippet));
-                        }
-                        output.push('\n');
-                    }
-                    had_structured = true;
-                }
-            }
-        }
+        let conn_info = self
+            .sql_manager
+            .get_connection(&connection_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
+                    connection_id
+                )
+            })?;
+
+        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
+        let result = client.describe_table(schema, table).await?;
+
+        let total_columns = result.rows.len();
+        let highlights: Vec<String> = result
+            .rows
+            .iter()
+            .take(5)
+            .map(|row| describe_column_row(row))
+            .collect();
+        let highlight_text = if highlights.is_empty() {
+            "nessuna colonna trovata".to_string()
+        } else {
+            highlights.join("; ")
+        };
+        let table_preview = render_result_table(&result, 50);
+        let payload = json!({
+            "columns": result.columns,
+            "rows": result.rows,
+        });
+        let json_pretty = serde_json::to_string_pretty(&payload)?;
 
-        if filtered_out > 0 {
-            output.push_str(&format!(
-                "ℹ️ {} risultati sono stati scartati perché la fonte non rientra nell'elenco di siti affidabili.\n\n",
-                filtered_out
-            ));
-        }
+        let mut response = String::new();
+        response.push_str(&format!("🔍 Struttura tabella {}.{}\n", schema, table));
+        response.push_str(&format!(
+            "- colonne totali: {}\n- anteprima: {}\n",
+            total_columns, highlight_text
+        ));
 
-        if !link_rewrites.is_empty() {
-            output.push_str("🔧 Ho normalizzato i seguenti link provenienti da Google News per puntare alla fonte originale:\n");
-            for (original, normalized) in &link_rewrites {
-                output.push_str(&format!("- {} → {}\n", original, normalized));
-            }
-            output.push('\n');
+        if let Some(table_markdown) = table_preview {
+            response.push_str("\n**Dettaglio**\n");
+            response.push_str(&table_markdown);
         }
 
-        if had_structured {
-            output.push_str(&format!("🔗 Altri risultati: {}", fallback_url));
-        } else {
-            output.push_str("⚠️ Non ho trovato risultati strutturati dal motore di ricerca.\n");
-            output.push_str(&format!("🔗 Prova a consultare: {}", fallback_url));
-        }
+        response.push_str("\n\n**JSON completo**\n```json\n");
+        response.push_str(&json_pretty);
+        response.push_str("\n```\n");
 
-        Ok(output)
+        Ok(response)
     }
 
-    fn refine_web_query(query: &str) -> (String, Option<String>) {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            return (String::new(), None);
-        }
+    async fn execute_sql_schema(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = self.resolve_connection_id(params).await?;
 
-        let mut working = trimmed.to_string();
-        let mut notes: Vec<String> = Vec::new();
+        let schema = params
+            .get("schema")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let max_tables = params
+            .get("max_tables")
+            .and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            })
+            .unwrap_or(mcp_sql::SCHEMA_DEFAULT_TABLES as u64)
+            .clamp(1, mcp_sql::SCHEMA_MAX_TABLES as u64) as usize;
 
-        if let Some(prefix_match) = COMMAND_PREFIX_RE.find(trimmed) {
-            let remainder = trimmed[prefix_match.end()..].trim();
-            if !remainder.is_empty() {
-                working = remainder.to_string();
-                notes.push(
-                    "Ho ignorato l'istruzione iniziale per concentrarmi sui termini di ricerca principali.".to_string(),
-                );
-            }
-        }
+        let conn_info = self
+            .sql_manager
+            .get_connection(&connection_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
+                    connection_id
+                )
+            })?;
 
-        if REDUNDANT_ODIERNA_RE.is_match(&working) {
-            let cleaned = REDUNDANT_ODIERNA_RE
-                .replace_all(&working, "")
-                .to_string();
-            if !cleaned.trim().is_empty() {
-                working = cleaned;
-            }
-        }
+        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
+        let result = mcp_sql::database_schema(client.sql_server()?, schema, max_tables).await?;
 
-        let today = Local::now().date_naive();
+        Ok(render_database_schema(&result, schema))
+    }
 
-        Self::apply_relative_keyword(
-            &mut working,
-            &QUERY_GIORNATA_ODIERNA_RE,
-            today,
-            "giornata odierna",
-            &mut notes,
-        );
-        Self::apply_relative_keyword(
-            &mut working,
-            &QUERY_ODIERNA_WORD_RE,
-            today,
-            "odierna",
-            &mut notes,
-        );
-        Self::apply_relative_keyword(
-            &mut working,
-            &QUERY_OGGI_RE,
-            today,
-            "oggi",
-            &mut notes,
-        );
-        Self::apply_relative_keyword(
-            &mut working,
-            &QUERY_IERI_RE,
-            today - Duration::days(1),
-            "ieri",
-            &mut notes,
-        );
-        Self::apply_relative_keyword(
-            &mut working,
-            &QUERY_DOMANI_RE,
-            today + Duration::days(1),
-            "domani",
-            &mut notes,
-        );
+    async fn execute_sql_explain(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = self.resolve_connection_id(params).await?;
 
-        let normalized = normalize_whitespace(&working);
-        let cleaned = normalized.trim();
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Parametro 'query' mancante"))?;
 
-        let final_query = if cleaned.is_empty() {
-            trimmed.to_string()
-        } else {
-            cleaned.to_string()
-        };
+        let conn_info = self
+            .sql_manager
+            .get_connection(&connection_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
+                    connection_id
+                )
+            })?;
 
-        let note = if notes.is_empty() {
-            None
-        } else {
-            Some(notes.join(" "))
-        };
+        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
+        let plans = mcp_sql::explain_query(client.sql_server()?, query).await?;
 
-        (final_query, note)
+        Ok(render_query_plans(&plans))
     }
 
-    fn classify_query_intent(query: &str) -> QueryIntent {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            return QueryIntent::General;
-        }
+    async fn execute_sql_disconnect(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        let connection_id = if let Some(id) = params.get("connection_id").and_then(|v| v.as_str()) {
+            id.to_string()
+        } else {
+            let last = self.last_sql_connection_id.lock().await;
+            last.clone()
+                .ok_or_else(|| anyhow!("Nessuna connessione SQL attiva da chiudere"))?
+        };
 
-        if NEWS_QUERY_RE.is_match(trimmed) {
-            return QueryIntent::News;
+        let removed = self.sql_manager.remove_connection(&connection_id);
+        if removed.is_none() {
+            return Err(anyhow!("Connessione '{}' non trovata", connection_id));
         }
 
-        if ANALYSIS_QUERY_RE.is_match(trimmed) {
-            return QueryIntent::DeepAnalysis;
+        {
+            let mut last = self.last_sql_connection_id.lock().await;
+            if last.as_ref() == Some(&connection_id) {
+                *last = None;
+            }
         }
 
-        QueryIntent::General
+        Ok(format!(
+            "✅ Connessione '{}' chiusa correttamente.",
+            connection_id
+        ))
     }
 
-    fn apply_relative_keyword(
-        working: &mut String,
-        regex: &Regex,
-        date: NaiveDate,
-        label: &str,
-        notes: &mut Vec<String>,
-    ) {
-        if regex.is_match(working) {
-            let formatted = Self::format_italian_date(date);
-            let updated = regex
-                .replace_all(working, formatted.as_str())
-                .to_string();
-            if updated != *working {
-                *working = updated;
-                notes.push(format!("Ho interpretato '{}' come {}.", label, formatted));
+    async fn resolve_connection_id(
+        &self,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        if let Some(id) = params.get("connection_id").and_then(|v| v.as_str()) {
+            let mut last = self.last_sql_connection_id.lock().await;
+            let id_string = id.to_string();
+            *last = Some(id_string.clone());
+            Ok(id_string)
+        } else {
+            let last = self.last_sql_connection_id.lock().await;
+            if let Some(id) = last.as_ref() {
+                Ok(id.clone())
+            } else {
+                Err(anyhow!(
+                    "Nessun connection_id fornito e nessuna connessione SQL attiva trovata. Esegui prima sql_connect."
+                ))
             }
         }
     }
+}
 
-    fn format_italian_date(date: NaiveDate) -> String {
-        let month = match date.month() {
-            1 => "gennaio",
-            2 => "febbraio",
-            3 => "marzo",
-            4 => "aprile",
-            5 => "maggio",
-            6 => "giugno",
-            7 => "luglio",
-            8 => "agosto",
-            9 => "settembre",
-            10 => "ottobre",
-            11 => "novembre",
-            12 => "dicembre",
-            _ => "",
-        };
-
-        format!("{} {} {}", date.day(), month, date.year())
-    }
+async fn connect_with_optional_trust<C, F, Fut>(
+    mut connect_fn: F,
+    requested_trust: bool,
+) -> Result<(C, bool, bool)>
+where
+    F: FnMut(bool) -> Fut,
+    Fut: Future<Output = Result<C>>,
+{
+    let mut trust = requested_trust;
+    let mut fallback_used = false;
 
-    fn is_trusted_url(url: &str) -> bool {
-        if let Ok(parsed) = Url::parse(url) {
-            if let Some(domain) = parsed.domain() {
-                return AgentSystem::is_trusted_domain(domain);
+    match connect_fn(trust).await {
+        Ok(client) => Ok((client, trust, fallback_used)),
+        Err(err) => {
+            if !trust && is_certificate_error(&err) {
+                let first_err_msg = err.to_string();
+                trust = true;
+                fallback_used = true;
+                match connect_fn(trust).await {
+                    Ok(client) => Ok((client, trust, fallback_used)),
+                    Err(second_err) => Err(anyhow!(
+                        "Connessione TLS fallita ({}). Ritentativo accettando il certificato non riuscito: {}",
+                        first_err_msg,
+                        second_err
+                    )),
+                }
+            } else {
+                Err(err)
             }
         }
+    }
+}
 
-        false
+fn is_certificate_error(error: &anyhow::Error) -> bool {
+    let message = error.to_string().to_lowercase();
+    message.contains("certificate")
+        || message.contains("unknownissuer")
+        || message.contains("unknown issuer")
+        || message.contains("tls")
+}
+
+#[derive(Debug, Clone)]
+struct TextStatistics {
+    word_count: usize,
+    sentence_count: usize,
+    avg_sentence_len: f64,
+}
+
+fn extract_text_from_path(path: &Path) -> Result<String> {
+    if !path.exists() {
+        anyhow::bail!("File non trovato: {}", path.display());
     }
 
-    fn is_trusted_domain(domain: &str) -> bool {
-        let domain_lc = domain.to_ascii_lowercase();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
 
-        for allowed in TRUSTED_DOMAINS {
-            if domain_lc == *allowed {
-                return true;
-            }
+    let text = match extension.as_str() {
+        "pdf" => extract_text_from_pdf(path)?,
+        "xlsx" | "xls" | "ods" => extract_text_from_spreadsheet(path)?,
+        "docx" => extract_text_from_docx(path)?,
+        "txt" | "md" | "csv" => fs::read_to_string(path)?,
+        other => anyhow::bail!("Formato file non supportato per riassunto: {}", other),
+    };
 
-            if let Some(prefix) = domain_lc.strip_suffix(allowed) {
-                if prefix.ends_with('.') {
-                    return true;
-                }
-            }
-        }
+    Ok(normalize_whitespace(&text))
+}
 
-        if domain_lc.ends_with(".gov")
-            || domain_lc.ends_with(".gov.it")
-            || domain_lc.ends_with(".gouv.fr")
-            || domain_lc.ends_with(".parliament.uk")
-            || domain_lc.ends_with(".int")
-            || domain_lc.ends_with(".eu")
-            || domain_lc.ends_with(".europa.eu")
-            || domain_lc.ends_with(".edu")
-        {
-            return true;
-        }
+fn extract_text_from_pdf(path: &Path) -> Result<String> {
+    let doc = Document::load(path)?;
+    Ok(pdf_document_text(&doc))
+}
 
-        false
-    }
+fn pdf_document_text(doc: &Document) -> String {
+    let mut text = String::new();
 
-    fn filter_trusted_news(
-        items: Vec<(String, Option<String>, String)>,
-        filtered_out: &mut usize,
-    ) -> Vec<(String, Option<String>, String)> {
-        items
-            .into_iter()
-            .filter(|(_, _, url)| {
-                if AgentSystem::is_trusted_url(url) {
-                    true
-                } else {
-                    *filtered_out += 1;
-                    false
-                }
-            })
-            .collect()
+    for page_num in 1..=doc.get_pages().len() {
+        if let Ok(page_text) = doc.extract_text(&[page_num as u32]) {
+            text.push_str(&page_text);
+            text.push('\n');
+        }
     }
 
-    fn normalize_google_news_link(url: &str) -> (String, Option<(String, String)>) {
-        if let Ok(parsed) = Url::parse(url) {
-            if parsed
-                .domain()
-                .map(|domain| domain == "news.google.com")
-                .unwrap_or(false)
-            {
-                let mut candidate = None;
-                for (key, value) in parsed.query_pairs() {
-                    if key == "url" || key == "u" {
-                        let decoded = value.into_owned();
-                        if !decoded.trim().is_empty() {
-                            candidate = Some(decoded);
-                            break;
-                        }
-                    }
-                }
+    text
+}
 
-                if let Some(target) = candidate {
-                    if target != url {
-                        if let Ok(parsed_target) = Url::parse(&target) {
-                            let normalized_string: String = parsed_target.into();
-                            let rewrite_pair = (url.to_string(), normalized_string.clone());
-                            return (normalized_string, Some(rewrite_pair));
-                        } else {
-                            return (target.clone(), Some((url.to_string(), target)));
-                        }
-                    }
-                }
+fn extract_text_from_spreadsheet(path: &Path) -> Result<String> {
+    let mut output = String::new();
+
+    if path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("xlsx"))
+    {
+        let mut workbook: Xlsx<_> = open_workbook(path)?;
+        let sheet_names = workbook.sheet_names();
+        for sheet_name in sheet_names {
+            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
+                append_range_text(&mut output, &sheet_name, &range);
+            }
+        }
+    } else if path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("xls"))
+    {
+        let mut workbook: Xls<_> = open_workbook(path)?;
+        let sheet_names = workbook.sheet_names();
+        for sheet_name in sheet_names {
+            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
+                append_range_text(&mut output, &sheet_name, &range);
+            }
+        }
+    } else {
+        let mut workbook: Ods<_> = open_workbook(path)?;
+        let sheet_names = workbook.sheet_names();
+        for sheet_name in sheet_names {
+            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
+                append_range_text(&mut output, &sheet_name, &range);
             }
         }
-
-        (url.to_string(), None)
     }
 
-    async fn fetch_google_news(
-        client: &Client,
-        query: &str,
-        max_results: usize,
-    ) -> Result<(
-        Vec<(String, Option<String>, String)>,
-        Vec<(String, String)>,
-    )> {
-        let encoded_query = urlencoding::encode(query);
-        let rss_url = format!(
-            "https://news.google.com/rss/search?q={}&hl=it&gl=IT&ceid=IT:it",
-            encoded_query
-        );
-
-        let response = client
-            .get(&rss_url)
-            .send()
-            .await
-            .context("Errore durante il recupero delle notizie")?
-            .error_for_status()
-            .context("Risposta non valida da Google News")?;
-
-        let body = response
-            .text()
-            .await
-            .context("Impossibile leggere il feed di notizie")?;
+    Ok(output)
+}
 
-        let mut items = Vec::new();
-        let mut rewrites = Vec::new();
-        let recency_cutoff = Utc::now() - Duration::hours(48); // enforce recent news only
+fn json_cell_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
 
-        for capture in NEWS_ITEM_RE.captures_iter(&body) {
-            let block = capture.get(1).map(|m| m.as_str()).unwrap_or("");
+/// Result of offline language detection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code when one exists (e.g. "it"), ISO 639-3 otherwise
+    pub code: String,
+    /// Native name of the language
+    pub name: String,
+    pub confidence: f64,
+    pub reliable: bool,
+}
 
-            let published = capture_rss_field(block, &NEWS_PUBDATE_RE)
-                .and_then(|raw| DateTime::parse_from_rfc2822(&raw).ok())
-                .map(|dt: DateTime<FixedOffset>| dt.with_timezone(&Utc));
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let info = whatlang::detect(text)?;
+    let iso3 = info.lang().code();
+    let code = iso_639_1(iso3).unwrap_or(iso3);
+
+    Some(DetectedLanguage {
+        code: code.to_string(),
+        name: info.lang().name().to_string(),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+    })
+}
 
-            if let Some(published) = published {
-                if published < recency_cutoff {
-                    continue;
-                }
-            } else {
-                continue;
-            }
+/// Translation services expect two-letter codes, whatlang reports ISO 639-3
+fn iso_639_1(iso3: &str) -> Option<&'static str> {
+    let code = match iso3 {
+        "ita" => "it",
+        "eng" => "en",
+        "fra" => "fr",
+        "deu" => "de",
+        "spa" => "es",
+        "por" => "pt",
+        "nld" => "nl",
+        "cat" => "ca",
+        "ron" => "ro",
+        "pol" => "pl",
+        "ces" => "cs",
+        "slk" => "sk",
+        "slv" => "sl",
+        "hrv" => "hr",
+        "srp" => "sr",
+        "bul" => "bg",
+        "rus" => "ru",
+        "ukr" => "uk",
+        "ell" => "el",
+        "hun" => "hu",
+        "swe" => "sv",
+        "dan" => "da",
+        "nob" => "nb",
+        "fin" => "fi",
+        "est" => "et",
+        "lav" => "lv",
+        "lit" => "lt",
+        "tur" => "tr",
+        "ara" => "ar",
+        "heb" => "he",
+        "pes" => "fa",
+        "hin" => "hi",
+        "ben" => "bn",
+        "cmn" => "zh",
+        "jpn" => "ja",
+        "kor" => "ko",
+        "vie" => "vi",
+        "tha" => "th",
+        "ind" => "id",
+        _ => return None,
+    };
+    Some(code)
+}
 
-            let title = capture_rss_field(block, &NEWS_TITLE_RE).unwrap_or_default();
-            let raw_link = capture_rss_field(block, &NEWS_LINK_RE).unwrap_or_default();
-            let (link, rewrite) = AgentSystem::normalize_google_news_link(&raw_link);
-            if let Some(pair) = rewrite {
-                rewrites.push(pair);
-            }
+/// Translation backends for `text_translate`, tried in order until one succeeds
+#[derive(Debug, Clone, PartialEq)]
+enum TranslationProvider {
+    DeepL {
+        api_key: String,
+    },
+    LibreTranslate {
+        endpoint: String,
+        api_key: Option<String>,
+    },
+    MyMemory,
+}
 
-            if title.is_empty() || link.is_empty() {
-                continue;
-            }
+impl TranslationProvider {
+    /// Configured providers first, MyMemory (no key required) always last
+    fn chain(settings: &TranslationSettings) -> Vec<Self> {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
 
-            let description = capture_rss_field(block, &NEWS_DESC_RE);
-            let snippet = description.and_then(|raw| {
-                let stripped = HTML_TAG_RE.replace_all(&raw, " ");
-                let normalized = normalize_whitespace(stripped.as_ref());
-                if normalized.is_empty() {
-                    None
-                } else {
-                    Some(normalized.chars().take(220).collect::<String>())
-                }
+        let mut chain = Vec::new();
+        if let Some(api_key) = non_empty(&settings.deepl_api_key) {
+            chain.push(Self::DeepL { api_key });
+        }
+        if let Some(endpoint) = non_empty(&settings.libretranslate_url) {
+            chain.push(Self::LibreTranslate {
+                endpoint: endpoint.trim_end_matches('/').to_string(),
+                api_key: non_empty(&settings.libretranslate_api_key),
             });
-
-            items.push((title, snippet, link));
-
-            if items.len() >= max_results {
-                break;
-            }
         }
-
-        Ok((items, rewrites))
+        chain.push(Self::MyMemory);
+        chain
     }
 
-    async fn execute_map_open(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let location = params
-            .get("location")
-            .and_then(|v| v.as_str())
-            .context("Parametro 'location' mancante")?;
+    fn name(&self) -> &'static str {
+        match self {
+            Self::DeepL { .. } => "DeepL",
+            Self::LibreTranslate { .. } => "LibreTranslate",
+            Self::MyMemory => "MyMemory",
+        }
+    }
 
-        let mode = params
-            .get("mode")
-            .and_then(|v| v.as_str())
-            .unwrap_or("search");
+    async fn translate(&self, text: &str, source: &str, target: &str) -> Result<String> {
+        let client = http_client::shared_client();
 
-        let encoded_location = urlencoding::encode(location);
+        let translated = match self {
+            Self::DeepL { api_key } => {
+                let endpoint = if api_key.ends_with(":fx") {
+                    "https://api-free.deepl.com/v2/translate"
+                } else {
+                    "https://api.deepl.com/v2/translate"
+                };
+                let mut body = json!({
+                    "text": [text],
+                    "target_lang": deepl_target_lang(target),
+                });
+                if source != "auto" {
+                    // Source languages never take a regional variant
+                    let source = source.split(['-', '_']).next().unwrap_or(source);
+                    body["source_lang"] = json!(source.to_uppercase());
+                }
 
-        let map_url = match mode {
-            "directions" => format!(
-                "https://www.google.com/maps/dir/?api=1&destination={}",
-                encoded_location
-            ),
-            _ => format!(
-                "https://www.google.com/maps/search/?api=1&query={}",
-                encoded_location
-            ),
+                let response = client
+                    .post(endpoint)
+                    .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
+                    .json(&body)
+                    .timeout(WEB_REQUEST_TIMEOUT)
+                    .send()
+                    .await
+                    .context("Errore richiesta")?;
+                // DeepL answers 456 when the monthly quota is used up
+                if response.status().as_u16() == 456 {
+                    anyhow::bail!("quota esaurita");
+                }
+                let payload: serde_json::Value = response
+                    .error_for_status()
+                    .context("Risposta non valida")?
+                    .json()
+                    .await
+                    .context("Errore parsing risposta")?;
+                payload["translations"][0]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+            }
+            Self::LibreTranslate { endpoint, api_key } => {
+                let mut body = json!({
+                    "q": text,
+                    "source": source,
+                    "target": target,
+                    "format": "text",
+                });
+                if let Some(key) = api_key {
+                    body["api_key"] = json!(key);
+                }
+
+                let payload: serde_json::Value = client
+                    .post(format!("{}/translate", endpoint))
+                    .json(&body)
+                    .timeout(WEB_REQUEST_TIMEOUT)
+                    .send()
+                    .await
+                    .context("Errore richiesta")?
+                    .error_for_status()
+                    .context("Risposta non valida")?
+                    .json()
+                    .await
+                    .context("Errore parsing risposta")?;
+                payload["translatedText"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+            }
+            Self::MyMemory => {
+                let url = format!(
+                    "https://api.mymemory.translated.net/get?q={}&langpair={}|{}",
+                    urlencoding::encode(text),
+                    source,
+                    target
+                );
+
+                let payload: serde_json::Value = client
+                    .get(&url)
+                    .timeout(WEB_REQUEST_TIMEOUT)
+                    .send()
+                    .await
+                    .context("Errore richiesta")?
+                    .error_for_status()
+                    .context("Risposta non valida")?
+                    .json()
+                    .await
+                    .context("Errore parsing risposta")?;
+
+                // Quota errors come back as HTTP 200 with the status in the body
+                let status = payload["responseStatus"].as_u64().unwrap_or(200);
+                if status != 200 {
+                    anyhow::bail!(
+                        "{}",
+                        payload["responseDetails"]
+                            .as_str()
+                            .unwrap_or("errore sconosciuto")
+                    );
+                }
+                payload["responseData"]["translatedText"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+            }
         };
 
-        Ok(format!("URL: {}", map_url))
+        let translated = translated.trim();
+        if translated.is_empty() || translated.starts_with("MYMEMORY WARNING") {
+            anyhow::bail!("nessun risultato");
+        }
+        Ok(translated.to_string())
     }
+}
 
-    async fn execute_youtube_search(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let query = params
-            .get("query")
-            .and_then(|v| v.as_str())
-            .context("Parametro 'query' mancante")?;
+/// DeepL target code: English and Portuguese need a regional variant, the bare
+/// "EN" and "PT" are deprecated. "en-gb" and "pt_pt" keep the variant asked for
+fn deepl_target_lang(target: &str) -> String {
+    let target = target.trim().replace('_', "-").to_uppercase();
+    match target.as_str() {
+        "EN" => "EN-US".to_string(),
+        "PT" => "PT-BR".to_string(),
+        _ => target,
+    }
+}
 
-        let encoded_query = urlencoding::encode(query);
-        let youtube_url = format!(
-            "https://www.youtube.com/results?search_query={}",
-            encoded_query
-        );
-        Ok(format!("URL: {}", youtube_url))
+/// Cell range such as "A1:D100", optionally qualified with a sheet ("Vendite!A1:D100").
+/// Coordinates are zero-based (row, column), as used by calamine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellRangeSpec {
+    pub sheet: Option<String>,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl CellRangeSpec {
+    fn label(&self) -> String {
+        format!(
+            "{}{}:{}{}",
+            column_name_from_index(self.start.1 as usize),
+            self.start.0 + 1,
+            column_name_from_index(self.end.1 as usize),
+            self.end.0 + 1
+        )
     }
 
-    async fn execute_text_translate(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let text = params
-            .get("text")
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .ok_or_else(|| anyhow!("Parametro 'text' mancante o vuoto"))?;
+    /// Cells of the sheet that fall inside the spec. The spec is intersected with the
+    /// used area first, so "A1:XFD1048576" does not allocate the whole grid
+    fn select(&self, sheet: &Range<Data>) -> Range<Data> {
+        let (Some(first), Some(last)) = (sheet.start(), sheet.end()) else {
+            return Range::empty();
+        };
+        let start = (self.start.0.max(first.0), self.start.1.max(first.1));
+        let end = (self.end.0.min(last.0), self.end.1.min(last.1));
+        if start.0 > end.0 || start.1 > end.1 {
+            return Range::empty();
+        }
+        sheet.range(start, end)
+    }
+}
 
-        if text.chars().count() > 1_500 {
-            anyhow::bail!("Testo troppo lungo: massimo 1500 caratteri");
+pub fn parse_cell_range(spec: &str) -> Result<CellRangeSpec> {
+    let spec = spec.trim();
+    let (sheet, cells) = match spec.rsplit_once('!') {
+        Some((sheet, cells)) => {
+            let sheet = sheet.trim().trim_matches('\'').to_string();
+            (Some(sheet).filter(|s| !s.is_empty()), cells)
         }
+        None => (None, spec),
+    };
 
-        let target_language = params
-            .get("target_language")
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .ok_or_else(|| anyhow!("Parametro 'target_language' mancante"))?
-            .to_lowercase();
+    let (first, last) = cells.split_once(':').unwrap_or((cells, cells));
+    let first = parse_cell_ref(first).ok_or_else(|| anyhow!("Cella non valida: {}", first))?;
+    let last = parse_cell_ref(last).ok_or_else(|| anyhow!("Cella non valida: {}", last))?;
 
-        let source_language = params
-            .get("source_language")
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .unwrap_or("auto");
+    Ok(CellRangeSpec {
+        sheet,
+        start: (first.0.min(last.0), first.1.min(last.1)),
+        end: (first.0.max(last.0), first.1.max(last.1)),
+    })
+}
 
-        let encoded_text = urlencoding::encode(text);
-        let langpair = format!("{}|{}", source_language, target_language);
+/// "B12" -> (11, 1)
+fn parse_cell_ref(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.trim().replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
 
-        let url = format!(
-            "https://api.mymemory.translated.net/get?q={}&langpair={}",
-            encoded_text, langpair
-        );
+    let mut column: u32 = 0;
+    for c in letters.chars() {
+        column = column
+            .checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, column - 1))
+}
 
-        let client = Client::new();
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .context("Errore richiesta traduzione")?
-            .error_for_status()
-            .context("Risposta traduzione non valida")?;
+pub fn list_spreadsheet_sheets(path: &Path) -> Result<Vec<String>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
 
-        let payload: serde_json::Value = response
-            .json()
-            .await
-            .context("Errore parsing risposta traduzione")?;
+    let sheets = match extension.as_str() {
+        "xlsx" => open_workbook::<Xlsx<_>, _>(path)?.sheet_names(),
+        "xls" => open_workbook::<Xls<_>, _>(path)?.sheet_names(),
+        "ods" => open_workbook::<Ods<_>, _>(path)?.sheet_names(),
+        other => anyhow::bail!("Formato non supportato per Excel: {}", other),
+    };
+    Ok(sheets)
+}
 
-        let translated = payload["responseData"]["translatedText"]
-            .as_str()
-            .unwrap_or_default()
-            .trim();
+/// Extracts only the requested sheets and/or cell range. With no selection every sheet is read.
+pub fn extract_spreadsheet_selection(
+    path: &Path,
+    sheets: &[String],
+    range: Option<&str>,
+) -> Result<String> {
+    let range = range.map(parse_cell_range).transpose()?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
 
-        if translated.is_empty() {
-            anyhow::bail!("Traduzione non disponibile");
+    match extension.as_str() {
+        "xlsx" => {
+            let workbook: Xlsx<_> = open_workbook(path)?;
+            read_workbook_selection(workbook, sheets, range.as_ref())
+        }
+        "xls" => {
+            let workbook: Xls<_> = open_workbook(path)?;
+            read_workbook_selection(workbook, sheets, range.as_ref())
+        }
+        "ods" => {
+            let workbook: Ods<_> = open_workbook(path)?;
+            read_workbook_selection(workbook, sheets, range.as_ref())
         }
+        other => anyhow::bail!("Formato non supportato per Excel: {}", other),
+    }
+}
 
-        let mut output = String::new();
-        output.push_str("🌐 Traduzione completata\n");
-        output.push_str(&format!("- Sorgente: {}\n", source_language));
-        output.push_str(&format!("- Destinazione: {}\n\n", target_language));
-        output.push_str("**Risultato**\n");
-        output.push_str(translated);
+fn read_workbook_selection<W>(
+    mut workbook: W,
+    sheets: &[String],
+    range: Option<&CellRangeSpec>,
+) -> Result<String>
+where
+    W: Reader<BufReader<fs::File>>,
+{
+    let available = workbook.sheet_names();
+    let requested: Vec<String> = match range.and_then(|r| r.sheet.clone()) {
+        Some(sheet) => vec![sheet],
+        None if !sheets.is_empty() => sheets.to_vec(),
+        None => available.clone(),
+    };
 
-        Ok(output)
+    let mut output = String::new();
+    for name in requested {
+        let sheet_name = available
+            .iter()
+            .find(|candidate| candidate.eq_ignore_ascii_case(&name))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Foglio non trovato: {} (disponibili: {})",
+                    name,
+                    available.join(", ")
+                )
+            })?;
+
+        let sheet_range = workbook
+            .worksheet_range(&sheet_name)
+            .map_err(|err| anyhow!("Foglio non leggibile {}: {:?}", sheet_name, err))?;
+
+        match range {
+            Some(spec) => {
+                let selected = spec.select(&sheet_range);
+                let label = format!("{} ({})", sheet_name, spec.label());
+                append_range_text(&mut output, &label, &selected);
+            }
+            None => append_range_text(&mut output, &sheet_name, &sheet_range),
+        }
     }
 
-    async fn execute_document_summarize(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let path = params
-            .get("path")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
+    if output.trim().is_empty() {
+        anyhow::bail!("La selezione non contiene dati");
+    }
 
-        let max_sentences = params
-            .get("max_sentences")
-            .and_then(|v| v.as_i64())
-            .map(|n| n.max(1).min(10) as usize)
-            .unwrap_or(5);
+    Ok(output)
+}
 
-        let text = extract_text_from_path(Path::new(path))
-            .with_context(|| format!("Impossibile leggere il documento: {}", path))?;
+fn append_range_text(output: &mut String, sheet_name: &str, range: &Range<Data>) {
+    output.push_str(&format!("=== Foglio: {} ===\n", sheet_name));
+    for row in range.rows() {
+        let values: Vec<String> = row
+            .iter()
+            .map(|cell| match cell {
+                Data::Empty => String::new(),
+                Data::String(s) => s.to_string(),
+                Data::Float(f) => format!("{:.4}", f),
+                Data::Int(i) => i.to_string(),
+                Data::Bool(b) => b.to_string(),
+                Data::DateTime(dt) => dt.to_string(),
+                Data::DateTimeIso(dt) => dt.to_string(),
+                _ => cell.to_string(),
+            })
+            .collect();
+        output.push_str(&values.join("\t"));
+        output.push('\n');
+    }
+    output.push('\n');
+}
 
-        if text.trim().is_empty() {
-            anyhow::bail!("Il documento non contiene testo analizzabile");
+fn capture_rss_field(block: &str, regex: &Regex) -> Option<String> {
+    let captures = regex.captures(block)?;
+    for idx in 1..captures.len() {
+        if let Some(m) = captures.get(idx) {
+            let value = m.as_str().trim();
+            if !value.is_empty() {
+                return Some(decode_html_entities(value).into_owned());
+            }
         }
+    }
+    None
+}
 
-        let summary = summarize_text(&text, max_sentences);
-        let stats = compute_text_statistics(&text);
+fn extract_text_from_docx(path: &Path) -> Result<String> {
+    extract_docx_text(fs::File::open(path)?)
+}
 
-        let mut output = String::new();
-        output.push_str("📝 Riassunto documento\n");
-        output.push_str(&format!(
-            "- parole totali: {}\n- frasi stimate: {}\n- lunghezza media frase: {:.1} parole\n\n",
-            stats.word_count, stats.sentence_count, stats.avg_sentence_len
-        ));
-        output.push_str("**Riassunto**\n");
-        output.push_str(&summary);
+fn extract_docx_text<R: Read + Seek>(reader: R) -> Result<String> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut document = archive.by_name("word/document.xml")?;
+    let mut xml_content = String::new();
+    document.read_to_string(&mut xml_content)?;
 
-        Ok(output)
+    lazy_static! {
+        static ref DOCX_TAG_REGEX: Regex = Regex::new(r"<[^>]+>").unwrap();
     }
 
-    async fn execute_excel_improve(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let path = params
-            .get("path")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
+    let text = DOCX_TAG_REGEX.replace_all(&xml_content, " ");
+    Ok(normalize_whitespace(&text))
+}
 
-        let improvement = analyze_excel(Path::new(path))
-            .with_context(|| format!("Impossibile analizzare il file Excel: {}", path))?;
+/// Paragraph by paragraph text of a .docx, for conversions that should keep the layout
+fn extract_docx_paragraphs(path: &Path) -> Result<String> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    let mut xml_content = String::new();
+    archive
+        .by_name("word/document.xml")?
+        .read_to_string(&mut xml_content)?;
 
-        Ok(improvement)
+    lazy_static! {
+        static ref DOCX_RUN_TAG_REGEX: Regex = Regex::new(r"<[^>]+>").unwrap();
     }
 
-    async fn execute_word_improve(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let path = params
-            .get("path")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'path' mancante"))?;
+    let paragraphs: Vec<String> = xml_content
+        .split("</w:p>")
+        .map(|paragraph| {
+            let text = DOCX_RUN_TAG_REGEX.replace_all(paragraph, "");
+            decode_html_entities(text.trim()).to_string()
+        })
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect();
 
-        let improvement = analyze_word_document(Path::new(path))
-            .with_context(|| format!("Impossibile analizzare il file Word: {}", path))?;
+    Ok(paragraphs.join("\n\n"))
+}
 
-        Ok(improvement)
+fn file_extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Canonical name of a conversion target
+fn document_format(name: &str) -> Result<&'static str> {
+    match name.trim().trim_start_matches('.').to_lowercase().as_str() {
+        "md" | "markdown" => Ok("md"),
+        "html" | "htm" => Ok("html"),
+        "txt" | "text" | "plain" => Ok("txt"),
+        "docx" | "word" => Ok("docx"),
+        "pdf" => Ok("pdf"),
+        other => anyhow::bail!(
+            "Formato di destinazione non supportato: {} (usa md, html, txt, docx o pdf)",
+            other
+        ),
     }
+}
 
-    async fn execute_sql_connect(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let server = params
-            .get("server")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'server' mancante"))?;
+/// Runs pandoc; `None` when it is not installed
+fn convert_with_pandoc(source: &Path, dest: &Path, target: &str) -> Option<Result<()>> {
+    let mut command = Command::new("pandoc");
+    command.arg(source).arg("-o").arg(dest).arg("--standalone");
+    // For PDF pandoc picks the writer and the engine from the output extension
+    let writer = match target {
+        "md" => Some("gfm"),
+        "txt" => Some("plain"),
+        "html" => Some("html"),
+        "docx" => Some("docx"),
+        _ => None,
+    };
+    if let Some(writer) = writer {
+        command.arg("-t").arg(writer);
+    }
 
-        let database = params
-            .get("database")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'database' mancante"))?;
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => return Some(Err(anyhow!("Impossibile avviare pandoc: {}", e))),
+    };
 
-        let auth_method = params
-            .get("auth_method")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'auth_method' mancante (usa 'windows' o 'sql')"))?;
+    if output.status.success() {
+        Some(Ok(()))
+    } else {
+        Some(Err(anyhow!(
+            "Conversione con pandoc non riuscita: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
 
-        let requested_trust = params
-            .get("trust_server_certificate")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+/// Basic conversions available without pandoc: anything readable to txt/md, md and text to html
+fn convert_document_internally(source: &Path, dest: &Path, target: &str) -> Result<()> {
+    let extension = file_extension(source);
+    let title = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Documento");
+
+    let output = match (extension.as_str(), target) {
+        ("md" | "markdown", "html") => {
+            markdown_to_html_document(&fs::read_to_string(source)?, title)
+        }
+        ("md" | "markdown", "txt") => markdown_to_text(&fs::read_to_string(source)?),
+        (_, "html") => text_to_html_document(&document_plain_text(source, &extension)?, title),
+        (_, "txt" | "md") => document_plain_text(source, &extension)?,
+        (_, other) => anyhow::bail!(
+            "La conversione in {} richiede pandoc: installalo da https://pandoc.org",
+            other
+        ),
+    };
 
-        let connection_id = format!("sql_{}", Uuid::new_v4());
+    fs::write(dest, output)
+        .with_context(|| format!("Impossibile scrivere il file: {}", dest.display()))
+}
+
+fn document_plain_text(path: &Path, extension: &str) -> Result<String> {
+    match extension {
+        "pdf" => extract_text_from_pdf(path),
+        "xlsx" | "xls" | "ods" => extract_text_from_spreadsheet(path),
+        "docx" => extract_docx_paragraphs(path),
+        "html" | "htm" => Ok(extract_readable_text(&fs::read_to_string(path)?).text),
+        "txt" | "md" | "markdown" | "csv" => Ok(fs::read_to_string(path)?),
+        other => anyhow::bail!("Formato sorgente non supportato: {}", other),
+    }
+}
+
+fn markdown_to_html_document(markdown: &str, title: &str) -> String {
+    let options = MarkdownOptions::ENABLE_TABLES
+        | MarkdownOptions::ENABLE_STRIKETHROUGH
+        | MarkdownOptions::ENABLE_TASKLISTS
+        | MarkdownOptions::ENABLE_FOOTNOTES;
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, MarkdownParser::new_ext(markdown, options));
+    html_document(title, &body)
+}
 
-        let mut stored_username = None;
-        let mut stored_password = None;
+fn text_to_html_document(text: &str, title: &str) -> String {
+    let body: String = text
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            let escaped = html_escape::encode_text(paragraph).replace('\n', "<br>\n");
+            format!("<p>{}</p>\n", escaped)
+        })
+        .collect();
+    html_document(title, &body)
+}
 
-        let (client, effective_trust, auto_trust_applied) =
-            if auth_method.eq_ignore_ascii_case("windows") {
-                connect_with_optional_trust(
-                    |trust| mcp_sql::connect_windows_auth(server, database, trust),
-                    requested_trust,
-                )
-                .await?
-            } else if auth_method.eq_ignore_ascii_case("sql") {
-                let username = params
-                    .get("username")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow!("Parametro 'username' richiesto per SQL auth"))?;
-
-                let password = params
-                    .get("password")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow!("Parametro 'password' richiesto per SQL auth"))?;
-
-                stored_username = Some(username.to_string());
-                stored_password = Some(password.to_string());
-
-                connect_with_optional_trust(
-                    |trust| mcp_sql::connect_sql_auth(server, database, username, password, trust),
-                    requested_trust,
-                )
-                .await?
-            } else {
-                return Err(anyhow!("auth_method non valido: usa 'windows' o 'sql'"));
-            };
+fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        html_escape::encode_text(title),
+        body
+    )
+}
 
-        drop(client);
+/// Text of a Markdown document without the markup, keeping paragraphs and list items
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in MarkdownParser::new_ext(markdown, MarkdownOptions::ENABLE_TABLES) {
+        match event {
+            MarkdownEvent::Text(content) | MarkdownEvent::Code(content) => text.push_str(&content),
+            MarkdownEvent::SoftBreak | MarkdownEvent::HardBreak => text.push('\n'),
+            MarkdownEvent::Start(MarkdownTag::Item) => text.push_str("- "),
+            MarkdownEvent::End(TagEnd::Item | TagEnd::TableRow | TagEnd::TableHead) => {
+                text.push('\n')
+            }
+            MarkdownEvent::End(TagEnd::TableCell) => text.push('\t'),
+            MarkdownEvent::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::List(_),
+            ) => text.push_str("\n\n"),
+            _ => {}
+        }
+    }
 
-        let conn_info = mcp_sql::SqlConnection {
-            connection_id: connection_id.clone(),
-            server: server.to_string(),
-            database: database.to_string(),
-            auth_type: auth_method.to_string(),
-            username: stored_username,
-            password: stored_password,
-            trust_server_certificate: effective_trust,
-        };
+    lazy_static! {
+        static ref BLANK_LINES_REGEX: Regex = Regex::new(r"\n{3,}").unwrap();
+    }
+    BLANK_LINES_REGEX
+        .replace_all(text.trim(), "\n\n")
+        .to_string()
+}
 
-        self.sql_manager.add_connection(conn_info);
+/// A file found by `file_search`, with the first matching line when searching by content
+struct FileSearchMatch {
+    path: PathBuf,
+    size: u64,
+    line: Option<(usize, String)>,
+}
 
-        {
-            let mut last_conn = self.last_sql_connection_id.lock().await;
-            *last_conn = Some(connection_id.clone());
+/// Case-insensitive regex for a glob on the file name: `*` any text, `?` one character
+fn glob_to_regex(pattern: &str) -> Result<Regex> {
+    let mut regex = String::from("(?i)^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
         }
+    }
+    regex.push('$');
+    Regex::new(&regex).with_context(|| format!("Pattern non valido: {}", pattern))
+}
 
-        let mut response = format!(
-            "✅ Connessione riuscita\nConnection ID: {}\nServer: {}\nDatabase: {}\nAutenticazione: {}\nTrust certificato TLS: {}",
-            connection_id,
-            server,
-            database,
-            auth_method,
-            if effective_trust { "disabilitata" } else { "attiva" }
-        );
+/// Content patterns that are not valid regular expressions are searched as plain text
+fn content_search_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(&format!("(?i){}", pattern))
+        .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(pattern))))
+        .with_context(|| format!("Pattern non valido: {}", pattern))
+}
 
-        if auto_trust_applied && !requested_trust {
-            response.push_str(
-                "\n⚠️ Il certificato TLS presentato dal server non è attendibile. La connessione è stata completata accettando il certificato per proseguire.",
-            );
+/// Walks `root` up to `FILE_SEARCH_MAX_DEPTH`, skipping hidden entries. Returns the
+/// matches and whether the search stopped at `max_results`.
+fn search_files(
+    root: &Path,
+    name_regex: &Regex,
+    content_regex: Option<&Regex>,
+    max_results: usize,
+) -> (Vec<FileSearchMatch>, bool) {
+    let entries = WalkDir::new(root)
+        .max_depth(FILE_SEARCH_MAX_DEPTH)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file());
+
+    let mut matches = Vec::new();
+    for entry in entries {
+        if !name_regex.is_match(&entry.file_name().to_string_lossy()) {
+            continue;
         }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let line = match content_regex {
+            Some(regex) => match first_matching_line(entry.path(), size, regex) {
+                Some(line) => Some(line),
+                None => continue,
+            },
+            None => None,
+        };
 
-        Ok(response)
+        if matches.len() == max_results {
+            return (matches, true);
+        }
+        matches.push(FileSearchMatch {
+            path: entry.into_path(),
+            size,
+            line,
+        });
     }
+    (matches, false)
+}
 
-    async fn execute_sql_query(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let connection_id = self.resolve_connection_id(params).await?;
+/// First line matching the regex, for text files small enough to be read
+fn first_matching_line(path: &Path, size: u64, regex: &Regex) -> Option<(usize, String)> {
+    if size > FILE_SEARCH_MAX_FILE_BYTES {
+        return None;
+    }
+    // Binary files are not valid UTF-8 and are skipped
+    let content = fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .enumerate()
+        .find(|(_, line)| regex.is_match(line))
+        .map(|(index, line)| {
+            let line = line.trim();
+            let mut text: String = line.chars().take(FILE_SEARCH_LINE_CHARS).collect();
+            if text.len() < line.len() {
+                text.push('…');
+            }
+            (index + 1, text)
+        })
+}
 
-        let query = params
-            .get("query")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'query' mancante"))?;
+/// Sizes collected by `disk_usage`
+#[derive(Debug, Default)]
+struct DiskUsage {
+    total: u64,
+    files: usize,
+    directories: usize,
+    /// Entries that could not be read, e.g. for missing permissions
+    unreadable: usize,
+    /// Direct children of the directory: name, whether it is a folder, size
+    children: Vec<(String, bool, u64)>,
+    /// The largest files below the directory with their size, biggest first
+    largest_files: Vec<(PathBuf, u64)>,
+}
 
-        let conn_info = self
-            .sql_manager
-            .get_connection(&connection_id)
-            .ok_or_else(|| {
-                anyhow!(
-                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
-                    connection_id
-                )
-            })?;
+/// Walks the whole tree below `root` (symlinks are not followed) and sums the
+/// file sizes, both overall and for each direct child. Only the `top` largest
+/// files are kept, so huge trees don't fill the memory with paths
+fn measure_disk_usage(root: &Path, top: usize) -> DiskUsage {
+    let mut usage = DiskUsage::default();
+    let mut children: HashMap<String, (bool, u64)> = HashMap::new();
+    // Min-heap: the smallest of the kept files is the first to go
+    let mut largest: BinaryHeap<Reverse<(u64, PathBuf)>> = BinaryHeap::with_capacity(top + 1);
+
+    for entry in WalkDir::new(root).min_depth(1) {
+        let Ok(entry) = entry else {
+            usage.unreadable += 1;
+            continue;
+        };
+        let Some(child) = entry
+            .path()
+            .strip_prefix(root)
+            .ok()
+            .and_then(|relative| relative.components().next())
+        else {
+            continue;
+        };
+        let child = child.as_os_str().to_string_lossy().to_string();
 
-        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
+        if entry.file_type().is_dir() {
+            usage.directories += 1;
+            children.entry(child).or_insert((true, 0));
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
 
-        let result = mcp_sql::run_query(&mut client, query).await?;
-        let summary = summarize_query_result(&result);
-        let table_preview = render_result_table(&result, 20);
-        let payload = json!({
-            "columns": result.columns,
-            "rows": result.rows,
-        });
-        let json_pretty = serde_json::to_string_pretty(&payload)?;
+        let Ok(metadata) = entry.metadata() else {
+            usage.unreadable += 1;
+            continue;
+        };
+        let size = metadata.len();
+        usage.total += size;
+        usage.files += 1;
+        children.entry(child).or_insert((entry.depth() > 1, 0)).1 += size;
+        largest.push(Reverse((size, entry.into_path())));
+        if largest.len() > top {
+            largest.pop();
+        }
+    }
 
-        let mut response = String::new();
-        response.push_str("📊 Risultato query\n");
-        response.push_str(&summary);
+    usage.children = children
+        .into_iter()
+        .map(|(name, (is_dir, size))| (name, is_dir, size))
+        .collect();
+    usage
+        .children
+        .sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    usage.largest_files = largest
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((size, path))| (path, size))
+        .collect();
+    usage
+}
 
-        if let Some(table) = table_preview {
-            response.push_str("\n\n**Anteprima dati**\n");
-            response.push_str(&table);
+/// Markdown report with the `top` largest children and files
+fn format_disk_usage(root: &Path, usage: &DiskUsage, top: usize) -> String {
+    let share = |size: u64| {
+        if usage.total == 0 {
+            0.0
+        } else {
+            size as f64 / usage.total as f64 * 100.0
         }
+    };
 
-        response.push_str("\n\n**JSON completo**\n```json\n");
-        response.push_str(&json_pretty);
-        response.push_str("\n```\n");
+    let mut output = format!(
+        "## Spazio occupato da {}\n\n**Totale:** {} in {} file e {} cartelle\n",
+        root.display(),
+        format_file_size(usage.total),
+        usage.files,
+        usage.directories
+    );
+    if usage.unreadable > 0 {
+        output.push_str(&format!(
+            "\n⚠️ {} elementi non leggibili (permessi insufficienti?) non sono conteggiati\n",
+            usage.unreadable
+        ));
+    }
+    if usage.children.is_empty() {
+        output.push_str("\nLa directory è vuota");
+        return output;
+    }
 
-        Ok(response)
+    output.push_str("\n### Elementi più grandi\n\n");
+    output.push_str("| Nome | Tipo | Dimensione | % |\n|---|---|---|---|\n");
+    for (name, is_dir, size) in usage.children.iter().take(top) {
+        let kind = if *is_dir {
+            "📁 cartella"
+        } else {
+            "📄 file"
+        };
+        output.push_str(&format!(
+            "| {} | {} | {} | {:.1}% |\n",
+            name,
+            kind,
+            format_file_size(*size),
+            share(*size)
+        ));
     }
 
-    async fn execute_sql_list_tables(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let connection_id = self.resolve_connection_id(params).await?;
+    output.push_str("\n### File più grandi\n\n");
+    output.push_str("| File | Dimensione | % |\n|---|---|---|\n");
+    for (path, size) in usage.largest_files.iter().take(top) {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        output.push_str(&format!(
+            "| {} | {} | {:.1}% |\n",
+            relative.display(),
+            format_file_size(*size),
+            share(*size)
+        ));
+    }
+    output.trim_end().to_string()
+}
 
-        let conn_info = self
-            .sql_manager
-            .get_connection(&connection_id)
-            .ok_or_else(|| {
-                anyhow!(
-                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
-                    connection_id
-                )
-            })?;
+/// PNG image of the QR code for `content`, with a quiet zone around it
+fn qr_code_png(content: &str) -> Result<Vec<u8>> {
+    let code = QrCode::new(content.as_bytes()).map_err(|e| match e {
+        QrError::DataTooLong => anyhow!("Testo troppo lungo per un QR code"),
+        e => anyhow!("Impossibile generare il QR code: {}", e),
+    })?;
+    let image = code
+        .render::<Luma<u8>>()
+        .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+        .build();
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .context("Impossibile codificare il QR code in PNG")?;
+    Ok(png)
+}
 
-        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
-        let result = mcp_sql::list_tables(&mut client).await?;
+fn open_zip_archive(path: &str) -> Result<ZipArchive<fs::File>> {
+    let file =
+        fs::File::open(path).with_context(|| format!("Impossibile aprire l'archivio: {}", path))?;
+    ZipArchive::new(file).map_err(zip_error)
+}
 
-        let total_items = result.rows.len();
-        let base_tables = result
-            .rows
-            .iter()
-            .filter(|row| matches_ignore_case(row.get("Type"), "BASE TABLE"))
-            .count();
-        let views = result
-            .rows
-            .iter()
-            .filter(|row| matches_ignore_case(row.get("Type"), "VIEW"))
-            .count();
-        let table_preview = render_result_table(&result, 40);
-        let payload = json!({
-            "columns": result.columns,
-            "rows": result.rows,
-        });
-        let json_pretty = serde_json::to_string_pretty(&payload)?;
+/// Error messages for damaged or password protected archives
+fn zip_error(error: ZipError) -> anyhow::Error {
+    match error {
+        ZipError::UnsupportedArchive(message) if message == ZipError::PASSWORD_REQUIRED => {
+            anyhow::anyhow!("Il file è protetto da password: archivi cifrati non supportati")
+        }
+        ZipError::UnsupportedArchive(message) => {
+            anyhow::anyhow!("Formato ZIP non supportato: {}", message)
+        }
+        ZipError::InvalidArchive(message) => {
+            anyhow::anyhow!("Archivio ZIP non valido o corrotto: {}", message)
+        }
+        ZipError::FileNotFound => anyhow::anyhow!("File non trovato nell'archivio"),
+        ZipError::Io(e) => anyhow::anyhow!("Errore di lettura dell'archivio: {}", e),
+    }
+}
 
-        let mut response = String::new();
-        response.push_str("📋 Tabelle disponibili\n");
-        response.push_str(&format!(
-            "- elementi totali: {}\n- tabelle: {}\n- viste: {}\n",
-            total_items, base_tables, views
-        ));
+/// Units of `datetime_calculate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeUnit {
+    Minutes,
+    Hours,
+    Days,
+    BusinessDays,
+    Weeks,
+    Months,
+    Years,
+}
 
-        if let Some(table) = table_preview {
-            response.push_str("\n**Dettaglio**\n");
-            response.push_str(&table);
+impl TimeUnit {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+            "minute" | "minutes" | "minuto" | "minuti" => Ok(Self::Minutes),
+            "hour" | "hours" | "ora" | "ore" => Ok(Self::Hours),
+            "day" | "days" | "giorno" | "giorni" => Ok(Self::Days),
+            "business_day" | "business_days" | "workday" | "workdays" | "giorno_lavorativo"
+            | "giorni_lavorativi" => Ok(Self::BusinessDays),
+            "week" | "weeks" | "settimana" | "settimane" => Ok(Self::Weeks),
+            "month" | "months" | "mese" | "mesi" => Ok(Self::Months),
+            "year" | "years" | "anno" | "anni" => Ok(Self::Years),
+            other => anyhow::bail!(
+                "Unità non supportata: {} (usa minutes, hours, days, business_days, weeks, months o years)",
+                other
+            ),
         }
+    }
 
-        response.push_str("\n\n**JSON completo**\n```json\n");
-        response.push_str(&json_pretty);
-        response.push_str("\n```\n");
-
-        Ok(response)
+    fn label(self) -> &'static str {
+        match self {
+            Self::Minutes => "minuti",
+            Self::Hours => "ore",
+            Self::Days => "giorni",
+            Self::BusinessDays => "giorni lavorativi",
+            Self::Weeks => "settimane",
+            Self::Months => "mesi",
+            Self::Years => "anni",
+        }
     }
 
-    async fn execute_sql_describe_table(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let connection_id = self.resolve_connection_id(params).await?;
+    fn has_time(self) -> bool {
+        matches!(self, Self::Minutes | Self::Hours)
+    }
+}
 
-        let schema = params
-            .get("schema")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'schema' mancante"))?;
+/// Parses a date for `datetime_calculate`; the flag tells whether it carried a time
+fn parse_local_datetime(value: &str) -> Result<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    match value.to_lowercase().as_str() {
+        "" | "now" | "adesso" => return Ok((Local::now().naive_local(), true)),
+        "today" | "oggi" => return Ok((Local::now().date_naive().and_time(NaiveTime::MIN), false)),
+        _ => {}
+    }
 
-        let table = params
-            .get("table")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Parametro 'table' mancante"))?;
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok((datetime.with_timezone(&Local).naive_local(), true));
+    }
+    const DATETIME_FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%d/%m/%Y %H:%M",
+    ];
+    for format in DATETIME_FORMATS {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok((datetime, true));
+        }
+    }
+    for format in ["%Y-%m-%d", "%d/%m/%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok((date.and_time(NaiveTime::MIN), false));
+        }
+    }
 
-        let conn_info = self
-            .sql_manager
-            .get_connection(&connection_id)
-            .ok_or_else(|| {
-                anyhow!(
-                    "Connessione '{}' non trovata. Esegui prima sql_connect.",
-                    connection_id
-                )
-            })?;
+    anyhow::bail!(
+        "Data non riconosciuta: '{}' (usa AAAA-MM-GG, AAAA-MM-GG HH:MM o GG/MM/AAAA)",
+        value
+    )
+}
 
-        let mut client = mcp_sql::connect_with_info(&conn_info).await?;
-        let result = mcp_sql::describe_table(&mut client, schema, table).await?;
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
 
-        let total_columns = result.rows.len();
-        let highlights: Vec<String> = result
-            .rows
-            .iter()
-            .take(5)
-            .map(|row| describe_column_row(row))
-            .collect();
-        let highlight_text = if highlights.is_empty() {
-            "nessuna colonna trovata".to_string()
+fn shift_datetime(datetime: NaiveDateTime, amount: i64, unit: TimeUnit) -> Result<NaiveDateTime> {
+    let fixed = |delta: Option<Duration>| delta.and_then(|d| datetime.checked_add_signed(d));
+    let months = |months: i64| {
+        let delta = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        if months < 0 {
+            datetime.checked_sub_months(delta)
         } else {
-            highlights.join("; ")
-        };
-        let table_preview = render_result_table(&result, 50);
-        let payload = json!({
-            "columns": result.columns,
-            "rows": result.rows,
-        });
-        let json_pretty = serde_json::to_string_pretty(&payload)?;
-
-        let mut response = String::new();
-        response.push_str(&format!("🔍 Struttura tabella {}.{}\n", schema, table));
-        response.push_str(&format!(
-            "- colonne totali: {}\n- anteprima: {}\n",
-            total_columns, highlight_text
-        ));
+            datetime.checked_add_months(delta)
+        }
+    };
 
-        if let Some(table_markdown) = table_preview {
-            response.push_str("\n**Dettaglio**\n");
-            response.push_str(&table_markdown);
+    let shifted = match unit {
+        TimeUnit::Minutes => fixed(Duration::try_minutes(amount)),
+        TimeUnit::Hours => fixed(Duration::try_hours(amount)),
+        TimeUnit::Days => fixed(Duration::try_days(amount)),
+        TimeUnit::Weeks => fixed(Duration::try_weeks(amount)),
+        TimeUnit::Months => months(amount),
+        TimeUnit::Years => amount.checked_mul(12).and_then(months),
+        TimeUnit::BusinessDays => {
+            let step = if amount < 0 { -1 } else { 1 };
+            let mut current = datetime;
+            let mut remaining = amount.unsigned_abs();
+            while remaining > 0 {
+                current = current
+                    .checked_add_signed(Duration::days(step))
+                    .context("Data fuori dall'intervallo supportato")?;
+                if !is_weekend(current.date()) {
+                    remaining -= 1;
+                }
+            }
+            Some(current)
         }
+    };
 
-        response.push_str("\n\n**JSON completo**\n```json\n");
-        response.push_str(&json_pretty);
-        response.push_str("\n```\n");
+    shifted.context("Data fuori dall'intervallo supportato")
+}
+
+/// Working days (Monday to Friday) after `from` up to and including `to`; negative when
+/// `to` comes first
+fn business_days_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    let (start, end, sign) = if from <= to {
+        (from, to, 1)
+    } else {
+        (to, from, -1)
+    };
+    let count = start
+        .iter_days()
+        .skip(1)
+        .take_while(|date| *date <= end)
+        .filter(|date| !is_weekend(*date))
+        .count() as i64;
+    count * sign
+}
+
+fn italian_weekday(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "lunedì",
+        Weekday::Tue => "martedì",
+        Weekday::Wed => "mercoledì",
+        Weekday::Thu => "giovedì",
+        Weekday::Fri => "venerdì",
+        Weekday::Sat => "sabato",
+        Weekday::Sun => "domenica",
+    }
+}
 
-        Ok(response)
+fn format_italian_datetime(datetime: NaiveDateTime, with_time: bool) -> String {
+    let date = format!(
+        "{} {}",
+        italian_weekday(datetime.weekday()),
+        AgentSystem::format_italian_date(datetime.date())
+    );
+    if with_time {
+        format!("{} alle {}", date, datetime.format("%H:%M"))
+    } else {
+        date
     }
+}
 
-    async fn execute_sql_disconnect(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        let connection_id = if let Some(id) = params.get("connection_id").and_then(|v| v.as_str()) {
-            id.to_string()
-        } else {
-            let last = self.last_sql_connection_id.lock().await;
-            last.clone()
-                .ok_or_else(|| anyhow!("Nessuna connessione SQL attiva da chiudere"))?
-        };
+/// Event recognized in the `text` parameter, described for the model and
+/// returned to the UI to be confirmed
+fn execute_parse_calendar_event(
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<(String, CalendarEventInput)> {
+    let text = params
+        .get("text")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'text' mancante")?;
+    let event = event_parser::parse_event(text, Local::now().naive_local())
+        .context("Nessuna data o ora riconosciuta nel testo")?;
+
+    let output = format!(
+        "📅 Evento proposto: {}\n- Inizio: {}\n- Fine: {}\nL'evento non è ancora nel calendario: l'utente può aggiungerlo con il pulsante mostrato nella chat.",
+        event.title,
+        format_italian_datetime(event.start, true),
+        format_italian_datetime(event.end, true)
+    );
+    Ok((output, event.to_input(text)))
+}
 
-        let removed = self.sql_manager.remove_connection(&connection_id);
-        if removed.is_none() {
-            return Err(anyhow!("Connessione '{}' non trovata", connection_id));
-        }
+/// Range of the `start_date`/`end_date` parameters: the start defaults to now
+/// and an end without time includes the whole day
+fn calendar_range(
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+    let to_utc = |datetime: NaiveDateTime| {
+        Local
+            .from_local_datetime(&datetime)
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+            .with_context(|| format!("Ora locale inesistente: {}", datetime))
+    };
 
-        {
-            let mut last = self.last_sql_connection_id.lock().await;
-            if last.as_ref() == Some(&connection_id) {
-                *last = None;
-            }
+    let start = match params.get("start_date").and_then(|v| v.as_str()) {
+        Some(value) => to_utc(parse_local_datetime(value)?.0)?,
+        None => Utc::now(),
+    };
+    let end = match params.get("end_date").and_then(|v| v.as_str()) {
+        Some(value) => {
+            let (end, with_time) = parse_local_datetime(value)?;
+            let end = if with_time {
+                end
+            } else {
+                end + Duration::days(1)
+            };
+            Some(to_utc(end)?)
         }
+        None => None,
+    };
+    if end.is_some_and(|end| end <= start) {
+        anyhow::bail!(
+            "La data di fine deve seguire l'inizio dell'intervallo ({})",
+            format_italian_datetime(start.with_timezone(&Local).naive_local(), true)
+        );
+    }
+    Ok((start, end))
+}
 
-        Ok(format!(
-            "✅ Connessione '{}' chiusa correttamente.",
-            connection_id
-        ))
+/// Start of a remote event in local time: Google sends RFC 3339 or a date for
+/// all-day events, Graph a UTC time without offset
+fn format_remote_event_start(value: &str) -> String {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return format_italian_datetime(datetime.with_timezone(&Local).naive_local(), true);
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        let local = Utc.from_utc_datetime(&datetime).with_timezone(&Local);
+        return format_italian_datetime(local.naive_local(), true);
     }
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => format_italian_datetime(date.and_time(NaiveTime::MIN), false),
+        Err(_) => value.to_string(),
+    }
+}
 
-    async fn resolve_connection_id(
-        &self,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> Result<String> {
-        if let Some(id) = params.get("connection_id").and_then(|v| v.as_str()) {
-            let mut last = self.last_sql_connection_id.lock().await;
-            let id_string = id.to_string();
-            *last = Some(id_string.clone());
-            Ok(id_string)
-        } else {
-            let last = self.last_sql_connection_id.lock().await;
-            if let Some(id) = last.as_ref() {
-                Ok(id.clone())
-            } else {
-                Err(anyhow!(
-                    "Nessun connection_id fornito e nessuna connessione SQL attiva trovata. Esegui prima sql_connect."
-                ))
+/// Events of the connected Outlook/Google calendars in the requested range
+async fn execute_calendar_list_events(
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<String> {
+    let (start, end) = calendar_range(params)?;
+    let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+
+    let providers: Vec<&str> = match params.get("provider").and_then(|v| v.as_str()) {
+        Some(provider) => vec![provider],
+        None => {
+            let mut connected = Vec::new();
+            if calendar_integration::is_outlook_connected().await? {
+                connected.push("outlook");
+            }
+            if calendar_integration::is_google_connected().await? {
+                connected.push("google");
             }
+            connected
         }
+    };
+    if providers.is_empty() {
+        anyhow::bail!("Nessun calendario Outlook o Google collegato");
     }
-}
 
-async fn connect_with_optional_trust<F, Fut>(
-    mut connect_fn: F,
-    requested_trust: bool,
-) -> Result<(mcp_sql::SqlClient, bool, bool)>
-where
-    F: FnMut(bool) -> Fut,
-    Fut: Future<Output = Result<mcp_sql::SqlClient>>,
-{
-    let mut trust = requested_trust;
-    let mut fallback_used = false;
+    // A provider that fails (expired token, network) must not hide the others
+    let mut output = String::new();
+    let mut failures = 0;
+    for provider in &providers {
+        let (label, listed) = match *provider {
+            "outlook" => (
+                "Outlook",
+                calendar_integration::list_outlook_events(limit, Some(start), end).await,
+            ),
+            "google" => (
+                "Google Calendar",
+                calendar_integration::list_google_events(limit, Some(start), end).await,
+            ),
+            other => anyhow::bail!("Provider sconosciuto: {} (usa outlook o google)", other),
+        };
+        let events: Vec<RemoteCalendarEvent> = match listed {
+            Ok(events) => events,
+            Err(e) => {
+                failures += 1;
+                output.push_str(&format!("📅 {}: errore: {}\n\n", label, e));
+                continue;
+            }
+        };
 
-    match connect_fn(trust).await {
-        Ok(client) => Ok((client, trust, fallback_used)),
-        Err(err) => {
-            if !trust && is_certificate_error(&err) {
-                let first_err_msg = err.to_string();
-                trust = true;
-                fallback_used = true;
-                match connect_fn(trust).await {
-                    Ok(client) => Ok((client, trust, fallback_used)),
-                    Err(second_err) => Err(anyhow!(
-                        "Connessione TLS fallita ({}). Ritentativo accettando il certificato non riuscito: {}",
-                        first_err_msg,
-                        second_err
-                    )),
-                }
-            } else {
-                Err(err)
+        output.push_str(&format!("📅 {} ({} eventi)\n", label, events.len()));
+        for event in &events {
+            output.push_str(&format!(
+                "- {}: {}",
+                format_remote_event_start(&event.start),
+                event.subject
+            ));
+            if let Some(location) = &event.location {
+                output.push_str(&format!(" · 📍 {}", location));
             }
+            output.push('\n');
         }
+        output.push('\n');
     }
-}
 
-fn is_certificate_error(error: &anyhow::Error) -> bool {
-    let message = error.to_string().to_lowercase();
-    message.contains("certificate")
-        || message.contains("unknownissuer")
-        || message.contains("unknown issuer")
-        || message.contains("tls")
+    if failures == providers.len() {
+        anyhow::bail!("{}", output.trim_end());
+    }
+    Ok(output.trim_end().to_string())
 }
 
-#[derive(Debug, Clone)]
-struct TextStatistics {
-    word_count: usize,
-    sentence_count: usize,
-    avg_sentence_len: f64,
-}
+/// Event of a `calendar_sync` call: the stored one for `event_id`, otherwise a
+/// new one built from the other parameters. The flag tells if it is new
+fn calendar_sync_event(
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<(CalendarEvent, bool)> {
+    let text = |name: &str| {
+        params
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let to_utc = |value: &str| -> Result<DateTime<Utc>> {
+        let datetime = parse_local_datetime(value)?.0;
+        Local
+            .from_local_datetime(&datetime)
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+            .with_context(|| format!("Ora locale inesistente: {}", datetime))
+    };
 
-fn extract_text_from_path(path: &Path) -> Result<String> {
-    if !path.exists() {
-        anyhow::bail!("File non trovato: {}", path.display());
+    if let Some(id) = text("event_id") {
+        let event = local_storage::load_calendar_events()?
+            .into_iter()
+            .find(|event| event.id == id)
+            .with_context(|| format!("Evento {} non trovato nel calendario", id))?;
+        return Ok((event, false));
     }
 
-    let extension = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    let title = text("title").context("Indica 'event_id' oppure 'title' e 'start'")?;
+    let start = to_utc(&text("start").context("Parametro 'start' mancante")?)?;
+    let end = text("end").map(|end| to_utc(&end)).transpose()?;
+    if end.is_some_and(|end| end <= start) {
+        anyhow::bail!("La fine dell'evento deve seguire l'inizio");
+    }
 
-    let text = match extension.as_str() {
-        "pdf" => extract_text_from_pdf(path)?,
-        "xlsx" | "xls" | "ods" => extract_text_from_spreadsheet(path)?,
-        "docx" => extract_text_from_docx(path)?,
-        "txt" | "md" | "csv" => fs::read_to_string(path)?,
-        other => anyhow::bail!("Formato file non supportato per riassunto: {}", other),
+    let now = Utc::now();
+    let event = CalendarEvent {
+        id: Uuid::new_v4().to_string(),
+        title,
+        description: text("description"),
+        start,
+        end,
+        source_text: None,
+        location: text("location"),
+        reminder_minutes: None,
+        recurrence: None,
+        created_at: now,
+        updated_at: now,
     };
-
-    Ok(normalize_whitespace(&text))
+    Ok((event, true))
 }
 
-fn extract_text_from_pdf(path: &Path) -> Result<String> {
-    let doc = Document::load(path)?;
-    let mut text = String::new();
+/// Providers of the `targets` parameter, as an array or a comma separated
+/// list; empty when missing
+fn calendar_sync_targets(params: &HashMap<String, serde_json::Value>) -> Result<Vec<&'static str>> {
+    let names: Vec<String> = match params.get("targets") {
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_string)
+            .collect(),
+        Some(serde_json::Value::String(list)) => list.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
 
-    for page_num in 1..=doc.get_pages().len() {
-        if let Ok(page_text) = doc.extract_text(&[page_num as u32]) {
-            text.push_str(&page_text);
-            text.push('\n');
+    let mut targets = Vec::new();
+    for name in names {
+        let target = match name.trim().to_lowercase().as_str() {
+            "outlook" => "outlook",
+            "google" | "google calendar" | "gcal" => "google",
+            "" => continue,
+            other => anyhow::bail!("Calendario sconosciuto: {} (usa outlook o google)", other),
+        };
+        if !targets.contains(&target) {
+            targets.push(target);
         }
     }
+    Ok(targets)
+}
 
-    Ok(text)
+fn calendar_provider_label(provider: &str) -> &'static str {
+    if provider == "outlook" {
+        "Outlook"
+    } else {
+        "Google Calendar"
+    }
 }
 
-fn extract_text_from_spreadsheet(path: &Path) -> Result<String> {
-    let mut output = String::new();
+fn dry_run_calendar_sync(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let (event, is_new) = calendar_sync_event(params)?;
+    let targets = calendar_sync_targets(params)?;
+    let destination = if targets.is_empty() {
+        "tutti i calendari collegati".to_string()
+    } else {
+        targets
+            .iter()
+            .map(|target| calendar_provider_label(target))
+            .collect::<Vec<_>>()
+            .join(" e ")
+    };
 
-    if path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map_or(false, |ext| ext.eq_ignore_ascii_case("xlsx"))
-    {
-        let mut workbook: Xlsx<_> = open_workbook(path)?;
-        let sheet_names = workbook.sheet_names();
-        for sheet_name in sheet_names {
-            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-                append_range_text(&mut output, &sheet_name, &range);
-            }
+    Ok(format!(
+        "Creerebbe l'evento '{}' ({}) su {}{}",
+        event.title,
+        format_italian_datetime(event.start.with_timezone(&Local).naive_local(), true),
+        destination,
+        if is_new {
+            ", aggiungendolo anche al calendario di MatePro se almeno un invio riesce"
+        } else {
+            ""
         }
-    } else if path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map_or(false, |ext| ext.eq_ignore_ascii_case("xls"))
-    {
-        let mut workbook: Xls<_> = open_workbook(path)?;
-        let sheet_names = workbook.sheet_names();
-        for sheet_name in sheet_names {
-            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-                append_range_text(&mut output, &sheet_name, &range);
+    ))
+}
+
+/// Sends a local event (existing or created now) to the remote calendars,
+/// returning the links of the created events. A new event is saved locally
+/// only when at least one calendar accepted it
+async fn execute_calendar_sync(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let (event, is_new) = calendar_sync_event(params)?;
+    let mut targets = calendar_sync_targets(params)?;
+    if targets.is_empty() {
+        if calendar_integration::is_outlook_connected().await? {
+            targets.push("outlook");
+        }
+        if calendar_integration::is_google_connected().await? {
+            targets.push("google");
+        }
+    }
+    if targets.is_empty() {
+        anyhow::bail!("Nessun calendario Outlook o Google collegato");
+    }
+
+    let mut lines = vec![format!(
+        "📅 {} ({})",
+        event.title,
+        format_italian_datetime(event.start.with_timezone(&Local).naive_local(), true)
+    )];
+    let mut failures = 0;
+    for target in &targets {
+        let pushed = if *target == "outlook" {
+            calendar_integration::push_local_event_to_outlook(&event).await
+        } else {
+            calendar_integration::push_local_event_to_google(&event).await
+        };
+        let label = calendar_provider_label(target);
+        match pushed {
+            Ok(remote) => match remote.web_link {
+                Some(link) => lines.push(format!("- {}: creato → {}", label, link)),
+                None => lines.push(format!("- {}: creato", label)),
+            },
+            Err(e) => {
+                failures += 1;
+                lines.push(format!("- {}: errore: {}", label, e));
             }
         }
+    }
+
+    if failures == targets.len() {
+        anyhow::bail!("{}", lines.join("\n"));
+    }
+    if is_new {
+        local_storage::add_calendar_event(event)?;
+    }
+    Ok(lines.join("\n"))
+}
+
+fn is_network_tool(name: &str) -> bool {
+    NETWORK_TOOLS.contains(&name)
+}
+
+/// Lines of a file's new content shown by the dry run
+const DRY_RUN_PREVIEW_LINES: usize = 20;
+/// Files of a `zip_create` dry run listed by name
+const DRY_RUN_LISTED_FILES: usize = 20;
+
+fn dry_run_shell(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let command = params
+        .get("command")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'command' mancante")?;
+    let shell = if cfg!(target_os = "windows") {
+        "PowerShell"
     } else {
-        let mut workbook: Ods<_> = open_workbook(path)?;
-        let sheet_names = workbook.sheet_names();
-        for sheet_name in sheet_names {
-            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-                append_range_text(&mut output, &sheet_name, &range);
+        "bash"
+    };
+    Ok(format!(
+        "Verrebbe eseguito con {} il comando:\n{}",
+        shell, command
+    ))
+}
+
+fn dry_run_file_write(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let path = params
+        .get("path")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'path' mancante")?;
+    let content = params
+        .get("content")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'content' mancante")?;
+
+    let mut preview = format!(
+        "Scriverebbe {} in {}\n{}\n\nContenuto:\n",
+        format_file_size(content.len() as u64),
+        path,
+        destination_note(Path::new(path))
+    );
+    let lines: Vec<&str> = content.lines().collect();
+    preview.push_str(&lines[..lines.len().min(DRY_RUN_PREVIEW_LINES)].join("\n"));
+    if lines.len() > DRY_RUN_PREVIEW_LINES {
+        preview.push_str(&format!(
+            "\n… (altre {} righe)",
+            lines.len() - DRY_RUN_PREVIEW_LINES
+        ));
+    }
+    Ok(preview)
+}
+
+fn dry_run_zip_create(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let sources: Vec<&str> = match params.get("sources") {
+        Some(serde_json::Value::Array(values)) => {
+            values.iter().filter_map(|v| v.as_str()).collect()
+        }
+        Some(serde_json::Value::String(single)) => vec![single.as_str()],
+        _ => anyhow::bail!("Parametro 'sources' mancante o non è una lista di percorsi"),
+    };
+    let dest_path = params
+        .get("dest_path")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'dest_path' mancante")?;
+
+    let mut files = Vec::new();
+    let mut total_size = 0u64;
+    let mut missing = Vec::new();
+    for source in sources {
+        if !Path::new(source).exists() {
+            missing.push(source);
+            continue;
+        }
+        let entries = WalkDir::new(source).sort_by_file_name().into_iter();
+        for entry in entries.flatten() {
+            if entry.file_type().is_file() {
+                total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                files.push(entry.path().display().to_string());
             }
         }
     }
 
-    Ok(output)
+    let mut preview = format!(
+        "Creerebbe l'archivio {} con {} file ({})\n{}",
+        dest_path,
+        files.len(),
+        format_file_size(total_size),
+        destination_note(Path::new(dest_path))
+    );
+    for file in files.iter().take(DRY_RUN_LISTED_FILES) {
+        preview.push_str(&format!("\n- {}", file));
+    }
+    if files.len() > DRY_RUN_LISTED_FILES {
+        preview.push_str(&format!(
+            "\n… e altri {} file",
+            files.len() - DRY_RUN_LISTED_FILES
+        ));
+    }
+    if !missing.is_empty() {
+        preview.push_str(&format!(
+            "\n\n⚠️ Percorsi non trovati, l'operazione fallirebbe: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(preview)
 }
 
-fn append_range_text(output: &mut String, sheet_name: &str, range: &Range<Data>) {
-    output.push_str(&format!("=== Foglio: {} ===\n", sheet_name));
-    for row in range.rows() {
-        let values: Vec<String> = row
-            .iter()
-            .map(|cell| match cell {
-                Data::Empty => String::new(),
-                Data::String(s) => s.to_string(),
-                Data::Float(f) => format!("{:.4}", f),
-                Data::Int(i) => i.to_string(),
-                Data::Bool(b) => b.to_string(),
-                Data::DateTime(dt) => dt.to_string(),
-                Data::DateTimeIso(dt) => dt.to_string(),
-                _ => cell.to_string(),
-            })
-            .collect();
-        output.push_str(&values.join("\t"));
-        output.push('\n');
+fn dry_run_excel_write(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let path = params
+        .get("path")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'path' mancante")?;
+    let rows = params
+        .get("rows")
+        .and_then(|v| v.as_array())
+        .context("Parametro 'rows' mancante o non è un array")?;
+    let columns = rows
+        .iter()
+        .filter_map(|row| row.as_array())
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+    let sheet_name = params
+        .get("sheet_name")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("Foglio1");
+    let headers = params
+        .get("headers")
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .map(json_cell_to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_else(|| "nessuna".to_string());
+
+    Ok(format!(
+        "Creerebbe il file Excel {} con il foglio '{}': {} righe × {} colonne\nIntestazioni: {}\n{}",
+        path,
+        sheet_name,
+        rows.len(),
+        columns,
+        headers,
+        destination_note(Path::new(path))
+    ))
+}
+
+fn dry_run_convert_document(params: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let source_path = params
+        .get("source_path")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'source_path' mancante")?;
+    let dest_path = params
+        .get("dest_path")
+        .and_then(|v| v.as_str())
+        .context("Parametro 'dest_path' mancante")?;
+    let dest = Path::new(dest_path);
+    let target = params
+        .get("target_format")
+        .and_then(|v| v.as_str())
+        .or_else(|| dest.extension().and_then(|e| e.to_str()))
+        .context("Indica 'target_format' oppure un'estensione in 'dest_path'")?;
+    let target = document_format(target)?;
+
+    let source = if Path::new(source_path).is_file() {
+        source_path.to_string()
+    } else {
+        format!("{} (⚠️ file non trovato)", source_path)
+    };
+    Ok(format!(
+        "Convertirebbe {} in formato {}\nDestinazione: {}\n{}",
+        source,
+        target,
+        dest_path,
+        destination_note(dest)
+    ))
+}
+
+/// What writing `path` would do to what is there now
+fn destination_note(path: &Path) -> String {
+    if path.is_dir() {
+        return "⚠️ Il percorso è una directory: l'operazione fallirebbe".to_string();
     }
-    output.push('\n');
+    if let Ok(metadata) = fs::metadata(path) {
+        return format!(
+            "⚠️ Il file esistente ({}) verrebbe sovrascritto; una copia di backup permetterà di annullare l'operazione",
+            format_file_size(metadata.len())
+        );
+    }
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => format!(
+            "⚠️ La cartella {} non esiste: l'operazione fallirebbe",
+            parent.display()
+        ),
+        _ => "Verrebbe creato un nuovo file".to_string(),
+    }
+}
+
+/// Path of the file a tool call is about to write, if any, as the tool will resolve it
+fn written_file(call: &ToolCall) -> Option<PathBuf> {
+    if call.tool_name == "generate_qr" {
+        return qr_dest_path(&call.parameters);
+    }
+    let (_, param) = FILE_WRITING_TOOLS
+        .iter()
+        .find(|(tool, _)| *tool == call.tool_name)?;
+    call.parameters
+        .get(*param)
+        .and_then(|v| v.as_str())
+        .map(PathBuf::from)
+}
+
+/// Destination chosen for `generate_qr`, with `.png` added when it has no
+/// extension. `None` when the image goes to the temp folder
+fn qr_dest_path(params: &HashMap<String, serde_json::Value>) -> Option<PathBuf> {
+    let path = params
+        .get("dest_path")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|path| !path.is_empty())?;
+    let mut dest = PathBuf::from(path);
+    if dest.extension().is_none() {
+        dest.set_extension("png");
+    }
+    Some(dest)
+}
+
+/// Page summary from the Wikipedia REST API (`/page/summary/{title}`)
+#[derive(Debug, Deserialize)]
+struct WikipediaSummary {
+    /// "standard", "disambiguation", ...
+    #[serde(rename = "type", default)]
+    kind: String,
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    extract: String,
+    #[serde(default)]
+    content_urls: serde_json::Value,
 }
 
-fn capture_rss_field(block: &str, regex: &Regex) -> Option<String> {
-    let captures = regex.captures(block)?;
-    for idx in 1..captures.len() {
-        if let Some(m) = captures.get(idx) {
-            let value = m.as_str().trim();
-            if !value.is_empty() {
-                return Some(decode_html_entities(value).into_owned());
-            }
-        }
+impl WikipediaSummary {
+    fn page_url(&self) -> Option<&str> {
+        self.content_urls["desktop"]["page"].as_str()
     }
-    None
 }
 
-fn extract_text_from_docx(path: &Path) -> Result<String> {
-    let file = fs::File::open(path)?;
-    let mut archive = ZipArchive::new(file)?;
-    let mut document = archive.by_name("word/document.xml")?;
-    let mut xml_content = String::new();
-    document.read_to_string(&mut xml_content)?;
+/// Wikipedia edition matching the system locale, Italian otherwise
+fn default_wikipedia_language() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .filter_map(|value| value.split(['_', '.', '-']).next().map(str::to_lowercase))
+        .find(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase()))
+        .unwrap_or_else(|| "it".to_string())
+}
 
-    lazy_static! {
-        static ref DOCX_TAG_REGEX: Regex = Regex::new(r"<[^>]+>").unwrap();
+/// Title as used in Wikipedia URLs: spaces become underscores
+fn wikipedia_title_path(title: &str) -> String {
+    urlencoding::encode(&title.trim().replace(' ', "_")).into_owned()
+}
+
+/// `None` when the page does not exist
+async fn fetch_wikipedia_summary(lang: &str, title: &str) -> Result<Option<WikipediaSummary>> {
+    let url = format!(
+        "https://{}.wikipedia.org/api/rest_v1/page/summary/{}",
+        lang,
+        wikipedia_title_path(title)
+    );
+    let response = http_client::shared_client()
+        .get(url)
+        .timeout(WEB_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Wikipedia non raggiungibile")?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
     }
 
-    let text = DOCX_TAG_REGEX.replace_all(&xml_content, " ");
-    Ok(normalize_whitespace(&text))
+    let summary = response
+        .error_for_status()
+        .context("Risposta non valida da Wikipedia")?
+        .json()
+        .await
+        .context("Errore parsing risposta di Wikipedia")?;
+    Ok(Some(summary))
+}
+
+async fn search_wikipedia_titles(lang: &str, query: &str) -> Result<Vec<String>> {
+    let url = format!("https://{}.wikipedia.org/w/api.php", lang);
+    let limit = WIKIPEDIA_SUGGESTIONS.to_string();
+    let payload: serde_json::Value = http_client::shared_client()
+        .get(url)
+        .query(&[
+            ("action", "opensearch"),
+            ("search", query),
+            ("limit", limit.as_str()),
+            ("namespace", "0"),
+            ("format", "json"),
+        ])
+        .timeout(WEB_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Wikipedia non raggiungibile")?
+        .error_for_status()
+        .context("Risposta non valida da Wikipedia")?
+        .json()
+        .await
+        .context("Errore parsing risposta di Wikipedia")?;
+    Ok(opensearch_titles(&payload))
+}
+
+/// OpenSearch answers `[query, [titles], [descriptions], [urls]]`
+fn opensearch_titles(payload: &serde_json::Value) -> Vec<String> {
+    payload[1]
+        .as_array()
+        .map(|titles| {
+            titles
+                .iter()
+                .filter_map(|title| title.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Path inside a ZIP archive: always '/' separated, whatever the platform
+fn zip_entry_name(relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn format_file_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+
+    let bytes_f = bytes as f64;
+    if bytes_f >= GB {
+        format!("{:.1} GB", bytes_f / GB)
+    } else if bytes_f >= MB {
+        format!("{:.1} MB", bytes_f / MB)
+    } else if bytes_f >= KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{} B", bytes)
+    }
 }
 
 fn summarize_text(text: &str, max_sentences: usize) -> String {
@@ -2776,6 +6749,124 @@ fn matches_ignore_case(value: Option<&serde_json::Value>, expected: &str) -> boo
         .unwrap_or(false)
 }
 
+/// Compact overview of the schema: one line per column, with keys and references
+fn render_database_schema(result: &mcp_sql::DatabaseSchema, schema: Option<&str>) -> String {
+    let mut response = String::new();
+    match schema {
+        Some(schema) => response.push_str(&format!("🗂️ Schema del database ({})\n", schema)),
+        None => response.push_str("🗂️ Schema del database\n"),
+    }
+
+    if result.tables.is_empty() {
+        response.push_str("\nNessuna tabella trovata.\n");
+        return response;
+    }
+
+    let views = result.tables.iter().filter(|table| table.is_view).count();
+    response.push_str(&format!(
+        "- tabelle: {}\n- viste: {}\n",
+        result.tables.len() - views,
+        views
+    ));
+    if result.total_tables > result.tables.len() {
+        response.push_str(&format!(
+            "- ⚠️ mostrate {} di {} tabelle: usa il parametro schema o max_tables per vedere le altre\n",
+            result.tables.len(),
+            result.total_tables
+        ));
+    }
+
+    for table in &result.tables {
+        let kind = if table.is_view { " (vista)" } else { "" };
+        response.push_str(&format!("\n**{}.{}**{}\n", table.schema, table.name, kind));
+
+        for column in &table.columns {
+            let mut line = format!("- {} {}", column.name, column.data_type);
+            if column.primary_key {
+                line.push_str(" PK");
+            }
+            if column.nullable {
+                line.push_str(" NULL");
+            }
+            for reference in &column.references {
+                line.push_str(&format!(" → {}", reference));
+            }
+            response.push_str(&line);
+            response.push('\n');
+        }
+    }
+
+    response
+}
+
+/// Operators that usually point to a missing index or an expensive step
+const PLAN_WARNINGS: &[(&str, &str)] = &[
+    ("Table Scan", "lettura completa di una tabella heap"),
+    ("Clustered Index Scan", "lettura completa della tabella"),
+    ("Index Scan", "lettura completa di un indice"),
+    ("Key Lookup", "colonne lette fuori dall'indice"),
+    ("RID Lookup", "colonne lette dalla heap, fuori dall'indice"),
+    ("Sort", "ordinamento in memoria"),
+    ("Hash Match", "join o aggregazione tramite tabella hash"),
+];
+
+/// Estimated plans as an indented tree with rows and cost of each operator
+fn render_query_plans(plans: &[mcp_sql::QueryPlan]) -> String {
+    let mut response =
+        String::from("🧭 Piano di esecuzione stimato (la query non è stata eseguita)\n");
+
+    if plans.is_empty() {
+        response.push_str("\nNessun piano restituito dal server.\n");
+        return response;
+    }
+
+    for (index, plan) in plans.iter().enumerate() {
+        response.push_str(&format!(
+            "\n**Istruzione {}**: `{}`\n",
+            index + 1,
+            truncate_string(&plan.statement, 200)
+        ));
+        if let Some(cost) = plan.total_cost {
+            response.push_str(&format!("- costo stimato totale: {:.4}\n", cost));
+        }
+
+        response.push_str("```\n");
+        for step in &plan.steps {
+            // Keep the leading spaces: they draw the operator tree
+            let mut line: String = step.text.chars().take(300).collect();
+            match (step.estimate_rows, step.subtree_cost) {
+                (Some(rows), Some(cost)) => {
+                    line.push_str(&format!("  [righe: {:.0}, costo: {:.4}]", rows, cost))
+                }
+                (Some(rows), None) => line.push_str(&format!("  [righe: {:.0}]", rows)),
+                _ => {}
+            }
+            response.push_str(&line);
+            response.push('\n');
+        }
+        response.push_str("```\n");
+
+        let warnings: Vec<String> = PLAN_WARNINGS
+            .iter()
+            .filter_map(|(op, description)| {
+                let count = plan
+                    .steps
+                    .iter()
+                    .filter(|step| step.physical_op.as_deref() == Some(*op))
+                    .count();
+                (count > 0).then(|| format!("- {} ×{}: {}", op, count, description))
+            })
+            .collect();
+        if !warnings.is_empty() {
+            response.push_str("Operazioni da valutare:\n");
+            response.push_str(&warnings.join("\n"));
+            response.push('\n');
+        }
+    }
+
+    response
+}
+
 fn describe_column_row(row: &HashMap<String, serde_json::Value>) -> String {
     let column = row
         .get("Column")
@@ -2875,6 +6966,227 @@ impl Default for AgentSystem {
     }
 }
 
+/// Resolves the URL host, rejects private/local addresses and pins the connection
+/// to the checked IPs so a second DNS answer cannot point elsewhere
+async fn web_fetch_client(url: &Url) -> Result<Client> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("Sono supportati solo URL http/https"));
+    }
+    let host = url.host_str().context("URL senza host")?;
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(url::Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        _ => tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            tokio::net::lookup_host((host, port)),
+        )
+        .await
+        .map_err(|_| anyhow!("Timeout nella risoluzione DNS di {}", host))?
+        .with_context(|| format!("Impossibile risolvere {}", host))?
+        .collect(),
+    };
+
+    if addrs.is_empty() {
+        return Err(anyhow!("Nessun indirizzo trovato per {}", host));
+    }
+    if let Some(blocked) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(anyhow!(
+            "Accesso negato: {} punta a un indirizzo privato o locale ({})",
+            host,
+            blocked.ip()
+        ));
+    }
+
+    http_client::pinned_client(host, &addrs)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || a >= 240
+                // Carrier-grade NAT 100.64.0.0/10 and benchmarking 198.18.0.0/15
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Main content of a web page as returned by `web_fetch`
+#[derive(Debug, Clone)]
+pub struct ReadablePage {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside",
+    "form", "button",
+];
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary"];
+const TEXT_BLOCK_TAGS: &[&str] = &[
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "p",
+    "li",
+    "pre",
+    "blockquote",
+    "td",
+];
+
+/// Readability-style extraction: the element whose paragraphs hold the most text
+/// is taken as the article body, then its text blocks are collected in order
+pub fn extract_readable_text(html: &str) -> ReadablePage {
+    let document = Html::parse_document(html);
+
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(element_text)
+        .filter(|title| !title.is_empty());
+
+    let paragraphs = Selector::parse("p").expect("selettore valido");
+    let mut scores: HashMap<_, usize> = HashMap::new();
+    for paragraph in document.select(&paragraphs) {
+        if is_boilerplate(&paragraph) {
+            continue;
+        }
+        let length = element_text(paragraph).len();
+        // Short paragraphs are usually captions, bylines or link lists
+        if length < 25 {
+            continue;
+        }
+        if let Some(parent) = paragraph.parent() {
+            *scores.entry(parent.id()).or_default() += length;
+            if let Some(grandparent) = parent.parent() {
+                *scores.entry(grandparent.id()).or_default() += length / 2;
+            }
+        }
+    }
+
+    let root = scores
+        .into_iter()
+        .max_by_key(|(_, score)| *score)
+        .and_then(|(id, _)| document.tree.get(id))
+        .and_then(ElementRef::wrap)
+        .unwrap_or_else(|| document.root_element());
+
+    let blocks = Selector::parse(&TEXT_BLOCK_TAGS.join(", ")).expect("selettore valido");
+    let mut lines: Vec<String> = Vec::new();
+    for block in root.select(&blocks) {
+        if is_boilerplate(&block) || is_nested_block(&block, &root) {
+            continue;
+        }
+        let text = element_text(block);
+        if text.is_empty() {
+            continue;
+        }
+        let line = match block.value().name() {
+            "h1" | "h2" | "h3" => format!("## {}", text),
+            "h4" | "h5" | "h6" => format!("### {}", text),
+            "li" => format!("- {}", text),
+            _ => text,
+        };
+        if lines.last() != Some(&line) {
+            lines.push(line);
+        }
+    }
+
+    // Pages without any text blocks: fall back to every visible text node
+    if lines.is_empty() {
+        for node in root.descendants() {
+            let Some(text) = node.value().as_text() else {
+                continue;
+            };
+            let visible = node
+                .parent()
+                .and_then(ElementRef::wrap)
+                .map(|parent| !is_boilerplate(&parent))
+                .unwrap_or(true);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if visible && !text.is_empty() {
+                lines.push(text);
+            }
+        }
+    }
+
+    ReadablePage {
+        title,
+        text: lines.join("\n\n"),
+    }
+}
+
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(|chunk| chunk.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_boilerplate(element: &ElementRef) -> bool {
+    std::iter::once(*element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .any(|el| {
+            BOILERPLATE_TAGS.contains(&el.value().name())
+                || el
+                    .value()
+                    .attr("role")
+                    .map(|role| BOILERPLATE_ROLES.contains(&role))
+                    .unwrap_or(false)
+        })
+}
+
+/// A block inside another block (e.g. a `p` inside a `li`) is already part of its parent's text
+fn is_nested_block(element: &ElementRef, root: &ElementRef) -> bool {
+    element
+        .ancestors()
+        .take_while(|ancestor| ancestor.id() != root.id())
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| TEXT_BLOCK_TAGS.contains(&ancestor.value().name()))
+}
+
+/// Rejects targets that could be interpreted as command-line options or contain spaces
+fn validate_network_target(target: &str) -> Result<&str> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Err(anyhow!("Host di destinazione vuoto"));
+    }
+    if target.starts_with('-')
+        || !target
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '_' | '%'))
+    {
+        return Err(anyhow!("Host non valido: {}", target));
+    }
+    Ok(target)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2901,4 +7213,561 @@ Questo comando lista tutti i file.
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].tool_name, "shell_execute");
     }
+
+    #[test]
+    fn test_parse_cell_range() {
+        let spec = parse_cell_range("Vendite!A1:D100").unwrap();
+        assert_eq!(spec.sheet.as_deref(), Some("Vendite"));
+        assert_eq!(spec.start, (0, 0));
+        assert_eq!(spec.end, (99, 3));
+        assert_eq!(spec.label(), "A1:D100");
+
+        let spec = parse_cell_range("'Q1 2024'!$AA10:B2").unwrap();
+        assert_eq!(spec.sheet.as_deref(), Some("Q1 2024"));
+        assert_eq!(spec.start, (1, 1));
+        assert_eq!(spec.end, (9, 26));
+
+        assert!(parse_cell_range("A0:B2").is_err());
+        assert!(parse_cell_range("12:B2").is_err());
+    }
+
+    #[test]
+    fn test_cell_range_select_clamps_to_sheet() {
+        let mut sheet: Range<Data> = Range::new((2, 1), (4, 3));
+        sheet.set_value((3, 2), Data::Int(42));
+
+        let selected = parse_cell_range("A1:XFD1048576").unwrap().select(&sheet);
+        assert_eq!(selected.start(), Some((2, 1)));
+        assert_eq!(selected.end(), Some((4, 3)));
+        assert_eq!(selected.get_value((3, 2)), Some(&Data::Int(42)));
+
+        let outside = parse_cell_range("Z100:Z200").unwrap().select(&sheet);
+        assert!(outside.is_empty());
+        assert!(parse_cell_range("A1:B2")
+            .unwrap()
+            .select(&Range::empty())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_deepl_target_lang() {
+        assert_eq!(deepl_target_lang("en"), "EN-US");
+        assert_eq!(deepl_target_lang("en-gb"), "EN-GB");
+        assert_eq!(deepl_target_lang("pt"), "PT-BR");
+        assert_eq!(deepl_target_lang("pt_pt"), "PT-PT");
+        assert_eq!(deepl_target_lang("it"), "IT");
+    }
+
+    #[test]
+    fn test_translation_provider_chain() {
+        let chain = TranslationProvider::chain(&TranslationSettings::default());
+        assert_eq!(chain, vec![TranslationProvider::MyMemory]);
+
+        let settings = TranslationSettings {
+            deepl_api_key: Some("abc:fx".to_string()),
+            libretranslate_url: Some("https://lt.example.org/".to_string()),
+            libretranslate_api_key: Some("  ".to_string()),
+        };
+        let chain = TranslationProvider::chain(&settings);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0].name(), "DeepL");
+        assert_eq!(
+            chain[1],
+            TranslationProvider::LibreTranslate {
+                endpoint: "https://lt.example.org".to_string(),
+                api_key: None,
+            }
+        );
+        assert_eq!(chain[2], TranslationProvider::MyMemory);
+    }
+
+    #[test]
+    fn test_detect_language() {
+        let detected = detect_language(
+            "Questo è un documento scritto in italiano che descrive il funzionamento dell'applicazione.",
+        )
+        .unwrap();
+        assert_eq!(detected.code, "it");
+        assert!(detected.confidence > 0.5);
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for blocked in [
+            "127.0.0.1",
+            "10.0.0.5",
+            "192.168.1.1",
+            "169.254.169.254",
+            "::1",
+            "fd00::1",
+        ] {
+            assert!(!is_public_ip(blocked.parse().unwrap()), "{}", blocked);
+        }
+        assert!(!is_public_ip("::ffff:192.168.0.1".parse().unwrap()));
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_extract_readable_text() {
+        let html = r#"<html><head><title>Notizia</title><script>var x = 1;</script></head>
+            <body><nav><p>Home · Chi siamo · Contatti e altri link del menu</p></nav>
+            <article><h1>Titolo articolo</h1>
+            <p>Primo paragrafo dell'articolo con abbastanza testo da contare.</p>
+            <p>Secondo paragrafo, anche questo sufficientemente lungo.</p></article>
+            <footer><p>Copyright 2024 - Tutti i diritti riservati a nessuno</p></footer></body></html>"#;
+
+        let page = extract_readable_text(html);
+        assert_eq!(page.title.as_deref(), Some("Notizia"));
+        assert!(page.text.starts_with("## Titolo articolo"));
+        assert!(page.text.contains("Secondo paragrafo"));
+        assert!(!page.text.contains("Copyright"));
+        assert!(!page.text.contains("Chi siamo"));
+        assert!(!page.text.contains("var x"));
+    }
+
+    #[test]
+    fn test_validate_network_target() {
+        assert_eq!(
+            validate_network_target(" example.com ").unwrap(),
+            "example.com"
+        );
+        assert!(validate_network_target("192.168.1.1").is_ok());
+        assert!(validate_network_target("fe80::1%eth0").is_ok());
+        assert!(validate_network_target("-f example.com").is_err());
+        assert!(validate_network_target("host; rm -rf /").is_err());
+        assert!(validate_network_target("").is_err());
+    }
+
+    #[test]
+    fn test_zip_error_messages() {
+        let encrypted = zip_error(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED));
+        assert!(encrypted.to_string().contains("password"));
+
+        let corrupted = zip_error(ZipError::InvalidArchive("Invalid zip header"));
+        assert!(corrupted.to_string().contains("corrotto"));
+
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_file_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[tokio::test]
+    async fn test_zip_create_keeps_existing_archive_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        for folder in ["uno/dati", "due/dati"] {
+            fs::create_dir_all(dir.path().join(folder)).unwrap();
+            fs::write(dir.path().join(folder).join("note.txt"), folder).unwrap();
+        }
+        let dest = dir.path().join("archivio.zip");
+        fs::write(&dest, "vecchio").unwrap();
+
+        let agent = AgentSystem::new();
+        let mut params = HashMap::new();
+        params.insert("dest_path".to_string(), json!(dest.to_string_lossy()));
+        params.insert(
+            "sources".to_string(),
+            json!([dir.path().join("uno/dati"), dir.path().join("due/dati")]),
+        );
+        let error = agent.execute_zip_create(&params).await.unwrap_err();
+        assert!(error.to_string().contains("stesso file"));
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "vecchio");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+
+        params.insert("sources".to_string(), json!([dir.path().join("uno")]));
+        agent.execute_zip_create(&params).await.unwrap();
+        let mut archive = ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        assert!(archive.by_name("uno/dati/note.txt").is_ok());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_offline_mode_blocks_network_tools() {
+        let mut agent = AgentSystem::new();
+        agent.set_offline_mode(true);
+
+        let call = ToolCall {
+            tool_name: "web_search".to_string(),
+            parameters: HashMap::new(),
+            raw_text: String::new(),
+        };
+        let result = agent.execute_tool(&call).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Modalità offline"));
+
+        assert!(!agent.get_tools_description().contains("### web_search"));
+        assert!(agent.get_tools_description().contains("### file_read"));
+        assert!(agent
+            .build_web_search_context("ultime notizie di oggi")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_qr() {
+        let dest = std::env::temp_dir().join(format!("matepro_qr_test_{}", Uuid::new_v4()));
+        let mut parameters = HashMap::new();
+        parameters.insert("content".to_string(), json!("https://example.org"));
+        parameters.insert("dest_path".to_string(), json!(dest.to_string_lossy()));
+        let mut call = ToolCall {
+            tool_name: "generate_qr".to_string(),
+            parameters,
+            raw_text: String::new(),
+        };
+        let saved = dest.with_extension("png");
+        assert_eq!(written_file(&call), Some(saved.clone()));
+
+        // Writing to a chosen path needs confirmation, the temp folder does not
+        let mut agent = AgentSystem::new();
+        assert!(agent.requires_confirmation(&call));
+        assert!(!agent.execute_tool(&call).await.unwrap().success);
+        agent.set_allow_dangerous(true);
+        let result = agent.execute_tool(&call).await.unwrap();
+        assert!(result.success);
+        assert!(result.image.unwrap().starts_with("data:image/png;base64,"));
+        assert!(fs::read(&saved).unwrap().starts_with(b"\x89PNG"));
+        // The confirmation is spent by the call
+        assert!(!agent.allow_dangerous);
+
+        call.parameters.insert("dest_path".to_string(), json!("  "));
+        assert!(!agent.requires_confirmation(&call));
+        assert_eq!(written_file(&call), None);
+
+        let too_long = qr_code_png(&"x".repeat(5000)).unwrap_err();
+        assert!(too_long.to_string().contains("troppo lungo"));
+        fs::remove_file(&saved).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_parse_calendar_event() {
+        let text = "pranzo con Marco venerdì alle 13";
+        let mut parameters = HashMap::new();
+        parameters.insert("text".to_string(), json!(text));
+        let call = ToolCall {
+            tool_name: "parse_calendar_event".to_string(),
+            parameters,
+            raw_text: String::new(),
+        };
+
+        let mut agent = AgentSystem::new();
+        let result = agent.execute_tool(&call).await.unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("venerdì"));
+        let event = result.calendar_event.unwrap();
+        assert_eq!(event.title, "Pranzo con Marco");
+        assert_eq!(event.source_text.as_deref(), Some(text));
+    }
+
+    #[test]
+    fn test_calendar_range() {
+        let mut params = HashMap::new();
+        params.insert("start_date".to_string(), json!("2025-03-01"));
+        params.insert("end_date".to_string(), json!("2025-03-31"));
+        let (start, end) = calendar_range(&params).unwrap();
+        let start = start.with_timezone(&Local).naive_local();
+        let end = end.unwrap().with_timezone(&Local).naive_local();
+
+        // The end day is included
+        assert_eq!(start.to_string(), "2025-03-01 00:00:00");
+        assert_eq!(end.to_string(), "2025-04-01 00:00:00");
+
+        params.insert("end_date".to_string(), json!("2025-03-31 18:30"));
+        let (_, end) = calendar_range(&params).unwrap();
+        let end = end.unwrap().with_timezone(&Local);
+        assert_eq!(end.format("%d/%m %H:%M").to_string(), "31/03 18:30");
+
+        let all_day = format_remote_event_start("2025-03-14");
+        assert_eq!(all_day, "venerdì 14 marzo 2025");
+        assert!(calendar_range(&HashMap::new()).unwrap().1.is_none());
+
+        // Without a start the range begins now: a past end is rejected here
+        let mut params = HashMap::new();
+        params.insert("end_date".to_string(), json!("2020-01-01"));
+        let error = calendar_range(&params).unwrap_err();
+        assert!(error.to_string().contains("deve seguire l'inizio"));
+    }
+
+    #[test]
+    fn test_calendar_sync_parameters() {
+        let mut params = HashMap::new();
+        params.insert("title".to_string(), json!("Riunione di progetto"));
+        params.insert("start".to_string(), json!("2025-03-14 10:00"));
+        params.insert("location".to_string(), json!("Sala riunioni"));
+        let (event, is_new) = calendar_sync_event(&params).unwrap();
+        assert!(is_new);
+        assert_eq!(event.title, "Riunione di progetto");
+        assert_eq!(event.location.as_deref(), Some("Sala riunioni"));
+        assert!(event.end.is_none());
+        let start = event.start.with_timezone(&Local).naive_local();
+        assert_eq!(start.to_string(), "2025-03-14 10:00:00");
+
+        params.insert("end".to_string(), json!("2025-03-14 09:00"));
+        assert!(calendar_sync_event(&params).is_err());
+        params.remove("title");
+        assert!(calendar_sync_event(&params).is_err());
+
+        let targets = json!(["Google", "outlook", "google"]);
+        params.insert("targets".to_string(), targets);
+        let targets = calendar_sync_targets(&params).unwrap();
+        assert_eq!(targets, vec!["google", "outlook"]);
+        params.insert("targets".to_string(), json!("outlook, gcal"));
+        let targets = calendar_sync_targets(&params).unwrap();
+        assert_eq!(targets, vec!["outlook", "google"]);
+        params.insert("targets".to_string(), json!(["icloud"]));
+        assert!(calendar_sync_targets(&params).is_err());
+        assert!(calendar_sync_targets(&HashMap::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wikipedia_helpers() {
+        assert_eq!(wikipedia_title_path("Dante Alighieri"), "Dante_Alighieri");
+        assert_eq!(wikipedia_title_path("C++"), "C%2B%2B");
+
+        let payload = json!(["roma", ["Roma", "AS Roma"], ["", ""], ["", ""]]);
+        assert_eq!(opensearch_titles(&payload), vec!["Roma", "AS Roma"]);
+        assert!(opensearch_titles(&json!({})).is_empty());
+
+        let summary: WikipediaSummary = serde_json::from_value(json!({
+            "type": "standard",
+            "title": "Roma",
+            "extract": "Roma è la capitale d'Italia.",
+            "content_urls": { "desktop": { "page": "https://it.wikipedia.org/wiki/Roma" } }
+        }))
+        .unwrap();
+        assert!(summary.page_url().unwrap().ends_with("/wiki/Roma"));
+        assert_eq!(summary.description, None);
+    }
+
+    #[test]
+    fn test_datetime_calculation() {
+        let (friday, has_time) = parse_local_datetime("10/05/2024").unwrap();
+        assert!(!has_time);
+        assert_eq!(friday.weekday(), Weekday::Fri);
+
+        let monday = shift_datetime(friday, 1, TimeUnit::BusinessDays).unwrap();
+        assert_eq!(monday.date(), NaiveDate::from_ymd_opt(2024, 5, 13).unwrap());
+        let back = shift_datetime(monday, -1, TimeUnit::BusinessDays).unwrap();
+        assert_eq!(back, friday);
+
+        let (end_of_january, _) = parse_local_datetime("2024-01-31 18:30").unwrap();
+        let shifted = shift_datetime(end_of_january, 1, TimeUnit::Months).unwrap();
+        assert_eq!(shifted.to_string(), "2024-02-29 18:30:00");
+        assert_eq!(
+            format_italian_datetime(shifted, true),
+            "giovedì 29 febbraio 2024 alle 18:30"
+        );
+
+        assert_eq!(business_days_between(friday.date(), monday.date()), 1);
+        assert_eq!(business_days_between(monday.date(), friday.date()), -1);
+        assert_eq!(
+            TimeUnit::parse("giorni lavorativi").unwrap(),
+            TimeUnit::BusinessDays
+        );
+        assert!(parse_local_datetime("domani sera").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_datetime_diff_direction() {
+        let agent = AgentSystem::new();
+        let diff = |base: &str, target: &str| -> HashMap<String, serde_json::Value> {
+            serde_json::from_value(json!({"operation": "diff", "base": base, "target": target}))
+                .unwrap()
+        };
+
+        let output = agent
+            .execute_datetime_calculate(&diff("2024-05-10 09:00", "2024-05-20 11:30"))
+            .await
+            .unwrap();
+        assert!(output.contains("- tra 10 giorni (1 settimane e 3 giorni)"));
+        assert!(output.contains("- tra 6 giorni lavorativi"));
+        assert!(output.contains("- tra 242 ore e 30 minuti"));
+
+        // Reversed range: the same amounts, in the past
+        let output = agent
+            .execute_datetime_calculate(&diff("2024-05-20 11:30", "2024-05-10 09:00"))
+            .await
+            .unwrap();
+        assert!(output.contains("- 10 giorni fa (1 settimane e 3 giorni)"));
+        assert!(output.contains("- 6 giorni lavorativi fa"));
+        assert!(output.contains("- 242 ore e 30 minuti fa"));
+        assert!(!output.contains(" -"), "{}", output);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let agent = AgentSystem::new();
+        let call = |tool_name: &str, parameters: serde_json::Value| ToolCall {
+            tool_name: tool_name.to_string(),
+            parameters: serde_json::from_value(parameters).unwrap(),
+            raw_text: String::new(),
+        };
+
+        let preview = agent
+            .dry_run(&call("shell_execute", json!({"command": "rm -rf build"})))
+            .unwrap()
+            .unwrap();
+        assert!(preview.ends_with("rm -rf build"));
+
+        let path = std::env::temp_dir().join(format!("matepro_dry_run_{}.txt", Uuid::new_v4()));
+        let content = (1..=25)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let preview = agent
+            .dry_run(&call(
+                "file_write",
+                json!({"path": path.to_str().unwrap(), "content": content}),
+            ))
+            .unwrap()
+            .unwrap();
+        assert!(preview.contains("Verrebbe creato un nuovo file"));
+        assert!(preview.contains("altre 5 righe"));
+        assert!(!path.exists());
+
+        assert!(agent
+            .dry_run(&call("file_read", json!({"path": "/tmp"})))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_file_operation_history() {
+        let dir = std::env::temp_dir().join(format!("matepro_undo_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut history = FileOperationHistory::new(dir.clone(), 2);
+
+        let existing = dir.join("note.txt");
+        fs::write(&existing, "originale").unwrap();
+        let operation = history.prepare("file_write", &existing).unwrap();
+        fs::write(&existing, "modificato").unwrap();
+        history.record(operation);
+
+        let created = dir.join("nuovo.txt");
+        let operation = history.prepare("file_write", &created).unwrap();
+        fs::write(&created, "contenuto").unwrap();
+        history.record(operation);
+        assert_eq!(history.depth(), 2);
+
+        history.undo_last().unwrap();
+        assert!(!created.exists());
+        history.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "originale");
+        assert!(history.undo_last().is_err());
+
+        // Beyond the depth the oldest operation and its backup are dropped
+        for _ in 0..3 {
+            let operation = history.prepare("file_write", &existing).unwrap();
+            history.record(operation);
+        }
+        assert_eq!(history.depth(), 2);
+        let backup_dir = history.backup_dir().unwrap().to_path_buf();
+        assert_eq!(fs::read_dir(&backup_dir).unwrap().count(), 2);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&backup_dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        drop(history);
+        assert!(!backup_dir.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_markdown_conversion() {
+        let markdown = "# Titolo\n\nTesto *enfatizzato* con `codice`.\n\n- uno\n- due";
+        assert_eq!(
+            markdown_to_text(markdown),
+            "Titolo\n\nTesto enfatizzato con codice.\n\n- uno\n- due"
+        );
+
+        let html = markdown_to_html_document(markdown, "Note <1>");
+        assert!(html.contains("<title>Note &lt;1&gt;</title>"));
+        assert!(html.contains("<h1>Titolo</h1>"));
+        assert!(html.contains("<em>enfatizzato</em>"));
+
+        assert_eq!(document_format(".Markdown").unwrap(), "md");
+        assert!(document_format("odt").is_err());
+    }
+
+    #[test]
+    fn test_zip_entry_name() {
+        assert_eq!(zip_entry_name(Path::new("docs/a.txt")), "docs/a.txt");
+        assert_eq!(zip_entry_name(Path::new("./a.txt")), "a.txt");
+        assert_eq!(zip_entry_name(Path::new("")), "");
+    }
+
+    #[test]
+    fn test_file_search() {
+        let dir = std::env::temp_dir().join(format!("matepro_search_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("report")).unwrap();
+        fs::create_dir_all(dir.join(".cache")).unwrap();
+        fs::write(dir.join("Note.TXT"), "prima riga\nBudget 2024 approvato").unwrap();
+        fs::write(dir.join("report/bilancio.txt"), "totale: 1200 euro").unwrap();
+        fs::write(dir.join("report/bilancio.csv"), "voce,importo").unwrap();
+        fs::write(dir.join(".cache/nascosto.txt"), "budget").unwrap();
+
+        let names = |matches: &[FileSearchMatch]| {
+            matches
+                .iter()
+                .map(|m| m.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let txt = glob_to_regex("*.txt").unwrap();
+        let (matches, truncated) = search_files(&dir, &txt, None, 10);
+        assert_eq!(names(&matches), vec!["Note.TXT", "bilancio.txt"]);
+        assert!(!truncated);
+
+        let (matches, truncated) = search_files(&dir, &txt, None, 1);
+        assert_eq!(matches.len(), 1);
+        assert!(truncated);
+
+        let budget = content_search_regex("budget").unwrap();
+        let (matches, _) = search_files(&dir, &glob_to_regex("*").unwrap(), Some(&budget), 10);
+        assert_eq!(names(&matches), vec!["Note.TXT"]);
+        assert_eq!(
+            matches[0].line,
+            Some((2, "Budget 2024 approvato".to_string()))
+        );
+
+        // Invalid regular expressions are searched as plain text
+        let literal = content_search_regex("1200 (").unwrap();
+        assert!(literal.is_match("totale: 1200 (circa)"));
+        let extension = glob_to_regex("bilancio.???").unwrap();
+        assert!(extension.is_match("bilancio.csv"));
+        assert!(!extension.is_match("bilancio.xlsx"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let dir = std::env::temp_dir().join(format!("matepro_usage_test_{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("video/2024")).unwrap();
+        fs::write(dir.join("video/2024/film.mp4"), vec![0u8; 3000]).unwrap();
+        fs::write(dir.join("video/trailer.mp4"), vec![0u8; 1000]).unwrap();
+        fs::write(dir.join("nota.txt"), vec![0u8; 500]).unwrap();
+
+        let usage = measure_disk_usage(&dir, 2);
+        assert_eq!(usage.total, 4500);
+        assert_eq!((usage.files, usage.directories), (3, 2));
+        assert_eq!(
+            usage.children,
+            vec![
+                ("video".to_string(), true, 4000),
+                ("nota.txt".to_string(), false, 500)
+            ]
+        );
+        let sizes: Vec<u64> = usage.largest_files.iter().map(|(_, size)| *size).collect();
+        assert_eq!(sizes, vec![3000, 1000]);
+
+        let report = format_disk_usage(&dir, &usage, 1);
+        assert!(report.contains("| video | 📁 cartella | 3.9 KB | 88.9% |"));
+        assert!(!report.contains("nota.txt"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
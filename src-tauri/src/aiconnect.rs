@@ -2,6 +2,7 @@
 // Integration with AIConnect orchestrator via mDNS discovery
 // Supports fallback to local Ollama when AIConnect is unavailable
 
+use crate::http_client;
 use anyhow::{anyhow, Context, Result};
 use mdns_sd::{ServiceDaemon, ServiceEvent};
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
@@ -61,6 +62,81 @@ pub struct AiConnectNode {
     pub models: Vec<String>,
     #[serde(default)]
     pub address: Option<String>,
+    /// Requests the node is processing, when the orchestrator reports it
+    #[serde(default, alias = "active_jobs")]
+    pub active_requests: Option<u32>,
+    /// Requests waiting for the node
+    #[serde(default, alias = "queue_length", alias = "pending_requests")]
+    pub queued_requests: Option<u32>,
+    /// Utilization percentages (0-100)
+    #[serde(default)]
+    pub cpu_usage: Option<f64>,
+    #[serde(default)]
+    pub gpu_usage: Option<f64>,
+    #[serde(default)]
+    pub memory_usage: Option<f64>,
+    /// Any other field of the node (hardware, version...), shown as is
+    #[serde(flatten)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Why a connection test failed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionIssue {
+    InvalidUrl,
+    /// Connection refused, unknown host or network down
+    Unreachable,
+    Timeout,
+    /// Certificate or handshake error on https endpoints
+    Tls,
+    /// 401, wrong or missing token/credentials
+    Unauthorized,
+    /// 403, credentials valid but not allowed
+    Forbidden,
+    /// 404, the server answers but the endpoint does not exist
+    NotFound,
+    HttpError,
+    /// 2xx with a body that is not from the expected service (e.g. a proxy login page)
+    UnexpectedResponse,
+}
+
+/// Result of a backend connection test, with details to show the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionDiagnostic {
+    pub url: String,
+    pub ok: bool,
+    /// HTTP status, when the server answered
+    pub status_code: Option<u16>,
+    pub issue: Option<ConnectionIssue>,
+    pub message: String,
+}
+
+impl ConnectionDiagnostic {
+    fn success(url: String, status_code: u16) -> Self {
+        Self {
+            url,
+            ok: true,
+            status_code: Some(status_code),
+            issue: None,
+            message: format!("Connessione riuscita (HTTP {})", status_code),
+        }
+    }
+
+    fn failure(
+        url: String,
+        status_code: Option<u16>,
+        issue: ConnectionIssue,
+        message: String,
+    ) -> Self {
+        Self {
+            url,
+            ok: false,
+            status_code,
+            issue: Some(issue),
+            message,
+        }
+    }
 }
 
 /// Response from AIConnect /internal/nodes endpoint
@@ -90,29 +166,64 @@ impl Default for BackendConfig {
     }
 }
 
+impl BackendConfig {
+    /// Copy without the authentication secrets, for diagnostics
+    pub fn redacted(&self) -> Self {
+        self.with_secret(crate::local_storage::REDACTED)
+    }
+
+    /// Copy with the token or password left empty, to be saved on disk: only the
+    /// kind of authentication (and the Basic username) is kept
+    pub fn without_secrets(&self) -> Self {
+        self.with_secret("")
+    }
+
+    /// Whether the token or password is filled in
+    pub fn has_secret(&self) -> bool {
+        match &self.auth {
+            AuthMethod::None => false,
+            AuthMethod::Bearer { token } => !token.is_empty(),
+            AuthMethod::Basic { password, .. } => !password.is_empty(),
+        }
+    }
+
+    fn with_secret(&self, secret: &str) -> Self {
+        let auth = match &self.auth {
+            AuthMethod::None => AuthMethod::None,
+            AuthMethod::Bearer { .. } => AuthMethod::Bearer {
+                token: secret.to_string(),
+            },
+            AuthMethod::Basic { username, .. } => AuthMethod::Basic {
+                username: username.clone(),
+                password: secret.to_string(),
+            },
+        };
+        Self {
+            auth,
+            ..self.clone()
+        }
+    }
+}
+
+/// Timeout for AIConnect API calls
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Timeout for reachability checks
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// AIConnect client with authentication support
 pub struct AiConnectClient {
-    http_client: reqwest::Client,
     config: Arc<Mutex<BackendConfig>>,
 }
 
 impl AiConnectClient {
     pub fn new() -> Self {
         Self {
-            http_client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
             config: Arc::new(Mutex::new(BackendConfig::default())),
         }
     }
 
     pub fn with_config(config: BackendConfig) -> Self {
         Self {
-            http_client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
             config: Arc::new(Mutex::new(config)),
         }
     }
@@ -168,9 +279,10 @@ impl AiConnectClient {
         let url = format!("{}{}", config.endpoint, path);
         let headers = Self::build_auth_headers(&config.auth);
 
-        self.http_client
+        http_client::shared_client()
             .get(&url)
             .headers(headers)
+            .timeout(REQUEST_TIMEOUT)
             .send()
             .await
             .context(format!("GET request to {} failed", url))
@@ -182,10 +294,11 @@ impl AiConnectClient {
         let url = format!("{}{}", config.endpoint, path);
         let headers = Self::build_auth_headers(&config.auth);
 
-        self.http_client
+        http_client::shared_client()
             .post(&url)
             .headers(headers)
             .json(body)
+            .timeout(REQUEST_TIMEOUT)
             .send()
             .await
             .context(format!("POST request to {} failed", url))
@@ -201,7 +314,12 @@ impl AiConnectClient {
                 let url = format!("{}/api/health", config.endpoint);
                 let headers = Self::build_auth_headers(&config.auth);
 
-                match self.http_client.get(&url).headers(headers).send().await {
+                let request = http_client::shared_client()
+                    .get(&url)
+                    .headers(headers)
+                    .timeout(HEALTH_CHECK_TIMEOUT);
+
+                match request.send().await {
                     Ok(response) => response.status().is_success(),
                     Err(_) => false,
                 }
@@ -210,7 +328,11 @@ impl AiConnectClient {
                 // Ollama uses /api/tags
                 let url = format!("{}/api/tags", config.endpoint);
 
-                match self.http_client.get(&url).send().await {
+                let request = http_client::shared_client()
+                    .get(&url)
+                    .timeout(HEALTH_CHECK_TIMEOUT);
+
+                match request.send().await {
                     Ok(response) => response.status().is_success(),
                     Err(_) => false,
                 }
@@ -231,10 +353,10 @@ impl AiConnectClient {
         let url = format!("{}/internal/nodes", config.endpoint);
         let headers = Self::build_auth_headers(&config.auth);
 
-        let response = self
-            .http_client
+        let response = http_client::shared_client()
             .get(&url)
             .headers(headers)
+            .timeout(REQUEST_TIMEOUT)
             .send()
             .await
             .context("Failed to fetch AIConnect nodes")?;
@@ -253,6 +375,79 @@ impl AiConnectClient {
 
         Ok(nodes_response.nodes)
     }
+
+    /// Base URL of a node, to send a request to it bypassing the load balancer
+    pub async fn node_endpoint(&self, node_id: &str) -> Result<String> {
+        let nodes = self.get_nodes().await?;
+        let node = nodes
+            .iter()
+            .find(|node| node.id == node_id)
+            .ok_or_else(|| anyhow!("Nodo {} non trovato", node_id))?;
+
+        if node.status.eq_ignore_ascii_case("offline") {
+            return Err(anyhow!("Il nodo {} è offline", node.name));
+        }
+
+        node.address
+            .as_deref()
+            .map(node_base_url)
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| anyhow!("Il nodo {} non espone un indirizzo", node.name))
+    }
+}
+
+/// Node addresses may omit the scheme ("10.0.0.5:11434")
+fn node_base_url(address: &str) -> String {
+    let address = address.trim().trim_end_matches('/');
+    if address.is_empty() || address.contains("://") {
+        address.to_string()
+    } else {
+        format!("http://{}", address)
+    }
+}
+
+/// Port Ollama listens on when the user types only a host name
+pub const OLLAMA_DEFAULT_PORT: u16 = 11434;
+
+/// Turns what the user typed into a base URL for Ollama: adds `http://` when the
+/// scheme is missing (and the default port with it), drops trailing slashes and
+/// rejects inputs that cannot be a server address
+pub fn normalize_ollama_url(input: &str) -> Result<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(anyhow!("Inserisci l'URL del server Ollama"));
+    }
+
+    let has_scheme = input.contains("://");
+    let candidate = if has_scheme {
+        input.to_string()
+    } else {
+        format!("http://{}", input)
+    };
+
+    let mut url = url::Url::parse(&candidate)
+        .map_err(|e| anyhow!("URL del server non valido '{}': {}", input, e))?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(anyhow!(
+            "Schema '{}' non supportato: usa http:// o https://",
+            url.scheme()
+        ));
+    }
+    if url.host_str().unwrap_or_default().is_empty() {
+        return Err(anyhow!("URL del server senza host: '{}'", input));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(anyhow!(
+            "L'URL del server non deve contenere parametri o frammenti: '{}'",
+            input
+        ));
+    }
+    if !has_scheme && url.port().is_none() {
+        let _ = url.set_port(Some(OLLAMA_DEFAULT_PORT));
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_string())
 }
 
 impl Default for AiConnectClient {
@@ -357,23 +552,11 @@ pub async fn auto_configure_backend(
             let endpoint = service.base_url();
 
             // Check if AIConnect is reachable
-            let client = match reqwest::Client::builder()
-                .timeout(Duration::from_secs(5))
-                .build()
-            {
-                Ok(c) => c,
-                Err(_) => {
-                    return BackendConfig {
-                        kind: BackendKind::OllamaLocal,
-                        endpoint: fallback_ollama_url.to_string(),
-                        auth: AuthMethod::None,
-                        aiconnect_service: None,
-                    };
-                }
-            };
-
             let health_url = format!("{}/api/health", endpoint);
-            if let Ok(response) = client.get(&health_url).send().await {
+            let request = http_client::shared_client()
+                .get(&health_url)
+                .timeout(HEALTH_CHECK_TIMEOUT);
+            if let Ok(response) = request.send().await {
                 if response.status().is_success() {
                     return BackendConfig {
                         kind: BackendKind::AiConnect,
@@ -395,43 +578,164 @@ pub async fn auto_configure_backend(
     }
 }
 
-/// Check if AIConnect is available at the given endpoint
-pub async fn check_aiconnect_health(endpoint: &str, auth: &AuthMethod) -> bool {
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-    {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
-
-    let url = format!("{}/api/health", endpoint);
+/// Check if AIConnect is available at the given endpoint, explaining why not
+pub async fn check_aiconnect_health(endpoint: &str, auth: &AuthMethod) -> ConnectionDiagnostic {
+    let url = format!("{}/api/health", endpoint.trim_end_matches('/'));
     let headers = AiConnectClient::build_auth_headers(auth);
+    diagnose_endpoint(url, headers, "AIConnect").await
+}
 
-    match client.get(&url).headers(headers).send().await {
-        Ok(response) => response.status().is_success(),
-        Err(_) => false,
-    }
+/// Check if Ollama is available at the given endpoint, explaining why not
+pub async fn diagnose_ollama(endpoint: &str) -> ConnectionDiagnostic {
+    let url = format!("{}/api/tags", endpoint.trim_end_matches('/'));
+    diagnose_endpoint(url, HeaderMap::new(), "Ollama").await
 }
 
 /// Check if Ollama is available at the given endpoint
 pub async fn check_ollama_health(endpoint: &str) -> bool {
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-    {
-        Ok(c) => c,
-        Err(_) => return false,
+    diagnose_ollama(endpoint).await.ok
+}
+
+async fn diagnose_endpoint(url: String, headers: HeaderMap, service: &str) -> ConnectionDiagnostic {
+    if let Err(e) = reqwest::Url::parse(&url) {
+        return ConnectionDiagnostic::failure(
+            url,
+            None,
+            ConnectionIssue::InvalidUrl,
+            format!("URL non valido: {}", e),
+        );
+    }
+
+    let request = http_client::shared_client()
+        .get(&url)
+        .headers(headers)
+        .timeout(HEALTH_CHECK_TIMEOUT);
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let (issue, message) = request_error_issue(&e);
+            return ConnectionDiagnostic::failure(url, None, issue, message);
+        }
     };
 
-    let url = format!("{}/api/tags", endpoint);
+    let status = response.status().as_u16();
+    if !response.status().is_success() {
+        let (issue, message) = status_issue(status, service);
+        return ConnectionDiagnostic::failure(url, Some(status), issue, message);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    if looks_like_html(&body) {
+        return ConnectionDiagnostic::failure(
+            url,
+            Some(status),
+            ConnectionIssue::UnexpectedResponse,
+            format!(
+                "Il server risponde con una pagina web invece che come {}: l'indirizzo potrebbe puntare a un altro servizio o a un proxy",
+                service
+            ),
+        );
+    }
+
+    ConnectionDiagnostic::success(url, status)
+}
+
+fn request_error_issue(error: &reqwest::Error) -> (ConnectionIssue, String) {
+    let details = error_causes(error);
+
+    if error.is_timeout() {
+        (
+            ConnectionIssue::Timeout,
+            format!(
+                "Nessuna risposta entro {} secondi: il server è lento o bloccato da un firewall",
+                HEALTH_CHECK_TIMEOUT.as_secs()
+            ),
+        )
+    } else if is_tls_error(&details) {
+        (
+            ConnectionIssue::Tls,
+            format!(
+                "Errore TLS/certificato ({}): verifica il certificato del server o prova con http://",
+                details
+            ),
+        )
+    } else {
+        (
+            ConnectionIssue::Unreachable,
+            format!(
+                "Server non raggiungibile ({}): controlla indirizzo, porta e che il servizio sia avviato",
+                details
+            ),
+        )
+    }
+}
 
-    match client.get(&url).send().await {
-        Ok(response) => response.status().is_success(),
-        Err(_) => false,
+/// Causes of a request error (refused, DNS, TLS...). reqwest's own message only
+/// repeats the URL, which could also trigger false TLS matches on host names.
+fn error_causes(error: &dyn std::error::Error) -> String {
+    let mut messages: Vec<String> = Vec::new();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let message = cause.to_string();
+        if !messages.contains(&message) {
+            messages.push(message);
+        }
+        source = cause.source();
+    }
+
+    if messages.is_empty() {
+        error.to_string()
+    } else {
+        messages.join(": ")
     }
 }
 
+fn is_tls_error(details: &str) -> bool {
+    let details = details.to_lowercase();
+    ["certificate", "tls", "ssl", "handshake", "unknownissuer"]
+        .iter()
+        .any(|marker| details.contains(marker))
+}
+
+fn status_issue(status: u16, service: &str) -> (ConnectionIssue, String) {
+    match status {
+        401 => (
+            ConnectionIssue::Unauthorized,
+            "Autenticazione fallita (HTTP 401): verifica token o credenziali".to_string(),
+        ),
+        403 => (
+            ConnectionIssue::Forbidden,
+            "Accesso negato (HTTP 403): le credenziali non hanno i permessi necessari".to_string(),
+        ),
+        404 => (
+            ConnectionIssue::NotFound,
+            format!(
+                "Il server risponde ma non sembra {} (HTTP 404): controlla indirizzo e porta",
+                service
+            ),
+        ),
+        407 => (
+            ConnectionIssue::HttpError,
+            "Il proxy richiede l'autenticazione (HTTP 407): controlla le impostazioni del proxy"
+                .to_string(),
+        ),
+        500..=599 => (
+            ConnectionIssue::HttpError,
+            format!("Errore interno del server {} (HTTP {})", service, status),
+        ),
+        _ => (
+            ConnectionIssue::HttpError,
+            format!("Risposta inattesa dal server (HTTP {})", status),
+        ),
+    }
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let start = body.trim_start().to_lowercase();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -464,4 +768,97 @@ mod tests {
         assert!(json.contains("bearer"));
         assert!(json.contains("test_token"));
     }
+
+    #[test]
+    fn test_backend_config_without_secrets() {
+        let config = BackendConfig {
+            kind: BackendKind::AiConnect,
+            endpoint: "http://10.0.0.5:8080".to_string(),
+            auth: AuthMethod::Basic {
+                username: "mario".to_string(),
+                password: "segreta".to_string(),
+            },
+            aiconnect_service: None,
+        };
+        assert!(config.has_secret());
+
+        let saved = config.without_secrets();
+        assert!(!saved.has_secret());
+        let json = serde_json::to_string(&saved).unwrap();
+        assert!(json.contains("mario"));
+        assert!(!json.contains("segreta"));
+        assert!(!BackendConfig::default().has_secret());
+    }
+
+    #[test]
+    fn test_node_load_deserialization() {
+        let json = r#"{"nodes":[
+            {"id":"n1","name":"gpu-box","status":"online","models":["llama3"],
+             "active_jobs":2,"queue_length":1,"gpu_usage":73.5,"gpu_model":"RTX 4090"},
+            {"id":"n2","name":"laptop","status":"offline"}
+        ]}"#;
+        let response: NodesResponse = serde_json::from_str(json).unwrap();
+
+        let node = &response.nodes[0];
+        assert_eq!(node.active_requests, Some(2));
+        assert_eq!(node.queued_requests, Some(1));
+        assert_eq!(node.gpu_usage, Some(73.5));
+        assert_eq!(node.metadata["gpu_model"], "RTX 4090");
+        assert!(response.nodes[1].models.is_empty());
+        assert_eq!(response.nodes[1].cpu_usage, None);
+    }
+
+    #[test]
+    fn test_node_base_url() {
+        assert_eq!(node_base_url("10.0.0.5:11434"), "http://10.0.0.5:11434");
+        assert_eq!(node_base_url("https://gpu-01.lan/ "), "https://gpu-01.lan");
+        assert_eq!(node_base_url("  "), "");
+    }
+
+    #[test]
+    fn test_normalize_ollama_url() {
+        assert_eq!(
+            normalize_ollama_url("localhost:11434").unwrap(),
+            "http://localhost:11434"
+        );
+        assert_eq!(
+            normalize_ollama_url(" http://192.168.1.5:11434// ").unwrap(),
+            "http://192.168.1.5:11434"
+        );
+        assert_eq!(
+            normalize_ollama_url("gpu-server").unwrap(),
+            "http://gpu-server:11434"
+        );
+        assert_eq!(
+            normalize_ollama_url("https://ollama.example.com/").unwrap(),
+            "https://ollama.example.com"
+        );
+        assert_eq!(
+            normalize_ollama_url("https://example.com/ollama/").unwrap(),
+            "https://example.com/ollama"
+        );
+        assert!(normalize_ollama_url("").is_err());
+        assert!(normalize_ollama_url("ftp://server").is_err());
+        assert!(normalize_ollama_url("http://").is_err());
+        assert!(normalize_ollama_url("localhost:abc").is_err());
+        assert!(normalize_ollama_url("http://server:11434/?x=1").is_err());
+    }
+
+    #[test]
+    fn test_status_issue() {
+        assert_eq!(status_issue(401, "Ollama").0, ConnectionIssue::Unauthorized);
+        assert_eq!(status_issue(403, "Ollama").0, ConnectionIssue::Forbidden);
+        let (issue, message) = status_issue(404, "AIConnect");
+        assert_eq!(issue, ConnectionIssue::NotFound);
+        assert!(message.contains("AIConnect"));
+        assert_eq!(status_issue(502, "Ollama").0, ConnectionIssue::HttpError);
+    }
+
+    #[test]
+    fn test_response_checks() {
+        assert!(is_tls_error("invalid peer certificate: UnknownIssuer"));
+        assert!(!is_tls_error("tcp connect error: Connection refused"));
+        assert!(looks_like_html("\n<!DOCTYPE html><html><body>Login</body>"));
+        assert!(!looks_like_html(r#"{"status":"ok"}"#));
+    }
 }
@@ -1,14 +1,13 @@
+use crate::http_client;
 use crate::local_storage::{
     self, CalendarEvent, CalendarIntegrations, GoogleCalendarIntegrationConfig, OutlookIntegrationConfig,
-    PendingDeviceFlow, PendingPkceFlow,
+    PendingDeviceFlow, PendingPkceFlow, RecurrenceFrequency, RecurrenceRule,
 };
 use anyhow::{anyhow, Context, Result};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
-use chrono::{Duration, Utc};
-use lazy_static::lazy_static;
+use chrono::{DateTime, Datelike, Duration, Local, SecondsFormat, Utc};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -21,9 +20,12 @@ use uuid::Uuid;
 
 const GRAPH_SCOPE: &str = "offline_access Calendars.ReadWrite";
 const GRAPH_ENDPOINT: &str = "https://graph.microsoft.com/v1.0";
+/// Used when the machine's zone is unknown
 const DEFAULT_TIME_ZONE: &str = "UTC";
 
 const GOOGLE_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+const GOOGLE_CALENDAR_LIST_SCOPE: &str =
+    "https://www.googleapis.com/auth/calendar.calendarlist.readonly";
 #[allow(dead_code)]
 const GOOGLE_DEVICE_CODE_ENDPOINT: &str = "https://oauth2.googleapis.com/device/code";
 const GOOGLE_TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
@@ -33,12 +35,19 @@ const GOOGLE_CALENDAR_API: &str = "https://www.googleapis.com/calendar/v3";
 const LOOPBACK_CALLBACK_PATH: &str = "/";
 const PKCE_POLL_INTERVAL_SECS: u64 = 2;
 
-lazy_static! {
-    static ref HTTP_CLIENT: Client = Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .expect("Impossibile creare il client HTTP per le integrazioni calendario");
-}
+/// Timeout for Google and Microsoft calendar API calls
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(30);
+/// Days of Outlook occurrences listed when no end date is given
+const OUTLOOK_VIEW_DEFAULT_DAYS: i64 = 365;
+/// Without a start date the events are listed from this many hours ago
+const LIST_DEFAULT_PAST_HOURS: i64 = 12;
+const GOOGLE_CLIENT_ID_SUFFIX: &str = ".apps.googleusercontent.com";
+// Codice fittizio usato per interrogare il token endpoint senza un'autorizzazione reale
+const PROBE_AUTHORIZATION_CODE: &str = "matepro-credentials-check";
+const OUTLOOK_RECONNECT_MESSAGE: &str =
+    "Accesso a Outlook revocato o scaduto: ricollega il calendario";
+const GOOGLE_RECONNECT_MESSAGE: &str =
+    "Accesso a Google Calendar revocato o scaduto: ricollega il calendario";
 
 fn sanitize_optional_string(value: &Option<String>) -> Option<String> {
     value
@@ -232,9 +241,15 @@ pub struct OutlookStatus {
     pub configured: bool,
     pub connected: bool,
     pub pending: bool,
+    /// L'accesso è stato revocato dal provider e va autorizzato di nuovo
+    pub reconnect_required: bool,
     pub tenant: Option<String>,
     pub client_id: Option<String>,
+    /// Calendario scelto, `None` per quello predefinito
+    pub calendar_id: Option<String>,
     pub expires_at: Option<String>,
+    /// Scadenza dell'autorizzazione in attesa nel browser
+    pub pending_expires_at: Option<String>,
     pub message: Option<String>,
     pub interval: Option<u64>,
     pub user_code: Option<String>,
@@ -246,9 +261,13 @@ pub struct GoogleCalendarStatus {
     pub configured: bool,
     pub connected: bool,
     pub pending: bool,
+    /// L'accesso è stato revocato dal provider e va autorizzato di nuovo
+    pub reconnect_required: bool,
     pub client_id: Option<String>,
     pub calendar_id: Option<String>,
     pub expires_at: Option<String>,
+    /// Scadenza dell'autorizzazione in attesa nel browser
+    pub pending_expires_at: Option<String>,
     pub message: Option<String>,
     pub interval: Option<u64>,
     pub user_code: Option<String>,
@@ -261,6 +280,14 @@ pub struct CalendarIntegrationStatus {
     pub google: GoogleCalendarStatus,
 }
 
+/// Risultato della verifica delle credenziali, prima di avviare l'autorizzazione
+#[derive(Debug, Serialize)]
+pub struct CalendarCredentialsCheck {
+    pub provider: String,
+    pub valid: bool,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct OutlookDeviceFlowStart {
     pub user_code: String,
@@ -289,6 +316,16 @@ pub struct RemoteCalendarEvent {
     pub source: &'static str,
 }
 
+/// Calendario dell'account, tra cui scegliere quello da usare
+#[derive(Debug, Serialize)]
+pub struct RemoteCalendar {
+    pub id: String,
+    pub name: String,
+    pub primary: bool,
+    pub can_edit: bool,
+    pub selected: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRemoteEventRequest {
     pub subject: String,
@@ -296,6 +333,8 @@ pub struct CreateRemoteEventRequest {
     pub end: String,
     pub body: Option<String>,
     pub location: Option<String>,
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRule>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -385,15 +424,31 @@ fn build_outlook_status(outlook: &OutlookIntegrationConfig) -> OutlookStatus {
         )
     };
 
+    let pending_expires_at = outlook
+        .pending_pkce
+        .as_ref()
+        .map(|p| p.expires_at)
+        .or_else(|| outlook.pending.as_ref().map(|p| p.expires_at))
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    let message = message.or_else(|| {
+        outlook
+            .reconnect_required
+            .then(|| OUTLOOK_RECONNECT_MESSAGE.to_string())
+    });
+
     OutlookStatus {
         configured,
         connected,
         pending,
+        reconnect_required: outlook.reconnect_required,
         tenant: sanitize_optional_string(&outlook.tenant),
         client_id: sanitize_optional_string(&outlook.client_id),
+        calendar_id: sanitize_optional_string(&outlook.calendar_id),
         expires_at: outlook
             .expires_at
             .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+        pending_expires_at,
         message,
         interval,
         user_code,
@@ -431,16 +486,31 @@ fn build_google_status(google: &GoogleCalendarIntegrationConfig) -> GoogleCalend
         )
     };
 
+    let pending_expires_at = google
+        .pending_pkce
+        .as_ref()
+        .map(|p| p.expires_at)
+        .or_else(|| google.pending.as_ref().map(|p| p.expires_at))
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    let message = message.or_else(|| {
+        google
+            .reconnect_required
+            .then(|| GOOGLE_RECONNECT_MESSAGE.to_string())
+    });
+
     GoogleCalendarStatus {
         configured,
         connected,
         pending,
+        reconnect_required: google.reconnect_required,
         client_id: sanitize_optional_string(&google.client_id),
         calendar_id: sanitize_optional_string(&google.calendar_id)
             .or_else(|| Some("primary".to_string())),
         expires_at: google
             .expires_at
             .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+        pending_expires_at,
         message,
         interval,
         user_code,
@@ -512,6 +582,10 @@ fn ensure_google_scopes(google: &mut GoogleCalendarIntegrationConfig) {
     if google.scopes.is_empty() {
         google.scopes = vec![GOOGLE_SCOPE.to_string()];
     }
+    // Le configurazioni precedenti non chiedevano l'elenco dei calendari
+    if !google.scopes.iter().any(|scope| scope == GOOGLE_CALENDAR_LIST_SCOPE) {
+        google.scopes.push(GOOGLE_CALENDAR_LIST_SCOPE.to_string());
+    }
     if google.calendar_id.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true) {
         google.calendar_id = Some("primary".to_string());
     }
@@ -552,6 +626,8 @@ pub fn set_outlook_credentials(client_id: String, tenant: Option<String>) -> Res
         let outlook = get_outlook_config_mut(&mut integrations);
 
         outlook.client_id = Some(client_id.trim().to_string());
+        // Il nuovo collegamento può usare un altro account
+        outlook.calendar_id = None;
         outlook.tenant = Some(
             tenant
                 .unwrap_or_else(|| "common".to_string())
@@ -564,6 +640,7 @@ pub fn set_outlook_credentials(client_id: String, tenant: Option<String>) -> Res
         outlook.access_token = None;
         outlook.refresh_token = None;
         outlook.expires_at = None;
+        outlook.reconnect_required = false;
         ensure_scopes(outlook);
 
         build_outlook_status(outlook)
@@ -588,6 +665,7 @@ pub fn disconnect_outlook() -> Result<CalendarIntegrationStatus> {
         outlook.access_token = None;
         outlook.refresh_token = None;
         outlook.expires_at = None;
+        outlook.reconnect_required = false;
         outlook.pending = None;
         outlook.pending_pkce = None;
 
@@ -625,6 +703,7 @@ pub fn set_google_credentials(client_id: String, calendar_id: Option<String>) ->
         google.access_token = None;
         google.refresh_token = None;
         google.expires_at = None;
+        google.reconnect_required = false;
 
         build_google_status(google)
     };
@@ -648,6 +727,7 @@ pub fn disconnect_google() -> Result<CalendarIntegrationStatus> {
         google.access_token = None;
         google.refresh_token = None;
         google.expires_at = None;
+        google.reconnect_required = false;
         google.pending = None;
         google.pending_pkce = None;
 
@@ -663,6 +743,244 @@ pub fn disconnect_google() -> Result<CalendarIntegrationStatus> {
     })
 }
 
+/// Verifica la configurazione OAuth del provider (`outlook` o `google`) con una
+/// chiamata minima, senza avviare il flow di autorizzazione
+pub async fn validate_calendar_credentials(provider: &str) -> Result<CalendarCredentialsCheck> {
+    let integrations = load_integrations()?;
+    let problem = match provider {
+        "outlook" => {
+            let outlook = integrations.outlook.unwrap_or_default();
+            check_outlook_credentials(&outlook).await?
+        }
+        "google" => {
+            let google = integrations.google.unwrap_or_default();
+            check_google_credentials(&google).await?
+        }
+        other => return Err(anyhow!("Provider calendario sconosciuto: {}", other)),
+    };
+
+    Ok(CalendarCredentialsCheck {
+        provider: provider.to_string(),
+        valid: problem.is_none(),
+        message: problem.unwrap_or_else(|| "La configurazione sembra valida".to_string()),
+    })
+}
+
+/// Descrizione del problema trovato, `None` se la configurazione è valida
+async fn check_outlook_credentials(outlook: &OutlookIntegrationConfig) -> Result<Option<String>> {
+    let (client_id, tenant) = match ensure_client_and_tenant(outlook) {
+        Ok(values) => values,
+        Err(err) => return Ok(Some(err.to_string())),
+    };
+    if Uuid::parse_str(&client_id).is_err() {
+        return Ok(Some(
+            "Il Client ID Outlook deve essere l'ID applicazione (GUID) dell'app".to_string(),
+        ));
+    }
+
+    let response = http_client::shared_client()
+        .get(format!(
+            "https://login.microsoftonline.com/{tenant}/v2.0/.well-known/openid-configuration"
+        ))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Verifica del tenant Outlook fallita")?;
+    if !response.status().is_success() {
+        return Ok(Some(format!("Tenant Outlook \"{}\" non trovato", tenant)));
+    }
+
+    let rejection = probe_token_endpoint(
+        &format!("https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token"),
+        &[
+            ("grant_type", "authorization_code"),
+            ("client_id", client_id.as_str()),
+            ("code", PROBE_AUTHORIZATION_CODE),
+            ("redirect_uri", "http://localhost/"),
+            ("scope", GRAPH_SCOPE),
+        ],
+    )
+    .await?;
+    Ok(rejection.map(|reason| format!("Client ID Outlook rifiutato da Microsoft: {}", reason)))
+}
+
+/// Descrizione del problema trovato, `None` se la configurazione è valida
+async fn check_google_credentials(
+    google: &GoogleCalendarIntegrationConfig,
+) -> Result<Option<String>> {
+    let client_id = match ensure_google_client_id(google) {
+        Ok(client_id) => client_id,
+        Err(err) => return Ok(Some(err.to_string())),
+    };
+    if !client_id.ends_with(GOOGLE_CLIENT_ID_SUFFIX) {
+        return Ok(Some(format!(
+            "Il Client ID Google deve terminare con \"{}\"",
+            GOOGLE_CLIENT_ID_SUFFIX
+        )));
+    }
+
+    let rejection = probe_token_endpoint(
+        GOOGLE_TOKEN_ENDPOINT,
+        &[
+            ("grant_type", "authorization_code"),
+            ("client_id", client_id.as_str()),
+            ("code", PROBE_AUTHORIZATION_CODE),
+            ("redirect_uri", "http://127.0.0.1/"),
+        ],
+    )
+    .await?;
+    Ok(rejection.map(|reason| format!("Client ID Google rifiutato da Google: {}", reason)))
+}
+
+/// Scambia un codice fittizio: il token endpoint controlla il client prima del
+/// codice, quindi solo un client sconosciuto o mal configurato produce
+/// `invalid_client`/`unauthorized_client`. Restituisce il motivo del rifiuto
+async fn probe_token_endpoint(endpoint: &str, form: &[(&str, &str)]) -> Result<Option<String>> {
+    let response = http_client::shared_client()
+        .post(endpoint)
+        .form(form)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Verifica delle credenziali fallita")?;
+    if response.status().is_success() {
+        return Ok(None);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let Ok(error) = serde_json::from_str::<TokenErrorResponse>(&body) else {
+        return Ok(None);
+    };
+    if error.error != "invalid_client" && error.error != "unauthorized_client" {
+        return Ok(None);
+    }
+
+    // Microsoft aggiunge trace ID e timestamp su righe successive
+    let reason = error
+        .error_description
+        .as_deref()
+        .and_then(|description| description.lines().next())
+        .map(|line| line.trim().trim_end_matches('.').to_string())
+        .filter(|line| !line.is_empty())
+        .unwrap_or(error.error);
+    Ok(Some(reason))
+}
+
+/// Calendari dell'account collegato al provider (`outlook` o `google`)
+pub async fn list_available_calendars(provider: &str) -> Result<Vec<RemoteCalendar>> {
+    match provider {
+        "outlook" => list_outlook_calendars().await,
+        "google" => list_google_calendars().await,
+        other => Err(anyhow!("Provider calendario sconosciuto: {}", other)),
+    }
+}
+
+/// Salva il calendario da usare per leggere e creare eventi; `None` torna a
+/// quello predefinito dell'account
+pub fn select_calendar(
+    provider: &str,
+    calendar_id: Option<String>,
+) -> Result<CalendarIntegrationStatus> {
+    let calendar_id = sanitize_optional_string(&calendar_id);
+    let mut integrations = load_integrations()?;
+    match provider {
+        "outlook" => get_outlook_config_mut(&mut integrations).calendar_id = calendar_id,
+        "google" => {
+            get_google_config_mut(&mut integrations).calendar_id =
+                Some(calendar_id.unwrap_or_else(|| "primary".to_string()))
+        }
+        other => return Err(anyhow!("Provider calendario sconosciuto: {}", other)),
+    }
+    store_integrations(&integrations)?;
+    get_calendar_status()
+}
+
+async fn list_google_calendars() -> Result<Vec<RemoteCalendar>> {
+    let mut integrations = load_integrations()?;
+    let (token, selected_id) = {
+        let google = get_google_config_mut(&mut integrations);
+        if !google.enabled || google.access_token.is_none() {
+            return Err(anyhow!("Google Calendar non è collegato"));
+        }
+        ensure_google_scopes(google);
+        let token = ensure_google_access_token(google).await?;
+        let selected_id = google
+            .calendar_id
+            .clone()
+            .unwrap_or_else(|| "primary".to_string());
+        (token, selected_id)
+    };
+
+    store_integrations(&integrations)?;
+
+    let response = http_client::shared_client()
+        .get(format!("{GOOGLE_CALENDAR_API}/users/me/calendarList"))
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Richiesta calendari Google fallita")?;
+
+    if response.status() == reqwest::StatusCode::FORBIDDEN {
+        return Err(anyhow!(
+            "Permesso per l'elenco dei calendari mancante: ricollega Google Calendar"
+        ));
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Impossibile recuperare i calendari Google (stato {}): {}",
+            status,
+            body
+        ));
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GoogleCalendarListResponse {
+        #[serde(default)]
+        items: Vec<GoogleCalendarListEntry>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct GoogleCalendarListEntry {
+        id: String,
+        #[serde(default)]
+        summary: Option<String>,
+        #[serde(default)]
+        summary_override: Option<String>,
+        #[serde(default)]
+        primary: bool,
+        #[serde(default)]
+        access_role: Option<String>,
+    }
+
+    let list: GoogleCalendarListResponse = response
+        .json()
+        .await
+        .context("Impossibile decodificare i calendari Google")?;
+
+    let calendars = list
+        .items
+        .into_iter()
+        .map(|entry| {
+            let selected = entry.id == selected_id || (entry.primary && selected_id == "primary");
+            RemoteCalendar {
+                name: sanitize_optional_string(&entry.summary_override)
+                    .or_else(|| sanitize_optional_string(&entry.summary))
+                    .unwrap_or_else(|| entry.id.clone()),
+                can_edit: matches!(entry.access_role.as_deref(), Some("owner" | "writer")),
+                primary: entry.primary,
+                selected,
+                id: entry.id,
+            }
+        })
+        .collect();
+
+    Ok(calendars)
+}
+
 pub async fn start_google_device_flow() -> Result<OutlookDeviceFlowStart> {
     // Authorization Code + PKCE (public client, niente client secret)
     let (listener, redirect_uri) = bind_loopback_listener().await?;
@@ -709,6 +1027,7 @@ pub async fn start_google_device_flow() -> Result<OutlookDeviceFlowStart> {
         google.access_token = None;
         google.refresh_token = None;
         google.expires_at = None;
+        google.reconnect_required = false;
 
         (authorization_url, expires_at)
     };
@@ -752,6 +1071,21 @@ pub async fn start_google_device_flow() -> Result<OutlookDeviceFlowStart> {
     })
 }
 
+/// `invalid_grant` al refresh: l'utente ha revocato l'accesso o il refresh
+/// token è scaduto, quindi riprovare non serve
+fn is_invalid_grant(body: &str) -> bool {
+    serde_json::from_str::<TokenErrorResponse>(body)
+        .map(|error| error.error == "invalid_grant")
+        .unwrap_or(false)
+}
+
+fn clear_revoked_google_tokens(google: &mut GoogleCalendarIntegrationConfig) {
+    google.access_token = None;
+    google.refresh_token = None;
+    google.expires_at = None;
+    google.reconnect_required = true;
+}
+
 async fn refresh_google_token(google: &mut GoogleCalendarIntegrationConfig) -> Result<()> {
     let client_id = ensure_google_client_id(google)?;
     let refresh_token = google
@@ -773,9 +1107,10 @@ async fn refresh_google_token(google: &mut GoogleCalendarIntegrationConfig) -> R
         form.push(("client_secret", secret));
     }
 
-    let response = HTTP_CLIENT
+    let response = http_client::shared_client()
         .post(GOOGLE_TOKEN_ENDPOINT)
         .form(&form)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Richiesta refresh token Google fallita")?;
@@ -783,6 +1118,14 @@ async fn refresh_google_token(google: &mut GoogleCalendarIntegrationConfig) -> R
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
+        if is_invalid_grant(&body) {
+            clear_revoked_google_tokens(google);
+            // Chi chiama si ferma sull'errore senza salvare, quindi lo stato va salvato qui
+            let mut integrations = load_integrations()?;
+            clear_revoked_google_tokens(get_google_config_mut(&mut integrations));
+            store_integrations(&integrations)?;
+            return Err(anyhow!(GOOGLE_RECONNECT_MESSAGE));
+        }
         return Err(anyhow!("Refresh token Google fallito: stato {} - {}", status, body));
     }
 
@@ -873,9 +1216,10 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
                     form.push(("client_secret", secret));
                 }
 
-                let response = HTTP_CLIENT
+                let response = http_client::shared_client()
                     .post(GOOGLE_TOKEN_ENDPOINT)
                     .form(&form)
+                    .timeout(REQUEST_TIMEOUT)
                     .send()
                     .await
                     .context("Richiesta token Google (PKCE) fallita")?;
@@ -894,6 +1238,7 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         google.refresh_token = Some(refresh);
                     }
                     google.expires_at = Some(expires_at);
+                    google.reconnect_required = false;
                     google.enabled = true;
 
                     (
@@ -955,7 +1300,7 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
             } else {
                 let (client_id, client_secret) = ensure_google_client(google)?;
 
-                let response = HTTP_CLIENT
+                let response = http_client::shared_client()
                     .post(GOOGLE_TOKEN_ENDPOINT)
                     .form(&[
                         ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
@@ -963,6 +1308,7 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         ("client_secret", client_secret.as_str()),
                         ("device_code", pending.device_code.as_str()),
                     ])
+                    .timeout(REQUEST_TIMEOUT)
                     .send()
                     .await
                     .context("Richiesta polling token Google fallita")?;
@@ -995,6 +1341,7 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         google.refresh_token = Some(refresh);
                     }
                     google.expires_at = Some(expires_at);
+                    google.reconnect_required = false;
                     google.enabled = true;
 
                     (
@@ -1098,7 +1445,31 @@ pub async fn poll_google_device_flow() -> Result<OutlookDeviceFlowPoll> {
     Ok(result)
 }
 
-pub async fn list_google_events(limit: usize) -> Result<Vec<RemoteCalendarEvent>> {
+/// Inizio effettivo dell'intervallo: senza `start_date` si parte da
+/// `LIST_DEFAULT_PAST_HOURS` ore fa. Una fine che non lo segue viene rifiutata
+/// qui invece che dal provider con un 400
+fn list_range_start(
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+) -> Result<DateTime<Utc>> {
+    let start = start_date.unwrap_or_else(|| Utc::now() - Duration::hours(LIST_DEFAULT_PAST_HOURS));
+    match end_date {
+        Some(end) if end <= start => Err(anyhow!(
+            "La data di fine deve essere successiva all'inizio dell'intervallo ({})",
+            start.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+        )),
+        _ => Ok(start),
+    }
+}
+
+/// Eventi del calendario Google scelto; senza `start_date` parte da 12 ore fa,
+/// senza `end_date` non ha limite superiore
+pub async fn list_google_events(
+    limit: usize,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+) -> Result<Vec<RemoteCalendarEvent>> {
+    let start = list_range_start(start_date, end_date)?;
     let mut integrations = load_integrations()?;
     let (token, calendar_id) = {
         let google = get_google_config_mut(&mut integrations);
@@ -1119,21 +1490,27 @@ pub async fn list_google_events(limit: usize) -> Result<Vec<RemoteCalendarEvent>
 
     store_integrations(&integrations)?;
 
-    let time_min = (Utc::now() - Duration::hours(12)).to_rfc3339();
+    let time_min = start.to_rfc3339();
     let max_results = limit.max(1).min(50);
 
-    let response = HTTP_CLIENT
+    let mut query = vec![
+        ("maxResults", max_results.to_string()),
+        ("singleEvents", "true".to_string()),
+        ("orderBy", "startTime".to_string()),
+        ("timeMin", time_min),
+    ];
+    if let Some(end_date) = end_date {
+        query.push(("timeMax", end_date.to_rfc3339()));
+    }
+
+    let response = http_client::shared_client()
         .get(format!(
             "{GOOGLE_CALENDAR_API}/calendars/{}/events",
             urlencoding::encode(&calendar_id)
         ))
-        .query(&[
-            ("maxResults", max_results.to_string()),
-            ("singleEvents", "true".to_string()),
-            ("orderBy", "startTime".to_string()),
-            ("timeMin", time_min),
-        ])
+        .query(&query)
         .header(AUTHORIZATION, format!("Bearer {}", token))
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Richiesta eventi Google Calendar fallita")?;
@@ -1250,6 +1627,8 @@ pub async fn create_google_event(request: CreateRemoteEventRequest) -> Result<Re
         end: GoogleDateTime<'a>,
         #[serde(skip_serializing_if = "Option::is_none")]
         location: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        recurrence: Option<Vec<String>>,
     }
 
     #[derive(Serialize)]
@@ -1260,21 +1639,28 @@ pub async fn create_google_event(request: CreateRemoteEventRequest) -> Result<Re
         time_zone: &'a str,
     }
 
+    // The times keep their offset; the zone is the one the recurrence is expanded in
+    let time_zone =
+        local_storage::local_time_zone().unwrap_or_else(|| DEFAULT_TIME_ZONE.to_string());
     let body = GoogleEventBody {
         summary: request.subject.as_str(),
         description: request.body.as_deref(),
         start: GoogleDateTime {
             date_time: request.start.as_str(),
-            time_zone: DEFAULT_TIME_ZONE,
+            time_zone: &time_zone,
         },
         end: GoogleDateTime {
             date_time: request.end.as_str(),
-            time_zone: DEFAULT_TIME_ZONE,
+            time_zone: &time_zone,
         },
         location: request.location.as_deref(),
+        recurrence: request
+            .recurrence
+            .as_ref()
+            .map(|rule| vec![format!("RRULE:{}", rule.to_rrule())]),
     };
 
-    let response = HTTP_CLIENT
+    let response = http_client::shared_client()
         .post(format!(
             "{GOOGLE_CALENDAR_API}/calendars/{}/events",
             urlencoding::encode(&calendar_id)
@@ -1282,6 +1668,7 @@ pub async fn create_google_event(request: CreateRemoteEventRequest) -> Result<Re
         .header(AUTHORIZATION, format!("Bearer {}", token))
         .header(CONTENT_TYPE, "application/json")
         .json(&body)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Creazione evento Google Calendar fallita")?;
@@ -1410,6 +1797,7 @@ pub async fn start_outlook_device_flow() -> Result<OutlookDeviceFlowStart> {
         outlook.access_token = None;
         outlook.refresh_token = None;
         outlook.expires_at = None;
+        outlook.reconnect_required = false;
 
         (authorization_url, expires_at)
     };
@@ -1453,6 +1841,13 @@ pub async fn start_outlook_device_flow() -> Result<OutlookDeviceFlowStart> {
     })
 }
 
+fn clear_revoked_outlook_tokens(outlook: &mut OutlookIntegrationConfig) {
+    outlook.access_token = None;
+    outlook.refresh_token = None;
+    outlook.expires_at = None;
+    outlook.reconnect_required = true;
+}
+
 async fn refresh_outlook_token(outlook: &mut OutlookIntegrationConfig) -> Result<()> {
     let (client_id, tenant) = ensure_client_and_tenant(outlook)?;
     let refresh_token = outlook
@@ -1460,7 +1855,7 @@ async fn refresh_outlook_token(outlook: &mut OutlookIntegrationConfig) -> Result
         .as_ref()
         .ok_or_else(|| anyhow!("Refresh token mancante per Outlook"))?;
 
-    let response = HTTP_CLIENT
+    let response = http_client::shared_client()
         .post(format!(
             "https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token"
         ))
@@ -1470,6 +1865,7 @@ async fn refresh_outlook_token(outlook: &mut OutlookIntegrationConfig) -> Result
             ("refresh_token", refresh_token.as_str()),
             ("scope", GRAPH_SCOPE),
         ])
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Richiesta refresh token Outlook fallita")?;
@@ -1477,6 +1873,14 @@ async fn refresh_outlook_token(outlook: &mut OutlookIntegrationConfig) -> Result
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
+        if is_invalid_grant(&body) {
+            clear_revoked_outlook_tokens(outlook);
+            // Chi chiama si ferma sull'errore senza salvare, quindi lo stato va salvato qui
+            let mut integrations = load_integrations()?;
+            clear_revoked_outlook_tokens(get_outlook_config_mut(&mut integrations));
+            store_integrations(&integrations)?;
+            return Err(anyhow!(OUTLOOK_RECONNECT_MESSAGE));
+        }
         return Err(anyhow!("Refresh token Outlook fallito: stato {} - {}", status, body));
     }
 
@@ -1546,7 +1950,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                     outlook.scopes.join(" ")
                 };
 
-                let response = HTTP_CLIENT
+                let response = http_client::shared_client()
                     .post(format!(
                         "https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token"
                     ))
@@ -1558,6 +1962,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         ("code_verifier", pending.code_verifier.as_str()),
                         ("scope", scope.as_str()),
                     ])
+                    .timeout(REQUEST_TIMEOUT)
                     .send()
                     .await
                     .context("Richiesta token Outlook (PKCE) fallita")?;
@@ -1576,6 +1981,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         outlook.refresh_token = Some(refresh);
                     }
                     outlook.expires_at = Some(expires_at);
+                    outlook.reconnect_required = false;
                     outlook.enabled = true;
 
                     (
@@ -1650,7 +2056,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                 )
             } else {
                 let (client_id, tenant) = ensure_client_and_tenant(outlook)?;
-                let response = HTTP_CLIENT
+                let response = http_client::shared_client()
                     .post(format!(
                         "https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token"
                     ))
@@ -1659,6 +2065,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         ("client_id", client_id.as_str()),
                         ("device_code", pending.device_code.as_str()),
                     ])
+                    .timeout(REQUEST_TIMEOUT)
                     .send()
                     .await
                     .context("Richiesta polling token Outlook fallita")?;
@@ -1677,6 +2084,7 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
                         outlook.refresh_token = Some(refresh);
                     }
                     outlook.expires_at = Some(expires_at);
+                    outlook.reconnect_required = false;
                     outlook.enabled = true;
 
                     (
@@ -1780,9 +2188,123 @@ pub async fn poll_outlook_device_flow() -> Result<OutlookDeviceFlowPoll> {
     Ok(result)
 }
 
-pub async fn list_outlook_events(limit: usize) -> Result<Vec<RemoteCalendarEvent>> {
+/// Percorso Graph degli eventi del calendario scelto, o di quello predefinito
+fn graph_events_url(outlook: &OutlookIntegrationConfig) -> String {
+    graph_calendar_url(outlook, "events")
+}
+
+/// `calendarView` del calendario scelto: a differenza di `events` espande le
+/// serie ricorrenti nelle singole occorrenze
+fn graph_calendar_view_url(outlook: &OutlookIntegrationConfig) -> String {
+    graph_calendar_url(outlook, "calendarView")
+}
+
+fn graph_calendar_url(outlook: &OutlookIntegrationConfig, collection: &str) -> String {
+    match sanitize_optional_string(&outlook.calendar_id) {
+        Some(id) => format!(
+            "{GRAPH_ENDPOINT}/me/calendars/{}/{collection}",
+            urlencoding::encode(&id)
+        ),
+        None => format!("{GRAPH_ENDPOINT}/me/{collection}"),
+    }
+}
+
+async fn list_outlook_calendars() -> Result<Vec<RemoteCalendar>> {
     let mut integrations = load_integrations()?;
-    let token = {
+    let (token, selected_id) = {
+        let outlook = get_outlook_config_mut(&mut integrations);
+        if !outlook.enabled || outlook.access_token.is_none() {
+            return Err(anyhow!("Outlook non è collegato"));
+        }
+        ensure_scopes(outlook);
+        let token = ensure_outlook_access_token(outlook).await?;
+        (token, sanitize_optional_string(&outlook.calendar_id))
+    };
+
+    store_integrations(&integrations)?;
+
+    let response = http_client::shared_client()
+        .get(format!("{GRAPH_ENDPOINT}/me/calendars"))
+        .query(&[("$select", "id,name,isDefaultCalendar,canEdit")])
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("Richiesta calendari Outlook fallita")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Impossibile recuperare i calendari Outlook (stato {}): {}",
+            status,
+            body
+        ));
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphCalendarsResponse {
+        #[serde(default)]
+        value: Vec<GraphCalendar>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct GraphCalendar {
+        id: String,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        is_default_calendar: bool,
+        #[serde(default)]
+        can_edit: bool,
+    }
+
+    let list: GraphCalendarsResponse = response
+        .json()
+        .await
+        .context("Impossibile decodificare i calendari Outlook")?;
+
+    let calendars = list
+        .value
+        .into_iter()
+        .map(|calendar| {
+            let selected = match selected_id.as_deref() {
+                Some(id) => calendar.id == id,
+                None => calendar.is_default_calendar,
+            };
+            RemoteCalendar {
+                name: sanitize_optional_string(&calendar.name)
+                    .unwrap_or_else(|| calendar.id.clone()),
+                primary: calendar.is_default_calendar,
+                can_edit: calendar.can_edit,
+                selected,
+                id: calendar.id,
+            }
+        })
+        .collect();
+
+    Ok(calendars)
+}
+
+/// Intervallo richiesto da `calendarView`, che vuole sempre entrambi gli estremi:
+/// senza `end_date` copre `OUTLOOK_VIEW_DEFAULT_DAYS` giorni
+fn graph_view_range(start: DateTime<Utc>, end_date: Option<DateTime<Utc>>) -> (String, String) {
+    let end = end_date.unwrap_or_else(|| start + Duration::days(OUTLOOK_VIEW_DEFAULT_DAYS));
+    let format = |date: DateTime<Utc>| date.to_rfc3339_opts(SecondsFormat::Secs, true);
+    (format(start), format(end))
+}
+
+/// Occorrenze del calendario Outlook scelto tra `start_date` ed `end_date`,
+/// comprese quelle degli eventi ricorrenti
+pub async fn list_outlook_events(
+    limit: usize,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+) -> Result<Vec<RemoteCalendarEvent>> {
+    let start = list_range_start(start_date, end_date)?;
+    let mut integrations = load_integrations()?;
+    let (token, view_url) = {
         let outlook = get_outlook_config_mut(&mut integrations);
         if !outlook.enabled {
             return Err(anyhow!("Outlook non è abilitato"));
@@ -1791,24 +2313,31 @@ pub async fn list_outlook_events(limit: usize) -> Result<Vec<RemoteCalendarEvent
             return Err(anyhow!("Outlook non è collegato"));
         }
         ensure_scopes(outlook);
-        ensure_outlook_access_token(outlook).await?
+        let token = ensure_outlook_access_token(outlook).await?;
+        (token, graph_calendar_view_url(outlook))
     };
 
     store_integrations(&integrations)?;
 
     let max_results = limit.max(1).min(50);
+    let (start, end) = graph_view_range(start, end_date);
 
-    let response = HTTP_CLIENT
-        .get(format!("{GRAPH_ENDPOINT}/me/events"))
-        .query(&[
-            ("$top", max_results.to_string()),
-            ("$orderby", "start/dateTime".to_string()),
-            (
-                "$select",
-                "id,subject,bodyPreview,start,end,location,webLink".to_string(),
-            ),
-        ])
+    let query = vec![
+        ("startDateTime", start),
+        ("endDateTime", end),
+        ("$top", max_results.to_string()),
+        ("$orderby", "start/dateTime".to_string()),
+        (
+            "$select",
+            "id,subject,bodyPreview,start,end,location,webLink".to_string(),
+        ),
+    ];
+
+    let response = http_client::shared_client()
+        .get(view_url)
+        .query(&query)
         .header(AUTHORIZATION, format!("Bearer {}", token))
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Richiesta eventi Outlook fallita")?;
@@ -1903,7 +2432,7 @@ pub async fn list_outlook_events(limit: usize) -> Result<Vec<RemoteCalendarEvent
 
 pub async fn create_outlook_event(request: CreateRemoteEventRequest) -> Result<RemoteCalendarEvent> {
     let mut integrations = load_integrations()?;
-    let token = {
+    let (token, events_url) = {
         let outlook = get_outlook_config_mut(&mut integrations);
         if !outlook.enabled {
             return Err(anyhow!("Outlook non è abilitato"));
@@ -1912,7 +2441,8 @@ pub async fn create_outlook_event(request: CreateRemoteEventRequest) -> Result<R
             return Err(anyhow!("Outlook non è collegato"));
         }
         ensure_scopes(outlook);
-        ensure_outlook_access_token(outlook).await?
+        let token = ensure_outlook_access_token(outlook).await?;
+        (token, graph_events_url(outlook))
     };
 
     store_integrations(&integrations)?;
@@ -1947,17 +2477,21 @@ pub async fn create_outlook_event(request: CreateRemoteEventRequest) -> Result<R
         body: Option<GraphBody<'a>>,
         #[serde(skip_serializing_if = "Option::is_none")]
         location: Option<GraphLocationBody<'a>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        recurrence: Option<serde_json::Value>,
     }
 
+    let (start, start_zone) = graph_date_time(&request.start);
+    let (end, end_zone) = graph_date_time(&request.end);
     let body = GraphCreateEvent {
         subject: request.subject.as_str(),
         start: GraphDateTime {
-            date_time: request.start.as_str(),
-            time_zone: DEFAULT_TIME_ZONE,
+            date_time: &start,
+            time_zone: &start_zone,
         },
         end: GraphDateTime {
-            date_time: request.end.as_str(),
-            time_zone: DEFAULT_TIME_ZONE,
+            date_time: &end,
+            time_zone: &end_zone,
         },
         body: request
             .body
@@ -1967,13 +2501,21 @@ pub async fn create_outlook_event(request: CreateRemoteEventRequest) -> Result<R
             .location
             .as_deref()
             .map(|name| GraphLocationBody { display_name: name }),
+        recurrence: request
+            .recurrence
+            .as_ref()
+            .map(|rule| graph_recurrence(rule, &request.start))
+            .transpose()?,
     };
 
-    let response = HTTP_CLIENT
-        .post(format!("{GRAPH_ENDPOINT}/me/events"))
+    let response = http_client::shared_client()
+        .post(events_url)
         .header(AUTHORIZATION, format!("Bearer {}", token))
         .header(CONTENT_TYPE, "application/json")
+        // The created event comes back in UTC, like the listed ones
+        .header("Prefer", "outlook.timezone=\"UTC\"")
         .json(&body)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
         .context("Creazione evento Outlook fallita")?;
@@ -2054,7 +2596,69 @@ pub async fn is_outlook_connected() -> Result<bool> {
     Ok(outlook.enabled && outlook.access_token.is_some())
 }
 
-pub async fn push_local_event_to_outlook(event: &CalendarEvent) -> Result<()> {
+/// `dateTime` and `timeZone` for Graph: the wall-clock time in the machine's
+/// zone, the one Outlook expands the recurrence in. Unchanged and in UTC when
+/// the zone is unknown
+fn graph_date_time(value: &str) -> (String, String) {
+    match (
+        DateTime::parse_from_rfc3339(value),
+        local_storage::local_time_zone(),
+    ) {
+        (Ok(time), Some(zone)) => (
+            time.with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S")
+                .to_string(),
+            zone,
+        ),
+        _ => (value.to_string(), DEFAULT_TIME_ZONE.to_string()),
+    }
+}
+
+/// Microsoft Graph `patternedRecurrence` for a rule. Day of the week and of the
+/// month come from the local start, like the local expansion. Outlook moves
+/// the 29th-31st to the last day of shorter months: Graph has no pattern that
+/// skips them as RRULE does
+fn graph_recurrence(rule: &RecurrenceRule, start: &str) -> Result<serde_json::Value> {
+    let start = DateTime::parse_from_rfc3339(start)
+        .context("Data di inizio non valida")?
+        .with_timezone(&Local);
+    let interval = rule.interval.max(1);
+
+    let pattern = match rule.frequency {
+        RecurrenceFrequency::Daily => serde_json::json!({ "type": "daily", "interval": interval }),
+        RecurrenceFrequency::Weekly => serde_json::json!({
+            "type": "weekly",
+            "interval": interval,
+            "daysOfWeek": [start.format("%A").to_string().to_lowercase()],
+        }),
+        RecurrenceFrequency::Monthly => serde_json::json!({
+            "type": "absoluteMonthly",
+            "interval": interval,
+            "dayOfMonth": start.day(),
+        }),
+    };
+
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let range = match (rule.until, rule.count) {
+        (Some(until), _) => serde_json::json!({
+            "type": "endDate",
+            "startDate": start_date,
+            "endDate": until.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+        }),
+        (None, Some(count)) => serde_json::json!({
+            "type": "numbered",
+            "startDate": start_date,
+            "numberOfOccurrences": count,
+        }),
+        (None, None) => serde_json::json!({ "type": "noEnd", "startDate": start_date }),
+    };
+
+    Ok(serde_json::json!({ "pattern": pattern, "range": range }))
+}
+
+/// Crea l'evento locale sul calendario remoto e restituisce quello creato,
+/// con il link web
+pub async fn push_local_event_to_outlook(event: &CalendarEvent) -> Result<RemoteCalendarEvent> {
     let subject = event.title.clone();
     let start = event.start.to_rfc3339();
     let end = event
@@ -2073,14 +2677,16 @@ pub async fn push_local_event_to_outlook(event: &CalendarEvent) -> Result<()> {
         start,
         end,
         body: Some(description),
-        location: None,
+        location: event.location.clone(),
+        recurrence: event.recurrence.clone(),
     };
 
-    let _ = create_outlook_event(request).await?;
-    Ok(())
+    create_outlook_event(request).await
 }
 
-pub async fn push_local_event_to_google(event: &CalendarEvent) -> Result<()> {
+/// Crea l'evento locale sul calendario remoto e restituisce quello creato,
+/// con il link web
+pub async fn push_local_event_to_google(event: &CalendarEvent) -> Result<RemoteCalendarEvent> {
     let subject = event.title.clone();
     let start = event.start.to_rfc3339();
     let end = event
@@ -2099,11 +2705,11 @@ pub async fn push_local_event_to_google(event: &CalendarEvent) -> Result<()> {
         start,
         end,
         body: Some(description),
-        location: None,
+        location: event.location.clone(),
+        recurrence: event.recurrence.clone(),
     };
 
-    let _ = create_google_event(request).await?;
-    Ok(())
+    create_google_event(request).await
 }
 
 #[allow(dead_code)]
@@ -2117,9 +2723,10 @@ pub async fn start_google_legacy_device_flow() -> Result<OutlookDeviceFlowStart>
         let (client_id, client_secret) = ensure_google_client(google)?;
         let scope = google.scopes.join(" ");
 
-        let response = HTTP_CLIENT
+        let response = http_client::shared_client()
             .post(GOOGLE_DEVICE_CODE_ENDPOINT)
             .form(&[("client_id", client_id.as_str()), ("scope", scope.as_str())])
+            .timeout(REQUEST_TIMEOUT)
             .send()
             .await
             .context("Richiesta device code Google fallita")?;
@@ -0,0 +1,531 @@
+// Conversation HTML Module
+// Exports a saved conversation as a standalone, read-only HTML page to share with
+// people who do not use MatePro: bubbles, rendered markdown and highlighted code,
+// with the CSS inlined and no scripts
+
+use crate::local_storage::{self, ConversationEntry, MemoryMessage};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use pulldown_cmark::{
+    CodeBlockKind, CowStr, Event as MarkdownEvent, Options as MarkdownOptions,
+    Parser as MarkdownParser, Tag as MarkdownTag, TagEnd,
+};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::path::Path;
+
+/// Styles of the chat bubbles, following the app's light and dark themes
+const STYLE: &str = r#"
+:root {
+    --bg-primary: #ffffff;
+    --bg-tertiary: #e5e5ea;
+    --text-primary: #1d1d1f;
+    --text-secondary: #86868b;
+    --accent: #007aff;
+    --user-bubble: #007aff;
+    --assistant-bubble: #e5e5ea;
+    --code-bg: #f5f5f7;
+    --border: #d2d2d7;
+    --hl-keyword: #ad3da4;
+    --hl-string: #d12f1b;
+    --hl-number: #272ad8;
+    --hl-comment: #707f8c;
+}
+@media (prefers-color-scheme: dark) {
+    :root {
+        --bg-primary: #1c1c1e;
+        --bg-tertiary: #3a3a3c;
+        --text-primary: #ffffff;
+        --text-secondary: #8e8e93;
+        --assistant-bubble: #3a3a3c;
+        --code-bg: #2c2c2e;
+        --border: #48484a;
+        --hl-keyword: #ff7ab2;
+        --hl-string: #ff8170;
+        --hl-number: #d9c97c;
+        --hl-comment: #7f8c98;
+    }
+}
+* { box-sizing: border-box; }
+body {
+    margin: 0;
+    font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+    background: var(--bg-primary);
+    color: var(--text-primary);
+}
+header {
+    max-width: 900px;
+    margin: 0 auto;
+    padding: 1.5rem 1rem 0.5rem;
+    border-bottom: 1px solid var(--border);
+}
+header h1 { font-size: 1.4rem; margin: 0 0 0.25rem; }
+header p { margin: 0; font-size: 0.8rem; color: var(--text-secondary); }
+main {
+    max-width: 900px;
+    margin: 0 auto;
+    padding: 1rem;
+    display: flex;
+    flex-direction: column;
+    gap: 1rem;
+}
+.message { display: flex; flex-direction: column; max-width: 80%; }
+.message.user { align-self: flex-end; }
+.message.assistant, .message.system { align-self: flex-start; }
+.message-bubble {
+    padding: 0.75rem 1rem;
+    border-radius: 18px;
+    line-height: 1.5;
+    word-wrap: break-word;
+    overflow-x: auto;
+}
+.message.user .message-bubble { background: var(--user-bubble); color: white; }
+.message.assistant .message-bubble,
+.message.system .message-bubble { background: var(--assistant-bubble); color: var(--text-primary); }
+.message-timestamp {
+    font-size: 0.7rem;
+    color: var(--text-secondary);
+    margin-top: 0.25rem;
+    padding: 0 0.5rem;
+}
+.message.user .message-timestamp { text-align: right; }
+.message-bubble p { margin: 0.5rem 0; }
+.message-bubble p:first-child { margin-top: 0; }
+.message-bubble p:last-child { margin-bottom: 0; }
+.message-bubble pre {
+    background: var(--code-bg);
+    color: var(--text-primary);
+    padding: 0.75rem;
+    border-radius: 8px;
+    overflow-x: auto;
+    margin: 0.5rem 0;
+}
+.message-bubble code { font-family: 'SF Mono', Monaco, 'Courier New', monospace; font-size: 0.9em; }
+.message-bubble ul, .message-bubble ol { margin: 0.5rem 0 0.5rem 1.5rem; padding-left: 0.75rem; }
+.message-bubble blockquote {
+    margin: 0.5rem 0;
+    padding-left: 0.75rem;
+    border-left: 3px solid var(--accent);
+    color: var(--text-secondary);
+}
+.message-bubble a { color: inherit; text-decoration: underline; }
+.message-bubble table { border-collapse: collapse; margin: 0.5rem 0; }
+.message-bubble th, .message-bubble td { border: 1px solid var(--border); padding: 0.3rem 0.6rem; }
+.message-bubble th { background: var(--code-bg); }
+.message-bubble details.think-block {
+    margin-bottom: 0.75rem;
+    padding: 0.5rem 0.75rem;
+    border: 1px dashed var(--border);
+    border-radius: 12px;
+}
+.message-bubble details.think-block summary { cursor: pointer; font-weight: 600; color: var(--text-secondary); }
+.message-bubble details.think-block .think-content { margin-top: 0.5rem; color: var(--text-secondary); }
+.hl-keyword { color: var(--hl-keyword); font-weight: 600; }
+.hl-string { color: var(--hl-string); }
+.hl-number { color: var(--hl-number); }
+.hl-comment { color: var(--hl-comment); font-style: italic; }
+footer { text-align: center; font-size: 0.7rem; color: var(--text-secondary); padding: 1rem; }
+"#;
+
+/// Keywords highlighted in code blocks, shared by the most common languages
+const KEYWORDS: &str =
+    "as async await break case catch class const continue def default do elif else \
+     enum except export extends false finally fn for from func function if impl \
+     import in interface lambda let match mod mut new None null pass pub raise \
+     return self static struct switch this throw trait true try type use var void \
+     while with yield False True";
+
+/// Keywords of SQL, matched regardless of case
+const SQL_KEYWORDS: &str =
+    "and as asc by case create delete desc distinct drop else end from group having \
+     in inner insert into is join left like limit not null on or order outer right \
+     select set table then top union update values when where with";
+
+/// Writes the conversation with the given id to `path` as a standalone HTML page
+pub fn export_conversation_html(id: &str, path: &Path) -> Result<()> {
+    let memory = local_storage::load_memory()?;
+    let conversation = memory
+        .conversations
+        .iter()
+        .find(|conversation| conversation.id == id)
+        .with_context(|| format!("Conversazione non trovata: {}", id))?;
+
+    fs::write(path, conversation_to_html(conversation))
+        .with_context(|| format!("Impossibile scrivere il file: {}", path.display()))
+}
+
+fn conversation_to_html(conversation: &ConversationEntry) -> String {
+    let mut subtitle = format!(
+        "{} · {} messaggi",
+        conversation
+            .created_at
+            .with_timezone(&Local)
+            .format("%d/%m/%Y %H:%M"),
+        conversation.messages.iter().filter(|m| !m.hidden).count()
+    );
+    if let Some(model) = &conversation.model {
+        subtitle.push_str(&format!(" · 🤖 {}", model));
+    }
+
+    let messages: String = conversation
+        .messages
+        .iter()
+        .filter(|message| !message.hidden)
+        .map(message_to_html)
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"it\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <header>\n<h1>{title}</h1>\n<p>{subtitle}</p>\n</header>\n\
+         <main>\n{messages}</main>\n<footer>Esportato da MatePro</footer>\n</body>\n</html>\n",
+        title = html_escape::encode_text(&conversation.title),
+        style = STYLE,
+        subtitle = html_escape::encode_text(&subtitle),
+        messages = messages,
+    )
+}
+
+fn message_to_html(message: &MemoryMessage) -> String {
+    let role = match message.role.as_str() {
+        "user" => "user",
+        "assistant" => "assistant",
+        _ => "system",
+    };
+
+    let mut body = String::new();
+    if role == "user" {
+        // Attached files are shared by name only
+        let content = local_storage::replace_attachments(&message.content);
+        let names: Vec<&str> = message
+            .attachments
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        if !names.is_empty() && !content.starts_with("[Allegati:") {
+            body.push_str(&format!(
+                "<p>📎 {}</p>\n",
+                html_escape::encode_text(&names.join(", "))
+            ));
+        }
+        body.push_str(&markdown_to_html(&content));
+    } else {
+        let (thinking, answer) = split_thinking(&message.content);
+        if let Some(thinking) = thinking {
+            body.push_str(&format!(
+                "<details class=\"think-block\"><summary>Ragionamento</summary>\
+                 <div class=\"think-content\">{}</div></details>\n",
+                markdown_to_html(&thinking)
+            ));
+        }
+        body.push_str(&markdown_to_html(&answer));
+    }
+
+    let timestamp = message
+        .timestamp
+        .as_deref()
+        .map(|timestamp| {
+            let timestamp = match DateTime::parse_from_rfc3339(timestamp) {
+                Ok(date) => date
+                    .with_timezone(&Local)
+                    .format("%d/%m/%Y %H:%M")
+                    .to_string(),
+                // Older entries only contain "HH:MM"
+                Err(_) => timestamp.to_string(),
+            };
+            format!(
+                "<div class=\"message-timestamp\">{}</div>\n",
+                html_escape::encode_text(&timestamp)
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        "<div class=\"message {}\">\n<div class=\"message-bubble\">\n{}</div>\n{}</div>\n",
+        role, body, timestamp
+    )
+}
+
+/// Reasoning of "thinking" models, kept apart as in the chat: (reasoning, answer)
+fn split_thinking(content: &str) -> (Option<String>, String) {
+    let mut thinking = Vec::new();
+    let mut answer = String::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("<think>") {
+        answer.push_str(&rest[..start]);
+        let after = &rest[start + "<think>".len()..];
+        match after.find("</think>") {
+            Some(end) => {
+                thinking.push(after[..end].trim().to_string());
+                rest = &after[end + "</think>".len()..];
+            }
+            // Unterminated block: the reply was cut while the model was still reasoning
+            None => {
+                thinking.push(after.trim().to_string());
+                rest = "";
+            }
+        }
+    }
+    answer.push_str(rest);
+
+    thinking.retain(|block| !block.is_empty());
+    let thinking = (!thinking.is_empty()).then(|| thinking.join("\n\n"));
+    (thinking, answer.trim().to_string())
+}
+
+/// Markdown rendered as HTML. Raw HTML written by the model is shown as text and
+/// script links are dropped, since the page is opened by someone else
+fn markdown_to_html(markdown: &str) -> String {
+    let options = MarkdownOptions::ENABLE_TABLES
+        | MarkdownOptions::ENABLE_STRIKETHROUGH
+        | MarkdownOptions::ENABLE_TASKLISTS;
+
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+    for event in MarkdownParser::new_ext(markdown, options) {
+        match event {
+            MarkdownEvent::Start(MarkdownTag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((language, String::new()));
+            }
+            MarkdownEvent::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            MarkdownEvent::End(TagEnd::CodeBlock) => {
+                let (language, code) = code_block.take().unwrap_or_default();
+                events.push(MarkdownEvent::Html(CowStr::from(code_block_html(
+                    &language, &code,
+                ))));
+            }
+            MarkdownEvent::Html(html) | MarkdownEvent::InlineHtml(html) => {
+                events.push(MarkdownEvent::Text(html));
+            }
+            MarkdownEvent::Start(MarkdownTag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(MarkdownEvent::Start(MarkdownTag::Link {
+                link_type,
+                dest_url: safe_url(dest_url, false),
+                title,
+                id,
+            })),
+            MarkdownEvent::Start(MarkdownTag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(MarkdownEvent::Start(MarkdownTag::Image {
+                link_type,
+                dest_url: safe_url(dest_url, true),
+                title,
+                id,
+            })),
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// Keeps relative links and the http(s)/mailto schemes (plus inline images for
+/// `<img>`), anything else becomes `#`. Browsers ignore whitespace and control
+/// characters inside a URL, so they are dropped before reading the scheme:
+/// `java\tscript:` is still `javascript:`
+fn safe_url(url: CowStr<'_>, image: bool) -> CowStr<'_> {
+    let cleaned: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_lowercase();
+    let scheme = cleaned
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+
+    let allowed = match scheme {
+        None => true,
+        Some("http" | "https" | "mailto") => true,
+        Some("data") => image && cleaned.starts_with("data:image/"),
+        Some(_) => false,
+    };
+    if allowed {
+        url
+    } else {
+        CowStr::from("#")
+    }
+}
+
+fn code_block_html(language: &str, code: &str) -> String {
+    let class = if language.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " class=\"language-{}\"",
+            html_escape::encode_double_quoted_attribute(language)
+        )
+    };
+    format!(
+        "<pre><code{}>{}</code></pre>\n",
+        class,
+        highlight_code(language, code)
+    )
+}
+
+/// Code escaped for HTML, with comments, strings, numbers and keywords wrapped in
+/// `hl-*` spans. Without a language the code is only escaped
+fn highlight_code(language: &str, code: &str) -> String {
+    let Some(regex) = highlight_regex(language) else {
+        return html_escape::encode_text(code).to_string();
+    };
+
+    let mut html = String::new();
+    let mut last = 0;
+    for captures in regex.captures_iter(code) {
+        let Some((class, token)) = ["comment", "string", "number", "keyword"]
+            .iter()
+            .find_map(|name| captures.name(name).map(|token| (*name, token)))
+        else {
+            continue;
+        };
+        html.push_str(&html_escape::encode_text(&code[last..token.start()]));
+        html.push_str(&format!(
+            "<span class=\"hl-{}\">{}</span>",
+            class,
+            html_escape::encode_text(token.as_str())
+        ));
+        last = token.end();
+    }
+    html.push_str(&html_escape::encode_text(&code[last..]));
+    html
+}
+
+fn highlight_regex(language: &str) -> Option<Regex> {
+    let language = language.to_lowercase();
+    let comment = match language.as_str() {
+        "" | "text" | "txt" | "plain" | "plaintext" | "markdown" | "md" => return None,
+        "python" | "py" | "bash" | "sh" | "shell" | "zsh" | "yaml" | "yml" | "toml" | "ruby"
+        | "rb" | "r" | "powershell" | "ps1" | "dockerfile" => r"#[^\n]*",
+        "sql" | "tsql" | "mysql" | "postgresql" | "sqlite" | "lua" => r"--[^\n]*|/\*[\s\S]*?\*/",
+        "html" | "xml" | "svg" => r"<!--[\s\S]*?-->",
+        _ => r"//[^\n]*|/\*[\s\S]*?\*/",
+    };
+    let is_sql = comment.starts_with("--") && language != "lua";
+    let keywords = if is_sql { SQL_KEYWORDS } else { KEYWORDS };
+    // In Rust a single quote also starts lifetimes, only double quotes make strings
+    let single_quoted = if language == "rust" || language == "rs" {
+        ""
+    } else {
+        r"|'(?:[^'\\\n]|\\.)*'"
+    };
+
+    let pattern = format!(
+        r#"(?P<comment>{})|(?P<string>"(?:[^"\\\n]|\\.)*"{})|(?P<number>\b\d+(?:\.\d+)?\b)|(?P<keyword>\b(?:{})\b)"#,
+        comment,
+        single_quoted,
+        keywords.split_whitespace().collect::<Vec<_>>().join("|")
+    );
+    RegexBuilder::new(&pattern)
+        .case_insensitive(is_sql)
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn message(role: &str, content: &str, hidden: bool) -> MemoryMessage {
+        MemoryMessage {
+            hidden,
+            ..MemoryMessage::test(role, content)
+        }
+    }
+
+    #[test]
+    fn test_conversation_to_html() {
+        let conversation = ConversationEntry {
+            id: "conv".to_string(),
+            title: "Query <clienti>".to_string(),
+            messages: vec![
+                message("system", "ISTRUZIONI SEGRETE", true),
+                message("user", "Come conto i clienti?", false),
+                message(
+                    "assistant",
+                    "<think>Serve una COUNT</think>Usa:\n\n```sql\nSELECT COUNT(*) FROM clienti -- totale\n```\n\n<script>alert(1)</script>\n\n[link](javascript:alert(1))",
+                    false,
+                ),
+            ],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: Some("llama3".to_string()),
+        };
+
+        let html = conversation_to_html(&conversation);
+        assert!(!html.contains("ISTRUZIONI SEGRETE"));
+        assert!(html.contains("<title>Query &lt;clienti&gt;</title>"));
+        assert!(html.contains("<div class=\"message user\">"));
+        assert!(html.contains("<details class=\"think-block\"><summary>Ragionamento</summary>"));
+        assert!(html.contains("<span class=\"hl-keyword\">SELECT</span>"));
+        assert!(html.contains("<span class=\"hl-comment\">-- totale</span>"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("javascript:"));
+    }
+
+    #[test]
+    fn test_safe_url() {
+        let safe = |url: &str| safe_url(CowStr::from(url.to_string()), false).to_string();
+        assert_eq!(
+            safe("https://example.org/a?b=c"),
+            "https://example.org/a?b=c"
+        );
+        assert_eq!(safe("mailto:info@example.org"), "mailto:info@example.org");
+        assert_eq!(safe("docs/readme.md"), "docs/readme.md");
+        assert_eq!(safe("#sezione"), "#sezione");
+        assert_eq!(safe("/percorso?x=a:b"), "/percorso?x=a:b");
+        // Schemes split by characters the browser drops are still recognized
+        assert_eq!(safe("java\tscript:alert(1)"), "#");
+        assert_eq!(safe(" JaVa\nScRiPt:alert(1)"), "#");
+        assert_eq!(safe("java\u{0}script:alert(1)"), "#");
+        assert_eq!(safe("vbscript:msgbox"), "#");
+        assert_eq!(safe("file:///etc/passwd"), "#");
+        assert_eq!(safe("data:image/png;base64,AAAA"), "#");
+        assert_eq!(
+            safe_url(CowStr::from("data:image/png;base64,AAAA"), true).to_string(),
+            "data:image/png;base64,AAAA"
+        );
+        assert_eq!(
+            safe_url(CowStr::from("data:text/html,<b>"), true).to_string(),
+            "#"
+        );
+
+        let html = markdown_to_html("[x](<java\tscript:alert(1)>)");
+        assert!(html.contains("href=\"#\""), "{}", html);
+    }
+
+    #[test]
+    fn test_highlight_code() {
+        assert_eq!(
+            highlight_code("python", "x = \"a<b\"  # nota"),
+            "x = <span class=\"hl-string\">\"a&lt;b\"</span>  <span class=\"hl-comment\"># nota</span>"
+        );
+        assert_eq!(
+            highlight_code("rust", "fn f<'a>(s: &'a str) -> u8 { 1 }"),
+            "<span class=\"hl-keyword\">fn</span> f&lt;'a&gt;(s: &amp;'a str) -&gt; u8 { <span class=\"hl-number\">1</span> }"
+        );
+        assert_eq!(highlight_code("", "<b>"), "&lt;b&gt;");
+    }
+}
@@ -0,0 +1,250 @@
+// Dictation Module
+// Microphone capture (cpal) and local Whisper transcription. Built only with the
+// `voice` feature: cpal needs ALSA headers on Linux and whisper-rs builds
+// whisper.cpp with cmake and clang
+
+use crate::local_storage::{self, VoiceSettings};
+use crate::speech::whisper_language;
+use anyhow::{anyhow, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Sample rate expected by Whisper
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+/// Longer recordings are truncated, the rest of the audio is discarded
+const MAX_RECORDING_SECS: usize = 120;
+/// Recordings shorter than this are treated as empty
+const MIN_RECORDING_SECS: f32 = 0.3;
+const DEFAULT_MODEL_DIR: &str = "models";
+const DEFAULT_MODEL_FILE: &str = "ggml-base.bin";
+
+/// Microphone recording in progress. The cpal stream is not `Send`, so it lives on
+/// its own thread until `stop` is called.
+pub struct Recorder {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+}
+
+/// Mono audio captured from the microphone
+pub struct Recording {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Recording {
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+}
+
+impl Recorder {
+    /// Starts recording from the default input device
+    pub fn start() -> Result<Self> {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let buffer = Arc::clone(&samples);
+        let thread = std::thread::spawn(move || match open_input_stream(buffer) {
+            Ok((stream, sample_rate)) => {
+                let _ = ready_tx.send(Ok(sample_rate));
+                // Closing the sender (Recorder dropped) also stops the recording
+                let _ = stop_rx.recv();
+                drop(stream);
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+            }
+        });
+
+        let sample_rate = ready_rx
+            .recv()
+            .context("Il thread di registrazione è terminato inaspettatamente")??;
+
+        Ok(Self {
+            stop: stop_tx,
+            thread,
+            samples,
+            sample_rate,
+        })
+    }
+
+    /// Stops the microphone and returns the captured audio
+    pub fn stop(self) -> Recording {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+
+        let samples = std::mem::take(&mut *self.samples.lock().unwrap());
+        Recording {
+            samples,
+            sample_rate: self.sample_rate,
+        }
+    }
+}
+
+fn open_input_stream(buffer: Arc<Mutex<Vec<f32>>>) -> Result<(Stream, u32)> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .context("Nessun microfono disponibile")?;
+    let supported = device
+        .default_input_config()
+        .context("Impossibile leggere la configurazione del microfono")?;
+    let sample_format = supported.sample_format();
+    let config: StreamConfig = supported.into();
+    let sample_rate = config.sample_rate.0;
+    let max_samples = sample_rate as usize * MAX_RECORDING_SECS;
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_input_stream::<f32>(&device, &config, buffer, max_samples),
+        SampleFormat::I16 => build_input_stream::<i16>(&device, &config, buffer, max_samples),
+        SampleFormat::U16 => build_input_stream::<u16>(&device, &config, buffer, max_samples),
+        other => anyhow::bail!("Formato audio del microfono non supportato: {:?}", other),
+    }?;
+    stream
+        .play()
+        .context("Impossibile avviare la registrazione")?;
+
+    Ok((stream, sample_rate))
+}
+
+/// Input stream that mixes the channels down to mono f32 samples
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    buffer: Arc<Mutex<Vec<f32>>>,
+    max_samples: usize,
+) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut samples = buffer.lock().unwrap();
+                for frame in data.chunks(channels) {
+                    if samples.len() >= max_samples {
+                        return;
+                    }
+                    let sum: f32 = frame.iter().map(|&sample| f32::from_sample(sample)).sum();
+                    samples.push(sum / frame.len() as f32);
+                }
+            },
+            |err| tracing::warn!("Errore durante la registrazione audio: {}", err),
+            None,
+        )
+        .context("Impossibile aprire il microfono")
+}
+
+/// Whisper model used for dictation: the configured one or the default location
+pub fn model_path(settings: &VoiceSettings) -> Result<PathBuf> {
+    match settings
+        .whisper_model_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(PathBuf::from(local_storage::get_data_directory()?)
+            .join(DEFAULT_MODEL_DIR)
+            .join(DEFAULT_MODEL_FILE)),
+    }
+}
+
+/// Transcribes the recording with the Whisper model. Blocking: run it off the async runtime.
+pub fn transcribe(recording: &Recording, settings: &VoiceSettings) -> Result<String> {
+    if recording.duration_secs() < MIN_RECORDING_SECS {
+        anyhow::bail!("Nessun audio registrato");
+    }
+
+    let model = model_path(settings)?;
+    if !model.exists() {
+        anyhow::bail!(
+            "Modello Whisper non trovato in {}. Scarica un modello ggml (es. ggml-base.bin da https://huggingface.co/ggerganov/whisper.cpp) e indicane il percorso nelle impostazioni",
+            model.display()
+        );
+    }
+    let model = model.to_string_lossy().to_string();
+
+    let context = WhisperContext::new_with_params(&model, WhisperContextParameters::default())
+        .map_err(|e| anyhow!("Impossibile caricare il modello Whisper: {}", e))?;
+    let mut whisper = context
+        .create_state()
+        .map_err(|e| anyhow!("Impossibile inizializzare Whisper: {}", e))?;
+
+    let language = whisper_language(&settings.language);
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(&language));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+
+    let audio = resample(
+        &recording.samples,
+        recording.sample_rate,
+        WHISPER_SAMPLE_RATE,
+    );
+    whisper
+        .full(params, &audio)
+        .map_err(|e| anyhow!("Trascrizione fallita: {}", e))?;
+
+    let segments = whisper
+        .full_n_segments()
+        .map_err(|e| anyhow!("Trascrizione fallita: {}", e))?;
+    let mut text = String::new();
+    for segment in 0..segments {
+        let segment_text = whisper
+            .full_get_segment_text(segment)
+            .map_err(|e| anyhow!("Trascrizione fallita: {}", e))?;
+        text.push_str(&segment_text);
+    }
+
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Linear interpolation resampling, enough for speech recognition
+fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let output_len = (samples.len() as f64 / ratio).floor() as usize;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let current = samples[index];
+            let next = samples.get(index + 1).copied().unwrap_or(current);
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample() {
+        let samples: Vec<f32> = (0..48).map(|i| i as f32).collect();
+        let resampled = resample(&samples, 48_000, 16_000);
+        assert_eq!(resampled.len(), 16);
+        assert_eq!(resampled[1], 3.0);
+        assert_eq!(resample(&samples, 16_000, 16_000), samples);
+
+        let upsampled = resample(&[0.0, 1.0], 8_000, 16_000);
+        assert_eq!(upsampled, vec![0.0, 0.5, 1.0, 1.0]);
+    }
+}
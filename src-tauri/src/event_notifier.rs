@@ -0,0 +1,170 @@
+// Event Notifier Module
+// Shows a desktop notification when the reminder of a calendar event is reached.
+// Runs on its own thread, so reminders arrive also with the window minimized or hidden
+
+use crate::local_storage::{self, CalendarEvent};
+use chrono::{DateTime, Duration, Local, Utc};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// Event received by the UI for each reminder, with the event title as payload
+pub const EVENT_REMINDER_EVENT: &str = "calendar-reminder";
+/// How often the calendar is read: a reminder arrives at most this late
+const CHECK_INTERVAL_SECS: i64 = 30;
+/// Longest reminder honoured, so far occurrences are never expanded
+const MAX_REMINDER_MINUTES: i64 = 7 * 24 * 60;
+
+/// Reminder reached for an occurrence of an event
+#[derive(Debug, Clone, PartialEq)]
+struct DueReminder {
+    title: String,
+    start: DateTime<Utc>,
+    location: Option<String>,
+}
+
+/// Starts checking the calendar in the background for the whole life of the app
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        // Occurrences already notified, by key, with their start to forget them later
+        let mut notified = HashMap::new();
+
+        loop {
+            let settings = local_storage::load_app_settings()
+                .map(|settings| settings.event_notifications)
+                .unwrap_or_default();
+
+            if settings.enabled {
+                match local_storage::load_calendar_events() {
+                    Ok(events) => {
+                        let now = Utc::now();
+                        let due = due_reminders(
+                            &events,
+                            now,
+                            settings.default_reminder_minutes,
+                            &mut notified,
+                        );
+                        for reminder in due {
+                            notify(&app, &reminder, now);
+                        }
+                    }
+                    Err(e) => tracing::debug!("Promemoria non controllati: {}", e),
+                }
+            }
+
+            std::thread::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS as u64));
+        }
+    });
+}
+
+/// Occurrences whose reminder has been reached and that have not started
+/// yet (or just started), skipping those in `notified`
+fn due_reminders(
+    events: &[CalendarEvent],
+    now: DateTime<Utc>,
+    default_minutes: u32,
+    notified: &mut HashMap<String, DateTime<Utc>>,
+) -> Vec<DueReminder> {
+    notified.retain(|_, start| *start > now - Duration::days(1));
+
+    let mut due = Vec::new();
+    for event in events {
+        let minutes =
+            i64::from(event.reminder_minutes.unwrap_or(default_minutes)).min(MAX_REMINDER_MINUTES);
+        let from = now - Duration::seconds(CHECK_INTERVAL_SECS);
+        let to = now + Duration::minutes(minutes) + Duration::seconds(1);
+
+        for (start, _) in event.occurrences_between(from, to) {
+            let key = format!("{}@{}", event.id, start.timestamp());
+            if notified.insert(key, start).is_none() {
+                due.push(DueReminder {
+                    title: event.title.clone(),
+                    start,
+                    location: event.location.clone(),
+                });
+            }
+        }
+    }
+    due
+}
+
+fn reminder_body(reminder: &DueReminder, now: DateTime<Utc>) -> String {
+    let minutes = (reminder.start - now).num_minutes();
+    let when = match minutes {
+        m if m <= 0 => "Adesso".to_string(),
+        1 => "Tra 1 minuto".to_string(),
+        m if m < 120 => format!("Tra {} minuti", m),
+        m => format!("Tra {} ore", m / 60),
+    };
+
+    let mut body = format!(
+        "{} · {}",
+        when,
+        reminder.start.with_timezone(&Local).format("%H:%M")
+    );
+    if let Some(location) = reminder.location.as_deref().filter(|l| !l.is_empty()) {
+        body.push_str(&format!(" · 📍 {}", location));
+    }
+    body
+}
+
+fn notify(app: &AppHandle, reminder: &DueReminder, now: DateTime<Utc>) {
+    let shown = app
+        .notification()
+        .builder()
+        .title(format!("📅 {}", reminder.title))
+        .body(reminder_body(reminder, now))
+        .show();
+    if let Err(e) = shown {
+        tracing::warn!("Notifica del promemoria non mostrata: {}", e);
+    }
+    let _ = app.emit(EVENT_REMINDER_EVENT, &reminder.title);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, start: DateTime<Utc>, reminder_minutes: Option<u32>) -> CalendarEvent {
+        CalendarEvent {
+            id: id.to_string(),
+            title: format!("Evento {}", id),
+            description: None,
+            start,
+            end: None,
+            source_text: None,
+            location: None,
+            reminder_minutes,
+            recurrence: None,
+            created_at: start,
+            updated_at: start,
+        }
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let now: DateTime<Utc> = "2025-03-14T09:00:00Z".parse().unwrap();
+        let events = vec![
+            event("soon", now + Duration::minutes(10), None),
+            event("later", now + Duration::minutes(40), None),
+            event("own", now + Duration::minutes(40), Some(60)),
+            event("past", now - Duration::minutes(10), None),
+        ];
+        let mut notified = HashMap::new();
+
+        let due = due_reminders(&events, now, 15, &mut notified);
+        let titles: Vec<_> = due.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Evento soon", "Evento own"]);
+
+        // Each occurrence is notified once
+        assert!(due_reminders(&events, now, 15, &mut notified).is_empty());
+        let later = now + Duration::minutes(30);
+        let due = due_reminders(&events, later, 15, &mut notified);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].title, "Evento later");
+        assert_eq!(
+            reminder_body(&due[0], later).split(" · ").next(),
+            Some("Tra 10 minuti")
+        );
+    }
+}
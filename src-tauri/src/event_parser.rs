@@ -0,0 +1,603 @@
+// Event Parser Module
+// Recognizes appointments written in Italian ("pranzo con Marco venerdì alle 13")
+// and turns them into calendar events for the user to confirm
+
+use crate::local_storage::RecurrenceRule;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+    Utc,
+};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Event sent by the UI to be created or updated, dates in RFC 3339
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarEventInput {
+    pub id: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    pub start: String,
+    pub end: Option<String>,
+    pub source_text: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub reminder_minutes: Option<u32>,
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRule>,
+}
+
+/// Used when the text gives a start but no end or duration
+const DEFAULT_EVENT_DURATION_MINUTES: i64 = 60;
+const MAX_TITLE_CHARS: usize = 60;
+/// Title of the events whose text is only a date
+const DEFAULT_EVENT_TITLE: &str = "Impegno";
+
+const WEEKDAYS_IT: [&str; 7] = [
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+    "domenica",
+];
+const MONTHS_IT: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+
+lazy_static! {
+    static ref RELATIVE_DAY_RE: Regex = Regex::new(
+        r"(?i)\b(dopodomani|domani|oggi|stamattina|stamani|stasera|stanotte)\b"
+    )
+    .unwrap();
+    static ref IN_DAYS_RE: Regex = Regex::new(
+        r"(?i)\b(?:tra|fra)\s+(\d{1,3}|un|una|due|tre|quattro|cinque|sei|sette|otto|nove|dieci)\s+(giorn[oi]|settiman[ae])\b"
+    )
+    .unwrap();
+    static ref WEEKDAY_RE: Regex = Regex::new(
+        r"(?i)\b(?:(?:il|la)\s+)?(?:prossim[oa]\s+)?(luned[iì]|marted[iì]|mercoled[iì]|gioved[iì]|venerd[iì]|sabato|domenica)(?:\s+prossim[oa])?\b"
+    )
+    .unwrap();
+    static ref DAY_MONTH_RE: Regex = Regex::new(
+        r"(?i)\b(?:il\s+|l'|l’)?(\d{1,2})°?\s+(gennaio|febbraio|marzo|aprile|maggio|giugno|luglio|agosto|settembre|ottobre|novembre|dicembre)(?:\s+(\d{4}))?\b"
+    )
+    .unwrap();
+    static ref NUMERIC_DATE_RE: Regex =
+        Regex::new(r"\b(?:il\s+)?(\d{1,2})/(\d{1,2})(?:/(\d{2}|\d{4}))?\b").unwrap();
+    static ref TIME_RANGE_RE: Regex = Regex::new(
+        r"(?i)\b(?:dalle|dall['’])\s*(\d{1,2})(?:[:.](\d{2}))?\s+(?:alle|all['’]|a)\s*(\d{1,2})(?:[:.](\d{2}))?\b"
+    )
+    .unwrap();
+    static ref TIME_RE: Regex = Regex::new(
+        r"(?i)(?:\b(?:alle|verso\s+le|ore|h)\s*|\ball['’])(\d{1,2})(?:[:.](\d{2}))?\b"
+    )
+    .unwrap();
+    static ref NOON_RE: Regex = Regex::new(r"(?i)\b(?:a\s+)?mezzogiorno\b").unwrap();
+    static ref DURATION_RE: Regex = Regex::new(
+        r"(?i)\bper\s+(?:(\d{1,3})\s+(or[ae]|minuti)|(un['’]\s*ora|mezz['’]\s*ora))\b"
+    )
+    .unwrap();
+    static ref PERIOD_RE: Regex = Regex::new(
+        r"(?i)\b(?:(?:di|del(?:la)?|la|in|nel(?:la)?)\s+)?(mattin[ao]|pomeriggio|sera|serata|notte)\b"
+    )
+    .unwrap();
+    /// Part of the day written right after a time: "alle 3 del pomeriggio"
+    static ref PERIOD_AFTER_TIME_RE: Regex = Regex::new(
+        r"(?i)^\s*(?:(?:di|del(?:la)?|la|in|nel(?:la)?)\s+)?(pomeriggio|sera|serata|stasera|notte|stanotte)\b"
+    )
+    .unwrap();
+    /// Part of the day written right before a time: "domani sera alle 8"
+    static ref PERIOD_BEFORE_TIME_RE: Regex =
+        Regex::new(r"(?i)\b(pomeriggio|sera|serata|stasera|notte|stanotte)\s*$").unwrap();
+    static ref LEADING_FILLER_RE: Regex = Regex::new(
+        r"(?i)^(?:(?:ricordami|ricorda(?:mi)?\s+che|aggiungi(?:\s+al\s+calendario)?|segna(?:mi)?|fissa|ho|devo|avrò|avro|abbiamo|c['’]è)\s+(?:di\s+)?)+"
+    )
+    .unwrap();
+    static ref TRIM_PUNCTUATION_RE: Regex =
+        Regex::new(r"^[\s,;:.\-–!?]+|[\s,;:.\-–!?]+$").unwrap();
+    static ref SPACES_RE: Regex = Regex::new(r"\s{2,}").unwrap();
+    static ref SENTENCE_END_RE: Regex = Regex::new(r"[.!?…]+(?:\s+|$)|\n+").unwrap();
+    /// Words that make a date an appointment rather than, say, a weather question
+    static ref APPOINTMENT_CUE_RE: Regex = Regex::new(
+        r"(?i)\b(?:ci\s+(?:vediamo|sentiamo|troviamo)|vediamoci|sentiamoci|appuntamento|riunione|incontro|meeting|call|videochiamata|colloquio|visita|dentista|medico|pranzo|cena|aperitivo|colazione|festa|compleanno|lezione|esame|scadenza|consegna|partita|allenamento|gara|concerto|volo|treno|prenotat[oa]|devo|dovrò|ho\s+(?:un|una|il|la|lo)|ricordami|segna(?:mi)?|fissa(?:to|ta)?)\b"
+    )
+    .unwrap();
+    static ref QUESTION_RE: Regex = Regex::new(
+        r"(?i)^(?:che|cosa|come|quando|quanto|quanti|quale|quali|qual|perché|perche|dove|chi|sai|puoi)\b.*\?\s*$"
+    )
+    .unwrap();
+}
+
+/// Event recognized in a text, times are local
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedEvent {
+    pub title: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl ParsedEvent {
+    /// Prefilled input for `add_calendar_event`, with the text kept as `source_text`
+    pub fn to_input(&self, source_text: &str) -> CalendarEventInput {
+        CalendarEventInput {
+            id: None,
+            title: self.title.clone(),
+            description: None,
+            start: local_to_rfc3339(self.start),
+            end: Some(local_to_rfc3339(self.end)),
+            source_text: Some(source_text.trim().to_string()),
+            location: None,
+            reminder_minutes: None,
+            recurrence: None,
+        }
+    }
+}
+
+/// Event described by `text` relative to the current local time, if it contains a date or time
+pub fn parse_event_from_text(text: &str) -> Option<CalendarEventInput> {
+    parse_event(text, Local::now().naive_local()).map(|event| event.to_input(text))
+}
+
+/// First appointment mentioned in a chat message, for the "add to calendar" suggestion
+pub fn detect_event_in_message(text: &str) -> Option<CalendarEventInput> {
+    let sentence = appointment_sentence(text)?;
+    parse_event(sentence, Local::now().naive_local()).map(|event| event.to_input(sentence))
+}
+
+/// Sentence that names an appointment and a date or time. Questions such as
+/// "che tempo fa domani?" are left out: they mention a day but plan nothing.
+fn appointment_sentence(text: &str) -> Option<&str> {
+    let mut start = 0;
+    let mut sentences = Vec::new();
+    for end in SENTENCE_END_RE.find_iter(text) {
+        sentences.push(&text[start..end.end()]);
+        start = end.end();
+    }
+    sentences.push(&text[start..]);
+
+    sentences
+        .into_iter()
+        .map(str::trim)
+        .filter(|sentence| APPOINTMENT_CUE_RE.is_match(sentence))
+        .filter(|sentence| !QUESTION_RE.is_match(sentence))
+        .find(|sentence| has_date_or_time(sentence))
+}
+
+fn has_date_or_time(sentence: &str) -> bool {
+    [
+        &*RELATIVE_DAY_RE,
+        &*IN_DAYS_RE,
+        &*WEEKDAY_RE,
+        &*DAY_MONTH_RE,
+        &*NUMERIC_DATE_RE,
+        &*TIME_RE,
+        &*NOON_RE,
+    ]
+    .iter()
+    .any(|re| re.is_match(sentence))
+}
+
+/// Event described by `text` relative to `now`. Without a date a time refers
+/// to today, or tomorrow when already past; without a time the part of the
+/// day or the meal named in the text decides it.
+pub fn parse_event(text: &str, now: NaiveDateTime) -> Option<ParsedEvent> {
+    let text = text.trim();
+    let today = now.date();
+    // Spans of the recognized expressions, removed from the title
+    let mut spans: Vec<Range<usize>> = Vec::new();
+
+    let date = find_date(text, today, &mut spans);
+    let found_time = find_time(text, &mut spans);
+    if date.is_none() && found_time.is_none() {
+        return None;
+    }
+
+    let duration = DURATION_RE.captures(text).map(|caps| {
+        spans.push(whole(&caps));
+        duration_minutes(&caps)
+    });
+    for period in PERIOD_RE.find_iter(text) {
+        spans.push(period.range());
+    }
+
+    let lowered = text.to_lowercase();
+    let (time, explicit_end) = match found_time {
+        Some((time, end, range)) => {
+            let period = period_near(text, &range);
+            (
+                adjust_for_period(time, period.as_deref()),
+                end.map(|end| adjust_for_period(end, period.as_deref())),
+            )
+        }
+        None => (default_time(&lowered), None),
+    };
+
+    let start = match date {
+        Some(date) => date.and_time(time),
+        None => {
+            let start = today.and_time(time);
+            if start < now {
+                start + Duration::days(1)
+            } else {
+                start
+            }
+        }
+    };
+
+    let end = match (explicit_end, duration) {
+        (Some(end_time), _) => {
+            let end = start.date().and_time(end_time);
+            if end > start {
+                end
+            } else {
+                end + Duration::days(1)
+            }
+        }
+        (None, Some(minutes)) => start + Duration::minutes(minutes),
+        (None, None) => start + Duration::minutes(DEFAULT_EVENT_DURATION_MINUTES),
+    };
+
+    Some(ParsedEvent {
+        title: derive_title(text, &spans),
+        start,
+        end,
+    })
+}
+
+fn find_date(text: &str, today: NaiveDate, spans: &mut Vec<Range<usize>>) -> Option<NaiveDate> {
+    if let Some(caps) = RELATIVE_DAY_RE.captures(text) {
+        spans.push(whole(&caps));
+        let offset = match caps[1].to_lowercase().as_str() {
+            "dopodomani" => 2,
+            "domani" => 1,
+            _ => 0,
+        };
+        return Some(today + Duration::days(offset));
+    }
+
+    if let Some(caps) = IN_DAYS_RE.captures(text) {
+        let amount = number_word(&caps[1])?;
+        let days = if caps[2].to_lowercase().starts_with("settiman") {
+            amount * 7
+        } else {
+            amount
+        };
+        spans.push(whole(&caps));
+        return Some(today + Duration::days(days));
+    }
+
+    if let Some(caps) = DAY_MONTH_RE.captures(text) {
+        let month = MONTHS_IT
+            .iter()
+            .position(|m| *m == caps[2].to_lowercase())?;
+        let date = calendar_date(&caps[1], month as u32 + 1, caps.get(3), today)?;
+        spans.push(whole(&caps));
+        return Some(date);
+    }
+
+    if let Some(caps) = NUMERIC_DATE_RE.captures(text) {
+        let month = caps[2].parse().ok()?;
+        let date = calendar_date(&caps[1], month, caps.get(3), today)?;
+        spans.push(whole(&caps));
+        return Some(date);
+    }
+
+    if let Some(caps) = WEEKDAY_RE.captures(text) {
+        let name = caps[1].to_lowercase().replace('ì', "i");
+        let target = WEEKDAYS_IT
+            .iter()
+            .position(|w| w.replace('ì', "i") == name)? as i64;
+        // Always the next one: "venerdì" said on a friday means next week
+        let current = today.weekday().num_days_from_monday() as i64;
+        let ahead = (target - current + 6).rem_euclid(7) + 1;
+        spans.push(whole(&caps));
+        return Some(today + Duration::days(ahead));
+    }
+
+    None
+}
+
+/// Date from day, month and optional year; without a year a day already past
+/// means next year
+fn calendar_date(
+    day: &str,
+    month: u32,
+    year: Option<regex::Match>,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    let day = day.parse().ok()?;
+    match year {
+        Some(year) => {
+            let year: i32 = year.as_str().parse().ok()?;
+            let year = if year < 100 { 2000 + year } else { year };
+            NaiveDate::from_ymd_opt(year, month, day)
+        }
+        None => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+/// Start time, end time for ranges such as "dalle 10 alle 12" and the span
+/// of the time in the text
+fn find_time(
+    text: &str,
+    spans: &mut Vec<Range<usize>>,
+) -> Option<(NaiveTime, Option<NaiveTime>, Range<usize>)> {
+    if let Some(caps) = TIME_RANGE_RE.captures(text) {
+        let start = clock_time(caps.get(1), caps.get(2));
+        let end = clock_time(caps.get(3), caps.get(4));
+        if let (Some(start), Some(end)) = (start, end) {
+            spans.push(whole(&caps));
+            return Some((start, Some(end), whole(&caps)));
+        }
+    }
+
+    if let Some(caps) = TIME_RE.captures(text) {
+        if let Some(time) = clock_time(caps.get(1), caps.get(2)) {
+            spans.push(whole(&caps));
+            return Some((time, None, whole(&caps)));
+        }
+    }
+
+    if let Some(noon) = NOON_RE.find(text) {
+        spans.push(noon.range());
+        return Some((NaiveTime::from_hms_opt(12, 0, 0)?, None, noon.range()));
+    }
+
+    None
+}
+
+fn clock_time(hours: Option<regex::Match>, minutes: Option<regex::Match>) -> Option<NaiveTime> {
+    let hours = hours?.as_str().parse().ok()?;
+    let minutes = match minutes {
+        Some(minutes) => minutes.as_str().parse().ok()?,
+        None => 0,
+    };
+    NaiveTime::from_hms_opt(hours, minutes, 0)
+}
+
+/// Part of the day written next to the time at `range`, lowercase. A period
+/// elsewhere in the text ("alle 9, la sera cena") says nothing about the time
+fn period_near(text: &str, range: &Range<usize>) -> Option<String> {
+    PERIOD_AFTER_TIME_RE
+        .captures(&text[range.end..])
+        .or_else(|| PERIOD_BEFORE_TIME_RE.captures(&text[..range.start]))
+        .map(|caps| caps[1].to_lowercase())
+}
+
+/// "alle 3 del pomeriggio", "alle 8 di sera": afternoon and evening hours
+/// are written on a 12-hour clock. At night only the late hours move:
+/// "alle 11 di notte" is 23:00 but "alle 2 di notte" stays 2:00
+fn adjust_for_period(time: NaiveTime, period: Option<&str>) -> NaiveTime {
+    let hours = match period {
+        Some("pomeriggio" | "sera" | "serata" | "stasera") => 1..12,
+        Some("notte" | "stanotte") => 7..12,
+        _ => return time,
+    };
+    if hours.contains(&time.hour()) {
+        time + Duration::hours(12)
+    } else {
+        time
+    }
+}
+
+/// Start of an event without an explicit time
+fn default_time(lowered: &str) -> NaiveTime {
+    const HINTS: &[(&str, u32)] = &[
+        ("colazione", 8),
+        ("pranzo", 13),
+        ("aperitivo", 19),
+        ("cena", 20),
+        ("mattin", 9),
+        ("stamani", 9),
+        ("pomeriggio", 15),
+        ("sera", 19),
+        ("notte", 22),
+    ];
+    let hour = HINTS
+        .iter()
+        .find(|(word, _)| lowered.contains(word))
+        .map(|(_, hour)| *hour)
+        .unwrap_or(9);
+    NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or(NaiveTime::MIN)
+}
+
+fn duration_minutes(caps: &Captures) -> i64 {
+    match (caps.get(1), caps.get(2), caps.get(3)) {
+        (Some(amount), Some(unit), _) => {
+            let amount: i64 = amount.as_str().parse().unwrap_or(1);
+            if unit.as_str().to_lowercase().starts_with("or") {
+                amount * 60
+            } else {
+                amount
+            }
+        }
+        (_, _, Some(word)) if word.as_str().to_lowercase().starts_with("mezz") => 30,
+        _ => 60,
+    }
+}
+
+fn number_word(word: &str) -> Option<i64> {
+    if let Ok(number) = word.parse() {
+        return Some(number);
+    }
+    let number = match word.to_lowercase().as_str() {
+        "un" | "una" => 1,
+        "due" => 2,
+        "tre" => 3,
+        "quattro" => 4,
+        "cinque" => 5,
+        "sei" => 6,
+        "sette" => 7,
+        "otto" => 8,
+        "nove" => 9,
+        "dieci" => 10,
+        _ => return None,
+    };
+    Some(number)
+}
+
+/// What is left of the text once the date and time expressions are removed
+fn derive_title(text: &str, spans: &[Range<usize>]) -> String {
+    let mut title = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        if spans.iter().any(|span| span.contains(&index)) {
+            // Keeps the words around a removed expression apart
+            if !title.ends_with(' ') {
+                title.push(' ');
+            }
+        } else {
+            title.push(c);
+        }
+    }
+
+    let title = SPACES_RE.replace_all(&title, " ");
+    let title = TRIM_PUNCTUATION_RE.replace_all(&title, "");
+    let title = LEADING_FILLER_RE.replace(&title, "");
+    let title = TRIM_PUNCTUATION_RE.replace_all(&title, "").to_string();
+
+    let mut chars = title.chars();
+    let Some(first) = chars.next() else {
+        return DEFAULT_EVENT_TITLE.to_string();
+    };
+    let mut title: String = first.to_uppercase().chain(chars).collect();
+    if title.chars().count() > MAX_TITLE_CHARS {
+        title = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+        title = title.trim_end().to_string();
+        title.push('…');
+    }
+    title
+}
+
+fn whole(caps: &Captures) -> Range<usize> {
+    caps.get(0).map(|m| m.range()).unwrap_or(0..0)
+}
+
+/// Local wall-clock time as UTC RFC 3339, the format stored by the calendar
+fn local_to_rfc3339(datetime: NaiveDateTime) -> String {
+    let local = Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        // Skipped by a daylight saving change
+        .unwrap_or_else(|| Local.from_utc_datetime(&datetime));
+    DateTime::<Utc>::from(local).to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 15 October 2025, 10:00
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 10, 15)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    fn at(day: u32, month: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        let year = if month >= 10 { 2025 } else { 2026 };
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let event = parse_event("pranzo con Marco venerdì alle 13", now()).unwrap();
+        assert_eq!(event.title, "Pranzo con Marco");
+        assert_eq!(event.start, at(17, 10, 13, 0));
+        assert_eq!(event.end, at(17, 10, 14, 0));
+
+        let event = parse_event("Domani dalle 9:30 alle 11 riunione di progetto", now()).unwrap();
+        assert_eq!(event.title, "Riunione di progetto");
+        assert_eq!(event.start, at(16, 10, 9, 30));
+        assert_eq!(event.end, at(16, 10, 11, 0));
+
+        let event = parse_event("ricordami di chiamare Anna alle 3 del pomeriggio", now()).unwrap();
+        assert_eq!(event.title, "Chiamare Anna");
+        assert_eq!(event.start, at(15, 10, 15, 0));
+
+        // Only a period next to the time changes it
+        let event = parse_event("alle 2 di notte backup del server", now()).unwrap();
+        assert_eq!(event.start, at(16, 10, 2, 0));
+        let event = parse_event("alle 11 di notte backup del server", now()).unwrap();
+        assert_eq!(event.start, at(15, 10, 23, 0));
+        let event = parse_event("domani sera alle 8 cinema", now()).unwrap();
+        assert_eq!(event.start, at(16, 10, 20, 0));
+        let event = parse_event("domani riunione alle 9, la sera cena", now()).unwrap();
+        assert_eq!(event.start, at(16, 10, 9, 0));
+
+        let event = parse_event("cena da Luca il 2 gennaio per 3 ore", now()).unwrap();
+        assert_eq!(event.title, "Cena da Luca");
+        assert_eq!(event.start, at(2, 1, 20, 0));
+        assert_eq!(event.end, at(2, 1, 23, 0));
+
+        let event = parse_event("dentista tra due settimane alle 8.15", now()).unwrap();
+        assert_eq!(event.start, at(29, 10, 8, 15));
+
+        let event = parse_event("mercoledi prossimo palestra", now()).unwrap();
+        assert_eq!(event.title, "Palestra");
+        assert_eq!(event.start, at(22, 10, 9, 0));
+
+        let event = parse_event("consegna progetto 20/11", now()).unwrap();
+        assert_eq!(event.title, "Consegna progetto");
+        assert_eq!(event.start, at(20, 11, 9, 0));
+
+        // A time already past today moves to tomorrow
+        let event = parse_event("call alle 8", now()).unwrap();
+        assert_eq!(event.start, at(16, 10, 8, 0));
+
+        assert!(parse_event("come stai?", now()).is_none());
+        assert!(parse_event("ho 32 anni", now()).is_none());
+    }
+
+    #[test]
+    fn test_appointment_sentence() {
+        assert_eq!(
+            appointment_sentence("Perfetto, grazie! Ci vediamo martedì alle 10. A presto"),
+            Some("Ci vediamo martedì alle 10.")
+        );
+        assert_eq!(
+            appointment_sentence("Riunione alle 14.30 in sala grande"),
+            Some("Riunione alle 14.30 in sala grande")
+        );
+        assert!(appointment_sentence("Che tempo fa domani a Roma?").is_none());
+        assert!(appointment_sentence("Quando è la riunione di domani?").is_none());
+        assert!(appointment_sentence("Scrivi una mail per la riunione").is_none());
+        assert!(appointment_sentence("Domani è un altro giorno").is_none());
+    }
+
+    #[test]
+    fn test_parse_event_input() {
+        let input = parse_event_from_text("Riunione domani alle 10").unwrap();
+        assert_eq!(input.title, "Riunione");
+        assert_eq!(
+            input.source_text.as_deref(),
+            Some("Riunione domani alle 10")
+        );
+        assert!(DateTime::parse_from_rfc3339(&input.start).is_ok());
+        assert!(DateTime::parse_from_rfc3339(input.end.as_deref().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,119 @@
+// GPU Module
+// Detects the NVIDIA GPUs through NVML and estimates whether an Ollama model
+// fits in their memory or spills over to CPU and system RAM
+
+#[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+use nvml_wrapper::Nvml;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Weights are not everything: KV cache and CUDA buffers need room too
+const MODEL_MEMORY_OVERHEAD: f64 = 1.2;
+/// Share of the VRAM considered usable, the rest is left to the desktop and other apps
+const USABLE_VRAM_SHARE: f64 = 0.9;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    pub name: String,
+    pub total_memory: u64,
+    pub free_memory: u64,
+}
+
+/// Where Ollama is expected to run a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelFit {
+    /// Entirely in VRAM
+    Gpu,
+    /// Split between VRAM and RAM: works, but noticeably slower
+    Partial,
+    /// In RAM on the CPU: slow
+    Cpu,
+}
+
+static GPUS: OnceLock<Vec<GpuInfo>> = OnceLock::new();
+
+/// GPUs found at the first call; the total memory does not change while the app runs
+pub fn detected_gpus() -> &'static [GpuInfo] {
+    GPUS.get_or_init(detect_gpus)
+}
+
+/// Fresh readings, including the memory currently free
+#[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+pub fn detect_gpus() -> Vec<GpuInfo> {
+    // Fails when the NVIDIA driver (and its NVML library) is not installed
+    let nvml = match Nvml::init() {
+        Ok(nvml) => nvml,
+        Err(e) => {
+            tracing::debug!("NVML non disponibile: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let count = nvml.device_count().unwrap_or(0);
+    (0..count)
+        .filter_map(|index| {
+            let device = nvml.device_by_index(index).ok()?;
+            let memory = device.memory_info().ok()?;
+            Some(GpuInfo {
+                name: device.name().unwrap_or_else(|_| format!("GPU {}", index)),
+                total_memory: memory.total,
+                free_memory: memory.free,
+            })
+        })
+        .collect()
+}
+
+/// Apple Silicon has no dedicated VRAM: Metal can use up to about 3/4 of the unified memory
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+pub fn detect_gpus() -> Vec<GpuInfo> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    vec![GpuInfo {
+        name: "Apple Silicon (memoria unificata)".to_string(),
+        total_memory: system.total_memory() / 4 * 3,
+        free_memory: system.available_memory(),
+    }]
+}
+
+/// Ollama splits a model across all GPUs, so their memory adds up.
+/// Without GPUs the model runs on the CPU.
+pub fn model_fit(model_size: u64, gpus: &[GpuInfo]) -> ModelFit {
+    let usable: f64 = gpus
+        .iter()
+        .map(|gpu| gpu.total_memory as f64 * USABLE_VRAM_SHARE)
+        .sum();
+    let needed = model_size as f64 * MODEL_MEMORY_OVERHEAD;
+
+    if usable >= needed {
+        ModelFit::Gpu
+    } else if usable >= needed / 2.0 {
+        ModelFit::Partial
+    } else {
+        ModelFit::Cpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1_073_741_824;
+
+    fn gpu(total_gb: u64) -> GpuInfo {
+        GpuInfo {
+            name: "Test GPU".to_string(),
+            total_memory: total_gb * GB,
+            free_memory: total_gb * GB,
+        }
+    }
+
+    #[test]
+    fn test_model_fit() {
+        assert_eq!(model_fit(4 * GB, &[gpu(8)]), ModelFit::Gpu);
+        assert_eq!(model_fit(8 * GB, &[gpu(8)]), ModelFit::Partial);
+        assert_eq!(model_fit(40 * GB, &[gpu(8)]), ModelFit::Cpu);
+        assert_eq!(model_fit(16 * GB, &[gpu(12), gpu(12)]), ModelFit::Gpu);
+        assert_eq!(model_fit(GB, &[]), ModelFit::Cpu);
+    }
+}
@@ -0,0 +1,143 @@
+// Shared HTTP client - one connection pool for Ollama, calendar, web and updates,
+// with the user's proxy, a common user-agent and a connect timeout
+
+use crate::local_storage::ProxySettings;
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy, Response};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// User-agent sent with every outgoing request
+pub const USER_AGENT: &str = concat!(
+    "MatePro/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/FrancescoZanti/MatePro)"
+);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref PROXY_SETTINGS: RwLock<ProxySettings> = RwLock::new(ProxySettings::default());
+    static ref SHARED_CLIENT: RwLock<Client> = RwLock::new(build_client());
+}
+
+/// Stores the proxy configuration and rebuilds the shared client with it
+pub fn set_proxy_settings(settings: ProxySettings) {
+    if let Ok(mut current) = PROXY_SETTINGS.write() {
+        *current = settings;
+    }
+    if let Ok(mut client) = SHARED_CLIENT.write() {
+        *client = build_client();
+    }
+}
+
+/// The client used for every outgoing request. Cloning is cheap and shares the pool.
+/// There is no overall timeout because Ollama generations can take minutes:
+/// set one per request with `RequestBuilder::timeout` where needed.
+pub fn shared_client() -> Client {
+    SHARED_CLIENT
+        .read()
+        .map(|client| client.clone())
+        .unwrap_or_else(|_| build_client())
+}
+
+/// Client for untrusted URLs: `host` resolves only to the already validated `addrs`
+/// and redirects are not followed, so the caller can check every hop.
+/// It always connects directly: a proxy would resolve the host again on its side
+/// and could reach a private address
+pub fn pinned_client(host: &str, addrs: &[SocketAddr]) -> Result<Client> {
+    client_builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(host, addrs)
+        .build()
+        .context("Impossibile creare il client HTTP")
+}
+
+fn build_client() -> Client {
+    client_builder().build().unwrap_or_else(|_| Client::new())
+}
+
+/// Without a custom proxy reqwest already honours HTTP_PROXY / HTTPS_PROXY / NO_PROXY
+fn client_builder() -> ClientBuilder {
+    let builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT);
+    let settings = PROXY_SETTINGS
+        .read()
+        .map(|settings| settings.clone())
+        .unwrap_or_default();
+
+    // Invalid settings are rejected when saved, so falling back here is only a safety net
+    match build_proxy(&settings) {
+        Ok(Some(proxy)) => builder.proxy(proxy),
+        _ => builder,
+    }
+}
+
+/// Builds the reqwest proxy for the given settings, `None` when disabled
+pub fn build_proxy(settings: &ProxySettings) -> Result<Option<Proxy>> {
+    let url = settings.url.trim();
+    if !settings.enabled || url.is_empty() {
+        return Ok(None);
+    }
+
+    let mut proxy = Proxy::all(url).with_context(|| format!("URL proxy non valido: {}", url))?;
+
+    if let Some(username) = settings.username.as_deref().filter(|u| !u.is_empty()) {
+        proxy = proxy.basic_auth(username, settings.password.as_deref().unwrap_or(""));
+    }
+
+    let proxy = proxy.no_proxy(NoProxy::from_string(&settings.no_proxy));
+    Ok(Some(proxy))
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaError {
+    error: String,
+}
+
+/// Text of a failed Ollama response: the `error` field of its `{"error": "..."}`
+/// body, otherwise the body itself. Proxies may answer with a whole HTML page,
+/// so it is cut to 300 characters
+pub async fn ollama_error_text(response: Response) -> String {
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<OllamaError>(&body)
+        .map(|e| e.error)
+        .unwrap_or(body);
+    message.trim().chars().take(300).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_proxy() {
+        let disabled = ProxySettings {
+            enabled: false,
+            url: "http://proxy.local:8080".to_string(),
+            ..Default::default()
+        };
+        assert!(build_proxy(&disabled).unwrap().is_none());
+
+        let socks = ProxySettings {
+            enabled: true,
+            url: "socks5://proxy.local:1080".to_string(),
+            username: Some("mario".to_string()),
+            password: Some("segreta".to_string()),
+            ..Default::default()
+        };
+        assert!(build_proxy(&socks).unwrap().is_some());
+
+        let invalid = ProxySettings {
+            enabled: true,
+            url: "not a url".to_string(),
+            ..Default::default()
+        };
+        assert!(build_proxy(&invalid).is_err());
+    }
+}
@@ -4,8 +4,20 @@
 pub mod agent;
 pub mod aiconnect;
 pub mod calendar_integration;
+pub mod conversation_html;
+#[cfg(feature = "voice")]
+pub mod dictation;
+pub mod event_notifier;
+pub mod event_parser;
+pub mod gpu;
+pub mod http_client;
 pub mod local_storage;
+pub mod logging;
 pub mod mcp_sql;
+pub mod rag;
+pub mod speech;
+pub mod sql_backends;
+pub mod storage_watcher;
 
 pub use agent::*;
 pub use aiconnect::*;
@@ -0,0 +1,164 @@
+// Application log - daily rotated files in the data directory, with a level that
+// can be changed at runtime from the settings
+
+use crate::local_storage;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+const LOG_DIR_NAME: &str = "logs";
+/// Files are named `matepro.YYYY-MM-DD.log`
+const LOG_FILE_PREFIX: &str = "matepro";
+const LOG_FILE_SUFFIX: &str = "log";
+/// Days of logs kept, older files are deleted on rotation
+const LOG_MAX_FILES: usize = 7;
+
+static LEVEL_HANDLE: OnceLock<reload::Handle<Targets, Registry>> = OnceLock::new();
+/// File appender behind the log writer, replaced when the data directory moves
+static APPENDER: OnceLock<Mutex<RollingFileAppender>> = OnceLock::new();
+
+/// Writes to the current file appender, so `reopen` can switch directory
+/// without rebuilding the subscriber
+struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match APPENDER.get() {
+            Some(appender) => appender
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match APPENDER.get() {
+            Some(appender) => appender
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Directory containing the log files
+pub fn log_dir() -> Result<PathBuf> {
+    Ok(PathBuf::from(local_storage::get_data_directory()?).join(LOG_DIR_NAME))
+}
+
+/// Installs the global subscriber. Debug builds also log to stderr.
+pub fn init(level: &str) -> Result<()> {
+    let level = parse_level(level).unwrap_or(LevelFilter::INFO);
+    let appender = build_appender(&log_dir()?)?;
+    APPENDER
+        .set(Mutex::new(appender))
+        .map_err(|_| anyhow::anyhow!("Logging già inizializzato"))?;
+    let (filter, handle) = reload::Layer::new(log_targets(level));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(|| LogWriter).with_ansi(false))
+        .with(cfg!(debug_assertions).then(|| fmt::layer().with_writer(std::io::stderr)))
+        .try_init()
+        .context("Logging già inizializzato")?;
+
+    let _ = LEVEL_HANDLE.set(handle);
+    Ok(())
+}
+
+/// Writes the next lines to the logs folder of the current data directory, after
+/// it was moved. Older files stay where they were
+pub fn reopen() -> Result<()> {
+    let appender = build_appender(&log_dir()?)?;
+    let current = APPENDER.get().context("Logging non inizializzato")?;
+    *current.lock().unwrap_or_else(PoisonError::into_inner) = appender;
+    Ok(())
+}
+
+fn build_appender(dir: &Path) -> Result<RollingFileAppender> {
+    std::fs::create_dir_all(dir).context("Impossibile creare la cartella dei log")?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(LOG_MAX_FILES)
+        .build(dir)
+        .context("Impossibile creare il file di log")
+}
+
+/// Changes the level of the running subscriber: error, warn, info or debug
+pub fn set_level(level: &str) -> Result<()> {
+    let level = parse_level(level)?;
+    LEVEL_HANDLE
+        .get()
+        .context("Logging non inizializzato")?
+        .reload(log_targets(level))
+        .context("Impossibile cambiare il livello di log")
+}
+
+/// Last lines of the most recent log file, oldest first
+pub fn recent_lines(max_lines: usize) -> Result<Vec<String>> {
+    let dir = log_dir()?;
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    // Dates in the file names sort chronologically
+    let latest = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.starts_with(LOG_FILE_PREFIX) && name.ends_with(&format!(".{}", LOG_FILE_SUFFIX))
+        })
+        .max();
+    let Some(latest) = latest else {
+        return Ok(Vec::new());
+    };
+
+    let content =
+        std::fs::read_to_string(dir.join(latest)).context("Impossibile leggere il file di log")?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(max_lines);
+    Ok(lines[start..].iter().map(|line| line.to_string()).collect())
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter> {
+    match level.trim().to_lowercase().as_str() {
+        "error" => Ok(LevelFilter::ERROR),
+        "warn" => Ok(LevelFilter::WARN),
+        "info" => Ok(LevelFilter::INFO),
+        "debug" => Ok(LevelFilter::DEBUG),
+        other => anyhow::bail!(
+            "Livello di log non valido: '{}' (usa error, warn, info o debug)",
+            other
+        ),
+    }
+}
+
+/// The chosen level applies to the app, dependencies (HTTP, SQL driver...) only
+/// report warnings so debug logs stay readable
+fn log_targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_default(level.min(LevelFilter::WARN))
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("debug").unwrap(), LevelFilter::DEBUG);
+        assert_eq!(parse_level(" WARN ").unwrap(), LevelFilter::WARN);
+        assert!(parse_level("verbose").is_err());
+    }
+}
@@ -7,38 +7,55 @@
 mod agent;
 mod aiconnect;
 mod calendar_integration;
+mod conversation_html;
+#[cfg(feature = "voice")]
+mod dictation;
+mod event_notifier;
+mod event_parser;
+mod gpu;
+mod http_client;
 mod local_storage;
+mod logging;
 mod mcp_sql;
+mod rag;
+mod speech;
+mod sql_backends;
+mod storage_watcher;
 
 use agent::{AgentSystem, ToolCall, ToolResult};
 use aiconnect::{
-    AiConnectClient, AiConnectNode, AuthMethod, BackendConfig, BackendKind, DiscoveredService,
+    AiConnectClient, AiConnectNode, AuthMethod, BackendConfig, BackendKind, ConnectionDiagnostic,
+    DiscoveredService,
 };
 use anyhow::Result;
 use calamine::{open_workbook, Ods, Reader, Xls, Xlsx};
 use chrono::{DateTime, Utc};
 use calendar_integration::{
-    CalendarIntegrationStatus, CreateRemoteEventRequest, OutlookDeviceFlowPoll,
-    OutlookDeviceFlowStart, RemoteCalendarEvent,
+    CalendarCredentialsCheck, CalendarIntegrationStatus, CreateRemoteEventRequest,
+    OutlookDeviceFlowPoll, OutlookDeviceFlowStart, RemoteCalendar, RemoteCalendarEvent,
+};
+use event_parser::CalendarEventInput;
+use local_storage::{
+    AppSettings, CalendarEvent, CalendarOccurrence, ConversationDraft, CustomSystemPrompt,
+    EventNotificationSettings, GenerationMetrics, LastBackend, LocalMemory, MemoryArchiveInfo,
+    MemoryLimits, MemoryMessage,
 };
-use local_storage::{CalendarEvent, CustomSystemPrompt, LocalMemory, MemoryMessage};
 use lopdf::Document;
+use mcp_sql::SqlBackend;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::process::Command;
 use std::sync::Arc;
-use tauri::State;
-use tokio::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::{oneshot, Mutex, Semaphore};
 
-#[cfg(target_os = "windows")]
 use semver::Version;
-#[cfg(target_os = "windows")]
-use std::time::Duration;
-#[cfg(target_os = "windows")]
+use std::time::{Duration, Instant};
 use tokio::fs::File;
-#[cfg(target_os = "windows")]
 use tokio::io::AsyncWriteExt;
 
 #[derive(Debug, Serialize)]
@@ -52,7 +69,11 @@ enum UpdateStatus {
         latest_version: String,
         download_url: String,
         asset_name: String,
+        checksum_url: Option<String>,
+        release_notes: Option<String>,
     },
+    /// Automatic check disabled or already performed recently
+    Skipped,
     Unsupported,
     Error {
         message: String,
@@ -68,6 +89,8 @@ pub struct Message {
     #[serde(default)]
     pub hidden: bool,
     pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<GenerationMetrics>,
 }
 
 #[derive(Debug, Serialize)]
@@ -80,6 +103,12 @@ struct ChatRequest {
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
     message: Message,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    eval_duration: Option<u64>,
+    #[serde(default)]
+    total_duration: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,6 +140,55 @@ pub struct ModelInfoResponse {
     pub size: u64,
     pub size_gb: f64,
     pub category: String,
+    /// Whether the model fits in the GPU memory; only known when Ollama runs on this machine
+    pub gpu_fit: Option<gpu::ModelFit>,
+}
+
+/// Raw response of Ollama's `/api/show`
+#[derive(Debug, Deserialize)]
+struct ShowModelResponse {
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    modelfile: Option<String>,
+    #[serde(default)]
+    parameters: Option<String>,
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    details: ShowModelDetails,
+    #[serde(default)]
+    model_info: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ShowModelDetails {
+    #[serde(default)]
+    family: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    parameter_size: Option<String>,
+    #[serde(default)]
+    quantization_level: Option<String>,
+}
+
+/// Model information shown in the model details panel
+#[derive(Debug, Serialize)]
+pub struct ModelDetails {
+    pub name: String,
+    pub family: Option<String>,
+    pub format: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+    pub context_length: Option<u64>,
+    pub parameters: Option<String>,
+    pub template: Option<String>,
+    pub system: Option<String>,
+    pub license: Option<String>,
+    pub modelfile: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -120,74 +198,165 @@ struct UserProfile {
     primary_language: Option<String>,
 }
 
-// Calendar input structures for commands
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct CalendarEventInput {
-    pub id: Option<String>,
-    pub title: String,
-    pub description: Option<String>,
-    pub start: String,
-    pub end: Option<String>,
-    pub source_text: Option<String>,
-}
-
 // ============ STATE ============
 
 struct AppState {
     ollama_url: Mutex<String>,
-    client: reqwest::Client,
     agent_system: Mutex<AgentSystem>,
     sql_manager: mcp_sql::SqlConnectionManager,
     last_sql_connection_id: Arc<Mutex<Option<String>>>,
     aiconnect_client: AiConnectClient,
     backend_config: Mutex<BackendConfig>,
-}
+    /// Last `/api/tags` result: (server URL, fetched at, models)
+    model_cache: Mutex<Option<(String, Instant, Vec<ModelInfoResponse>)>>,
+    /// Conversations whose title is being generated in background
+    title_requests: Mutex<HashSet<String>>,
+    /// Microphone recording started by `start_voice_recording`
+    #[cfg(feature = "voice")]
+    voice_recorder: Mutex<Option<dictation::Recorder>>,
+    /// Stops the reply being read aloud by `speak_text`
+    speech_playback: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+/// How long `list_models` reuses the cached model list
+const MODEL_CACHE_TTL: Duration = Duration::from_secs(30);
+
+const CONVERSATION_TITLE_PROMPT: &str = "Genera un titolo breve (massimo 6 parole) per la conversazione seguente. \
+Rispondi solo con il titolo, senza virgolette né punteggiatura finale, nella lingua della conversazione.";
+/// Messages and characters per message sent to the model to generate a title
+const CONVERSATION_TITLE_MAX_MESSAGES: usize = 4;
+const CONVERSATION_TITLE_MAX_CHARS: usize = 600;
+const CONVERSATION_TITLE_MAX_WORDS: usize = 6;
+const CONVERSATION_TITLE_MAX_LENGTH: usize = 60;
 
 impl Default for AppState {
     fn default() -> Self {
         let sql_manager = mcp_sql::SqlConnectionManager::new();
         let last_sql_connection_id = Arc::new(Mutex::new(None));
-        let agent =
+        let mut agent =
             AgentSystem::with_shared_state(sql_manager.clone(), last_sql_connection_id.clone());
+        if let Ok(settings) = local_storage::load_app_settings() {
+            agent.set_offline_mode(settings.offline_mode);
+        }
 
         Self {
             ollama_url: Mutex::new("http://localhost:11434".to_string()),
-            client: reqwest::Client::new(),
             agent_system: Mutex::new(agent),
             sql_manager,
             last_sql_connection_id,
             aiconnect_client: AiConnectClient::new(),
             backend_config: Mutex::new(BackendConfig::default()),
+            model_cache: Mutex::new(None),
+            title_requests: Mutex::new(HashSet::new()),
+            #[cfg(feature = "voice")]
+            voice_recorder: Mutex::new(None),
+            speech_playback: Mutex::new(None),
         }
     }
 }
 
 // ============ UPDATE SUPPORT ============
 
-#[cfg(target_os = "windows")]
 #[derive(Debug, Deserialize)]
 struct GitHubAsset {
     name: String,
     browser_download_url: String,
 }
 
-#[cfg(target_os = "windows")]
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
     tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
     assets: Vec<GitHubAsset>,
 }
 
+/// Result of `download_and_install_update`, used by the UI to decide what to tell the user
+#[derive(Debug, Serialize)]
+struct UpdateInstallOutcome {
+    path: String,
+    close_app: bool,
+    message: String,
+}
+
+/// Document information read from a PDF's Info dictionary
+#[derive(Debug, Clone, Default, Serialize)]
+struct PdfMetadata {
+    title: Option<String>,
+    author: Option<String>,
+    pages: usize,
+    created: Option<String>,
+}
+
+impl PdfMetadata {
+    /// One-line header prepended to the extracted text to give the model some context
+    fn header(&self) -> String {
+        let mut parts = vec![format!(
+            "Documento: {}",
+            self.title.as_deref().unwrap_or("senza titolo")
+        )];
+        parts.push(format!(
+            "{} {}",
+            self.pages,
+            if self.pages == 1 { "pagina" } else { "pagine" }
+        ));
+        if let Some(author) = &self.author {
+            parts.push(format!("autore {}", author));
+        }
+        if let Some(created) = &self.created {
+            parts.push(format!("creato il {}", created));
+        }
+        parts.join(", ")
+    }
+}
+
+// Asset di release accettati per la piattaforma corrente: (parola chiave, estensione),
+// in ordine di preferenza
 #[cfg(target_os = "windows")]
-async fn latest_windows_release() -> Result<UpdateStatus, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(15))
-        .user_agent("MatePro-Updater")
-        .build()
-        .map_err(|e| format!("Impossibile creare client HTTP: {}", e))?;
+const UPDATE_ASSET_PATTERNS: &[(&str, &str)] = &[("windows", ".exe")];
+#[cfg(target_os = "macos")]
+const UPDATE_ASSET_PATTERNS: &[(&str, &str)] = &[("macos", ".dmg"), ("macos", ".app.tar.gz")];
+#[cfg(target_os = "linux")]
+const UPDATE_ASSET_PATTERNS: &[(&str, &str)] = &[
+    ("linux", ".AppImage"),
+    ("linux", ".deb"),
+    ("linux", ".rpm"),
+    ("linux", ".tar.gz"),
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+const UPDATE_ASSET_PATTERNS: &[(&str, &str)] = &[];
+
+fn select_update_asset(assets: &[GitHubAsset]) -> Option<&GitHubAsset> {
+    UPDATE_ASSET_PATTERNS
+        .iter()
+        .find_map(|(keyword, extension)| {
+            assets
+                .iter()
+                .find(|asset| asset.name.contains(keyword) && asset.name.ends_with(extension))
+        })
+}
+
+// Estrae l'hash SHA256 atteso da un file `.sha256` (formato `sha256sum`: "<hash>  <nome>"
+// oppure solo "<hash>")
+fn parse_sha256_checksum(text: &str, asset_name: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
 
-    let release: GitHubRelease = client
+        match parts.next() {
+            Some(name) if name.trim_start_matches('*') != asset_name => None,
+            _ => Some(hash.to_lowercase()),
+        }
+    })
+}
+
+async fn latest_release() -> Result<UpdateStatus, String> {
+    let release: GitHubRelease = http_client::shared_client()
         .get("https://api.github.com/repos/FrancescoZanti/MatePro/releases/latest")
+        .timeout(Duration::from_secs(15))
         .send()
         .await
         .map_err(|e| format!("Errore richiesta GitHub: {}", e))?
@@ -211,53 +380,80 @@ async fn latest_windows_release() -> Result<UpdateStatus, String> {
         });
     }
 
-    let asset = release
+    let asset = select_update_asset(&release.assets).ok_or_else(|| {
+        format!(
+            "Nessun pacchetto per {} trovato nella release {}",
+            std::env::consts::OS,
+            latest_version
+        )
+    })?;
+
+    let checksum_name = format!("{}.sha256", asset.name);
+    let checksum_url = release
         .assets
-        .into_iter()
-        .find(|asset| asset.name.contains("windows") && asset.name.ends_with(".exe"))
-        .ok_or_else(|| {
-            format!(
-                "Nessun installer Windows trovato per la release {}",
-                latest_version
-            )
-        })?;
+        .iter()
+        .find(|candidate| candidate.name == checksum_name)
+        .map(|candidate| candidate.browser_download_url.clone());
 
     Ok(UpdateStatus::UpdateAvailable {
         current_version: current_version.to_string(),
         latest_version: latest_semver.to_string(),
-        download_url: asset.browser_download_url,
-        asset_name: asset.name,
+        download_url: asset.browser_download_url.clone(),
+        asset_name: asset.name.clone(),
+        checksum_url,
+        release_notes: release.body.clone().filter(|body| !body.trim().is_empty()),
     })
 }
 
-#[cfg(target_os = "windows")]
-async fn download_installer(url: &str, version: &str) -> Result<std::path::PathBuf, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(120))
-        .user_agent("MatePro-Updater")
-        .build()
-        .map_err(|e| format!("Impossibile creare client HTTP: {}", e))?;
-
-    let response = client
+async fn download_installer(
+    url: &str,
+    version: &str,
+    expected_sha256: Option<&str>,
+) -> Result<PathBuf, String> {
+    let response = http_client::shared_client()
         .get(url)
+        .timeout(Duration::from_secs(120))
         .send()
         .await
         .map_err(|e| format!("Errore download installer: {}", e))?
         .error_for_status()
         .map_err(|e| format!("Download fallito: {}", e))?;
 
-    let mut installer_path = std::env::temp_dir();
-    installer_path.push(format!("matepro-update-{}-installer.exe", version));
+    // Conserva il nome dell'asset: l'estensione determina come verrà aperto
+    let asset_name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && !name.contains(".."))
+        .unwrap_or("installer");
 
-    let mut file = File::create(&installer_path)
-        .await
-        .map_err(|e| format!("Impossibile creare file temporaneo: {}", e))?;
+    // Su Linux il pacchetto resta a disposizione dell'utente nella cartella Download
+    let mut installer_path = if cfg!(target_os = "linux") {
+        dirs::download_dir().unwrap_or_else(std::env::temp_dir)
+    } else {
+        std::env::temp_dir()
+    };
+    installer_path.push(format!("matepro-update-{}-{}", version, asset_name));
 
     let bytes = response
         .bytes()
         .await
         .map_err(|e| format!("Errore lettura dati installer: {}", e))?;
 
+    // Verifica l'integrità prima di scrivere su disco qualcosa che verrà eseguito
+    if let Some(expected) = expected_sha256 {
+        let actual = format!("{:x}", Sha256::digest(&bytes));
+        if actual != expected {
+            return Err(format!(
+                "Checksum SHA256 non valido per {}: atteso {}, ottenuto {}",
+                asset_name, expected, actual
+            ));
+        }
+    }
+
+    let mut file = File::create(&installer_path)
+        .await
+        .map_err(|e| format!("Impossibile creare file temporaneo: {}", e))?;
+
     file.write_all(&bytes)
         .await
         .map_err(|e| format!("Impossibile salvare installer: {}", e))?;
@@ -269,53 +465,167 @@ async fn download_installer(url: &str, version: &str) -> Result<std::path::PathB
 }
 
 #[cfg(target_os = "windows")]
+fn launch_installer(path: &std::path::Path) -> Result<UpdateInstallOutcome, String> {
+    Command::new(path)
+        .spawn()
+        .map_err(|e| format!("Impossibile avviare l'installer: {}", e))?;
+
+    Ok(UpdateInstallOutcome {
+        path: path.display().to_string(),
+        close_app: true,
+        message:
+            "Installazione avviata. L'applicazione verrà chiusa per completare l'aggiornamento."
+                .to_string(),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn launch_installer(path: &std::path::Path) -> Result<UpdateInstallOutcome, String> {
+    // `open` monta il .dmg (o estrae il .tar.gz) e mostra l'app nel Finder
+    Command::new("open")
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("Impossibile aprire il pacchetto: {}", e))?;
+
+    Ok(UpdateInstallOutcome {
+        path: path.display().to_string(),
+        close_app: false,
+        message: "Pacchetto aperto. Trascina MatePro nella cartella Applicazioni e riavvia l'app."
+            .to_string(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn launch_installer(path: &std::path::Path) -> Result<UpdateInstallOutcome, String> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    // .deb/.rpm vengono aperti dal gestore pacchetti; per gli altri formati
+    // non c'è un installer e ci limitiamo a mostrare la cartella
+    let (target, message) = if name.ends_with(".deb") || name.ends_with(".rpm") {
+        (
+            path.to_path_buf(),
+            "Pacchetto aperto nel gestore software. Completa l'installazione e riavvia MatePro.",
+        )
+    } else {
+        (
+            path.parent()
+                .map(PathBuf::from)
+                .unwrap_or_else(std::env::temp_dir),
+            "Aggiornamento scaricato. Sostituisci l'eseguibile con il nuovo file e riavvia MatePro.",
+        )
+    };
+
+    Command::new("xdg-open")
+        .arg(&target)
+        .spawn()
+        .map_err(|e| format!("Impossibile aprire {}: {}", target.display(), e))?;
+
+    Ok(UpdateInstallOutcome {
+        path: path.display().to_string(),
+        close_app: false,
+        message: message.to_string(),
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn launch_installer(_path: &std::path::Path) -> Result<UpdateInstallOutcome, String> {
+    Err("Gli aggiornamenti automatici non sono supportati su questa piattaforma".to_string())
+}
+
 #[tauri::command]
 async fn check_for_updates() -> Result<UpdateStatus, String> {
-    match latest_windows_release().await {
+    if UPDATE_ASSET_PATTERNS.is_empty() {
+        return Ok(UpdateStatus::Unsupported);
+    }
+
+    match latest_release().await {
         Ok(status) => Ok(status),
-        Err(message) => Ok(UpdateStatus::Error { message }),
+        Err(message) => {
+            tracing::warn!("Controllo aggiornamenti fallito: {}", message);
+            Ok(UpdateStatus::Error { message })
+        }
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-#[tauri::command]
-async fn check_for_updates() -> Result<UpdateStatus, String> {
-    Ok(UpdateStatus::Unsupported)
-}
+/// Minimum interval between two automatic update checks
+const AUTO_UPDATE_CHECK_INTERVAL_HOURS: i64 = 24;
 
-#[cfg(target_os = "windows")]
 #[tauri::command]
-async fn download_and_install_update(url: String, version: String) -> Result<(), String> {
-    let installer_path = download_installer(&url, &version).await?;
+async fn check_for_updates_on_startup() -> Result<UpdateStatus, String> {
+    let mut settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    if !settings.auto_update_check {
+        return Ok(UpdateStatus::Skipped);
+    }
 
-    std::process::Command::new(&installer_path)
-        .spawn()
-        .map_err(|e| format!("Impossibile avviare l'installer: {}", e))?;
+    let now = Utc::now();
+    if let Some(last_check) = settings.last_update_check {
+        if now - last_check < chrono::Duration::hours(AUTO_UPDATE_CHECK_INTERVAL_HOURS) {
+            return Ok(UpdateStatus::Skipped);
+        }
+    }
 
-    Ok(())
+    settings.last_update_check = Some(now);
+    local_storage::save_app_settings(&settings).map_err(|e| e.to_string())?;
+
+    check_for_updates().await
+}
+
+async fn fetch_expected_checksum(checksum_url: &str, asset_name: &str) -> Result<String, String> {
+    let text = http_client::shared_client()
+        .get(checksum_url)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("Errore download checksum: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Download checksum fallito: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Errore lettura checksum: {}", e))?;
+
+    parse_sha256_checksum(&text, asset_name)
+        .ok_or_else(|| format!("File checksum non valido per {}", asset_name))
 }
 
-#[cfg(not(target_os = "windows"))]
 #[tauri::command]
-async fn download_and_install_update(_url: String, _version: String) -> Result<(), String> {
-    Err("Gli aggiornamenti automatici sono disponibili solo su Windows".to_string())
+async fn download_and_install_update(
+    url: String,
+    version: String,
+    checksum_url: Option<String>,
+    allow_unverified: Option<bool>,
+) -> Result<UpdateInstallOutcome, String> {
+    let asset_name = url.rsplit('/').next().unwrap_or_default().to_string();
+
+    let expected = match checksum_url {
+        Some(checksum_url) => Some(fetch_expected_checksum(&checksum_url, &asset_name).await?),
+        None if allow_unverified.unwrap_or(false) => None,
+        None => {
+            return Err(
+                "La release non include un checksum SHA256: installazione bloccata".to_string(),
+            );
+        }
+    };
+
+    let installer_path = download_installer(&url, &version, expected.as_deref()).await?;
+    launch_installer(&installer_path)
 }
 
 // ============ HELPER FUNCTIONS ============
 
 fn get_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let total_seconds = now + 3600; // +1 hour for CET
-    let hours = (total_seconds / 3600) % 24;
-    let minutes = (total_seconds % 3600) / 60;
-    format!("{:02}:{:02}", hours, minutes)
+    // Full ISO 8601 local time (DST handled by chrono); the UI decides how to display it
+    chrono::Local::now().to_rfc3339()
 }
 
 fn extract_text_from_pdf(path: &PathBuf) -> Result<String> {
+    // pdftotext -layout keeps columns and tables readable, so prefer it when installed
+    if let Some(layout_text) = extract_text_from_pdf_with_pdftotext(path) {
+        return Ok(layout_text);
+    }
+
     let doc = Document::load(path)?;
     let mut text = String::new();
     let pages = doc.get_pages();
@@ -328,17 +638,96 @@ fn extract_text_from_pdf(path: &PathBuf) -> Result<String> {
     }
 
     if text.trim().is_empty() {
-        if let Some(fallback_text) = extract_text_from_pdf_with_pdftotext(path) {
-            return Ok(fallback_text);
-        }
         anyhow::bail!(
             "Impossibile estrarre testo dal PDF. Il file potrebbe contenere solo immagini o testo protetto."
         );
     }
 
+    if looks_like_scrambled_pdf_text(&text) {
+        return Ok(format!(
+            "[Nota: il testo estratto potrebbe essere disordinato (PDF multi-colonna o con tabelle). Installa pdftotext (poppler-utils) per un'estrazione più fedele.]\n\n{}",
+            text
+        ));
+    }
+
     Ok(text)
 }
 
+/// Heuristic for lopdf output where columns got interleaved: lots of tiny
+/// line fragments, or words glued together without spaces
+fn looks_like_scrambled_pdf_text(text: &str) -> bool {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.len() < 10 {
+        return false;
+    }
+
+    let fragment_lines = lines.iter().filter(|l| l.chars().count() <= 3).count();
+    if fragment_lines * 10 > lines.len() * 4 {
+        return true;
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return false;
+    }
+    let glued_words = words.iter().filter(|w| w.chars().count() > 30).count();
+    glued_words * 20 > words.len()
+}
+
+fn extract_pdf_metadata(path: &PathBuf) -> Result<PdfMetadata> {
+    let doc = Document::load(path)?;
+    let mut metadata = PdfMetadata {
+        pages: doc.get_pages().len(),
+        ..Default::default()
+    };
+
+    let info = match doc.trailer.get(b"Info") {
+        Ok(lopdf::Object::Reference(id)) => doc.get_dictionary(*id).ok(),
+        Ok(lopdf::Object::Dictionary(dict)) => Some(dict),
+        _ => None,
+    };
+
+    if let Some(info) = info {
+        let field = |key: &[u8]| {
+            info.get(key)
+                .ok()
+                .and_then(|obj| obj.as_str().ok())
+                .map(decode_pdf_text_string)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        metadata.title = field(b"Title");
+        metadata.author = field(b"Author");
+        metadata.created = field(b"CreationDate").map(|raw| format_pdf_date(&raw).unwrap_or(raw));
+    }
+
+    Ok(metadata)
+}
+
+/// PDF text strings are either UTF-16BE with a BOM or (roughly) Latin-1
+fn decode_pdf_text_string(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+/// Converts a PDF date ("D:20230115103000+01'00'") to "15/01/2023"
+fn format_pdf_date(raw: &str) -> Option<String> {
+    let digits = raw.trim().trim_start_matches("D:");
+    let date = chrono::NaiveDate::parse_from_str(digits.get(..8)?, "%Y%m%d").ok()?;
+    Some(date.format("%d/%m/%Y").to_string())
+}
+
 fn extract_text_from_pdf_with_pdftotext(path: &PathBuf) -> Option<String> {
     let output = Command::new("pdftotext")
         .arg("-layout")
@@ -424,32 +813,153 @@ fn extract_text_from_file(path: &PathBuf) -> Result<String> {
     let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
     match extension.to_lowercase().as_str() {
-        "pdf" => extract_text_from_pdf(path),
+        "pdf" => {
+            let text = extract_text_from_pdf(path)?;
+            match extract_pdf_metadata(path) {
+                Ok(metadata) => Ok(format!("[{}]\n\n{}", metadata.header(), text)),
+                Err(_) => Ok(text),
+            }
+        }
         "xlsx" | "xls" | "ods" => extract_text_from_excel(path),
         "txt" | "md" | "csv" => {
             let content = fs::read_to_string(path)?;
             Ok(content)
         }
+        "json" => {
+            let content = fs::read_to_string(path)?;
+            match serde_json::from_str::<serde_json::Value>(&content) {
+                Ok(value) => Ok(serde_json::to_string_pretty(&value)?),
+                Err(e) => Ok(format!(
+                    "[Avviso: JSON non valido ({}), contenuto riportato così com'è]\n\n{}",
+                    e, content
+                )),
+            }
+        }
+        "xml" => {
+            let content = fs::read_to_string(path)?;
+            match pretty_print_xml(&content) {
+                Some(formatted) => Ok(formatted),
+                None => Ok(format!(
+                    "[Avviso: XML non ben formato, contenuto riportato così com'è]\n\n{}",
+                    content
+                )),
+            }
+        }
         _ => anyhow::bail!("Formato file non supportato: {}", extension),
     }
 }
 
+/// Re-indents an XML document, two spaces per level. Elements containing only
+/// text stay on one line. Returns `None` when tags are unbalanced or unterminated.
+fn pretty_print_xml(input: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut depth: usize = 0;
+    let mut rest = input.trim();
+
+    let push_line = |out: &mut String, depth: usize, line: &str| {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(line);
+        out.push('\n');
+    };
+
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = rest[..end].trim();
+            if !text.is_empty() {
+                push_line(&mut out, depth, text);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else {
+            ">"
+        };
+        let end = rest.find(terminator)? + terminator.len();
+        let tag = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        if tag.starts_with("</") {
+            depth = depth.checked_sub(1)?;
+            push_line(&mut out, depth, tag);
+        } else if tag.ends_with("/>") || tag.starts_with("<?") || tag.starts_with("<!") {
+            push_line(&mut out, depth, tag);
+        } else {
+            // <name>text</name> is kept on a single line
+            if let Some(text_end) = rest.find('<') {
+                let after_text = &rest[text_end..];
+                if text_end > 0 && after_text.starts_with("</") {
+                    let close_end = after_text.find('>')? + 1;
+                    let text = rest[..text_end].trim();
+                    let line = format!("{}{}{}", tag, text, &after_text[..close_end]);
+                    push_line(&mut out, depth, &line);
+                    rest = after_text[close_end..].trim_start();
+                    continue;
+                }
+            }
+            push_line(&mut out, depth, tag);
+            depth += 1;
+        }
+    }
+
+    if depth == 0 {
+        Some(out)
+    } else {
+        None
+    }
+}
+
 async fn check_server(url: &str) -> bool {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_millis(1500))
-        .build()
-        .unwrap();
+    let request = http_client::shared_client()
+        .get(format!("{}/api/tags", url))
+        .timeout(Duration::from_millis(1500));
 
-    match client.get(format!("{}/api/tags", url)).send().await {
+    match request.send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
 }
 
+/// Message for a failed Ollama response, built from its error text:
+/// "Modello 'x' non trovato" is more useful than a bare "404 Not Found"
+async fn ollama_error_message(response: reqwest::Response, model: Option<&str>) -> String {
+    let status = response.status();
+    let message = http_client::ollama_error_text(response).await;
+    let lower = message.to_lowercase();
+
+    match model {
+        Some(model) if lower.contains("model") && lower.contains("not found") => format!(
+            "Modello '{}' non trovato sul server: scaricalo con `ollama pull {}`",
+            model, model
+        ),
+        _ if message.is_empty() => format!("Errore risposta: {}", status),
+        _ => format!("Errore risposta ({}): {}", status, message),
+    }
+}
+
 // ============ TAURI COMMANDS ============
 
+/// Progress of the subnet scan, emitted as `scan_progress` while hosts are checked
+#[derive(Debug, Clone, Serialize)]
+struct ScanProgress {
+    checked: usize,
+    total: usize,
+    found: Vec<String>,
+}
+
+/// Hosts checked between two progress events when no server turns up
+const SCAN_PROGRESS_STEP: usize = 16;
+/// Hosts probed at the same time when the frontend does not pass a limit: a burst
+/// over the whole subnet looks like a port scan to firewalls and IDS
+const SCAN_DEFAULT_CONCURRENCY: usize = 32;
+
 #[tauri::command]
-async fn scan_network() -> Vec<String> {
+async fn scan_network(app: AppHandle, max_concurrency: Option<usize>) -> Vec<String> {
     let mut servers = Vec::new();
 
     // Check localhost
@@ -470,55 +980,108 @@ async fn scan_network() -> Vec<String> {
             let octets = ip.octets();
             let base = format!("{}.{}.{}", octets[0], octets[1], octets[2]);
 
-            let mut handles = vec![];
-            for i in 1..255 {
+            let concurrency = max_concurrency
+                .unwrap_or(SCAN_DEFAULT_CONCURRENCY)
+                .clamp(1, 254);
+            let permits = Arc::new(Semaphore::new(concurrency));
+            let mut checks = tokio::task::JoinSet::new();
+            for i in 1..255u8 {
                 let url = format!("http://{}.{}:11434", base, i);
-                let handle = tokio::spawn(async move {
+                let permits = permits.clone();
+                checks.spawn(async move {
+                    // The semaphore is never closed, so acquiring cannot fail
+                    let _permit = permits.acquire_owned().await.ok()?;
                     if check_server(&url).await {
-                        Some(url)
+                        Some((i, url))
                     } else {
                         None
                     }
                 });
-                handles.push(handle);
             }
 
-            for handle in handles {
-                if let Ok(Some(url)) = handle.await {
-                    if !servers.contains(&url) {
-                        servers.push(url);
+            let total = checks.len();
+            let mut checked = 0;
+            let mut subnet_servers: Vec<(u8, String)> = Vec::new();
+            let progress = |checked: usize, subnet_servers: &[(u8, String)]| {
+                let mut found = servers.clone();
+                found.extend(subnet_servers.iter().map(|(_, url)| url.clone()));
+                let _ = app.emit(
+                    "scan_progress",
+                    ScanProgress {
+                        checked,
+                        total,
+                        found,
+                    },
+                );
+            };
+            progress(checked, &subnet_servers);
+
+            // Hosts answer in any order: report them as they come
+            while let Some(result) = checks.join_next().await {
+                checked += 1;
+                let found_new = match result {
+                    Ok(Some((i, url))) if !servers.contains(&url) => {
+                        subnet_servers.push((i, url));
+                        true
                     }
+                    _ => false,
+                };
+                if found_new || checked % SCAN_PROGRESS_STEP == 0 || checked == total {
+                    progress(checked, &subnet_servers);
                 }
             }
+
+            subnet_servers.sort_by_key(|(i, _)| *i);
+            servers.extend(subnet_servers.into_iter().map(|(_, url)| url));
         }
     }
 
     servers
 }
 
+#[tauri::command]
+fn normalize_ollama_url(url: String) -> Result<String, String> {
+    aiconnect::normalize_ollama_url(&url).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn connect_to_server(state: State<'_, Arc<AppState>>, url: String) -> Result<(), String> {
+    let url = aiconnect::normalize_ollama_url(&url).map_err(|e| e.to_string())?;
     if !check_server(&url).await {
+        tracing::warn!("Server Ollama non raggiungibile: {}", url);
         return Err("Impossibile connettersi al server Ollama".to_string());
     }
 
+    tracing::info!("Connesso al server Ollama {}", url);
     let mut ollama_url = state.ollama_url.lock().await;
     *ollama_url = url;
     Ok(())
 }
 
 #[tauri::command]
-async fn list_models(state: State<'_, Arc<AppState>>) -> Result<Vec<ModelInfoResponse>, String> {
+async fn list_models(
+    state: State<'_, Arc<AppState>>,
+    force_refresh: Option<bool>,
+) -> Result<Vec<ModelInfoResponse>, String> {
     let url = state.ollama_url.lock().await;
-    let response = state
-        .client
+
+    if !force_refresh.unwrap_or(false) {
+        let cache = state.model_cache.lock().await;
+        if let Some((cached_url, fetched_at, models)) = cache.as_ref() {
+            if *cached_url == *url && fetched_at.elapsed() < MODEL_CACHE_TTL {
+                return Ok(models.clone());
+            }
+        }
+    }
+
+    let response = http_client::shared_client()
         .get(format!("{}/api/tags", *url))
         .send()
         .await
         .map_err(|e| format!("Errore connessione: {}", e))?;
 
     if !response.status().is_success() {
-        return Err(format!("Errore risposta: {}", response.status()));
+        return Err(ollama_error_message(response, None).await);
     }
 
     let json: serde_json::Value = response
@@ -526,6 +1089,13 @@ async fn list_models(state: State<'_, Arc<AppState>>) -> Result<Vec<ModelInfoRes
         .await
         .map_err(|e| format!("Errore parsing JSON: {}", e))?;
 
+    // The GPUs of this machine say nothing about a remote server
+    let local_ip = local_ip_address::local_ip().ok();
+    let is_local = server_identity(&url, local_ip)
+        .await
+        .starts_with("127.0.0.1:");
+    let gpus = is_local.then(gpu::detected_gpus);
+
     let models: Vec<ModelInfoResponse> = json["models"]
         .as_array()
         .unwrap_or(&vec![])
@@ -542,10 +1112,13 @@ async fn list_models(state: State<'_, Arc<AppState>>) -> Result<Vec<ModelInfoRes
                 size,
                 size_gb: model.size_gb(),
                 category: model.weight_category().to_string(),
+                gpu_fit: gpus.map(|gpus| gpu::model_fit(size, gpus)),
             })
         })
         .collect();
 
+    *state.model_cache.lock().await = Some((url.clone(), Instant::now(), models.clone()));
+
     Ok(models)
 }
 
@@ -554,14 +1127,21 @@ async fn chat(
     state: State<'_, Arc<AppState>>,
     model: String,
     messages: Vec<Message>,
+    node_id: Option<String>,
+    enable_web_context: Option<bool>,
 ) -> Result<Message, String> {
     let mut messages = messages;
 
-    if let Some(last_user_index) = messages
-        .iter()
-        .rposition(|message| message.role == "user" && !message.hidden)
-    {
-        let last_user_content = messages[last_user_index].content.clone();
+    // Without the flag the web search context is added as before
+    let last_user_content = if enable_web_context.unwrap_or(true) {
+        messages
+            .iter()
+            .rfind(|message| message.role == "user" && !message.hidden)
+            .map(|message| message.content.clone())
+    } else {
+        None
+    };
+    if let Some(last_user_content) = last_user_content {
         let context = {
             let agent = state.agent_system.lock().await;
             agent
@@ -570,33 +1150,36 @@ async fn chat(
         };
 
         if let Some(context_text) = context {
-            let context_message = Message {
-                role: "system".to_string(),
-                content: context_text,
-                hidden: true,
-                timestamp: Some(get_timestamp()),
-            };
-            messages.insert(last_user_index, context_message);
+            add_system_context(&mut messages, context_text);
         }
     }
 
-    let url = state.ollama_url.lock().await;
+    // A node chosen by the user bypasses the AIConnect load balancer
+    let url = match node_id.filter(|id| !id.is_empty()) {
+        Some(node_id) => state
+            .aiconnect_client
+            .node_endpoint(&node_id)
+            .await
+            .map_err(|e| format!("Errore selezione nodo: {}", e))?,
+        None => state.ollama_url.lock().await.clone(),
+    };
     let request = ChatRequest {
         model,
         messages,
         stream: false,
     };
 
-    let response = state
-        .client
-        .post(format!("{}/api/chat", *url))
+    let timeout = chat_timeout();
+    let response = http_client::shared_client()
+        .post(format!("{}/api/chat", url))
+        .timeout(timeout)
         .json(&request)
         .send()
         .await
-        .map_err(|e| format!("Errore richiesta: {}", e))?;
+        .map_err(|e| chat_request_error(e, timeout))?;
 
     if !response.status().is_success() {
-        return Err(format!("Errore risposta: {}", response.status()));
+        return Err(ollama_error_message(response, Some(&request.model)).await);
     }
 
     let chat_response: ChatResponse = response
@@ -604,16 +1187,110 @@ async fn chat(
         .await
         .map_err(|e| format!("Errore parsing risposta: {}", e))?;
 
+    let metrics = match (chat_response.eval_count, chat_response.eval_duration) {
+        (Some(eval_count), Some(eval_duration)) => Some(GenerationMetrics {
+            eval_count,
+            eval_duration,
+            total_duration: chat_response.total_duration,
+        }),
+        _ => None,
+    };
+
     Ok(Message {
         role: chat_response.message.role,
         content: chat_response.message.content,
         hidden: false,
         timestamp: Some(get_timestamp()),
+        metrics,
+    })
+}
+
+/// Appends `context` to the leading system message, creating it when missing:
+/// Ollama models expect all the instructions in a single `system` message
+fn add_system_context(messages: &mut Vec<Message>, context: String) {
+    match messages.first_mut() {
+        Some(first) if first.role == "system" => {
+            first.content.push_str("\n\n");
+            first.content.push_str(&context);
+        }
+        _ => messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: context,
+                hidden: true,
+                timestamp: Some(get_timestamp()),
+                metrics: None,
+            },
+        ),
+    }
+}
+
+/// Chat requests have their own timeout, much longer than the health checks
+fn chat_timeout() -> Duration {
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+    Duration::from_secs(settings.chat_timeout_secs.max(1))
+}
+
+fn chat_request_error(error: reqwest::Error, timeout: Duration) -> String {
+    if error.is_timeout() {
+        format!(
+            "Il modello non ha risposto entro {} secondi: aumenta il timeout delle chat nelle impostazioni",
+            timeout.as_secs()
+        )
+    } else {
+        format!("Errore richiesta: {}", error)
+    }
+}
+
+#[tauri::command]
+async fn show_model(state: State<'_, Arc<AppState>>, name: String) -> Result<ModelDetails, String> {
+    let url = state.ollama_url.lock().await;
+    let response = http_client::shared_client()
+        .post(format!("{}/api/show", *url))
+        .json(&serde_json::json!({ "model": name }))
+        .send()
+        .await
+        .map_err(|e| format!("Errore connessione: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(ollama_error_message(response, Some(&name)).await);
+    }
+
+    let show: ShowModelResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Errore parsing risposta: {}", e))?;
+
+    // model_info keys are prefixed by the architecture, e.g. "llama.context_length"
+    let context_length = show.model_info.as_ref().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+    });
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+
+    Ok(ModelDetails {
+        name,
+        family: show.details.family,
+        format: show.details.format,
+        parameter_size: show.details.parameter_size,
+        quantization_level: show.details.quantization_level,
+        context_length,
+        parameters: non_empty(show.parameters),
+        template: non_empty(show.template),
+        system: non_empty(show.system),
+        license: non_empty(show.license),
+        modelfile: non_empty(show.modelfile),
     })
 }
 
 #[tauri::command]
-async fn read_file(path: String) -> Result<(String, String), String> {
+async fn read_file(
+    path: String,
+    sheets: Option<Vec<String>>,
+    cell_range: Option<String>,
+) -> Result<(String, String), String> {
     let path_buf = PathBuf::from(&path);
 
     // Validate path doesn't contain directory traversal
@@ -633,20 +1310,119 @@ async fn read_file(path: String) -> Result<(String, String), String> {
         .unwrap_or("file")
         .to_string();
 
-    let content =
-        extract_text_from_file(&path_buf).map_err(|e| format!("Errore lettura file: {}", e))?;
+    let sheets = sheets.unwrap_or_default();
+    let cell_range = cell_range.filter(|r| !r.trim().is_empty());
+    let content = if !sheets.is_empty() || cell_range.is_some() {
+        agent::extract_spreadsheet_selection(&path_buf, &sheets, cell_range.as_deref())
+    } else {
+        extract_text_from_file(&path_buf).map(|text| with_detected_language(&text))
+    }
+    .map_err(|e| format!("Errore lettura file: {}", e))?;
 
     Ok((filename, content))
 }
 
+/// Prepend the detected language so the model knows what it is reading
+fn with_detected_language(text: &str) -> String {
+    // A few KB are plenty for detection and keep large attachments cheap
+    let sample: String = text.chars().take(4000).collect();
+    match agent::detect_language(&sample).filter(|d| d.reliable) {
+        Some(d) => format!("[Lingua rilevata: {} ({})]\n{}", d.name, d.code, text),
+        None => text.to_string(),
+    }
+}
+
 #[tauri::command]
-async fn get_tools_description(state: State<'_, Arc<AppState>>) -> Result<String, String> {
-    let agent = state.agent_system.lock().await;
-    Ok(agent.get_tools_description())
+async fn list_spreadsheet_sheets(path: String) -> Result<Vec<String>, String> {
+    agent::list_spreadsheet_sheets(&PathBuf::from(path)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn parse_tool_calls(
+async fn get_pdf_metadata(path: String) -> Result<PdfMetadata, String> {
+    extract_pdf_metadata(&PathBuf::from(path)).map_err(|e| e.to_string())
+}
+
+/// Embeds a long attachment with the configured embedding model
+#[tauri::command]
+async fn index_document(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    content: String,
+) -> Result<rag::IndexedDocument, String> {
+    let url = state.ollama_url.lock().await.clone();
+    let settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    rag::index_document(&url, &settings.embedding_model, &name, &content)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn retrieve_chunks(
+    state: State<'_, Arc<AppState>>,
+    query: String,
+    document_ids: Vec<String>,
+    top_k: Option<usize>,
+) -> Result<Vec<rag::RetrievedChunk>, String> {
+    let url = state.ollama_url.lock().await.clone();
+    let settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    let top_k = top_k.unwrap_or(6).clamp(1, 20);
+    rag::retrieve_chunks(
+        &url,
+        &settings.embedding_model,
+        &query,
+        &document_ids,
+        top_k,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn generate_embedding(
+    state: State<'_, Arc<AppState>>,
+    model: String,
+    text: String,
+) -> Result<Vec<f32>, String> {
+    let url = state.ollama_url.lock().await.clone();
+    rag::generate_embedding(&url, &model, &text)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn generate_embeddings(
+    state: State<'_, Arc<AppState>>,
+    model: String,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    let url = state.ollama_url.lock().await.clone();
+    rag::generate_embeddings(&url, &model, &texts)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn semantic_search(
+    state: State<'_, Arc<AppState>>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<rag::ConversationMatch>, String> {
+    let url = state.ollama_url.lock().await.clone();
+    let settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(10).clamp(1, 50);
+    rag::semantic_search(&url, &settings.embedding_model, &query, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_tools_description(state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    let agent = state.agent_system.lock().await;
+    Ok(agent.get_tools_description())
+}
+
+#[tauri::command]
+async fn parse_tool_calls(
     state: State<'_, Arc<AppState>>,
     response: String,
 ) -> Result<Vec<ToolCall>, String> {
@@ -660,10 +1436,16 @@ async fn execute_tool(
     tool_call: ToolCall,
 ) -> Result<ToolResult, String> {
     let mut agent = state.agent_system.lock().await;
-    agent
+    let result = agent
         .execute_tool(&tool_call)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    match &result.error {
+        Some(error) => tracing::warn!("Tool {} fallito: {}", result.tool_name, error),
+        None => tracing::info!("Tool {} eseguito", result.tool_name),
+    }
+    Ok(result)
 }
 
 #[tauri::command]
@@ -673,22 +1455,48 @@ async fn set_allow_dangerous(state: State<'_, Arc<AppState>>, allow: bool) -> Re
     Ok(())
 }
 
+/// Preview of what a dangerous tool would do, shown in the confirmation dialog
+#[tauri::command]
+async fn dry_run_tool(
+    state: State<'_, Arc<AppState>>,
+    tool_call: ToolCall,
+) -> Result<Option<String>, String> {
+    let agent = state.agent_system.lock().await;
+    agent.dry_run(&tool_call).map_err(|e| e.to_string())
+}
+
+/// Restores the file written by the agent's last file operation
+#[tauri::command]
+async fn undo_last_file_operation(state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    let agent = state.agent_system.lock().await;
+    let message = agent
+        .undo_last_file_operation()
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("{}", message);
+    Ok(message)
+}
+
+#[tauri::command]
+async fn get_file_undo_depth(state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    let agent = state.agent_system.lock().await;
+    Ok(agent.file_undo_depth().await)
+}
+
+/// Whether the call needs the user's confirmation before running
 #[tauri::command]
 async fn check_tool_dangerous(
     state: State<'_, Arc<AppState>>,
-    tool_name: String,
+    tool_call: ToolCall,
 ) -> Result<bool, String> {
     let agent = state.agent_system.lock().await;
-    Ok(agent
-        .tools
-        .get(&tool_name)
-        .map(|t| t.dangerous)
-        .unwrap_or(false))
+    Ok(agent.requires_confirmation(&tool_call))
 }
 
 #[tauri::command]
 async fn sql_connect(
     state: State<'_, Arc<AppState>>,
+    db_type: Option<String>,
     server: String,
     database: String,
     auth_method: String,
@@ -697,22 +1505,18 @@ async fn sql_connect(
     trust_server_certificate: Option<bool>,
 ) -> Result<String, String> {
     let connection_id = format!("sql_{}", uuid::Uuid::new_v4());
+    let db_type = mcp_sql::DbType::parse(db_type.as_deref().unwrap_or_default())
+        .map_err(|e| e.to_string())?;
     let trust_server_certificate = trust_server_certificate.unwrap_or(false);
 
-    let _client = if auth_method == "windows" {
-        mcp_sql::connect_windows_auth(&server, &database, trust_server_certificate)
-            .await
-            .map_err(|e| e.to_string())?
-    } else {
-        let user = username.as_deref().ok_or("Username richiesto")?;
-        let pass = password.as_deref().ok_or("Password richiesta")?;
-        mcp_sql::connect_sql_auth(&server, &database, user, pass, trust_server_certificate)
-            .await
-            .map_err(|e| e.to_string())?
-    };
+    if auth_method != "windows" {
+        username.as_deref().ok_or("Username richiesto")?;
+        password.as_deref().ok_or("Password richiesta")?;
+    }
 
     let conn_info = mcp_sql::SqlConnection {
         connection_id: connection_id.clone(),
+        db_type,
         server,
         database,
         auth_type: auth_method,
@@ -721,6 +1525,24 @@ async fn sql_connect(
         trust_server_certificate,
     };
 
+    if let Err(e) = mcp_sql::connect_with_info(&conn_info).await {
+        tracing::error!(
+            "Connessione {} a {}/{} fallita: {}",
+            db_type.label(),
+            conn_info.server,
+            conn_info.database,
+            e
+        );
+        return Err(e.to_string());
+    }
+    tracing::info!(
+        "Connessione {} stabilita: {}/{} ({})",
+        db_type.label(),
+        conn_info.server,
+        conn_info.database,
+        conn_info.auth_type
+    );
+
     state.sql_manager.add_connection(conn_info);
 
     let mut last_conn = state.last_sql_connection_id.lock().await;
@@ -752,9 +1574,17 @@ async fn sql_query(
         .await
         .map_err(|e| e.to_string())?;
 
-    mcp_sql::run_query(&mut client, &query)
-        .await
-        .map_err(|e| e.to_string())
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+    let timeout = Duration::from_secs(settings.sql_query_timeout_secs.max(1));
+
+    let mut result = client.query(&query, timeout).await.map_err(|e| {
+        tracing::warn!("Query SQL fallita su {}: {}", conn_info.server, e);
+        e.to_string()
+    })?;
+    if settings.sql_masking.enabled {
+        mcp_sql::mask_query_result(&mut result, &settings.sql_masking.patterns);
+    }
+    Ok(result)
 }
 
 #[tauri::command]
@@ -779,9 +1609,7 @@ async fn sql_list_tables(
         .await
         .map_err(|e| e.to_string())?;
 
-    mcp_sql::list_tables(&mut client)
-        .await
-        .map_err(|e| e.to_string())
+    client.list_tables().await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -808,7 +1636,70 @@ async fn sql_describe_table(
         .await
         .map_err(|e| e.to_string())?;
 
-    mcp_sql::describe_table(&mut client, &schema, &table)
+    client
+        .describe_table(&schema, &table)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sql_schema(
+    state: State<'_, Arc<AppState>>,
+    connection_id: Option<String>,
+    schema: Option<String>,
+    max_tables: Option<usize>,
+) -> Result<mcp_sql::DatabaseSchema, String> {
+    let conn_id = match connection_id {
+        Some(id) => id,
+        None => {
+            let last = state.last_sql_connection_id.lock().await;
+            last.clone().ok_or("Nessuna connessione SQL attiva")?
+        }
+    };
+
+    let conn_info = state
+        .sql_manager
+        .get_connection(&conn_id)
+        .ok_or("Connessione non trovata")?;
+
+    let mut client = mcp_sql::connect_with_info(&conn_info)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let max_tables = max_tables
+        .unwrap_or(mcp_sql::SCHEMA_DEFAULT_TABLES)
+        .clamp(1, mcp_sql::SCHEMA_MAX_TABLES);
+    let client = client.sql_server().map_err(|e| e.to_string())?;
+    mcp_sql::database_schema(client, schema.as_deref(), max_tables)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sql_explain(
+    state: State<'_, Arc<AppState>>,
+    connection_id: Option<String>,
+    query: String,
+) -> Result<Vec<mcp_sql::QueryPlan>, String> {
+    let conn_id = match connection_id {
+        Some(id) => id,
+        None => {
+            let last = state.last_sql_connection_id.lock().await;
+            last.clone().ok_or("Nessuna connessione SQL attiva")?
+        }
+    };
+
+    let conn_info = state
+        .sql_manager
+        .get_connection(&conn_id)
+        .ok_or("Connessione non trovata")?;
+
+    let mut client = mcp_sql::connect_with_info(&conn_info)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let client = client.sql_server().map_err(|e| e.to_string())?;
+    mcp_sql::explain_query(client, &query)
         .await
         .map_err(|e| e.to_string())
 }
@@ -878,6 +1769,53 @@ fn get_user_profile() -> UserProfile {
     }
 }
 
+/// Profile notes for the system prompt, or nothing when personalization is off
+#[tauri::command]
+fn get_user_profile_prompt() -> Result<Option<String>, String> {
+    let settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    if !settings.personalize_with_profile {
+        return Ok(None);
+    }
+    Ok(user_profile_prompt(&get_user_profile()))
+}
+
+fn user_profile_prompt(profile: &UserProfile) -> Option<String> {
+    let mut notes = Vec::new();
+    if let Some(name) = &profile.display_name {
+        notes.push(format!("L'utente si chiama {}.", name));
+    }
+    if let Some(language) = profile.primary_language.as_deref().and_then(language_name) {
+        notes.push(format!(
+            "Rispondi in {} a meno che l'utente non scriva o chieda esplicitamente un'altra lingua.",
+            language
+        ));
+    }
+
+    if notes.is_empty() {
+        None
+    } else {
+        Some(notes.join(" "))
+    }
+}
+
+/// Italian name of the language of a locale such as `it_IT` or `en-US`
+fn language_name(locale: &str) -> Option<&'static str> {
+    let code = locale.split(['_', '-']).next()?.to_lowercase();
+    let name = match code.as_str() {
+        "it" => "italiano",
+        "en" => "inglese",
+        "fr" => "francese",
+        "de" => "tedesco",
+        "es" => "spagnolo",
+        "pt" => "portoghese",
+        "nl" => "olandese",
+        "pl" => "polacco",
+        "ro" => "rumeno",
+        _ => return None,
+    };
+    Some(name)
+}
+
 // ============ LOCAL STORAGE COMMANDS ============
 
 /// Load conversation memory from local storage
@@ -889,7 +1827,9 @@ fn load_memory() -> Result<LocalMemory, String> {
 /// Save conversation memory to local storage
 #[tauri::command]
 fn save_memory(memory: LocalMemory) -> Result<(), String> {
-    local_storage::save_memory(&memory).map_err(|e| e.to_string())
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+    local_storage::save_memory(&memory, MemoryLimits::from_settings(&settings))
+        .map_err(|e| e.to_string())
 }
 
 /// Load custom system prompt from local storage
@@ -904,23 +1844,225 @@ fn save_custom_system_prompt(prompt: CustomSystemPrompt) -> Result<(), String> {
     local_storage::save_custom_system_prompt(&prompt).map_err(|e| e.to_string())
 }
 
-/// Add a new conversation to memory
+/// Load application settings from local storage
+#[tauri::command]
+fn load_app_settings() -> Result<AppSettings, String> {
+    local_storage::load_app_settings().map_err(|e| e.to_string())
+}
+
+/// Save application settings to local storage
+#[tauri::command]
+async fn save_app_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: AppSettings,
+) -> Result<(), String> {
+    http_client::build_proxy(&settings.proxy).map_err(|e| e.to_string())?;
+    logging::parse_level(&settings.log_level).map_err(|e| e.to_string())?;
+    local_storage::save_app_settings(&settings).map_err(|e| e.to_string())?;
+    let _ = logging::set_level(&settings.log_level);
+    state
+        .agent_system
+        .lock()
+        .await
+        .set_offline_mode(settings.offline_mode);
+    http_client::set_proxy_settings(settings.proxy);
+    Ok(())
+}
+
+/// Add a new conversation to memory. Without an explicit title the first message is
+/// used as placeholder and a generated title replaces it once the model has replied
 #[tauri::command]
-fn add_conversation_to_memory(
-    title: String,
+async fn add_conversation_to_memory(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    title: Option<String>,
     messages: Vec<MemoryMessage>,
     model: Option<String>,
 ) -> Result<String, String> {
-    local_storage::add_conversation(title, messages, model).map_err(|e| e.to_string())
+    let explicit_title = title.filter(|t| !t.trim().is_empty());
+    let generate_title = explicit_title.is_none();
+    let title =
+        explicit_title.unwrap_or_else(|| local_storage::default_conversation_title(&messages));
+
+    let id = local_storage::add_conversation(title, messages, model.clone())
+        .map_err(|e| e.to_string())?;
+
+    if generate_title {
+        schedule_conversation_title(app, state.inner().clone(), id.clone(), model).await;
+    }
+
+    Ok(id)
 }
 
 /// Update an existing conversation in memory
 #[tauri::command]
-fn update_conversation_in_memory(
+async fn update_conversation_in_memory(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
     id: String,
     messages: Vec<MemoryMessage>,
+    model: Option<String>,
 ) -> Result<(), String> {
-    local_storage::update_conversation(&id, messages).map_err(|e| e.to_string())
+    local_storage::update_conversation(&id, messages).map_err(|e| e.to_string())?;
+    schedule_conversation_title(app, state.inner().clone(), id, model).await;
+    Ok(())
+}
+
+/// Delete a single message from a saved conversation, returning the remaining messages
+#[tauri::command]
+fn delete_message_from_conversation(
+    conv_id: String,
+    message_index: usize,
+) -> Result<Vec<MemoryMessage>, String> {
+    local_storage::delete_message(&conv_id, message_index).map_err(|e| e.to_string())
+}
+
+/// Rename a saved conversation
+#[tauri::command]
+fn rename_conversation(id: String, new_title: String) -> Result<(), String> {
+    local_storage::rename_conversation(&id, &new_title).map_err(|e| e.to_string())
+}
+
+/// Ask the model for a concise title based on the first exchange of a conversation
+async fn generate_conversation_title(
+    url: &str,
+    model: &str,
+    messages: &[MemoryMessage],
+) -> Result<String> {
+    let excerpt = messages
+        .iter()
+        .filter(|message| !message.hidden && message.role != "system")
+        .take(CONVERSATION_TITLE_MAX_MESSAGES)
+        .map(|message| {
+            let content: String = message
+                .content
+                .chars()
+                .take(CONVERSATION_TITLE_MAX_CHARS)
+                .collect();
+            format!("{}: {}", message.role, content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if excerpt.is_empty() {
+        anyhow::bail!("Nessun messaggio da cui generare il titolo");
+    }
+
+    let request = ChatRequest {
+        model: model.to_string(),
+        messages: vec![Message {
+            role: "user".to_string(),
+            content: format!("{}\n\n{}", CONVERSATION_TITLE_PROMPT, excerpt),
+            hidden: false,
+            timestamp: None,
+            metrics: None,
+        }],
+        stream: false,
+    };
+
+    let response = http_client::shared_client()
+        .post(format!("{}/api/chat", url))
+        .timeout(chat_timeout())
+        .json(&request)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        anyhow::bail!(ollama_error_message(response, Some(model)).await);
+    }
+
+    let chat_response: ChatResponse = response.json().await?;
+
+    clean_conversation_title(&chat_response.message.content)
+        .ok_or_else(|| anyhow::anyhow!("Il modello non ha restituito un titolo"))
+}
+
+/// Generates the title in background when the conversation still has the placeholder
+/// taken from the first message and the model has already replied
+async fn schedule_conversation_title(
+    app: AppHandle,
+    state: Arc<AppState>,
+    id: String,
+    model: Option<String>,
+) {
+    let Some(model) = model.filter(|m| !m.is_empty()) else {
+        return;
+    };
+    let Ok(memory) = local_storage::load_memory() else {
+        return;
+    };
+    let Some(entry) = memory.conversations.into_iter().find(|c| c.id == id) else {
+        return;
+    };
+
+    let has_reply = entry
+        .messages
+        .iter()
+        .any(|m| m.role == "assistant" && !m.hidden);
+    if !has_reply || entry.title != local_storage::default_conversation_title(&entry.messages) {
+        return;
+    }
+    if !state.title_requests.lock().await.insert(id.clone()) {
+        return;
+    }
+
+    let url = state.ollama_url.lock().await.clone();
+    tokio::spawn(async move {
+        let result = match generate_conversation_title(&url, &model, &entry.messages).await {
+            // The user may have renamed the conversation while the model was answering
+            Ok(title) => local_storage::set_generated_title(&id, &title)
+                .map(|applied| applied.then_some(title)),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(Some(title)) => {
+                let _ = app.emit(
+                    "conversation-title-updated",
+                    serde_json::json!({ "id": id, "title": title }),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // Keep the placeholder and retry on the next save
+                tracing::warn!("Generazione del titolo di {} non riuscita: {}", id, e);
+                state.title_requests.lock().await.remove(&id);
+            }
+        }
+    });
+}
+
+/// Keeps the first meaningful line of the reply, without reasoning blocks, quotes or labels
+fn clean_conversation_title(raw: &str) -> Option<String> {
+    let without_thinking = match raw.rfind("</think>") {
+        Some(end) => &raw[end + "</think>".len()..],
+        None => raw,
+    };
+
+    let line = without_thinking
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Titolo:")
+        .or_else(|| line.strip_prefix("titolo:"))
+        .unwrap_or(line);
+    let title = line
+        .trim_matches(|c: char| "\"'*#.«»".contains(c) || c.is_whitespace())
+        .split_whitespace()
+        .take(CONVERSATION_TITLE_MAX_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = title.trim_end_matches(|c: char| ".,;:".contains(c));
+
+    if title.is_empty() {
+        return None;
+    }
+    if title.chars().count() <= CONVERSATION_TITLE_MAX_LENGTH {
+        return Some(title.to_string());
+    }
+
+    let truncated: String = title.chars().take(CONVERSATION_TITLE_MAX_LENGTH).collect();
+    Some(format!("{}…", truncated.trim_end()))
 }
 
 /// Delete a conversation from memory
@@ -935,12 +2077,215 @@ fn clear_all_conversations() -> Result<(), String> {
     local_storage::clear_all_conversations().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn export_conversations_jsonl(
+    path: String,
+    model: Option<String>,
+    include_attachments: Option<bool>,
+) -> Result<usize, String> {
+    local_storage::export_conversations_jsonl(
+        &PathBuf::from(path),
+        model.as_deref().filter(|m| !m.is_empty()),
+        include_attachments.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Saves a conversation as a standalone HTML page, to share it read-only
+#[tauri::command]
+fn export_conversation_html(id: String, path: String) -> Result<(), String> {
+    conversation_html::export_conversation_html(&id, &PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn import_conversations(path: String, format: Option<String>) -> Result<usize, String> {
+    local_storage::import_conversations(&PathBuf::from(path), format.as_deref().unwrap_or("auto"))
+        .map_err(|e| e.to_string())
+}
+
+/// List the archives holding the oldest conversations
+#[tauri::command]
+fn list_memory_archives() -> Result<Vec<MemoryArchiveInfo>, String> {
+    local_storage::list_memory_archives().map_err(|e| e.to_string())
+}
+
+/// Load the conversations of a memory archive
+#[tauri::command]
+fn load_memory_archive(file_name: String) -> Result<LocalMemory, String> {
+    local_storage::load_memory_archive(&file_name).map_err(|e| e.to_string())
+}
+
+/// Load the autosaved draft of the active chat
+#[tauri::command]
+fn load_conversation_draft() -> Result<Option<ConversationDraft>, String> {
+    local_storage::load_conversation_draft().map_err(|e| e.to_string())
+}
+
+/// Autosave the active chat as a draft
+#[tauri::command]
+fn save_conversation_draft(draft: ConversationDraft) -> Result<(), String> {
+    local_storage::save_conversation_draft(&draft).map_err(|e| e.to_string())
+}
+
+/// Remove the draft once the conversation is saved or closed
+#[tauri::command]
+fn clear_conversation_draft() -> Result<(), String> {
+    local_storage::clear_conversation_draft().map_err(|e| e.to_string())
+}
+
 /// Get the path to the data directory
 #[tauri::command]
 fn get_data_directory() -> Result<String, String> {
     local_storage::get_data_directory().map_err(|e| e.to_string())
 }
 
+/// Space taken by the data folder, by category
+#[tauri::command]
+async fn get_storage_stats() -> Result<local_storage::StorageStats, String> {
+    tokio::task::spawn_blocking(local_storage::get_storage_stats)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Remove the expired caches and the orphan draft
+#[tauri::command]
+async fn cleanup_storage() -> Result<local_storage::StorageCleanup, String> {
+    let cleanup = tokio::task::spawn_blocking(local_storage::cleanup_storage)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Pulizia dati: {} documenti e {} conversazioni rimossi dalla cache, {} byte liberati",
+        cleanup.removed_documents,
+        cleanup.removed_conversation_embeddings,
+        cleanup.freed_bytes
+    );
+    Ok(cleanup)
+}
+
+/// Move the data to another folder (e.g. one synced to the cloud); an empty
+/// path restores the default folder
+#[tauri::command]
+async fn set_data_directory(
+    app: AppHandle,
+    path: String,
+) -> Result<local_storage::DataDirectoryChange, String> {
+    let change = tokio::task::spawn_blocking(move || local_storage::set_data_directory(&path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if let Err(e) = logging::reopen() {
+        tracing::warn!("Log ancora nella cartella precedente: {}", e);
+    }
+    tracing::info!(
+        "Cartella dati spostata in {} ({} elementi migrati)",
+        change.path,
+        change.migrated
+    );
+    if let Err(e) = storage_watcher::watch_data_dir(app) {
+        tracing::warn!("Monitoraggio della cartella dati non disponibile: {}", e);
+    }
+    Ok(change)
+}
+
+/// Get the directory containing the rotated log files
+#[tauri::command]
+fn get_log_path() -> Result<String, String> {
+    logging::log_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Change the log level and remember it in the settings
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    logging::set_level(&level).map_err(|e| e.to_string())?;
+    let mut settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    settings.log_level = level.trim().to_lowercase();
+    local_storage::save_app_settings(&settings).map_err(|e| e.to_string())?;
+    tracing::info!("Livello di log impostato a {}", settings.log_level);
+    Ok(())
+}
+
+/// Log lines included in the diagnostic report
+const DIAGNOSTIC_LOG_LINES: usize = 300;
+
+/// Support information written by `generate_diagnostic_report`, secrets removed
+#[derive(Debug, Serialize)]
+struct DiagnosticReport {
+    generated_at: String,
+    app_version: String,
+    os: String,
+    arch: String,
+    ollama_url: String,
+    backend: BackendConfig,
+    backend_reachable: bool,
+    models: Vec<String>,
+    calendar: Option<CalendarIntegrationStatus>,
+    settings: Option<AppSettings>,
+    recent_logs: Vec<String>,
+    /// Sections that could not be collected, with the reason
+    errors: Vec<String>,
+}
+
+/// Write a JSON report to attach to bug reports: versions, backend, models,
+/// integrations, settings and latest logs, without passwords or tokens
+#[tauri::command]
+async fn generate_diagnostic_report(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<String, String> {
+    let mut errors = Vec::new();
+
+    let backend = state.backend_config.lock().await.redacted();
+    let backend_reachable = check_backend_health(state.clone()).await.unwrap_or(false);
+    let models = match list_models(state.clone(), Some(true)).await {
+        Ok(models) => models
+            .into_iter()
+            .map(|m| format!("{} ({:.1} GB)", m.name, m.size_gb))
+            .collect(),
+        Err(e) => {
+            errors.push(format!("Modelli: {}", e));
+            Vec::new()
+        }
+    };
+    let calendar = calendar_integration::get_calendar_status()
+        .map_err(|e| errors.push(format!("Calendario: {}", e)))
+        .ok();
+    let settings = local_storage::load_app_settings()
+        .map(|settings| settings.redacted())
+        .map_err(|e| errors.push(format!("Impostazioni: {}", e)))
+        .ok();
+    let recent_logs = logging::recent_lines(DIAGNOSTIC_LOG_LINES).unwrap_or_else(|e| {
+        errors.push(format!("Log: {}", e));
+        Vec::new()
+    });
+
+    let report = DiagnosticReport {
+        generated_at: get_timestamp(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: sysinfo::System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+        arch: std::env::consts::ARCH.to_string(),
+        ollama_url: state.ollama_url.lock().await.clone(),
+        backend,
+        backend_reachable,
+        models,
+        calendar,
+        settings,
+        recent_logs,
+        errors,
+    };
+
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Impossibile salvare il report: {}", e))?;
+    tracing::info!("Report diagnostico salvato in {}", path);
+    Ok(path)
+}
+
 // ============ CALENDAR COMMANDS ============
 
 fn parse_datetime(value: &str) -> Result<DateTime<Utc>, String> {
@@ -954,22 +2299,37 @@ fn load_calendar_events() -> Result<Vec<CalendarEvent>, String> {
     local_storage::load_calendar_events().map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-fn add_calendar_event(event: CalendarEventInput) -> Result<String, String> {
+/// Stored event for an input coming from the UI
+fn calendar_event_from_input(
+    event: CalendarEventInput,
+    id: String,
+    created_at: DateTime<Utc>,
+) -> Result<CalendarEvent, String> {
     let start = parse_datetime(&event.start)?;
     let end = match event.end {
         Some(ref end_str) if !end_str.is_empty() => Some(parse_datetime(end_str)?),
         _ => None,
     };
 
-    local_storage::add_calendar_event(
-        event.title,
-        event.description,
+    Ok(CalendarEvent {
+        id,
+        title: event.title,
+        description: event.description,
         start,
         end,
-        event.source_text,
-    )
-    .map_err(|e| e.to_string())
+        source_text: event.source_text,
+        location: event.location,
+        reminder_minutes: event.reminder_minutes,
+        recurrence: event.recurrence,
+        created_at,
+        updated_at: Utc::now(),
+    })
+}
+
+#[tauri::command]
+fn add_calendar_event(event: CalendarEventInput) -> Result<String, String> {
+    let event = calendar_event_from_input(event, uuid::Uuid::new_v4().to_string(), Utc::now())?;
+    local_storage::add_calendar_event(event).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -978,11 +2338,6 @@ fn update_calendar_event(event: CalendarEventInput) -> Result<(), String> {
         .id
         .clone()
         .ok_or_else(|| "ID evento mancante".to_string())?;
-    let start = parse_datetime(&event.start)?;
-    let end = match event.end {
-        Some(ref end_str) if !end_str.is_empty() => Some(parse_datetime(end_str)?),
-        _ => None,
-    };
 
     let current_events = local_storage::load_calendar_events().map_err(|e| e.to_string())?;
     let original = current_events
@@ -990,20 +2345,33 @@ fn update_calendar_event(event: CalendarEventInput) -> Result<(), String> {
         .find(|ev| ev.id == id)
         .ok_or_else(|| "Evento non trovato".to_string())?;
 
-    let updated = CalendarEvent {
-        id: original.id,
-        title: event.title,
-        description: event.description,
-        start,
-        end,
-        source_text: event.source_text,
-        created_at: original.created_at,
-        updated_at: Utc::now(),
-    };
-
+    let updated = calendar_event_from_input(event, original.id, original.created_at)?;
     local_storage::update_calendar_event(updated).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn get_calendar_occurrences(from: String, to: String) -> Result<Vec<CalendarOccurrence>, String> {
+    let from = parse_datetime(&from)?;
+    let to = parse_datetime(&to)?;
+    local_storage::calendar_occurrences(from, to).map_err(|e| e.to_string())
+}
+
+/// Turns the reminders of the calendar events on or off, optionally changing
+/// the default advance
+#[tauri::command]
+fn set_event_notifications(
+    enabled: bool,
+    default_reminder_minutes: Option<u32>,
+) -> Result<EventNotificationSettings, String> {
+    let mut settings = local_storage::load_app_settings().map_err(|e| e.to_string())?;
+    settings.event_notifications.enabled = enabled;
+    if let Some(minutes) = default_reminder_minutes {
+        settings.event_notifications.default_reminder_minutes = minutes;
+    }
+    local_storage::save_app_settings(&settings).map_err(|e| e.to_string())?;
+    Ok(settings.event_notifications)
+}
+
 #[tauri::command]
 fn delete_calendar_event(id: String) -> Result<(), String> {
     local_storage::delete_calendar_event(&id).map_err(|e| e.to_string())
@@ -1014,6 +2382,16 @@ fn clear_calendar_events() -> Result<(), String> {
     local_storage::clear_calendar_events().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn parse_event_from_text(text: String) -> Option<CalendarEventInput> {
+    event_parser::parse_event_from_text(&text)
+}
+
+#[tauri::command]
+fn detect_calendar_event(text: String) -> Option<CalendarEventInput> {
+    event_parser::detect_event_in_message(&text)
+}
+
 #[tauri::command]
 fn export_calendar_to_ics() -> Result<String, String> {
     local_storage::export_calendar_to_ics().map_err(|e| e.to_string())
@@ -1053,39 +2431,79 @@ fn disconnect_google_calendar() -> Result<CalendarIntegrationStatus, String> {
     calendar_integration::disconnect_google().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn validate_calendar_credentials(
+    provider: String,
+) -> Result<CalendarCredentialsCheck, String> {
+    calendar_integration::validate_calendar_credentials(&provider)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_available_calendars(provider: String) -> Result<Vec<RemoteCalendar>, String> {
+    calendar_integration::list_available_calendars(&provider)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn select_calendar(
+    provider: String,
+    calendar_id: Option<String>,
+) -> Result<CalendarIntegrationStatus, String> {
+    calendar_integration::select_calendar(&provider, calendar_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn start_outlook_calendar_device_flow() -> Result<OutlookDeviceFlowStart, String> {
     calendar_integration::start_outlook_device_flow()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| {
+            tracing::error!("Avvio dell'autorizzazione Outlook fallito: {}", e);
+            e.to_string()
+        })
 }
 
 #[tauri::command]
 async fn start_google_calendar_device_flow() -> Result<OutlookDeviceFlowStart, String> {
     calendar_integration::start_google_device_flow()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| {
+            tracing::error!("Avvio dell'autorizzazione Google fallito: {}", e);
+            e.to_string()
+        })
 }
 
 #[tauri::command]
 async fn poll_outlook_calendar_device_flow() -> Result<OutlookDeviceFlowPoll, String> {
     calendar_integration::poll_outlook_device_flow()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| {
+            tracing::error!("Autorizzazione Outlook fallita: {}", e);
+            e.to_string()
+        })
 }
 
 #[tauri::command]
 async fn poll_google_calendar_device_flow() -> Result<OutlookDeviceFlowPoll, String> {
     calendar_integration::poll_google_device_flow()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| {
+            tracing::error!("Autorizzazione Google fallita: {}", e);
+            e.to_string()
+        })
 }
 
 #[tauri::command]
 async fn list_outlook_calendar_events(
     limit: Option<usize>,
+    start_date: Option<String>,
+    end_date: Option<String>,
 ) -> Result<Vec<RemoteCalendarEvent>, String> {
-    calendar_integration::list_outlook_events(limit.unwrap_or(10))
+    let start_date = start_date.as_deref().map(parse_datetime).transpose()?;
+    let end_date = end_date.as_deref().map(parse_datetime).transpose()?;
+    calendar_integration::list_outlook_events(limit.unwrap_or(10), start_date, end_date)
         .await
         .map_err(|e| e.to_string())
 }
@@ -1093,8 +2511,12 @@ async fn list_outlook_calendar_events(
 #[tauri::command]
 async fn list_google_calendar_events(
     limit: Option<usize>,
+    start_date: Option<String>,
+    end_date: Option<String>,
 ) -> Result<Vec<RemoteCalendarEvent>, String> {
-    calendar_integration::list_google_events(limit.unwrap_or(10))
+    let start_date = start_date.as_deref().map(parse_datetime).transpose()?;
+    let end_date = end_date.as_deref().map(parse_datetime).transpose()?;
+    calendar_integration::list_google_events(limit.unwrap_or(10), start_date, end_date)
         .await
         .map_err(|e| e.to_string())
 }
@@ -1156,6 +2578,109 @@ async fn is_google_calendar_connected() -> Result<bool, String> {
         .map_err(|e| e.to_string())
 }
 
+// ============ VOICE COMMANDS ============
+
+#[cfg(not(feature = "voice"))]
+const VOICE_DISABLED: &str =
+    "Dettatura non disponibile: questa versione di MatePro è compilata senza la funzione voice";
+
+/// Start recording from the default microphone
+#[cfg(feature = "voice")]
+#[tauri::command]
+async fn start_voice_recording(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let mut recorder = state.voice_recorder.lock().await;
+    if recorder.is_some() {
+        return Err("Registrazione già in corso".to_string());
+    }
+    *recorder = Some(dictation::Recorder::start().map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+#[cfg(not(feature = "voice"))]
+#[tauri::command]
+async fn start_voice_recording() -> Result<(), String> {
+    Err(VOICE_DISABLED.to_string())
+}
+
+/// Stop the recording and transcribe it with Whisper in the configured language
+#[cfg(feature = "voice")]
+#[tauri::command]
+async fn stop_voice_recording(state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    let recorder = state
+        .voice_recorder
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| "Nessuna registrazione in corso".to_string())?;
+    let recording = recorder.stop();
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || dictation::transcribe(&recording, &settings.voice))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            tracing::warn!("Trascrizione vocale fallita: {}", e);
+            e.to_string()
+        })
+}
+
+#[cfg(not(feature = "voice"))]
+#[tauri::command]
+async fn stop_voice_recording() -> Result<String, String> {
+    Err(VOICE_DISABLED.to_string())
+}
+
+/// Stop the recording discarding the audio
+#[cfg(feature = "voice")]
+#[tauri::command]
+async fn cancel_voice_recording(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(recorder) = state.voice_recorder.lock().await.take() {
+        recorder.stop();
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "voice"))]
+#[tauri::command]
+async fn cancel_voice_recording() -> Result<(), String> {
+    Ok(())
+}
+
+/// Read a reply aloud, stopping the one currently playing. Returns when the
+/// reading ends or is stopped with `stop_speaking`.
+#[tauri::command]
+async fn speak_text(state: State<'_, Arc<AppState>>, text: String) -> Result<(), String> {
+    let text = speech::speakable_text(&text);
+    if text.is_empty() {
+        return Err("Nessun testo da leggere".to_string());
+    }
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+    let language = speech::read_aloud_language(&settings.voice, &text);
+
+    let (stop_tx, stop_rx) = oneshot::channel();
+    if let Some(previous) = state.speech_playback.lock().await.replace(stop_tx) {
+        let _ = previous.send(());
+    }
+
+    let result = speech::speak(&text, &language, stop_rx).await;
+
+    // Forget the handle unless another reading has already replaced it
+    let mut playback = state.speech_playback.lock().await;
+    if playback.as_ref().is_some_and(|stop| stop.is_closed()) {
+        *playback = None;
+    }
+    result.map_err(|e| e.to_string())
+}
+
+/// Stop reading aloud
+#[tauri::command]
+async fn stop_speaking(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(stop) = state.speech_playback.lock().await.take() {
+        let _ = stop.send(());
+    }
+    Ok(())
+}
+
 // ============ AICONNECT COMMANDS ============
 
 /// Discovery result for AIConnect and Ollama services
@@ -1163,18 +2688,58 @@ async fn is_google_calendar_connected() -> Result<bool, String> {
 struct DiscoveryResult {
     aiconnect_found: bool,
     aiconnect_services: Vec<DiscoveredService>,
-    ollama_servers: Vec<String>,
+    ollama_servers: Vec<DiscoveredOllama>,
     recommended_backend: BackendKind,
 }
 
-/// Scan network for AIConnect and Ollama services
+/// Ollama server with the discovery methods that found it ("mdns", "scan")
+#[derive(Debug, Clone, Serialize)]
+struct DiscoveredOllama {
+    url: String,
+    sources: Vec<String>,
+}
+
+/// Key identifying the machine behind a server URL: the same server is often found
+/// as `http://hostname:11434` via mDNS and as `http://192.168.1.5:11434` by the scan.
+/// The local machine's addresses all map to the loopback one.
+async fn server_identity(url: &str, local_ip: Option<IpAddr>) -> String {
+    let Some((host, port)) = url::Url::parse(url).ok().and_then(|parsed| {
+        let host = parsed.host_str()?.trim_matches(['[', ']']).to_string();
+        Some((host, parsed.port_or_known_default()?))
+    }) else {
+        return url.to_string();
+    };
+
+    let resolved = match tokio::net::lookup_host((host.as_str(), port)).await {
+        Ok(addrs) => {
+            let addrs: Vec<_> = addrs.collect();
+            addrs
+                .iter()
+                .find(|addr| addr.is_ipv4())
+                .or(addrs.first())
+                .map(|addr| addr.ip())
+        }
+        Err(_) => None,
+    };
+
+    match resolved {
+        Some(ip) if ip.is_loopback() || Some(ip) == local_ip => format!("127.0.0.1:{}", port),
+        Some(ip) => format!("{}:{}", ip, port),
+        None => format!("{}:{}", host.to_lowercase(), port),
+    }
+}
+
+/// Scan network for AIConnect and Ollama services. `max_concurrency` limits the
+/// subnet scan as in `scan_network`
 #[tauri::command]
-async fn scan_services() -> DiscoveryResult {
+async fn scan_services(app: AppHandle, max_concurrency: Option<usize>) -> DiscoveryResult {
     use std::time::Duration;
 
     let mut aiconnect_services = Vec::new();
-    let mut ollama_servers = Vec::new();
+    let mut ollama_servers: Vec<DiscoveredOllama> = Vec::new();
+    let mut identities: Vec<String> = Vec::new();
     let mut aiconnect_found = false;
+    let local_ip = local_ip_address::local_ip().ok();
 
     // Try mDNS discovery for AIConnect (with 2 second timeout)
     if let Ok(services) = aiconnect::discover_aiconnect(Duration::from_secs(2)).await {
@@ -1182,21 +2747,40 @@ async fn scan_services() -> DiscoveryResult {
         aiconnect_found = !aiconnect_services.is_empty();
     }
 
+    let mut candidates = Vec::new();
+
     // Discover Ollama instances advertised via mDNS
     if let Ok(services) = aiconnect::discover_ollama(Duration::from_secs(2)).await {
         for service in services {
             let url = service.base_url();
-            if check_server(&url).await && !ollama_servers.contains(&url) {
-                ollama_servers.push(url);
+            if check_server(&url).await {
+                candidates.push((url, "mdns"));
             }
         }
     }
 
     // Fall back to subnet scan (includes localhost) to preserve legacy behaviour
-    let scanned_servers = scan_network().await;
-    for server in scanned_servers {
-        if !ollama_servers.contains(&server) {
-            ollama_servers.push(server);
+    for server in scan_network(app, max_concurrency).await {
+        candidates.push((server, "scan"));
+    }
+
+    // The first URL found for a server is kept, the other methods are recorded
+    for (url, source) in candidates {
+        let identity = server_identity(&url, local_ip).await;
+        match identities.iter().position(|known| *known == identity) {
+            Some(idx) => {
+                let sources = &mut ollama_servers[idx].sources;
+                if !sources.iter().any(|known| known == source) {
+                    sources.push(source.to_string());
+                }
+            }
+            None => {
+                identities.push(identity);
+                ollama_servers.push(DiscoveredOllama {
+                    url,
+                    sources: vec![source.to_string()],
+                });
+            }
         }
     }
 
@@ -1226,8 +2810,13 @@ async fn get_backend_config(state: State<'_, Arc<AppState>>) -> Result<BackendCo
 #[tauri::command]
 async fn set_backend_config(
     state: State<'_, Arc<AppState>>,
-    config: BackendConfig,
+    mut config: BackendConfig,
 ) -> Result<(), String> {
+    if config.kind == BackendKind::OllamaLocal {
+        config.endpoint =
+            aiconnect::normalize_ollama_url(&config.endpoint).map_err(|e| e.to_string())?;
+    }
+
     // Update the backend config
     {
         let mut backend = state.backend_config.lock().await;
@@ -1271,8 +2860,17 @@ async fn connect_aiconnect(
     };
 
     // Check if AIConnect is reachable
-    if !aiconnect::check_aiconnect_health(&endpoint, &auth).await {
-        return Err("Impossibile connettersi ad AIConnect".to_string());
+    let diagnostic = aiconnect::check_aiconnect_health(&endpoint, &auth).await;
+    if !diagnostic.ok {
+        tracing::warn!(
+            "Connessione ad AIConnect {} fallita: {}",
+            endpoint,
+            diagnostic.message
+        );
+        return Err(format!(
+            "Impossibile connettersi ad AIConnect: {}",
+            diagnostic.message
+        ));
     }
 
     // Update configuration
@@ -1327,7 +2925,9 @@ async fn check_backend_health(state: State<'_, Arc<AppState>>) -> Result<bool, S
 
     let is_healthy = match config.kind {
         BackendKind::AiConnect => {
-            aiconnect::check_aiconnect_health(&config.endpoint, &config.auth).await
+            aiconnect::check_aiconnect_health(&config.endpoint, &config.auth)
+                .await
+                .ok
         }
         BackendKind::OllamaLocal => aiconnect::check_ollama_health(&config.endpoint).await,
     };
@@ -1335,6 +2935,53 @@ async fn check_backend_health(state: State<'_, Arc<AppState>>) -> Result<bool, S
     Ok(is_healthy)
 }
 
+/// Test the configured backend and explain why the connection fails: HTTP status,
+/// rejected credentials, TLS errors or responses that don't come from the backend
+#[tauri::command]
+async fn diagnose_backend_connection(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ConnectionDiagnostic, String> {
+    let config = state.backend_config.lock().await.clone();
+
+    let diagnostic = match config.kind {
+        BackendKind::AiConnect => {
+            aiconnect::check_aiconnect_health(&config.endpoint, &config.auth).await
+        }
+        BackendKind::OllamaLocal => aiconnect::diagnose_ollama(&config.endpoint).await,
+    };
+
+    if !diagnostic.ok {
+        tracing::warn!(
+            "Test di connessione a {} fallito: {}",
+            diagnostic.url,
+            diagnostic.message
+        );
+    }
+    Ok(diagnostic)
+}
+
+/// Backend and model used last time. The frontend applies it with
+/// `set_backend_config` and skips the network scan if `check_backend_health` passes.
+#[tauri::command]
+fn load_last_backend() -> Result<Option<LastBackend>, String> {
+    local_storage::load_last_backend().map_err(|e| e.to_string())
+}
+
+/// Remember the current backend and the selected model for the next startup
+#[tauri::command]
+async fn save_last_backend(
+    state: State<'_, Arc<AppState>>,
+    selected_model: Option<String>,
+) -> Result<(), String> {
+    let backend = state.backend_config.lock().await.clone();
+    local_storage::save_last_backend(&LastBackend {
+        backend,
+        selected_model,
+        saved_at: Utc::now(),
+    })
+    .map_err(|e| e.to_string())
+}
+
 /// Auto-discover and configure the best available backend
 #[tauri::command]
 async fn auto_configure(state: State<'_, Arc<AppState>>) -> Result<BackendConfig, String> {
@@ -1363,50 +3010,111 @@ async fn auto_configure(state: State<'_, Arc<AppState>>) -> Result<BackendConfig
 // ============ MAIN ============
 
 fn main() {
+    // The proxy must be known before AppState builds its HTTP client
+    let settings = local_storage::load_app_settings().unwrap_or_default();
+    let _ = logging::init(&settings.log_level);
+    http_client::set_proxy_settings(settings.proxy);
+    tracing::info!("MatePro {} avviato", env!("CARGO_PKG_VERSION"));
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(Arc::new(AppState::default()))
+        .setup(|app| {
+            // Without the watcher external changes are only seen after a restart
+            if let Err(e) = storage_watcher::watch_data_dir(app.handle().clone()) {
+                tracing::warn!("Monitoraggio della cartella dati non disponibile: {}", e);
+            }
+            event_notifier::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_network,
+            normalize_ollama_url,
             connect_to_server,
             list_models,
+            show_model,
             chat,
             read_file,
+            get_pdf_metadata,
+            index_document,
+            retrieve_chunks,
+            generate_embedding,
+            generate_embeddings,
+            semantic_search,
+            list_spreadsheet_sheets,
             get_tools_description,
             parse_tool_calls,
             execute_tool,
             set_allow_dangerous,
             check_tool_dangerous,
+            dry_run_tool,
+            undo_last_file_operation,
+            get_file_undo_depth,
             sql_connect,
             sql_query,
             sql_list_tables,
             sql_describe_table,
+            sql_schema,
+            sql_explain,
             sql_disconnect,
             get_timestamp_cmd,
             get_app_version,
             get_user_profile,
+            get_user_profile_prompt,
             check_for_updates,
+            check_for_updates_on_startup,
             download_and_install_update,
             // Local storage commands
             load_memory,
             save_memory,
             load_custom_system_prompt,
             save_custom_system_prompt,
+            load_app_settings,
+            save_app_settings,
             add_conversation_to_memory,
             update_conversation_in_memory,
             delete_conversation_from_memory,
+            rename_conversation,
+            delete_message_from_conversation,
             clear_all_conversations,
+            export_conversations_jsonl,
+            export_conversation_html,
+            import_conversations,
+            list_memory_archives,
+            load_memory_archive,
+            load_conversation_draft,
+            save_conversation_draft,
+            clear_conversation_draft,
             get_data_directory,
+            set_data_directory,
+            get_storage_stats,
+            cleanup_storage,
+            get_log_path,
+            set_log_level,
+            generate_diagnostic_report,
+            start_voice_recording,
+            stop_voice_recording,
+            cancel_voice_recording,
+            speak_text,
+            stop_speaking,
             // Calendar commands
             load_calendar_events,
             add_calendar_event,
             update_calendar_event,
+            get_calendar_occurrences,
+            set_event_notifications,
             delete_calendar_event,
             clear_calendar_events,
             export_calendar_to_ics,
+            parse_event_from_text,
+            detect_calendar_event,
             get_calendar_integrations_status,
+            validate_calendar_credentials,
+            list_available_calendars,
+            select_calendar,
             set_outlook_calendar_credentials,
             disconnect_outlook_calendar,
             start_outlook_calendar_device_flow,
@@ -1429,8 +3137,44 @@ fn main() {
             connect_aiconnect,
             get_aiconnect_nodes,
             check_backend_health,
+            diagnose_backend_connection,
             auto_configure,
+            load_last_backend,
+            save_last_backend,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_parse_sha256_checksum() {
+        let asset = "MatePro_0.0.16_amd64.deb";
+
+        // Formato `sha256sum`, anche con l'hash in maiuscolo
+        let text = format!("{}  {}\n", HASH.to_uppercase(), asset);
+        assert_eq!(parse_sha256_checksum(&text, asset), Some(HASH.to_string()));
+
+        // Modalità binaria: il nome è preceduto da `*`
+        let text = format!("{} *{}\n", HASH, asset);
+        assert_eq!(parse_sha256_checksum(&text, asset), Some(HASH.to_string()));
+
+        // Solo l'hash
+        assert_eq!(parse_sha256_checksum(HASH, asset), Some(HASH.to_string()));
+
+        // Hash di un altro file
+        let text = format!("{}  MatePro_0.0.16_x86_64.rpm\n", HASH);
+        assert_eq!(parse_sha256_checksum(&text, asset), None);
+
+        // Hash troncato o con caratteri non esadecimali
+        let text = format!("{}  {}\n", &HASH[..63], asset);
+        assert_eq!(parse_sha256_checksum(&text, asset), None);
+        let text = format!("{}z  {}\n", &HASH[..63], asset);
+        assert_eq!(parse_sha256_checksum(&text, asset), None);
+    }
+}
@@ -5,12 +5,15 @@
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::{NoExpand, Regex};
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{Number, Value};
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tiberius::{AuthMethod, Client, Config, Query};
 use tiberius::{ColumnType, Row};
 use tokio::net::TcpStream;
@@ -30,9 +33,219 @@ pub struct QueryResult {
     pub rows: Vec<HashMap<String, Value>>,
 }
 
+/// Column of a table in the database schema overview
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaColumn {
+    pub name: String,
+    /// Type with its length, e.g. nvarchar(50)
+    pub data_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+    /// Referenced columns as schema.table.column
+    pub references: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaTable {
+    pub schema: String,
+    pub name: String,
+    pub is_view: bool,
+    pub columns: Vec<SchemaColumn>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DatabaseSchema {
+    pub tables: Vec<SchemaTable>,
+    /// Tables found before applying the limit
+    pub total_tables: usize,
+}
+
+/// Regex whose matches are hidden in query results before they reach the model
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MaskingPattern {
+    pub name: String,
+    pub pattern: String,
+    pub replacement: String,
+}
+
+/// Emails, Italian tax codes, IBANs and long numbers such as card numbers
+pub fn default_masking_patterns() -> Vec<MaskingPattern> {
+    [
+        (
+            "email",
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            "***@***",
+        ),
+        (
+            "codice_fiscale",
+            r"(?i)\b[A-Z]{6}[0-9LMNP-V]{2}[A-EHLMPR-T][0-9LMNP-V]{2}[A-Z][0-9LMNP-V]{3}[A-Z]\b",
+            "****",
+        ),
+        ("iban", iban_pattern().as_str(), "****"),
+        ("numero_lungo", r"\b\d(?:[ -]?\d){11,18}\b", "****"),
+    ]
+    .into_iter()
+    .map(|(name, pattern, replacement)| MaskingPattern {
+        name: name.to_string(),
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+    })
+    .collect()
+}
+
+/// Country code and total length of the IBANs of the SEPA countries
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AD", 24),
+    ("AT", 20),
+    ("BE", 16),
+    ("BG", 22),
+    ("CH", 21),
+    ("CY", 28),
+    ("CZ", 24),
+    ("DE", 22),
+    ("DK", 18),
+    ("EE", 20),
+    ("ES", 24),
+    ("FI", 18),
+    ("FR", 27),
+    ("GB", 22),
+    ("GI", 23),
+    ("GR", 27),
+    ("HR", 21),
+    ("HU", 28),
+    ("IE", 22),
+    ("IS", 26),
+    ("IT", 27),
+    ("LI", 21),
+    ("LT", 20),
+    ("LU", 20),
+    ("LV", 21),
+    ("MC", 27),
+    ("MT", 31),
+    ("NL", 18),
+    ("NO", 15),
+    ("PL", 28),
+    ("PT", 25),
+    ("RO", 24),
+    ("SE", 24),
+    ("SI", 19),
+    ("SK", 24),
+    ("SM", 27),
+    ("VA", 22),
+];
+
+/// Uppercase IBANs with a known country code and its exact length, written
+/// compact or in groups of four ("IT60 X054 2811 ...")
+fn iban_pattern() -> String {
+    let countries: Vec<String> = IBAN_LENGTHS
+        .iter()
+        .map(|(country, length)| {
+            let bban = length - 4;
+            let mut pattern = format!(r"{}[0-9]{{2}}(?:\s?[A-Z0-9]{{4}}){{{}}}", country, bban / 4);
+            if bban % 4 > 0 {
+                pattern.push_str(&format!(r"\s?[A-Z0-9]{{{}}}", bban % 4));
+            }
+            pattern
+        })
+        .collect();
+    format!(r"\b(?:{})\b", countries.join("|"))
+}
+
+/// Replaces the sensitive values of the result in place and returns how many
+/// cells changed. Numbers that match become strings. Invalid patterns are skipped.
+pub fn mask_query_result(result: &mut QueryResult, patterns: &[MaskingPattern]) -> usize {
+    let rules: Vec<(Regex, &str)> = patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(&pattern.pattern) {
+            Ok(regex) => Some((regex, pattern.replacement.as_str())),
+            Err(e) => {
+                tracing::warn!(
+                    "Pattern di mascheramento '{}' non valido: {}",
+                    pattern.name,
+                    e
+                );
+                None
+            }
+        })
+        .collect();
+
+    let mut masked = 0;
+    for value in result.rows.iter_mut().flat_map(|row| row.values_mut()) {
+        let original = match value {
+            Value::String(text) => text.clone(),
+            Value::Number(number) => number.to_string(),
+            _ => continue,
+        };
+
+        let mut text = original.clone();
+        for (regex, replacement) in &rules {
+            text = regex.replace_all(&text, NoExpand(replacement)).into_owned();
+        }
+        if text != original {
+            *value = Value::String(text);
+            masked += 1;
+        }
+    }
+
+    masked
+}
+
+/// Operator of an estimated execution plan
+#[derive(Clone, Debug, Serialize)]
+pub struct PlanStep {
+    /// Operator text with the tree indentation of SHOWPLAN ("  |--Index Seek(...)")
+    pub text: String,
+    pub physical_op: Option<String>,
+    pub estimate_rows: Option<f64>,
+    /// Estimated cost of the operator and of its inputs
+    pub subtree_cost: Option<f64>,
+}
+
+/// Estimated plan of one statement of the query
+#[derive(Clone, Debug, Serialize)]
+pub struct QueryPlan {
+    pub statement: String,
+    pub total_cost: Option<f64>,
+    pub steps: Vec<PlanStep>,
+}
+
+/// Database engine behind a connection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbType {
+    #[default]
+    SqlServer,
+    Postgres,
+    MySql,
+}
+
+impl DbType {
+    /// Accepts the serialized names plus the common aliases ("mssql", "postgresql", ...)
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "sqlserver" | "mssql" => Ok(DbType::SqlServer),
+            "postgres" | "postgresql" | "pg" => Ok(DbType::Postgres),
+            "mysql" | "mariadb" => Ok(DbType::MySql),
+            other => Err(anyhow!(
+                "Tipo di database non supportato: '{}' (usa 'sqlserver', 'postgres' o 'mysql')",
+                other
+            )),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DbType::SqlServer => "SQL Server",
+            DbType::Postgres => "PostgreSQL",
+            DbType::MySql => "MySQL",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SqlConnection {
     pub connection_id: String,
+    pub db_type: DbType,
     pub server: String,
     pub database: String,
     pub auth_type: String,
@@ -210,12 +423,15 @@ fn float_value(row: &Row, idx: usize) -> Result<Option<Value>> {
         .map(Value::Number))
 }
 
+/// Decimals that do not fit a JSON number are kept as text
+pub(crate) fn decimal_to_json(decimal: Decimal) -> Value {
+    try_number_from_decimal(decimal)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(decimal.to_string()))
+}
+
 fn decimal_value(row: &Row, idx: usize) -> Result<Option<Value>> {
-    Ok(row.try_get::<Decimal, _>(idx)?.map(|decimal| {
-        try_number_from_decimal(decimal)
-            .map(Value::Number)
-            .unwrap_or_else(|| Value::String(decimal.to_string()))
-    }))
+    Ok(row.try_get::<Decimal, _>(idx)?.map(decimal_to_json))
 }
 
 fn string_value(row: &Row, idx: usize) -> Result<Option<Value>> {
@@ -333,9 +549,36 @@ pub async fn connect_sql_auth(
     Ok(client)
 }
 
+/// Applied to the queries when the caller does not choose a timeout
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub async fn run_query(client: &mut SqlClient, query: &str) -> Result<QueryResult> {
+    run_query_with_timeout(client, query, DEFAULT_QUERY_TIMEOUT).await
+}
+
+/// Runs a read-only query, giving up after `timeout`. The abandoned request leaves
+/// the client unusable: callers open a connection per query and drop it, and
+/// closing the connection makes the server abort the statement.
+pub async fn run_query_with_timeout(
+    client: &mut SqlClient,
+    query: &str,
+    timeout: Duration,
+) -> Result<QueryResult> {
     validate_readonly_query(query)?;
 
+    with_query_timeout(timeout, fetch_query_result(client, query)).await
+}
+
+pub(crate) async fn with_query_timeout<T>(
+    timeout: Duration,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::time::timeout(timeout, future)
+        .await
+        .map_err(|_| anyhow!("Query scaduta dopo {}s", timeout.as_secs()))?
+}
+
+async fn fetch_query_result(client: &mut SqlClient, query: &str) -> Result<QueryResult> {
     let mut stream = Query::new(query).query(client).await?;
 
     let schema: Vec<tiberius::Column> = stream
@@ -409,28 +652,356 @@ pub async fn describe_table(
     run_query(client, &query).await
 }
 
-pub async fn connect_with_info(conn: &SqlConnection) -> Result<SqlClient> {
-    if conn.auth_type == "windows" {
-        connect_windows_auth(&conn.server, &conn.database, conn.trust_server_certificate).await
-    } else {
-        let username = conn
-            .username
-            .as_deref()
-            .ok_or_else(|| anyhow!("Username mancante per connessione SQL"))?;
-        let password = conn
-            .password
-            .as_deref()
-            .ok_or_else(|| anyhow!("Password mancante per connessione SQL"))?;
-
-        connect_sql_auth(
-            &conn.server,
-            &conn.database,
-            username,
-            password,
-            conn.trust_server_certificate,
-        )
-        .await
+/// Tables returned by `database_schema`, to keep large databases within the context
+pub const SCHEMA_DEFAULT_TABLES: usize = 100;
+pub const SCHEMA_MAX_TABLES: usize = 500;
+
+/// Tables, columns, primary keys and foreign keys in a single query. Only the
+/// first `max_tables` tables are returned.
+pub async fn database_schema(
+    client: &mut SqlClient,
+    schema: Option<&str>,
+    max_tables: usize,
+) -> Result<DatabaseSchema> {
+    let schema_filter = schema
+        .map(|schema| format!("AND c.TABLE_SCHEMA = '{}'", schema.replace('\'', "''")))
+        .unwrap_or_default();
+    let query = format!(
+        r#"
+        SELECT
+            c.TABLE_SCHEMA as [Schema],
+            c.TABLE_NAME as [Table],
+            t.TABLE_TYPE as [TableType],
+            c.COLUMN_NAME as [Column],
+            c.DATA_TYPE as [Type],
+            c.CHARACTER_MAXIMUM_LENGTH as [MaxLength],
+            c.IS_NULLABLE as [Nullable],
+            CASE WHEN pk.COLUMN_NAME IS NULL THEN 0 ELSE 1 END as [PrimaryKey],
+            fk.REF_SCHEMA as [RefSchema],
+            fk.REF_TABLE as [RefTable],
+            fk.REF_COLUMN as [RefColumn]
+        FROM INFORMATION_SCHEMA.COLUMNS c
+        JOIN INFORMATION_SCHEMA.TABLES t
+            ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME
+        LEFT JOIN (
+            SELECT ku.TABLE_SCHEMA, ku.TABLE_NAME, ku.COLUMN_NAME
+            FROM INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc
+            JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE ku
+                ON ku.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA
+                AND ku.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+            WHERE tc.CONSTRAINT_TYPE = 'PRIMARY KEY'
+        ) pk
+            ON pk.TABLE_SCHEMA = c.TABLE_SCHEMA
+            AND pk.TABLE_NAME = c.TABLE_NAME
+            AND pk.COLUMN_NAME = c.COLUMN_NAME
+        LEFT JOIN (
+            SELECT
+                fku.TABLE_SCHEMA, fku.TABLE_NAME, fku.COLUMN_NAME,
+                pku.TABLE_SCHEMA as REF_SCHEMA,
+                pku.TABLE_NAME as REF_TABLE,
+                pku.COLUMN_NAME as REF_COLUMN
+            FROM INFORMATION_SCHEMA.REFERENTIAL_CONSTRAINTS rc
+            JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE fku
+                ON fku.CONSTRAINT_SCHEMA = rc.CONSTRAINT_SCHEMA
+                AND fku.CONSTRAINT_NAME = rc.CONSTRAINT_NAME
+            JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE pku
+                ON pku.CONSTRAINT_SCHEMA = rc.UNIQUE_CONSTRAINT_SCHEMA
+                AND pku.CONSTRAINT_NAME = rc.UNIQUE_CONSTRAINT_NAME
+                AND pku.ORDINAL_POSITION = fku.ORDINAL_POSITION
+        ) fk
+            ON fk.TABLE_SCHEMA = c.TABLE_SCHEMA
+            AND fk.TABLE_NAME = c.TABLE_NAME
+            AND fk.COLUMN_NAME = c.COLUMN_NAME
+        WHERE t.TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+        {}
+        ORDER BY c.TABLE_SCHEMA, c.TABLE_NAME, c.ORDINAL_POSITION
+        "#,
+        schema_filter
+    );
+
+    let result = run_query(client, &query).await?;
+    Ok(build_database_schema(&result.rows, max_tables))
+}
+
+/// Groups the rows of the schema query (one per column and foreign key) by table
+fn build_database_schema(rows: &[HashMap<String, Value>], max_tables: usize) -> DatabaseSchema {
+    let text = |row: &HashMap<String, Value>, key: &str| {
+        row.get(key)
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut tables: Vec<SchemaTable> = Vec::new();
+    let mut total_tables = 0;
+    let mut current: Option<(String, String)> = None;
+
+    for row in rows {
+        let key = (text(row, "Schema"), text(row, "Table"));
+        if current.as_ref() != Some(&key) {
+            total_tables += 1;
+            if tables.len() < max_tables {
+                tables.push(SchemaTable {
+                    schema: key.0.clone(),
+                    name: key.1.clone(),
+                    is_view: text(row, "TableType").eq_ignore_ascii_case("VIEW"),
+                    columns: Vec::new(),
+                });
+            }
+            current = Some(key);
+        }
+
+        // Rows of the tables beyond the limit are only counted
+        if tables.len() < total_tables {
+            continue;
+        }
+        let Some(table) = tables.last_mut() else {
+            continue;
+        };
+
+        let column_name = text(row, "Column");
+        let reference = match (
+            row.get("RefSchema").and_then(|v| v.as_str()),
+            row.get("RefTable").and_then(|v| v.as_str()),
+            row.get("RefColumn").and_then(|v| v.as_str()),
+        ) {
+            (Some(schema), Some(table), Some(column)) => {
+                Some(format!("{}.{}.{}", schema, table, column))
+            }
+            _ => None,
+        };
+
+        // A column in several foreign keys comes back once per key
+        if let Some(column) = table.columns.iter_mut().find(|c| c.name == column_name) {
+            column.references.extend(reference);
+            continue;
+        }
+
+        let mut data_type = text(row, "Type");
+        match row.get("MaxLength").and_then(|v| v.as_i64()) {
+            Some(-1) => data_type.push_str("(max)"),
+            Some(length) => data_type.push_str(&format!("({})", length)),
+            None => {}
+        }
+
+        table.columns.push(SchemaColumn {
+            name: column_name,
+            data_type,
+            nullable: text(row, "Nullable").eq_ignore_ascii_case("YES"),
+            primary_key: row.get("PrimaryKey").and_then(|v| v.as_i64()) == Some(1),
+            references: reference.into_iter().collect(),
+        });
+    }
+
+    DatabaseSchema {
+        tables,
+        total_tables,
+    }
+}
+
+/// Estimated execution plan of a read-only query. With SHOWPLAN_ALL on the
+/// statements are compiled but not executed.
+pub async fn explain_query(client: &mut SqlClient, query: &str) -> Result<Vec<QueryPlan>> {
+    validate_readonly_query(query)?;
+
+    // SET SHOWPLAN must be the only statement of its batch
+    client
+        .simple_query("SET SHOWPLAN_ALL ON")
+        .await?
+        .into_results()
+        .await?;
+    let result = match client.simple_query(query).await {
+        Ok(stream) => stream.into_results().await,
+        Err(e) => Err(e),
+    };
+    client
+        .simple_query("SET SHOWPLAN_ALL OFF")
+        .await?
+        .into_results()
+        .await?;
+
+    let mut plans = Vec::new();
+    for rows in result? {
+        if let Some(plan) = plan_from_rows(&rows_to_json(&rows)?) {
+            plans.push(plan);
+        }
     }
+    Ok(plans)
+}
+
+fn rows_to_json(rows: &[Row]) -> Result<Vec<HashMap<String, Value>>> {
+    rows.iter()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .enumerate()
+                .map(|(idx, column)| {
+                    let value = column_value_to_json(row, idx, column.column_type())?;
+                    Ok((column.name().to_string(), value))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// SHOWPLAN_ALL returns, for each statement, a row with the statement followed by
+/// one PLAN_ROW per operator
+fn plan_from_rows(rows: &[HashMap<String, Value>]) -> Option<QueryPlan> {
+    let text = |row: &HashMap<String, Value>| {
+        row.get("StmtText")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .trim_end()
+            .to_string()
+    };
+    let number = |row: &HashMap<String, Value>, key: &str| row.get(key).and_then(|v| v.as_f64());
+    let is_step = |row: &&HashMap<String, Value>| {
+        row.get("Type").and_then(|value| value.as_str()) == Some("PLAN_ROW")
+    };
+
+    let statement = rows.iter().find(|row| !is_step(row))?;
+    let steps = rows
+        .iter()
+        .filter(is_step)
+        .map(|row| PlanStep {
+            text: text(row),
+            physical_op: row
+                .get("PhysicalOp")
+                .and_then(|value| value.as_str())
+                .map(str::to_string),
+            estimate_rows: number(row, "EstimateRows"),
+            subtree_cost: number(row, "TotalSubtreeCost"),
+        })
+        .collect();
+
+    Some(QueryPlan {
+        statement: text(statement).trim().to_string(),
+        total_cost: number(statement, "TotalSubtreeCost"),
+        steps,
+    })
+}
+
+/// Operations every supported database engine provides. Queries are validated
+/// as read-only by each implementation before reaching the server.
+#[allow(async_fn_in_trait)]
+pub trait SqlBackend: Sized {
+    async fn connect(conn: &SqlConnection) -> Result<Self>;
+    async fn query(&mut self, query: &str, timeout: Duration) -> Result<QueryResult>;
+    async fn list_tables(&mut self) -> Result<QueryResult>;
+    async fn describe_table(&mut self, schema: &str, table_name: &str) -> Result<QueryResult>;
+}
+
+impl SqlBackend for SqlClient {
+    async fn connect(conn: &SqlConnection) -> Result<Self> {
+        if conn.auth_type == "windows" {
+            connect_windows_auth(&conn.server, &conn.database, conn.trust_server_certificate).await
+        } else {
+            let (username, password) = credentials(conn)?;
+
+            connect_sql_auth(
+                &conn.server,
+                &conn.database,
+                username,
+                password,
+                conn.trust_server_certificate,
+            )
+            .await
+        }
+    }
+
+    async fn query(&mut self, query: &str, timeout: Duration) -> Result<QueryResult> {
+        run_query_with_timeout(self, query, timeout).await
+    }
+
+    async fn list_tables(&mut self) -> Result<QueryResult> {
+        list_tables(self).await
+    }
+
+    async fn describe_table(&mut self, schema: &str, table_name: &str) -> Result<QueryResult> {
+        describe_table(self, schema, table_name).await
+    }
+}
+
+pub(crate) fn credentials(conn: &SqlConnection) -> Result<(&str, &str)> {
+    let username = conn
+        .username
+        .as_deref()
+        .ok_or_else(|| anyhow!("Username mancante per connessione SQL"))?;
+    let password = conn
+        .password
+        .as_deref()
+        .ok_or_else(|| anyhow!("Password mancante per connessione SQL"))?;
+    Ok((username, password))
+}
+
+/// Client of the engine selected by `SqlConnection::db_type`
+pub enum DbClient {
+    SqlServer(Box<SqlClient>),
+    Postgres(tokio_postgres::Client),
+    MySql(sqlx::MySqlConnection),
+}
+
+impl DbClient {
+    /// Schema overview and execution plans rely on SQL Server specific features
+    pub fn sql_server(&mut self) -> Result<&mut SqlClient> {
+        match self {
+            DbClient::SqlServer(client) => Ok(client),
+            DbClient::Postgres(_) => Err(anyhow!(
+                "Funzione disponibile solo per SQL Server (connessione PostgreSQL)"
+            )),
+            DbClient::MySql(_) => Err(anyhow!(
+                "Funzione disponibile solo per SQL Server (connessione MySQL)"
+            )),
+        }
+    }
+}
+
+impl SqlBackend for DbClient {
+    async fn connect(conn: &SqlConnection) -> Result<Self> {
+        Ok(match conn.db_type {
+            DbType::SqlServer => {
+                DbClient::SqlServer(Box::new(<SqlClient as SqlBackend>::connect(conn).await?))
+            }
+            DbType::Postgres => {
+                DbClient::Postgres(<tokio_postgres::Client as SqlBackend>::connect(conn).await?)
+            }
+            DbType::MySql => {
+                DbClient::MySql(<sqlx::MySqlConnection as SqlBackend>::connect(conn).await?)
+            }
+        })
+    }
+
+    async fn query(&mut self, query: &str, timeout: Duration) -> Result<QueryResult> {
+        match self {
+            DbClient::SqlServer(client) => SqlBackend::query(client.as_mut(), query, timeout).await,
+            DbClient::Postgres(client) => SqlBackend::query(client, query, timeout).await,
+            DbClient::MySql(client) => SqlBackend::query(client, query, timeout).await,
+        }
+    }
+
+    async fn list_tables(&mut self) -> Result<QueryResult> {
+        match self {
+            DbClient::SqlServer(client) => SqlBackend::list_tables(client.as_mut()).await,
+            DbClient::Postgres(client) => SqlBackend::list_tables(client).await,
+            DbClient::MySql(client) => SqlBackend::list_tables(client).await,
+        }
+    }
+
+    async fn describe_table(&mut self, schema: &str, table_name: &str) -> Result<QueryResult> {
+        match self {
+            DbClient::SqlServer(client) => {
+                SqlBackend::describe_table(client.as_mut(), schema, table_name).await
+            }
+            DbClient::Postgres(client) => {
+                SqlBackend::describe_table(client, schema, table_name).await
+            }
+            DbClient::MySql(client) => SqlBackend::describe_table(client, schema, table_name).await,
+        }
+    }
+}
+
+pub async fn connect_with_info(conn: &SqlConnection) -> Result<DbClient> {
+    DbClient::connect(conn).await
 }
 
 #[cfg(test)]
@@ -455,6 +1026,127 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_build_database_schema() {
+        let row =
+            |table: &str, column: &str, max_length: Value, pk: i64, reference: Option<&str>| {
+                let mut row = HashMap::new();
+                row.insert("Schema".to_string(), Value::from("dbo"));
+                row.insert("Table".to_string(), Value::from(table));
+                row.insert("TableType".to_string(), Value::from("BASE TABLE"));
+                row.insert("Column".to_string(), Value::from(column));
+                row.insert("Type".to_string(), Value::from("nvarchar"));
+                row.insert("MaxLength".to_string(), max_length);
+                row.insert("Nullable".to_string(), Value::from("NO"));
+                row.insert("PrimaryKey".to_string(), Value::from(pk));
+                if let Some(reference) = reference {
+                    row.insert("RefSchema".to_string(), Value::from("dbo"));
+                    row.insert("RefTable".to_string(), Value::from(reference));
+                    row.insert("RefColumn".to_string(), Value::from("Id"));
+                }
+                row
+            };
+        let rows = vec![
+            row("Clienti", "Id", Value::Null, 1, None),
+            row("Ordini", "Id", Value::Null, 1, None),
+            row("Ordini", "ClienteId", Value::Null, 0, Some("Clienti")),
+            row("Ordini", "ClienteId", Value::Null, 0, Some("Fornitori")),
+            row("Ordini", "Note", Value::from(-1), 0, None),
+            row("Prodotti", "Id", Value::Null, 1, None),
+            row("Prodotti", "Nome", Value::from(50), 0, None),
+        ];
+
+        let schema = build_database_schema(&rows, 2);
+        assert_eq!(schema.total_tables, 3);
+        assert_eq!(schema.tables.len(), 2);
+
+        let orders = &schema.tables[1];
+        assert_eq!(orders.columns.len(), 3);
+        assert!(orders.columns[0].primary_key);
+        assert_eq!(
+            orders.columns[1].references,
+            vec!["dbo.Clienti.Id", "dbo.Fornitori.Id"]
+        );
+        assert_eq!(orders.columns[2].data_type, "nvarchar(max)");
+    }
+
+    #[test]
+    fn test_plan_from_rows() {
+        let row = |text: &str, kind: &str, op: Value, cost: f64| {
+            let mut row = HashMap::new();
+            row.insert("StmtText".to_string(), Value::from(text));
+            row.insert("Type".to_string(), Value::from(kind));
+            row.insert("PhysicalOp".to_string(), op);
+            row.insert("EstimateRows".to_string(), Value::from(42.0));
+            row.insert("TotalSubtreeCost".to_string(), Value::from(cost));
+            row
+        };
+        let rows = vec![
+            row(
+                "SELECT * FROM Ordini WHERE ClienteId = 5\r\n",
+                "SELECT",
+                Value::Null,
+                0.5,
+            ),
+            row(
+                "  |--Clustered Index Scan(OBJECT:([Ordini].[PK_Ordini]))",
+                "PLAN_ROW",
+                Value::from("Clustered Index Scan"),
+                0.5,
+            ),
+        ];
+
+        let plan = plan_from_rows(&rows).unwrap();
+        assert_eq!(plan.statement, "SELECT * FROM Ordini WHERE ClienteId = 5");
+        assert_eq!(plan.total_cost, Some(0.5));
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(
+            plan.steps[0].physical_op.as_deref(),
+            Some("Clustered Index Scan")
+        );
+        assert!(plan.steps[0].text.starts_with("  |--"));
+        assert!(plan_from_rows(&[]).is_none());
+    }
+
+    #[test]
+    fn test_mask_query_result() {
+        let row = |value: Value| HashMap::from([("Valore".to_string(), value)]);
+        let mut result = QueryResult {
+            columns: Vec::new(),
+            rows: vec![
+                row(Value::from("Contatto: mario.rossi@example.com")),
+                row(Value::from("RSSMRA85T10A562S")),
+                row(Value::from("IT60X0542811101000000123456")),
+                row(Value::from("IT60 X054 2811 1010 0000 0123 456")),
+                row(Value::from("it60x0542811101000000123456")),
+                row(Value::from("XX60X0542811101000000123456")),
+                row(Value::from("IT60X05428111010000001234")),
+                row(Value::from("4111 1111 1111 1111")),
+                row(Value::from(4111111111111111u64)),
+                row(Value::from("Ordine 12345 del 2024-01-01 12:00:00")),
+                row(Value::from(42)),
+                row(Value::Null),
+            ],
+        };
+
+        let masked = mask_query_result(&mut result, &default_masking_patterns());
+        let values: Vec<&Value> = result.rows.iter().map(|row| &row["Valore"]).collect();
+        assert_eq!(masked, 6);
+        assert_eq!(values[0], "Contatto: ***@***");
+        assert_eq!(values[1], "****");
+        assert_eq!(values[2], "****");
+        assert_eq!(values[3], "****");
+        // Lowercase, unknown country or wrong length: not an IBAN
+        assert_eq!(values[4], "it60x0542811101000000123456");
+        assert_eq!(values[5], "XX60X0542811101000000123456");
+        assert_eq!(values[6], "IT60X05428111010000001234");
+        assert_eq!(values[7], "****");
+        assert_eq!(values[8], "****");
+        assert_eq!(values[9], "Ordine 12345 del 2024-01-01 12:00:00");
+        assert_eq!(values[10], &Value::from(42));
+        assert_eq!(values[11], &Value::Null);
+    }
+
     #[test]
     fn test_validate_forbidden_queries() {
         let invalid_queries = vec![
@@ -0,0 +1,479 @@
+// RAG module - retrieval over attached documents and saved conversations
+// Long documents are split in chunks, embedded through Ollama and kept in a local
+// index; at question time only the most similar chunks are sent to the model.
+// Saved conversations are embedded message by message for the semantic search.
+
+use crate::http_client;
+use crate::local_storage::{
+    self, ConversationEmbedding, ConversationEntry, MessageEmbedding, RagChunk, RagDocument,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::time::Duration;
+
+/// Chunk length in characters, with some overlap so sentences are not lost at the edges
+const CHUNK_SIZE: usize = 1200;
+const CHUNK_OVERLAP: usize = 200;
+/// Oldest documents are dropped from the index beyond this limit
+const MAX_INDEXED_DOCUMENTS: usize = 50;
+const EMBEDDING_TIMEOUT: Duration = Duration::from_secs(60);
+/// Only the beginning of long messages (e.g. with attachments) is embedded
+const MAX_MESSAGE_CHARS: usize = 2000;
+const SNIPPET_CHARS: usize = 160;
+
+/// Summary returned after indexing a document
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedDocument {
+    pub id: String,
+    pub name: String,
+    pub chunks: usize,
+    /// True when the document was already in the index
+    pub cached: bool,
+}
+
+/// A chunk selected for a query, with its cosine similarity
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievedChunk {
+    pub document_id: String,
+    pub document_name: String,
+    pub text: String,
+    pub score: f32,
+}
+
+/// A saved conversation matching a semantic search
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationMatch {
+    pub conversation_id: String,
+    pub title: String,
+    /// Beginning of the most similar message
+    pub snippet: String,
+    pub score: f32,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    #[serde(default)]
+    embedding: Vec<f32>,
+}
+
+/// Splits, embeds and stores a document. Re-indexing the same content is a no-op.
+pub async fn index_document(
+    ollama_url: &str,
+    model: &str,
+    name: &str,
+    content: &str,
+) -> Result<IndexedDocument> {
+    let id = document_id(model, content);
+
+    if let Some(existing) = local_storage::load_rag_index()?
+        .documents
+        .iter()
+        .find(|doc| doc.id == id)
+    {
+        return Ok(IndexedDocument {
+            id,
+            name: existing.name.clone(),
+            chunks: existing.chunks.len(),
+            cached: true,
+        });
+    }
+
+    let texts = chunk_text(content, CHUNK_SIZE, CHUNK_OVERLAP);
+    if texts.is_empty() {
+        return Err(anyhow!("Il documento non contiene testo da indicizzare"));
+    }
+
+    let mut chunks = Vec::with_capacity(texts.len());
+    for text in texts {
+        let embedding = generate_embedding(ollama_url, model, &text).await?;
+        chunks.push(RagChunk { text, embedding });
+    }
+    let chunk_count = chunks.len();
+
+    // Reloaded because embedding can take a while and another document may have been added
+    let mut index = local_storage::load_rag_index()?;
+    index.documents.retain(|doc| doc.id != id);
+    index.documents.push(RagDocument {
+        id: id.clone(),
+        name: name.to_string(),
+        model: model.to_string(),
+        indexed_at: Utc::now(),
+        chunks,
+    });
+    if index.documents.len() > MAX_INDEXED_DOCUMENTS {
+        let excess = index.documents.len() - MAX_INDEXED_DOCUMENTS;
+        index.documents.drain(..excess);
+    }
+    local_storage::save_rag_index(&index)?;
+
+    Ok(IndexedDocument {
+        id,
+        name: name.to_string(),
+        chunks: chunk_count,
+        cached: false,
+    })
+}
+
+/// Returns the `top_k` chunks of the given documents most similar to the query
+pub async fn retrieve_chunks(
+    ollama_url: &str,
+    model: &str,
+    query: &str,
+    document_ids: &[String],
+    top_k: usize,
+) -> Result<Vec<RetrievedChunk>> {
+    let index = local_storage::load_rag_index()?;
+    let documents: Vec<&RagDocument> = index
+        .documents
+        .iter()
+        .filter(|doc| document_ids.contains(&doc.id) && doc.model == model)
+        .collect();
+
+    if documents.is_empty() || query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query_embedding = generate_embedding(ollama_url, model, query).await?;
+
+    let mut scored: Vec<RetrievedChunk> = documents
+        .iter()
+        .flat_map(|doc| {
+            doc.chunks.iter().map(|chunk| RetrievedChunk {
+                document_id: doc.id.clone(),
+                document_name: doc.name.clone(),
+                text: chunk.text.clone(),
+                score: cosine_similarity(&query_embedding, &chunk.embedding),
+            })
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    scored.truncate(top_k);
+    Ok(scored)
+}
+
+/// Finds the saved conversations closest in meaning to the query.
+/// Conversations added or changed since the last search are embedded first.
+pub async fn semantic_search(
+    ollama_url: &str,
+    model: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<ConversationMatch>> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let memory = local_storage::load_memory()?;
+    let mut index = local_storage::load_conversation_embeddings()?;
+    if index.model != model {
+        index.model = model.to_string();
+        index.conversations.clear();
+    }
+    index.conversations.retain(|entry| {
+        memory
+            .conversations
+            .iter()
+            .any(|c| c.id == entry.conversation_id)
+    });
+
+    let mut changed = false;
+    for conversation in &memory.conversations {
+        let previous = index
+            .conversations
+            .iter()
+            .position(|entry| entry.conversation_id == conversation.id);
+        if let Some(position) = previous {
+            if index.conversations[position].updated_at >= conversation.updated_at {
+                continue;
+            }
+        }
+
+        let old = previous.map(|position| index.conversations.remove(position));
+        let entry = match embed_conversation(ollama_url, model, conversation, old.as_ref()).await {
+            Ok(entry) => entry,
+            Err(e) => {
+                // Keep the conversations embedded so far and the old vectors of this one,
+                // so the next search resumes from here
+                index.conversations.extend(old);
+                if changed {
+                    local_storage::save_conversation_embeddings(&index)?;
+                }
+                return Err(e);
+            }
+        };
+        index.conversations.push(entry);
+        changed = true;
+    }
+    if changed {
+        local_storage::save_conversation_embeddings(&index)?;
+    }
+
+    let query_embedding = generate_embedding(ollama_url, model, query).await?;
+
+    let mut matches: Vec<ConversationMatch> = index
+        .conversations
+        .iter()
+        .filter_map(|entry| {
+            let conversation = memory
+                .conversations
+                .iter()
+                .find(|c| c.id == entry.conversation_id)?;
+            let (best, score) = entry
+                .messages
+                .iter()
+                .map(|m| (m, cosine_similarity(&query_embedding, &m.embedding)))
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))?;
+            let snippet: String = conversation
+                .messages
+                .get(best.index)
+                .map(|m| m.content.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default()
+                .chars()
+                .take(SNIPPET_CHARS)
+                .collect();
+
+            Some(ConversationMatch {
+                conversation_id: conversation.id.clone(),
+                title: conversation.title.clone(),
+                snippet,
+                score,
+                updated_at: conversation.updated_at,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Embeds the visible messages, reusing the vectors of unchanged ones
+async fn embed_conversation(
+    ollama_url: &str,
+    model: &str,
+    conversation: &ConversationEntry,
+    previous: Option<&ConversationEmbedding>,
+) -> Result<ConversationEmbedding> {
+    let mut messages = Vec::new();
+
+    for (index, message) in conversation.messages.iter().enumerate() {
+        if message.hidden || message.role == "system" {
+            continue;
+        }
+        let text: String = message
+            .content
+            .trim()
+            .chars()
+            .take(MAX_MESSAGE_CHARS)
+            .collect();
+        if text.is_empty() {
+            continue;
+        }
+
+        let hash = text_hash(&text);
+        let reused = previous
+            .and_then(|p| p.messages.iter().find(|m| m.hash == hash))
+            .map(|m| m.embedding.clone());
+        let embedding = match reused {
+            Some(embedding) => embedding,
+            None => generate_embedding(ollama_url, model, &text).await?,
+        };
+
+        messages.push(MessageEmbedding {
+            index,
+            hash,
+            embedding,
+        });
+    }
+
+    Ok(ConversationEmbedding {
+        conversation_id: conversation.id.clone(),
+        updated_at: conversation.updated_at,
+        messages,
+    })
+}
+
+/// Embeds a single text through Ollama `/api/embeddings`
+pub async fn generate_embedding(ollama_url: &str, model: &str, text: &str) -> Result<Vec<f32>> {
+    let response = http_client::shared_client()
+        .post(format!("{}/api/embeddings", ollama_url))
+        .json(&serde_json::json!({ "model": model, "prompt": text }))
+        .timeout(EMBEDDING_TIMEOUT)
+        .send()
+        .await
+        .context("Errore di connessione al server per gli embedding")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let message = http_client::ollama_error_text(response).await;
+        return Err(embedding_error(model, status, &message));
+    }
+
+    let parsed: EmbeddingResponse = response
+        .json()
+        .await
+        .context("Risposta embedding non valida")?;
+    if parsed.embedding.is_empty() {
+        return Err(anyhow!(
+            "Il modello '{}' non supporta gli embedding: usa un modello dedicato come nomic-embed-text",
+            model
+        ));
+    }
+
+    Ok(parsed.embedding)
+}
+
+/// Embeds several texts with the same model, in order
+pub async fn generate_embeddings(
+    ollama_url: &str,
+    model: &str,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for (position, text) in texts.iter().enumerate() {
+        let embedding = generate_embedding(ollama_url, model, text)
+            .await
+            .map_err(|e| anyhow!("Testo {} di {}: {}", position + 1, texts.len(), e))?;
+        embeddings.push(embedding);
+    }
+    Ok(embeddings)
+}
+
+fn embedding_error(model: &str, status: reqwest::StatusCode, message: &str) -> anyhow::Error {
+    let lower = message.to_lowercase();
+    if status == reqwest::StatusCode::NOT_FOUND || lower.contains("not found") {
+        anyhow!(
+            "Modello '{}' non trovato: scaricalo con `ollama pull {}`",
+            model,
+            model
+        )
+    } else if lower.contains("does not support") || lower.contains("not support") {
+        anyhow!(
+            "Il modello '{}' non supporta gli embedding: usa un modello dedicato come nomic-embed-text",
+            model
+        )
+    } else {
+        anyhow!("Errore embedding ({}): {}", status, message)
+    }
+}
+
+fn document_id(model: &str, content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn text_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Splits text in windows of `size` characters overlapping by `overlap`,
+/// cutting at a line or sentence end when one falls in the second half of the window
+pub fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start..end];
+            let cut = window
+                .iter()
+                .rposition(|c| *c == '\n')
+                .filter(|&pos| pos > size / 2)
+                .or_else(|| {
+                    window
+                        .iter()
+                        .rposition(|c| matches!(c, '.' | '!' | '?'))
+                        .filter(|&pos| pos > size / 2)
+                });
+            if let Some(pos) = cut {
+                end = start + pos + 1;
+            }
+        }
+
+        let chunk: String = chars[start..end].iter().collect();
+        let chunk = chunk.trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+
+    chunks
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text() {
+        assert!(chunk_text("   ", 100, 20).is_empty());
+        assert_eq!(chunk_text("Testo breve.", 100, 20), vec!["Testo breve."]);
+
+        let text = "Prima frase del documento. ".repeat(40);
+        let chunks = chunk_text(&text, 200, 50);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 200));
+        assert!(chunks.iter().all(|c| c.ends_with('.')));
+    }
+
+    #[test]
+    fn test_embedding_error() {
+        let missing = embedding_error(
+            "nomic-embed-text",
+            reqwest::StatusCode::NOT_FOUND,
+            "model not found",
+        );
+        assert!(missing.to_string().contains("ollama pull nomic-embed-text"));
+
+        let unsupported = embedding_error(
+            "llava",
+            reqwest::StatusCode::BAD_REQUEST,
+            "\"llava\" does not support embeddings",
+        );
+        assert!(unsupported
+            .to_string()
+            .contains("non supporta gli embedding"));
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
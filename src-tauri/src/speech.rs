@@ -0,0 +1,274 @@
+// Voice output - replies read aloud with the system speech synthesizer.
+// Dictation lives in `dictation`, behind the `voice` feature
+
+use crate::agent::detect_language;
+use crate::local_storage::VoiceSettings;
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::io::ErrorKind;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::oneshot;
+
+/// Language code for Whisper: "it_IT" → "it", empty → "auto"
+pub(crate) fn whisper_language(language: &str) -> String {
+    let code = language.trim().split(['-', '_']).next().unwrap_or_default();
+    if code.is_empty() {
+        "auto".to_string()
+    } else {
+        code.to_lowercase()
+    }
+}
+
+// ============ READ ALOUD ============
+
+const NO_TTS_ENGINE: &str =
+    "Nessun motore di sintesi vocale disponibile. Su Linux installa espeak-ng o speech-dispatcher";
+
+/// Windows speech synthesizer, the text is read from stdin. `{language}` is
+/// a lowercase code validated by `read_aloud_language`.
+const WINDOWS_TTS_SCRIPT: &str = "$ErrorActionPreference = 'Stop'; \
+[Console]::InputEncoding = [System.Text.Encoding]::UTF8; \
+Add-Type -AssemblyName System.Speech; \
+$synth = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+$voice = $synth.GetInstalledVoices() | Where-Object { $_.Enabled -and $_.VoiceInfo.Culture.TwoLetterISOLanguageName -eq '{language}' } | Select-Object -First 1; \
+if ($voice) { $synth.SelectVoice($voice.VoiceInfo.Name) }; \
+$synth.Speak([Console]::In.ReadToEnd())";
+
+/// System command that reads text aloud
+struct TtsEngine {
+    program: &'static str,
+    args: Vec<String>,
+    /// Text written on stdin, otherwise passed as last argument
+    text_on_stdin: bool,
+}
+
+/// Engines for this platform in order of preference, the first installed one is used
+async fn tts_engines(language: &str) -> Vec<TtsEngine> {
+    if cfg!(target_os = "windows") {
+        let script = WINDOWS_TTS_SCRIPT.replace("{language}", language);
+        vec![TtsEngine {
+            program: "powershell",
+            args: vec![
+                "-NoProfile".to_string(),
+                "-NonInteractive".to_string(),
+                "-Command".to_string(),
+                script,
+            ],
+            text_on_stdin: true,
+        }]
+    } else if cfg!(target_os = "macos") {
+        let mut args = vec!["-f".to_string(), "-".to_string()];
+        if let Some(voice) = macos_voice(language).await {
+            args.push("-v".to_string());
+            args.push(voice);
+        }
+        vec![TtsEngine {
+            program: "say",
+            args,
+            text_on_stdin: true,
+        }]
+    } else {
+        let espeak_args = vec![
+            "-v".to_string(),
+            language.to_string(),
+            "--stdin".to_string(),
+        ];
+        vec![
+            TtsEngine {
+                program: "espeak-ng",
+                args: espeak_args.clone(),
+                text_on_stdin: true,
+            },
+            TtsEngine {
+                program: "espeak",
+                args: espeak_args,
+                text_on_stdin: true,
+            },
+            TtsEngine {
+                program: "spd-say",
+                args: vec![
+                    "--wait".to_string(),
+                    "-l".to_string(),
+                    language.to_string(),
+                    "--".to_string(),
+                ],
+                text_on_stdin: false,
+            },
+        ]
+    }
+}
+
+/// First macOS voice for the language, from `say -v ?` lines like
+/// "Alice               it_IT    # Ciao! Mi chiamo Alice."
+async fn macos_voice(language: &str) -> Option<String> {
+    let output = tokio::process::Command::new("say")
+        .args(["-v", "?"])
+        .output()
+        .await
+        .ok()?;
+    let voices = String::from_utf8_lossy(&output.stdout);
+    let pattern = regex::Regex::new(r"^(.+?)\s+([a-z]{2,3})[_-][A-Za-z0-9]+\s+#").ok()?;
+
+    voices.lines().find_map(|line| {
+        let captures = pattern.captures(line)?;
+        (&captures[2] == language).then(|| captures[1].trim().to_string())
+    })
+}
+
+/// Reads the text aloud with the system synthesizer until it ends or `stop` fires
+pub async fn speak(text: &str, language: &str, mut stop: oneshot::Receiver<()>) -> Result<()> {
+    for engine in tts_engines(language).await {
+        let mut command = tokio::process::Command::new(engine.program);
+        command
+            .args(&engine.args)
+            .stdin(if engine.text_on_stdin {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        if !engine.text_on_stdin {
+            command.arg(text);
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(anyhow!("Impossibile avviare {}: {}", engine.program, e)),
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .await
+                .context("Impossibile inviare il testo al motore di sintesi vocale")?;
+        }
+
+        tokio::select! {
+            status = child.wait() => {
+                let status = status.context("Errore durante la lettura ad alta voce")?;
+                if !status.success() {
+                    anyhow::bail!("{} terminato con errore ({})", engine.program, status);
+                }
+            }
+            _ = &mut stop => {
+                let _ = child.kill().await;
+            }
+        }
+        return Ok(());
+    }
+
+    anyhow::bail!(NO_TTS_ENGINE)
+}
+
+/// Voice language for a reply: the configured one, or the detected language of the text
+pub fn read_aloud_language(settings: &VoiceSettings, text: &str) -> String {
+    let configured = whisper_language(&settings.read_aloud_language);
+    let language = if configured == "auto" {
+        detect_language(text)
+            .map(|detected| detected.code)
+            .unwrap_or_else(|| whisper_language(&settings.language))
+    } else {
+        configured
+    };
+
+    // Interpolated in the engines' arguments and in the Windows script
+    if (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()) {
+        language
+    } else {
+        "it".to_string()
+    }
+}
+
+/// Plain text to read from a markdown reply: no reasoning, code blocks, links or markup
+pub fn speakable_text(markdown: &str) -> String {
+    lazy_static! {
+        static ref THINK_RE: Regex = Regex::new(r"(?s)<think>.*?(</think>|$)").unwrap();
+        static ref LINK_RE: Regex = Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap();
+        static ref URL_RE: Regex = Regex::new(r"https?://\S+").unwrap();
+        static ref LINE_PREFIX_RE: Regex =
+            Regex::new(r"^\s*(#{1,6}\s+|>\s?|[-*+]\s+|\d+[.)]\s+)").unwrap();
+        static ref TABLE_SEPARATOR_RE: Regex = Regex::new(r"^\s*\|?[\s:|-]+\|?\s*$").unwrap();
+    }
+
+    let without_think = THINK_RE.replace_all(markdown, "");
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in without_think.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block || trimmed.is_empty() || TABLE_SEPARATOR_RE.is_match(line) {
+            continue;
+        }
+
+        let line = LINE_PREFIX_RE.replace(line, "");
+        let line = LINK_RE.replace_all(&line, "$1");
+        let line = URL_RE.replace_all(&line, "");
+        let line = line
+            .replace("**", "")
+            .replace("__", "")
+            .replace("~~", "")
+            .replace(['*', '`'], "");
+        let line = line
+            .split('|')
+            .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|cell| !cell.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whisper_language() {
+        assert_eq!(whisper_language("it_IT"), "it");
+        assert_eq!(whisper_language("EN-us"), "en");
+        assert_eq!(whisper_language("auto"), "auto");
+        assert_eq!(whisper_language(" "), "auto");
+    }
+
+    #[test]
+    fn test_speakable_text() {
+        let markdown = "<think>ragiono</think>## Risultato\n\n\
+- **Primo** punto con [un link](https://example.com)\n\
+```rust\nfn main() {}\n```\n\
+| Nome | Valore |\n|---|---|\n| a | 1 |\n\
+Vedi https://example.org per `dettagli`.";
+
+        assert_eq!(
+            speakable_text(markdown),
+            "Risultato\nPrimo punto con un link\nNome, Valore\na, 1\nVedi per dettagli."
+        );
+    }
+
+    #[test]
+    fn test_read_aloud_language() {
+        let mut settings = VoiceSettings::default();
+        settings.read_aloud_language = "en_US".to_string();
+        assert_eq!(read_aloud_language(&settings, "Ciao a tutti"), "en");
+
+        settings.read_aloud_language = "auto".to_string();
+        let text = "Questa è una risposta scritta in italiano, abbastanza lunga da riconoscerla.";
+        assert_eq!(read_aloud_language(&settings, text), "it");
+
+        settings.read_aloud_language = "it'; rm".to_string();
+        assert_eq!(read_aloud_language(&settings, "x"), "it");
+    }
+}
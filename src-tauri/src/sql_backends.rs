@@ -0,0 +1,493 @@
+// SQL Backends Module
+// PostgreSQL (tokio-postgres) and MySQL (sqlx) implementations of `SqlBackend`.
+// SQL Server is implemented in `mcp_sql` on top of Tiberius.
+
+use crate::mcp_sql::{
+    credentials, decimal_to_json, validate_readonly_query, with_query_timeout, QueryResult,
+    SqlBackend, SqlColumnInfo, SqlConnection, DEFAULT_QUERY_TIMEOUT,
+};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use serde_json::{Number, Value};
+use sqlx::mysql::{MySqlConnectOptions, MySqlConnection, MySqlRow, MySqlSslMode};
+use sqlx::types::Json;
+use sqlx::{Column, ConnectOptions, Executor, Row, TypeInfo};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_postgres::config::SslMode;
+use tokio_postgres::types::ToSql;
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+const POSTGRES_DEFAULT_PORT: u16 = 5432;
+const MYSQL_DEFAULT_PORT: u16 = 3306;
+
+/// Splits "host:port" or "[ipv6]:port"; without a port the default of the engine is used
+fn host_and_port(server: &str, default_port: u16) -> Result<(String, u16)> {
+    let server = server.trim();
+    let invalid_port = || anyhow!("Porta non valida nel server '{}'", server);
+
+    // The brackets are only needed to write the port, the drivers want the bare address
+    if let Some(rest) = server.strip_prefix('[') {
+        let (host, after) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow!("Indirizzo IPv6 non chiuso nel server '{}'", server))?;
+        let port = match after {
+            "" => default_port,
+            _ => after
+                .strip_prefix(':')
+                .and_then(|port| port.parse().ok())
+                .ok_or_else(invalid_port)?,
+        };
+        return Ok((host.to_string(), port));
+    }
+
+    match server.split_once(':') {
+        // More than one colon is an IPv6 address without port
+        Some((host, port)) if !port.contains(':') => {
+            let port = port.parse().map_err(|_| invalid_port())?;
+            Ok((host.to_string(), port))
+        }
+        _ => Ok((server.to_string(), default_port)),
+    }
+}
+
+fn check_sql_auth(conn: &SqlConnection) -> Result<()> {
+    if conn.auth_type == "windows" {
+        return Err(anyhow!(
+            "Autenticazione Windows non disponibile per {}: usa username e password",
+            conn.db_type.label()
+        ));
+    }
+    Ok(())
+}
+
+// ============ PostgreSQL ============
+
+/// Accepts any server certificate: used only when the connection trusts it
+#[derive(Debug)]
+struct TrustAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for TrustAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Same meaning as for SQL Server: without trust TLS is required and the
+/// certificate is verified, with trust TLS is used when the server offers it
+fn postgres_tls(trust_server_certificate: bool) -> Result<(SslMode, MakeRustlsConnect)> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let (ssl_mode, config) = if trust_server_certificate {
+        let config = builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(TrustAnyCertificate(provider)))
+            .with_no_client_auth();
+        (SslMode::Prefer, config)
+    } else {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config = builder.with_root_certificates(roots).with_no_client_auth();
+        (SslMode::Require, config)
+    };
+    Ok((ssl_mode, MakeRustlsConnect::new(config)))
+}
+
+impl SqlBackend for tokio_postgres::Client {
+    async fn connect(conn: &SqlConnection) -> Result<Self> {
+        check_sql_auth(conn)?;
+        let (username, password) = credentials(conn)?;
+        let (host, port) = host_and_port(&conn.server, POSTGRES_DEFAULT_PORT)?;
+        let (ssl_mode, tls) = postgres_tls(conn.trust_server_certificate)?;
+
+        let mut config = tokio_postgres::Config::new();
+        config
+            .host(&host)
+            .port(port)
+            .dbname(&conn.database)
+            .user(username)
+            .password(password)
+            .application_name("MatePro")
+            .ssl_mode(ssl_mode)
+            .connect_timeout(Duration::from_secs(15));
+
+        let (client, connection) = config.connect(tls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::warn!("Connessione PostgreSQL terminata: {}", e);
+            }
+        });
+
+        // Second line of defence behind validate_readonly_query
+        client
+            .batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")
+            .await?;
+
+        Ok(client)
+    }
+
+    async fn query(&mut self, query: &str, timeout: Duration) -> Result<QueryResult> {
+        validate_readonly_query(query)?;
+
+        with_query_timeout(timeout, fetch_postgres(self, query, &[])).await
+    }
+
+    async fn list_tables(&mut self) -> Result<QueryResult> {
+        let query = r#"
+            SELECT
+                table_schema::text AS "Schema",
+                table_name::text AS "Table",
+                table_type::text AS "Type"
+            FROM information_schema.tables
+            WHERE table_type IN ('BASE TABLE', 'VIEW')
+            AND table_schema NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY table_schema, table_name
+        "#;
+
+        with_query_timeout(DEFAULT_QUERY_TIMEOUT, fetch_postgres(self, query, &[])).await
+    }
+
+    async fn describe_table(&mut self, schema: &str, table_name: &str) -> Result<QueryResult> {
+        let query = r#"
+            SELECT
+                column_name::text AS "Column",
+                data_type::text AS "Type",
+                character_maximum_length::int4 AS "MaxLength",
+                is_nullable::text AS "Nullable",
+                column_default::text AS "Default"
+            FROM information_schema.columns
+            WHERE table_schema = $1
+            AND table_name = $2
+            ORDER BY ordinal_position
+        "#;
+
+        with_query_timeout(
+            DEFAULT_QUERY_TIMEOUT,
+            fetch_postgres(self, query, &[&schema, &table_name]),
+        )
+        .await
+    }
+}
+
+async fn fetch_postgres(
+    client: &tokio_postgres::Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<QueryResult> {
+    // Preparing first gives the columns even when no row comes back
+    let statement = client.prepare(query).await?;
+    let rows = client.query(&statement, params).await?;
+
+    let columns: Vec<SqlColumnInfo> = statement
+        .columns()
+        .iter()
+        .map(|column| SqlColumnInfo {
+            name: column.name().to_string(),
+            data_type: column.type_().name().to_string(),
+        })
+        .collect();
+
+    let mut data_rows = Vec::new();
+    for row in rows {
+        let mut row_map = HashMap::new();
+        for (idx, column) in columns.iter().enumerate() {
+            row_map.insert(
+                column.name.clone(),
+                postgres_value(&row, idx, &column.data_type)?,
+            );
+        }
+        data_rows.push(row_map);
+    }
+
+    Ok(QueryResult {
+        columns,
+        rows: data_rows,
+    })
+}
+
+fn postgres_value(row: &tokio_postgres::Row, idx: usize, type_name: &str) -> Result<Value> {
+    let value = match type_name {
+        "bool" => row.try_get::<_, Option<bool>>(idx)?.map(Value::Bool),
+        "int2" => row.try_get::<_, Option<i16>>(idx)?.map(Value::from),
+        "int4" => row.try_get::<_, Option<i32>>(idx)?.map(Value::from),
+        "int8" => row.try_get::<_, Option<i64>>(idx)?.map(Value::from),
+        "oid" => row.try_get::<_, Option<u32>>(idx)?.map(Value::from),
+        "float4" => row
+            .try_get::<_, Option<f32>>(idx)?
+            .and_then(|v| Number::from_f64(v as f64))
+            .map(Value::Number),
+        "float8" => row
+            .try_get::<_, Option<f64>>(idx)?
+            .and_then(Number::from_f64)
+            .map(Value::Number),
+        "numeric" => row.try_get::<_, Option<Decimal>>(idx)?.map(decimal_to_json),
+        "timestamp" => row
+            .try_get::<_, Option<NaiveDateTime>>(idx)?
+            .map(|dt| Value::String(dt.to_string())),
+        "timestamptz" => row
+            .try_get::<_, Option<DateTime<Utc>>>(idx)?
+            .map(|dt| Value::String(dt.to_rfc3339())),
+        "date" => row
+            .try_get::<_, Option<NaiveDate>>(idx)?
+            .map(|d| Value::String(d.to_string())),
+        "time" => row
+            .try_get::<_, Option<NaiveTime>>(idx)?
+            .map(|t| Value::String(t.to_string())),
+        "json" | "jsonb" => row.try_get::<_, Option<Value>>(idx)?,
+        "uuid" => row
+            .try_get::<_, Option<uuid::Uuid>>(idx)?
+            .map(|id| Value::String(id.to_string())),
+        "bytea" => row
+            .try_get::<_, Option<Vec<u8>>>(idx)?
+            .map(|bytes| Value::String(general_purpose::STANDARD.encode(bytes))),
+        // Text-like types decode as strings; the others are only named
+        _ => match row.try_get::<_, Option<String>>(idx) {
+            Ok(text) => text.map(Value::String),
+            Err(_) => Some(Value::String(format!("<{}>", type_name))),
+        },
+    };
+
+    Ok(value.unwrap_or(Value::Null))
+}
+
+// ============ MySQL ============
+
+impl SqlBackend for MySqlConnection {
+    async fn connect(conn: &SqlConnection) -> Result<Self> {
+        check_sql_auth(conn)?;
+        let (username, password) = credentials(conn)?;
+        let (host, port) = host_and_port(&conn.server, MYSQL_DEFAULT_PORT)?;
+
+        // Same meaning as for SQL Server: without trust the certificate is verified
+        let ssl_mode = if conn.trust_server_certificate {
+            MySqlSslMode::Preferred
+        } else {
+            MySqlSslMode::VerifyIdentity
+        };
+
+        let mut connection = MySqlConnectOptions::new()
+            .host(&host)
+            .port(port)
+            .database(&conn.database)
+            .username(username)
+            .password(password)
+            .ssl_mode(ssl_mode)
+            .connect()
+            .await?;
+
+        // Second line of defence behind validate_readonly_query
+        connection
+            .execute("SET SESSION TRANSACTION READ ONLY")
+            .await?;
+
+        Ok(connection)
+    }
+
+    async fn query(&mut self, query: &str, timeout: Duration) -> Result<QueryResult> {
+        validate_readonly_query(query)?;
+
+        with_query_timeout(timeout, fetch_mysql(self, sqlx::query(query), query)).await
+    }
+
+    async fn list_tables(&mut self) -> Result<QueryResult> {
+        let query = r#"
+            SELECT
+                TABLE_SCHEMA AS `Schema`,
+                TABLE_NAME AS `Table`,
+                TABLE_TYPE AS `Type`
+            FROM information_schema.TABLES
+            WHERE TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+            AND TABLE_SCHEMA = DATABASE()
+            ORDER BY TABLE_SCHEMA, TABLE_NAME
+        "#;
+
+        with_query_timeout(
+            DEFAULT_QUERY_TIMEOUT,
+            fetch_mysql(self, sqlx::query(query), query),
+        )
+        .await
+    }
+
+    async fn describe_table(&mut self, schema: &str, table_name: &str) -> Result<QueryResult> {
+        // In MySQL the schema is the database: "dbo" and empty fall back to the current one
+        let query = r#"
+            SELECT
+                COLUMN_NAME AS `Column`,
+                DATA_TYPE AS `Type`,
+                CHARACTER_MAXIMUM_LENGTH AS `MaxLength`,
+                IS_NULLABLE AS `Nullable`,
+                COLUMN_DEFAULT AS `Default`
+            FROM information_schema.COLUMNS
+            WHERE TABLE_SCHEMA = COALESCE(NULLIF(NULLIF(?, ''), 'dbo'), DATABASE())
+            AND TABLE_NAME = ?
+            ORDER BY ORDINAL_POSITION
+        "#;
+
+        let statement = sqlx::query(query).bind(schema).bind(table_name);
+        with_query_timeout(DEFAULT_QUERY_TIMEOUT, fetch_mysql(self, statement, query)).await
+    }
+}
+
+async fn fetch_mysql(
+    connection: &mut MySqlConnection,
+    statement: sqlx::query::Query<'_, sqlx::MySql, sqlx::mysql::MySqlArguments>,
+    query: &str,
+) -> Result<QueryResult> {
+    let rows: Vec<MySqlRow> = statement.fetch_all(&mut *connection).await?;
+
+    let columns: Vec<SqlColumnInfo> = match rows.first() {
+        Some(row) => row.columns().iter().map(mysql_column_info).collect(),
+        // Without rows the columns come from the statement description
+        None => connection
+            .describe(query)
+            .await?
+            .columns()
+            .iter()
+            .map(mysql_column_info)
+            .collect(),
+    };
+
+    let mut data_rows = Vec::new();
+    for row in &rows {
+        let mut row_map = HashMap::new();
+        for (idx, column) in columns.iter().enumerate() {
+            row_map.insert(
+                column.name.clone(),
+                mysql_value(row, idx, &column.data_type)?,
+            );
+        }
+        data_rows.push(row_map);
+    }
+
+    Ok(QueryResult {
+        columns,
+        rows: data_rows,
+    })
+}
+
+fn mysql_column_info(column: &sqlx::mysql::MySqlColumn) -> SqlColumnInfo {
+    SqlColumnInfo {
+        name: column.name().to_string(),
+        data_type: column.type_info().name().to_lowercase(),
+    }
+}
+
+fn mysql_value(row: &MySqlRow, idx: usize, type_name: &str) -> Result<Value> {
+    let value = match type_name {
+        "boolean" => row.try_get::<Option<bool>, _>(idx)?.map(Value::Bool),
+        "tinyint" | "smallint" | "mediumint" | "int" | "bigint" => {
+            row.try_get::<Option<i64>, _>(idx)?.map(Value::from)
+        }
+        name if name.ends_with(" unsigned") => row.try_get::<Option<u64>, _>(idx)?.map(Value::from),
+        "float" | "double" => row
+            .try_get::<Option<f64>, _>(idx)?
+            .and_then(Number::from_f64)
+            .map(Value::Number),
+        "decimal" => row.try_get::<Option<Decimal>, _>(idx)?.map(decimal_to_json),
+        "datetime" | "timestamp" => row
+            .try_get::<Option<NaiveDateTime>, _>(idx)?
+            .map(|dt| Value::String(dt.to_string())),
+        "date" => row
+            .try_get::<Option<NaiveDate>, _>(idx)?
+            .map(|d| Value::String(d.to_string())),
+        "time" => match row.try_get::<Option<NaiveTime>, _>(idx) {
+            Ok(time) => time.map(|t| Value::String(t.to_string())),
+            Err(_) => Some(Value::String(format!("<{}>", type_name))),
+        },
+        "json" => row
+            .try_get::<Option<Json<Value>>, _>(idx)?
+            .map(|json| json.0),
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => row
+            .try_get::<Option<Vec<u8>>, _>(idx)?
+            .map(|bytes| Value::String(general_purpose::STANDARD.encode(bytes))),
+        // TIME beyond 24 hours, YEAR, BIT and GEOMETRY have no common
+        // representation: try them as text and otherwise only name the type
+        _ => match row.try_get::<Option<String>, _>(idx) {
+            Ok(text) => text.map(Value::String),
+            Err(_) => Some(Value::String(format!("<{}>", type_name))),
+        },
+    };
+
+    Ok(value.unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_and_port() {
+        assert_eq!(
+            host_and_port("db.local", 5432).unwrap(),
+            ("db.local".to_string(), 5432)
+        );
+        assert_eq!(
+            host_and_port(" 10.0.0.5:3307 ", 3306).unwrap(),
+            ("10.0.0.5".to_string(), 3307)
+        );
+        assert_eq!(
+            host_and_port("::1", 5432).unwrap(),
+            ("::1".to_string(), 5432)
+        );
+        assert_eq!(
+            host_and_port("[::1]:5433", 5432).unwrap(),
+            ("::1".to_string(), 5433)
+        );
+        assert_eq!(
+            host_and_port("[fe80::1]", 3306).unwrap(),
+            ("fe80::1".to_string(), 3306)
+        );
+        assert!(host_and_port("db.local:porta", 5432).is_err());
+        assert!(host_and_port("[::1", 5432).is_err());
+        assert!(host_and_port("[::1]5432", 5432).is_err());
+    }
+}
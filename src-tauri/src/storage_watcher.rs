@@ -0,0 +1,122 @@
+// Storage Watcher Module
+// Notifies the UI when the local storage files are changed outside the app,
+// e.g. by a cloud sync client or by another MatePro instance
+
+use crate::local_storage;
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Event received by the UI, with the kind of data to reload as payload
+pub const STORAGE_CHANGED_EVENT: &str = "local-storage-changed";
+/// Sync clients often write a file in several steps: wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Active watcher; replacing it stops the previous one
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+
+/// Starts watching the current data directory, replacing any previous watcher
+pub fn watch_data_dir(app: AppHandle) -> Result<()> {
+    let data_dir = PathBuf::from(local_storage::get_data_directory()?);
+    let (sender, receiver) = mpsc::channel();
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            let _ = sender.send(event);
+        }
+    })
+    .context("Impossibile creare il watcher della cartella dati")?;
+    watcher
+        .watch(&data_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Impossibile monitorare {}", data_dir.display()))?;
+
+    // Dropping the old watcher closes its channel and ends its thread
+    *WATCHER
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Watcher della cartella dati non disponibile"))? =
+        Some(watcher);
+
+    std::thread::spawn(move || {
+        while let Ok(event) = receiver.recv() {
+            let mut changed = changed_files(&event);
+
+            loop {
+                match receiver.recv_timeout(DEBOUNCE) {
+                    Ok(event) => changed.extend(changed_files(&event)),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let kinds: BTreeSet<&str> = changed
+                .iter()
+                .filter(|name| !local_storage::is_own_write(&data_dir.join(name)))
+                .filter_map(|name| watched_kind(name))
+                .collect();
+
+            for kind in kinds {
+                tracing::debug!("Dati modificati esternamente: {}", kind);
+                let _ = app.emit(STORAGE_CHANGED_EVENT, kind);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Names of the watched files touched by an event
+fn changed_files(event: &Event) -> BTreeSet<String> {
+    if matches!(event.kind, EventKind::Access(_) | EventKind::Other) {
+        return BTreeSet::new();
+    }
+
+    event
+        .paths
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| watched_kind(name).is_some())
+        .collect()
+}
+
+fn watched_kind(file_name: &str) -> Option<&'static str> {
+    local_storage::WATCHED_FILES
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .map(|(_, kind)| *kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    #[test]
+    fn test_changed_files() {
+        let event = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(PathBuf::from("/dati/MatePro/memory.json"))
+            .add_path(PathBuf::from("/dati/MatePro/rag_index.json"));
+        let changed = changed_files(&event);
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), vec!["memory.json"]);
+
+        let event = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/dati/MatePro/calendar.json"));
+        assert_eq!(changed_files(&event).len(), 1);
+
+        let event = Event::new(EventKind::Access(AccessKind::Any))
+            .add_path(PathBuf::from("/dati/MatePro/memory.json"));
+        assert!(changed_files(&event).is_empty());
+    }
+
+    #[test]
+    fn test_watched_kind() {
+        assert_eq!(watched_kind("memory.json"), Some("memory"));
+        assert_eq!(watched_kind("system_prompt.json"), Some("settings"));
+        assert_eq!(watched_kind("conversation_draft.json"), None);
+    }
+}